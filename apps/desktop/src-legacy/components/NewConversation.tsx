@@ -69,11 +69,10 @@ export function NewConversation() {
     }
 
     // Create a thread ID based on sorted public keys (deterministic)
-    // Must match Rust backend format: direct_{sorted_keys}[0..32]
+    // Must match Rust backend format: direct_{lower}_{higher}
     // Ensure lowercase to match Rust hex output and sort order
     const keys = [myPk.toLowerCase(), result.public_key.toLowerCase()].sort();
-    const joined = keys.join('_');
-    const threadId = `direct_${joined.substring(0, 32)}`;
+    const threadId = `direct_${keys.join('_')}`;
 
     navigate(`/messages/${threadId}`, {
       state: {
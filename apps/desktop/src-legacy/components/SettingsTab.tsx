@@ -81,8 +81,13 @@ export function SettingsTab() {
   const handleExportBackup = async () => {
     try {
       setExporting(true);
-      const backup = await exportIdentityBackup();
-      setBackupKey(backup.private_key);
+      const passphrase = window.prompt('Choose a passphrase to encrypt the backup');
+      if (!passphrase) return;
+      const lock = await invoke<{ enabled: boolean }>('get_app_lock_status');
+      const pin = lock.enabled ? window.prompt('Enter your PIN to export the backup') : undefined;
+      if (pin === null) return;
+      const backup = await exportIdentityBackup(passphrase, pin);
+      setBackupKey(JSON.stringify(backup.backup));
       setShowBackup(true);
     } catch (e) {
       console.error('Failed to export backup:', e);
@@ -359,8 +364,8 @@ function BackupModal({
         <div className="p-6">
           <h2 className="text-xl font-bold mb-2">Identity Backup</h2>
           <p className="text-slate-400 text-sm mb-4">
-            This is your private key. Store it securely - anyone with this key
-            can access your identity.
+            This is your encrypted identity backup. You need it and your
+            passphrase to restore your identity on another device.
           </p>
 
           <div className="bg-slate-900 rounded-lg p-4 mb-4">
@@ -371,8 +376,8 @@ function BackupModal({
 
           <div className="bg-red-900/20 border border-red-500/30 rounded-lg p-3 mb-6">
             <p className="text-red-400 text-sm">
-              ⚠️ Never share this key. Never store it in cloud services. Write it
-              down and keep it safe.
+              ⚠️ Keep the passphrase somewhere separate from the backup. Anyone
+              with both can access your identity.
             </p>
           </div>
 
@@ -381,7 +386,7 @@ function BackupModal({
               onClick={handleCopy}
               className="btn btn-secondary flex-1"
             >
-              {copied ? '✓ Copied' : 'Copy Key'}
+              {copied ? '✓ Copied' : 'Copy Backup'}
             </button>
             <button onClick={onClose} className="btn btn-primary flex-1">
               Done
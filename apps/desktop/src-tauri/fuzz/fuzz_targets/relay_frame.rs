@@ -0,0 +1,16 @@
+//! Relay frame parsing on arbitrary WebSocket text
+//!
+//! ```text
+//! cargo +nightly fuzz run relay_frame
+//! ```
+
+#![no_main]
+
+use gns_browser::network::parse_incoming_message;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = parse_incoming_message(text);
+    }
+});
@@ -1,8 +1,27 @@
 #pragma once
 
+#include <stdbool.h>
+#include <stdint.h>
+
 namespace ffi {
     extern "C" {
         void start_app();
+
+        // Native entry points (src/native). `done` is called once, from
+        // any thread, with the result and the context passed in.
+        typedef void (*gns_done_fn)(void *context, int32_t result);
+
+        void gns_handle_push(const char *data_json, gns_done_fn done, void *context);
+        void gns_register_push_token(const char *platform, const char *token, gns_done_fn done, void *context);
+        void gns_set_app_foreground(bool foreground);
+        void gns_run_background_fetch(gns_done_fn done, void *context);
+
+        typedef void (*gns_motion_switch_fn)(bool on);
+
+        void gns_set_motion_switch(gns_motion_switch_fn motion_switch);
+        void gns_report_motion(const char *activity, bool significant_motion);
     }
 }
 
+// Hooks the app delegate needs before the Rust app starts (native_hooks.mm)
+void gns_install_native_hooks(void);
@@ -1,6 +1,7 @@
 #include "bindings/bindings.h"
 
 int main(int argc, char * argv[]) {
+	gns_install_native_hooks();
 	ffi::start_app();
 	return 0;
 }
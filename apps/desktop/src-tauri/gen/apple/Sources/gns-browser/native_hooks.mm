@@ -0,0 +1,170 @@
+// Native hooks for the iOS app
+//
+// Tauri owns the app delegate, so the delegate methods for remote
+// notifications are added to its class once launching finishes. They hand
+// the work to the Rust core through the entry points in src/native, which
+// don't need the webview. The relay-sync background task is registered
+// before launch, as BGTaskScheduler requires, and scheduled each time the
+// app goes to the background. CoreMotion activity updates run while the
+// Rust core has breadcrumb collection on.
+
+#import <BackgroundTasks/BackgroundTasks.h>
+#import <CoreMotion/CoreMotion.h>
+#import <UIKit/UIKit.h>
+#import <objc/runtime.h>
+
+#include "bindings/bindings.h"
+
+// Must match BGTaskSchedulerPermittedIdentifiers in Info.plist
+static NSString *const RelaySyncTask = @"com.gcrumbs.browser.relay-sync";
+
+// Earliest the OS should run the next relay sync
+static const NSTimeInterval RelaySyncInterval = 15 * 60;
+
+typedef void (^FetchCompletion)(UIBackgroundFetchResult);
+
+static void push_done(void *context, int32_t result) {
+	FetchCompletion completion = (__bridge_transfer FetchCompletion)context;
+	UIBackgroundFetchResult fetch_result = result > 0 ? UIBackgroundFetchResultNewData
+		: result == 0 ? UIBackgroundFetchResultNoData
+		: UIBackgroundFetchResultFailed;
+	dispatch_async(dispatch_get_main_queue(), ^{
+		completion(fetch_result);
+	});
+}
+
+static void token_done(void *context, int32_t result) {
+	if (result < 0) {
+		NSLog(@"Push token registration failed");
+	}
+}
+
+static void did_register_for_remote_notifications(id self, SEL _cmd, UIApplication *app, NSData *token) {
+	const unsigned char *bytes = (const unsigned char *)token.bytes;
+	NSMutableString *hex = [NSMutableString stringWithCapacity:token.length * 2];
+	for (NSUInteger i = 0; i < token.length; i++) {
+		[hex appendFormat:@"%02x", bytes[i]];
+	}
+	ffi::gns_register_push_token("apns", hex.UTF8String, token_done, NULL);
+}
+
+static void did_fail_to_register_for_remote_notifications(id self, SEL _cmd, UIApplication *app, NSError *error) {
+	NSLog(@"Remote notifications unavailable: %@", error);
+}
+
+static void did_receive_remote_notification(id self, SEL _cmd, UIApplication *app, NSDictionary *user_info, FetchCompletion completion) {
+	NSData *json = [NSJSONSerialization isValidJSONObject:user_info]
+		? [NSJSONSerialization dataWithJSONObject:user_info options:0 error:nil]
+		: nil;
+	if (json == nil) {
+		completion(UIBackgroundFetchResultFailed);
+		return;
+	}
+	NSString *data = [[NSString alloc] initWithData:json encoding:NSUTF8StringEncoding];
+	ffi::gns_handle_push(data.UTF8String, push_done, (__bridge_retained void *)[completion copy]);
+}
+
+static void schedule_relay_sync(void) {
+	BGAppRefreshTaskRequest *request = [[BGAppRefreshTaskRequest alloc] initWithIdentifier:RelaySyncTask];
+	request.earliestBeginDate = [NSDate dateWithTimeIntervalSinceNow:RelaySyncInterval];
+	NSError *error = nil;
+	if (![[BGTaskScheduler sharedScheduler] submitTaskRequest:request error:&error]) {
+		NSLog(@"Relay sync not scheduled: %@", error);
+	}
+}
+
+static void relay_sync_done(void *context, int32_t result) {
+	BGTask *task = (__bridge_transfer BGTask *)context;
+	[task setTaskCompletedWithSuccess:result >= 0];
+}
+
+static void run_relay_sync(BGTask *task) {
+	// Queue the next one first, in case this one runs out of time
+	schedule_relay_sync();
+	task.expirationHandler = ^{
+		NSLog(@"Relay sync ran out of time");
+	};
+	ffi::gns_run_background_fetch(relay_sync_done, (__bridge_retained void *)task);
+}
+
+static CMMotionActivityManager *motion_manager;
+
+static const char *motion_activity_name(CMMotionActivity *activity) {
+	if (activity.confidence == CMMotionActivityConfidenceLow) return "unknown";
+	if (activity.automotive) return "automotive";
+	if (activity.cycling) return "cycling";
+	if (activity.running) return "running";
+	if (activity.walking) return "walking";
+	if (activity.stationary) return "stationary";
+	return "unknown";
+}
+
+static void switch_motion_updates(bool on) {
+	dispatch_async(dispatch_get_main_queue(), ^{
+		if (![CMMotionActivityManager isActivityAvailable]) {
+			return;
+		}
+		if (motion_manager == nil) {
+			motion_manager = [[CMMotionActivityManager alloc] init];
+		}
+		if (!on) {
+			[motion_manager stopActivityUpdates];
+			return;
+		}
+		[motion_manager startActivityUpdatesToQueue:[NSOperationQueue mainQueue]
+			withHandler:^(CMMotionActivity *activity) {
+				if (activity != nil) {
+					ffi::gns_report_motion(motion_activity_name(activity), false);
+				}
+			}];
+	});
+}
+
+static void add_method(Class cls, SEL selector, IMP imp, const char *types) {
+	if (!class_addMethod(cls, selector, imp, types)) {
+		NSLog(@"App delegate already implements %@", NSStringFromSelector(selector));
+	}
+}
+
+static void add_delegate_methods(Class cls) {
+	add_method(cls, @selector(application:didRegisterForRemoteNotificationsWithDeviceToken:),
+		(IMP)did_register_for_remote_notifications, "v@:@@");
+	add_method(cls, @selector(application:didFailToRegisterForRemoteNotificationsWithError:),
+		(IMP)did_fail_to_register_for_remote_notifications, "v@:@@");
+	add_method(cls, @selector(application:didReceiveRemoteNotification:fetchCompletionHandler:),
+		(IMP)did_receive_remote_notification, "v@:@@@?");
+}
+
+void gns_install_native_hooks(void) {
+	ffi::gns_set_motion_switch(switch_motion_updates);
+
+	[[BGTaskScheduler sharedScheduler] registerForTaskWithIdentifier:RelaySyncTask
+		usingQueue:nil
+		launchHandler:^(BGTask *task) {
+			run_relay_sync(task);
+		}];
+
+	NSNotificationCenter *center = [NSNotificationCenter defaultCenter];
+	[center addObserverForName:UIApplicationDidEnterBackgroundNotification
+		object:nil
+		queue:nil
+		usingBlock:^(NSNotification *note) {
+			ffi::gns_set_app_foreground(false);
+			schedule_relay_sync();
+		}];
+	[center addObserverForName:UIApplicationWillEnterForegroundNotification
+		object:nil
+		queue:nil
+		usingBlock:^(NSNotification *note) {
+			ffi::gns_set_app_foreground(true);
+		}];
+
+	[center addObserverForName:UIApplicationDidFinishLaunchingNotification
+		object:nil
+		queue:nil
+		usingBlock:^(NSNotification *note) {
+			UIApplication *app = [UIApplication sharedApplication];
+			add_delegate_methods([app.delegate class]);
+			[app registerForRemoteNotifications];
+		}];
+}
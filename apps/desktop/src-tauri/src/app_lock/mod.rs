@@ -0,0 +1,457 @@
+//! App Lock - PIN / biometric gate in front of the IPC commands
+//!
+//! When a PIN is set the app starts locked, and locks again after
+//! `idle_timeout_secs` without a command from the UI. While locked,
+//! `guard` rejects every command except the few needed to unlock and the
+//! lifecycle and background hooks, so a borrowed or stolen unlocked device
+//! doesn't expose messages or keys. Events carrying message content are
+//! held meanwhile and delivered on unlock; see `emit`.
+//! Repeated wrong PINs back off exponentially; the count is kept with the
+//! config, so restarting doesn't reset it. An optional duress PIN unlocks
+//! the decoy identity instead; see `crate::duress`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::duress::Persona;
+use crate::AppState;
+
+/// Error returned for commands refused while locked
+pub const LOCKED_ERROR: &str = "App is locked";
+
+/// Commands that work while locked
+const ALLOWED_WHILE_LOCKED: &[&str] = &[
+    "get_app_lock_status",
+    "unlock_app",
+    "unlock_app_biometric",
+    "set_app_foreground",
+    "run_background_fetch",
+    "handle_push_notification",
+];
+
+/// Commands the app makes on its own, which don't count as activity
+const PASSIVE_COMMANDS: &[&str] = &[
+    "get_app_lock_status",
+    "set_app_foreground",
+    "run_background_fetch",
+    "handle_push_notification",
+];
+
+/// Events carrying decrypted content, held while locked
+const HELD_WHILE_LOCKED: &[&str] = &[
+    "new_message",
+    "message_synced",
+    "contact_request",
+    "channel_post",
+    "location_updated",
+    "poll_updated",
+    "event_rsvp",
+    "proximity_requested",
+];
+
+/// Held events kept at most; the UI reloads on unlock anyway
+const MAX_HELD_EVENTS: usize = 500;
+
+pub const MIN_PIN_LEN: usize = 4;
+
+/// Default idle time before locking again
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u32 = 5 * 60;
+
+/// Wrong PINs allowed before attempts are throttled
+const FREE_ATTEMPTS: u32 = 5;
+const BASE_BACKOFF_MS: i64 = 30 * 1000;
+const MAX_BACKOFF_MS: i64 = 60 * 60 * 1000;
+
+/// How often the idle timer is checked
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Persisted lock settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockConfig {
+    /// Argon2 PHC string; no PIN means the lock is off
+    pub pin_hash: Option<String>,
+    pub biometric: bool,
+    pub idle_timeout_secs: u32,
+    /// Argon2 PHC string of the PIN that unlocks the decoy identity
+    pub duress_pin_hash: Option<String>,
+    #[serde(default)]
+    pub attempts: PinAttempts,
+}
+
+/// Wrong PINs since the last right one
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinAttempts {
+    pub failures: u32,
+    /// Unix timestamp in milliseconds before which PINs are refused
+    pub retry_after: i64,
+}
+
+impl LockConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.pin_hash.is_some()
+    }
+
+    /// This config with `duress_pin` as the duress PIN, or none
+    ///
+    /// Biometric unlock is turned off alongside, since a fingerprint can be
+    /// forced where a PIN can't.
+    pub fn with_duress_pin(&self, duress_pin: Option<&str>) -> Result<LockConfig, String> {
+        let Some(pin_hash) = &self.pin_hash else {
+            return Err("Set an app lock PIN first".to_string());
+        };
+
+        let mut config = self.clone();
+        config.duress_pin_hash = match duress_pin {
+            Some(pin) if check_pin(pin, pin_hash) => {
+                return Err("The duress PIN must differ from the app lock PIN".to_string())
+            }
+            Some(pin) => {
+                config.biometric = false;
+                Some(hash_pin(pin)?)
+            }
+            None => None,
+        };
+        Ok(config)
+    }
+}
+
+/// Lock state for the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct LockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub biometric: bool,
+    pub idle_timeout_secs: u32,
+    /// Unix timestamp in milliseconds before which PINs are refused
+    pub retry_after: Option<i64>,
+}
+
+pub struct AppLock {
+    config: Mutex<LockConfig>,
+    locked: AtomicBool,
+    /// Unix timestamp in milliseconds of the last command
+    last_activity: AtomicI64,
+    /// Held while a PIN is checked, so guesses can't run in parallel
+    verifying: Mutex<()>,
+    /// Content events that arrived while locked, oldest first
+    held: Mutex<VecDeque<(String, Value)>>,
+}
+
+impl AppLock {
+    /// Starts locked when a PIN is configured
+    pub fn new(config: LockConfig) -> Self {
+        Self {
+            locked: AtomicBool::new(config.is_enabled()),
+            config: Mutex::new(config),
+            last_activity: AtomicI64::new(now_ms()),
+            verifying: Mutex::new(()),
+            held: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    pub fn config(&self) -> LockConfig {
+        self.config.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set_config(&self, config: LockConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    fn set_attempts(&self, attempts: PinAttempts) {
+        self.config.lock().unwrap_or_else(|e| e.into_inner()).attempts = attempts;
+    }
+
+    pub fn status(&self) -> LockStatus {
+        let config = self.config();
+        LockStatus {
+            enabled: config.is_enabled(),
+            locked: self.is_locked(),
+            biometric: config.biometric,
+            idle_timeout_secs: config.idle_timeout_secs,
+            retry_after: Some(config.attempts.retry_after).filter(|t| *t > now_ms()),
+        }
+    }
+
+    /// Lock now; a no-op when no PIN is set
+    pub fn lock(&self) -> bool {
+        if !self.config().is_enabled() {
+            return false;
+        }
+        !self.locked.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn unlock(&self) {
+        self.set_attempts(PinAttempts::default());
+        self.last_activity.store(now_ms(), Ordering::SeqCst);
+        self.locked.store(false, Ordering::SeqCst);
+    }
+
+    /// May `command` run? Allowed commands count as activity, except the
+    /// ones the app makes on its own.
+    pub fn permits(&self, command: &str) -> bool {
+        if self.is_locked() && !ALLOWED_WHILE_LOCKED.contains(&command) {
+            return false;
+        }
+        if !PASSIVE_COMMANDS.contains(&command) {
+            self.last_activity.store(now_ms(), Ordering::SeqCst);
+        }
+        true
+    }
+
+    /// Keep a content event until unlock if locked; otherwise hand the
+    /// payload back to be emitted now
+    fn hold(&self, event: &str, payload: Value) -> Option<Value> {
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        // Checked under the queue lock, so `take_held` after unlocking
+        // can't miss an event
+        if !HELD_WHILE_LOCKED.contains(&event) || !self.is_locked() {
+            return Some(payload);
+        }
+        if held.len() == MAX_HELD_EVENTS {
+            held.pop_front();
+        }
+        held.push_back((event.to_string(), payload));
+        None
+    }
+
+    /// Events held while locked, to deliver after `unlock`
+    pub fn take_held(&self) -> Vec<(String, Value)> {
+        self.held.lock().unwrap_or_else(|e| e.into_inner()).drain(..).collect()
+    }
+
+    /// Lock if idle for longer than the timeout; true if it just locked
+    pub fn lock_if_idle(&self, now: i64) -> bool {
+        let timeout_ms = i64::from(self.config().idle_timeout_secs) * 1000;
+        let idle_ms = now - self.last_activity.load(Ordering::SeqCst);
+        timeout_ms > 0 && idle_ms >= timeout_ms && self.lock()
+    }
+
+    /// Check a PIN against the stored hashes, throttling repeated failures
+    ///
+    /// Returns the persona the PIN unlocks. The attempt count changes in
+    /// `config()`, which callers save so the backoff survives a restart.
+    /// Argon2 is deliberately slow; call this off the async workers.
+    pub fn verify_pin(&self, pin: &str, now: i64) -> Result<Persona, String> {
+        let _verifying = self.verifying.lock().unwrap_or_else(|e| e.into_inner());
+        let config = self.config();
+        let Some(hash) = config.pin_hash else {
+            return Err("App lock is not enabled".to_string());
+        };
+
+        let mut attempts = config.attempts;
+        if now < attempts.retry_after {
+            return Err(format!("Too many attempts, try again in {}s", (attempts.retry_after - now + 999) / 1000));
+        }
+
+        let persona = if check_pin(pin, &hash) {
+            Some(Persona::Primary)
+        } else {
+            config.duress_pin_hash.filter(|duress| check_pin(pin, duress)).map(|_| Persona::Decoy)
+        };
+        if let Some(persona) = persona {
+            self.set_attempts(PinAttempts::default());
+            return Ok(persona);
+        }
+
+        attempts.failures += 1;
+        attempts.retry_after = now + backoff_ms(attempts.failures);
+        self.set_attempts(attempts);
+        Err("Incorrect PIN".to_string())
+    }
+}
+
+/// Throttle after `failures` consecutive wrong PINs
+fn backoff_ms(failures: u32) -> i64 {
+    match failures.checked_sub(FREE_ATTEMPTS) {
+        None => 0,
+        Some(extra) => BASE_BACKOFF_MS
+            .saturating_mul(1i64 << extra.min(16))
+            .min(MAX_BACKOFF_MS),
+    }
+}
+
+/// Argon2id hash of a PIN in PHC format
+pub fn hash_pin(pin: &str) -> Result<String, String> {
+    if pin.chars().count() < MIN_PIN_LEN {
+        return Err(format!("PIN must be at least {} characters", MIN_PIN_LEN));
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn check_pin(pin: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(pin.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+/// Wrap the command handler so it refuses commands while locked
+pub fn guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let permitted = invoke
+            .message
+            .webview()
+            .try_state::<AppState>()
+            .is_none_or(|state| state.app_lock.permits(invoke.message.command()));
+        if !permitted {
+            invoke.resolver.reject(LOCKED_ERROR);
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+/// Emit an event to the UI, holding it until unlock if it carries message
+/// content and the app is locked
+pub fn emit<R: Runtime, S: Serialize>(app_handle: &AppHandle<R>, event: &str, payload: S) {
+    let payload = match serde_json::to_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::error!("Failed to serialize {} event: {}", event, e);
+            return;
+        }
+    };
+    let payload = match app_handle.try_state::<AppState>() {
+        Some(state) => state.app_lock.hold(event, payload),
+        None => Some(payload),
+    };
+    if let Some(payload) = payload {
+        if let Err(e) = app_handle.emit(event, payload) {
+            tracing::error!("Failed to emit {} event: {}", event, e);
+        }
+    }
+}
+
+/// Deliver the events held while locked; call after unlocking
+pub fn release_held<R: Runtime>(app_handle: &AppHandle<R>, lock: &AppLock) {
+    for (event, payload) in lock.take_held() {
+        let _ = app_handle.emit(&event, payload);
+    }
+}
+
+/// Lock again after the configured idle time, emitting `app_locked`
+pub fn start_auto_lock(app_handle: AppHandle, lock: Arc<AppLock>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+            if lock.lock_if_idle(now_ms()) {
+                tracing::info!("App locked after idle timeout");
+                let _ = app_handle.emit("app_locked", ());
+            }
+        }
+    });
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked_app() -> AppLock {
+        AppLock::new(LockConfig {
+            pin_hash: Some(hash_pin("1234").unwrap()),
+            biometric: false,
+            idle_timeout_secs: 60,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff_ms(FREE_ATTEMPTS - 1), 0);
+        assert_eq!(backoff_ms(FREE_ATTEMPTS), BASE_BACKOFF_MS);
+        assert_eq!(backoff_ms(FREE_ATTEMPTS + 2), BASE_BACKOFF_MS * 4);
+        assert_eq!(backoff_ms(100), MAX_BACKOFF_MS);
+    }
+
+    #[test]
+    fn test_lock_gate() {
+        let lock = locked_app();
+        assert!(lock.is_locked());
+        assert!(!lock.permits("get_threads"));
+        assert!(lock.permits("unlock_app"));
+        assert!(lock.permits("run_background_fetch"));
+
+        assert!(lock.verify_pin("0000", now_ms()).is_err());
+        assert!(lock.verify_pin("1234", now_ms()).is_ok());
+        lock.unlock();
+        assert!(lock.permits("get_threads"));
+
+        let now = now_ms();
+        assert!(!lock.lock_if_idle(now));
+        assert!(lock.lock_if_idle(now + 61_000));
+        assert!(lock.is_locked());
+
+        // Without a PIN there is nothing to lock
+        assert!(!AppLock::new(LockConfig::default()).lock());
+    }
+
+    #[test]
+    fn test_content_events_wait_for_unlock() {
+        let lock = locked_app();
+        let payload = serde_json::json!({ "id": "m1" });
+        assert_eq!(lock.hold("app_locked", payload.clone()), Some(payload.clone()));
+        assert_eq!(lock.hold("new_message", payload.clone()), None);
+
+        lock.unlock();
+        assert_eq!(lock.hold("new_message", payload.clone()), Some(payload.clone()));
+        assert_eq!(lock.take_held(), vec![("new_message".to_string(), payload)]);
+        assert!(lock.take_held().is_empty());
+    }
+
+    #[test]
+    fn test_duress_pin() {
+        let lock = locked_app();
+        assert!(lock.config().with_duress_pin(Some("1234")).is_err());
+
+        let config = lock.config().with_duress_pin(Some("9999")).unwrap();
+        lock.set_config(config);
+        assert_eq!(lock.verify_pin("1234", now_ms()), Ok(Persona::Primary));
+        assert_eq!(lock.verify_pin("9999", now_ms()), Ok(Persona::Decoy));
+        assert!(lock.verify_pin("0000", now_ms()).is_err());
+
+        let config = lock.config().with_duress_pin(None).unwrap();
+        assert!(config.duress_pin_hash.is_none());
+        assert!(LockConfig::default().with_duress_pin(Some("9999")).is_err());
+    }
+
+    #[test]
+    fn test_wrong_pins_are_throttled() {
+        let lock = locked_app();
+        let now = now_ms();
+        for _ in 0..FREE_ATTEMPTS {
+            assert_eq!(lock.verify_pin("0000", now).unwrap_err(), "Incorrect PIN");
+        }
+        // Even the right PIN waits out the backoff
+        assert!(lock.verify_pin("1234", now + 1000).unwrap_err().starts_with("Too many attempts"));
+
+        // ...including after a restart with the saved config
+        let restarted = AppLock::new(serde_json::from_str(&serde_json::to_string(&lock.config()).unwrap()).unwrap());
+        assert_eq!(restarted.config().attempts.failures, FREE_ATTEMPTS);
+        assert!(restarted.verify_pin("1234", now + 1000).unwrap_err().starts_with("Too many attempts"));
+        assert!(restarted.verify_pin("1234", now + BASE_BACKOFF_MS).is_ok());
+        assert_eq!(restarted.config().attempts, PinAttempts::default());
+    }
+}
@@ -0,0 +1,217 @@
+//! Attachments - Size-capped on-disk cache for downloaded attachments
+//!
+//! Files are named by the SHA-256 of their URL. Reading a file bumps its
+//! modification time, so when the cache grows past its cap the least
+//! recently used files are evicted first.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::network::ApiClient;
+
+/// Default cap; phones get less room than desktops
+#[cfg(any(target_os = "ios", target_os = "android"))]
+pub const DEFAULT_MAX_BYTES: u64 = 200 * 1024 * 1024;
+#[cfg(not(any(target_os = "ios", target_os = "android")))]
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Largest attachment downloaded
+pub const MAX_ATTACHMENT_BYTES: usize = 50 * 1024 * 1024;
+
+/// Disk used by the cache
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheUsage {
+    pub bytes: u64,
+    pub files: u64,
+    pub limit_bytes: u64,
+}
+
+/// A cached file as seen by eviction
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    last_used: SystemTime,
+}
+
+pub struct AttachmentCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    /// Serializes writes and evictions
+    lock: Mutex<()>,
+}
+
+impl AttachmentCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            dir,
+            max_bytes,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Cache under the platform cache directory
+    pub fn open() -> io::Result<Self> {
+        let dir = dirs::cache_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find cache directory"))?
+            .join("gns-browser")
+            .join("attachments");
+        fs::create_dir_all(&dir)?;
+        Ok(Self::new(dir, DEFAULT_MAX_BYTES))
+    }
+
+    /// Cached bytes for `url`, marking them as recently used
+    pub fn get(&self, url: &str) -> Option<Vec<u8>> {
+        let path = self.path_for(url);
+        let bytes = fs::read(&path).ok()?;
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(bytes)
+    }
+
+    /// Store `bytes` for `url`, evicting old files if over the cap
+    pub fn put(&self, url: &str, bytes: &[u8]) -> io::Result<()> {
+        if bytes.len() as u64 > self.max_bytes {
+            return Ok(());
+        }
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.dir)?;
+        // Write then rename so readers never see a partial file
+        let path = self.path_for(url);
+        let partial = path.with_extension("part");
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, &path)?;
+
+        self.evict_locked()
+    }
+
+    /// Cached bytes for `url`, downloading and caching them on a miss
+    pub async fn fetch(&self, api: &ApiClient, url: &str, max_bytes: usize) -> Result<Vec<u8>, String> {
+        if let Some(bytes) = self.get(url) {
+            return Ok(bytes);
+        }
+
+        let bytes = api.fetch_media(url, max_bytes).await.map_err(|e| e.to_string())?;
+        if let Err(e) = self.put(url, &bytes) {
+            tracing::warn!("Failed to cache attachment: {}", e);
+        }
+        Ok(bytes)
+    }
+
+    pub fn usage(&self) -> CacheUsage {
+        let entries = self.entries();
+        CacheUsage {
+            bytes: entries.iter().map(|e| e.size).sum(),
+            files: entries.len() as u64,
+            limit_bytes: self.max_bytes,
+        }
+    }
+
+    /// Delete every cached file, returning the bytes freed
+    pub fn clear(&self) -> io::Result<u64> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut freed = 0;
+        for entry in self.entries() {
+            fs::remove_file(&entry.path)?;
+            freed += entry.size;
+        }
+        Ok(freed)
+    }
+
+    fn path_for(&self, url: &str) -> PathBuf {
+        self.dir.join(hex::encode(Sha256::digest(url.as_bytes())))
+    }
+
+    fn evict_locked(&self) -> io::Result<()> {
+        for path in lru_victims(self.entries(), self.max_bytes) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<CacheEntry> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        dir.filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                metadata.is_file().then(|| CacheEntry {
+                    path: entry.path(),
+                    size: metadata.len(),
+                    last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                })
+            })
+            .filter(|entry| !is_partial(&entry.path))
+            .collect()
+    }
+}
+
+fn is_partial(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "part")
+}
+
+/// Least recently used files to delete to fit within `max_bytes`
+fn lru_victims(mut entries: Vec<CacheEntry>, max_bytes: u64) -> Vec<PathBuf> {
+    let mut total: u64 = entries.iter().map(|e| e.size).sum();
+    entries.sort_by_key(|e| e.last_used);
+
+    entries
+        .into_iter()
+        .take_while(|entry| {
+            let over = total > max_bytes;
+            total -= entry.size;
+            over
+        })
+        .map(|entry| entry.path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lru_victims() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let entry = |name: &str, size, secs| CacheEntry {
+            path: PathBuf::from(name),
+            size,
+            last_used: at(secs),
+        };
+
+        let entries = vec![entry("new", 40, 30), entry("old", 40, 10), entry("mid", 40, 20)];
+        assert_eq!(lru_victims(entries.clone(), 120), Vec::<PathBuf>::new());
+        assert_eq!(lru_victims(entries.clone(), 100), vec![PathBuf::from("old")]);
+        assert_eq!(lru_victims(entries, 40), vec![PathBuf::from("old"), PathBuf::from("mid")]);
+    }
+
+    #[test]
+    fn test_put_get_clear() {
+        let dir = std::env::temp_dir().join(format!("gns-attachments-{}", std::process::id()));
+        let cache = AttachmentCache::new(dir.clone(), 10);
+
+        cache.put("https://a", b"12345").unwrap();
+        cache.put("https://b", b"67890").unwrap();
+        assert_eq!(cache.get("https://a").as_deref(), Some(&b"12345"[..]));
+        assert_eq!(cache.usage().files, 2);
+
+        // Too big to cache at all
+        cache.put("https://c", &[0; 11]).unwrap();
+        assert!(cache.get("https://c").is_none());
+
+        assert_eq!(cache.clear().unwrap(), 10);
+        assert_eq!(cache.usage().bytes, 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
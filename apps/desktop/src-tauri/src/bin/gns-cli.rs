@@ -0,0 +1,7 @@
+//! GNS CLI - Headless entry point
+//!
+//! Scriptable send/receive/resolve/claim/export; see `gns_browser::cli`.
+
+fn main() {
+    std::process::exit(gns_browser::cli::main());
+}
@@ -0,0 +1,223 @@
+//! Calendar - Event invites and RSVPs carried in envelopes
+//!
+//! An invite is a `gns/event` message: title, start and end (ms since the
+//! epoch, UTC), and optionally a location and description. The recipient
+//! answers with a `gns/event.rsvp` message in the same thread. Both are
+//! ordinary messages, so they show up in the conversation; the events
+//! table alongside tracks our answer to each invite (and, as organizer,
+//! everyone else's) for the agenda view and ICS export.
+
+use serde::{Deserialize, Serialize};
+
+/// Payload type for an invite
+pub const EVENT_PAYLOAD_TYPE: &str = "gns/event";
+
+/// Payload type for an answer to an invite
+pub const EVENT_RSVP_PAYLOAD_TYPE: &str = "gns/event.rsvp";
+
+/// Longest event title, in characters
+pub const MAX_EVENT_TITLE: usize = 200;
+
+/// Longest event, so typos in the end date don't make year-long events
+pub const MAX_EVENT_DURATION_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// The invite payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventInvite {
+    pub id: String,
+    pub organizer: String,
+    pub title: String,
+    pub start: i64,
+    pub end: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// An answer to an invite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RsvpStatus {
+    Accepted,
+    Tentative,
+    Declined,
+}
+
+impl RsvpStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RsvpStatus::Accepted => "accepted",
+            RsvpStatus::Tentative => "tentative",
+            RsvpStatus::Declined => "declined",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "accepted" => Some(RsvpStatus::Accepted),
+            "tentative" => Some(RsvpStatus::Tentative),
+            "declined" => Some(RsvpStatus::Declined),
+            _ => None,
+        }
+    }
+}
+
+/// The RSVP payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRsvp {
+    pub event_id: String,
+    pub status: RsvpStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// Someone's answer, as the organizer sees it
+#[derive(Debug, Clone, Serialize)]
+pub struct EventResponse {
+    pub public_key: String,
+    pub status: RsvpStatus,
+    pub responded_at: i64,
+}
+
+/// An invite we sent or received, with where it stands
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarEvent {
+    #[serde(flatten)]
+    pub invite: EventInvite,
+    pub thread_id: Option<String>,
+    /// Our answer; None until we respond. Organizers count as accepted.
+    pub my_status: Option<RsvpStatus>,
+    /// Answers received, for events we organize
+    pub responses: Vec<EventResponse>,
+}
+
+/// Check an invite's fields make sense
+pub fn check_invite(invite: &EventInvite) -> Result<(), String> {
+    if invite.title.trim().is_empty() || invite.title.chars().count() > MAX_EVENT_TITLE {
+        return Err(format!("Event titles must be 1-{} characters", MAX_EVENT_TITLE));
+    }
+    if invite.end < invite.start {
+        return Err("Event ends before it starts".to_string());
+    }
+    if invite.end - invite.start > MAX_EVENT_DURATION_MS {
+        return Err("Events can last at most 30 days".to_string());
+    }
+    Ok(())
+}
+
+/// Events as an iCalendar (RFC 5545) file
+pub fn to_ics(events: &[CalendarEvent], now: i64) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//GNS//Browser//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for event in events {
+        let invite = &event.invite;
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@gns", invite.id));
+        lines.push(format!("DTSTAMP:{}", ics_time(now)));
+        lines.push(format!("DTSTART:{}", ics_time(invite.start)));
+        lines.push(format!("DTEND:{}", ics_time(invite.end)));
+        lines.push(format!("SUMMARY:{}", ics_text(&invite.title)));
+        if let Some(location) = &invite.location {
+            lines.push(format!("LOCATION:{}", ics_text(location)));
+        }
+        if let Some(description) = &invite.description {
+            lines.push(format!("DESCRIPTION:{}", ics_text(description)));
+        }
+        lines.push(format!("ORGANIZER:urn:gns:{}", invite.organizer));
+        if event.my_status == Some(RsvpStatus::Tentative) {
+            lines.push("STATUS:TENTATIVE".to_string());
+        } else {
+            lines.push("STATUS:CONFIRMED".to_string());
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in lines {
+        ics.push_str(&fold(&line));
+        ics.push_str("\r\n");
+    }
+    ics
+}
+
+/// UTC date-time in iCalendar's basic format
+fn ics_time(ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Escape a TEXT value
+fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line at 75 octets without splitting a character
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invite(title: &str, start: i64, end: i64) -> EventInvite {
+        EventInvite {
+            id: "e1".to_string(),
+            organizer: "ab".repeat(32),
+            title: title.to_string(),
+            start,
+            end,
+            location: Some("Room 1, 2nd floor".to_string()),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_check_invite() {
+        assert!(check_invite(&invite("Standup", 1_000, 2_000)).is_ok());
+        assert!(check_invite(&invite("Standup", 2_000, 1_000)).is_err());
+        assert!(check_invite(&invite(" ", 1_000, 2_000)).is_err());
+        assert!(check_invite(&invite("Trip", 0, MAX_EVENT_DURATION_MS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_ics_export() {
+        let event = CalendarEvent {
+            invite: invite(&format!("Planning; {}", "long title ".repeat(8)), 1_700_000_000_000, 1_700_003_600_000),
+            thread_id: None,
+            my_status: Some(RsvpStatus::Accepted),
+            responses: Vec::new(),
+        };
+        let ics = to_ics(&[event], 1_700_000_000_000);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("DTSTART:20231114T221320Z\r\n"));
+        assert!(ics.contains("DTEND:20231114T231320Z\r\n"));
+        assert!(ics.contains("LOCATION:Room 1\\, 2nd floor\r\n"));
+        assert!(ics.contains("SUMMARY:Planning\\; long title"));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Capabilities - Modules and endpoints advertised in the identity record
+//!
+//! The published record carries `modules` (features this client
+//! understands, e.g. `compression`, `sealed_sender`, `groups`) and
+//! `endpoints` (where to reach it besides the relay, e.g. a push gateway).
+//! The built-in modules are always advertised; the app and third-party
+//! modules can register more, stored locally and included on the next
+//! publish.
+//!
+//! Senders read the recipient's modules to pick features it can handle.
+//! A record with no modules at all predates this registry, so it is
+//! treated as supporting everything this client did before.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// zstd-compressed payloads (`contentEncoding: "zstd"`)
+pub const COMPRESSION: &str = "compression";
+
+/// Sealed-sender envelopes
+pub const SEALED_SENDER: &str = "sealed_sender";
+
+/// Direct delivery on the local network
+pub const LAN_DELIVERY: &str = "lan_delivery";
+
+/// ML-KEM-768 key for hybrid encryption, published as `pq_encryption_key`
+#[cfg(feature = "pq-hybrid")]
+pub const PQ_KEM_KEY: &str = "pq_kem_key";
+
+/// Longest module id or endpoint kind
+const MAX_ID_LEN: usize = 64;
+
+/// Most modules or endpoints registered on top of the built-ins
+pub const MAX_REGISTERED: usize = 32;
+
+/// A feature advertised in the record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordModule {
+    pub id: String,
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Module-specific settings, published as given
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub config: Value,
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+/// Somewhere other than the relay this identity can be reached
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordEndpoint {
+    /// What the endpoint is for, e.g. `push`
+    pub kind: String,
+    pub url: String,
+}
+
+/// Modules every build of this client supports
+pub fn builtin_modules() -> Vec<RecordModule> {
+    let ids = [
+        COMPRESSION,
+        SEALED_SENDER,
+        LAN_DELIVERY,
+        #[cfg(feature = "pq-hybrid")]
+        PQ_KEM_KEY,
+    ];
+
+    ids.into_iter()
+        .map(|id| RecordModule {
+            id: id.to_string(),
+            version: 1,
+            config: Value::Null,
+        })
+        .collect()
+}
+
+/// Modules to publish: the built-ins, then the registered ones
+pub fn advertised_modules(registered: Vec<RecordModule>) -> Vec<RecordModule> {
+    let mut modules = builtin_modules();
+    modules.extend(registered.into_iter().filter(|m| !is_builtin(&m.id)));
+    modules
+}
+
+pub fn is_builtin(id: &str) -> bool {
+    builtin_modules().iter().any(|m| m.id == id)
+}
+
+/// Module ids and endpoint kinds: lowercase letters, digits, `_`, `-`, `.`
+pub fn validate_id(id: &str) -> Result<(), String> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid id: {:?}", id))
+    }
+}
+
+/// Endpoints must be https or wss
+pub fn validate_endpoint_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid endpoint URL: {}", e))?;
+    match parsed.scheme() {
+        "https" | "wss" if parsed.host().is_some() => Ok(()),
+        _ => Err("Endpoints must be https:// or wss:// URLs".to_string()),
+    }
+}
+
+/// Module ids from a record's `modules` array (objects or bare strings)
+pub fn module_ids(modules: &Value) -> Vec<String> {
+    modules
+        .as_array()
+        .map(|modules| {
+            modules
+                .iter()
+                .filter_map(|m| m.get("id").unwrap_or(m).as_str())
+                .map(|id| id.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// What a peer's record says it supports
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    ids: Option<HashSet<String>>,
+}
+
+impl Capabilities {
+    pub fn from_ids(ids: &[String]) -> Self {
+        Self {
+            ids: (!ids.is_empty()).then(|| ids.iter().cloned().collect()),
+        }
+    }
+
+    /// Whether the peer handles `module`; always true for records that
+    /// advertise no modules
+    pub fn supports(&self, module: &str) -> bool {
+        self.ids.as_ref().is_none_or(|ids| ids.contains(module))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_module_ids() {
+        let modules = json!([{ "id": "compression", "version": 1 }, "groups", { "version": 2 }]);
+        assert_eq!(module_ids(&modules), vec!["compression", "groups"]);
+        assert!(module_ids(&json!(null)).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_records_support_everything() {
+        let legacy = Capabilities::from_ids(&[]);
+        assert!(legacy.supports(COMPRESSION));
+        assert!(legacy.supports(SEALED_SENDER));
+
+        let modern = Capabilities::from_ids(&["compression".to_string()]);
+        assert!(modern.supports(COMPRESSION));
+        assert!(!modern.supports(SEALED_SENDER));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(validate_id("supports_groups").is_ok());
+        assert!(validate_id("Groups").is_err());
+        assert!(validate_id("").is_err());
+        assert!(validate_endpoint_url("https://push.example.com/gns").is_ok());
+        assert!(validate_endpoint_url("http://push.example.com").is_err());
+        assert!(validate_endpoint_url("not a url").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Channels - Public signed feeds
+//!
+//! A channel is a one-to-many feed: its publisher posts signed but
+//! unencrypted envelopes, and anyone who knows the channel ID can follow
+//! it. The relay stores posts and streams new ones to subscribers in a
+//! `channel_post` frame; after each connect we tell it which channels we
+//! follow and fetch whatever was posted while we were away.
+//!
+//! A channel's ID is derived from its publisher's key and a nonce, so no
+//! one else can publish a descriptor under it. Subscribing checks the
+//! descriptor's signature and that the publisher has a valid signed
+//! record; each post must then verify against the publisher's key before
+//! it is cached.
+
+use gns_crypto_core::GnsIdentity;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{sign_statement, verify_statement};
+use crate::network::frame::RelayFrame;
+use crate::network::{ApiClient, NetworkError, RelayConnection};
+use crate::storage::DatabaseHandle;
+use crate::validation;
+
+/// Longest channel name, in characters
+pub const MAX_CHANNEL_NAME: usize = 80;
+
+/// Longest channel description, in characters
+pub const MAX_CHANNEL_DESCRIPTION: usize = 500;
+
+/// Posts fetched per request when catching up
+pub const CATCH_UP_BATCH: u32 = 100;
+
+/// Signed description of a channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelInfo {
+    pub id: String,
+    pub publisher: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub created_at: i64,
+    pub nonce: String,
+    pub signature: String,
+}
+
+/// A channel as we know it locally
+#[derive(Debug, Clone, Serialize)]
+pub struct Channel {
+    #[serde(flatten)]
+    pub info: ChannelInfo,
+    /// We publish this channel
+    pub is_own: bool,
+    pub subscribed: bool,
+}
+
+/// One signed post
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelPost {
+    pub id: String,
+    pub channel_id: String,
+    pub publisher: String,
+    pub payload_type: String,
+    pub payload: Value,
+    pub timestamp: i64,
+    pub signature: String,
+}
+
+/// Channel ID for a publisher and nonce (hex, 32 characters)
+pub fn channel_id(publisher: &str, nonce: &str) -> String {
+    let digest = Sha256::digest(format!("gns-channel:{}:{}", publisher.to_lowercase(), nonce).as_bytes());
+    hex::encode(&digest[..16])
+}
+
+/// Describe and sign a new channel
+pub fn create_channel(identity: &GnsIdentity, name: &str, description: Option<String>, now: i64) -> ChannelInfo {
+    let publisher = identity.public_key_hex();
+    let nonce = hex::encode(uuid::Uuid::new_v4().as_bytes());
+    let mut info = ChannelInfo {
+        id: channel_id(&publisher, &nonce),
+        publisher,
+        name: name.to_string(),
+        description,
+        created_at: now,
+        nonce,
+        signature: String::new(),
+    };
+    info.signature = sign_statement(identity, &info);
+    info
+}
+
+/// Sign a post to one of our channels
+pub fn create_post(identity: &GnsIdentity, channel_id: &str, payload_type: &str, payload: Value, now: i64) -> ChannelPost {
+    let mut post = ChannelPost {
+        id: uuid::Uuid::new_v4().to_string(),
+        channel_id: channel_id.to_string(),
+        publisher: identity.public_key_hex(),
+        payload_type: payload_type.to_string(),
+        payload,
+        timestamp: now,
+        signature: String::new(),
+    };
+    post.signature = sign_statement(identity, &post);
+    post
+}
+
+/// Check a descriptor's ID and self-signature
+pub fn verify_channel(info: &ChannelInfo) -> Result<(), String> {
+    if info.id != channel_id(&info.publisher, &info.nonce) {
+        return Err("channel ID does not belong to its publisher".to_string());
+    }
+    if !verify_statement(info, &info.publisher, &info.signature) {
+        return Err("signature does not verify".to_string());
+    }
+    Ok(())
+}
+
+/// Check a post belongs to `channel` and is signed by its publisher
+pub fn verify_post(post: &ChannelPost, channel: &ChannelInfo) -> Result<(), String> {
+    if post.channel_id != channel.id {
+        return Err("post is for another channel".to_string());
+    }
+    if !post.publisher.eq_ignore_ascii_case(&channel.publisher) {
+        return Err("post is not from the channel's publisher".to_string());
+    }
+    if !verify_statement(post, &channel.publisher, &post.signature) {
+        return Err("signature does not verify".to_string());
+    }
+
+    let bytes = serde_json::to_vec(&post.payload).map_err(|e| e.to_string())?;
+    validation::validate_payload(&post.payload_type, &bytes).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Fetch posts made since the newest one we have, keeping those that
+/// verify. Returns the posts that were new to us.
+pub async fn catch_up(
+    api: &ApiClient,
+    database: &DatabaseHandle,
+    channel: &ChannelInfo,
+) -> Result<Vec<ChannelPost>, NetworkError> {
+    let id = channel.id.clone();
+    let mut since = database.call(move |db| db.latest_channel_post_time(&id)).await;
+    let mut added = Vec::new();
+    loop {
+        let posts = api.fetch_channel_posts(&channel.id, since, CATCH_UP_BATCH).await?;
+        let fetched = posts.len();
+        let newest = posts.iter().map(|p| p.timestamp).max();
+        let advanced = newest > since;
+        since = newest.or(since);
+
+        let valid: Vec<ChannelPost> = posts
+            .into_iter()
+            .filter(|post| match verify_post(post, channel) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("Dropping post {} in channel {}: {}", post.id, channel.id, e);
+                    false
+                }
+            })
+            .collect();
+        let saved = database
+            .call(move |db| {
+                valid
+                    .into_iter()
+                    .filter(|post| db.save_channel_post(post).unwrap_or(false))
+                    .collect::<Vec<_>>()
+            })
+            .await;
+        added.extend(saved);
+
+        if fetched < CATCH_UP_BATCH as usize || !advanced {
+            return Ok(added);
+        }
+    }
+}
+
+/// Tell the relay which channels to stream to us. Called once the relay
+/// has welcomed us; returns the channels to catch up on.
+pub async fn announce(relay: &RelayConnection, database: &DatabaseHandle) -> Result<Vec<ChannelInfo>, NetworkError> {
+    let channels = database.call(|db| db.subscribed_channels()).await;
+    if !channels.is_empty() {
+        let channel_ids = channels.iter().map(|c| c.id.clone()).collect();
+        relay.send_frame(&RelayFrame::ChannelSubscribe { channel_ids }).await?;
+    }
+    Ok(channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_channel_descriptor() {
+        let identity = GnsIdentity::generate();
+        let info = create_channel(&identity, "Release notes", None, 1_000);
+        assert_eq!(info.id.len(), 32);
+        assert!(verify_channel(&info).is_ok());
+
+        let mut renamed = info.clone();
+        renamed.name = "Something else".to_string();
+        assert!(verify_channel(&renamed).is_err());
+
+        // Someone else can't sign a descriptor under our ID
+        let mallory = GnsIdentity::generate();
+        let mut claimed = create_channel(&mallory, "Release notes", None, 1_000);
+        claimed.id = info.id.clone();
+        assert!(verify_channel(&claimed).is_err());
+    }
+
+    #[test]
+    fn test_posts_verify_against_publisher() {
+        let identity = GnsIdentity::generate();
+        let channel = create_channel(&identity, "News", Some("Daily".to_string()), 1_000);
+        let post = create_post(&identity, &channel.id, "text/plain", json!({ "text": "hello" }), 2_000);
+        assert!(verify_post(&post, &channel).is_ok());
+
+        let mut edited = post.clone();
+        edited.payload = json!({ "text": "goodbye" });
+        assert!(verify_post(&edited, &channel).is_err());
+
+        let other = create_channel(&identity, "Other", None, 1_000);
+        assert!(verify_post(&post, &other).is_err());
+
+        let mallory = GnsIdentity::generate();
+        let forged = create_post(&mallory, &channel.id, "text/plain", json!({ "text": "hello" }), 2_000);
+        assert!(verify_post(&forged, &channel).is_err());
+
+        let unknown = create_post(&identity, &channel.id, "bogus", json!({}), 2_000);
+        assert!(verify_post(&unknown, &channel).is_err());
+    }
+}
@@ -0,0 +1,467 @@
+//! Headless CLI
+//!
+//! `gns-cli` drives the same core as the app (keychain identity, local
+//! database, resolver, relay) without Tauri, so messaging can be scripted
+//! and a relay-connected agent can run in tests. Results are JSON on
+//! stdout; logs go to stderr at `warn` unless `RUST_LOG` says otherwise.
+//!
+//! `receive` prints envelopes as they arrive but doesn't store them: spam
+//! filtering, contact requests and the rest of the message pipeline need
+//! the running app. It refuses to start while the app is running, since
+//! the two relay connections would split incoming messages.
+//!
+//! When an app lock PIN is set, every command asks for it first, and the
+//! duress PIN opens the decoy identity just as it does in the app.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gns_crypto_core::{create_envelope_with_options, open_envelope, EnvelopeOptions};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::app_lock::AppLock;
+use crate::commands::commands_handle::{claim_signing_data, trajectory_proof};
+use crate::commands::handles::{ClaimRequirements, ClaimStage, ClaimWorkflow};
+use crate::crypto::IdentityManager;
+use crate::duress::Persona;
+use crate::error::AppError;
+use crate::export::{self, ExportedThread};
+use crate::network::ack::{AckStatus, RELAY_ACK_TIMEOUT};
+use crate::network::{ApiClient, ClaimProof, IncomingMessage, RelayConnection};
+use crate::payloads;
+use crate::resolver::{self, CachedIdentity};
+use crate::storage::{Database, DatabaseHandle};
+use crate::validation::validate_payload;
+
+const DEFAULT_API_URL: &str = "https://gns-browser-production.up.railway.app";
+const DEFAULT_RELAY_URL: &str = "wss://gns-browser-production.up.railway.app";
+
+const USAGE: &str = "\
+Usage: gns-cli <command> [options]
+
+Commands:
+  resolve <handle|public-key>                 Look up an identity
+  send <handle|public-key> <text> [--thread <id>]
+                                              Send a text message and wait for the relay's ack
+  receive [--count <n>] [--timeout <secs>]    Print incoming messages, one JSON object per line
+  claim                                       Claim the reserved handle
+  export [--format json|markdown] [--out <path>]
+                                              Export every thread
+
+Environment:
+  GNS_API_URL, GNS_RELAY_URL                  Override the backend and relay
+  GNS_PIN                                     App lock PIN, instead of asking
+  RUST_LOG                                    Log filter (default: warn)";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Resolve { recipient: String },
+    Send { recipient: String, text: String, thread_id: Option<String> },
+    /// Stop after `count` messages or `timeout` without one, if given
+    Receive { count: Option<usize>, timeout: Option<Duration> },
+    Claim,
+    Export { markdown: bool, out: Option<PathBuf> },
+    Help,
+}
+
+/// Parse the arguments after the program name
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let Some((name, rest)) = args.split_first() else {
+        return Ok(Command::Help);
+    };
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        if let Some(option) = arg.strip_prefix("--") {
+            let value = rest.next().ok_or_else(|| format!("--{} needs a value", option))?;
+            options.push((option, value.as_str()));
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+
+    let mut option = |wanted: &str| {
+        options
+            .iter()
+            .position(|(name, _)| *name == wanted)
+            .map(|i| options.remove(i).1.to_string())
+    };
+    let command = match (name.as_str(), positional.as_slice()) {
+        ("resolve", [recipient]) => Command::Resolve { recipient: recipient.to_string() },
+        ("send", [recipient, text]) => Command::Send {
+            recipient: recipient.to_string(),
+            text: text.to_string(),
+            thread_id: option("thread"),
+        },
+        ("receive", []) => Command::Receive {
+            count: option("count").map(|n| n.parse().map_err(|_| "--count must be a number")).transpose()?,
+            timeout: option("timeout")
+                .map(|s| s.parse().map(Duration::from_secs).map_err(|_| "--timeout must be a number of seconds"))
+                .transpose()?,
+        },
+        ("claim", []) => Command::Claim,
+        ("export", []) => Command::Export {
+            markdown: match option("format").as_deref() {
+                None | Some("json") => false,
+                Some("markdown") => true,
+                Some(other) => return Err(format!("Unknown export format: {}", other)),
+            },
+            out: option("out").map(PathBuf::from),
+        },
+        ("help" | "--help" | "-h", _) => Command::Help,
+        ("resolve" | "send" | "receive" | "claim" | "export", _) => {
+            return Err(format!("Wrong arguments for {}\n\n{}", name, USAGE))
+        }
+        _ => return Err(format!("Unknown command: {}\n\n{}", name, USAGE)),
+    };
+    if let Some((name, _)) = options.first() {
+        return Err(format!("Unknown option: --{}", name));
+    }
+    Ok(command)
+}
+
+/// Entry point for the `gns-cli` binary; returns the exit code
+pub fn main() -> i32 {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into());
+    tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr).init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse(&args) {
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return 1;
+        }
+    };
+    match runtime.block_on(run(command)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error [{}]: {}", e.code(), e);
+            1
+        }
+    }
+}
+
+/// The parts of the app core a command needs
+struct Core {
+    identity: IdentityManager,
+    database: DatabaseHandle,
+    api: Arc<ApiClient>,
+    relay_url: String,
+}
+
+impl Core {
+    fn open() -> Result<Self, AppError> {
+        let api_url = std::env::var("GNS_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        let mut identity = IdentityManager::new()?;
+        let mut database = Database::open()?;
+
+        // The lock config always lives in the primary database, and so does
+        // the wrong-PIN count; saving it makes each run share the app's backoff
+        let lock = database.get_app_lock_config();
+        if lock.is_enabled() {
+            let pin = read_pin()?;
+            let lock = AppLock::new(lock);
+            let verified = lock.verify_pin(&pin, chrono::Utc::now().timestamp_millis());
+            database.set_app_lock_config(&lock.config())?;
+            let persona = verified?;
+            if persona != Persona::Primary {
+                database = Database::open_persona(persona)?;
+                identity.switch_persona(persona)?;
+            }
+        }
+
+        let api = ApiClient::new(&api_url)?;
+        api.set_doh_url(database.get_dns_over_https());
+
+        Ok(Self {
+            identity,
+            database: DatabaseHandle::spawn(database)?,
+            api: Arc::new(api),
+            relay_url: std::env::var("GNS_RELAY_URL").unwrap_or_else(|_| DEFAULT_RELAY_URL.to_string()),
+        })
+    }
+
+    /// A relay connection for our identity
+    async fn connect(&self, incoming: Option<mpsc::Sender<IncomingMessage>>) -> Result<RelayConnection, AppError> {
+        let public_key = self.identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+        let relay = RelayConnection::new(&self.relay_url)?.with_store(self.database.clone());
+        let relay = match incoming {
+            Some(tx) => relay.with_incoming_channel(tx),
+            None => relay,
+        };
+        relay.connect(&public_key).await?;
+        Ok(relay)
+    }
+
+    async fn resolve(&self, recipient: &str) -> Result<CachedIdentity, AppError> {
+        if is_public_key(recipient) {
+            resolver::resolve_identity(&self.api, &self.database, recipient)
+                .await?
+                .ok_or_else(|| AppError::not_found("Identity"))
+        } else {
+            let handle = recipient.trim_start_matches('@').to_lowercase();
+            resolver::resolve_handle(&self.api, &self.database, &handle)
+                .await?
+                .ok_or_else(|| AppError::not_found("Handle"))
+        }
+    }
+}
+
+/// The app lock PIN, from `GNS_PIN` or asked for on the terminal
+fn read_pin() -> Result<String, AppError> {
+    if let Ok(pin) = std::env::var("GNS_PIN") {
+        return Ok(pin);
+    }
+    eprint!("App lock PIN: ");
+    std::io::stderr().flush()?;
+    let mut pin = String::new();
+    std::io::stdin().read_line(&mut pin)?;
+    Ok(pin.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn is_public_key(recipient: &str) -> bool {
+    recipient.len() == 64 && recipient.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn print_json(value: &serde_json::Value) {
+    println!("{}", value);
+}
+
+async fn run(command: Command) -> Result<(), AppError> {
+    if matches!(command, Command::Receive { .. }) && crate::instance::is_running() {
+        return Err("GNS Browser is running and already receives messages; quit it first".into());
+    }
+    let core = Core::open()?;
+    match command {
+        Command::Resolve { recipient } => {
+            let resolved = core.resolve(&recipient).await?;
+            print_json(&serde_json::to_value(resolved)?);
+        }
+        Command::Send { recipient, text, thread_id } => send(&core, &recipient, &text, thread_id).await?,
+        Command::Receive { count, timeout } => receive(&core, count, timeout).await?,
+        Command::Claim => claim(&core).await?,
+        Command::Export { markdown, out } => export(&core, markdown, out).await?,
+        Command::Help => {}
+    }
+    Ok(())
+}
+
+async fn send(core: &Core, recipient: &str, text: &str, thread_id: Option<String>) -> Result<(), AppError> {
+    let payload_type = "text/plain";
+    let payload_bytes = serde_json::to_vec(&json!({ "text": text }))?;
+    validate_payload(payload_type, &payload_bytes)?;
+
+    let identity = core.identity.get_identity().ok_or(AppError::NoIdentity)?;
+    let info = core.resolve(recipient).await?.info;
+    let my_handle = core.identity.cached_handle();
+    let envelope = create_envelope_with_options(
+        identity,
+        &info.public_key,
+        &info.encryption_key,
+        payload_type,
+        &payload_bytes,
+        &EnvelopeOptions {
+            sender_handle: my_handle.as_deref(),
+            thread_id: thread_id.as_deref(),
+            reply_to_id: None,
+            recipient_pq_key_hex: info.pq_encryption_key.as_deref(),
+            compression_threshold: None,
+        },
+    )?;
+
+    let relay = core.connect(None).await?;
+    let ack = relay.send_envelope(&envelope).await?;
+
+    let (saved, handle) = (envelope.clone(), info.handle.clone());
+    core.database
+        .call(move |db| db.save_sent_message(&saved, &payload_bytes, handle.as_deref(), None))
+        .await?;
+
+    let status = ack.wait(RELAY_ACK_TIMEOUT).await.unwrap_or_else(|| AckStatus::Rejected {
+        reason: "No acknowledgement from relay".to_string(),
+    });
+    let (id, new_status) = (envelope.id.clone(), status.message_status());
+    core.database.call(move |db| db.advance_message_status(&id, new_status)).await?;
+    let _ = relay.disconnect().await;
+
+    print_json(&json!({
+        "message_id": envelope.id,
+        "thread_id": envelope.thread_id,
+        "ack": status,
+    }));
+    match status {
+        AckStatus::Rejected { reason } => Err(AppError::Server { reason }),
+        _ => Ok(()),
+    }
+}
+
+async fn receive(core: &Core, count: Option<usize>, timeout: Option<Duration>) -> Result<(), AppError> {
+    let identity = core.identity.get_identity().ok_or(AppError::NoIdentity)?;
+    let (tx, mut rx) = mpsc::channel(100);
+    let relay = core.connect(Some(tx)).await?;
+
+    let mut received = 0;
+    while count != Some(received) {
+        let next = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, rx.recv()).await {
+                Ok(next) => next,
+                Err(_) => break,
+            },
+            None => rx.recv().await,
+        };
+        let Some(message) = next else {
+            break;
+        };
+        let IncomingMessage::Envelope(envelope) = message else {
+            continue;
+        };
+        let opened = match open_envelope(identity, &envelope) {
+            Ok(opened) => opened,
+            Err(e) => {
+                tracing::warn!("Failed to open envelope {}: {}", envelope.id, e);
+                continue;
+            }
+        };
+        let payload = match payloads::registry().decode(&opened.payload_type, &opened.payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Rejected envelope {}: {}", envelope.id, e);
+                continue;
+            }
+        };
+        print_json(&json!({
+            "id": envelope.id,
+            "thread_id": opened.thread_id,
+            "from_public_key": opened.from_public_key,
+            "from_handle": opened.from_handle,
+            "payload_type": opened.payload_type,
+            "payload": payload,
+            "timestamp": opened.timestamp,
+            "signature_valid": opened.signature_valid,
+        }));
+        let _ = std::io::stdout().flush();
+        received += 1;
+    }
+
+    let _ = relay.disconnect().await;
+    Ok(())
+}
+
+/// Claim the reserved handle. Publishing the record is left to the app,
+/// which finishes claimed workflows at startup.
+async fn claim(core: &Core) -> Result<(), AppError> {
+    let identity = core.identity.get_identity().ok_or(AppError::NoIdentity)?;
+    let handle = core.identity.cached_handle().ok_or("No handle reserved")?;
+    let public_key = identity.public_key_hex();
+
+    let proof = trajectory_proof(&core.database, &public_key).await?;
+    let requirements = ClaimRequirements::new(proof.breadcrumb_count, proof.trust_score);
+    if !requirements.is_met() {
+        print_json(&json!({ "success": false, "requirements": requirements }));
+        return Err("Requirements not met".into());
+    }
+
+    let proof = ClaimProof {
+        breadcrumb_count: proof.breadcrumb_count,
+        first_breadcrumb_at: proof.first_breadcrumb_at,
+        trust_score: proof.trust_score,
+    };
+    let signature = hex::encode(identity.sign_bytes(claim_signing_data(&handle, &public_key, &proof).as_bytes()));
+    let result = core.api.claim_handle_with_proof(&handle, &public_key, &proof, &signature).await?;
+
+    if result.success {
+        let now = chrono::Utc::now().timestamp_millis();
+        core.database
+            .call(move |db| {
+                let mut workflow = db
+                    .get_claim_workflow(&public_key)
+                    .unwrap_or_else(|| ClaimWorkflow::reserved(&public_key, &handle, true, None, now));
+                workflow.last_error = None;
+                workflow.set_stage(ClaimStage::Claimed, now);
+                db.save_claim_workflow(&workflow)
+            })
+            .await?;
+    }
+    print_json(&serde_json::to_value(&result)?);
+    Ok(())
+}
+
+async fn export(core: &Core, markdown: bool, out: Option<PathBuf>) -> Result<(), AppError> {
+    let threads = core
+        .database
+        .call(|db| {
+            let mut threads = Vec::new();
+            for thread in db.get_threads(true, None, u32::MAX)? {
+                let mut messages = db.get_messages(&thread.id, u32::MAX)?;
+                messages.reverse();
+                threads.push(ExportedThread { thread, messages });
+            }
+            Ok::<_, crate::storage::DatabaseError>(threads)
+        })
+        .await?;
+
+    let bytes = if markdown {
+        export::render_markdown(&threads, core.identity.cached_handle().as_deref()).into_bytes()
+    } else {
+        export::render_json(&threads)?
+    };
+    match out {
+        Some(path) => std::fs::write(path, bytes)?,
+        None => std::io::stdout().write_all(&bytes)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(&[]).unwrap(), Command::Help);
+        assert_eq!(
+            parse(&args("send @alice hi --thread t1")).unwrap(),
+            Command::Send {
+                recipient: "@alice".to_string(),
+                text: "hi".to_string(),
+                thread_id: Some("t1".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(&args("receive --timeout 5")).unwrap(),
+            Command::Receive { count: None, timeout: Some(Duration::from_secs(5)) }
+        );
+        assert_eq!(
+            parse(&args("export --format markdown")).unwrap(),
+            Command::Export { markdown: true, out: None }
+        );
+
+        assert!(parse(&args("send @alice")).is_err());
+        assert!(parse(&args("receive --count")).is_err());
+        assert!(parse(&args("receive --count many")).is_err());
+        assert!(parse(&args("claim --force yes")).is_err());
+        assert!(parse(&args("export --format pdf")).is_err());
+        assert!(parse(&args("frobnicate")).is_err());
+    }
+}
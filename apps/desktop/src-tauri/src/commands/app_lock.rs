@@ -0,0 +1,204 @@
+//! App Lock Commands
+//!
+//! Configure the PIN / biometric lock and unlock the app. Everything else
+//! is refused while locked; see `app_lock::guard`.
+
+use crate::app_lock::{self, hash_pin, LockConfig, LockStatus, DEFAULT_IDLE_TIMEOUT_SECS};
+use crate::duress::{self, Persona};
+use crate::error::AppError;
+use crate::storage::Database;
+use crate::AppState;
+use tauri::{AppHandle, Emitter, State};
+
+#[tauri::command]
+pub async fn get_app_lock_status(state: State<'_, AppState>) -> Result<LockStatus, AppError> {
+    Ok(state.app_lock.status())
+}
+
+/// Turn the lock on or off, change the PIN, biometrics or idle timeout
+///
+/// Changing an existing lock needs `current_pin`. `pin` is the new PIN,
+/// required when enabling for the first time.
+#[tauri::command]
+pub async fn set_app_lock(
+    state: State<'_, AppState>,
+    enabled: bool,
+    pin: Option<String>,
+    current_pin: Option<String>,
+    biometric: Option<bool>,
+    idle_timeout_secs: Option<u32>,
+) -> Result<LockStatus, AppError> {
+    if state.app_lock.config().is_enabled() {
+        verify_primary_pin(&state, current_pin.unwrap_or_default()).await?;
+    }
+    let current = state.app_lock.config();
+    let config = tauri::async_runtime::spawn_blocking(move || {
+        if !enabled {
+            return Ok(LockConfig::default());
+        }
+
+        let pin_hash = match pin {
+            Some(pin) => hash_pin(&pin)?,
+            None => current.pin_hash.ok_or("A PIN is required to enable the app lock")?,
+        };
+        Ok::<_, String>(LockConfig {
+            pin_hash: Some(pin_hash),
+            biometric: biometric.unwrap_or(current.biometric) && current.duress_pin_hash.is_none(),
+            idle_timeout_secs: idle_timeout_secs
+                .or(Some(current.idle_timeout_secs).filter(|t| *t > 0))
+                .unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS),
+            duress_pin_hash: current.duress_pin_hash,
+            attempts: current.attempts,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    save_config(&state, config).await
+}
+
+/// Set or remove the duress PIN, which unlocks a decoy identity
+///
+/// Needs the real PIN. Setting one turns off biometric unlock.
+#[tauri::command]
+pub async fn set_duress_pin(
+    state: State<'_, AppState>,
+    current_pin: String,
+    duress_pin: Option<String>,
+) -> Result<LockStatus, AppError> {
+    verify_primary_pin(&state, current_pin).await?;
+    let current = state.app_lock.config();
+    let config = tauri::async_runtime::spawn_blocking(move || current.with_duress_pin(duress_pin.as_deref()))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    save_config(&state, config).await
+}
+
+/// Check a PIN off the async workers, then save the attempt count so the
+/// backoff outlives a restart
+async fn verify_pin(state: &AppState, pin: String) -> Result<Persona, String> {
+    let lock = state.app_lock.clone();
+    let verified = tauri::async_runtime::spawn_blocking(move || {
+        lock.verify_pin(&pin, chrono::Utc::now().timestamp_millis())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Err(e) = store_config(state, state.app_lock.config()).await {
+        tracing::error!("Failed to save PIN attempts: {}", e);
+    }
+    verified
+}
+
+/// Config changes need the real PIN; the duress PIN is refused like a wrong one
+async fn verify_primary_pin(state: &AppState, pin: String) -> Result<(), String> {
+    match verify_pin(state, pin).await? {
+        Persona::Primary => Ok(()),
+        Persona::Decoy => Err("Incorrect PIN".to_string()),
+    }
+}
+
+/// Confirm a PIN before revealing key material (`dangerous_` commands)
+///
+/// Nothing to confirm when the lock is off. Otherwise the PIN must unlock
+/// the identity that is currently active, so the duress PIN can only ever
+/// reveal the decoy's key.
+pub(crate) async fn confirm_pin(state: &AppState, pin: Option<String>) -> Result<(), String> {
+    if !state.app_lock.config().is_enabled() {
+        return Ok(());
+    }
+    let pin = pin.ok_or("PIN confirmation required")?;
+    let persona = verify_pin(state, pin).await?;
+
+    if persona != state.identity.lock().await.persona() {
+        return Err("Incorrect PIN".to_string());
+    }
+    Ok(())
+}
+
+/// Save and apply a new lock config
+async fn save_config(state: &AppState, config: LockConfig) -> Result<LockStatus, AppError> {
+    store_config(state, config.clone()).await?;
+    state.app_lock.set_config(config);
+
+    Ok(state.app_lock.status())
+}
+
+/// Lock settings live in the primary database, whichever persona is active
+async fn store_config(state: &AppState, config: LockConfig) -> Result<(), AppError> {
+    if state.identity.lock().await.persona() == Persona::Primary {
+        state
+            .database
+            .call(move |db| db.set_app_lock_config(&config))
+            .await?;
+    } else {
+        tauri::async_runtime::spawn_blocking(move || Database::open_persona(Persona::Primary)?.set_app_lock_config(&config))
+            .await
+            .map_err(|e| e.to_string())??;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn lock_app(app_handle: AppHandle, state: State<'_, AppState>) -> Result<LockStatus, AppError> {
+    if state.app_lock.lock() {
+        let _ = app_handle.emit("app_locked", ());
+    }
+    Ok(state.app_lock.status())
+}
+
+/// Unlock with a PIN; the duress PIN switches to the decoy identity and
+/// the real PIN back to the real one
+///
+/// Events held while locked are delivered, unless the persona changed:
+/// they belong to the other identity.
+#[tauri::command]
+pub async fn unlock_app(app_handle: AppHandle, state: State<'_, AppState>, pin: String) -> Result<LockStatus, AppError> {
+    let persona = verify_pin(&state, pin).await?;
+
+    let switched = state.identity.lock().await.persona() != persona;
+    duress::switch_persona(&state, persona).await?;
+    state.app_lock.unlock();
+    if switched {
+        state.app_lock.take_held();
+    } else {
+        app_lock::release_held(&app_handle, &state.app_lock);
+    }
+    Ok(state.app_lock.status())
+}
+
+/// Unlock with the platform's biometric prompt (mobile only)
+#[tauri::command]
+pub async fn unlock_app_biometric(app_handle: AppHandle, state: State<'_, AppState>) -> Result<LockStatus, AppError> {
+    let config = state.app_lock.config();
+    if !config.biometric || config.duress_pin_hash.is_some() {
+        return Err("Biometric unlock is not enabled".into());
+    }
+
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    {
+        use tauri_plugin_biometric::{AuthOptions, BiometricExt};
+
+        app_handle
+            .biometric()
+            .authenticate(
+                "Unlock GNS Browser".to_string(),
+                AuthOptions {
+                    allow_device_credential: false,
+                    ..Default::default()
+                },
+            )?;
+
+        state.app_lock.unlock();
+        app_lock::release_held(&app_handle, &state.app_lock);
+        store_config(&state, state.app_lock.config()).await?;
+        Ok(state.app_lock.status())
+    }
+
+    #[cfg(not(any(target_os = "ios", target_os = "android")))]
+    {
+        let _ = app_handle;
+        Err("Biometric unlock is not available on this platform".into())
+    }
+}
@@ -0,0 +1,57 @@
+//! Attachment Commands
+//!
+//! Download attachments through the local cache and report or clear the
+//! disk space it uses.
+
+use base64::Engine;
+use serde::Serialize;
+use tauri::State;
+
+use crate::attachments::{CacheUsage, MAX_ATTACHMENT_BYTES};
+use crate::error::AppError;
+use crate::AppState;
+
+/// Disk used by the app's data
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    pub database_bytes: u64,
+    pub attachment_cache: CacheUsage,
+}
+
+/// Attachment bytes (base64), served from the cache when possible
+#[tauri::command]
+pub async fn get_attachment(state: State<'_, AppState>, url: String) -> Result<String, AppError> {
+    let bytes = state
+        .attachments
+        .fetch(&state.api, &url, MAX_ATTACHMENT_BYTES)
+        .await?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+#[tauri::command]
+pub async fn get_storage_usage(state: State<'_, AppState>) -> Result<StorageUsage, AppError> {
+    let database_bytes = state
+        .database
+        .call(|db| db.size_bytes())
+        .await?;
+
+    let attachments = state.attachments.clone();
+    let attachment_cache = tauri::async_runtime::spawn_blocking(move || attachments.usage())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(StorageUsage {
+        database_bytes,
+        attachment_cache,
+    })
+}
+
+/// Delete all cached attachments, returns the bytes freed
+#[tauri::command]
+pub async fn clear_attachment_cache(state: State<'_, AppState>) -> Result<u64, AppError> {
+    let attachments = state.attachments.clone();
+    tauri::async_runtime::spawn_blocking(move || attachments.clear())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(AppError::from)
+}
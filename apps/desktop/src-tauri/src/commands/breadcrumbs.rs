@@ -1,19 +1,33 @@
+use crate::error::AppError;
+use crate::location::audit::{self, AuditReport};
+use crate::location::sync::DeviceConditions;
+use crate::location::{MotionActivity, PrivacyZone};
+use crate::trust::trajectory::{self, TrajectoryStats};
+use crate::trust::{compute_trust_score, TrustScore};
 use crate::AppState;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 use gns_crypto_core::Breadcrumb;
 
 // ==================== Commands ====================
 
 /// Get breadcrumb collection status
 #[tauri::command]
-pub async fn get_breadcrumb_status(state: State<'_, AppState>) -> Result<BreadcrumbStatus, String> {
-    let db = state.database.lock().await;
-
+pub async fn get_breadcrumb_status(state: State<'_, AppState>) -> Result<BreadcrumbStatus, AppError> {
     // Get counts
-    let count = db.count_breadcrumbs().unwrap_or(0);
-    let unique_locations = db.count_unique_locations().unwrap_or(0);
-    let first_breadcrumb = db.get_first_breadcrumb_time();
-    let last_breadcrumb = db.get_last_breadcrumb_time();
+    let (count, unique_locations, first_breadcrumb, last_breadcrumb, pending_upload, history) = state
+        .database
+        .call(|db| {
+            (
+                db.count_breadcrumbs().unwrap_or(0),
+                db.count_unique_locations().unwrap_or(0),
+                db.get_first_breadcrumb_time(),
+                db.get_last_breadcrumb_time(),
+                db.count_unsynced_breadcrumbs().unwrap_or(0),
+                db.get_breadcrumb_history().unwrap_or_default(),
+            )
+        })
+        .await;
+    let trust_score = compute_trust_score(&history).score;
 
     // Check handle status - only true if handle is claimed on the network
     // A cached/reserved handle is NOT the same as a claimed handle
@@ -23,8 +37,7 @@ pub async fn get_breadcrumb_status(state: State<'_, AppState>) -> Result<Breadcr
             // Handle is claimed if user has collected 100+ breadcrumbs
             // This proves they're a real human with proof-of-trajectory
             // TODO: Also check network for actual claim status in the future
-            // TODO: Add trust_score >= 20 requirement when trust system is implemented
-            count >= 100
+            count >= 100 && trust_score >= 20.0
         }
         None => false,
     };
@@ -74,14 +87,18 @@ pub async fn get_breadcrumb_status(state: State<'_, AppState>) -> Result<Breadcr
         collection_enabled,
         handle_claimed,
         estimated_completion_at: estimated_completion,
+        pending_upload,
     })
 }
 
 /// Get breadcrumb count
 #[tauri::command]
-pub async fn get_breadcrumb_count(state: State<'_, AppState>) -> Result<u32, String> {
-    let db = state.database.lock().await;
-    db.count_breadcrumbs().map_err(|e| e.to_string())
+pub async fn get_breadcrumb_count(state: State<'_, AppState>) -> Result<u32, AppError> {
+    state
+        .database
+        .call(|db| db.count_breadcrumbs())
+        .await
+        .map_err(AppError::from)
 }
 
 /// Enable or disable breadcrumb collection (mobile only)
@@ -89,21 +106,23 @@ pub async fn get_breadcrumb_count(state: State<'_, AppState>) -> Result<u32, Str
 pub async fn set_collection_enabled(
     enabled: bool,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     #[cfg(any(target_os = "ios", target_os = "android"))]
     {
         // Persist state to database
-        let mut db = state.database.lock().await;
-        db.set_collection_enabled(enabled).map_err(|e| e.to_string())?;
-        drop(db); // Release lock before accessing collector
+        state
+            .database
+            .call(move |db| db.set_collection_enabled(enabled))
+            .await?;
         
         // Update collector
         let mut collector: tokio::sync::MutexGuard<'_, crate::location::BreadcrumbCollector> = state.breadcrumb_collector.lock().await;
         if enabled {
-            collector.start().map_err(|e| e.to_string())?;
+            collector.start()?;
         } else {
             collector.stop();
         }
+        crate::native::set_motion_updates(enabled);
         
         tracing::info!("📍 Breadcrumb collection {}", if enabled { "enabled" } else { "disabled" });
         Ok(())
@@ -111,7 +130,7 @@ pub async fn set_collection_enabled(
 
     #[cfg(not(any(target_os = "ios", target_os = "android")))]
     {
-        Err("Breadcrumb collection is only available on mobile devices".to_string())
+        Err("Breadcrumb collection is only available on mobile devices".into())
     }
 }
 
@@ -121,18 +140,38 @@ pub async fn drop_breadcrumb(
     latitude: f64,
     longitude: f64,
     accuracy: Option<f64>,
+    app_handle: AppHandle,
     state: State<'_, AppState>,
-) -> Result<DropBreadcrumbResult, String> {
-    use gns_crypto_core::breadcrumb::create_breadcrumb;
+) -> Result<DropBreadcrumbResult, AppError> {
+    use gns_crypto_core::breadcrumb::{create_breadcrumb, lat_lng_to_h3, DEFAULT_H3_RESOLUTION};
     
     // Get identity
     let identity_mgr = state.identity.lock().await;
     let identity = identity_mgr.get_identity()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
-    // Get last breadcrumb hash for chain
-    let mut db = state.database.lock().await;
-    let recent = db.get_recent_breadcrumbs(1).map_err(|e| e.to_string())?;
+    // Don't record anything inside a privacy zone
+    let cell = lat_lng_to_h3(latitude, longitude, DEFAULT_H3_RESOLUTION)?;
+    let (zone, count, recent) = state
+        .database
+        .call(move |db| {
+            let zone = db.get_privacy_zones().into_iter().find(|z| z.cells.contains(&cell));
+            let count = db.count_breadcrumbs()?;
+            // Get last breadcrumb for the hash chain
+            let recent = db.get_recent_breadcrumbs(1)?;
+            Ok::<_, crate::storage::DatabaseError>((zone, count, recent))
+        })
+        .await?;
+    if let Some(zone) = zone {
+        tracing::info!("📍 Skipping breadcrumb inside privacy zone '{}'", zone.name);
+        return Ok(DropBreadcrumbResult {
+            success: false,
+            count,
+            h3_cell: String::new(),
+            privacy_zone: Some(zone.name),
+        });
+    }
+
     let prev_hash = recent.first().map(|b| {
         // Hash the previous breadcrumb
         use sha2::{Sha256, Digest};
@@ -148,13 +187,21 @@ pub async fn drop_breadcrumb(
         longitude,
         None, // Use default H3 resolution
         prev_hash,
-    ).map_err(|e| e.to_string())?;
-    
-    // Save to database
-    db.save_breadcrumb(&breadcrumb).map_err(|e| e.to_string())?;
+    )?;
     
-    // Get updated count
-    let count = db.count_breadcrumbs().map_err(|e| e.to_string())?;
+    // Save to database and get updated count
+    drop(identity_mgr);
+    let saved = breadcrumb.clone();
+    let count = state
+        .database
+        .call(move |db| {
+            db.save_breadcrumb(&saved)?;
+            db.count_breadcrumbs()
+        })
+        .await?;
+
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    state.breadcrumb_collector.lock().await.record_collection_at(&breadcrumb.h3_index);
     
     tracing::info!(
         "📍 Breadcrumb #{} dropped at H3: {} (accuracy: {:?}m)",
@@ -162,75 +209,400 @@ pub async fn drop_breadcrumb(
         &breadcrumb.h3_index,
         accuracy
     );
+
+    // Claim the reserved handle once this breadcrumb meets the requirements
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        crate::commands::commands_handle::advance_claim(&app_handle, &state).await;
+    });
     
     Ok(DropBreadcrumbResult {
         success: true,
         count,
         h3_cell: breadcrumb.h3_index,
+        privacy_zone: None,
     })
 }
 
+/// Report a motion signal for motion-aware collection (mobile only)
+///
+/// On iOS, CoreMotion feeds the collector through `native::gns_report_motion`;
+/// this is for signals the webview obtains itself.
+#[tauri::command]
+pub async fn report_motion_activity(
+    activity: MotionActivity,
+    significant_motion: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    {
+        let mut collector = state.breadcrumb_collector.lock().await;
+        collector.report_motion(activity, significant_motion.unwrap_or(false));
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "ios", target_os = "android")))]
+    {
+        let _ = (activity, significant_motion, state);
+        Err("Motion activity is only available on mobile devices".into())
+    }
+}
+
+/// Ask the collector whether to collect now. Without coordinates this answers
+/// whether a GPS fix is worth requesting; with them, whether to drop a breadcrumb there.
+#[tauri::command]
+pub async fn should_collect_breadcrumb(
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    {
+        let collector = state.breadcrumb_collector.lock().await;
+        Ok(match (latitude, longitude) {
+            (Some(lat), Some(lng)) => collector.should_collect_at(lat, lng),
+            _ => collector.needs_location_fix(),
+        })
+    }
+
+    #[cfg(not(any(target_os = "ios", target_os = "android")))]
+    {
+        let _ = (latitude, longitude, state);
+        Ok(false)
+    }
+}
+
+/// Report network and battery state so background uploads can wait for a
+/// cheap connection and enough charge
+#[tauri::command]
+pub async fn set_device_conditions(
+    metered: bool,
+    battery_level: f32,
+    is_charging: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.breadcrumb_sync.set_conditions(DeviceConditions {
+        metered,
+        battery_level: battery_level.clamp(0.0, 1.0),
+        is_charging,
+    });
+    Ok(())
+}
+
+/// Whether background breadcrumb uploads are paused
+#[tauri::command]
+pub async fn get_breadcrumb_upload_paused(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.breadcrumb_sync.is_paused())
+}
+
+/// Pause or resume background breadcrumb uploads
+#[tauri::command]
+pub async fn set_breadcrumb_upload_paused(
+    paused: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.set_breadcrumb_upload_paused(paused))
+        .await?;
+
+    state.breadcrumb_sync.set_paused(paused);
+    tracing::info!("Breadcrumb upload {}", if paused { "paused" } else { "resumed" });
+    Ok(())
+}
+
+/// Upload pending breadcrumbs now, regardless of device conditions
+#[tauri::command]
+pub async fn sync_breadcrumbs_now(state: State<'_, AppState>) -> Result<u32, AppError> {
+    crate::location::sync::sync_breadcrumbs(&state.identity, &state.database, &state.api)
+        .await
+        .map_err(AppError::from)
+}
+
 /// Get list of recent breadcrumbs for history view
 #[tauri::command]
 pub async fn list_breadcrumbs(
     state: State<'_, AppState>,
     limit: Option<u32>,
     offset: Option<u32>,
-) -> Result<Vec<Breadcrumb>, String> {
-    let db = state.database.lock().await;
-    db.get_breadcrumbs(limit.unwrap_or(50), offset.unwrap_or(0))
-        .map_err(|e| e.to_string())
+) -> Result<Vec<Breadcrumb>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_breadcrumbs(limit.unwrap_or(50), offset.unwrap_or(0)))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Re-verify every stored breadcrumb against the current identity and
+/// report the rows that fail; with `quarantine`, rows with a bad
+/// signature are moved out of the breadcrumbs table
+#[tauri::command]
+pub async fn audit_breadcrumbs(
+    state: State<'_, AppState>,
+    quarantine: Option<bool>,
+) -> Result<AuditReport, AppError> {
+    let public_key = state
+        .identity
+        .lock()
+        .await
+        .public_key_hex()
+        .ok_or(AppError::NoIdentity)?;
+    let now = chrono::Utc::now().timestamp();
+    let report = state
+        .database
+        .call(move |db| {
+            let rows = db.get_breadcrumbs_for_audit()?;
+            let mut report = audit::audit(&rows, &public_key, now);
+            if quarantine.unwrap_or(false) {
+                let bad: Vec<(i64, &str)> = report
+                    .anomalies
+                    .iter()
+                    .filter(|a| a.kind.quarantines())
+                    .map(|a| (a.id, a.kind.as_str()))
+                    .collect();
+                report.quarantined = db.quarantine_breadcrumbs(&bad)?;
+            }
+            Ok::<_, crate::storage::DatabaseError>(report)
+        })
+        .await?;
+
+    if !report.anomalies.is_empty() {
+        tracing::warn!(
+            "Breadcrumb audit: {} of {} rows failed, {} quarantined",
+            report.anomalies.len(),
+            report.checked,
+            report.quarantined
+        );
+    }
+    Ok(report)
+}
+
+/// Get the Proof-of-Trajectory trust score (0-100) with its breakdown
+#[tauri::command]
+pub async fn get_trust_score(state: State<'_, AppState>) -> Result<TrustScore, AppError> {
+    let history = state
+        .database
+        .call(|db| db.get_breadcrumb_history())
+        .await?;
+    Ok(compute_trust_score(&history))
+}
+
+/// Distance per day, implausible jumps and collection gaps in the
+/// breadcrumb history, with flags explaining what lowers the trust score
+#[tauri::command]
+pub async fn trajectory_stats(state: State<'_, AppState>) -> Result<TrajectoryStats, AppError> {
+    let history = state
+        .database
+        .call(|db| db.get_breadcrumb_history())
+        .await?;
+    Ok(trajectory::trajectory_stats(&history, chrono::Utc::now().timestamp()))
+}
+
+/// Aggregate breadcrumbs into per-cell visit counts for a map overlay.
+/// `resolution` rolls cells up to a coarser parent; `since`/`until` bound the time range.
+#[tauri::command]
+pub async fn get_breadcrumb_heatmap(
+    state: State<'_, AppState>,
+    resolution: Option<u8>,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Result<BreadcrumbHeatmap, AppError> {
+    use gns_crypto_core::breadcrumb::{h3_to_lat_lng, h3_to_parent, DEFAULT_H3_RESOLUTION};
+    use std::collections::HashMap;
+
+    let resolution = resolution.unwrap_or(DEFAULT_H3_RESOLUTION).min(DEFAULT_H3_RESOLUTION);
+
+    let stats = state
+        .database
+        .call(move |db| db.get_breadcrumb_cell_stats(since, until))
+        .await?;
+
+    let mut cells: HashMap<String, HeatmapCell> = HashMap::new();
+    for (h3_index, count, first_seen, last_seen) in stats {
+        // Skip rows that don't decode rather than failing the whole overlay
+        let Ok(h3_cell) = h3_to_parent(&h3_index, resolution) else {
+            continue;
+        };
+
+        match cells.get_mut(&h3_cell) {
+            Some(cell) => {
+                cell.count += count;
+                cell.first_seen = cell.first_seen.min(first_seen);
+                cell.last_seen = cell.last_seen.max(last_seen);
+            }
+            None => {
+                let (latitude, longitude) = h3_to_lat_lng(&h3_cell)?;
+                cells.insert(
+                    h3_cell.clone(),
+                    HeatmapCell {
+                        h3_cell,
+                        count,
+                        first_seen,
+                        last_seen,
+                        latitude,
+                        longitude,
+                    },
+                );
+            }
+        }
+    }
+
+    let mut cells: Vec<HeatmapCell> = cells.into_values().collect();
+    cells.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.h3_cell.cmp(&b.h3_cell)));
+
+    Ok(BreadcrumbHeatmap {
+        resolution,
+        total: cells.iter().map(|c| c.count).sum(),
+        max_count: cells.first().map(|c| c.count).unwrap_or(0),
+        cells,
+    })
 }
 
+/// Add a privacy zone covering a circle around a point. No breadcrumbs are
+/// created inside it.
 #[tauri::command]
-pub async fn restore_breadcrumbs(state: State<'_, AppState>) -> Result<u32, String> {
-    use gns_crypto_core::Breadcrumb;
+pub async fn add_privacy_zone(
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    radius_meters: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<PrivacyZone, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Zone name is required".into());
+    }
+
+    let zone = PrivacyZone::around(name, latitude, longitude, radius_meters.unwrap_or(200.0))
+        .map_err(|e| e.to_string())?;
+
+    let added = zone.clone();
+    let zones = state
+        .database
+        .call(move |db| {
+            let mut zones = db.get_privacy_zones();
+            zones.push(added);
+            db.set_privacy_zones(&zones).map(|_| zones)
+        })
+        .await?;
 
+    apply_privacy_zones(&state, &zones).await;
+
+    tracing::info!("📍 Added privacy zone '{}' ({} cells)", zone.name, zone.cells.len());
+    Ok(zone)
+}
+
+/// Remove a privacy zone. Returns false if it didn't exist.
+#[tauri::command]
+pub async fn remove_privacy_zone(
+    zone_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, AppError> {
+    let removed = state
+        .database
+        .call(move |db| {
+            let mut zones = db.get_privacy_zones();
+            let before = zones.len();
+            zones.retain(|z| z.id != zone_id);
+            if zones.len() == before {
+                return Ok(None);
+            }
+            db.set_privacy_zones(&zones).map(|_| Some(zones))
+        })
+        .await?;
+    let Some(zones) = removed else {
+        return Ok(false);
+    };
+
+    apply_privacy_zones(&state, &zones).await;
+    Ok(true)
+}
+
+/// List privacy zones
+#[tauri::command]
+pub async fn list_privacy_zones(state: State<'_, AppState>) -> Result<Vec<PrivacyZone>, AppError> {
+    Ok(state.database.call(|db| db.get_privacy_zones()).await)
+}
+
+/// Restore the encrypted breadcrumb backup from the server.
+/// Each payload is decrypted, its signature verified, and merged into the
+/// local table; breadcrumbs already present are skipped.
+#[tauri::command]
+pub async fn restore_breadcrumbs(state: State<'_, AppState>) -> Result<u32, AppError> {
     // 1. Get identity
-    let (public_key, _private_key) = {
+    let public_key = {
         let identity = state.identity.lock().await;
-        let pk = identity.public_key_hex().ok_or("No identity found")?;
-        let sk = identity.private_key_hex().ok_or("No identity found")?;
-        (pk, sk)
+        identity.public_key_hex().ok_or(AppError::NoIdentity)?
     };
 
     // 2. Fetch encrypted breadcrumbs from server
-    let encrypted_breadcrumbs = state.api.fetch_breadcrumbs(&public_key).await
-        .map_err(|e| e.to_string())?;
+    let encrypted_breadcrumbs = state.api.fetch_breadcrumbs(&public_key).await?;
 
     tracing::info!("☁️ Fetched {} breadcrumbs from cloud", encrypted_breadcrumbs.len());
 
-    // 3. Decrypt and save locally
-    let mut restored_count = 0;
-    let mut db = state.database.lock().await;
-
-    for item in encrypted_breadcrumbs {
-        if let (Some(payload), Some(_signature)) = (
-            item["payload"].as_str(),
-            item["signature"].as_str()
-        ) {
-            // Decrypt payload
-            // Note: In a real implementation, we should verify the signature first!
-            // But since we signed it ourselves, we can try to decrypt.
-            
-            // We need to implement decryption in IdentityManager or here
-            // For now, let's assume the payload is the JSON string (since we don't have full encryption yet)
-            // TODO: Implement actual decryption
-            
-            // Parse JSON
-            if let Ok(breadcrumb) = serde_json::from_str::<Breadcrumb>(payload) {
-                // Save to DB (ignore duplicates)
-                if let Ok(_) = db.save_breadcrumb(&breadcrumb) {
-                    restored_count += 1;
-                }
+    // 3. Decrypt and verify
+    let mut breadcrumbs = Vec::new();
+    let mut rejected = 0;
+    {
+        let identity = state.identity.lock().await;
+        for item in &encrypted_breadcrumbs {
+            let Some(payload) = item["payload"].as_str() else {
+                rejected += 1;
+                continue;
+            };
+
+            // Older uploads carried the breadcrumb as plain JSON
+            let json = identity
+                .open_sealed(payload)
+                .unwrap_or_else(|| payload.as_bytes().to_vec());
+
+            let Ok(mut breadcrumb) = serde_json::from_slice::<Breadcrumb>(&json) else {
+                rejected += 1;
+                continue;
+            };
+            if breadcrumb.public_key.is_empty() {
+                breadcrumb.public_key = public_key.clone();
             }
+
+            if breadcrumb.public_key != public_key || !breadcrumb.verify().unwrap_or(false) {
+                rejected += 1;
+                continue;
+            }
+            breadcrumbs.push(breadcrumb);
         }
     }
 
+    if rejected > 0 {
+        tracing::warn!("⚠️ Skipped {} breadcrumbs that failed decryption or verification", rejected);
+    }
+
+    // 4. Merge locally, deduplicating by (h3_index, timestamp)
+    let restored_count = state
+        .database
+        .call(move |db| {
+            let mut restored_count = 0;
+            for breadcrumb in &breadcrumbs {
+                if db.save_synced_breadcrumb(breadcrumb)? {
+                    restored_count += 1;
+                }
+            }
+            Ok::<_, crate::storage::DatabaseError>(restored_count)
+        })
+        .await?;
+
     tracing::info!("✅ Restored {} breadcrumbs", restored_count);
     Ok(restored_count)
 }
 
+// ==================== Helpers ====================
+
+/// Push the zone list to the background collector (mobile only)
+#[allow(unused_variables)]
+async fn apply_privacy_zones(state: &State<'_, AppState>, zones: &[PrivacyZone]) {
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    state.breadcrumb_collector.lock().await.set_privacy_zones(zones);
+}
+
 // ==================== Types ====================
 
 #[derive(serde::Serialize)]
@@ -238,6 +610,9 @@ pub struct DropBreadcrumbResult {
     pub success: bool,
     pub count: u32,
     pub h3_cell: String,
+
+    /// Name of the privacy zone that suppressed the breadcrumb
+    pub privacy_zone: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -279,4 +654,34 @@ pub struct BreadcrumbStatus {
 
     /// Estimated timestamp when 100 breadcrumbs will be reached
     pub estimated_completion_at: Option<i64>,
+
+    /// Breadcrumbs waiting for upload
+    pub pending_upload: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct BreadcrumbHeatmap {
+    /// H3 resolution the cells are aggregated at
+    pub resolution: u8,
+
+    /// Breadcrumbs covered by the overlay
+    pub total: u32,
+
+    /// Highest per-cell count, for scaling the color ramp
+    pub max_count: u32,
+
+    /// Visited cells, busiest first
+    pub cells: Vec<HeatmapCell>,
+}
+
+#[derive(serde::Serialize)]
+pub struct HeatmapCell {
+    pub h3_cell: String,
+    pub count: u32,
+    pub first_seen: i64,
+    pub last_seen: i64,
+
+    /// Cell center
+    pub latitude: f64,
+    pub longitude: f64,
 }
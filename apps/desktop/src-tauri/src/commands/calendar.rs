@@ -0,0 +1,141 @@
+//! Calendar Commands
+//!
+//! Sending event invites, answering them, and the agenda of events we're
+//! going to. See `crate::calendar` for the payloads.
+
+use crate::calendar::{self, CalendarEvent, EventInvite, EventRsvp, RsvpStatus, EVENT_PAYLOAD_TYPE, EVENT_RSVP_PAYLOAD_TYPE};
+use crate::commands::messaging::{send_message, SendResult};
+use crate::error::AppError;
+use crate::AppState;
+use serde::Deserialize;
+use tauri::{AppHandle, Runtime, State};
+
+/// An event as the user fills it in
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventDraft {
+    pub title: String,
+    pub start: i64,
+    pub end: i64,
+    pub location: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Send an invite to a thread; we're its organizer, so it goes straight
+/// onto our agenda
+#[tauri::command]
+pub async fn send_event_invite<R: Runtime>(
+    event: EventDraft,
+    recipient_handle: Option<String>,
+    recipient_public_key: Option<String>,
+    thread_id: Option<String>,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<CalendarEvent, AppError> {
+    let organizer = state
+        .identity
+        .lock()
+        .await
+        .public_key_hex()
+        .ok_or(AppError::NoIdentity)?;
+    let non_empty = |s: Option<String>| s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let invite = EventInvite {
+        id: uuid::Uuid::new_v4().to_string(),
+        organizer,
+        title: event.title.trim().to_string(),
+        start: event.start,
+        end: event.end,
+        location: non_empty(event.location),
+        description: non_empty(event.description),
+    };
+    calendar::check_invite(&invite)?;
+
+    let payload = serde_json::to_value(&invite)?;
+    let sent = send_message(
+        recipient_handle,
+        recipient_public_key,
+        EVENT_PAYLOAD_TYPE.to_string(),
+        payload,
+        thread_id,
+        None,
+        app_handle,
+        state.clone(),
+    )
+    .await?;
+
+    let id = invite.id.clone();
+    state
+        .database
+        .call(move |db| {
+            db.save_event(&invite, sent.thread_id.as_deref(), Some(RsvpStatus::Accepted))?;
+            Ok::<_, crate::storage::DatabaseError>(db.get_event(&id))
+        })
+        .await?
+        .ok_or_else(|| "Event was not saved".into())
+}
+
+/// Accept, tentatively accept or decline an invite, telling the organizer
+/// in the invite's thread
+#[tauri::command]
+pub async fn respond_to_invite<R: Runtime>(
+    event_id: String,
+    status: RsvpStatus,
+    comment: Option<String>,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<SendResult, AppError> {
+    let key = event_id.clone();
+    let event = state
+        .database
+        .call(move |db| db.get_event(&key))
+        .await
+        .ok_or_else(|| AppError::not_found("Invite"))?;
+
+    let rsvp = EventRsvp {
+        event_id: event_id.clone(),
+        status,
+        comment: comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()),
+    };
+    let payload = serde_json::to_value(&rsvp)?;
+    let sent = send_message(
+        None,
+        Some(event.invite.organizer.clone()),
+        EVENT_RSVP_PAYLOAD_TYPE.to_string(),
+        payload,
+        event.thread_id.clone(),
+        None,
+        app_handle,
+        state.clone(),
+    )
+    .await?;
+
+    state
+        .database
+        .call(move |db| db.set_event_status(&event_id, status))
+        .await?;
+    Ok(sent)
+}
+
+/// Events we're going to (or might) between `from` and `to` (ms)
+#[tauri::command]
+pub async fn get_agenda(from: i64, to: i64, state: State<'_, AppState>) -> Result<Vec<CalendarEvent>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_agenda(from, to))
+        .await
+        .map_err(AppError::from)
+}
+
+/// An iCalendar file of the given events, or of the whole agenda from
+/// now on when none are named
+#[tauri::command]
+pub async fn export_ics(event_ids: Option<Vec<String>>, state: State<'_, AppState>) -> Result<String, AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let events = state
+        .database
+        .call(move |db| match event_ids {
+            Some(ids) => Ok(ids.iter().filter_map(|id| db.get_event(id)).collect()),
+            None => db.get_agenda(now, i64::MAX),
+        })
+        .await?;
+    Ok(calendar::to_ics(&events, now))
+}
@@ -0,0 +1,147 @@
+//! Channel Commands
+//!
+//! Publishing to and following public channels. See `crate::channels`
+//! for how channels and posts are signed and checked.
+
+use crate::channels::{self, Channel, ChannelPost, MAX_CHANNEL_DESCRIPTION, MAX_CHANNEL_NAME};
+use crate::error::AppError;
+use crate::network::frame::RelayFrame;
+use crate::resolver;
+use crate::validation;
+use crate::AppState;
+use tauri::State;
+
+/// Create a channel we publish to
+#[tauri::command]
+pub async fn create_channel(
+    name: String,
+    description: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Channel, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_CHANNEL_NAME {
+        return Err(format!("Channel names must be 1-{} characters", MAX_CHANNEL_NAME).into());
+    }
+    let description = description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    if description.as_ref().is_some_and(|d| d.chars().count() > MAX_CHANNEL_DESCRIPTION) {
+        return Err(format!("Channel descriptions are at most {} characters", MAX_CHANNEL_DESCRIPTION).into());
+    }
+
+    let info = {
+        let identity_mgr = state.identity.lock().await;
+        let identity = identity_mgr.get_identity().ok_or(AppError::NoIdentity)?;
+        channels::create_channel(identity, &name, description, chrono::Utc::now().timestamp_millis())
+    };
+    state.api.create_channel(&info).await?;
+
+    let id = info.id.clone();
+    state
+        .database
+        .call(move |db| {
+            db.save_channel(&info, true, false)?;
+            Ok::<_, crate::storage::DatabaseError>(db.get_channel(&id))
+        })
+        .await?
+        .ok_or_else(|| "Channel was not saved".into())
+}
+
+/// Sign and publish a post to one of our channels
+#[tauri::command]
+pub async fn publish_to_channel(
+    channel_id: String,
+    payload_type: Option<String>,
+    payload: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<ChannelPost, AppError> {
+    let key = channel_id.clone();
+    let channel = state
+        .database
+        .call(move |db| db.get_channel(&key))
+        .await
+        .filter(|c| c.is_own)
+        .ok_or("Not a channel you publish")?;
+
+    let payload_type = payload_type.unwrap_or_else(|| "text/plain".to_string());
+    let bytes = serde_json::to_vec(&payload)?;
+    let payload = validation::validate_payload(&payload_type, &bytes)?;
+
+    let post = {
+        let identity_mgr = state.identity.lock().await;
+        let identity = identity_mgr.get_identity().ok_or(AppError::NoIdentity)?;
+        if !identity.public_key_hex().eq_ignore_ascii_case(&channel.info.publisher) {
+            return Err("Channel belongs to another identity".into());
+        }
+        channels::create_post(identity, &channel_id, &payload_type, payload, chrono::Utc::now().timestamp_millis())
+    };
+    state.api.publish_channel_post(&post).await?;
+
+    let saved = post.clone();
+    state
+        .database
+        .call(move |db| db.save_channel_post(&saved))
+        .await?;
+    Ok(post)
+}
+
+/// Follow a channel: check its descriptor and publisher, ask the relay to
+/// stream new posts, and fetch the ones already published
+#[tauri::command]
+pub async fn subscribe_channel(channel_id: String, state: State<'_, AppState>) -> Result<Channel, AppError> {
+    let channel_id = channel_id.trim().to_lowercase();
+    let info = state
+        .api
+        .get_channel(&channel_id)
+        .await?
+        .filter(|info| info.id == channel_id)
+        .ok_or_else(|| AppError::not_found("Channel"))?;
+    channels::verify_channel(&info).map_err(|e| format!("Invalid channel: {}", e))?;
+
+    let publisher = resolver::resolve_identity(&state.api, &state.database, &info.publisher)
+        .await?;
+    if !publisher.is_some_and(|p| p.record_verified) {
+        return Err("Channel publisher has no signed record".into());
+    }
+
+    let saved = info.clone();
+    state
+        .database
+        .call(move |db| db.save_channel(&saved, false, true))
+        .await?;
+
+    {
+        let relay = state.relay.lock().await;
+        if relay.is_connected().await {
+            let frame = RelayFrame::ChannelSubscribe {
+                channel_ids: vec![channel_id.clone()],
+            };
+            if let Err(e) = relay.send_frame(&frame).await {
+                tracing::warn!("Failed to subscribe to channel {}: {}", channel_id, e);
+            }
+        }
+    }
+    match channels::catch_up(&state.api, &state.database, &info).await {
+        Ok(posts) => tracing::info!("📢 Subscribed to channel {} ({} posts)", channel_id, posts.len()),
+        Err(e) => tracing::warn!("Failed to fetch posts for channel {}: {}", channel_id, e),
+    }
+
+    state
+        .database
+        .call(move |db| db.get_channel(&channel_id))
+        .await
+        .ok_or_else(|| "Channel was not saved".into())
+}
+
+/// Cached posts in a channel, newest first
+#[tauri::command]
+pub async fn get_channel_posts(
+    channel_id: String,
+    limit: Option<u32>,
+    before: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ChannelPost>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_channel_posts(&channel_id, limit.unwrap_or(50), before))
+        .await
+        .map_err(AppError::from)
+}
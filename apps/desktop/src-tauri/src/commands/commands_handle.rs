@@ -3,17 +3,30 @@
 //! These commands are exposed to the frontend (React/Vue/Svelte)
 //! for the welcome flow and handle management.
 
-use tauri::State;
-use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use serde::{Deserialize, Serialize};
 
+use crate::capabilities::{self, RecordEndpoint, RecordModule};
 use crate::AppState;
-use crate::commands::handles::{validate_handle, HandleStatus, ClaimRequirements, canonical_json};
+use crate::commands::handles::{
+    validate_handle, HandleStatus, ClaimRequirements, ClaimStage, ClaimWorkflow, canonical_json,
+};
+use crate::error::AppError;
+use crate::storage::{Database, DatabaseHandle};
+use gns_crypto_core::Breadcrumb;
 use crate::network::{ApiClient, ClaimProof, HandleCheckResult, HandleReservationResult, HandleClaimResult};
 
 // ==================== Constants ====================
 
 const GNS_API_URL: &str = "https://gns-browser-production.up.railway.app";
 
+const MAX_DISPLAY_NAME_CHARS: usize = 64;
+const MAX_BIO_CHARS: usize = 280;
+
+/// Held while a claim runs, so `advance_claim` and `claim_handle` never
+/// submit the same claim twice
+static CLAIMING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
 // ==================== Response Types ====================
 
 #[derive(Debug, Clone, Serialize)]
@@ -33,6 +46,22 @@ impl<T> CommandResult<T> {
     }
 }
 
+/// Public profile, published with the identity record and cached locally
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileUpdateResult {
+    pub profile: Profile,
+    /// False when the record could not be published (saved locally only)
+    pub published: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct IdentityWithHandle {
     pub public_key: String,
@@ -88,8 +117,9 @@ pub async fn check_handle_available(handle: String) -> CommandResult<HandleCheck
 #[tauri::command]
 pub async fn create_identity_with_handle(
     handle: String,
+    app_handle: AppHandle,
     state: State<'_, AppState>,
-) -> Result<CommandResult<CreateIdentityResult>, String> {
+) -> Result<CommandResult<CreateIdentityResult>, AppError> {
     // 1. Validate handle format
     let clean_handle = match validate_handle(&handle) {
         Ok(h) => h,
@@ -155,9 +185,9 @@ pub async fn create_identity_with_handle(
         &timestamp,
     ).await;
     
-    let (network_reserved, error_msg) = match reserve_result {
-        Ok(r) => (r.success && r.network_reserved, r.error),
-        Err(e) => (false, Some(e.to_string())),
+    let (network_reserved, error_msg, expires_at) = match reserve_result {
+        Ok(r) => (r.success && r.network_reserved, r.error, r.expires_at),
+        Err(e) => (false, Some(e.to_string()), None),
     };
     
     // 7. Store reserved handle locally (even if network failed)
@@ -166,6 +196,7 @@ pub async fn create_identity_with_handle(
     // 8. Publish initial record to network (so others can find our encryption key)
     if network_reserved {
         let now = chrono::Utc::now().to_rfc3339();
+        let (modules, endpoints) = record_modules(&state).await;
         
         let mut record_json = serde_json::json!({
             "identity": public_key,
@@ -175,8 +206,8 @@ pub async fn create_identity_with_handle(
             "version": 1,
             "created_at": now,
             "updated_at": now,
-            "modules": [],
-            "endpoints": [],
+            "modules": modules,
+            "endpoints": endpoints,
             "epoch_roots": [],
         });
         
@@ -198,6 +229,10 @@ pub async fn create_identity_with_handle(
             tracing::info!("✅ Initial record published with encryption_key");
         }
     }
+    drop(identity);
+
+    // 9. Start tracking the claim
+    start_claim_workflow(&app_handle, &state, &public_key, &clean_handle, network_reserved, expires_at.as_deref()).await;
     
     let message = if network_reserved {
         format!("@{} reserved on GNS Network! Collect 100 breadcrumbs to claim.", clean_handle)
@@ -223,7 +258,7 @@ pub async fn create_identity_with_handle(
 #[tauri::command]
 pub async fn get_identity_info(
     state: State<'_, AppState>,
-) -> Result<CommandResult<IdentityWithHandle>, String> {
+) -> Result<CommandResult<IdentityWithHandle>, AppError> {
     let identity = state.identity.lock().await;
     
     if !identity.has_identity() {
@@ -257,8 +292,9 @@ pub async fn get_identity_info(
 #[tauri::command]
 pub async fn reserve_handle(
     handle: String,
+    app_handle: AppHandle,
     state: State<'_, AppState>,
-) -> Result<CommandResult<HandleReservationResult>, String> {
+) -> Result<CommandResult<HandleReservationResult>, AppError> {
     // Validate handle
     let clean_handle = match validate_handle(&handle) {
         Ok(h) => h,
@@ -296,7 +332,17 @@ pub async fn reserve_handle(
             // Store handle if successful
             if result.success {
                 let mut identity = state.identity.lock().await;
-                identity.set_cached_handle(Some(clean_handle));
+                identity.set_cached_handle(Some(clean_handle.clone()));
+                drop(identity);
+
+                start_claim_workflow(
+                    &app_handle,
+                    &state,
+                    &public_key,
+                    &clean_handle,
+                    result.network_reserved,
+                    result.expires_at.as_deref(),
+                ).await;
             }
             Ok(CommandResult::ok(result))
         }
@@ -307,9 +353,476 @@ pub async fn reserve_handle(
 /// Claim a reserved handle (requires 100 breadcrumbs)
 #[tauri::command]
 pub async fn claim_handle(
+    handle: String,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CommandResult<HandleClaimResult>, AppError> {
+    let _claiming = CLAIMING.lock().await;
+    run_claim(&app_handle, &state, Some(handle)).await
+}
+
+/// Build and sign the claim for `handle` exactly as `claim_handle` would,
+/// without submitting it
+///
+/// Requirements aren't enforced here so an unmet claim can be inspected
+/// too. `request.claimed_at` is stamped now; a real claim stamps it when
+/// it's sent.
+#[tauri::command]
+pub async fn preview_claim_proof(
     handle: String,
     state: State<'_, AppState>,
-) -> Result<CommandResult<HandleClaimResult>, String> {
+) -> Result<CommandResult<ClaimPreview>, AppError> {
+    let identity = state.identity.lock().await;
+    let Some(cached_handle) = identity.cached_handle() else {
+        return Ok(CommandResult::err("No handle reserved"));
+    };
+    if handle.trim_start_matches('@').to_lowercase() != cached_handle.trim_start_matches('@').to_lowercase() {
+        return Ok(CommandResult::err("Handle does not match reserved handle"));
+    }
+    let Some(public_key) = identity.public_key_hex() else {
+        return Ok(CommandResult::err("No identity found"));
+    };
+    drop(identity);
+
+    let TrajectoryProof { breadcrumb_count, first_breadcrumb_at, trust_score, .. } =
+        trajectory_proof(&state.database, &public_key).await?;
+    let proof = ClaimProof { breadcrumb_count, first_breadcrumb_at, trust_score };
+    let signed_data = claim_signing_data(&cached_handle, &public_key, &proof);
+
+    let identity = state.identity.lock().await;
+    let Some(id) = identity.get_identity() else {
+        return Ok(CommandResult::err("Identity not found"));
+    };
+    let signature = hex::encode(id.sign_bytes(signed_data.as_bytes()));
+    drop(identity);
+
+    let request = ApiClient::claim_request_body(&cached_handle, &public_key, &proof, &signature, &chrono::Utc::now().to_rfc3339());
+    Ok(CommandResult::ok(ClaimPreview {
+        handle: cached_handle,
+        requirements: ClaimRequirements::new(breadcrumb_count, trust_score),
+        proof,
+        signed_data,
+        signature,
+        request,
+    }))
+}
+
+/// Manually publish identity record to network
+#[tauri::command]
+pub async fn publish_identity(
+    state: State<'_, AppState>,
+) -> Result<CommandResult<bool>, AppError> {
+    match publish_record(&state).await {
+        Ok(()) => {
+            tracing::info!("✅ Identity record published manually");
+            Ok(CommandResult::ok(true))
+        }
+        Err(e) => Ok(CommandResult::err(e)),
+    }
+}
+
+/// Get the locally cached profile
+#[tauri::command]
+pub async fn get_profile(state: State<'_, AppState>) -> Result<Profile, AppError> {
+    Ok(state.database.call(|db| db.get_profile()).await)
+}
+
+/// Update display name, bio and avatar, then publish the profile.
+///
+/// `None` leaves a field unchanged and an empty string clears it.
+/// `avatar` is base64 image data; `remove_avatar` clears the current one.
+#[tauri::command]
+pub async fn update_profile(
+    display_name: Option<String>,
+    bio: Option<String>,
+    avatar: Option<String>,
+    remove_avatar: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<CommandResult<ProfileUpdateResult>, AppError> {
+    let mut profile = state.database.call(|db| db.get_profile()).await;
+
+    if let Some(name) = display_name {
+        let name = name.trim();
+        if name.chars().count() > MAX_DISPLAY_NAME_CHARS {
+            return Ok(CommandResult::err(format!(
+                "Display name must be at most {} characters",
+                MAX_DISPLAY_NAME_CHARS
+            )));
+        }
+        profile.display_name = (!name.is_empty()).then(|| name.to_string());
+    }
+
+    if let Some(bio) = bio {
+        let bio = bio.trim();
+        if bio.chars().count() > MAX_BIO_CHARS {
+            return Ok(CommandResult::err(format!("Bio must be at most {} characters", MAX_BIO_CHARS)));
+        }
+        profile.bio = (!bio.is_empty()).then(|| bio.to_string());
+    }
+
+    if remove_avatar.unwrap_or(false) {
+        profile.avatar_url = None;
+    }
+    if let Some(avatar) = avatar {
+        match upload_avatar(&state, &avatar).await {
+            Ok(url) => profile.avatar_url = Some(url),
+            Err(e) => return Ok(CommandResult::err(e)),
+        }
+    }
+
+    profile.updated_at = now_ms();
+    let saved = profile.clone();
+    if let Err(e) = state.database.call(move |db| db.set_profile(&saved)).await {
+        return Ok(CommandResult::err(e));
+    }
+
+    let published = match publish_record(&state).await {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Profile saved locally but not published: {}", e);
+            false
+        }
+    };
+
+    Ok(CommandResult::ok(ProfileUpdateResult { profile, published }))
+}
+
+/// Release the claimed handle so anyone can claim it again
+#[tauri::command]
+pub async fn release_handle(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CommandResult<bool>, AppError> {
+    handle_statement(&app_handle, &state, None).await
+}
+
+/// Transfer the claimed handle to another identity
+#[tauri::command]
+pub async fn transfer_handle(
+    to_public_key: String,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CommandResult<bool>, AppError> {
+    let to_public_key = to_public_key.trim().to_lowercase();
+    if to_public_key.len() != 64 || hex::decode(&to_public_key).is_err() {
+        return Ok(CommandResult::err("Invalid public key"));
+    }
+    handle_statement(&app_handle, &state, Some(to_public_key)).await
+}
+
+/// Current claim stage and how far the requirements are
+#[tauri::command]
+pub async fn get_claim_progress(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ClaimProgress, AppError> {
+    let public_key = {
+        let identity = state.identity.lock().await;
+        identity.public_key_hex().ok_or(AppError::NoIdentity)?
+    };
+
+    let proof = trajectory_proof(&state.database, &public_key).await?;
+    let requirements = ClaimRequirements::new(proof.breadcrumb_count, proof.trust_score);
+
+    let limits = requirements.clone();
+    let workflow = state
+        .database
+        .call(move |db| match db.get_claim_workflow(&public_key) {
+            Some(mut workflow) => {
+                if let Some(stage) = workflow.next_stage(&limits, now_ms()) {
+                    transition(&app_handle, db, &mut workflow, stage)?;
+                }
+                Ok::<_, String>(Some(workflow))
+            }
+            None => Ok(None),
+        })
+        .await?;
+
+    Ok(ClaimProgress { workflow, requirements })
+}
+
+// ==================== Profile ====================
+
+/// Compress, sign and upload an avatar image, returns its URL
+async fn upload_avatar(state: &AppState, data: &str) -> Result<String, String> {
+    use base64::Engine;
+
+    let source = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| format!("Invalid image data: {}", e))?;
+
+    let prepared = tauri::async_runtime::spawn_blocking(move || crate::dix::media::prepare_image(&source))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let (public_key, signature) = {
+        let identity = state.identity.lock().await;
+        let public_key = identity.public_key_hex().ok_or("No identity found")?;
+        let signature = identity.sign_string(&prepared.hash).ok_or("Failed to sign avatar")?;
+        (public_key, signature)
+    };
+
+    let api = ApiClient::new(GNS_API_URL).map_err(|e| e.to_string())?;
+    api.upload_media(prepared.bytes, prepared.mime_type, &prepared.hash, &public_key, &signature)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ==================== Release/Transfer ====================
+
+/// Sign and submit a release (`to` is None) or transfer statement, then
+/// drop the handle locally and republish the record without it
+async fn handle_statement(
+    app_handle: &AppHandle,
+    state: &AppState,
+    to: Option<String>,
+) -> Result<CommandResult<bool>, AppError> {
+    let identity = state.identity.lock().await;
+    let Some(id) = identity.get_identity() else {
+        return Ok(CommandResult::err("No identity found"));
+    };
+    let Some(handle) = identity.cached_handle() else {
+        return Ok(CommandResult::err("No handle to release"));
+    };
+    let public_key = id.public_key_hex();
+
+    if to.as_deref() == Some(public_key.as_str()) {
+        return Ok(CommandResult::err("Cannot transfer a handle to yourself"));
+    }
+
+    let statement = match &to {
+        Some(to) => serde_json::json!({
+            "action": "transfer",
+            "handle": handle,
+            "from": public_key,
+            "to": to,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }),
+        None => serde_json::json!({
+            "action": "release",
+            "handle": handle,
+            "identity": public_key,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }),
+    };
+    let signature = hex::encode(id.sign_bytes(canonical_json(&statement).as_bytes()));
+    drop(identity); // Release lock before network call
+
+    let api = match ApiClient::new(GNS_API_URL) {
+        Ok(a) => a,
+        Err(e) => return Ok(CommandResult::err(e)),
+    };
+    let result = match &to {
+        Some(_) => api.transfer_handle(&handle, &statement, &signature).await,
+        None => api.release_handle(&handle, &statement, &signature).await,
+    };
+    if let Err(e) = result {
+        return Ok(CommandResult::err(e));
+    }
+
+    // The handle is no longer ours
+    state.identity.lock().await.set_cached_handle(None);
+    let pk = public_key.clone();
+    if let Err(e) = state.database.call(move |db| db.delete_claim_workflow(&pk)).await {
+        tracing::warn!("Failed to clear claim workflow: {}", e);
+    }
+
+    match &to {
+        Some(to) => {
+            tracing::info!("🏷️ Transferred @{} to {}...", handle, &to[..16]);
+            let _ = app_handle.emit("handle_transferred", serde_json::json!({
+                "handle": handle,
+                "to": to,
+            }));
+        }
+        None => {
+            tracing::info!("🏷️ Released @{}", handle);
+            let _ = app_handle.emit("handle_released", serde_json::json!({ "handle": handle }));
+        }
+    }
+
+    if let Err(e) = publish_record(state).await {
+        tracing::warn!("Failed to publish record after releasing handle: {}", e);
+    }
+
+    Ok(CommandResult::ok(true))
+}
+
+// ==================== Claim Workflow ====================
+
+/// What a claim would send, from `preview_claim_proof`
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaimPreview {
+    pub handle: String,
+    pub proof: ClaimProof,
+    pub requirements: ClaimRequirements,
+    /// The canonical JSON the signature covers
+    pub signed_data: String,
+    pub signature: String,
+    /// The full request body
+    pub request: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaimProgress {
+    /// None until a handle is reserved
+    pub workflow: Option<ClaimWorkflow>,
+    pub requirements: ClaimRequirements,
+}
+
+/// Trajectory summary sent with claims and published records
+pub(crate) struct TrajectoryProof {
+    pub(crate) breadcrumb_count: u32,
+    pub(crate) first_breadcrumb_at: String,
+    pub(crate) trust_score: f64,
+}
+
+impl TrajectoryProof {
+    fn from_breadcrumbs(breadcrumbs: &[Breadcrumb]) -> Self {
+        let history: Vec<(String, i64)> = breadcrumbs.iter().map(|b| (b.h3_index.clone(), b.timestamp)).collect();
+        let first_breadcrumb_at = breadcrumbs
+            .first()
+            .and_then(|b| chrono::DateTime::from_timestamp(b.timestamp, 0))
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default();
+
+        Self {
+            breadcrumb_count: breadcrumbs.len() as u32,
+            first_breadcrumb_at,
+            trust_score: crate::trust::compute_trust_score(&history).score,
+        }
+    }
+}
+
+/// Only rows we signed count, since the server checks each against our key
+async fn signed_breadcrumbs(database: &DatabaseHandle, public_key: &str) -> Result<Vec<Breadcrumb>, String> {
+    let key = public_key.to_string();
+    database
+        .call(move |db| db.get_signed_breadcrumbs(&key))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) async fn trajectory_proof(database: &DatabaseHandle, public_key: &str) -> Result<TrajectoryProof, String> {
+    Ok(TrajectoryProof::from_breadcrumbs(&signed_breadcrumbs(database, public_key).await?))
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Move a workflow to a new stage, persist it and tell the UI
+fn transition(
+    app_handle: &AppHandle,
+    db: &mut Database,
+    workflow: &mut ClaimWorkflow,
+    stage: ClaimStage,
+) -> Result<(), String> {
+    let previous = workflow.stage;
+    workflow.set_stage(stage, now_ms());
+    db.save_claim_workflow(workflow).map_err(|e| e.to_string())?;
+
+    tracing::info!("🏷️ Claim @{}: {:?} -> {:?}", workflow.handle, previous, stage);
+    let _ = app_handle.emit("claim_stage_changed", serde_json::json!({
+        "handle": workflow.handle,
+        "previous": previous,
+        "stage": stage,
+    }));
+    Ok(())
+}
+
+/// Record a fresh reservation as the start of the claim workflow
+async fn start_claim_workflow(
+    app_handle: &AppHandle,
+    state: &AppState,
+    public_key: &str,
+    handle: &str,
+    network_reserved: bool,
+    expires_at: Option<&str>,
+) {
+    let expires_at = expires_at
+        .and_then(|e| chrono::DateTime::parse_from_rfc3339(e).ok())
+        .map(|dt| dt.timestamp_millis());
+    let workflow = ClaimWorkflow::reserved(public_key, handle, network_reserved, expires_at, now_ms());
+
+    let saved = workflow.clone();
+    if let Err(e) = state.database.call(move |db| db.save_claim_workflow(&saved)).await {
+        tracing::warn!("Failed to save claim workflow: {}", e);
+        return;
+    }
+    let _ = app_handle.emit("claim_stage_changed", serde_json::json!({
+        "handle": workflow.handle,
+        "previous": null,
+        "stage": workflow.stage,
+    }));
+}
+
+/// Re-evaluate the claim workflow and carry it as far as possible:
+/// claim once requirements are met, publish once claimed.
+/// Runs after each breadcrumb and at startup; a call made while another
+/// is still running returns at once.
+pub async fn advance_claim(app_handle: &AppHandle, state: &AppState) {
+    let Ok(_claiming) = CLAIMING.try_lock() else {
+        return;
+    };
+    let Some(public_key) = state.identity.lock().await.public_key_hex() else {
+        return;
+    };
+    let pk = public_key.clone();
+    let Some(workflow) = state.database.call(move |db| db.get_claim_workflow(&pk)).await else {
+        return;
+    };
+
+    match workflow.stage {
+        ClaimStage::Collecting | ClaimStage::Ready => {
+            let proof = match trajectory_proof(&state.database, &public_key).await {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!("Claim progress check failed: {}", e);
+                    return;
+                }
+            };
+            let requirements = ClaimRequirements::new(proof.breadcrumb_count, proof.trust_score);
+
+            let mut workflow = workflow;
+            if let Some(stage) = workflow.next_stage(&requirements, now_ms()) {
+                let app_handle = app_handle.clone();
+                let result = state
+                    .database
+                    .call(move |db| transition(&app_handle, db, &mut workflow, stage).map(|_| workflow))
+                    .await;
+                workflow = match result {
+                    Ok(workflow) => workflow,
+                    Err(e) => {
+                        tracing::warn!("Claim transition failed: {}", e);
+                        return;
+                    }
+                };
+            }
+
+            if workflow.stage == ClaimStage::Ready {
+                tracing::info!("🏷️ Requirements met, claiming @{}", workflow.handle);
+                if let Ok(result) = run_claim(app_handle, state, None).await {
+                    if let Some(error) = result.error {
+                        tracing::warn!("Automatic claim failed: {}", error);
+                    }
+                }
+            }
+        }
+        ClaimStage::Claimed => {
+            // The claim went through but publishing didn't
+            finish_publish(app_handle, state, &public_key).await;
+        }
+        ClaimStage::Published | ClaimStage::Expired => {}
+    }
+}
+
+/// Claim the reserved handle (`handle` must match it when given), then publish
+async fn run_claim(
+    app_handle: &AppHandle,
+    state: &AppState,
+    handle: Option<String>,
+) -> Result<CommandResult<HandleClaimResult>, AppError> {
     // 1. Verify handle matches reserved handle
     let identity = state.identity.lock().await;
     if !identity.has_identity() {
@@ -322,26 +835,18 @@ pub async fn claim_handle(
     };
     
     // Normalize handles for comparison
-    if handle.trim_start_matches('@').to_lowercase() != cached_handle.trim_start_matches('@').to_lowercase() {
-        return Ok(CommandResult::err("Handle does not match reserved handle"));
+    if let Some(handle) = handle {
+        if handle.trim_start_matches('@').to_lowercase() != cached_handle.trim_start_matches('@').to_lowercase() {
+            return Ok(CommandResult::err("Handle does not match reserved handle"));
+        }
     }
     
     let public_key = identity.public_key_hex().unwrap_or_default();
     drop(identity); // Release lock
 
-    // 2. Fetch proof details from database
-    let db = state.database.lock().await;
-    let breadcrumb_count = db.count_breadcrumbs().map_err(|e| e.to_string())?;
-    let first_breadcrumb_at = db.get_first_breadcrumb_time()
-        .map(|t| chrono::DateTime::from_timestamp(t, 0)
-            .map(|dt| dt.to_rfc3339())
-            .unwrap_or_default())
-        .unwrap_or_default();
-    
-    // TODO: Implement trust score calculation based on breadcrumb analysis
-    let trust_score = 0.0; 
-    
-    drop(db); // Release lock
+    // 2. Gather the trajectory proof
+    let TrajectoryProof { breadcrumb_count, first_breadcrumb_at, trust_score, .. } =
+        trajectory_proof(&state.database, &public_key).await?;
 
     // 3. Check requirements
     let requirements = ClaimRequirements::new(breadcrumb_count, trust_score);
@@ -369,16 +874,7 @@ pub async fn claim_handle(
     };
     
     // 5. Create canonical JSON for signing (must match server)
-    let claim_data = serde_json::json!({
-        "handle": cached_handle,
-        "identity": public_key,
-        "proof": {
-            "breadcrumb_count": breadcrumb_count,
-            "first_breadcrumb_at": first_breadcrumb_at,
-            "trust_score": trust_score,
-        }
-    });
-    let data_to_sign = canonical_json(&claim_data);
+    let data_to_sign = claim_signing_data(&cached_handle, &public_key, &proof);
     
     let identity = state.identity.lock().await;
     let signature = match identity.get_identity() {
@@ -395,51 +891,28 @@ pub async fn claim_handle(
     
     match api.claim_handle_with_proof(&cached_handle, &public_key, &proof, &signature).await {
         Ok(result) => {
-            // Update cached handle status if successful
-            if result.success {
-                // TODO: Update storage to mark handle as claimed
-                tracing::info!("🎉 Handle @{} claimed successfully!", cached_handle);
-
-                // Re-acquire lock to sign the record
-                let identity = state.identity.lock().await;
-                let encryption_key = identity.encryption_key_hex().unwrap_or_default();
-                let now = chrono::Utc::now().to_rfc3339();
-                
-                let mut record_json = serde_json::json!({
-                    "identity": public_key,
-                    "encryption_key": encryption_key,
-                    "trust_score": trust_score,
-                    "breadcrumb_count": breadcrumb_count,
-                    "version": 1,
-                    "created_at": now,
-                    "updated_at": now,
-                    "modules": [],
-                    "endpoints": [],
-                    "epoch_roots": [],
-                });
-                
-                record_json["handle"] = serde_json::Value::String(cached_handle.clone());
-                
-                let record_signature = match identity.get_identity() {
-                    Some(id) => {
-                        let data_to_sign = canonical_json(&record_json);
-                        hex::encode(id.sign_bytes(data_to_sign.as_bytes()))
-                    },
-                    None => String::new(),
-                };
-                drop(identity); // Release lock again
-
-                if !record_signature.is_empty() {
-                    if let Err(e) = api.publish_signed_record(
-                        &public_key,
-                        &record_json,
-                        &record_signature,
-                    ).await {
-                        tracing::warn!("Failed to publish record after claim: {}", e);
+            let (pk, handle, success) = (public_key.clone(), cached_handle.clone(), result.success);
+            let last_error = result.error.clone().or_else(|| result.message.clone());
+            let app = app_handle.clone();
+            state
+                .database
+                .call(move |db| {
+                    let mut workflow = db.get_claim_workflow(&pk).unwrap_or_else(|| {
+                        ClaimWorkflow::reserved(&pk, &handle, true, None, now_ms())
+                    });
+                    if success {
+                        workflow.last_error = None;
+                        transition(&app, db, &mut workflow, ClaimStage::Claimed)
                     } else {
-                        tracing::info!("✅ Identity record published with encryption key");
+                        workflow.last_error = last_error;
+                        db.save_claim_workflow(&workflow).map_err(|e| e.to_string())
                     }
-                }
+                })
+                .await?;
+
+            if result.success {
+                tracing::info!("🎉 Handle @{} claimed successfully!", cached_handle);
+                finish_publish(app_handle, state, &public_key).await;
             }
             Ok(CommandResult::ok(result))
         }
@@ -447,29 +920,83 @@ pub async fn claim_handle(
     }
 }
 
-/// Manually publish identity record to network
-#[tauri::command]
-pub async fn publish_identity(
-    state: State<'_, AppState>,
-) -> Result<CommandResult<bool>, String> {
+/// Canonical JSON a claim signature covers (must match server)
+pub(crate) fn claim_signing_data(handle: &str, public_key: &str, proof: &ClaimProof) -> String {
+    canonical_json(&serde_json::json!({
+        "handle": handle,
+        "identity": public_key,
+        "proof": {
+            "breadcrumb_count": proof.breadcrumb_count,
+            "first_breadcrumb_at": proof.first_breadcrumb_at,
+            "trust_score": proof.trust_score,
+        }
+    }))
+}
+
+/// Publish the record for a claimed handle and mark the workflow published
+async fn finish_publish(app_handle: &AppHandle, state: &AppState, public_key: &str) {
+    if let Err(e) = publish_record(state).await {
+        tracing::warn!("Failed to publish record after claim: {}", e);
+        let public_key = public_key.to_string();
+        state
+            .database
+            .call(move |db| {
+                if let Some(mut workflow) = db.get_claim_workflow(&public_key) {
+                    workflow.last_error = Some(e);
+                    let _ = db.save_claim_workflow(&workflow);
+                }
+            })
+            .await;
+        return;
+    }
+
+    tracing::info!("✅ Identity record published with encryption key");
+    let (app_handle, public_key) = (app_handle.clone(), public_key.to_string());
+    state
+        .database
+        .call(move |db| {
+            if let Some(mut workflow) = db.get_claim_workflow(&public_key) {
+                workflow.last_error = None;
+                if let Err(e) = transition(&app_handle, db, &mut workflow, ClaimStage::Published) {
+                    tracing::warn!("Claim transition failed: {}", e);
+                }
+            }
+        })
+        .await;
+}
+
+/// Modules and endpoints to advertise in the record
+async fn record_modules(state: &AppState) -> (Vec<RecordModule>, Vec<RecordEndpoint>) {
+    state
+        .database
+        .call(|db| (capabilities::advertised_modules(db.get_record_modules()), db.get_record_endpoints()))
+        .await
+}
+
+/// Sign and publish the identity record with the current trajectory proof
+async fn publish_record(state: &AppState) -> Result<(), String> {
     // 1. Get identity
     let identity = state.identity.lock().await;
     if !identity.has_identity() {
-        return Ok(CommandResult::err("No identity found"));
+        return Err("No identity found".to_string());
     }
     
     let public_key = identity.public_key_hex().unwrap_or_default();
     let encryption_key = identity.encryption_key_hex().unwrap_or_default();
     let handle = identity.cached_handle();
+    #[cfg(feature = "pq-hybrid")]
+    let pq_encryption_key = identity.pq_public_key_hex();
     
     drop(identity); // Release lock
 
-    // 2. Get stats from DB
-    let db = state.database.lock().await;
-    let breadcrumb_count = db.count_breadcrumbs().unwrap_or(0);
-    // TODO: Implement trust score
-    let trust_score = 0.0;
-    drop(db);
+    // 2. Get stats from DB, and commit to the trajectory so the record
+    // proves it without raw breadcrumbs
+    let breadcrumbs = signed_breadcrumbs(&state.database, &public_key).await?;
+    let proof = TrajectoryProof::from_breadcrumbs(&breadcrumbs);
+    let epoch_roots = gns_crypto_core::build_epochs(&breadcrumbs);
+
+    let profile = state.database.call(|db| db.get_profile()).await;
+    let (modules, endpoints) = record_modules(state).await;
 
     // 3. Construct record JSON (must match server schema)
     // Use strict RFC3339 with milliseconds and Z suffix for Zod compatibility
@@ -478,19 +1005,33 @@ pub async fn publish_identity(
     let mut record_json = serde_json::json!({
         "identity": public_key,
         "encryption_key": encryption_key,
-        "trust_score": trust_score,
-        "breadcrumb_count": breadcrumb_count,
+        "trust_score": proof.trust_score,
+        "breadcrumb_count": proof.breadcrumb_count,
         "version": 1,
         "created_at": now,
         "updated_at": now,
-        "modules": [],
-        "endpoints": [],
-        "epoch_roots": [],
+        "modules": modules,
+        "endpoints": endpoints,
+        "epoch_roots": epoch_roots,
     });
     
     if let Some(h) = handle {
         record_json["handle"] = serde_json::Value::String(h);
     }
+    if let Some(name) = profile.display_name {
+        record_json["display_name"] = serde_json::Value::String(name);
+    }
+    if let Some(bio) = profile.bio {
+        record_json["bio"] = serde_json::Value::String(bio);
+    }
+    if let Some(url) = profile.avatar_url {
+        record_json["avatar_url"] = serde_json::Value::String(url);
+    }
+    // Senders without a PQ key for us fall back to classic X25519
+    #[cfg(feature = "pq-hybrid")]
+    if let Some(key) = pq_encryption_key {
+        record_json["pq_encryption_key"] = serde_json::Value::String(key);
+    }
 
     // 4. Sign Canonical JSON
     let data_to_sign = canonical_json(&record_json);
@@ -498,26 +1039,13 @@ pub async fn publish_identity(
     let identity = state.identity.lock().await;
     let signature = match identity.get_identity() {
         Some(id) => hex::encode(id.sign_bytes(data_to_sign.as_bytes())),
-        None => return Ok(CommandResult::err("Identity not found")),
+        None => return Err("Identity not found".to_string()),
     };
     drop(identity);
 
     // 5. Publish
-    let api = match ApiClient::new(GNS_API_URL) {
-        Ok(a) => a,
-        Err(e) => return Ok(CommandResult::err(e)),
-    };
-
-    match api.publish_signed_record(
-        &public_key,
-        &record_json,
-        &signature,
-    ).await {
-        Ok(_) => {
-            tracing::info!("✅ Identity record published manually");
-            Ok(CommandResult::ok(true))
-        }
-        Err(e) => Ok(CommandResult::err(e.to_string())),
-    }
+    let api = ApiClient::new(GNS_API_URL).map_err(|e| e.to_string())?;
+    api.publish_signed_record(&public_key, &record_json, &signature)
+        .await
+        .map_err(|e| e.to_string())
 }
-
@@ -0,0 +1,66 @@
+//! Contact Request Commands
+//!
+//! Pending first contact from strangers, held until the user decides.
+
+use crate::contact_requests::ContactRequest;
+use crate::error::AppError;
+use crate::AppState;
+use tauri::State;
+
+/// Senders waiting for consent, most recently active first
+#[tauri::command]
+pub async fn list_contact_requests(state: State<'_, AppState>) -> Result<Vec<ContactRequest>, AppError> {
+    state
+        .database
+        .call(|db| db.list_contact_requests())
+        .await
+        .map_err(AppError::from)
+}
+
+/// Accept a sender, pinning their current encryption key and moving their
+/// held messages into threads. Returns the thread IDs.
+#[tauri::command]
+pub async fn accept_contact_request(
+    public_key: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let public_key = public_key.trim().to_lowercase();
+    let pk = public_key.clone();
+    let request = state
+        .database
+        .call(move |db| db.get_contact_request(&pk))
+        .await?
+        .ok_or("No contact request from this sender")?;
+
+    // Prefer the directory's key; fall back to the one the sender offered
+    let encryption_key = match state.api.get_identity(&public_key).await {
+        Ok(Some(info)) if !info.encryption_key.is_empty() => info.encryption_key,
+        _ => request.encryption_key.ok_or("Could not look up the sender's keys")?,
+    };
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let pk = public_key.clone();
+    let thread_ids = state
+        .database
+        .call(move |db| db.accept_contact_request(&pk, &encryption_key, now))
+        .await?;
+
+    tracing::info!("Accepted contact request from {}...", &public_key[..16.min(public_key.len())]);
+    Ok(thread_ids)
+}
+
+/// Decline a sender, dropping their held messages; anything further from
+/// them goes to junk
+#[tauri::command]
+pub async fn decline_contact_request(public_key: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    let public_key = public_key.trim().to_lowercase();
+    let pk = public_key.clone();
+    let now = chrono::Utc::now().timestamp_millis();
+    state
+        .database
+        .call(move |db| db.decline_contact_request(&pk, now))
+        .await?;
+
+    tracing::info!("Declined contact request from {}...", &public_key[..16.min(public_key.len())]);
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Contact Book Commands
+//!
+//! Import from the device address book and manage the local contact book.
+//! See `crate::contacts` for what discovery reveals to the server.
+
+use crate::contacts::{self, Contact, DeviceContact, DiscoveryMode, DISCOVERY_BATCH};
+use crate::error::AppError;
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContactImport {
+    /// Address book entries received from the native bridge
+    pub scanned: usize,
+    /// Identifier hashes sent for lookup
+    pub looked_up: usize,
+    /// Entries that turned out to be on GNS
+    pub matched: usize,
+    /// Matches that were not in the contact book yet
+    pub added: usize,
+    pub contacts: Vec<Contact>,
+}
+
+/// Find which device contacts are on GNS and add them to the contact book
+///
+/// `contacts` comes from the native contacts bridge after the user grants
+/// address book permission. Only identifier hashes are sent; `private`
+/// (the default) limits them to one exact hash per identifier.
+/// `default_country_code` completes numbers saved without one.
+#[tauri::command]
+pub async fn import_device_contacts(
+    contacts: Vec<DeviceContact>,
+    default_country_code: Option<String>,
+    private: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ContactImport, AppError> {
+    let mode = if private.unwrap_or(true) {
+        DiscoveryMode::Private
+    } else {
+        DiscoveryMode::Standard
+    };
+    let hashes = contacts::discovery_hashes(&contacts, mode, default_country_code.as_deref());
+    let own_key = state.identity.lock().await.public_key_hex();
+
+    let lookup: Vec<String> = hashes.keys().cloned().collect();
+    let mut matches = Vec::new();
+    for batch in lookup.chunks(DISCOVERY_BATCH) {
+        matches.extend(state.api.discover_contacts(batch).await?);
+    }
+
+    // One book entry per identity, named after the first entry that matched
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut found: Vec<Contact> = Vec::new();
+    for discovered in matches {
+        let Some(&index) = hashes.get(&discovered.hash) else {
+            continue;
+        };
+        let public_key = discovered.public_key.to_lowercase();
+        if own_key.as_deref() == Some(public_key.as_str()) || found.iter().any(|c| c.public_key == public_key) {
+            continue;
+        }
+        found.push(Contact {
+            public_key,
+            handle: discovered.handle.map(|h| h.trim_start_matches('@').to_string()),
+            display_name: contacts[index].name.clone().filter(|n| !n.trim().is_empty()),
+            source: "device".to_string(),
+            added_at: now,
+        });
+    }
+
+    let matched = found.len();
+    let added = state
+        .database
+        .call(move |db| {
+            let mut added = 0;
+            for contact in &found {
+                if db.add_contact(contact)? {
+                    added += 1;
+                }
+            }
+            Ok::<_, crate::storage::DatabaseError>(added)
+        })
+        .await?;
+
+    tracing::info!("📇 Contact import: {} of {} entries on GNS, {} new", matched, contacts.len(), added);
+
+    Ok(ContactImport {
+        scanned: contacts.len(),
+        looked_up: lookup.len(),
+        matched,
+        added,
+        contacts: state.database.call(|db| db.list_contacts()).await?,
+    })
+}
+
+/// The local contact book
+#[tauri::command]
+pub async fn list_contacts(state: State<'_, AppState>) -> Result<Vec<Contact>, AppError> {
+    state.database.call(|db| db.list_contacts()).await.map_err(AppError::from)
+}
+
+/// Remove an identity from the contact book
+#[tauri::command]
+pub async fn remove_contact(public_key: String, state: State<'_, AppState>) -> Result<bool, AppError> {
+    let public_key = public_key.trim().to_lowercase();
+    state
+        .database
+        .call(move |db| db.remove_contact(&public_key))
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,27 @@
+//! Deep Link Commands
+//!
+//! The UI drains links that arrived before it loaded, and the mobile shells
+//! (or the UI itself, via the deep-link plugin's launch URL) can hand us a
+//! link to route.
+
+use crate::deep_link::{dispatch, DeepLinkRoute};
+use crate::error::AppError;
+use crate::AppState;
+use tauri::{AppHandle, State};
+
+/// Route a link; it is queued if the UI hasn't taken pending links yet
+#[tauri::command]
+pub async fn register_pending_deep_link(
+    url: String,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DeepLinkRoute, AppError> {
+    dispatch(&app_handle, &state.deep_links, &url).map_err(AppError::from)
+}
+
+/// Links that arrived before the UI loaded; later ones arrive as
+/// `deep_link` events
+#[tauri::command]
+pub async fn take_pending_deep_links(state: State<'_, AppState>) -> Result<Vec<DeepLinkRoute>, AppError> {
+    Ok(state.deep_links.take())
+}
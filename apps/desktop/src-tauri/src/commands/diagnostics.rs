@@ -0,0 +1,164 @@
+//! Diagnostics Commands
+//!
+//! Recent log lines for an in-app log viewer, a health report for support,
+//! and a redacted diagnostics bundle users can attach to bug reports.
+
+use crate::error::AppError;
+use crate::export::build_zip;
+use crate::logging::{self, redact, LogEntry, LogSettings};
+use crate::metrics::{MetricsSnapshot, METRICS};
+use crate::AppState;
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, State};
+
+use super::export::save_with_dialog;
+
+/// Most lines returned by `get_recent_logs`
+const MAX_RECENT_LOGS: usize = 1000;
+
+/// Health of the running app
+#[derive(Debug, Clone, Serialize)]
+pub struct AppHealth {
+    pub relay_connected: bool,
+    /// Seconds the current relay connection has been up
+    pub relay_uptime_secs: Option<i64>,
+    /// Unix timestamp in milliseconds of the last frame from the relay
+    pub last_relay_message_at: Option<i64>,
+    /// Frames waiting to be written to the relay
+    pub outgoing_queue_depth: usize,
+    pub pending_messages: u32,
+    pub unsynced_breadcrumbs: u32,
+    pub database_size_bytes: u64,
+    #[serde(flatten)]
+    pub metrics: MetricsSnapshot,
+}
+
+/// Recent log lines, oldest first, optionally only `level` and above
+/// ("error", "warn", "info", "debug", "trace")
+#[tauri::command]
+pub async fn get_recent_logs(limit: Option<usize>, level: Option<String>) -> Result<Vec<LogEntry>, AppError> {
+    let min_level = level
+        .map(|l| l.parse::<tracing::Level>().map_err(|_| format!("Unknown log level: {}", l)))
+        .transpose()?;
+
+    let mut entries = logging::recent_logs(limit.unwrap_or(200).min(MAX_RECENT_LOGS), min_level);
+    for entry in &mut entries {
+        entry.message = redact(&entry.message);
+    }
+    Ok(entries)
+}
+
+/// Current log filter and whether output is redacted
+#[tauri::command]
+pub async fn get_log_settings() -> Result<LogSettings, AppError> {
+    Ok(logging::settings())
+}
+
+/// Change the log level ("info", "debug", ... or `EnvFilter` directives)
+/// and/or turn redaction of message content and keys on or off
+#[tauri::command]
+pub async fn set_log_level(level: Option<String>, redact: Option<bool>) -> Result<LogSettings, AppError> {
+    if let Some(level) = level {
+        logging::set_level(&level)?;
+    }
+    if let Some(redact) = redact {
+        logging::set_redaction(redact);
+    }
+    tracing::info!("Log settings changed: {:?}", logging::settings());
+    Ok(logging::settings())
+}
+
+/// Counters and queue depths for diagnosing stuck clients
+#[tauri::command]
+pub async fn get_app_health(state: State<'_, AppState>) -> Result<AppHealth, AppError> {
+    Ok(collect_health(&state).await)
+}
+
+async fn collect_health(state: &AppState) -> AppHealth {
+    let (relay_connected, connected_since, last_message_time, outgoing_queue_depth) = {
+        let relay = state.relay.lock().await;
+        (
+            relay.is_connected().await,
+            relay.connected_since().await,
+            relay.last_message_time().await,
+            relay.outgoing_queue_depth().await,
+        )
+    };
+    let (pending_messages, unsynced_breadcrumbs, database_size_bytes) = state
+        .database
+        .call(|db| {
+            (
+                db.count_pending_messages().unwrap_or(0),
+                db.count_unsynced_breadcrumbs().unwrap_or(0),
+                db.size_bytes().unwrap_or(0),
+            )
+        })
+        .await;
+
+    AppHealth {
+        relay_connected,
+        relay_uptime_secs: connected_since.map(|since| chrono::Utc::now().timestamp() - since),
+        last_relay_message_at: last_message_time.map(|t| t * 1000),
+        outgoing_queue_depth,
+        pending_messages,
+        unsynced_breadcrumbs,
+        database_size_bytes,
+        metrics: METRICS.snapshot(),
+    }
+}
+
+/// Save a zip of redacted log files and app state summary.
+/// Returns the written path, or None if the user cancelled.
+#[tauri::command]
+pub async fn export_diagnostics(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let public_key = state.identity.lock().await.public_key_hex();
+    let health = collect_health(&state).await;
+    let (thread_count, breadcrumb_count) = state
+        .database
+        .call(|db| {
+            (
+                db.get_threads(true, None, u32::MAX).map(|t| t.len()).unwrap_or(0),
+                db.count_breadcrumbs().unwrap_or(0),
+            )
+        })
+        .await;
+
+    let summary = json!({
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "platform": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "public_key": public_key.map(|pk| redact(&pk)),
+        "thread_count": thread_count,
+        "breadcrumb_count": breadcrumb_count,
+        "health": health,
+    });
+
+    let mut files = vec![(
+        "diagnostics.json".to_string(),
+        serde_json::to_vec_pretty(&summary)?,
+    )];
+    for path in logging::log_files() {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()).map(|n| n.to_string()) else {
+            continue;
+        };
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                let redacted: String = String::from_utf8_lossy(&bytes)
+                    .lines()
+                    .map(|line| redact(line) + "\n")
+                    .collect();
+                files.push((format!("logs/{}", name), redacted.into_bytes()));
+            }
+            Err(e) => tracing::warn!("Skipping log file {}: {}", path.display(), e),
+        }
+    }
+
+    let bytes = build_zip(&files)?;
+    let name = format!("gns-diagnostics-{}", chrono::Utc::now().format("%Y%m%d-%H%M"));
+    save_with_dialog(&app_handle, &name, "zip", bytes).await
+}
@@ -1,68 +1,737 @@
 use crate::AppState;
-use crate::dix::{DixPost, DixPostData, DixUserData, DixMedia};
-use tauri::State;
+use crate::dix::{self, DixPost, DixPostData, DixUserData, DixMedia, DixImageUpload, DixReplies, DixFollowUser, DixService, DixSyncDelta, DixBookmark, DixTombstone, DixFilters};
+use crate::commands::messaging::{send_message, SendResult};
+use crate::dix::notifications::{self, NotificationFeed};
+use crate::dix::share::{self, DixRef, QuoteConsent, QuotedMessage, DIX_REF_PAYLOAD_TYPE, QUOTE_CONSENT_FIELD};
+use crate::error::AppError;
+use crate::storage::DatabaseHandle;
+use serde_json::json;
+use tauri::{AppHandle, Emitter, State};
 
 #[tauri::command]
 pub async fn create_post(
     state: State<'_, AppState>,
     text: String,
     media: Vec<DixMedia>,
+    images: Option<Vec<DixImageUpload>>,
     reply_to_id: Option<String>,
-) -> Result<DixPost, String> {
-    state.dix.create_post(text, media, reply_to_id).await
+    proximity_id: Option<String>,
+) -> Result<DixPost, AppError> {
+    let proximity = match proximity_id {
+        Some(id) => Some(
+            state
+                .database
+                .call(move |db| db.get_proximity_attestation(&id))
+                .await
+                .ok_or_else(|| AppError::not_found("Proximity proof"))?
+                .attestation,
+        ),
+        None => None,
+    };
+    state.dix.create_post(text, media, images.unwrap_or_default(), reply_to_id, None, proximity, None).await.map_err(AppError::from)
 }
 
+/// Timeline page, served from the local cache when possible.
+///
+/// Cached pages return immediately and are refreshed in the background;
+/// `timeline_updated` fires when the refresh brings new or changed posts.
 #[tauri::command]
 pub async fn get_timeline(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     limit: Option<u32>,
     offset: Option<u32>,
-) -> Result<Vec<DixPost>, String> {
-    state.dix.get_timeline(limit.unwrap_or(20), offset.unwrap_or(0)).await
+    following_only: Option<bool>,
+) -> Result<Vec<DixPost>, AppError> {
+    let limit = limit.unwrap_or(20);
+    let offset = offset.unwrap_or(0);
+    let following_only = following_only.unwrap_or(false);
+
+    let own_handle = state.identity.lock().await.cached_handle();
+
+    let authors = if following_only {
+        let pk = state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?;
+        let following = state
+            .database
+            .call(move |db| db.get_dix_following(&pk))
+            .await?;
+        Some(following.into_iter().map(|u| u.public_key).collect::<Vec<_>>())
+    } else {
+        None
+    };
+
+    let cache_authors = authors.clone();
+    let mut cached = state
+        .database
+        .call(move |db| db.get_cached_dix_timeline(limit, offset, cache_authors.as_deref()))
+        .await
+        .unwrap_or_default();
+    // Filters may have changed since these were cached
+    state.dix.apply_filters(&mut cached);
+
+    let mut posts = if cached.is_empty() {
+        // Nothing cached yet, so wait for the network
+        let authors = if following_only {
+            Some(load_following(&state).await?.into_iter().map(|u| u.public_key).collect())
+        } else {
+            None
+        };
+        sync_timeline(&app_handle, &state.dix, &state.database, own_handle.as_deref(), limit, offset, authors)
+            .await?
+            .0
+    } else {
+        let dix = state.dix.clone();
+        let database = state.database.clone();
+        tauri::async_runtime::spawn(async move {
+            match sync_timeline(&app_handle, &dix, &database, own_handle.as_deref(), limit, offset, authors).await {
+                Ok((_, delta)) if !delta.is_empty() => {
+                    let _ = app_handle.emit("timeline_updated", json!({
+                        "followingOnly": following_only,
+                        "limit": limit,
+                        "offset": offset,
+                        "newPosts": delta.new_posts,
+                        "updatedPosts": delta.updated_posts,
+                    }));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::debug!("Timeline sync failed: {}", e),
+            }
+        });
+        cached
+    };
+
+    // Our own replies may not be counted by the server yet
+    let ids: Vec<String> = posts.iter().map(|p| p.id.clone()).collect();
+    let reply_counts = state
+        .database
+        .call(move |db| ids.iter().map(|id| db.get_dix_reply_count(id)).collect::<Vec<_>>())
+        .await;
+    for (post, cached) in posts.iter_mut().zip(reply_counts) {
+        if let Some(cached) = cached {
+            post.engagement.replies = post.engagement.replies.max(cached as i32);
+        }
+    }
+
+    Ok(posts)
+}
+
+/// Fetch a timeline page and merge it into the local cache.
+///
+/// Emits `dix_mention` for posts seen for the first time that mention `own_handle`.
+async fn sync_timeline(
+    app_handle: &AppHandle,
+    dix: &DixService,
+    database: &DatabaseHandle,
+    own_handle: Option<&str>,
+    limit: u32,
+    offset: u32,
+    authors: Option<Vec<String>>,
+) -> Result<(Vec<DixPost>, DixSyncDelta), String> {
+    let posts = match authors {
+        Some(authors) => dix.get_following_timeline(limit, offset, &authors).await?,
+        None => dix.get_timeline(limit, offset).await?,
+    };
+
+    let (posts, delta) = database
+        .call(move |db| {
+            let mut posts = posts;
+            db.remove_tombstoned_dix_posts(&mut posts);
+            db.upsert_dix_posts(&posts).map(|delta| (posts, delta))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(handle) = own_handle {
+        notify_mentions(app_handle, &posts, &delta.new_post_ids, handle);
+    }
+
+    Ok((posts, delta))
+}
+
+fn notify_mentions(app_handle: &AppHandle, posts: &[DixPost], new_ids: &[String], handle: &str) {
+    let own_handle = dix::normalize_tag(handle);
+
+    for post in posts.iter().filter(|p| new_ids.contains(&p.id)) {
+        let is_own = post.author.handle.as_deref().map(dix::normalize_tag) == Some(own_handle.clone());
+        if is_own || !dix::mentions_handle(post, &own_handle) {
+            continue;
+        }
+
+        let _ = app_handle.emit("dix_mention", json!({
+            "postId": post.id,
+            "authorPublicKey": post.author.public_key,
+            "authorHandle": post.author.handle,
+            "text": post.content.text,
+        }));
+    }
 }
 
 #[tauri::command]
 pub async fn like_post(
     state: State<'_, AppState>,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let (pk, sig) = {
         let identity = state.identity.lock().await;
         // Using public_key_hex() as established in file reading
-        let pk = identity.public_key_hex().ok_or("No identity")?;
+        let pk = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
         let sig = identity.sign_string(&id).ok_or("Failed to sign")?;
         (pk, sig)
     };
-    state.dix.like_post(&id, &pk, &sig).await
+    state.dix.like_post(&id, &pk, &sig).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn repost_post(
     state: State<'_, AppState>,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let (pk, sig) = {
         let identity = state.identity.lock().await;
         // Using public_key_hex() as established in file reading
-        let pk = identity.public_key_hex().ok_or("No identity")?;
+        let pk = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
         let sig = identity.sign_string(&id).ok_or("Failed to sign")?;
         (pk, sig)
     };
-    state.dix.repost_post(&id, &pk, &sig).await
+    state.dix.repost_post(&id, &pk, &sig).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_post(
     state: State<'_, AppState>,
     id: String,
-) -> Result<DixPostData, String> {
-    state.dix.get_post(&id).await
+) -> Result<DixPostData, AppError> {
+    state.dix.get_post(&id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_posts_by_user(
     state: State<'_, AppState>,
     public_key: String,
-) -> Result<DixUserData, String> {
-    state.dix.get_posts_by_user(&public_key).await
+) -> Result<DixUserData, AppError> {
+    let mut data = state.dix.get_posts_by_user(&public_key).await?;
+
+    // The server may not have processed our deletions yet
+    let posts = std::mem::take(&mut data.posts);
+    data.posts = state
+        .database
+        .call(move |db| {
+            let mut posts = posts;
+            db.remove_tombstoned_dix_posts(&mut posts);
+            posts
+        })
+        .await;
+
+    Ok(data)
+}
+
+#[tauri::command]
+pub async fn reply_to_post(
+    state: State<'_, AppState>,
+    post_id: String,
+    text: String,
+    media: Option<Vec<DixMedia>>,
+) -> Result<DixPost, AppError> {
+    let reply = state.dix.reply_to_post(&post_id, text, media.unwrap_or_default()).await?;
+
+    let id = post_id.clone();
+    if let Err(e) = state.database.call(move |db| db.increment_dix_reply_count(&id)).await {
+        tracing::warn!("Failed to update reply count for {}: {}", post_id, e);
+    }
+
+    Ok(reply)
+}
+
+#[tauri::command]
+pub async fn get_replies(
+    state: State<'_, AppState>,
+    post_id: String,
+) -> Result<DixReplies, AppError> {
+    let replies = state.dix.get_replies(&post_id).await?;
+
+    let (id, count) = (post_id.clone(), replies.reply_count);
+    if let Err(e) = state.database.call(move |db| db.set_dix_reply_count(&id, count)).await {
+        tracing::warn!("Failed to cache reply count for {}: {}", post_id, e);
+    }
+
+    Ok(replies)
+}
+
+#[tauri::command]
+pub async fn follow_user(
+    state: State<'_, AppState>,
+    public_key: String,
+    handle: Option<String>,
+    display_name: Option<String>,
+    avatar_url: Option<String>,
+) -> Result<(), AppError> {
+    let (pk, sig) = {
+        let identity = state.identity.lock().await;
+        let pk = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+        let sig = identity.sign_string(&format!("follow:{}", public_key)).ok_or("Failed to sign")?;
+        (pk, sig)
+    };
+
+    if pk == public_key {
+        return Err("Cannot follow yourself".into());
+    }
+
+    state.dix.follow_user(&public_key, &pk, &sig).await?;
+
+    let user = DixFollowUser {
+        public_key,
+        handle,
+        display_name,
+        avatar_url,
+    };
+    let cached = user.clone();
+    if let Err(e) = state.database.call(move |db| db.add_dix_following(&pk, &cached)).await {
+        tracing::warn!("Failed to cache follow of {}: {}", user.public_key, e);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unfollow_user(
+    state: State<'_, AppState>,
+    public_key: String,
+) -> Result<(), AppError> {
+    let (pk, sig) = {
+        let identity = state.identity.lock().await;
+        let pk = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+        let sig = identity.sign_string(&format!("unfollow:{}", public_key)).ok_or("Failed to sign")?;
+        (pk, sig)
+    };
+
+    state.dix.unfollow_user(&public_key, &pk, &sig).await?;
+
+    let key = public_key.clone();
+    if let Err(e) = state.database.call(move |db| db.remove_dix_following(&pk, &key)).await {
+        tracing::warn!("Failed to uncache follow of {}: {}", public_key, e);
+    }
+
+    Ok(())
+}
+
+/// Who a user follows (defaults to us; served from cache when offline)
+#[tauri::command]
+pub async fn get_following(
+    state: State<'_, AppState>,
+    public_key: Option<String>,
+) -> Result<Vec<DixFollowUser>, AppError> {
+    let own_pk = state.identity.lock().await.public_key_hex();
+
+    match public_key {
+        Some(pk) if Some(&pk) != own_pk.as_ref() => state.dix.get_following(&pk).await.map_err(AppError::from),
+        _ => load_following(&state).await,
+    }
+}
+
+#[tauri::command]
+pub async fn get_followers(
+    state: State<'_, AppState>,
+    public_key: Option<String>,
+) -> Result<Vec<DixFollowUser>, AppError> {
+    let pk = match public_key {
+        Some(pk) => pk,
+        None => state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?,
+    };
+    state.dix.get_followers(&pk).await.map_err(AppError::from)
+}
+
+/// Our follow list: refreshed from the server, falling back to the local cache
+async fn load_following(state: &State<'_, AppState>) -> Result<Vec<DixFollowUser>, AppError> {
+    let pk = state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?;
+
+    match state.dix.get_following(&pk).await {
+        Ok(users) => {
+            let cached = users.clone();
+            if let Err(e) = state.database.call(move |db| db.replace_dix_following(&pk, &cached)).await {
+                tracing::warn!("Failed to cache follow list: {}", e);
+            }
+            Ok(users)
+        }
+        Err(e) => {
+            tracing::debug!("Follow list unavailable, using cache: {}", e);
+            state
+                .database
+                .call(move |db| db.get_dix_following(&pk))
+                .await
+                .map_err(AppError::from)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_posts_by_hashtag(
+    state: State<'_, AppState>,
+    tag: String,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<DixPost>, AppError> {
+    state.dix.get_posts_by_hashtag(&tag, limit.unwrap_or(20), offset.unwrap_or(0)).await.map_err(AppError::from)
+}
+
+/// Posts mentioning a handle (defaults to our own)
+#[tauri::command]
+pub async fn get_mentions(
+    state: State<'_, AppState>,
+    handle: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<DixPost>, AppError> {
+    let handle = match handle {
+        Some(h) => h,
+        None => state.identity.lock().await.cached_handle().ok_or("No handle claimed")?,
+    };
+    state.dix.get_mentions(&handle, limit.unwrap_or(20), offset.unwrap_or(0)).await.map_err(AppError::from)
+}
+
+/// Save a post locally. The server is never told what we bookmark.
+#[tauri::command]
+pub async fn bookmark_post(
+    state: State<'_, AppState>,
+    post_id: String,
+    collection: Option<String>,
+) -> Result<DixBookmark, AppError> {
+    let id = post_id.clone();
+    let cached = state.database.call(move |db| db.get_cached_dix_post(&id)).await;
+    let post = match cached {
+        Some(post) => post,
+        None => state.dix.get_post(&post_id).await?.post,
+    };
+
+    let bookmark = DixBookmark {
+        post,
+        collection: collection.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()),
+        bookmarked_at: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let (pk, sealed) = {
+        let identity = state.identity.lock().await;
+        let pk = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+        let json = serde_json::to_vec(&bookmark)?;
+        let sealed = identity.seal_for_self(&json).ok_or("Failed to encrypt bookmark")?;
+        (pk, sealed)
+    };
+
+    state
+        .database
+        .call(move |db| db.save_dix_bookmark(&pk, &post_id, &sealed))
+        .await?;
+
+    Ok(bookmark)
+}
+
+#[tauri::command]
+pub async fn unbookmark_post(
+    state: State<'_, AppState>,
+    post_id: String,
+) -> Result<bool, AppError> {
+    let pk = state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?;
+    state
+        .database
+        .call(move |db| db.delete_dix_bookmark(&pk, &post_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Bookmarks, newest first, optionally limited to one collection
+#[tauri::command]
+pub async fn get_bookmarks(
+    state: State<'_, AppState>,
+    collection: Option<String>,
+) -> Result<Vec<DixBookmark>, AppError> {
+    let bookmarks = load_bookmarks(&state).await?;
+    Ok(match collection {
+        Some(name) => bookmarks
+            .into_iter()
+            .filter(|b| b.collection.as_deref() == Some(name.as_str()))
+            .collect(),
+        None => bookmarks,
+    })
+}
+
+/// Names of all bookmark collections in use
+#[tauri::command]
+pub async fn get_bookmark_collections(
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let mut names: Vec<String> = load_bookmarks(&state)
+        .await?
+        .into_iter()
+        .filter_map(|b| b.collection)
+        .collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+async fn load_bookmarks(state: &State<'_, AppState>) -> Result<Vec<DixBookmark>, String> {
+    let pk = state.identity.lock().await.public_key_hex().ok_or("No identity")?;
+    let sealed = state
+        .database
+        .call(move |db| db.get_dix_bookmarks(&pk))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let identity = state.identity.lock().await;
+    Ok(sealed
+        .iter()
+        .filter_map(|s| identity.open_sealed(s))
+        .filter_map(|json| serde_json::from_slice(&json).ok())
+        .collect())
+}
+
+/// Delete one of our posts with a signed tombstone
+#[tauri::command]
+pub async fn delete_post(
+    state: State<'_, AppState>,
+    post_id: String,
+) -> Result<DixTombstone, AppError> {
+    let id = post_id.clone();
+    let cached = state.database.call(move |db| db.get_cached_dix_post(&id)).await;
+    let post = match cached {
+        Some(post) => post,
+        None => state.dix.get_post(&post_id).await?.post,
+    };
+
+    let tombstone = state.dix.delete_post(&post).await?;
+
+    let applied = tombstone.clone();
+    state
+        .database
+        .call(move |db| db.apply_dix_tombstone(&applied))
+        .await?;
+
+    Ok(tombstone)
+}
+
+/// Replace the text of one of our posts with a signed edit
+#[tauri::command]
+pub async fn edit_post(
+    state: State<'_, AppState>,
+    post_id: String,
+    text: String,
+) -> Result<DixPost, AppError> {
+    let id = post_id.clone();
+    let cached = state.database.call(move |db| db.get_cached_dix_post(&id)).await;
+    let mut post = match cached {
+        Some(post) => post,
+        None => state.dix.get_post(&post_id).await?.post,
+    };
+
+    let edit = state.dix.edit_post(&post, text).await?;
+
+    let applied = edit.clone();
+    let updated = state
+        .database
+        .call(move |db| db.apply_dix_edit(&applied))
+        .await?;
+    match updated {
+        Some(updated) => Ok(updated),
+        None => {
+            edit.apply_to(&mut post);
+            Ok(post)
+        }
+    }
+}
+
+/// Likes, reposts, replies, follows and mentions, folded per post
+#[tauri::command]
+pub async fn get_notifications(
+    state: State<'_, AppState>,
+) -> Result<NotificationFeed, AppError> {
+    state
+        .database
+        .call(|db| db.list_dix_activity(notifications::FEED_LIMIT))
+        .await
+        .map(|activity| notifications::build_feed(&activity))
+        .map_err(AppError::from)
+}
+
+/// Mark notifications read by their `activity_ids`, or all of them
+#[tauri::command]
+pub async fn mark_notifications_read(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    activity_ids: Option<Vec<i64>>,
+) -> Result<NotificationFeed, AppError> {
+    let feed = state
+        .database
+        .call(move |db| {
+            db.mark_dix_activity_read(activity_ids.as_deref())?;
+            db.list_dix_activity(notifications::FEED_LIMIT)
+        })
+        .await
+        .map(|activity| notifications::build_feed(&activity))?;
+    let _ = app_handle.emit("notifications_updated", &feed);
+    Ok(feed)
+}
+
+/// Send a snapshot of a post, signature included, into a thread
+#[tauri::command]
+pub async fn share_post_to_thread(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    post_id: String,
+    thread_id: String,
+    comment: Option<String>,
+) -> Result<SendResult, AppError> {
+    let id = post_id.clone();
+    let cached = state.database.call(move |db| db.get_cached_dix_post(&id)).await;
+    let post = match cached {
+        Some(post) => post,
+        None => state.dix.get_post(&post_id).await?.post,
+    };
+    if !dix::verify_post(&post) {
+        return Err("Post signature doesn't verify".into());
+    }
+
+    let tid = thread_id.clone();
+    let thread = state
+        .database
+        .call(move |db| db.get_thread(&tid))
+        .await?
+        .ok_or_else(|| AppError::not_found("Thread"))?;
+
+    let dix_ref = DixRef {
+        post,
+        comment: comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()),
+    };
+    let payload = serde_json::to_value(&dix_ref)?;
+    send_message(
+        None,
+        Some(thread.participant_public_key),
+        DIX_REF_PAYLOAD_TYPE.to_string(),
+        payload,
+        Some(thread_id),
+        None,
+        app_handle,
+        state,
+    )
+    .await
+}
+
+/// Post a chat message's text to Dix as a quote
+///
+/// Our own messages can always be quoted. Anyone else's only if they sent
+/// it with `dix_quote_consent`; their signature goes on the post with it.
+#[tauri::command]
+pub async fn quote_message_to_dix(
+    state: State<'_, AppState>,
+    message_id: String,
+    comment: Option<String>,
+) -> Result<DixPost, AppError> {
+    let message = state
+        .database
+        .call(move |db| db.get_message(&message_id))
+        .await?
+        .ok_or_else(|| AppError::not_found("Message"))?;
+    let text = message
+        .payload
+        .get("text")
+        .and_then(|t| t.as_str())
+        .filter(|t| !t.trim().is_empty())
+        .ok_or("Only text messages can be quoted on Dix")?;
+
+    let quote = if message.is_outgoing {
+        let identity = state.identity.lock().await;
+        let author = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+        let signed_at = chrono::Utc::now().timestamp_millis();
+        let signature = identity
+            .sign_string(&share::quote_signing_message(&author, text, signed_at))
+            .ok_or("Failed to sign")?;
+        QuotedMessage::new(&author, identity.cached_handle(), text, QuoteConsent { signed_at, signature })
+    } else {
+        let consent = message
+            .payload
+            .get(QUOTE_CONSENT_FIELD)
+            .and_then(|c| serde_json::from_value::<QuoteConsent>(c.clone()).ok())
+            .ok_or("The sender hasn't agreed to this message being quoted on Dix")?;
+        QuotedMessage::new(&message.from_public_key, message.from_handle.clone(), text, consent)
+    };
+    if !share::verify_quoted_message(&quote) {
+        return Err("The sender's consent doesn't cover this message".into());
+    }
+
+    let comment = comment.map(|c| c.trim().to_string()).unwrap_or_default();
+    state.dix.create_post(comment, vec![], vec![], None, None, None, Some(quote)).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_filters(
+    state: State<'_, AppState>,
+) -> Result<DixFilters, AppError> {
+    Ok(state.dix.filters())
+}
+
+#[tauri::command]
+pub async fn add_muted_word(
+    state: State<'_, AppState>,
+    word: String,
+) -> Result<DixFilters, AppError> {
+    let word = word.trim().to_lowercase();
+    if word.is_empty() {
+        return Err("Muted word cannot be empty".into());
+    }
+    update_filters(&state, |f| {
+        if !f.muted_words.contains(&word) {
+            f.muted_words.push(word);
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_muted_word(
+    state: State<'_, AppState>,
+    word: String,
+) -> Result<DixFilters, AppError> {
+    let word = word.trim().to_lowercase();
+    update_filters(&state, |f| f.muted_words.retain(|w| *w != word)).await
+}
+
+#[tauri::command]
+pub async fn mute_author(
+    state: State<'_, AppState>,
+    public_key: String,
+) -> Result<DixFilters, AppError> {
+    let public_key = public_key.to_lowercase();
+    update_filters(&state, |f| {
+        if !f.muted_authors.contains(&public_key) {
+            f.muted_authors.push(public_key);
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn unmute_author(
+    state: State<'_, AppState>,
+    public_key: String,
+) -> Result<DixFilters, AppError> {
+    let public_key = public_key.to_lowercase();
+    update_filters(&state, |f| f.muted_authors.retain(|a| *a != public_key)).await
+}
+
+/// Change the filters, persist them and apply them to DixService
+async fn update_filters(
+    state: &State<'_, AppState>,
+    change: impl FnOnce(&mut DixFilters),
+) -> Result<DixFilters, AppError> {
+    let pk = state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?;
+
+    let mut filters = state.dix.filters();
+    change(&mut filters);
+
+    let saved = filters.clone();
+    state
+        .database
+        .call(move |db| db.set_dix_filters(&pk, &saved))
+        .await?;
+    state.dix.set_filters(filters.clone());
+
+    Ok(filters)
 }
@@ -0,0 +1,349 @@
+//! Export Commands
+//!
+//! Export one thread or all messages to a file chosen with the save dialog,
+//! and move all app data between devices.
+
+use std::collections::BTreeMap;
+
+use crate::attachments::MAX_ATTACHMENT_BYTES;
+use crate::error::AppError;
+use crate::export::{self, ExportFormat, ExportedThread};
+use crate::storage::TransferRow;
+use crate::AppState;
+use gns_crypto_core::EncryptedBackup;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+
+/// File format tag for app data backups
+const APP_DATA_FORMAT: &str = "gns-app-data";
+const APP_DATA_VERSION: u32 = 1;
+
+/// Export a single thread. Returns the written path, or None if the user cancelled.
+#[tauri::command]
+pub async fn export_thread(
+    app_handle: AppHandle,
+    thread_id: String,
+    format: ExportFormat,
+    include_attachments: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let exported = state
+        .database
+        .call(move |db| {
+            let thread = db
+                .get_thread(&thread_id)?
+                .ok_or_else(|| AppError::not_found("Thread"))?;
+            let mut messages = db.get_messages(&thread_id, u32::MAX)?;
+            messages.reverse();
+            Ok::<_, AppError>(ExportedThread { thread, messages })
+        })
+        .await?;
+
+    if format == ExportFormat::Eml && !exported.is_email() {
+        return Err("EML export is only available for email threads".into());
+    }
+
+    let name = export::sanitize_file_name(&exported.title());
+    let (bytes, extension) =
+        build_export(&state, vec![exported], format, include_attachments.unwrap_or(false)).await?;
+
+    save_with_dialog(&app_handle, &name, extension, bytes).await
+}
+
+/// Export every thread. Returns the written path, or None if the user cancelled.
+#[tauri::command]
+pub async fn export_all_messages(
+    app_handle: AppHandle,
+    format: ExportFormat,
+    include_attachments: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let threads = state
+        .database
+        .call(|db| {
+            let mut threads = Vec::new();
+            for thread in db.get_threads(true, None, u32::MAX)? {
+                let mut messages = db.get_messages(&thread.id, u32::MAX)?;
+                messages.reverse();
+                threads.push(ExportedThread { thread, messages });
+            }
+            Ok::<_, crate::storage::DatabaseError>(threads)
+        })
+        .await?;
+
+    // EML only makes sense for email threads
+    let threads: Vec<ExportedThread> = if format == ExportFormat::Eml {
+        threads.into_iter().filter(|t| t.is_email()).collect()
+    } else {
+        threads
+    };
+    if threads.is_empty() {
+        return Err("Nothing to export".into());
+    }
+
+    let name = format!("gns-messages-{}", chrono::Utc::now().format("%Y%m%d"));
+    let (bytes, extension) =
+        build_export(&state, threads, format, include_attachments.unwrap_or(false)).await?;
+
+    save_with_dialog(&app_handle, &name, extension, bytes).await
+}
+
+/// Export messages, settings, contacts and (with a passphrase) the identity
+/// into one file for moving to another device.
+/// Returns the written path, or None if the user cancelled.
+#[tauri::command]
+pub async fn export_app_data(
+    app_handle: AppHandle,
+    passphrase: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let (public_key, handle, seed) = {
+        let identity = state.identity.lock().await;
+        (identity.public_key_hex(), identity.cached_handle(), identity.private_key_bytes())
+    };
+
+    let identity = match (passphrase.filter(|p| !p.is_empty()), seed) {
+        (Some(passphrase), Some(seed)) => {
+            // Argon2 is deliberately slow; keep it off the async workers
+            let backup = tauri::async_runtime::spawn_blocking(move || {
+                let identity = gns_crypto_core::GnsIdentity::from_slice(seed.expose_secret())?;
+                gns_crypto_core::export_encrypted(&identity, &passphrase)
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Failed to encrypt identity: {}", e))?;
+            Some(backup)
+        }
+        (Some(_), None) => return Err("No identity to export".into()),
+        (None, _) => None,
+    };
+
+    let tables = state
+        .database
+        .call(|db| db.export_tables())
+        .await?;
+
+    let backup = AppDataBackup {
+        format: APP_DATA_FORMAT.to_string(),
+        version: APP_DATA_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        public_key,
+        handle,
+        identity,
+        tables,
+    };
+    let bytes = serde_json::to_vec(&backup)?;
+
+    let name = format!("gns-backup-{}", chrono::Utc::now().format("%Y%m%d"));
+    save_with_dialog(&app_handle, &name, "gnsbackup", bytes).await
+}
+
+/// Import an app data backup chosen with the open dialog.
+///
+/// Messages are merged by envelope ID, so importing the same file twice
+/// adds nothing. The identity is restored only when this device has none
+/// and the passphrase is given. Returns None if the user cancelled.
+#[tauri::command]
+pub async fn import_app_data(
+    app_handle: AppHandle,
+    passphrase: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<AppDataImportSummary>, AppError> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app_handle
+        .dialog()
+        .file()
+        .add_filter("GNS Backup", &["gnsbackup", "json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let path = path.into_path()?;
+
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read backup: {}", e))?;
+    let backup: AppDataBackup =
+        serde_json::from_slice(&bytes).map_err(|e| format!("Not a GNS backup: {}", e))?;
+    if backup.format != APP_DATA_FORMAT {
+        return Err("Not a GNS backup".into());
+    }
+    if backup.version > APP_DATA_VERSION {
+        return Err(format!("Backup version {} is newer than this app supports", backup.version).into());
+    }
+
+    let local_public_key = state.identity.lock().await.public_key_hex();
+
+    let mut identity_restored = false;
+    match (&local_public_key, &backup.public_key) {
+        (Some(local), Some(theirs)) if local != theirs => {
+            return Err("This backup belongs to a different identity".into());
+        }
+        (None, _) => {
+            if let (Some(encrypted), Some(passphrase)) = (backup.identity.clone(), passphrase) {
+                let seed_hex = tauri::async_runtime::spawn_blocking(move || {
+                    gns_crypto_core::import_encrypted(&encrypted, &passphrase).map(|i| i.private_key_hex())
+                })
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|_| "Wrong passphrase or corrupted backup".to_string())?;
+
+                let mut identity = state.identity.lock().await;
+                identity.import_from_hex(&seed_hex)?;
+                identity.set_cached_handle(backup.handle.clone());
+                identity_restored = true;
+            }
+        }
+        _ => {}
+    }
+
+    let tables = backup.tables;
+    let rows_added = state
+        .database
+        .call(move |db| db.import_tables(&tables))
+        .await?;
+
+    tracing::info!("Imported app data from {}: {:?}", path.display(), rows_added);
+
+    Ok(Some(AppDataImportSummary {
+        rows_added,
+        identity_restored,
+    }))
+}
+
+/// Render threads in the chosen format. Returns the bytes and file extension.
+async fn build_export(
+    state: &State<'_, AppState>,
+    threads: Vec<ExportedThread>,
+    format: ExportFormat,
+    include_attachments: bool,
+) -> Result<(Vec<u8>, &'static str), String> {
+    let own_handle = state.identity.lock().await.cached_handle();
+
+    let attachments = if include_attachments || format == ExportFormat::Encrypted {
+        fetch_attachments(state, &threads).await
+    } else {
+        vec![]
+    };
+
+    let main_file = match format {
+        ExportFormat::Json | ExportFormat::Encrypted => Some(("messages.json".to_string(), export::render_json(&threads)?)),
+        ExportFormat::Markdown => Some((
+            "messages.md".to_string(),
+            export::render_markdown(&threads, own_handle.as_deref()).into_bytes(),
+        )),
+        ExportFormat::Eml => None,
+    };
+
+    // A single file needs no archive
+    if let Some((_, bytes)) = &main_file {
+        if attachments.is_empty() && format != ExportFormat::Encrypted {
+            return Ok((bytes.clone(), format.extension()));
+        }
+    }
+
+    let mut files: Vec<(String, Vec<u8>)> = main_file.into_iter().collect();
+    if format == ExportFormat::Eml {
+        for thread in &threads {
+            let dir = export::sanitize_file_name(&thread.title());
+            for message in &thread.messages {
+                let eml = export::render_eml(message, &thread.thread, own_handle.as_deref());
+                files.push((format!("{}/{}.eml", dir, export::sanitize_file_name(&message.id)), eml.into_bytes()));
+            }
+        }
+    }
+    files.extend(attachments);
+
+    let archive = export::build_zip(&files)?;
+
+    if format != ExportFormat::Encrypted {
+        return Ok((archive, "zip"));
+    }
+
+    // Only this identity can open the archive
+    let identity = state.identity.lock().await;
+    let public_key = identity.public_key_hex().ok_or("No identity")?;
+    let sealed = identity.seal_for_self(&archive).ok_or("Failed to encrypt archive")?;
+    let wrapped = json!({
+        "format": "gns-archive",
+        "version": export::EXPORT_VERSION,
+        "publicKey": public_key,
+        "sealed": sealed,
+    });
+
+    Ok((serde_json::to_vec(&wrapped).map_err(|e| e.to_string())?, format.extension()))
+}
+
+/// Download attachments referenced by the exported messages
+async fn fetch_attachments(state: &State<'_, AppState>, threads: &[ExportedThread]) -> Vec<(String, Vec<u8>)> {
+    let mut files = Vec::new();
+
+    for attachment in threads.iter().flat_map(|t| t.messages.iter()).flat_map(export::attachments_of) {
+        match state.attachments.fetch(&state.api, &attachment.url, MAX_ATTACHMENT_BYTES).await {
+            Ok(bytes) => files.push((
+                format!(
+                    "attachments/{}/{}",
+                    export::sanitize_file_name(&attachment.message_id),
+                    attachment.filename
+                ),
+                bytes,
+            )),
+            Err(e) => tracing::warn!("Skipping attachment {}: {}", attachment.filename, e),
+        }
+    }
+
+    files
+}
+
+/// Ask where to save and write the file. Returns None if the user cancelled.
+pub(crate) async fn save_with_dialog(
+    app_handle: &AppHandle,
+    name: &str,
+    extension: &str,
+    bytes: Vec<u8>,
+) -> Result<Option<String>, AppError> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    app_handle
+        .dialog()
+        .file()
+        .set_file_name(format!("{}.{}", name, extension))
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let path = path.into_path()?;
+
+    std::fs::write(&path, bytes).map_err(|e| format!("Failed to write export: {}", e))?;
+    tracing::info!("Exported messages to {}", path.display());
+
+    Ok(Some(path.display().to_string()))
+}
+
+/// Portable bundle of everything needed to move to a new device
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppDataBackup {
+    format: String,
+    version: u32,
+    exported_at: String,
+    public_key: Option<String>,
+    handle: Option<String>,
+    /// Passphrase-encrypted identity, if the user chose to include it
+    identity: Option<EncryptedBackup>,
+    tables: BTreeMap<String, Vec<TransferRow>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppDataImportSummary {
+    /// New rows per table; existing rows are never overwritten
+    pub rows_added: BTreeMap<String, usize>,
+    pub identity_restored: bool,
+}
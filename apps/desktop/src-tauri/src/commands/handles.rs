@@ -157,6 +157,106 @@ impl ClaimRequirements {
     }
 }
 
+// ==================== Claim Workflow ====================
+
+/// Where a handle is in the reserve → collect → claim → publish flow
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimStage {
+    /// Reserved, collecting breadcrumbs
+    Collecting,
+    /// Requirements met, claim pending
+    Ready,
+    /// Claimed on the network, record not yet published
+    Claimed,
+    /// Claimed and record published
+    Published,
+    /// Reservation lapsed before the claim
+    Expired,
+}
+
+impl ClaimStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimStage::Collecting => "collecting",
+            ClaimStage::Ready => "ready",
+            ClaimStage::Claimed => "claimed",
+            ClaimStage::Published => "published",
+            ClaimStage::Expired => "expired",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "collecting" => Some(ClaimStage::Collecting),
+            "ready" => Some(ClaimStage::Ready),
+            "claimed" => Some(ClaimStage::Claimed),
+            "published" => Some(ClaimStage::Published),
+            "expired" => Some(ClaimStage::Expired),
+            _ => None,
+        }
+    }
+}
+
+/// Persisted claim progress for an identity (timestamps in ms)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClaimWorkflow {
+    pub public_key: String,
+    pub handle: String,
+    pub stage: ClaimStage,
+    pub network_reserved: bool,
+    pub reserved_at: i64,
+    /// Reservation expiry, when the server gave one
+    pub expires_at: Option<i64>,
+    pub claimed_at: Option<i64>,
+    pub published_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub updated_at: i64,
+}
+
+impl ClaimWorkflow {
+    /// Start a workflow for a freshly reserved handle
+    pub fn reserved(public_key: &str, handle: &str, network_reserved: bool, expires_at: Option<i64>, now: i64) -> Self {
+        Self {
+            public_key: public_key.to_string(),
+            handle: handle.to_string(),
+            stage: ClaimStage::Collecting,
+            network_reserved,
+            reserved_at: now,
+            expires_at,
+            claimed_at: None,
+            published_at: None,
+            last_error: None,
+            updated_at: now,
+        }
+    }
+
+    /// The stage local state implies, if it differs from the current one.
+    /// Only covers transitions that need no network call.
+    pub fn next_stage(&self, requirements: &ClaimRequirements, now: i64) -> Option<ClaimStage> {
+        match self.stage {
+            ClaimStage::Collecting | ClaimStage::Ready
+                if self.expires_at.is_some_and(|expires| now >= expires) =>
+            {
+                Some(ClaimStage::Expired)
+            }
+            ClaimStage::Collecting if requirements.is_met() => Some(ClaimStage::Ready),
+            ClaimStage::Ready if !requirements.is_met() => Some(ClaimStage::Collecting),
+            _ => None,
+        }
+    }
+
+    pub fn set_stage(&mut self, stage: ClaimStage, now: i64) {
+        self.stage = stage;
+        self.updated_at = now;
+        match stage {
+            ClaimStage::Claimed => self.claimed_at = Some(now),
+            ClaimStage::Published => self.published_at = Some(now),
+            _ => {}
+        }
+    }
+}
+
 // ==================== Errors ====================
 
 #[derive(Debug, Clone, Serialize, thiserror::Error)]
@@ -255,6 +355,25 @@ mod tests {
         assert!(validate_handle("HAS_CAPS").is_ok()); // Converted to lowercase
     }
     
+    #[test]
+    fn test_claim_workflow_transitions() {
+        let mut workflow = ClaimWorkflow::reserved("pk", "alice", true, Some(1_000), 0);
+        let short = ClaimRequirements::new(50, 40.0);
+        let met = ClaimRequirements::new(120, 40.0);
+
+        assert_eq!(workflow.next_stage(&short, 10), None);
+        assert_eq!(workflow.next_stage(&met, 10), Some(ClaimStage::Ready));
+
+        workflow.set_stage(ClaimStage::Ready, 10);
+        assert_eq!(workflow.next_stage(&short, 20), Some(ClaimStage::Collecting));
+        assert_eq!(workflow.next_stage(&met, 1_000), Some(ClaimStage::Expired));
+
+        workflow.set_stage(ClaimStage::Claimed, 30);
+        assert_eq!(workflow.claimed_at, Some(30));
+        assert_eq!(workflow.next_stage(&met, 2_000), None);
+        assert_eq!(ClaimStage::parse(workflow.stage.as_str()), Some(ClaimStage::Claimed));
+    }
+
     #[test]
     fn test_canonical_json() {
         let json = serde_json::json!({
@@ -2,41 +2,30 @@
 //!
 //! Commands for managing the user's cryptographic identity.
 
+use crate::error::AppError;
+use crate::qr;
 use crate::AppState;
-use gns_crypto_core::GnsIdentity;
+use base64::Engine;
+use gns_crypto_core::{EncryptedBackup, EncryptedPayload, GnsIdentity, IdentityCard, SecretString};
 use tauri::State;
 
 /// Get the user's Ed25519 public key (hex)
 #[tauri::command]
-pub async fn get_public_key(state: State<'_, AppState>) -> Result<Option<String>, String> {
+pub async fn get_public_key(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
     let identity = state.identity.lock().await;
     Ok(identity.public_key_hex())
 }
 
-/// Sign a string message with the user's private key
-#[tauri::command]
-pub async fn sign_string(
-    message: String,
-    state: State<'_, AppState>,
-) -> Result<Option<String>, String> {
-    let identity = state.identity.lock().await; // Do not hold lock across await if possible, but signing is fast
-    // Actually, signing doesn't yield, so it's fine.
-    // However, identity.sign_string might handle the error internally, let's check identity implementation
-    // But wait, the grep showed sign_string returns Option<String> in crypto/mod.rs
-    
-    Ok(identity.sign_string(&message))
-}
-
 /// Get the user's X25519 encryption key (hex)
 #[tauri::command]
-pub async fn get_encryption_key(state: State<'_, AppState>) -> Result<Option<String>, String> {
+pub async fn get_encryption_key(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
     let identity = state.identity.lock().await;
     Ok(identity.encryption_key_hex())
 }
 
 /// Get the user's current claimed @handle (if any)
 #[tauri::command]
-pub async fn get_current_handle(state: State<'_, AppState>) -> Result<Option<String>, String> {
+pub async fn get_current_handle(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
     let identity = state.identity.lock().await;
 
     // First check local cache
@@ -61,23 +50,21 @@ pub async fn get_current_handle(state: State<'_, AppState>) -> Result<Option<Str
 
 /// Check if the user has an identity
 #[tauri::command]
-pub async fn has_identity(state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn has_identity(state: State<'_, AppState>) -> Result<bool, AppError> {
     let identity = state.identity.lock().await;
     Ok(identity.has_identity())
 }
 
 /// Generate a new identity
 #[tauri::command]
-pub async fn generate_identity(state: State<'_, AppState>) -> Result<IdentityInfo, String> {
+pub async fn generate_identity(state: State<'_, AppState>) -> Result<IdentityInfo, AppError> {
     let mut identity = state.identity.lock().await;
 
     if identity.has_identity() {
-        return Err(
-            "Identity already exists. Export backup before generating new identity.".to_string(),
-        );
+        return Err("Identity already exists. Export backup before generating new identity.".into());
     }
 
-    identity.generate_new().map_err(|e| e.to_string())?;
+    identity.generate_new()?;
 
     Ok(IdentityInfo {
         public_key: identity.public_key_hex().unwrap_or_default(),
@@ -88,19 +75,18 @@ pub async fn generate_identity(state: State<'_, AppState>) -> Result<IdentityInf
 /// Import an identity from private key hex
 #[tauri::command]
 pub async fn import_identity(
-    private_key_hex: String,
+    private_key_hex: SecretString,
     state: State<'_, AppState>,
-) -> Result<IdentityInfo, String> {
+) -> Result<IdentityInfo, AppError> {
     let mut identity = state.identity.lock().await;
 
     // Validate the private key first
-    let test_identity = GnsIdentity::from_hex(&private_key_hex)
+    let test_identity = GnsIdentity::from_hex(private_key_hex.expose_secret())
         .map_err(|e| format!("Invalid private key: {}", e))?;
 
     // Import into keychain
     identity
-        .import_from_hex(&private_key_hex)
-        .map_err(|e| e.to_string())?;
+        .import_from_hex(&private_key_hex)?;
 
     Ok(IdentityInfo {
         public_key: test_identity.public_key_hex(),
@@ -109,26 +95,42 @@ pub async fn import_identity(
 }
 
 /// Export identity backup (for migration)
-/// ⚠️ This returns the private key - handle with extreme care!
+///
+/// The seed is sealed with `passphrase` (Argon2id + ChaCha20-Poly1305);
+/// this is the only way the key leaves the app. Needs the app lock PIN
+/// when the lock is enabled.
 #[tauri::command]
-pub async fn export_identity_backup(state: State<'_, AppState>) -> Result<IdentityBackup, String> {
-    let identity = state.identity.lock().await;
-
-    let private_key = identity.private_key_hex().ok_or("No identity to export")?;
+pub async fn export_identity_backup(
+    passphrase: String,
+    pin: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<IdentityBackup, AppError> {
+    crate::commands::app_lock::confirm_pin(&state, pin).await?;
 
-    let public_key = identity.public_key_hex().ok_or("No identity to export")?;
+    let (seed, public_key, encryption_key) = {
+        let identity = state.identity.lock().await;
+        (
+            identity.private_key_bytes().ok_or(AppError::NoIdentity)?,
+            identity.public_key_hex().ok_or(AppError::NoIdentity)?,
+            identity.encryption_key_hex().ok_or(AppError::NoIdentity)?,
+        )
+    };
 
-    let encryption_key = identity
-        .encryption_key_hex()
-        .ok_or("No identity to export")?;
+    // Argon2 is deliberately slow; keep it off the async workers
+    let backup = tauri::async_runtime::spawn_blocking(move || {
+        let identity = GnsIdentity::from_slice(seed.expose_secret())?;
+        gns_crypto_core::export_encrypted(&identity, &passphrase)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("Failed to encrypt identity: {}", e))?;
 
     // Get breadcrumb count
-    let db = state.database.lock().await;
-    let breadcrumb_count = db.count_breadcrumbs().unwrap_or(0);
+    let breadcrumb_count = state.database.call(|db| db.count_breadcrumbs()).await.unwrap_or(0);
 
     Ok(IdentityBackup {
-        version: 1,
-        private_key,
+        version: 2,
+        backup,
         public_key,
         encryption_key,
         breadcrumb_count,
@@ -136,10 +138,79 @@ pub async fn export_identity_backup(state: State<'_, AppState>) -> Result<Identi
     })
 }
 
+/// Restore an identity from a backup made by `export_identity_backup`
+#[tauri::command]
+pub async fn import_identity_backup(
+    backup: EncryptedBackup,
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<IdentityInfo, AppError> {
+    let seed_hex = tauri::async_runtime::spawn_blocking(move || {
+        gns_crypto_core::import_encrypted(&backup, &passphrase).map(|i| i.private_key_hex())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|_| "Wrong passphrase or corrupted backup".to_string())?;
+
+    let mut identity = state.identity.lock().await;
+    identity.import_from_hex(&seed_hex)?;
+
+    Ok(IdentityInfo {
+        public_key: identity.public_key_hex().unwrap_or_default(),
+        encryption_key: identity.encryption_key_hex().unwrap_or_default(),
+    })
+}
+
+/// Prefixed to everything `sign_payload` signs. No protocol message
+/// starts with it, so the webview can't get a record, handle statement,
+/// breadcrumb or envelope signed through this command.
+pub const SIGN_PAYLOAD_CONTEXT: &[u8] = b"gns-app-sign-v1:";
+
+/// Sign a payload with the identity key
+///
+/// The webview never holds the key; anything it needs signed comes here.
+/// `encoding` says how `payload` is encoded (default UTF-8 text). The
+/// signature covers `SIGN_PAYLOAD_CONTEXT` followed by the payload.
+#[tauri::command]
+pub async fn sign_payload(
+    payload: String,
+    encoding: Option<PayloadEncoding>,
+    state: State<'_, AppState>,
+) -> Result<SignedPayload, AppError> {
+    let bytes = encoding.unwrap_or_default().decode(&payload)?;
+
+    let identity = state.identity.lock().await;
+    let signer = identity.get_identity().ok_or(AppError::NoIdentity)?;
+
+    let message = [SIGN_PAYLOAD_CONTEXT, bytes.as_slice()].concat();
+    Ok(SignedPayload {
+        public_key: signer.public_key_hex(),
+        signature: hex::encode(signer.sign_bytes(&message)),
+    })
+}
+
+/// Decrypt a payload addressed to our encryption key
+///
+/// Returns the plaintext as base64.
+#[tauri::command]
+pub async fn decrypt_payload(
+    encrypted: EncryptedPayload,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let identity = state.identity.lock().await;
+    let recipient = identity.get_identity().ok_or(AppError::NoIdentity)?;
+
+    let plaintext = recipient
+        .decrypt(&encrypted)
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(plaintext))
+}
+
 /// Delete identity from Keychain and clear all local data
 /// ⚠️ This is destructive and cannot be undone!
 #[tauri::command]
-pub async fn delete_identity(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn delete_identity(state: State<'_, AppState>) -> Result<(), AppError> {
     tracing::warn!("🗑️ delete_identity called - clearing Keychain and local data");
     
     // 1. Clear the identity from IdentityManager (clears Keychain)
@@ -149,10 +220,11 @@ pub async fn delete_identity(state: State<'_, AppState>) -> Result<(), String> {
     }
     
     // 2. Clear the database
-    {
-        let mut db = state.database.lock().await;
-        db.clear_all().map_err(|e| format!("Failed to clear database: {}", e))?;
-    }
+    state
+        .database
+        .call(|db| db.clear_all())
+        .await
+        .map_err(|e| format!("Failed to clear database: {}", e))?;
     
     // 3. Disconnect from relay
     {
@@ -164,6 +236,76 @@ pub async fn delete_identity(state: State<'_, AppState>) -> Result<(), String> {
     Ok(())
 }
 
+/// Generate a QR code with our signed identity card for in-person exchange
+#[tauri::command]
+pub async fn generate_identity_qr(
+    size: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<IdentityQr, AppError> {
+    use base64::Engine;
+
+    let display_name = state.database.call(|db| db.get_profile().display_name).await;
+
+    let card = {
+        let identity = state.identity.lock().await;
+        let id = identity.get_identity().ok_or(AppError::NoIdentity)?;
+        IdentityCard::create(id, identity.cached_handle(), display_name, None, None)?
+    };
+
+    let payload = qr::encode_card(&card)?;
+    let size = size.unwrap_or(qr::DEFAULT_QR_SIZE).clamp(128, 2048);
+    let render_payload = payload.clone();
+    let png = tauri::async_runtime::spawn_blocking(move || qr::render_png(&render_payload, size))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    Ok(IdentityQr {
+        payload,
+        image: format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        ),
+    })
+}
+
+/// Validate a scanned identity QR code and open a thread with its owner
+#[tauri::command]
+pub async fn parse_identity_qr(
+    payload: String,
+    state: State<'_, AppState>,
+) -> Result<ScannedIdentity, AppError> {
+    let card = qr::decode_card(&payload)?;
+
+    let my_public_key = state.identity.lock().await.public_key_hex();
+    let is_self = my_public_key.as_deref() == Some(card.public_key.as_str());
+
+    let thread_id = match my_public_key {
+        Some(my_pk) if !is_self => {
+            let thread_id = crate::storage::direct_thread_id(&my_pk, &card.public_key);
+
+            let (id, pk, handle) = (thread_id.clone(), card.public_key.clone(), card.handle.clone());
+            state
+                .database
+                .call(move |db| db.get_or_create_thread(&id, &pk, handle.as_deref(), None))
+                .await?;
+            Some(thread_id)
+        }
+        _ => None,
+    };
+
+    tracing::info!("📷 Scanned identity {}...", &card.public_key[..16]);
+
+    Ok(ScannedIdentity {
+        public_key: card.public_key,
+        encryption_key: card.encryption_key,
+        handle: card.handle,
+        display_name: card.display_name,
+        issued_at: card.issued_at,
+        is_self,
+        thread_id,
+    })
+}
+
 /// Identity information (safe to expose)
 #[derive(serde::Serialize)]
 pub struct IdentityInfo {
@@ -171,13 +313,67 @@ pub struct IdentityInfo {
     pub encryption_key: String,
 }
 
-/// Identity backup (contains private key!)
+/// Identity backup; the key is inside `backup`, sealed with the passphrase
 #[derive(serde::Serialize)]
 pub struct IdentityBackup {
     pub version: u32,
-    pub private_key: String,
+    pub backup: EncryptedBackup,
     pub public_key: String,
     pub encryption_key: String,
     pub breadcrumb_count: u32,
     pub created_at: i64,
 }
+
+/// How a payload handed to `sign_payload` is encoded
+#[derive(Debug, Default, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadEncoding {
+    #[default]
+    Utf8,
+    Hex,
+    Base64,
+}
+
+impl PayloadEncoding {
+    fn decode(self, payload: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Utf8 => Ok(payload.as_bytes().to_vec()),
+            Self::Hex => hex::decode(payload).map_err(|e| format!("Invalid hex payload: {}", e)),
+            Self::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(payload)
+                .map_err(|e| format!("Invalid base64 payload: {}", e)),
+        }
+    }
+}
+
+/// Signature made by `sign_payload`
+#[derive(serde::Serialize)]
+pub struct SignedPayload {
+    /// Ed25519 public key the signature verifies against (hex)
+    pub public_key: String,
+    /// Ed25519 signature (hex)
+    pub signature: String,
+}
+
+/// Our identity as a QR code
+#[derive(serde::Serialize)]
+pub struct IdentityQr {
+    /// Text encoded in the code
+    pub payload: String,
+    /// PNG data URL
+    pub image: String,
+}
+
+/// A validated identity from a scanned QR code
+#[derive(serde::Serialize)]
+pub struct ScannedIdentity {
+    pub public_key: String,
+    pub encryption_key: String,
+    pub handle: Option<String>,
+    pub display_name: Option<String>,
+    pub issued_at: i64,
+    /// The code is our own
+    pub is_self: bool,
+    /// Direct thread with this identity, ready for messaging
+    pub thread_id: Option<String>,
+}
@@ -0,0 +1,43 @@
+//! Unified Inbox Command
+//!
+//! One call for the home screen. See `crate::inbox` for how items are
+//! ranked.
+
+use crate::error::AppError;
+use crate::inbox::{self, InboxItem, InboxPage};
+use crate::AppState;
+use tauri::State;
+
+/// Chat and email threads, pending contact requests and Dix mentions,
+/// ranked and paginated. Everything comes from the local database; Dix
+/// mentions are those the notification poller has found.
+#[tauri::command]
+pub async fn get_unified_inbox(
+    limit: Option<u32>,
+    offset: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<InboxPage, AppError> {
+    let limit = limit.unwrap_or(50);
+    let offset = offset.unwrap_or(0);
+    // Any source could fill the whole page
+    let wanted = offset + limit;
+
+    state
+        .database
+        .call(move |db| {
+            let threads = db.get_threads(false, Some(false), wanted)?;
+            let requests = db.list_contact_requests()?;
+            let mentions = db.list_dix_mentions(wanted)?;
+            let truncated = threads.len() as u32 >= wanted || mentions.len() as u32 >= wanted;
+
+            let mut items: Vec<InboxItem> = threads.into_iter().map(InboxItem::from).collect();
+            items.extend(requests.into_iter().map(InboxItem::from));
+            items.extend(mentions.into_iter().filter_map(|mention| {
+                let post = mention.post_id.as_deref().and_then(|id| db.get_cached_dix_post(id));
+                InboxItem::from_mention(mention, post.as_ref())
+            }));
+            Ok::<_, crate::storage::DatabaseError>(inbox::page(items, offset as usize, limit as usize, truncated))
+        })
+        .await
+        .map_err(AppError::from)
+}
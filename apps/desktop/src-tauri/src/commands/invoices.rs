@@ -0,0 +1,184 @@
+//! Invoice Commands
+//!
+//! Sending payment requests to a thread and paying the ones we receive.
+//! See `crate::invoices` for the payloads and how payment is checked.
+
+use crate::commands::messaging::send_message;
+use crate::error::AppError;
+use crate::invoices::{
+    self, Invoice, InvoicePaid, InvoiceRecord, InvoiceStatus, INVOICE_PAID_PAYLOAD_TYPE, INVOICE_PAYLOAD_TYPE,
+};
+use crate::stellar::StellarService;
+use crate::AppState;
+use serde::Deserialize;
+use tauri::{AppHandle, Runtime, State};
+
+/// An invoice as the user fills it in
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvoiceDraft {
+    pub amount: f64,
+    /// Defaults to GNS
+    pub asset: Option<String>,
+    pub memo: Option<String>,
+    /// When it stops being payable (ms)
+    pub expires_at: i64,
+}
+
+/// Ask a thread to pay us
+#[tauri::command]
+pub async fn send_invoice<R: Runtime>(
+    invoice: InvoiceDraft,
+    recipient_handle: Option<String>,
+    recipient_public_key: Option<String>,
+    thread_id: Option<String>,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<InvoiceRecord, AppError> {
+    let issuer = state
+        .identity
+        .lock()
+        .await
+        .public_key_hex()
+        .ok_or(AppError::NoIdentity)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    if invoice.expires_at <= now {
+        return Err("Invoice would already be expired".into());
+    }
+    if invoice.expires_at - now > invoices::MAX_INVOICE_LIFETIME_MS {
+        return Err("Invoices can stay open for at most 90 days".into());
+    }
+    let invoice = Invoice {
+        id: invoices::new_invoice_id(),
+        issuer,
+        amount: invoice.amount,
+        asset: invoice.asset.unwrap_or_else(|| "GNS".to_string()).to_uppercase(),
+        memo: invoice.memo.map(|m| m.trim().to_string()).filter(|m| !m.is_empty()),
+        expires_at: invoice.expires_at,
+    };
+    invoices::check_invoice(&invoice)?;
+
+    let payload = serde_json::to_value(&invoice)?;
+    let sent = send_message(
+        recipient_handle,
+        recipient_public_key,
+        INVOICE_PAYLOAD_TYPE.to_string(),
+        payload,
+        thread_id,
+        None,
+        app_handle,
+        state.clone(),
+    )
+    .await?;
+
+    let id = invoice.id.clone();
+    state
+        .database
+        .call(move |db| {
+            db.save_invoice(&invoice, sent.thread_id.as_deref(), true)?;
+            Ok::<_, crate::storage::DatabaseError>(db.get_invoice(&id))
+        })
+        .await?
+        .ok_or_else(|| "Invoice was not saved".into())
+}
+
+/// Pay a received invoice and tell the issuer in its thread
+///
+/// Like `send_gns`, the payment must first be reviewed with
+/// `preview_transaction` for the invoice's issuer and amount; its token
+/// is `preview_token`.
+#[tauri::command]
+pub async fn pay_invoice<R: Runtime>(
+    invoice_id: String,
+    preview_token: String,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<InvoiceRecord, AppError> {
+    let key = invoice_id.clone();
+    let record = state
+        .database
+        .call(move |db| db.get_invoice(&key))
+        .await
+        .ok_or_else(|| AppError::not_found("Invoice"))?;
+    if record.outgoing {
+        return Err("That's your own invoice".into());
+    }
+    match record.status {
+        InvoiceStatus::Paid => return Err("Invoice is already paid".into()),
+        InvoiceStatus::Expired => return Err("Invoice has expired".into()),
+        InvoiceStatus::Pending => {}
+    }
+    let invoice = record.invoice;
+    let pay_to = StellarService::gns_key_to_stellar(&invoice.issuer)?;
+
+    let result = {
+        let identity = state.identity.lock().await;
+        let sender_pk = identity.public_key().ok_or(AppError::NoIdentity)?;
+        let sender_private_key = identity.private_key_bytes().ok_or("No private key available")?;
+
+        let stellar = {
+            let mut stellar = state.stellar.lock().await;
+            stellar.previews().confirm(&preview_token, &pay_to, invoice.amount)?;
+            stellar.detached()
+        };
+        stellar
+            .send_gns(
+                &sender_pk,
+                sender_private_key.expose_secret(),
+                None,
+                None,
+                &invoice.issuer,
+                invoice.amount,
+                Some(&invoices::invoice_memo(&invoice.id)),
+            )
+            .await?
+    };
+    if !result.success {
+        return Err(AppError::Payment {
+            reason: result.error.unwrap_or_else(|| "Payment failed".to_string()),
+        });
+    }
+    let tx_hash = result.hash.ok_or("Payment went through without a transaction hash")?;
+
+    let (id, hash) = (invoice.id.clone(), tx_hash.clone());
+    state
+        .database
+        .call(move |db| db.mark_invoice_paid(&id, &hash, chrono::Utc::now().timestamp_millis()))
+        .await?;
+
+    // The payment went through, so a lost notice isn't worth failing over
+    let notice = InvoicePaid {
+        invoice_id: invoice.id.clone(),
+        tx_hash,
+    };
+    let payload = serde_json::to_value(&notice)?;
+    if let Err(e) = send_message(
+        None,
+        Some(invoice.issuer.clone()),
+        INVOICE_PAID_PAYLOAD_TYPE.to_string(),
+        payload,
+        record.thread_id,
+        None,
+        app_handle,
+        state.clone(),
+    )
+    .await
+    {
+        tracing::warn!("Paid invoice {} but couldn't notify the issuer: {}", invoice.id, e);
+    }
+
+    state
+        .database
+        .call(move |db| db.get_invoice(&invoice_id))
+        .await
+        .ok_or_else(|| AppError::not_found("Invoice"))
+}
+
+/// Invoices in a thread, or all of them, newest first
+#[tauri::command]
+pub async fn get_invoices(thread_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<InvoiceRecord>, AppError> {
+    state
+        .database
+        .call(move |db| db.list_invoices(thread_id.as_deref()))
+        .await
+        .map_err(AppError::from)
+}
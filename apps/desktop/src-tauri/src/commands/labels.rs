@@ -0,0 +1,117 @@
+//! Label Commands
+//!
+//! User-defined labels (Work, Family, Receipts, ...) for threads, which the
+//! email-style UI shows as folders. Labels are local only; a thread can
+//! carry any number of them.
+
+use crate::commands::messaging::ThreadPreview;
+use crate::error::AppError;
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+/// Longest label name, in characters
+const MAX_LABEL_NAME: usize = 40;
+
+/// A label and how many threads carry it
+#[derive(Debug, Clone, Serialize)]
+pub struct Label {
+    pub id: String,
+    pub name: String,
+    /// `#rrggbb`, if the user picked one
+    pub color: Option<String>,
+    pub created_at: i64,
+    pub thread_count: u32,
+}
+
+/// Create a label
+#[tauri::command]
+pub async fn create_label(
+    name: String,
+    color: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Label, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_LABEL_NAME {
+        return Err(format!("Label names must be 1-{} characters", MAX_LABEL_NAME).into());
+    }
+    if let Some(color) = &color {
+        let valid = color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err("Label colors must be #rrggbb".into());
+        }
+    }
+
+    let label = Label {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        color: color.map(|c| c.to_lowercase()),
+        created_at: chrono::Utc::now().timestamp_millis(),
+        thread_count: 0,
+    };
+    state
+        .database
+        .call(move |db| {
+            if db.label_name_taken(&label.name) {
+                return Err(format!("A label named \"{}\" already exists", label.name).into());
+            }
+            db.create_label(&label)?;
+            Ok(label)
+        })
+        .await
+}
+
+/// All labels, by name
+#[tauri::command]
+pub async fn list_labels(state: State<'_, AppState>) -> Result<Vec<Label>, AppError> {
+    state.database.call(|db| db.list_labels()).await.map_err(AppError::from)
+}
+
+/// Delete a label, taking it off every thread
+#[tauri::command]
+pub async fn delete_label(label_id: String, state: State<'_, AppState>) -> Result<bool, AppError> {
+    state
+        .database
+        .call(move |db| db.delete_label(&label_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Put a label on a thread (or take it off with `assigned: false`).
+/// Returns the ids of the thread's labels afterwards.
+#[tauri::command]
+pub async fn assign_label(
+    thread_id: String,
+    label_id: String,
+    assigned: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    state
+        .database
+        .call(move |db| {
+            if db.get_label(&label_id).is_none() {
+                return Err("Label not found".into());
+            }
+            if db.get_thread(&thread_id)?.is_none() {
+                return Err("Thread not found".into());
+            }
+            db.set_thread_label(&thread_id, &label_id, assigned.unwrap_or(true))?;
+            Ok(db.get_thread_labels(&thread_id))
+        })
+        .await
+}
+
+/// Threads carrying a label, by last activity
+#[tauri::command]
+pub async fn get_threads_by_label(
+    label_id: String,
+    include_archived: Option<bool>,
+    limit: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ThreadPreview>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_threads_by_label(&label_id, include_archived.unwrap_or(false), limit.unwrap_or(50)))
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,31 @@
+//! LAN Delivery Commands
+//!
+//! Opting in to direct delivery on the local network, and the devices seen.
+
+use crate::error::AppError;
+use crate::lan::LanPeer;
+use crate::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_lan_delivery(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.lan.is_enabled())
+}
+
+/// Turn direct LAN delivery on or off. Takes effect immediately.
+#[tauri::command]
+pub async fn set_lan_delivery(enabled: bool, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.set_lan_delivery(enabled))
+        .await?;
+    state.lan.set_enabled(enabled);
+    tracing::info!("LAN delivery {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+/// GNS devices currently seen on the local network
+#[tauri::command]
+pub async fn list_lan_peers(state: State<'_, AppState>) -> Result<Vec<LanPeer>, AppError> {
+    Ok(state.lan.list_peers(chrono::Utc::now().timestamp_millis()))
+}
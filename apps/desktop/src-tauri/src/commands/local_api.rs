@@ -0,0 +1,35 @@
+//! Local API Commands
+//!
+//! Opting in to the JSON-RPC server for local tools, and the URL and
+//! session token to give them.
+
+use crate::error::AppError;
+use crate::local_api::LocalApiStatus;
+use crate::AppState;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub async fn get_local_api(state: State<'_, AppState>) -> Result<LocalApiStatus, AppError> {
+    Ok(state.local_api.status())
+}
+
+/// Start or stop the server. Takes effect immediately; starting issues a
+/// new token.
+#[tauri::command]
+pub async fn set_local_api(
+    enabled: bool,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<LocalApiStatus, AppError> {
+    if enabled {
+        state.local_api.clone().start(app_handle).await?;
+    } else {
+        state.local_api.disable();
+    }
+    state
+        .database
+        .call(move |db| db.set_local_api(enabled))
+        .await?;
+    tracing::info!("Local API {}", if enabled { "enabled" } else { "disabled" });
+    Ok(state.local_api.status())
+}
@@ -0,0 +1,218 @@
+//! Location Sharing Commands
+//!
+//! Sending the current or a pinned H3 cell to a thread, keeping live
+//! shares moving, and the live shares we can see. See
+//! `crate::location::share` for how cells are quantized.
+
+use crate::commands::messaging::send_message;
+use crate::error::AppError;
+use crate::location::share::{self, LocationShare, SharedLocation, LOCATION_PAYLOAD_TYPE, MAX_LIVE_MINUTES};
+use crate::resolver;
+use crate::AppState;
+use gns_crypto_core::breadcrumb::DEFAULT_H3_RESOLUTION;
+use gns_crypto_core::create_envelope_with_metadata;
+use serde::Deserialize;
+use tauri::{AppHandle, Runtime, State};
+
+/// What to share, as the user picks it
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocationDraft {
+    /// The current position; ignored when a cell is pinned
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// A cell picked on the map instead of the current position
+    pub h3_cell: Option<String>,
+    pub label: Option<String>,
+    /// Keep the share moving for this long; current positions only
+    pub live_minutes: Option<u32>,
+    /// Coarser than the breadcrumb resolution, if wanted
+    pub resolution: Option<u8>,
+}
+
+/// Share a location in a thread
+///
+/// The current position is refused inside a privacy zone, like a
+/// breadcrumb would be.
+#[tauri::command]
+pub async fn share_location<R: Runtime>(
+    location: LocationDraft,
+    recipient_handle: Option<String>,
+    recipient_public_key: Option<String>,
+    thread_id: Option<String>,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<SharedLocation, AppError> {
+    let resolution = location.resolution.unwrap_or(DEFAULT_H3_RESOLUTION).min(DEFAULT_H3_RESOLUTION);
+    let h3_cell = match (&location.h3_cell, location.latitude, location.longitude) {
+        (Some(_), _, _) if location.live_minutes.is_some() => {
+            return Err("Pinned locations can't be live".into());
+        }
+        (Some(cell), _, _) => share::quantize_cell(cell, resolution)?,
+        (None, Some(latitude), Some(longitude)) => {
+            let zones = state.database.call(|db| db.get_privacy_zones()).await;
+            if let Some(zone) = share::privacy_zone_at(&zones, latitude, longitude) {
+                return Err(format!("You're inside the privacy zone \"{}\"", zone.name).into());
+            }
+            share::quantize(latitude, longitude, resolution)?
+        }
+        _ => return Err("Give a position or a cell to share".into()),
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    let live_until = match location.live_minutes {
+        Some(minutes) if minutes == 0 || minutes > MAX_LIVE_MINUTES => {
+            return Err(format!("Live shares last 1-{} minutes", MAX_LIVE_MINUTES).into());
+        }
+        Some(minutes) => Some(now + i64::from(minutes) * 60_000),
+        None => None,
+    };
+
+    // The stored share names the peer by key, whichever way it was given
+    let peer_public_key = match (recipient_public_key, &recipient_handle) {
+        (Some(pk), _) => pk,
+        (None, Some(handle)) => {
+            resolver::resolve_handle(&state.api, &state.database, handle)
+                .await
+                .map_err(|e| format!("Failed to resolve handle: {}", e))?
+                .ok_or_else(|| AppError::not_found("Handle"))?
+                .info
+                .public_key
+        }
+        (None, None) => return Err("Must provide either recipient_handle or recipient_public_key".into()),
+    };
+
+    let share = LocationShare {
+        share_id: uuid::Uuid::new_v4().to_string(),
+        h3_cell,
+        resolution,
+        live_until,
+        updated_at: now,
+        label: location.label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty()),
+    };
+    share::check_share(&share)?;
+
+    let payload = serde_json::to_value(&share)?;
+    let sent = send_message(
+        None,
+        Some(peer_public_key.clone()),
+        LOCATION_PAYLOAD_TYPE.to_string(),
+        payload,
+        thread_id,
+        None,
+        app_handle,
+        state.clone(),
+    )
+    .await?;
+
+    let shared = SharedLocation::new(share, sent.thread_id, peer_public_key, true);
+    let saved = shared.clone();
+    state
+        .database
+        .call(move |db| db.save_location_share(&saved))
+        .await?;
+    Ok(shared)
+}
+
+/// Move our live shares to the current position
+///
+/// Called by the UI whenever it gets a fix. Shares only get a message
+/// when the cell changes, and none while inside a privacy zone. Returns
+/// the shares that moved.
+#[tauri::command]
+pub async fn update_live_location(
+    latitude: f64,
+    longitude: f64,
+    state: State<'_, AppState>,
+) -> Result<Vec<SharedLocation>, AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let (live, zones) = state
+        .database
+        .call(move |db| db.live_location_shares(now, Some(true)).map(|live| (live, db.get_privacy_zones())))
+        .await?;
+    if live.is_empty() || share::privacy_zone_at(&zones, latitude, longitude).is_some() {
+        return Ok(Vec::new());
+    }
+
+    let mut moved = Vec::new();
+    for mut shared in live {
+        let h3_cell = share::quantize(latitude, longitude, shared.share.resolution)?;
+        if h3_cell == shared.share.h3_cell {
+            continue;
+        }
+        shared.share.h3_cell = h3_cell;
+        shared.share.updated_at = now;
+        match send_update(&state, shared).await {
+            Ok(shared) => moved.push(shared),
+            Err(e) => tracing::warn!("Failed to update location share: {}", e),
+        }
+    }
+    Ok(moved)
+}
+
+/// End one of our live shares now
+#[tauri::command]
+pub async fn stop_location_share(share_id: String, state: State<'_, AppState>) -> Result<SharedLocation, AppError> {
+    let mut shared = state
+        .database
+        .call(move |db| db.get_location_share(&share_id))
+        .await
+        .filter(|shared| shared.outgoing)
+        .ok_or_else(|| AppError::not_found("Location share"))?;
+    let now = chrono::Utc::now().timestamp_millis();
+    if !shared.is_live(now) {
+        return Ok(shared);
+    }
+    shared.share.live_until = Some(now);
+    shared.share.updated_at = now;
+    send_update(&state, shared).await
+}
+
+/// Live shares, ours and those sent to us
+#[tauri::command]
+pub async fn get_live_locations(state: State<'_, AppState>) -> Result<Vec<SharedLocation>, AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    state
+        .database
+        .call(move |db| db.live_location_shares(now, None))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Send a moved or ended share straight to the peer; updates aren't
+/// stored as thread messages
+async fn send_update(state: &State<'_, AppState>, shared: SharedLocation) -> Result<SharedLocation, AppError> {
+    let peer = resolver::resolve_identity(&state.api, &state.database, &shared.peer_public_key)
+        .await
+        .map_err(|e| format!("Failed to get identity: {}", e))?
+        .ok_or_else(|| AppError::not_found("Identity"))?;
+    let payload_bytes = serde_json::to_vec(&shared.share)?;
+
+    {
+        let identity_mgr = state.identity.lock().await;
+        let identity = identity_mgr.get_identity().ok_or(AppError::NoIdentity)?;
+        let envelope = create_envelope_with_metadata(
+            identity,
+            identity_mgr.cached_handle().as_deref(),
+            &shared.peer_public_key,
+            &peer.info.encryption_key,
+            LOCATION_PAYLOAD_TYPE,
+            &payload_bytes,
+            shared.thread_id.as_deref(),
+            None,
+        )
+        .map_err(|e| format!("Failed to create envelope: {}", e))?;
+
+        let relay = state.relay.lock().await;
+        relay
+            .send_envelope(&envelope)
+            .await
+            .map_err(|e| format!("Failed to send: {}", e))?;
+    }
+
+    let saved = SharedLocation::new(shared.share, shared.thread_id, shared.peer_public_key, true);
+    let stored = saved.clone();
+    state
+        .database
+        .call(move |db| db.save_location_share(&stored))
+        .await?;
+    Ok(saved)
+}
@@ -0,0 +1,51 @@
+//! Database Maintenance Commands
+//!
+//! Size reporting, on-demand maintenance and message retention for the
+//! local database. Maintenance and pruning also run automatically.
+
+use crate::error::AppError;
+use crate::storage::{DbStats, MaintenanceReport, PruneReport, RetentionPolicy};
+use crate::AppState;
+use tauri::State;
+
+/// Database file size, free space and per-table row counts
+#[tauri::command]
+pub async fn get_db_stats(state: State<'_, AppState>) -> Result<DbStats, AppError> {
+    state
+        .database
+        .call(|db| db.get_db_stats())
+        .await
+        .map_err(AppError::from)
+}
+
+/// Run an integrity check, repair missing indexes and VACUUM
+#[tauri::command]
+pub async fn run_db_maintenance(state: State<'_, AppState>) -> Result<MaintenanceReport, AppError> {
+    state
+        .database
+        .call(|db| db.run_maintenance())
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_retention_policy(state: State<'_, AppState>) -> Result<RetentionPolicy, AppError> {
+    Ok(state.database.call(|db| db.get_retention_policy()).await)
+}
+
+/// Save the retention policy and prune right away
+#[tauri::command]
+pub async fn set_retention_policy(
+    state: State<'_, AppState>,
+    policy: RetentionPolicy,
+) -> Result<PruneReport, AppError> {
+    policy.validate()?;
+    state
+        .database
+        .call(move |db| {
+            db.set_retention_policy(policy)?;
+            db.prune_messages(policy, chrono::Utc::now().timestamp_millis())
+        })
+        .await
+        .map_err(AppError::from)
+}
@@ -2,129 +2,323 @@
 //!
 //! Commands for sending and receiving encrypted messages.
 
+use crate::capabilities::{self, Capabilities};
+use crate::dix::share::{self, QuoteConsent, QUOTE_CONSENT_FIELD};
 use crate::AppState;
 // TODO: Add envelope function when implemented
 // use gns_crypto_core::GnsIdentity;
-use tauri::State;
-use gns_crypto_core::create_envelope_with_metadata;
-use sha2::Digest;
+use tauri::{AppHandle, Runtime, State};
+use gns_crypto_core::{
+    create_envelope_with_metadata, create_envelope_with_options, create_sealed_envelope, EnvelopeOptions,
+};
+use crate::error::AppError;
+use crate::network::ack::{AckStatus, RELAY_ACK_TIMEOUT};
+use crate::network::frame::RelayFrame;
+use crate::network::link_preview::{self, LINK_PREVIEW_FIELD};
+use crate::payloads;
+use crate::quotes::{self, QuoteStatus, QUOTE_DIGEST_FIELD};
+use crate::rate_limit::SenderLimit;
+use crate::resolver;
+use crate::spam::SenderVerdict;
+use crate::validation::{validate_payload, PayloadKind};
 
 /// Send an encrypted message
 #[tauri::command]
-pub async fn send_message(
+#[allow(clippy::too_many_arguments)]
+pub async fn send_message<R: Runtime>(
     recipient_handle: Option<String>,
     recipient_public_key: Option<String>,
     payload_type: String,
-    payload: serde_json::Value,
+    mut payload: serde_json::Value,
     thread_id: Option<String>,
     reply_to_id: Option<String>,
+    app_handle: AppHandle<R>,
     state: State<'_, AppState>,
-) -> Result<SendResult, String> {
+) -> Result<SendResult, AppError> {
+    // Replies commit to the exact message they quote
+    if let Some(reply_to) = reply_to_id.clone() {
+        let original = state
+            .database
+            .call(move |db| db.get_message(&reply_to))
+            .await?;
+        if let (Some(original), Some(fields)) = (original, payload.as_object_mut()) {
+            fields.insert(QUOTE_DIGEST_FIELD.to_string(), quotes::digest_of(&original).into());
+        }
+    }
+
+    // Consent to be quoted on Dix is signed over the text it covers
+    if payload.get(QUOTE_CONSENT_FIELD) == Some(&serde_json::Value::Bool(true)) {
+        let text = payload
+            .get("text")
+            .and_then(|t| t.as_str())
+            .ok_or("Only text can be quoted on Dix")?
+            .to_string();
+        let identity = state.identity.lock().await;
+        let author = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+        let signed_at = chrono::Utc::now().timestamp_millis();
+        let signature = identity
+            .sign_string(&share::quote_signing_message(&author, &text, signed_at))
+            .ok_or("Failed to sign quote consent")?;
+        payload[QUOTE_CONSENT_FIELD] = serde_json::to_value(QuoteConsent { signed_at, signature })?;
+    }
+
+    // Previews are fetched by the sender only, and only when enabled
+    let preview_url = payload
+        .get("text")
+        .and_then(|t| t.as_str())
+        .filter(|_| PayloadKind::from_type(&payload_type) == Some(PayloadKind::Chat))
+        .filter(|_| payload.get(LINK_PREVIEW_FIELD).is_none())
+        .and_then(link_preview::first_url);
+    if let Some(url) = preview_url {
+        if state.database.call(|db| db.get_link_previews()).await {
+            match link_preview::fetch_preview(&url).await {
+                Ok(Some(preview)) => {
+                    if let (Ok(preview), Some(fields)) = (serde_json::to_value(preview), payload.as_object_mut()) {
+                        fields.insert(LINK_PREVIEW_FIELD.to_string(), preview);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::debug!("No link preview: {}", e),
+            }
+        }
+    }
+
+    // Serialize and check the payload before any network work
+    let payload_bytes =
+        serde_json::to_vec(&payload).map_err(|e| format!("Failed to serialize payload: {}", e))?;
+    payloads::registry().decode(&payload_type, &payload_bytes)?;
+
+    // Threads opted into sealed sender hide who we are from the relay
+    let sealed_thread = thread_id.clone();
+    let (sealed, compression_threshold) = state
+        .database
+        .call(move |db| {
+            let sealed = sealed_thread.is_some_and(|tid| db.is_sealed_sender_thread(&tid));
+            (sealed, db.get_compression_threshold())
+        })
+        .await;
+
     // Get our identity
     let identity_mgr = state.identity.lock().await;
     let identity = identity_mgr
         .get_identity()
-        .ok_or("No identity configured")?;
+        .ok_or(AppError::NoIdentity)?;
 
     let my_handle = identity_mgr.cached_handle();
 
     // Resolve recipient
-    let (recipient_pk, recipient_enc_key) = if let Some(handle) = &recipient_handle {
+    let (recipient_pk, recipient_enc_key, recipient_pq_key, recipient_modules) = if let Some(handle) = &recipient_handle {
         // Resolve handle to keys
-        let info = state
-            .api
-            .resolve_handle(handle)
+        let info = resolver::resolve_handle(&state.api, &state.database, handle)
             .await
             .map_err(|e| format!("Failed to resolve handle: {}", e))?
-            .ok_or("Handle not found")?;
+            .ok_or_else(|| AppError::not_found("Handle"))?
+            .info;
 
-        (info.public_key, info.encryption_key)
+        (info.public_key, info.encryption_key, info.pq_encryption_key, info.modules)
     } else if let Some(pk) = recipient_public_key {
         // Fetch encryption key for public key
-        let info = state
-            .api
-            .get_identity(&pk)
+        let info = resolver::resolve_identity(&state.api, &state.database, &pk)
             .await
             .map_err(|e| format!("Failed to get identity: {}", e))?
-            .ok_or("Identity not found")?;
+            .ok_or_else(|| AppError::not_found("Identity"))?
+            .info;
 
-        (pk, info.encryption_key)
+        (pk, info.encryption_key, info.pq_encryption_key, info.modules)
     } else {
-        return Err("Must provide either recipient_handle or recipient_public_key".to_string());
+        return Err("Must provide either recipient_handle or recipient_public_key".into());
     };
 
-    // Serialize payload
-    let payload_bytes =
-        serde_json::to_vec(&payload).map_err(|e| format!("Failed to serialize payload: {}", e))?;
-
-    // Create envelope
-    let envelope = create_envelope_with_metadata(
-        &identity,
-        my_handle.as_deref(),
-        &recipient_pk,
-        &recipient_enc_key,
-        &payload_type,
-        &payload_bytes,
-        thread_id.as_deref(),
-        reply_to_id.as_deref(),
-    )
-    .map_err(|e| format!("Failed to create envelope: {}", e))?;
+    // Only use features the recipient's record says it understands
+    let recipient_caps = Capabilities::from_ids(&recipient_modules);
+    let sealed = if sealed && !recipient_caps.supports(capabilities::SEALED_SENDER) {
+        tracing::warn!("Recipient does not support sealed sender; sending a regular envelope");
+        false
+    } else {
+        sealed
+    };
+    let compression_threshold = compression_threshold.filter(|_| recipient_caps.supports(capabilities::COMPRESSION));
+
+    // Create envelope; `envelope` is what we store, `wire` what the relay sees
+    let (envelope, wire) = if sealed {
+        let wire = create_sealed_envelope(
+            identity,
+            my_handle.as_deref(),
+            &recipient_pk,
+            &recipient_enc_key,
+            &payload_type,
+            &payload_bytes,
+            thread_id.as_deref(),
+            reply_to_id.as_deref(),
+        )
+        .map_err(|e| format!("Failed to create envelope: {}", e))?;
+
+        let mut envelope = wire.clone();
+        envelope.from_public_key = identity.public_key_hex();
+        envelope.from_handle = my_handle.clone();
+        envelope.payload_type = payload_type.clone();
+        envelope.thread_id = thread_id.clone();
+        envelope.reply_to_id = reply_to_id.clone();
+        (envelope, wire)
+    } else {
+        // Hybrid when the recipient published a PQ key, classic otherwise
+        let envelope = create_envelope_with_options(
+            identity,
+            &recipient_pk,
+            &recipient_enc_key,
+            &payload_type,
+            &payload_bytes,
+            &EnvelopeOptions {
+                sender_handle: my_handle.as_deref(),
+                thread_id: thread_id.as_deref(),
+                reply_to_id: reply_to_id.as_deref(),
+                recipient_pq_key_hex: recipient_pq_key.as_deref(),
+                compression_threshold,
+            },
+        )
+        .map_err(|e| format!("Failed to create envelope: {}", e))?;
+        (envelope.clone(), envelope)
+    };
 
-    // Send via relay
+    // Straight to the recipient's device when it's on our network,
+    // otherwise via relay
+    let direct = state.lan.deliver(&recipient_pk, &wire).await;
     let relay = state.relay.lock().await;
-    relay
-        .send_envelope(&envelope)
-        .await
-        .map_err(|e| format!("Failed to send: {}", e))?;
+    let ack = match direct {
+        Some(_) => None,
+        None => Some(
+            relay
+                .send_envelope(&wire)
+                .await
+                .map_err(|e| format!("Failed to send: {}", e))?,
+        ),
+    };
 
     // Phase 1.5: Sync to connected Browsers (Real-time)
     // We must tell our other devices (browsers) that we sent this message,
     // otherwise they will see an encrypted envelope from the server and have no way to decrypt it.
     let text_content = payload.get("text").and_then(|t| t.as_str()).unwrap_or("");
     if !text_content.is_empty() {
-        let sync_event = serde_json::json!({
-            "type": "message_synced",
-            "to": [identity.public_key_hex()],
-            "messageId": envelope.id,
-            "conversationWith": recipient_pk,
-            "decryptedText": text_content,
-            "direction": "outgoing",
-            "timestamp": envelope.timestamp,
-        });
+        let sync_event = RelayFrame::MessageSynced {
+            to: vec![identity.public_key_hex()],
+            message_id: envelope.id.clone(),
+            conversation_with: recipient_pk.clone(),
+            decrypted_text: text_content.to_string(),
+            direction: "outgoing".to_string(),
+            timestamp: Some(envelope.timestamp),
+            from_handle: None,
+            payload: None,
+        };
         
-        if let Err(e) = relay.send_raw(&sync_event.to_string()).await {
+        if let Err(e) = relay.send_frame(&sync_event).await {
              // Non-fatal, just log
-             println!("Failed to sync sent message to browser: {}", e);
+             tracing::warn!("Failed to sync sent message to browser: {}", e);
         }
     }
 
     // Store locally
-    let mut db = state.database.lock().await;
     // Sanitize handle (remove leading @ if present) to avoid duplication
-    let clean_handle = recipient_handle.as_deref().map(|h| h.trim_start_matches('@'));
-    
-    db.save_sent_message(&envelope, &payload_bytes, clean_handle, reply_to_id)
+    let clean_handle = recipient_handle.map(|h| h.trim_start_matches('@').to_string());
+    let saved = envelope.clone();
+    state
+        .database
+        .call(move |db| db.save_sent_message(&saved, &payload_bytes, clean_handle.as_deref(), reply_to_id))
+        .await
         .map_err(|e| format!("Failed to save locally: {}", e))?;
 
+    // Saved as `sent`; the ack moves it on, or marks it failed
+    let database = state.database.clone();
+    let id = envelope.id.clone();
+    tauri::async_runtime::spawn(async move {
+        let status = match (direct, ack) {
+            (Some(status), _) => status,
+            (None, Some(ack)) => ack.wait(RELAY_ACK_TIMEOUT).await.unwrap_or_else(|| {
+                tracing::warn!("No relay ack for message {}", id);
+                AckStatus::Rejected { reason: "No acknowledgement from relay".to_string() }
+            }),
+            (None, None) => return,
+        };
+        crate::message_handler::apply_ack(&app_handle, &database, &id, &status).await;
+    });
+
     Ok(SendResult {
         message_id: envelope.id.clone(),
         thread_id: envelope.thread_id.clone(),
     })
 }
 
-/// Get all conversation threads
+/// Send a plain text message to a handle, for the quick-compose window
+#[tauri::command]
+pub async fn quick_send<R: Runtime>(
+    recipient_handle: String,
+    text: String,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<SendResult, AppError> {
+    let handle = recipient_handle.trim().trim_start_matches('@').to_lowercase();
+    if handle.is_empty() {
+        return Err("Recipient handle is required".into());
+    }
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Message is empty".into());
+    }
+
+    send_message(
+        Some(handle),
+        None,
+        "text/plain".to_string(),
+        serde_json::json!({ "text": text }),
+        None,
+        None,
+        app_handle,
+        state,
+    )
+    .await
+}
+
+/// Senders currently muted or throttled by the incoming rate limit
+#[tauri::command]
+pub async fn get_rate_limit_state(state: State<'_, AppState>) -> Result<Vec<SenderLimit>, AppError> {
+    Ok(state.rate_limiter.state(chrono::Utc::now().timestamp_millis()))
+}
+
+/// Get conversation threads; `junk` lists the requests / junk folder
+/// instead of the inbox
 #[tauri::command]
 pub async fn get_threads(
     include_archived: Option<bool>,
     limit: Option<u32>,
+    junk: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<Vec<ThreadPreview>, String> {
-    let db = state.database.lock().await;
-    let threads = db
-        .get_threads(include_archived.unwrap_or(false), limit.unwrap_or(50))
-        .map_err(|e| e.to_string())?;
+) -> Result<Vec<ThreadPreview>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_threads(include_archived.unwrap_or(false), Some(junk.unwrap_or(false)), limit.unwrap_or(50)))
+        .await
+        .map_err(AppError::from)
+}
 
-    Ok(threads)
+/// Move a thread's sender out of junk, and keep their future threads out
+#[tauri::command]
+pub async fn accept_request(thread_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    judge_thread(&state, thread_id, SenderVerdict::Accepted).await
+}
+
+/// Move a thread's sender into junk, along with their future threads
+#[tauri::command]
+pub async fn mark_spam(thread_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    judge_thread(&state, thread_id, SenderVerdict::Spam).await
+}
+
+async fn judge_thread(state: &AppState, thread_id: String, verdict: SenderVerdict) -> Result<(), AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let public_key = state
+        .database
+        .call(move |db| db.judge_thread(&thread_id, verdict, now))
+        .await?;
+    tracing::info!("Marked {} as {}", &public_key[..16.min(public_key.len())], verdict.as_str());
+    Ok(())
 }
 
 /// Get a single thread
@@ -132,9 +326,12 @@ pub async fn get_threads(
 pub async fn get_thread(
     thread_id: String,
     state: State<'_, AppState>,
-) -> Result<Option<ThreadPreview>, String> {
-    let db = state.database.lock().await;
-    db.get_thread(&thread_id).map_err(|e| e.to_string())
+) -> Result<Option<ThreadPreview>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_thread(&thread_id))
+        .await
+        .map_err(AppError::from)
 }
 
 /// Get messages in a thread
@@ -144,34 +341,138 @@ pub async fn get_messages(
     limit: Option<u32>,
     _before_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<Message>, String> {
-    let db = state.database.lock().await;
-    let messages = db
-        .get_messages(&thread_id, limit.unwrap_or(50))
-        .map_err(|e| e.to_string())?;
-
-    Ok(messages)
+) -> Result<Vec<Message>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_messages(&thread_id, limit.unwrap_or(50)))
+        .await
+        .map_err(AppError::from)
 }
 
 /// Mark a thread as read
 #[tauri::command]
-pub async fn mark_thread_read(thread_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let mut db = state.database.lock().await;
-    db.mark_thread_read(&thread_id).map_err(|e| e.to_string())
+pub async fn mark_thread_read(thread_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.mark_thread_read(&thread_id))
+        .await
+        .map_err(AppError::from)
 }
 
 /// Delete a thread
 #[tauri::command]
-pub async fn delete_thread(thread_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let mut db = state.database.lock().await;
-    db.delete_thread(&thread_id).map_err(|e| e.to_string())
+pub async fn delete_thread(thread_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.delete_thread(&thread_id))
+        .await
+        .map_err(AppError::from)
 }
 
 /// Delete a message
 #[tauri::command]
-pub async fn delete_message(message_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let mut db = state.database.lock().await;
-    db.delete_message(&message_id).map_err(|e| e.to_string())
+pub async fn delete_message(message_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.delete_message(&message_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Star a message for quick retrieval; starred messages are also kept
+/// by message retention
+#[tauri::command]
+pub async fn star_message(message_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    set_starred(&state, message_id, true).await
+}
+
+/// Remove a message's star
+#[tauri::command]
+pub async fn unstar_message(message_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    set_starred(&state, message_id, false).await
+}
+
+async fn set_starred(state: &AppState, message_id: String, starred: bool) -> Result<(), AppError> {
+    let found = state
+        .database
+        .call(move |db| db.set_message_starred(&message_id, starred))
+        .await?;
+    if found {
+        Ok(())
+    } else {
+        Err("Message not found".into())
+    }
+}
+
+/// Starred messages across all threads, newest first. Pass the oldest
+/// timestamp seen as `before` to page.
+#[tauri::command]
+pub async fn get_starred_messages(
+    limit: Option<u32>,
+    before: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Message>, AppError> {
+    state
+        .database
+        .call(move |db| db.get_starred_messages(limit.unwrap_or(50), before))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Is sealed sender enabled for a thread?
+#[tauri::command]
+pub async fn get_thread_sealed_sender(thread_id: String, state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.database.call(move |db| db.is_sealed_sender_thread(&thread_id)).await)
+}
+
+/// Turn sealed sender on or off for a thread
+#[tauri::command]
+pub async fn set_thread_sealed_sender(
+    thread_id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.set_sealed_sender_thread(&thread_id, enabled))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Payload size (bytes) from which outgoing messages are compressed, or null if off
+#[tauri::command]
+pub async fn get_compression_threshold(state: State<'_, AppState>) -> Result<Option<usize>, AppError> {
+    Ok(state.database.call(|db| db.get_compression_threshold()).await)
+}
+
+/// Set the compression threshold; null turns compression off
+#[tauri::command]
+pub async fn set_compression_threshold(
+    threshold: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.set_compression_threshold(threshold))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Whether previews are fetched for links in outgoing messages
+#[tauri::command]
+pub async fn get_link_previews(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.database.call(|db| db.get_link_previews()).await)
+}
+
+/// Turn link previews for outgoing messages on or off. Previews are never
+/// fetched for incoming messages.
+#[tauri::command]
+pub async fn set_link_previews(enabled: bool, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.set_link_previews(enabled))
+        .await
+        .map_err(AppError::from)
 }
 
 /// Add a reaction to a message
@@ -182,29 +483,28 @@ pub async fn add_reaction(
     recipient_public_key: String,
     _recipient_handle: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     // Get our identity
     let identity_mgr = state.identity.lock().await;
     let identity = identity_mgr
         .get_identity()
-        .ok_or("No identity configured")?;
+        .ok_or(AppError::NoIdentity)?;
     let my_handle = identity_mgr.cached_handle();
 
     // Resolve recipient encryption key
-    let info = state
-        .api
-        .get_identity(&recipient_public_key)
+    let info = resolver::resolve_identity(&state.api, &state.database, &recipient_public_key)
         .await
         .map_err(|e| format!("Failed to get identity: {}", e))?
-        .ok_or("Identity not found")?;
-    let recipient_enc_key = info.encryption_key;
+        .ok_or_else(|| AppError::not_found("Identity"))?;
+    let recipient_enc_key = info.info.encryption_key;
 
     // Create payload
     let payload = serde_json::json!({
         "target_message_id": message_id,
         "emoji": emoji
     });
-    let payload_bytes = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+    let payload_bytes = serde_json::to_vec(&payload)?;
+    validate_payload("reaction", &payload_bytes)?;
 
     // Create envelope
     let envelope = create_envelope_with_metadata(
@@ -227,8 +527,11 @@ pub async fn add_reaction(
         .map_err(|e| format!("Failed to send: {}", e))?;
 
     // Store locally
-    let mut db = state.database.lock().await;
-    db.save_reaction(&message_id, &identity.public_key_hex(), &emoji, envelope.timestamp)
+    let (my_pk, timestamp) = (identity.public_key_hex(), envelope.timestamp);
+    state
+        .database
+        .call(move |db| db.save_reaction(&message_id, &my_pk, &emoji, timestamp))
+        .await
         .map_err(|e| format!("Failed to save reaction: {}", e))?;
 
     Ok(())
@@ -246,12 +549,12 @@ pub async fn save_sent_email_message(
     thread_id: Option<String>,
     message_id: Option<String>, // Added parameter
     state: State<'_, AppState>,
-) -> Result<SendResult, String> {
+) -> Result<SendResult, AppError> {
     // Get our identity
     let identity_mgr = state.identity.lock().await;
     let identity = identity_mgr
         .get_identity()
-        .ok_or("No identity configured")?;
+        .ok_or(AppError::NoIdentity)?;
     let my_handle = identity_mgr.cached_handle();
 
     // Create payload
@@ -262,7 +565,7 @@ pub async fn save_sent_email_message(
         "to": [recipient_email], // Simplified 
         "is_email": true
     });
-    let payload_bytes = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+    let payload_bytes = serde_json::to_vec(&payload)?;
 
     // Determine thread ID
     // If provided (reply), use it.
@@ -270,19 +573,9 @@ pub async fn save_sent_email_message(
     let final_thread_id = if let Some(tid) = thread_id {
         tid
     } else {
-        // Use shared normalization logic for consistency
-        let s = crate::message_handler::normalize_subject(&subject);
-        
-        // Check if subject is effectively empty, fallback to random
-        if s.is_empty() {
-            uuid::Uuid::new_v4().to_string()
-        } else {
-            // Generate SHA256 hash
-            let mut hasher = sha2::Sha256::new();
-            hasher.update(s.as_bytes());
-            let result = hasher.finalize();
-            hex::encode(result)
-        }
+        // Use shared normalization logic for consistency; an empty
+        // subject gets a thread of its own
+        payloads::subject_thread_id(&subject).unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
     };
 
     // Create envelope targeting the Email Gateway
@@ -306,32 +599,31 @@ pub async fn save_sent_email_message(
     .map_err(|e| format!("Failed to create envelope: {}", e))?;
 
     // Store locally
-    let mut db = state.database.lock().await;
     // We pass recipient_email as the handle so the thread shows the email address instead of Gateway Key
-    db.save_sent_message(
-        &envelope, 
-        &payload_bytes, 
-        Some(&recipient_email), 
-        None
-    ).map_err(|e| format!("Failed to save locally: {}", e))?;
+    let (saved, saved_payload, saved_email) = (envelope.clone(), payload_bytes.clone(), recipient_email.clone());
+    state
+        .database
+        .call(move |db| db.save_sent_message(&saved, &saved_payload, Some(&saved_email), None))
+        .await
+        .map_err(|e| format!("Failed to save locally: {}", e))?;
 
     // Phase 1.5: Sync to connected Mobile/Browsers (Real-time)
     // We must tell our other devices that we sent this email.
-    let sync_event = serde_json::json!({
-        "type": "message_synced",
-        "to": [identity.public_key_hex()],
-        "messageId": envelope.id,
-        "conversationWith": gateway_public_key, // Emails are technically with Gateway
-        "decryptedText": snippet, // Use snippet or body? Body might be huge. Mobile expects text.
-        "direction": "outgoing",
-        "timestamp": envelope.timestamp,
-        "payload": payload, // Send full payload for Email reconstruction
-    });
+    let sync_event = RelayFrame::MessageSynced {
+        to: vec![identity.public_key_hex()],
+        message_id: envelope.id.clone(),
+        conversation_with: gateway_public_key.to_string(), // Emails are technically with Gateway
+        decrypted_text: snippet.to_string(), // Use snippet or body? Body might be huge. Mobile expects text.
+        direction: "outgoing".to_string(),
+        timestamp: Some(envelope.timestamp),
+        from_handle: None,
+        payload: Some(payload.clone()), // Send full payload for Email reconstruction
+    };
     
     let relay = state.relay.lock().await;
-    if let Err(e) = relay.send_raw(&sync_event.to_string()).await {
+    if let Err(e) = relay.send_frame(&sync_event).await {
             // Non-fatal, just log
-            println!("Failed to sync sent email to devices: {}", e);
+            tracing::warn!("Failed to sync sent email to devices: {}", e);
     }
 
     Ok(SendResult {
@@ -346,12 +638,12 @@ pub async fn request_message_decryption(
     message_ids: Vec<String>,
     conversation_with: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let relay = state.relay.lock().await;
     relay
         .send_decryption_request(message_ids, &conversation_with)
         .await
-        .map_err(|e| format!("Failed to send decryption request: {}", e))
+        .map_err(AppError::from)
 }
 
 /// Resolve a handle to identity info
@@ -359,7 +651,7 @@ pub async fn request_message_decryption(
 pub async fn resolve_handle(
     handle: String,
     state: State<'_, AppState>,
-) -> Result<Option<HandleInfo>, String> {
+) -> Result<Option<HandleInfo>, AppError> {
     let info = state
         .api
         .resolve_handle(&handle)
@@ -374,6 +666,7 @@ pub async fn resolve_handle(
         display_name: i.display_name,
         avatar_url: i.avatar_url,
         is_verified: i.is_verified,
+        verifications: i.verifications,
     }))
 }
 
@@ -396,6 +689,8 @@ pub struct ThreadPreview {
     pub is_pinned: bool,
     pub is_muted: bool,
     pub subject: Option<String>,
+    /// In the requests / junk folder rather than the inbox
+    pub is_junk: bool,
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -418,6 +713,8 @@ pub struct Message {
     pub reply_to_id: Option<String>,
     pub is_starred: bool,
     pub forwarded_from_id: Option<String>,
+    /// For replies carrying a quote digest, whether it matched our copy
+    pub quote_status: Option<QuoteStatus>,
     pub reactions: Vec<Reaction>,
 }
 
@@ -429,4 +726,5 @@ pub struct HandleInfo {
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
     pub is_verified: bool,
+    pub verifications: Vec<crate::verifications::VerifiedProof>,
 }
@@ -5,17 +5,74 @@
 //! - identity: Key management and identity operations
 //! - commands_handle: Handle resolution and claiming
 //! - messaging: Sending and receiving messages
+//! - inbox: Chats, email, contact requests and Dix mentions in one list
 //! - breadcrumbs: Location proof collection
 //! - network: Connection management
 //! - stellar: Stellar/GNS token operations
+//! - reports: Abuse reports for messages and posts
+//! - export: Thread and message export
+//! - verifications: Proofs of control over websites and social accounts
+//! - safety: Safety numbers and per-contact key verification
+//! - contact_requests: Accepting or declining first contact from strangers
+//! - contacts: Contact book and hashed discovery of device contacts
+//! - labels: User-defined thread labels shown as folders
+//! - channels: Publishing to and following public signed channels
+//! - polls: Polls in threads and Dix posts, votes and tallies
+//! - calendar: Event invites, RSVPs, the agenda and ICS export
+//! - invoices: Payment requests in threads and paying them
+//! - location: Sharing current, pinned and live locations as H3 cells
+//! - proximity: Co-location proofs with a peer
+//! - presence: Contacts' online status and our presence sharing setting
+//! - lan: Direct delivery to devices on the local network
+//! - local_api: The JSON-RPC server for local tools
+//! - rules: Automation rules and confirming the scripts they run
+//! - push: Push token registration and background push handling
+//! - deep_links: Pending deep links for the UI
+//! - diagnostics: Recent logs, app health and redacted diagnostics export
+//! - maintenance: Database size reporting, maintenance and message retention
+//! - attachments: Cached attachment downloads and storage usage
+//! - app_lock: PIN / biometric app lock and duress PIN
+//! - wipe: Panic wipe and remote wipe opt-in
+//! - records: Viewing and hand-publishing the signed GNS identity record
+//! - secure_storage: Small UI secrets kept in the OS keychain
 //! - utils: Miscellaneous utilities
+//!
+//! Commands fail with `crate::error::AppError`, which reaches the UI with a
+//! stable error code.
 
 pub mod identity;
 pub mod commands_handle;
 pub mod messaging;
+pub mod inbox;
 pub mod breadcrumbs;
 pub mod network;
 pub mod stellar;
 pub mod handles;
 pub mod utils;
 pub mod dix;
+pub mod reports;
+pub mod export;
+pub mod verifications;
+pub mod safety;
+pub mod contact_requests;
+pub mod contacts;
+pub mod labels;
+pub mod channels;
+pub mod polls;
+pub mod calendar;
+pub mod invoices;
+pub mod location;
+pub mod proximity;
+pub mod presence;
+pub mod lan;
+pub mod local_api;
+pub mod rules;
+pub mod push;
+pub mod deep_links;
+pub mod diagnostics;
+pub mod maintenance;
+pub mod attachments;
+pub mod app_lock;
+pub mod wipe;
+pub mod records;
+pub mod secure_storage;
@@ -1,13 +1,41 @@
 //! Network Commands
 //!
-//! Commands for managing network connectivity.
+//! Commands for managing network connectivity, including the hooks the
+//! mobile shells call on lifecycle changes and background wakeups.
 
+use crate::error::AppError;
+use crate::message_handler::handle_envelope;
+use crate::network::codec::FrameEncoding;
 use crate::AppState;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
+
+/// The DNS-over-HTTPS resolver federated handles are looked up with, if any
+#[tauri::command]
+pub async fn get_dns_over_https(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    Ok(state.api.doh_url())
+}
+
+/// Opt in to TXT lookups for federated handles through a DNS-over-HTTPS
+/// resolver (e.g. `https://cloudflare-dns.com/dns-query`), or turn them off.
+/// The resolver sees every federated handle looked up.
+#[tauri::command]
+pub async fn set_dns_over_https(url: Option<String>, state: State<'_, AppState>) -> Result<(), AppError> {
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    if url.as_deref().is_some_and(|u| !u.starts_with("https://")) {
+        return Err("The resolver URL must be https".into());
+    }
+    let saved = url.clone();
+    state
+        .database
+        .call(move |db| db.set_dns_over_https(saved.as_deref()))
+        .await?;
+    state.api.set_doh_url(url);
+    Ok(())
+}
 
 /// Get current connection status
 #[tauri::command]
-pub async fn get_connection_status(state: State<'_, AppState>) -> Result<ConnectionStatus, String> {
+pub async fn get_connection_status(state: State<'_, AppState>) -> Result<ConnectionStatus, AppError> {
     let relay = state.relay.lock().await;
 
     Ok(ConnectionStatus {
@@ -15,18 +43,80 @@ pub async fn get_connection_status(state: State<'_, AppState>) -> Result<Connect
         relay_url: relay.url().to_string(),
         last_message_at: relay.last_message_time().await,
         reconnect_attempts: relay.reconnect_attempts().await,
+        send_queue_depth: relay.outgoing_queue_depth().await,
+        unacked_envelopes: relay.unacked_count(),
+        frame_encoding: relay.frame_encoding().await,
     })
 }
 
 /// Force reconnect to relay
 #[tauri::command]
-pub async fn reconnect(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn reconnect(state: State<'_, AppState>) -> Result<(), AppError> {
     let identity = state.identity.lock().await;
-    let public_key = identity.public_key_hex().ok_or("No identity configured")?;
+    let public_key = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
     drop(identity);
     
     let relay = state.relay.lock().await;
-    relay.reconnect(&public_key).await.map_err(|e| e.to_string())
+    relay.reconnect(&public_key).await.map_err(AppError::from)
+}
+
+/// Report whether the app is in the foreground
+///
+/// Called on visibility changes; the iOS lifecycle hooks report through
+/// `native::gns_set_app_foreground`. The relay keepalive checks less often
+/// while backgrounded.
+#[tauri::command]
+pub async fn set_app_foreground(foreground: bool, state: State<'_, AppState>) -> Result<(), AppError> {
+    tracing::debug!("App {}", if foreground { "foregrounded" } else { "backgrounded" });
+    state.relay_keepalive.set_foreground(foreground);
+    Ok(())
+}
+
+/// Fetch and process messages queued on the server, then return
+///
+/// Returns how many new messages were stored. The iOS background task
+/// reaches the same code through `native::gns_run_background_fetch`.
+#[tauri::command]
+pub async fn run_background_fetch(app_handle: AppHandle) -> Result<u32, AppError> {
+    background_fetch(&app_handle).await
+}
+
+/// Drain messages queued on the server once, for a caller that has to
+/// know when the work is done
+pub(crate) async fn background_fetch(app_handle: &AppHandle) -> Result<u32, AppError> {
+    let state = app_handle.state::<AppState>();
+    let public_key = state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?;
+
+    // Get the relay back up for anything that arrives after this
+    state.relay_keepalive.wake();
+
+    let envelopes = state
+        .api
+        .fetch_pending_messages(&public_key)
+        .await?;
+
+    let mut received = 0;
+    for envelope in envelopes {
+        let id = envelope.id.clone();
+        let stored = state
+            .database
+            .call(move |db| db.get_message(&id))
+            .await?
+            .is_some();
+        if stored {
+            continue;
+        }
+
+        if handle_envelope(app_handle, &state.identity, &state.database, &state.relay, envelope)
+            .await
+            .is_some()
+        {
+            received += 1;
+        }
+    }
+
+    tracing::info!("Background fetch stored {} messages", received);
+    Ok(received)
 }
 
 #[derive(serde::Serialize)]
@@ -35,4 +125,10 @@ pub struct ConnectionStatus {
     pub relay_url: String,
     pub last_message_at: Option<i64>,
     pub reconnect_attempts: u32,
+    /// Frames waiting to be written to the relay
+    pub send_queue_depth: usize,
+    /// Envelopes sent but not yet acked by the relay
+    pub unacked_envelopes: usize,
+    /// JSON text or MessagePack binary frames
+    pub frame_encoding: FrameEncoding,
 }
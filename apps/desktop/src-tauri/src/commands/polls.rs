@@ -0,0 +1,183 @@
+//! Poll Commands
+//!
+//! Creating polls in threads or on Dix, voting, and reading the tally.
+//! See `crate::polls` for who sees which votes.
+
+use crate::commands::messaging::send_message;
+use crate::error::AppError;
+use crate::polls::{self, Poll, PollResults, POLL_PAYLOAD_TYPE, POLL_VOTE_PAYLOAD_TYPE};
+use crate::resolver;
+use crate::AppState;
+use gns_crypto_core::create_envelope_with_metadata;
+use serde::Serialize;
+use tauri::{AppHandle, Runtime, State};
+
+/// Where a new poll went
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedPoll {
+    pub poll: Poll,
+    /// Set when sent in a thread
+    pub message_id: Option<String>,
+    pub thread_id: Option<String>,
+    /// Set when posted to Dix
+    pub dix_post_id: Option<String>,
+}
+
+/// Create a poll and send it to a thread, or post it to Dix when no
+/// recipient is given
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_poll<R: Runtime>(
+    question: String,
+    options: Vec<String>,
+    multiple_choice: Option<bool>,
+    closes_at: Option<i64>,
+    recipient_handle: Option<String>,
+    recipient_public_key: Option<String>,
+    thread_id: Option<String>,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<CreatedPoll, AppError> {
+    let question = question.trim().to_string();
+    let options: Vec<String> = options.iter().map(|o| o.trim().to_string()).collect();
+    polls::check_poll_text(&question, &options)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    if closes_at.is_some_and(|closes_at| closes_at <= now) {
+        return Err("Poll must close in the future".into());
+    }
+
+    let poll = {
+        let identity_mgr = state.identity.lock().await;
+        let identity = identity_mgr.get_identity().ok_or(AppError::NoIdentity)?;
+        polls::create_poll(identity, &question, options, multiple_choice.unwrap_or(false), closes_at, now)
+    };
+
+    let mut created = CreatedPoll {
+        poll: poll.clone(),
+        message_id: None,
+        thread_id: None,
+        dix_post_id: None,
+    };
+    if recipient_handle.is_some() || recipient_public_key.is_some() {
+        let payload = serde_json::to_value(&poll)?;
+        let sent = send_message(
+            recipient_handle,
+            recipient_public_key,
+            POLL_PAYLOAD_TYPE.to_string(),
+            payload,
+            thread_id,
+            None,
+            app_handle,
+            state.clone(),
+        )
+        .await?;
+        created.message_id = Some(sent.message_id);
+        created.thread_id = sent.thread_id;
+    } else {
+        let post = state
+            .dix
+            .create_post(question, vec![], vec![], None, Some(poll.clone()), None, None)
+            .await?;
+        created.dix_post_id = Some(post.id);
+    }
+
+    let (thread_id, dix_post_id) = (created.thread_id.clone(), created.dix_post_id.clone());
+    state
+        .database
+        .call(move |db| db.save_poll(&poll, thread_id.as_deref(), dix_post_id.as_deref()))
+        .await?;
+    Ok(created)
+}
+
+/// Vote on a poll, replacing any earlier vote of ours
+///
+/// `poll` is needed the first time for polls seen on Dix, which aren't
+/// stored until voted on.
+#[tauri::command]
+pub async fn vote_poll(
+    poll_id: String,
+    choices: Vec<u32>,
+    poll: Option<Poll>,
+    state: State<'_, AppState>,
+) -> Result<PollResults, AppError> {
+    let key = poll_id.clone();
+    let poll = match state.database.call(move |db| db.get_poll(&key)).await {
+        Some(stored) => stored,
+        None => {
+            let poll = poll.filter(|p| p.id == poll_id).ok_or_else(|| AppError::not_found("Poll"))?;
+            polls::verify_poll(&poll).map_err(|e| format!("Invalid poll: {}", e))?;
+            let saved = poll.clone();
+            state
+                .database
+                .call(move |db| db.save_poll(&saved, None, None))
+                .await?;
+            poll
+        }
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    if poll.is_closed(now) {
+        return Err("Poll is closed".into());
+    }
+
+    let identity_mgr = state.identity.lock().await;
+    let identity = identity_mgr.get_identity().ok_or(AppError::NoIdentity)?;
+    let my_pk = identity.public_key_hex();
+    let vote = polls::create_vote(identity, &poll, choices, now)?;
+
+    // The creator tallies; everyone else sends their vote there
+    if !poll.creator.eq_ignore_ascii_case(&my_pk) {
+        let creator = resolver::resolve_identity(&state.api, &state.database, &poll.creator)
+            .await
+            .map_err(|e| format!("Failed to get identity: {}", e))?
+            .ok_or_else(|| AppError::not_found("Poll creator"))?;
+        let payload_bytes = serde_json::to_vec(&vote)?;
+        let envelope = create_envelope_with_metadata(
+            identity,
+            identity_mgr.cached_handle().as_deref(),
+            &poll.creator,
+            &creator.info.encryption_key,
+            POLL_VOTE_PAYLOAD_TYPE,
+            &payload_bytes,
+            None,
+            None,
+        )
+        .map_err(|e| format!("Failed to create envelope: {}", e))?;
+
+        let relay = state.relay.lock().await;
+        relay
+            .send_envelope(&envelope)
+            .await
+            .map_err(|e| format!("Failed to send: {}", e))?;
+    }
+    drop(identity_mgr);
+
+    state
+        .database
+        .call(move |db| {
+            db.record_poll_vote(&vote)?;
+            let votes = db.get_poll_votes(&poll.id)?;
+            Ok::<_, crate::storage::DatabaseError>(polls::tally(&poll, &votes, &my_pk, now))
+        })
+        .await
+        .map_err(AppError::from)
+}
+
+/// A poll's tally: every vote for polls we created, our own otherwise
+#[tauri::command]
+pub async fn get_poll_results(poll_id: String, state: State<'_, AppState>) -> Result<PollResults, AppError> {
+    let my_pk = state
+        .identity
+        .lock()
+        .await
+        .public_key_hex()
+        .ok_or(AppError::NoIdentity)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    state
+        .database
+        .call(move |db| {
+            let poll = db.get_poll(&poll_id).ok_or_else(|| AppError::not_found("Poll"))?;
+            let votes = db.get_poll_votes(&poll_id)?;
+            Ok(polls::tally(&poll, &votes, &my_pk, now))
+        })
+        .await
+}
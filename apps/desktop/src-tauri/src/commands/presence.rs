@@ -0,0 +1,38 @@
+//! Presence Commands
+//!
+//! Contacts' online status, and whether we share our own.
+
+use crate::error::AppError;
+use crate::network::frame::RelayFrame;
+use crate::presence::Presence;
+use crate::AppState;
+use tauri::State;
+
+/// A peer's online status and last-seen time
+#[tauri::command]
+pub async fn get_presence(peer: String, state: State<'_, AppState>) -> Result<Presence, AppError> {
+    Ok(state.presence.get(peer.trim()))
+}
+
+#[tauri::command]
+pub async fn get_share_presence(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.database.call(|db| db.get_share_presence()).await)
+}
+
+/// Opt in or out of letting contacts see when we're online. Takes effect
+/// now if connected, otherwise on the next connect.
+#[tauri::command]
+pub async fn set_share_presence(share: bool, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.set_share_presence(share))
+        .await?;
+
+    let relay = state.relay.lock().await;
+    if relay.is_connected().await {
+        relay
+            .send_frame(&RelayFrame::PresenceSettings { share })
+            .await?;
+    }
+    Ok(())
+}
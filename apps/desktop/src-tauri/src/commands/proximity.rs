@@ -0,0 +1,125 @@
+//! Proximity Commands
+//!
+//! Proving to a peer that we're together, and the proofs made so far.
+//! See `crate::proximity` for the exchange.
+
+use crate::error::AppError;
+use crate::location::share::privacy_zone_at;
+use crate::proximity::{self, ProximityRequest, StoredAttestation, MAX_GRID_STEPS, PROXIMITY_PAYLOAD_TYPE};
+use crate::resolver;
+use crate::AppState;
+use gns_crypto_core::create_envelope_with_metadata;
+use gns_crypto_core::proximity::{create_proximity_claim, DEFAULT_MAX_GRID_STEPS, PROXIMITY_WINDOW_SECONDS};
+use serde::Serialize;
+use tauri::State;
+
+/// Where a proof stands after our side is sent
+#[derive(Debug, Clone, Serialize)]
+pub struct ProximityStatus {
+    /// Set once both claims are in
+    pub attestation: Option<StoredAttestation>,
+    /// The peer hasn't sent a claim for this window yet; `proximity_proven`
+    /// fires when they do
+    pub waiting_for_peer: bool,
+}
+
+/// Sign our current position for a proof with `peer_public_key` and send
+/// it to them
+///
+/// The peer does the same from their device (they get a
+/// `proximity_requested` event if we go first). Refused inside a privacy
+/// zone, like a breadcrumb would be.
+#[tauri::command]
+pub async fn prove_proximity(
+    peer_public_key: String,
+    latitude: f64,
+    longitude: f64,
+    max_steps: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<ProximityStatus, AppError> {
+    let max_steps = max_steps.unwrap_or(DEFAULT_MAX_GRID_STEPS);
+    if max_steps > MAX_GRID_STEPS {
+        return Err(format!("Proofs allow at most {} grid steps", MAX_GRID_STEPS).into());
+    }
+    let zones = state.database.call(|db| db.get_privacy_zones()).await;
+    if let Some(zone) = privacy_zone_at(&zones, latitude, longitude) {
+        return Err(format!("You're inside the privacy zone \"{}\"", zone.name).into());
+    }
+
+    let peer = resolver::resolve_identity(&state.api, &state.database, &peer_public_key)
+        .await
+        .map_err(|e| format!("Failed to get identity: {}", e))?
+        .ok_or_else(|| AppError::not_found("Identity"))?;
+
+    let ours = {
+        let identity_mgr = state.identity.lock().await;
+        let identity = identity_mgr.get_identity().ok_or(AppError::NoIdentity)?;
+        let request = ProximityRequest {
+            claim: create_proximity_claim(identity, latitude, longitude, &peer_public_key)?,
+            max_steps,
+        };
+        let payload_bytes = serde_json::to_vec(&request)?;
+        let envelope = create_envelope_with_metadata(
+            identity,
+            identity_mgr.cached_handle().as_deref(),
+            &peer_public_key,
+            &peer.info.encryption_key,
+            PROXIMITY_PAYLOAD_TYPE,
+            &payload_bytes,
+            None,
+            None,
+        )
+        .map_err(|e| format!("Failed to create envelope: {}", e))?;
+
+        let relay = state.relay.lock().await;
+        relay
+            .send_envelope(&envelope)
+            .await
+            .map_err(|e| format!("Failed to send: {}", e))?;
+        request
+    };
+
+    // If the peer went first, their claim is already here
+    let peer_pk = peer_public_key.clone();
+    let saved = ours.clone();
+    let theirs = state
+        .database
+        .call(move |db| {
+            db.save_proximity_claim(&peer_pk, true, &saved)?;
+            Ok::<_, crate::storage::DatabaseError>(db.get_proximity_claim(&peer_pk, false))
+        })
+        .await?
+        .filter(|theirs| {
+            (theirs.claim.breadcrumb.timestamp - ours.claim.breadcrumb.timestamp).abs() <= PROXIMITY_WINDOW_SECONDS
+        });
+    let Some(theirs) = theirs else {
+        return Ok(ProximityStatus {
+            attestation: None,
+            waiting_for_peer: true,
+        });
+    };
+
+    let stored = proximity::combine(&ours, &theirs, &peer_public_key)?;
+    let saved = stored.clone();
+    state
+        .database
+        .call(move |db| db.save_proximity_attestation(&saved))
+        .await?;
+    Ok(ProximityStatus {
+        attestation: Some(stored),
+        waiting_for_peer: false,
+    })
+}
+
+/// Proofs made with everyone, or with one peer, newest first
+#[tauri::command]
+pub async fn get_proximity_attestations(
+    peer_public_key: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<StoredAttestation>, AppError> {
+    state
+        .database
+        .call(move |db| db.list_proximity_attestations(peer_public_key.as_deref()))
+        .await
+        .map_err(AppError::from)
+}
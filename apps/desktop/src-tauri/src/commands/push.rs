@@ -0,0 +1,123 @@
+//! Push Commands
+//!
+//! Registering the device's FCM/APNs token with the backend, and handling
+//! a push the webview passes on. The native layer reaches the same code
+//! through the `native` entry points. Registrations are signed so only
+//! the key's owner can route its pushes to a device.
+
+use crate::commands::handles::canonical_json;
+use crate::error::AppError;
+use crate::message_handler::IncomingMessageEvent;
+use crate::push::{handle_push, PushPlatform, PushRegistration};
+use crate::AppState;
+use tauri::{AppHandle, State};
+
+/// Register this device's push token
+#[tauri::command]
+pub async fn register_push_token(
+    platform: PushPlatform,
+    token: String,
+    state: State<'_, AppState>,
+) -> Result<PushRegistration, AppError> {
+    register(&state, platform, token).await
+}
+
+/// Sign and send a push token to the backend, then remember it
+///
+/// Shared by the command and the native entry point that receives the
+/// token straight from the OS.
+pub(crate) async fn register(
+    state: &AppState,
+    platform: PushPlatform,
+    token: String,
+) -> Result<PushRegistration, AppError> {
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        return Err("Push token is empty".into());
+    }
+
+    let registration = PushRegistration {
+        platform,
+        token,
+        registered_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let (registration_json, signature) = sign_registration(&registration, state).await?;
+
+    state
+        .api
+        .register_push_token(&registration_json, &signature)
+        .await?;
+
+    tracing::info!("Registered {} push token", registration.platform.as_str());
+
+    let saved = registration.clone();
+    state
+        .database
+        .call(move |db| db.set_push_registration(Some(&saved)))
+        .await?;
+
+    Ok(registration)
+}
+
+/// Stop pushes to this device
+#[tauri::command]
+pub async fn unregister_push_token(state: State<'_, AppState>) -> Result<(), AppError> {
+    let Some(registration) = state.database.call(|db| db.get_push_registration()).await else {
+        return Ok(());
+    };
+
+    let (registration_json, signature) = sign_registration(&registration, &state).await?;
+    state
+        .api
+        .unregister_push_token(&registration_json, &signature)
+        .await?;
+
+    state
+        .database
+        .call(|db| db.set_push_registration(None))
+        .await
+        .map_err(AppError::from)
+}
+
+/// The currently registered push token, if any
+#[tauri::command]
+pub async fn get_push_registration(
+    state: State<'_, AppState>,
+) -> Result<Option<PushRegistration>, AppError> {
+    Ok(state.database.call(|db| db.get_push_registration()).await)
+}
+
+/// Handle a push delivered to the app: fetch, decrypt, store and notify
+///
+/// Allowed while the app is locked, but the message is only returned
+/// when unlocked.
+#[tauri::command]
+pub async fn handle_push_notification(
+    data: serde_json::Value,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<IncomingMessageEvent>, AppError> {
+    let event = handle_push(&app_handle, &data).await?;
+    Ok(event.filter(|_| !state.app_lock.is_locked()))
+}
+
+/// Signed `{public_key, platform, token, timestamp}` for the backend
+async fn sign_registration(
+    registration: &PushRegistration,
+    state: &AppState,
+) -> Result<(serde_json::Value, String), String> {
+    let identity = state.identity.lock().await;
+    let public_key = identity.public_key_hex().ok_or("No identity")?;
+
+    let registration_json = serde_json::json!({
+        "public_key": public_key,
+        "platform": registration.platform.as_str(),
+        "token": registration.token,
+        "timestamp": chrono::Utc::now().timestamp_millis(),
+    });
+    let signature = identity
+        .sign_string(&canonical_json(&registration_json))
+        .ok_or("Failed to sign push registration")?;
+
+    Ok((registration_json, signature))
+}
@@ -0,0 +1,265 @@
+//! Record Commands
+//!
+//! View the signed GNS record published for an identity, and publish a
+//! hand-edited one. The record is what other clients resolve: keys,
+//! handle, profile, trajectory epoch roots, and the `modules` and
+//! `endpoints` that third-party modules hang off. Modules and endpoints
+//! registered here are published with the record from then on.
+
+use crate::capabilities::{self, RecordEndpoint, RecordModule, MAX_REGISTERED};
+use crate::commands::handles::canonical_json;
+use crate::error::AppError;
+use crate::AppState;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::State;
+
+/// Arrays every record carries, even when empty
+const RECORD_LISTS: &[&str] = &["modules", "endpoints", "epoch_roots"];
+
+/// A published record and whether its signature checks out
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordView {
+    pub public_key: String,
+    pub record: Value,
+    pub signature: Option<String>,
+    /// `signature` verifies against `public_key` over the canonical JSON
+    pub signature_valid: bool,
+    /// The record is our own
+    pub is_self: bool,
+}
+
+/// Fetch the full record published for `public_key`
+#[tauri::command]
+pub async fn get_published_record(
+    public_key: String,
+    state: State<'_, AppState>,
+) -> Result<Option<RecordView>, AppError> {
+    let public_key = public_key.to_lowercase();
+    let Some(published) = state.api.get_record(&public_key).await? else {
+        return Ok(None);
+    };
+
+    let is_self = state.identity.lock().await.public_key_hex().as_deref() == Some(public_key.as_str());
+    let signature_valid = published.signature.as_deref().is_some_and(|signature| {
+        gns_crypto_core::signing::verify_signature_hex(
+            &public_key,
+            canonical_json(&published.record_json).as_bytes(),
+            signature,
+        )
+        .unwrap_or(false)
+    });
+
+    Ok(Some(RecordView {
+        public_key,
+        record: published.record_json,
+        signature: published.signature,
+        signature_valid,
+        is_self,
+    }))
+}
+
+/// Sign and publish a hand-edited identity record
+///
+/// `identity` and `encryption_key` must be our own (they are filled in
+/// when missing) so an edit can't leave other clients unable to reach us;
+/// `updated_at` is set to now. Everything else, including `modules` and
+/// `endpoints`, is published as given.
+#[tauri::command]
+pub async fn publish_custom_record(
+    record_json: Value,
+    state: State<'_, AppState>,
+) -> Result<RecordView, AppError> {
+    let mut record = record_json;
+    let (public_key, signature) = {
+        let identity = state.identity.lock().await;
+        let signer = identity.get_identity().ok_or(AppError::NoIdentity)?;
+        let public_key = signer.public_key_hex();
+
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        prepare_record(&mut record, &public_key, &signer.encryption_key_hex(), &now)?;
+
+        let signature = hex::encode(signer.sign_bytes(canonical_json(&record).as_bytes()));
+        (public_key, signature)
+    };
+
+    state
+        .api
+        .publish_signed_record(&public_key, &record, &signature)
+        .await?;
+
+    Ok(RecordView {
+        public_key,
+        record,
+        signature: Some(signature),
+        signature_valid: true,
+        is_self: true,
+    })
+}
+
+/// Modules and endpoints the record advertises
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordModules {
+    /// Built-in modules first, then registered ones
+    pub modules: Vec<RecordModule>,
+    pub endpoints: Vec<RecordEndpoint>,
+}
+
+/// Modules and endpoints included when the record is next published
+#[tauri::command]
+pub async fn list_record_modules(state: State<'_, AppState>) -> Result<RecordModules, AppError> {
+    Ok(state
+        .database
+        .call(|db| RecordModules {
+            modules: capabilities::advertised_modules(db.get_record_modules()),
+            endpoints: db.get_record_endpoints(),
+        })
+        .await)
+}
+
+/// Advertise a module (or update its version and config)
+///
+/// Takes effect the next time the record is published.
+#[tauri::command]
+pub async fn register_module(
+    id: String,
+    version: Option<u32>,
+    config: Option<Value>,
+    state: State<'_, AppState>,
+) -> Result<RecordModules, AppError> {
+    capabilities::validate_id(&id)?;
+    if capabilities::is_builtin(&id) {
+        return Err(format!("`{}` is built in", id).into());
+    }
+    let module = RecordModule {
+        id,
+        version: version.unwrap_or(1),
+        config: config.unwrap_or(Value::Null),
+    };
+
+    state
+        .database
+        .call(move |db| {
+            let mut modules = db.get_record_modules();
+            match modules.iter().position(|m| m.id == module.id) {
+                Some(i) => modules[i] = module,
+                None if modules.len() >= MAX_REGISTERED => {
+                    return Err(format!("At most {} modules can be registered", MAX_REGISTERED).into());
+                }
+                None => modules.push(module),
+            }
+            db.set_record_modules(&modules)?;
+            Ok(RecordModules {
+                modules: capabilities::advertised_modules(modules),
+                endpoints: db.get_record_endpoints(),
+            })
+        })
+        .await
+}
+
+/// Advertise an endpoint, replacing any earlier one of the same kind
+///
+/// Takes effect the next time the record is published.
+#[tauri::command]
+pub async fn register_endpoint(
+    kind: String,
+    url: String,
+    state: State<'_, AppState>,
+) -> Result<RecordModules, AppError> {
+    capabilities::validate_id(&kind)?;
+    capabilities::validate_endpoint_url(&url)?;
+    let endpoint = RecordEndpoint { kind, url };
+
+    state
+        .database
+        .call(move |db| {
+            let mut endpoints = db.get_record_endpoints();
+            match endpoints.iter().position(|e| e.kind == endpoint.kind) {
+                Some(i) => endpoints[i] = endpoint,
+                None if endpoints.len() >= MAX_REGISTERED => {
+                    return Err(format!("At most {} endpoints can be registered", MAX_REGISTERED).into());
+                }
+                None => endpoints.push(endpoint),
+            }
+            db.set_record_endpoints(&endpoints)?;
+            Ok(RecordModules {
+                modules: capabilities::advertised_modules(db.get_record_modules()),
+                endpoints,
+            })
+        })
+        .await
+}
+
+/// Check a record against our keys and fill in what the server requires
+fn prepare_record(record: &mut Value, public_key: &str, encryption_key: &str, now: &str) -> Result<(), String> {
+    let fields = record.as_object_mut().ok_or("Record must be a JSON object")?;
+
+    for (field, ours) in [("identity", public_key), ("encryption_key", encryption_key)] {
+        match fields.get(field) {
+            None => {
+                fields.insert(field.to_string(), Value::String(ours.to_string()));
+            }
+            Some(Value::String(value)) if value.eq_ignore_ascii_case(ours) => {}
+            Some(_) => return Err(format!("`{}` must be this identity's key", field)),
+        }
+    }
+
+    for field in RECORD_LISTS {
+        match fields.get(*field) {
+            None => {
+                fields.insert(field.to_string(), Value::Array(Vec::new()));
+            }
+            Some(Value::Array(_)) => {}
+            Some(_) => return Err(format!("`{}` must be an array", field)),
+        }
+    }
+
+    match fields.get("version") {
+        None => {
+            fields.insert("version".to_string(), Value::from(1));
+        }
+        Some(version) if version.as_u64().is_some_and(|v| v >= 1) => {}
+        Some(_) => return Err("`version` must be a positive integer".to_string()),
+    }
+
+    fields
+        .entry("created_at")
+        .or_insert_with(|| Value::String(now.to_string()));
+    fields.insert("updated_at".to_string(), Value::String(now.to_string()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NOW: &str = "2025-01-01T00:00:00.000Z";
+
+    #[test]
+    fn test_prepare_fills_required_fields() {
+        let (pk, ek) = ("aa".repeat(32), "bb".repeat(32));
+        let mut record = json!({ "modules": [{ "id": "dix" }] });
+        prepare_record(&mut record, &pk, &ek, NOW).unwrap();
+
+        assert_eq!(record["identity"], pk);
+        assert_eq!(record["encryption_key"], ek);
+        assert_eq!(record["modules"][0]["id"], "dix");
+        assert_eq!(record["endpoints"], json!([]));
+        assert_eq!(record["version"], 1);
+        assert_eq!(record["updated_at"], NOW);
+    }
+
+    #[test]
+    fn test_prepare_rejects_foreign_keys_and_bad_shapes() {
+        let (pk, ek) = ("aa".repeat(32), "bb".repeat(32));
+        let check = |mut record: Value| prepare_record(&mut record, &pk, &ek, NOW);
+
+        assert!(check(json!({ "identity": "cc".repeat(32) })).is_err());
+        assert!(check(json!({ "encryption_key": "cc".repeat(32) })).is_err());
+        assert!(check(json!({ "endpoints": "https://example.com" })).is_err());
+        assert!(check(json!({ "version": 0 })).is_err());
+        assert!(check(json!([])).is_err());
+        assert!(check(json!({ "identity": "AA".repeat(32), "version": 3 })).is_ok());
+    }
+}
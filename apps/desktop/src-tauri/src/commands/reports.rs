@@ -0,0 +1,149 @@
+//! Report Commands
+//!
+//! Reporting abusive messages and posts. Reports are signed by the reporter,
+//! submitted to the backend, and logged locally so the user can see what
+//! they have reported.
+
+use crate::commands::handles::canonical_json;
+use crate::error::AppError;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Longest excerpt attached to a report
+const MAX_EXCERPT_CHARS: usize = 500;
+
+/// Report a message or post
+///
+/// The decrypted text of a message is only attached when `include_excerpt`
+/// is true, which the UI must only set after asking the user.
+#[tauri::command]
+pub async fn report_content(
+    content_type: ReportedContentType,
+    content_id: String,
+    reason: ReportReason,
+    details: Option<String>,
+    include_excerpt: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ContentReport, AppError> {
+    // Find who we're reporting, and the excerpt if the user agreed to share it
+    let (reported_public_key, text) = match content_type {
+        ReportedContentType::Message => {
+            let id = content_id.clone();
+            let message = state
+                .database
+                .call(move |db| db.get_message(&id))
+                .await?
+                .ok_or_else(|| AppError::not_found("Message"))?;
+            if message.is_outgoing {
+                return Err("Cannot report your own message".into());
+            }
+            let text = message.payload.get("text").and_then(|t| t.as_str()).map(String::from);
+            (message.from_public_key, text)
+        }
+        ReportedContentType::Post => {
+            let id = content_id.clone();
+            let cached = state.database.call(move |db| db.get_cached_dix_post(&id)).await;
+            let post = match cached {
+                Some(post) => post,
+                None => state.dix.get_post(&content_id).await?.post,
+            };
+            (post.author.public_key, Some(post.content.text))
+        }
+    };
+
+    let excerpt = if include_excerpt.unwrap_or(false) {
+        text.map(|t| t.chars().take(MAX_EXCERPT_CHARS).collect::<String>())
+    } else {
+        None
+    };
+
+    let identity = state.identity.lock().await;
+    let reporter_public_key = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+
+    let report = ContentReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        content_type,
+        content_id,
+        reported_public_key,
+        reason,
+        details: details.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()),
+        excerpt,
+        reporter_public_key,
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let report_json = serde_json::to_value(&report)?;
+    let signature = identity
+        .sign_string(&canonical_json(&report_json))
+        .ok_or("Failed to sign report")?;
+    drop(identity);
+
+    state
+        .api
+        .submit_report(&report_json, &signature)
+        .await?;
+
+    tracing::info!("Submitted report {} for {} {}", report.id, report.content_type.as_str(), report.content_id);
+
+    let saved = report.clone();
+    state
+        .database
+        .call(move |db| db.save_report(&saved, &signature))
+        .await?;
+
+    Ok(report)
+}
+
+/// Reports we have made, newest first
+#[tauri::command]
+pub async fn get_reports(state: State<'_, AppState>) -> Result<Vec<ContentReport>, AppError> {
+    let pk = state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?;
+    state
+        .database
+        .call(move |db| db.get_reports(&pk))
+        .await
+        .map_err(AppError::from)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportedContentType {
+    Message,
+    Post,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    Impersonation,
+    Illegal,
+    Other,
+}
+
+/// A signed abuse report, as submitted and as logged locally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentReport {
+    pub id: String,
+    pub content_type: ReportedContentType,
+    pub content_id: String,
+    pub reported_public_key: String,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    /// Text of the reported content, only with the user's consent
+    pub excerpt: Option<String>,
+    pub reporter_public_key: String,
+    pub created_at: i64,
+}
+
+impl ReportedContentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Message => "message",
+            Self::Post => "post",
+        }
+    }
+}
+
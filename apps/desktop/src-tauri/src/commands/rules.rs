@@ -0,0 +1,83 @@
+//! Rule Commands
+//!
+//! Creating and listing automation rules, and confirming the script runs
+//! they ask for. See `crate::rules` for how rules are evaluated.
+
+use crate::error::AppError;
+use crate::rules::{self, Rule, RuleAction, RuleTrigger, ScriptOutcome, ScriptRequest};
+use crate::AppState;
+use tauri::State;
+
+/// Create a rule, enabled
+#[tauri::command]
+pub async fn create_rule(
+    name: String,
+    trigger: RuleTrigger,
+    actions: Vec<RuleAction>,
+    state: State<'_, AppState>,
+) -> Result<Rule, AppError> {
+    let rule = Rule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        enabled: true,
+        trigger,
+        actions,
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    rule.validate()?;
+
+    state
+        .database
+        .call(move |db| {
+            for action in &rule.actions {
+                if let RuleAction::MoveToLabel { label_id } = action {
+                    if db.get_label(label_id).is_none() {
+                        return Err(AppError::not_found("Label"));
+                    }
+                }
+            }
+            db.save_rule(&rule)?;
+            Ok(rule)
+        })
+        .await
+}
+
+/// All rules, in the order they run
+#[tauri::command]
+pub async fn list_rules(state: State<'_, AppState>) -> Result<Vec<Rule>, AppError> {
+    state.database.call(|db| db.list_rules()).await.map_err(AppError::from)
+}
+
+/// Delete a rule
+#[tauri::command]
+pub async fn delete_rule(rule_id: String, state: State<'_, AppState>) -> Result<bool, AppError> {
+    state
+        .database
+        .call(move |db| db.delete_rule(&rule_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Script runs waiting for the user, e.g. after a restart of the UI
+#[tauri::command]
+pub async fn get_rule_script_requests(state: State<'_, AppState>) -> Result<Vec<ScriptRequest>, AppError> {
+    Ok(state.rule_scripts.list(chrono::Utc::now().timestamp_millis()))
+}
+
+/// Allow or refuse a script run a rule asked for. Allowed scripts run
+/// right away; refused ones are dropped.
+#[tauri::command]
+pub async fn confirm_rule_script(
+    request_id: String,
+    approved: bool,
+    state: State<'_, AppState>,
+) -> Result<Option<ScriptOutcome>, AppError> {
+    let request = state
+        .rule_scripts
+        .take(&request_id, chrono::Utc::now().timestamp_millis())
+        .ok_or_else(|| AppError::not_found("Script request"))?;
+    if !approved {
+        return Ok(None);
+    }
+    Ok(Some(rules::run_script(&request).await?))
+}
@@ -0,0 +1,199 @@
+//! Safety Number Commands
+//!
+//! Out-of-band verification of a peer's keys. Both sides compute the same
+//! 60-digit number; once the user confirms it matches, the peer's current
+//! encryption key is remembered and any later change is flagged.
+
+use crate::error::AppError;
+use crate::resolver;
+use crate::AppState;
+use gns_crypto_core::{format_safety_number, safety_number};
+use serde::Serialize;
+use tauri::State;
+
+/// What we know about a peer's keys
+#[derive(Debug, Clone, Serialize)]
+pub struct ContactKey {
+    pub public_key: String,
+    pub encryption_key: String,
+    pub verified: bool,
+    pub verified_at: Option<i64>,
+    /// Set when the key changed after verification
+    pub key_changed_at: Option<i64>,
+    pub checked_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SafetyNumber {
+    pub peer_public_key: String,
+    pub peer_encryption_key: String,
+    /// 60 digits
+    pub digits: String,
+    /// Digits in groups of five
+    pub formatted: String,
+    pub verified: bool,
+    pub key_changed: bool,
+}
+
+/// Compute the safety number for a conversation with `peer_pk`
+#[tauri::command]
+pub async fn get_safety_number(
+    peer_pk: String,
+    state: State<'_, AppState>,
+) -> Result<SafetyNumber, AppError> {
+    let peer_pk = peer_pk.trim().to_lowercase();
+    if peer_pk.len() != 64 || hex::decode(&peer_pk).is_err() {
+        return Err("Invalid public key".into());
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+
+    // Prefer the peer's current key; fall back to the last one we saw
+    let peer_encryption_key = match state.api.get_identity(&peer_pk).await {
+        Ok(Some(info)) if !info.encryption_key.is_empty() => {
+            let (pk, key) = (peer_pk.clone(), info.encryption_key.clone());
+            state
+                .database
+                .call(move |db| db.record_contact_key(&pk, &key, now))
+                .await?;
+            info.encryption_key
+        }
+        _ => {
+            let pk = peer_pk.clone();
+            state
+                .database
+                .call(move |db| db.get_contact_key(&pk))
+                .await
+                .map(|c| c.encryption_key)
+                .ok_or("Could not look up the peer's keys")?
+        }
+    };
+
+    let (our_public_key, our_encryption_key) = {
+        let identity = state.identity.lock().await;
+        (
+            identity.public_key_hex().ok_or(AppError::NoIdentity)?,
+            identity.encryption_key_hex().ok_or(AppError::NoIdentity)?,
+        )
+    };
+
+    let digits = safety_number(&our_public_key, &our_encryption_key, &peer_pk, &peer_encryption_key)?;
+    let pk = peer_pk.clone();
+    let contact = state.database.call(move |db| db.get_contact_key(&pk)).await;
+
+    Ok(SafetyNumber {
+        formatted: format_safety_number(&digits),
+        digits,
+        verified: contact.as_ref().is_some_and(|c| c.verified),
+        key_changed: contact.as_ref().is_some_and(|c| c.key_changed_at.is_some()),
+        peer_public_key: peer_pk,
+        peer_encryption_key,
+    })
+}
+
+/// Mark a peer as verified (their current key) or clear verification
+#[tauri::command]
+pub async fn set_contact_verified(
+    peer_pk: String,
+    verified: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let peer_pk = peer_pk.trim().to_lowercase();
+    let pk = peer_pk.clone();
+    state
+        .database
+        .call(move |db| {
+            if db.get_contact_key(&pk).is_none() {
+                return Err("Compare safety numbers before verifying".into());
+            }
+            db.set_contact_verified(&pk, verified, chrono::Utc::now().timestamp_millis())
+                .map_err(AppError::from)
+        })
+        .await?;
+
+    tracing::info!("🔐 Contact {}... verified={}", &peer_pk[..16], verified);
+    Ok(())
+}
+
+/// Verification state for a peer, if we have seen their keys
+#[tauri::command]
+pub async fn get_contact_key(
+    peer_pk: String,
+    state: State<'_, AppState>,
+) -> Result<Option<ContactKey>, AppError> {
+    let peer_pk = peer_pk.trim().to_string();
+    Ok(state.database.call(move |db| db.get_contact_key(&peer_pk)).await)
+}
+
+/// Outcome of re-fetching one peer's keys
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRefresh {
+    pub public_key: String,
+    /// Current encryption key; None if the identity no longer resolves
+    pub encryption_key: Option<String>,
+    /// Differs from the key we had cached
+    pub key_changed: bool,
+    /// Differs from the key the user verified
+    pub verified_key_changed: bool,
+    pub record_verified: bool,
+    pub error: Option<String>,
+}
+
+/// Re-fetch keys for `public_keys`, or for every cached contact
+///
+/// Bypasses the resolution cache's TTL; use after a key-change warning or
+/// to warm the cache before going offline.
+#[tauri::command]
+pub async fn refresh_contact_keys(
+    public_keys: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<KeyRefresh>, AppError> {
+    let public_keys = match public_keys {
+        Some(keys) => keys.into_iter().map(|k| k.trim().to_lowercase()).collect(),
+        None => state
+            .database
+            .call(|db| db.cached_public_keys())
+            .await?,
+    };
+
+    let mut results = Vec::with_capacity(public_keys.len());
+    for public_key in public_keys {
+        let pk = public_key.clone();
+        let previous = state
+            .database
+            .call(move |db| db.cached_identity(&pk).map(|c| c.info.encryption_key))
+            .await;
+
+        let mut refresh = KeyRefresh {
+            public_key: public_key.clone(),
+            encryption_key: None,
+            key_changed: false,
+            verified_key_changed: false,
+            record_verified: false,
+            error: None,
+        };
+        match resolver::refresh_identity(&state.api, &state.database, &public_key).await {
+            Ok(Some(cached)) => {
+                let key = cached.info.encryption_key;
+                refresh.key_changed = previous.is_some_and(|p| !p.eq_ignore_ascii_case(&key));
+                refresh.record_verified = cached.record_verified;
+
+                // Keep safety-number state in step for peers we track
+                let (pk, encryption_key) = (public_key.clone(), key.clone());
+                refresh.verified_key_changed = state
+                    .database
+                    .call(move |db| {
+                        if db.get_contact_key(&pk).is_none() {
+                            return Ok(false);
+                        }
+                        db.record_contact_key(&pk, &encryption_key, chrono::Utc::now().timestamp_millis())
+                    })
+                    .await?;
+                refresh.encryption_key = Some(key);
+            }
+            Ok(None) => refresh.error = Some("Identity not found".to_string()),
+            Err(e) => refresh.error = Some(e.to_string()),
+        }
+        results.push(refresh);
+    }
+    Ok(results)
+}
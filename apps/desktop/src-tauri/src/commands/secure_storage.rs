@@ -0,0 +1,65 @@
+//! Secure Storage Commands
+//!
+//! Small secrets the UI keeps in the OS keychain, under the same service
+//! name as the identity. UI keys get their own prefix so they can never
+//! land on an identity entry.
+
+use keyring::Entry;
+
+use crate::crypto::{IdentityError, SERVICE_NAME};
+use crate::error::AppError;
+
+/// Prefix that keeps UI keys apart from the identity's entries
+const UI_KEY_PREFIX: &str = "ui.";
+
+/// Service name these secrets were stored under before it matched the identity's
+const LEGACY_SERVICE_NAME: &str = "gns-browser";
+
+fn keychain_error(e: keyring::Error) -> AppError {
+    IdentityError::KeychainError(e.to_string()).into()
+}
+
+fn entry(key: &str) -> Result<Entry, AppError> {
+    Entry::new(SERVICE_NAME, &format!("{}{}", UI_KEY_PREFIX, key)).map_err(keychain_error)
+}
+
+fn legacy_entry(key: &str) -> Result<Entry, AppError> {
+    Entry::new(LEGACY_SERVICE_NAME, key).map_err(keychain_error)
+}
+
+#[tauri::command]
+pub fn secure_store(key: String, value: String) -> Result<(), AppError> {
+    entry(&key)?.set_password(&value).map_err(keychain_error)
+}
+
+#[tauri::command]
+pub fn secure_get(key: String) -> Result<Option<String>, AppError> {
+    match entry(&key)?.get_password() {
+        Ok(password) => return Ok(Some(password)),
+        Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(keychain_error(e)),
+    }
+
+    // Move a value saved under the old service name on first read
+    let legacy = legacy_entry(&key)?;
+    match legacy.get_password() {
+        Ok(password) => {
+            entry(&key)?.set_password(&password).map_err(keychain_error)?;
+            let _ = legacy.delete_password();
+            Ok(Some(password))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(keychain_error(e)),
+    }
+}
+
+#[tauri::command]
+pub fn secure_delete(key: String) -> Result<(), AppError> {
+    for entry in [entry(&key)?, legacy_entry(&key)?] {
+        match entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(keychain_error(e)),
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,25 @@
 //! Stellar Tauri Commands
 //!
-//! Exposes Stellar/GNS token functionality to the React frontend
+//! Exposes Stellar/GNS token functionality to the React frontend.
+//! Sends are previewed first; see `crate::stellar::preview`.
 
-use tauri::State;
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use tauri::{AppHandle, State};
 use serde::{Deserialize, Serialize};
+use crate::commands::export::save_with_dialog;
+use crate::error::AppError;
+use crate::export::payments::{self as payments_export, PaymentExportFormat, PaymentRow};
 use crate::AppState;
-use crate::stellar::{StellarService, PaymentHistoryItem, StellarError};
+use crate::stellar::{StellarError, StellarService, PaymentHistoryItem, TransactionPreview};
+use crate::stellar::earnings::{self, EarningsSummary};
+use crate::stellar::preview;
+use crate::stellar::prices::PriceSource;
+use crate::stellar::hardware::{self, HardwareSigningConfig, HardwareWalletInfo};
+
+/// Held for the length of a GNS claim
+static CLAIMING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 
 // ==================== RESPONSE TYPES ====================
 
@@ -44,32 +58,37 @@ pub struct SendGnsRequest {
     pub memo: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareSignResponse {
+    pub signed_xdr: String,
+    pub signer_address: String,
+}
+
 // ==================== COMMANDS ====================
 
 /// Get Stellar address for current identity
 #[tauri::command]
 pub async fn get_stellar_address(
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let identity = state.identity.lock().await;
     
     let public_key = identity.public_key()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
     // Convert GNS key to Stellar address
     StellarService::gns_key_to_stellar(&public_key)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Get Stellar Explorer URL for account
 #[tauri::command]
 pub async fn get_stellar_explorer_url(
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let identity = state.identity.lock().await;
-    let public_key = identity.public_key().ok_or("No identity found")?;
-    let stellar_address = StellarService::gns_key_to_stellar(&public_key)
-        .map_err(|e| e.to_string())?;
+    let public_key = identity.public_key().ok_or(AppError::NoIdentity)?;
+    let stellar_address = StellarService::gns_key_to_stellar(&public_key)?;
     
     let stellar = state.stellar.lock().await;
     let base_url = if stellar.config().use_testnet {
@@ -85,17 +104,16 @@ pub async fn get_stellar_explorer_url(
 #[tauri::command]
 pub async fn get_stellar_balances(
     state: State<'_, AppState>,
-) -> Result<StellarBalancesResponse, String> {
+) -> Result<StellarBalancesResponse, AppError> {
     let identity = state.identity.lock().await;
     
     let public_key = identity.public_key()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
     // Get Stellar service
     let stellar = state.stellar.lock().await;
     
-    let balances = stellar.get_stellar_balances(&public_key).await
-        .map_err(|e| e.to_string())?;
+    let balances = stellar.get_stellar_balances(&public_key).await?;
     
     Ok(StellarBalancesResponse {
         stellar_address: balances.stellar_address,
@@ -116,23 +134,63 @@ pub async fn get_stellar_balances(
 }
 
 /// Claim all GNS tokens (creates trustline if needed)
+///
+/// Refused without contacting the backend when every completed breadcrumb
+/// epoch has already been rewarded.
 #[tauri::command]
 pub async fn claim_gns_tokens(
     state: State<'_, AppState>,
-) -> Result<TransactionResponse, String> {
+) -> Result<TransactionResponse, AppError> {
     let identity = state.identity.lock().await;
     
     let public_key = identity.public_key()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
     let private_key = identity.private_key_bytes()
         .ok_or("No private key available")?;
     
-    // Get Stellar service
-    let stellar = state.stellar.lock().await;
+    // Serialize claims, so a second one sees the epochs the first recorded
+    let _claiming = CLAIMING.lock().await;
+    let stellar = state.stellar.lock().await.detached();
+
+    let pk = public_key.clone();
+    let (breadcrumbs, rewarded) = state
+        .database
+        .call(move |db| Ok::<_, crate::storage::DatabaseError>((db.get_signed_breadcrumbs(&pk)?, db.get_rewarded_epochs(&pk)?)))
+        .await?;
+    let epochs = earnings::unrewarded_epochs(&gns_crypto_core::build_epochs(&breadcrumbs), &rewarded);
+    if epochs.is_empty() {
+        return Ok(TransactionResponse {
+            success: false,
+            hash: None,
+            error: Some(earnings::nothing_to_claim(&rewarded, breadcrumbs.len())),
+            message: None,
+        });
+    }
+
+    let stellar_address = StellarService::gns_key_to_stellar(&public_key)?;
+    let amount: f64 = stellar.get_gns_claimable_balances(&stellar_address).await
+        .unwrap_or_default()
+        .iter()
+        .map(|cb| cb.amount.parse::<f64>().unwrap_or(0.0))
+        .sum();
 
     // Claim all GNS tokens
-    match stellar.claim_all_gns(&public_key, &private_key).await {
+    let result = stellar.claim_all_gns(&public_key, private_key.expose_secret()).await;
+    if let Ok(result) = &result {
+        if result.success {
+            let tx_hash = result.hash.clone();
+            let recorded = state
+                .database
+                .call(move |db| db.record_gns_claim(&public_key, tx_hash.as_deref(), amount, &epochs))
+                .await;
+            if let Err(e) = recorded {
+                tracing::warn!("GNS claim succeeded but wasn't recorded: {}", e);
+            }
+        }
+    }
+
+    match result {
         Ok(result) => Ok(TransactionResponse {
             success: result.success,
             hash: result.hash.clone(),
@@ -156,20 +214,20 @@ pub async fn claim_gns_tokens(
 #[tauri::command]
 pub async fn create_gns_trustline(
     state: State<'_, AppState>,
-) -> Result<TransactionResponse, String> {
+) -> Result<TransactionResponse, AppError> {
     let identity = state.identity.lock().await;
     
     let public_key = identity.public_key()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
     let private_key = identity.private_key_bytes()
         .ok_or("No private key available")?;
     
     // Get Stellar service
-    let stellar = state.stellar.lock().await;
+    let stellar = state.stellar.lock().await.detached();
 
     // Create trustline
-    match stellar.create_gns_trustline(&public_key, &private_key).await {
+    match stellar.create_gns_trustline(&public_key, private_key.expose_secret()).await {
         Ok(result) => Ok(TransactionResponse {
             success: result.success,
             hash: result.hash,
@@ -189,49 +247,71 @@ pub async fn create_gns_trustline(
     }
 }
 
+/// Show what sending GNS will do: operations, fee and resulting balances.
+/// The returned token is required by `send_gns`.
+#[tauri::command]
+pub async fn preview_transaction(
+    request: SendGnsRequest,
+    state: State<'_, AppState>,
+) -> Result<TransactionPreview, AppError> {
+    let sender_pk = state.identity.lock().await.public_key()
+        .ok_or(AppError::NoIdentity)?;
+    let (_, recipient_address) = resolve_recipient(&state, &request).await?;
+
+    let mut stellar = state.stellar.lock().await;
+    let sender = stellar.get_stellar_balances(&sender_pk).await?;
+    let recipient_has_trustline = stellar.recipient_trustline(&recipient_address).await;
+    let fee_stroops = stellar.fee_per_operation().await;
+
+    let preview = preview::payment_preview(
+        &stellar,
+        &sender,
+        &recipient_address,
+        recipient_has_trustline,
+        request.amount,
+        fee_stroops,
+    );
+    stellar.previews().insert(preview.clone());
+    Ok(preview)
+}
+
 /// Send GNS tokens
+///
+/// `preview_token` must come from `preview_transaction` for the same
+/// recipient and amount; each token sends once.
 #[tauri::command]
 pub async fn send_gns(
     request: SendGnsRequest,
+    preview_token: String,
     state: State<'_, AppState>,
-) -> Result<TransactionResponse, String> {
+) -> Result<TransactionResponse, AppError> {
+    // Resolve recipient
+    let (recipient_pk, recipient_address) = resolve_recipient(&state, &request).await?;
+
     let identity = state.identity.lock().await;
     
     let sender_pk = identity.public_key()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
     let sender_private_key = identity.private_key_bytes()
         .ok_or("No private key available")?;
     
-    // Convert sender to Stellar address
-    let _sender_stellar = StellarService::gns_key_to_stellar(&sender_pk)
-        .map_err(|e| e.to_string())?;
-    
-    // Resolve recipient
-    let recipient_pk = if let Some(handle) = &request.recipient_handle {
-        // Look up handle via API
-        let api = &state.api;
-        let resolved = api.resolve_handle(handle).await
-            .map_err(|e| format!("Failed to resolve handle: {}", e))?
-            .ok_or_else(|| format!("Handle @{} not found", handle))?;
-        resolved.public_key
-    } else if let Some(pk) = &request.recipient_public_key {
-        pk.clone()
-    } else {
-        return Err("No recipient specified".to_string());
+    // Only what the user reviewed goes out
+    let stellar = {
+        let mut stellar = state.stellar.lock().await;
+        stellar.previews().confirm(&preview_token, &recipient_address, request.amount)?;
+        stellar.detached()
     };
-    
-    // Get Stellar service
-    let stellar = state.stellar.lock().await;
 
     // Send GNS
     match stellar.send_gns(
         &sender_pk,
-        &sender_private_key,
+        sender_private_key.expose_secret(),
         None, 
         None, 
         &recipient_pk, // We already resolved this to a hex string
         request.amount,
+        request.memo.as_deref(),
     ).await {
         Ok(result) => Ok(TransactionResponse {
             success: result.success,
@@ -257,19 +337,48 @@ pub async fn send_gns(
     }
 }
 
+/// GNS claimed so far, the breadcrumb epochs rewarded and those still
+/// open to claim
+#[tauri::command]
+pub async fn get_earnings_summary(
+    state: State<'_, AppState>,
+) -> Result<EarningsSummary, AppError> {
+    let public_key = state.identity.lock().await.public_key()
+        .ok_or(AppError::NoIdentity)?;
+
+    let (breadcrumbs, rewarded, claims) = state
+        .database
+        .call(move |db| {
+            Ok::<_, crate::storage::DatabaseError>((
+                db.get_signed_breadcrumbs(&public_key)?,
+                db.get_rewarded_epochs(&public_key)?,
+                db.list_gns_claims(&public_key)?,
+            ))
+        })
+        .await?;
+
+    let unclaimed = earnings::unrewarded_epochs(&gns_crypto_core::build_epochs(&breadcrumbs), &rewarded);
+    Ok(EarningsSummary {
+        total_claimed: claims.iter().map(|c| c.amount).sum(),
+        claims,
+        rewarded_epochs: rewarded.len() as u32,
+        unclaimed_epochs: unclaimed.len() as u32,
+        breadcrumbs_to_next_epoch: earnings::breadcrumbs_to_next_epoch(breadcrumbs.len()),
+    })
+}
+
 /// Fund account on testnet (development only)
 #[tauri::command]
 pub async fn fund_testnet_account(
     state: State<'_, AppState>,
-) -> Result<TransactionResponse, String> {
+) -> Result<TransactionResponse, AppError> {
     let identity = state.identity.lock().await;
     
     let public_key = identity.public_key()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
     // Convert to Stellar address
-    let stellar_address = StellarService::gns_key_to_stellar(&public_key)
-        .map_err(|e| e.to_string())?;
+    let stellar_address = StellarService::gns_key_to_stellar(&public_key)?;
     
     // Get Stellar service
     let stellar = state.stellar.lock().await;
@@ -300,19 +409,227 @@ pub async fn fund_testnet_account(
 pub async fn get_payment_history(
     limit: Option<u32>,
     state: State<'_, AppState>,
-) -> Result<Vec<PaymentHistoryItem>, String> {
+) -> Result<Vec<PaymentHistoryItem>, AppError> {
     let identity = state.identity.lock().await;
     
     let public_key = identity.public_key()
-        .ok_or("No identity found")?;
+        .ok_or(AppError::NoIdentity)?;
     
     // Convert to Stellar address
-    let stellar_address = StellarService::gns_key_to_stellar(&public_key)
-        .map_err(|e| e.to_string())?;
+    let stellar_address = StellarService::gns_key_to_stellar(&public_key)?;
     
     let stellar = state.stellar.lock().await;
     
     // Fetch from Horizon API
     stellar.get_payment_history(&stellar_address, limit.unwrap_or(20)).await
-        .map_err(|e: StellarError| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// The recipient as given (GNS key or Stellar address) and their Stellar
+/// address
+async fn resolve_recipient(state: &AppState, request: &SendGnsRequest) -> Result<(String, String), String> {
+    let recipient = if let Some(handle) = &request.recipient_handle {
+        // Look up handle via API
+        let resolved = state.api.resolve_handle(handle).await
+            .map_err(|e| format!("Failed to resolve handle: {}", e))?
+            .ok_or_else(|| format!("Handle @{} not found", handle))?;
+        resolved.public_key
+    } else if let Some(pk) = &request.recipient_public_key {
+        pk.clone()
+    } else {
+        return Err("No recipient specified".to_string());
+    };
+
+    let address = if recipient.starts_with('G') {
+        recipient.clone()
+    } else {
+        StellarService::gns_key_to_stellar(&recipient).map_err(|e| e.to_string())?
+    };
+    Ok((recipient, address))
+}
+
+// ==================== EXPORT ====================
+
+/// Inclusive range of UTC days; either end open
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DateRange {
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+/// Export GNS and XLM payments in `date_range`, each valued in fiat on
+/// the day it happened. Returns the written path, or None if the user
+/// cancelled.
+#[tauri::command]
+pub async fn export_payment_history(
+    app_handle: AppHandle,
+    format: PaymentExportFormat,
+    date_range: Option<DateRange>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let public_key = state.identity.lock().await.public_key()
+        .ok_or(AppError::NoIdentity)?;
+    let stellar_address = StellarService::gns_key_to_stellar(&public_key)?;
+
+    let range = date_range.unwrap_or_default();
+    let since = range.since.map(|d| format!("{}T00:00:00Z", d));
+    let until = range.until.map(|d| format!("{}T23:59:59Z", d));
+
+    // Paging can take a while, so don't hold the shared service
+    let service = StellarService::new(state.stellar.lock().await.config().clone());
+    let payments = service
+        .get_payments_between(&stellar_address, since.as_deref(), until.as_deref())
+        .await?;
+    if payments.is_empty() {
+        return Err("No payments in this date range".into());
+    }
+
+    let source = state.database.call(|db| db.get_price_source()).await;
+    let client = reqwest::Client::new();
+    let mut prices: HashMap<(String, String), Option<f64>> = HashMap::new();
+    let mut rows = Vec::with_capacity(payments.len());
+    for payment in &payments {
+        let day = payment.created_at.get(..10).unwrap_or_default().to_string();
+        let key = (payment.asset_code.clone(), day);
+        if !prices.contains_key(&key) {
+            let price = day_price(&state, &client, &source, &key.0, &key.1).await;
+            prices.insert(key.clone(), price);
+        }
+        rows.push(PaymentRow::new(payment, prices[&key], &source.currency));
+    }
+
+    let bytes = payments_export::render(&rows, format)?;
+    let name = format!("gns-payments-{}", chrono::Utc::now().format("%Y%m%d"));
+    save_with_dialog(&app_handle, &name, format.extension(), bytes).await
+}
+
+/// Price of one unit of `asset_code` on `day`, from the cache or the
+/// price API; None if unavailable
+async fn day_price(state: &AppState, client: &reqwest::Client, source: &PriceSource, asset_code: &str, day: &str) -> Option<f64> {
+    let (asset, currency, day_key) = (asset_code.to_string(), source.currency.clone(), day.to_string());
+    let cached = state
+        .database
+        .call(move |db| db.get_cached_price(&asset, &currency, &day_key))
+        .await;
+    if cached.is_some() {
+        return cached;
+    }
+
+    let date = NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()?;
+    let price = match source.fetch(client, asset_code, date).await {
+        Ok(price) => price?,
+        Err(e) => {
+            tracing::warn!("No {} price for {}: {}", asset_code, day, e);
+            return None;
+        }
+    };
+    let (asset, currency, day) = (asset_code.to_string(), source.currency.clone(), day.to_string());
+    if let Err(e) = state.database.call(move |db| db.cache_price(&asset, &currency, &day, price)).await {
+        tracing::warn!("Failed to cache price: {}", e);
+    }
+    Some(price)
+}
+
+/// The price API used to value exports
+#[tauri::command]
+pub async fn get_price_source(state: State<'_, AppState>) -> Result<PriceSource, AppError> {
+    Ok(state.database.call(|db| db.get_price_source()).await)
+}
+
+/// Change the price API used to value exports
+#[tauri::command]
+pub async fn set_price_source(
+    source: PriceSource,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    if !source.url.starts_with("https://") || !source.url.contains("{coin}") {
+        return Err("Price URL must be https and contain {coin}".into());
+    }
+    if source.currency.trim().is_empty() {
+        return Err("Currency is required".into());
+    }
+    let source = PriceSource {
+        currency: source.currency.trim().to_lowercase(),
+        ..source
+    };
+    state
+        .database
+        .call(move |db| db.set_price_source(&source))
+        .await
+        .map_err(AppError::from)
+}
+
+// ==================== HARDWARE WALLET COMMANDS ====================
+
+/// List connected hardware wallets
+#[tauri::command]
+pub async fn list_hardware_wallets() -> Result<Vec<HardwareWalletInfo>, AppError> {
+    tauri::async_runtime::spawn_blocking(hardware::list_devices)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(AppError::from)
+}
+
+/// Sign a transaction XDR on a hardware wallet
+///
+/// Blocks until the user confirms or rejects on the device.
+#[tauri::command]
+pub async fn sign_with_hardware(
+    xdr: String,
+    device_id: Option<String>,
+    account_index: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<HardwareSignResponse, AppError> {
+    let (config, passphrase) = {
+        let stellar = state.stellar.lock().await;
+        let config = HardwareSigningConfig {
+            use_hardware: true,
+            device_id: device_id.or_else(|| stellar.signing_config().device_id.clone()),
+            account_index: account_index.unwrap_or(stellar.signing_config().account_index),
+        };
+        (config, stellar.config().network_passphrase.clone())
+    };
+
+    let (public_key, signed_xdr) = tauri::async_runtime::spawn_blocking(move || {
+        let signer = hardware::open_hardware_signer(&config)?;
+        let public_key = signer.public_key()?;
+        let signed_xdr = StellarService::sign_xdr(&passphrase, &xdr, signer.as_ref())?;
+        Ok::<_, StellarError>((public_key, signed_xdr))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(HardwareSignResponse {
+        signed_xdr,
+        signer_address: StellarService::gns_key_to_stellar(&hex::encode(public_key))?,
+    })
+}
+
+/// Get the hardware signing preference for the current account
+#[tauri::command]
+pub async fn get_hardware_signing(
+    state: State<'_, AppState>,
+) -> Result<HardwareSigningConfig, AppError> {
+    let public_key = state.identity.lock().await.public_key().ok_or(AppError::NoIdentity)?;
+
+    Ok(state.database.call(move |db| db.get_hardware_signing(&public_key)).await)
+}
+
+/// Enable or disable hardware signing for the current account
+#[tauri::command]
+pub async fn set_hardware_signing(
+    config: HardwareSigningConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let public_key = state.identity.lock().await.public_key().ok_or(AppError::NoIdentity)?;
+
+    let saved = config.clone();
+    state
+        .database
+        .call(move |db| db.set_hardware_signing(&public_key, &saved))
+        .await?;
+
+    let mut stellar = state.stellar.lock().await;
+    stellar.set_signing_config(config);
+    Ok(())
 }
@@ -2,12 +2,13 @@
 //!
 //! Miscellaneous utility commands.
 
+use crate::error::AppError;
 use crate::AppState;
-use tauri::State;
+use tauri::{AppHandle, State};
 
 /// Get app version information
 #[tauri::command]
-pub async fn get_app_version() -> Result<AppVersion, String> {
+pub async fn get_app_version() -> Result<AppVersion, AppError> {
     Ok(AppVersion {
         version: env!("CARGO_PKG_VERSION").to_string(),
         build_date: option_env!("BUILD_DATE").unwrap_or("unknown").to_string(),
@@ -19,24 +20,28 @@ pub async fn get_app_version() -> Result<AppVersion, String> {
 
 /// Open a URL in the system browser
 #[tauri::command]
-pub async fn open_external_url(url: String) -> Result<(), String> {
+pub async fn open_external_url(url: String) -> Result<(), AppError> {
     if !url.starts_with("http://") && !url.starts_with("https://") {
-        return Err("Only HTTP/HTTPS URLs can be opened externally".to_string());
+        return Err("Only HTTP/HTTPS URLs can be opened externally".into());
     }
 
-    open::that(&url).map_err(|e| format!("Failed to open URL: {}", e))
+    open::that(&url).map_err(|e| format!("Failed to open URL: {}", e).into())
 }
 
 /// Get offline status for the offline UI page
 #[tauri::command]
-pub async fn get_offline_status(state: State<'_, AppState>) -> Result<OfflineStatus, String> {
-    let db = state.database.lock().await;
-    let relay = state.relay.lock().await;
-
-    let breadcrumb_count = db.count_breadcrumbs().unwrap_or(0);
-    let pending_messages = db.count_pending_messages().unwrap_or(0);
-    let last_sync = db.get_last_sync_time();
-    let is_online = relay.is_connected().await;
+pub async fn get_offline_status(state: State<'_, AppState>) -> Result<OfflineStatus, AppError> {
+    let (breadcrumb_count, pending_messages, last_sync) = state
+        .database
+        .call(|db| {
+            (
+                db.count_breadcrumbs().unwrap_or(0),
+                db.count_pending_messages().unwrap_or(0),
+                db.get_last_sync_time(),
+            )
+        })
+        .await;
+    let is_online = state.relay.lock().await.is_connected().await;
 
     Ok(OfflineStatus {
         is_online,
@@ -50,6 +55,37 @@ pub async fn get_offline_status(state: State<'_, AppState>) -> Result<OfflineSta
     })
 }
 
+/// The quick-compose hotkey, or `None` if disabled
+#[tauri::command]
+pub async fn get_quick_compose_shortcut(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    Ok(state.database.call(|db| db.get_quick_compose_shortcut()).await)
+}
+
+/// Change or disable (`None`) the quick-compose hotkey
+#[tauri::command]
+pub async fn set_quick_compose_shortcut(
+    shortcut: Option<String>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        let shortcut = shortcut.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        crate::shortcut::register_quick_compose(&app_handle, shortcut.as_deref())?;
+
+        state
+            .database
+            .call(move |db| db.set_quick_compose_shortcut(shortcut.as_deref()))
+            .await
+            .map_err(AppError::from)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Err("Global shortcuts are only available on desktop".into())
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct AppVersion {
     pub version: String,
@@ -0,0 +1,123 @@
+//! Verification Commands
+//!
+//! Proving control of websites and social accounts. The user creates a
+//! signed proof, publishes it, then submits its URL. We check the proof
+//! ourselves before handing it to the backend so mistakes surface early.
+
+use crate::error::AppError;
+use crate::verifications::{
+    proof_document, verify_proof, ProofStatement, Verification, VerificationKind, VerificationStatus,
+};
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+/// Largest proof page we download
+const MAX_PROOF_BYTES: usize = 256 * 1024;
+
+/// A signed proof ready to be published
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationChallenge {
+    pub kind: VerificationKind,
+    pub target: String,
+    /// Text to publish verbatim
+    pub document: String,
+    /// Where to publish it, when the location is fixed (websites)
+    pub proof_url: Option<String>,
+}
+
+/// Create and sign a proof for a website or social account
+#[tauri::command]
+pub async fn create_verification_challenge(
+    kind: VerificationKind,
+    target: String,
+    state: State<'_, AppState>,
+) -> Result<VerificationChallenge, AppError> {
+    let target = kind.normalize_target(&target)?;
+
+    let identity = state.identity.lock().await;
+    let public_key = identity.public_key_hex().ok_or(AppError::NoIdentity)?;
+    let statement = ProofStatement::new(kind, &target, &public_key, identity.cached_handle());
+    let signature = identity
+        .sign_string(&statement.to_text())
+        .ok_or("Failed to sign proof")?;
+    drop(identity);
+
+    Ok(VerificationChallenge {
+        kind,
+        proof_url: kind.proof_url(&target),
+        document: proof_document(&statement, &signature),
+        target,
+    })
+}
+
+/// Fetch a published proof, check it, and submit it to the backend
+#[tauri::command]
+pub async fn submit_verification(
+    kind: VerificationKind,
+    target: String,
+    proof_url: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Verification, AppError> {
+    let target = kind.normalize_target(&target)?;
+    let proof_url = proof_url
+        .map(|u| u.trim().to_string())
+        .or_else(|| kind.proof_url(&target))
+        .ok_or("A proof URL is required for this account type")?;
+
+    if !kind.accepts_proof_url(&target, &proof_url) {
+        return Err(format!("The proof for {} must be published by that account", target).into());
+    }
+
+    let public_key = state.identity.lock().await.public_key_hex().ok_or(AppError::NoIdentity)?;
+
+    let content = state
+        .api
+        .fetch_media(&proof_url, MAX_PROOF_BYTES)
+        .await
+        .map_err(|e| format!("Failed to fetch proof: {}", e))?;
+    let content = String::from_utf8_lossy(&content);
+    let (statement, signature) = verify_proof(&content, kind, &target, &public_key)?;
+
+    let (status, error) = state
+        .api
+        .submit_verification(&statement, &signature, &proof_url)
+        .await?;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let verification = Verification {
+        kind,
+        target,
+        proof_url,
+        status,
+        error,
+        submitted_at: now,
+        verified_at: (status == VerificationStatus::Verified).then_some(now),
+    };
+
+    tracing::info!(
+        "Submitted {} verification for {}: {:?}",
+        kind.as_str(),
+        verification.target,
+        status
+    );
+
+    let saved = verification.clone();
+    state
+        .database
+        .call(move |db| {
+            let mut verifications = db.get_verifications();
+            verifications.retain(|v| !(v.kind == saved.kind && v.target == saved.target));
+            verifications.push(saved);
+            db.set_verifications(&verifications)
+        })
+        .await?;
+
+    Ok(verification)
+}
+
+/// Our submitted proofs
+#[tauri::command]
+pub async fn list_verifications(state: State<'_, AppState>) -> Result<Vec<Verification>, AppError> {
+    Ok(state.database.call(|db| db.get_verifications()).await)
+}
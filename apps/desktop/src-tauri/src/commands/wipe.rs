@@ -0,0 +1,30 @@
+//! Wipe Commands
+//!
+//! Panic wipe of all local data, and the opt-in for remote wipe from the
+//! user's other devices.
+
+use crate::error::AppError;
+use crate::wipe::{self, WipeReport};
+use crate::AppState;
+use tauri::{AppHandle, State};
+
+/// Erase the identity, database, attachment cache and logs
+#[tauri::command]
+pub async fn wipe_all_data(app_handle: AppHandle, state: State<'_, AppState>) -> Result<WipeReport, AppError> {
+    Ok(wipe::wipe_all_data(&app_handle, &state).await)
+}
+
+#[tauri::command]
+pub async fn get_remote_wipe_enabled(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.database.call(|db| db.get_remote_wipe_enabled()).await)
+}
+
+/// Allow a signed remote-wipe envelope from another of our devices to erase this one
+#[tauri::command]
+pub async fn set_remote_wipe_enabled(enabled: bool, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .database
+        .call(move |db| db.set_remote_wipe_enabled(enabled))
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,57 @@
+//! Contact Requests - First-contact consent
+//!
+//! The first messages from a stranger are held as a pending request rather
+//! than opening a thread. Nothing from them reaches the inbox until the
+//! user accepts, which pins the sender's encryption key and releases the
+//! held messages into their threads. Declining drops the held messages and
+//! sends anything further from that sender to junk.
+//!
+//! A sender can introduce themselves explicitly with a `gns/contact-request`
+//! payload carrying a short note and their encryption key; any other first
+//! message is held the same way.
+
+use serde::Serialize;
+
+/// Payload type for an explicit introduction
+pub const CONTACT_REQUEST_PAYLOAD_TYPE: &str = "gns/contact-request";
+
+/// A sender waiting for the user's consent
+#[derive(Debug, Clone, Serialize)]
+pub struct ContactRequest {
+    pub public_key: String,
+    pub handle: Option<String>,
+    /// Key offered in a signed `gns/contact-request`, used for pinning if
+    /// the directory can't be reached
+    pub encryption_key: Option<String>,
+    /// Text of the earliest held message
+    pub preview: Option<String>,
+    pub message_count: u32,
+    pub received_at: i64,
+    pub updated_at: i64,
+}
+
+/// A decrypted message held until its sender is accepted
+#[derive(Debug, Clone)]
+pub struct HeldMessage {
+    pub id: String,
+    pub thread_id: String,
+    pub from_public_key: String,
+    pub from_handle: Option<String>,
+    pub payload_type: String,
+    pub payload: serde_json::Value,
+    pub timestamp: i64,
+    pub signature_valid: bool,
+}
+
+impl HeldMessage {
+    /// The encryption key a signed introduction offers
+    pub fn offered_encryption_key(&self) -> Option<&str> {
+        if self.payload_type != CONTACT_REQUEST_PAYLOAD_TYPE || !self.signature_valid {
+            return None;
+        }
+        self.payload
+            .get("encryption_key")
+            .and_then(|k| k.as_str())
+            .filter(|k| k.len() == 64 && hex::decode(k).is_ok())
+    }
+}
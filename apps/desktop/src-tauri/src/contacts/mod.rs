@@ -0,0 +1,187 @@
+//! Contacts - Local contact book and address book discovery
+//!
+//! The contact book lists identities the user knows, added by hand or
+//! found among the device's contacts. The native contacts bridge reads
+//! the address book once the user grants permission and hands the entries
+//! to `import_device_contacts`; from there only salted SHA-256 hashes of
+//! normalized phone numbers and emails are sent to `/contacts/discover`.
+//! Names and raw identifiers never leave the device.
+//!
+//! Standard discovery also hashes common variants of each identifier (the
+//! national form of a number, an email without its `+tag`) so differently
+//! formatted entries still match. Private discovery sends exactly one hash
+//! per identifier, so the server learns nothing beyond the exact values
+//! being looked up.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain separator mixed into every identifier hash
+const HASH_SALT: &str = "gns-contact-discovery-v1:";
+
+/// Most hashes sent in one discovery request
+pub const DISCOVERY_BATCH: usize = 500;
+
+/// Bounds on the digits in an E.164 number
+const MIN_PHONE_DIGITS: usize = 7;
+const MAX_PHONE_DIGITS: usize = 15;
+
+/// An address book entry as read by the native bridge
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeviceContact {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub phones: Vec<String>,
+    #[serde(default)]
+    pub emails: Vec<String>,
+}
+
+/// An identity in the contact book
+#[derive(Debug, Clone, Serialize)]
+pub struct Contact {
+    pub public_key: String,
+    pub handle: Option<String>,
+    /// Name from the device address book, or set by the user
+    pub display_name: Option<String>,
+    /// How the contact was added: `manual` or `device`
+    pub source: String,
+    pub added_at: i64,
+}
+
+/// How much identifier information discovery may reveal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryMode {
+    /// Hash each identifier and its common variants
+    Standard,
+    /// Exactly one hash per normalized identifier
+    Private,
+}
+
+/// Hash of a normalized identifier, as sent to the server
+pub fn identifier_hash(identifier: &str) -> String {
+    hex::encode(Sha256::digest(format!("{}{}", HASH_SALT, identifier).as_bytes()))
+}
+
+/// E.164 form of a phone number (`+15551234567`)
+///
+/// Numbers without an international prefix need `default_country_code`
+/// (digits, e.g. `"1"` or `"+44"`); a national trunk `0` is dropped.
+pub fn normalize_phone(raw: &str, default_country_code: Option<&str>) -> Option<String> {
+    let trimmed = raw.trim();
+    let digits: String = trimmed.chars().filter(|c| c.is_ascii_digit()).collect();
+
+    let international = if trimmed.starts_with('+') {
+        digits
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        rest.to_string()
+    } else {
+        let country: String = default_country_code?.chars().filter(|c| c.is_ascii_digit()).collect();
+        if country.is_empty() {
+            return None;
+        }
+        format!("{}{}", country, digits.trim_start_matches('0'))
+    };
+
+    (MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS)
+        .contains(&international.len())
+        .then(|| format!("+{}", international))
+}
+
+/// Lowercased email, or None if it isn't one
+pub fn normalize_email(raw: &str) -> Option<String> {
+    let email = raw.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    (!local.is_empty() && domain.contains('.') && !domain.contains('@')).then_some(email)
+}
+
+/// Hashes to look up for one contact, each mapped back to the contact
+fn contact_hashes(contact: &DeviceContact, mode: DiscoveryMode, default_country_code: Option<&str>) -> Vec<String> {
+    let mut identifiers = Vec::new();
+
+    for phone in contact.phones.iter().filter_map(|p| normalize_phone(p, default_country_code)) {
+        if mode == DiscoveryMode::Standard {
+            // The same number saved without its country code
+            let country = default_country_code.map(|c| c.trim_start_matches('+'));
+            if let Some(national) = country.and_then(|c| phone.strip_prefix('+')?.strip_prefix(c)) {
+                if national.len() >= MIN_PHONE_DIGITS {
+                    identifiers.push(national.to_string());
+                }
+            }
+        }
+        identifiers.push(phone);
+    }
+
+    for email in contact.emails.iter().filter_map(|e| normalize_email(e)) {
+        if mode == DiscoveryMode::Standard {
+            if let Some((local, domain)) = email.split_once('@') {
+                if let Some((base, _tag)) = local.split_once('+') {
+                    identifiers.push(format!("{}@{}", base, domain));
+                }
+            }
+        }
+        identifiers.push(email);
+    }
+
+    let mut hashes: Vec<String> = identifiers.iter().map(|i| identifier_hash(i)).collect();
+    hashes.sort();
+    hashes.dedup();
+    hashes
+}
+
+/// Every hash to look up, with the index of the contact it came from
+pub fn discovery_hashes(
+    contacts: &[DeviceContact],
+    mode: DiscoveryMode,
+    default_country_code: Option<&str>,
+) -> HashMap<String, usize> {
+    let mut hashes = HashMap::new();
+    for (index, contact) in contacts.iter().enumerate() {
+        for hash in contact_hashes(contact, mode, default_country_code) {
+            hashes.entry(hash).or_insert(index);
+        }
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_phone() {
+        assert_eq!(normalize_phone("+1 (555) 123-4567", None).as_deref(), Some("+15551234567"));
+        assert_eq!(normalize_phone("0044 20 7946 0958", None).as_deref(), Some("+442079460958"));
+        assert_eq!(normalize_phone("020 7946 0958", Some("+44")).as_deref(), Some("+442079460958"));
+        assert_eq!(normalize_phone("555 1234", None), None);
+        assert_eq!(normalize_phone("+12", None), None);
+    }
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(normalize_email(" Alice@Example.org ").as_deref(), Some("alice@example.org"));
+        assert_eq!(normalize_email("alice"), None);
+        assert_eq!(normalize_email("alice@localhost"), None);
+    }
+
+    #[test]
+    fn test_private_mode_sends_one_hash_per_identifier() {
+        let contact = DeviceContact {
+            name: Some("Alice".to_string()),
+            phones: vec!["+1 555 123 4567".to_string()],
+            emails: vec!["alice+news@example.org".to_string()],
+        };
+
+        let private = contact_hashes(&contact, DiscoveryMode::Private, Some("1"));
+        assert_eq!(private.len(), 2);
+        assert!(private.contains(&identifier_hash("+15551234567")));
+        assert!(!private.iter().any(|h| h.contains("alice") || h.contains("555")));
+
+        let standard = contact_hashes(&contact, DiscoveryMode::Standard, Some("1"));
+        assert_eq!(standard.len(), 4);
+        assert!(standard.contains(&identifier_hash("5551234567")));
+        assert!(standard.contains(&identifier_hash("alice@example.org")));
+    }
+}
@@ -4,9 +4,13 @@
 
 
 pub use gns_crypto_core::GnsIdentity;
+use gns_crypto_core::{SecretBytes, SecretString};
 use keyring::Entry;
+use serde::Serialize;
 
-const SERVICE_NAME: &str = "com.gcrumbs.browser";
+use crate::duress::Persona;
+
+pub(crate) const SERVICE_NAME: &str = "com.gcrumbs.browser";
 const IDENTITY_KEY: &str = "identity_private_key";
 const HANDLE_KEY: &str = "cached_handle";
 
@@ -17,6 +21,9 @@ pub struct IdentityManager {
     
     /// Cached handle
     cached_handle: Option<String>,
+
+    /// Whose keychain entries are in use
+    persona: Persona,
 }
 
 impl IdentityManager {
@@ -25,20 +32,44 @@ impl IdentityManager {
         let mut manager = Self {
             identity: None,
             cached_handle: None,
+            persona: Persona::Primary,
         };
-        
-        // Try to load existing identity from keychain
-        if let Ok(private_key) = manager.load_from_keychain() {
-            if let Ok(identity) = GnsIdentity::from_hex(&private_key) {
-                manager.identity = Some(identity);
-            }
-        }
-        
-        // Load cached handle
-        manager.cached_handle = manager.load_cached_handle().ok();
-        
+        manager.load();
         Ok(manager)
     }
+
+    /// A manager holding `identity`, without touching the keychain
+    #[cfg(test)]
+    pub(crate) fn with_identity(identity: GnsIdentity) -> Self {
+        Self {
+            identity: Some(identity),
+            cached_handle: None,
+            persona: Persona::Primary,
+        }
+    }
+
+    /// Load the active persona's identity and handle from the keychain
+    fn load(&mut self) {
+        self.identity = self
+            .load_from_keychain()
+            .ok()
+            .and_then(|private_key| GnsIdentity::from_hex(private_key.expose_secret()).ok());
+        self.cached_handle = self.load_cached_handle().ok();
+    }
+
+    pub fn persona(&self) -> Persona {
+        self.persona
+    }
+
+    /// Switch to another persona's identity, creating the decoy on first use
+    pub fn switch_persona(&mut self, persona: Persona) -> Result<(), IdentityError> {
+        self.persona = persona;
+        self.load();
+        if persona == Persona::Decoy && self.identity.is_none() {
+            self.generate_new()?;
+        }
+        Ok(())
+    }
     
     /// Check if an identity exists
     pub fn has_identity(&self) -> bool {
@@ -65,18 +96,16 @@ impl IdentityManager {
         self.identity.as_ref().map(|i| i.encryption_key_hex())
     }
     
-    /// Get private key hex (USE WITH CAUTION!)
-    pub fn private_key_hex(&self) -> Option<String> {
-        self.identity.as_ref().map(|i| i.private_key_hex())
+    /// Get ML-KEM public key hex, published for hybrid encryption
+    #[cfg(feature = "pq-hybrid")]
+    pub fn pq_public_key_hex(&self) -> Option<String> {
+        self.identity.as_ref().and_then(|i| i.pq_public_key_hex().ok())
     }
     
     /// Get private key as bytes (USE WITH CAUTION!)
-    /// Returns the first 32 bytes (seed) for signing
-    pub fn private_key_bytes(&self) -> Option<Vec<u8>> {
-        self.identity.as_ref().and_then(|i| {
-            let hex = i.private_key_hex();
-            hex::decode(&hex).ok()
-        })
+    /// Returns the 32-byte seed for signing; never leaves the backend
+    pub fn private_key_bytes(&self) -> Option<SecretBytes> {
+        self.identity.as_ref().map(|i| i.private_key_bytes())
     }
     
     /// Sign a string message and return hex signature
@@ -87,6 +116,22 @@ impl IdentityManager {
         })
     }
     
+    /// Encrypt data to our own key, for local storage only we can read
+    pub fn seal_for_self(&self, plaintext: &[u8]) -> Option<String> {
+        let identity = self.identity.as_ref()?;
+        let encrypted = identity
+            .encrypt_for(plaintext, &identity.encryption_public_key_bytes())
+            .ok()?;
+        serde_json::to_string(&encrypted).ok()
+    }
+
+    /// Decrypt data sealed with `seal_for_self`
+    pub fn open_sealed(&self, sealed: &str) -> Option<Vec<u8>> {
+        let identity = self.identity.as_ref()?;
+        let encrypted = serde_json::from_str(sealed).ok()?;
+        identity.decrypt(&encrypted).ok()
+    }
+
     /// Get cached handle
     pub fn cached_handle(&self) -> Option<String> {
         self.cached_handle.clone()
@@ -106,10 +151,9 @@ impl IdentityManager {
     /// Generate a new identity
     pub fn generate_new(&mut self) -> Result<(), IdentityError> {
         let identity = GnsIdentity::generate();
-        let private_key_hex = identity.private_key_hex();
         
         // Save to keychain
-        self.save_to_keychain(&private_key_hex)?;
+        self.save_to_keychain(&identity.private_key_hex())?;
         
         self.identity = Some(identity);
         self.cached_handle = None;
@@ -118,8 +162,8 @@ impl IdentityManager {
     }
     
     /// Import identity from hex private key
-    pub fn import_from_hex(&mut self, private_key_hex: &str) -> Result<(), IdentityError> {
-        let identity = GnsIdentity::from_hex(private_key_hex)
+    pub fn import_from_hex(&mut self, private_key_hex: &SecretString) -> Result<(), IdentityError> {
+        let identity = GnsIdentity::from_hex(private_key_hex.expose_secret())
             .map_err(|e| IdentityError::InvalidKey(e.to_string()))?;
         
         // Save to keychain
@@ -132,25 +176,30 @@ impl IdentityManager {
     }
     
     // ==================== Keychain Operations ====================
+
+    fn entry_name(&self, key: &str) -> String {
+        format!("{}{}", key, self.persona.storage_suffix())
+    }
     
-    fn load_from_keychain(&self) -> Result<String, IdentityError> {
-        let entry = Entry::new(SERVICE_NAME, IDENTITY_KEY)
+    fn load_from_keychain(&self) -> Result<SecretString, IdentityError> {
+        let entry = Entry::new(SERVICE_NAME, &self.entry_name(IDENTITY_KEY))
             .map_err(|e| IdentityError::KeychainError(e.to_string()))?;
         
         entry.get_password()
+            .map(SecretString::from)
             .map_err(|e| IdentityError::KeychainError(e.to_string()))
     }
     
-    fn save_to_keychain(&self, private_key_hex: &str) -> Result<(), IdentityError> {
-        let entry = Entry::new(SERVICE_NAME, IDENTITY_KEY)
+    fn save_to_keychain(&self, private_key_hex: &SecretString) -> Result<(), IdentityError> {
+        let entry = Entry::new(SERVICE_NAME, &self.entry_name(IDENTITY_KEY))
             .map_err(|e| IdentityError::KeychainError(e.to_string()))?;
         
-        entry.set_password(private_key_hex)
+        entry.set_password(private_key_hex.expose_secret())
             .map_err(|e| IdentityError::KeychainError(e.to_string()))
     }
     
     fn load_cached_handle(&self) -> Result<String, IdentityError> {
-        let entry = Entry::new(SERVICE_NAME, HANDLE_KEY)
+        let entry = Entry::new(SERVICE_NAME, &self.entry_name(HANDLE_KEY))
             .map_err(|e| IdentityError::KeychainError(e.to_string()))?;
         
         entry.get_password()
@@ -158,7 +207,7 @@ impl IdentityManager {
     }
     
     fn save_cached_handle(&self, handle: &str) -> Result<(), IdentityError> {
-        let entry = Entry::new(SERVICE_NAME, HANDLE_KEY)
+        let entry = Entry::new(SERVICE_NAME, &self.entry_name(HANDLE_KEY))
             .map_err(|e| IdentityError::KeychainError(e.to_string()))?;
         
         entry.set_password(handle)
@@ -166,7 +215,7 @@ impl IdentityManager {
     }
     
     fn clear_cached_handle(&self) -> Result<(), IdentityError> {
-        let entry = Entry::new(SERVICE_NAME, HANDLE_KEY)
+        let entry = Entry::new(SERVICE_NAME, &self.entry_name(HANDLE_KEY))
             .map_err(|e| IdentityError::KeychainError(e.to_string()))?;
         
         entry.delete_password()
@@ -175,7 +224,7 @@ impl IdentityManager {
 
     /// Clear all identity data (delete from keychain and memory)
     pub fn clear(&mut self) -> Result<(), IdentityError> {
-        let entry = Entry::new(SERVICE_NAME, IDENTITY_KEY)
+        let entry = Entry::new(SERVICE_NAME, &self.entry_name(IDENTITY_KEY))
             .map_err(|e| IdentityError::KeychainError(e.to_string()))?;
         
         // Best effort deletion
@@ -187,6 +236,35 @@ impl IdentityManager {
         
         Ok(())
     }
+
+    /// Clear every persona's identity, leaving the primary active
+    pub fn clear_all(&mut self) -> Result<(), IdentityError> {
+        for persona in [Persona::Decoy, Persona::Primary] {
+            self.persona = persona;
+            self.clear()?;
+        }
+        Ok(())
+    }
+}
+
+/// Sign a struct that carries its own `signature` field: the signature
+/// (hex) covers the canonical JSON of every other field
+pub fn sign_statement<T: Serialize>(identity: &GnsIdentity, value: &T) -> String {
+    hex::encode(identity.sign_bytes(&statement_bytes(value)))
+}
+
+/// Check a signature made by `sign_statement`
+pub fn verify_statement<T: Serialize>(value: &T, public_key: &str, signature: &str) -> bool {
+    gns_crypto_core::signing::verify_signature_hex(&public_key.to_lowercase(), &statement_bytes(value), signature)
+        .unwrap_or(false)
+}
+
+fn statement_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut statement = serde_json::to_value(value).unwrap_or_default();
+    if let Some(fields) = statement.as_object_mut() {
+        fields.remove("signature");
+    }
+    crate::commands::handles::canonical_json(&statement).into_bytes()
 }
 
 /// Identity manager errors
@@ -0,0 +1,255 @@
+//! Deep Links - Parsing and dispatching gns:// and related URIs
+//!
+//! Supported links:
+//! - `gns://@alice` — open a profile
+//! - `gns://thread/<id>` — open a message thread
+//! - `gns://post/<id>` — open a DIX post
+//! - `gns-migrate:<token>` — continue an identity migration
+//! - `web+stellar:pay?...` / `web+stellar:tx?...` — SEP-7 payment requests
+//!
+//! Links are emitted to the UI as a typed `deep_link` event. Links that
+//! arrive before the UI has loaded (e.g. the one that launched the app) are
+//! queued until it calls `take_pending_deep_links`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Longest ID accepted in a thread or post link
+const MAX_ID_LEN: usize = 128;
+
+/// Most links kept while the UI is loading
+const MAX_PENDING: usize = 16;
+
+/// A parsed deep link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "route", rename_all = "snake_case")]
+pub enum DeepLinkRoute {
+    Profile { handle: String },
+    Thread { thread_id: String },
+    Post { post_id: String },
+    Migrate { token: String },
+    StellarPay { params: BTreeMap<String, String> },
+    StellarTx { params: BTreeMap<String, String> },
+}
+
+/// Parse a deep link URL into a route
+pub fn parse_deep_link(url: &str) -> Result<DeepLinkRoute, String> {
+    let url = url.trim();
+
+    if let Some(rest) = strip_scheme(url, "gns-migrate") {
+        let token = rest.trim_start_matches("//").trim_end_matches('/');
+        if token.is_empty() {
+            return Err("Migration link has no token".to_string());
+        }
+        return Ok(DeepLinkRoute::Migrate { token: token.to_string() });
+    }
+
+    if let Some(rest) = strip_scheme(url, "web+stellar") {
+        let (operation, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let params = parse_query(query)?;
+        return match operation {
+            "pay" if params.contains_key("destination") => Ok(DeepLinkRoute::StellarPay { params }),
+            "tx" if params.contains_key("xdr") => Ok(DeepLinkRoute::StellarTx { params }),
+            "pay" | "tx" => Err(format!("Stellar {} link is missing required parameters", operation)),
+            other => Err(format!("Unsupported Stellar operation: {}", other)),
+        };
+    }
+
+    let Some(rest) = strip_scheme(url, "gns") else {
+        return Err(format!("Unsupported link: {}", url));
+    };
+    let path = rest.trim_start_matches("//");
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let (head, tail) = path.split_once('/').unwrap_or((path, ""));
+
+    match (head, tail.trim_end_matches('/')) {
+        ("thread", id) => Ok(DeepLinkRoute::Thread { thread_id: parse_id(id)? }),
+        ("post", id) => Ok(DeepLinkRoute::Post { post_id: parse_id(id)? }),
+        (_, "") => {
+            let handle = head.trim_start_matches('@').to_lowercase();
+            let valid = !handle.is_empty()
+                && handle.len() <= 20
+                && handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(format!("Invalid handle in link: {}", head));
+            }
+            Ok(DeepLinkRoute::Profile { handle })
+        }
+        _ => Err(format!("Unsupported link: {}", url)),
+    }
+}
+
+/// `url` without `scheme:`, matching the scheme case-insensitively
+fn strip_scheme<'a>(url: &'a str, scheme: &str) -> Option<&'a str> {
+    let (found, rest) = url.split_once(':')?;
+    found.eq_ignore_ascii_case(scheme).then_some(rest)
+}
+
+fn parse_id(id: &str) -> Result<String, String> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(id.to_string())
+    } else {
+        Err(format!("Invalid ID in link: {}", id))
+    }
+}
+
+fn parse_query(query: &str) -> Result<BTreeMap<String, String>, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(key)?, percent_decode(value)?))
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value.get(i + 1..i + 3).ok_or("Truncated escape in link")?;
+                out.push(u8::from_str_radix(hex, 16).map_err(|_| "Invalid escape in link")?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| "Link is not valid UTF-8".to_string())
+}
+
+/// Links waiting for the UI, and whether it is ready for events
+#[derive(Default)]
+pub struct DeepLinkQueue {
+    inner: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    ui_ready: bool,
+    pending: Vec<DeepLinkRoute>,
+}
+
+impl DeepLinkQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a route if the UI isn't ready; returns it back if it should be
+    /// emitted now
+    fn hold(&self, route: DeepLinkRoute) -> Option<DeepLinkRoute> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.ui_ready {
+            return Some(route);
+        }
+        if inner.pending.len() >= MAX_PENDING {
+            inner.pending.remove(0);
+        }
+        inner.pending.push(route);
+        None
+    }
+
+    /// Hand queued routes to the UI; later links are emitted directly
+    pub fn take(&self) -> Vec<DeepLinkRoute> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.ui_ready = true;
+        std::mem::take(&mut inner.pending)
+    }
+}
+
+/// Route a link to the UI, or queue it if the UI hasn't loaded yet
+pub fn dispatch(app_handle: &AppHandle, queue: &DeepLinkQueue, url: &str) -> Result<DeepLinkRoute, String> {
+    let route = parse_deep_link(url).inspect_err(|e| tracing::warn!("Ignoring deep link: {}", e))?;
+
+    tracing::info!("Deep link: {:?}", route);
+    if let Some(route) = queue.hold(route.clone()) {
+        if let Err(e) = app_handle.emit("deep_link", &route) {
+            tracing::error!("Failed to emit deep_link event: {}", e);
+        }
+    }
+    Ok(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gns_links() {
+        assert_eq!(
+            parse_deep_link("gns://@Alice").unwrap(),
+            DeepLinkRoute::Profile { handle: "alice".to_string() }
+        );
+        assert_eq!(
+            parse_deep_link("gns://bob_1/").unwrap(),
+            DeepLinkRoute::Profile { handle: "bob_1".to_string() }
+        );
+        assert_eq!(
+            parse_deep_link("gns://thread/direct_abc123").unwrap(),
+            DeepLinkRoute::Thread { thread_id: "direct_abc123".to_string() }
+        );
+        assert_eq!(
+            parse_deep_link("GNS://post/9f1c-22?ref=share").unwrap(),
+            DeepLinkRoute::Post { post_id: "9f1c-22".to_string() }
+        );
+        assert_eq!(
+            parse_deep_link("gns-migrate:tok3n").unwrap(),
+            DeepLinkRoute::Migrate { token: "tok3n".to_string() }
+        );
+
+        assert!(parse_deep_link("gns://thread/").is_err());
+        assert!(parse_deep_link("gns://thread/../../etc").is_err());
+        assert!(parse_deep_link("gns://@not a handle").is_err());
+        assert!(parse_deep_link("gns://settings/keys").is_err());
+        assert!(parse_deep_link("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_parse_stellar_links() {
+        let route = parse_deep_link(
+            "web+stellar:pay?destination=GABC&amount=12.5&asset_code=GNS&memo=hi%20there",
+        )
+        .unwrap();
+        let DeepLinkRoute::StellarPay { params } = route else {
+            panic!("expected pay route");
+        };
+        assert_eq!(params["destination"], "GABC");
+        assert_eq!(params["amount"], "12.5");
+        assert_eq!(params["memo"], "hi there");
+
+        assert!(matches!(
+            parse_deep_link("web+stellar:tx?xdr=AAAA%2B%2F").unwrap(),
+            DeepLinkRoute::StellarTx { .. }
+        ));
+        assert!(parse_deep_link("web+stellar:pay?amount=1").is_err());
+        assert!(parse_deep_link("web+stellar:sign?xdr=AAAA").is_err());
+        assert!(parse_deep_link("web+stellar:pay?destination=%zz").is_err());
+    }
+
+    #[test]
+    fn test_queue_holds_until_ui_ready() {
+        let queue = DeepLinkQueue::new();
+        let route = DeepLinkRoute::Post { post_id: "p1".to_string() };
+
+        assert!(queue.hold(route.clone()).is_none());
+        assert_eq!(queue.take(), vec![route.clone()]);
+        assert_eq!(queue.hold(route.clone()), Some(route));
+        assert!(queue.take().is_empty());
+    }
+}
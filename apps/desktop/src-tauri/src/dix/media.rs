@@ -0,0 +1,117 @@
+//! DIX media processing
+//!
+//! Images are normalized before upload: decoded, downscaled, re-encoded
+//! as JPEG (which also strips EXIF/GPS metadata), hashed, and given a
+//! blurhash placeholder so timelines can render instantly.
+//!
+//! Uploads are not encrypted: Dix posts are public, and their media is
+//! served at the URL the post carries.
+
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, GenericImageView};
+use sha2::{Digest, Sha256};
+
+/// Longest edge of uploaded images
+const MAX_DIMENSION: u32 = 2048;
+
+/// JPEG quality for uploads
+const JPEG_QUALITY: u8 = 82;
+
+/// Longest edge of the thumbnail used to compute blurhash
+const BLURHASH_SAMPLE: u32 = 64;
+
+/// Reject source images larger than this before decoding
+pub const MAX_SOURCE_BYTES: usize = 20 * 1024 * 1024;
+
+/// An image ready for upload
+#[derive(Debug, Clone)]
+pub struct PreparedImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+    /// SHA-256 of `bytes` (hex)
+    pub hash: String,
+    pub blurhash: String,
+}
+
+/// Decode, downscale, re-encode and hash an image
+pub fn prepare_image(source: &[u8]) -> Result<PreparedImage, String> {
+    if source.len() > MAX_SOURCE_BYTES {
+        return Err(format!("Image too large ({} bytes)", source.len()));
+    }
+
+    let image = image::load_from_memory(source)
+        .map_err(|e| format!("Unsupported image: {}", e))?;
+
+    let image = if image.width() > MAX_DIMENSION || image.height() > MAX_DIMENSION {
+        image.resize(MAX_DIMENSION, MAX_DIMENSION, image::imageops::FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    let (width, height) = image.dimensions();
+
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+
+    Ok(PreparedImage {
+        hash: hex::encode(Sha256::digest(&bytes)),
+        blurhash: blurhash_for(&image)?,
+        bytes,
+        mime_type: "image/jpeg",
+        width,
+        height,
+    })
+}
+
+fn blurhash_for(image: &DynamicImage) -> Result<String, String> {
+    let sample = image.thumbnail(BLURHASH_SAMPLE, BLURHASH_SAMPLE).to_rgba8();
+    let (w, h) = sample.dimensions();
+
+    // 4x3 components is the usual choice for landscape, flip for portrait
+    let (cx, cy) = if w >= h { (4, 3) } else { (3, 4) };
+
+    blurhash::encode(cx, cy, w, h, sample.as_raw())
+        .map_err(|e| format!("Blurhash failed: {:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let img = ImageBuffer::from_fn(width, height, |x, y| {
+            Rgb([(x % 256) as u8, (y % 256) as u8, 128u8])
+        });
+        let mut out = std::io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut out, image::ImageFormat::Png)
+            .unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_prepare_image_downscales_and_hashes() {
+        let prepared = prepare_image(&png_bytes(3000, 1500)).unwrap();
+
+        assert_eq!(prepared.width, MAX_DIMENSION);
+        assert_eq!(prepared.height, MAX_DIMENSION / 2);
+        assert_eq!(prepared.mime_type, "image/jpeg");
+        assert_eq!(prepared.hash, hex::encode(Sha256::digest(&prepared.bytes)));
+        assert!(!prepared.blurhash.is_empty());
+    }
+
+    #[test]
+    fn test_small_image_keeps_size() {
+        let prepared = prepare_image(&png_bytes(100, 80)).unwrap();
+        assert_eq!((prepared.width, prepared.height), (100, 80));
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(prepare_image(b"not an image").is_err());
+    }
+}
@@ -2,11 +2,21 @@
 //!
 //! Handles creating, signing, and publishing posts to DIX via Supabase.
 
+pub mod media;
+pub mod notifications;
+pub mod share;
+
 use crate::crypto::{IdentityManager, GnsIdentity};
+use gns_crypto_core::signing::verify_signature_hex;
 use crate::network::ApiClient;
+use crate::polls::{self, Poll};
+use share::QuotedMessage;
+use gns_crypto_core::ProximityAttestation;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
 use tokio::sync::Mutex;
 
 // ===========================================
@@ -53,6 +63,15 @@ pub struct DixPostContent {
     #[serde(default)]
     pub links: Vec<DixLink>,
     pub location: Option<String>,
+    /// A poll attached by the author, signed on its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll: Option<Poll>,
+    /// A proof the author was with someone, made with them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proximity: Option<ProximityAttestation>,
+    /// A chat message quoted with its sender's consent, signed by them
+    #[serde(rename = "quotedMessage", default, skip_serializing_if = "Option::is_none")]
+    pub quoted_message: Option<QuotedMessage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +80,23 @@ pub struct DixMedia {
     pub media_type: String, // 'image', 'video'
     pub url: String,
     pub alt: Option<String>,
+    /// SHA-256 of the uploaded file (hex), covered by the post signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blurhash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+/// An image attached to a new post, as sent by the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixImageUpload {
+    /// Base64-encoded image file (JPEG, PNG, GIF or WebP)
+    pub data: String,
+    pub alt: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +124,12 @@ pub struct DixPostMeta {
     pub breadcrumbs_at_post: i32,
     #[serde(rename = "createdAt")]
     pub created_at: String,
+    /// Set when the author has edited the post
+    #[serde(rename = "editedAt", default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<String>,
+    /// Author signature over the edit record; replaces `signature` once edited
+    #[serde(rename = "editSignature", default, skip_serializing_if = "Option::is_none")]
+    pub edit_signature: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +140,142 @@ pub struct DixPostThread {
     pub quote_of_id: Option<String>,
 }
 
+/// A reply and its nested replies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixReplyNode {
+    pub post: DixPost,
+    pub children: Vec<DixReplyNode>,
+}
+
+/// Verified comment tree for a post
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixReplies {
+    #[serde(rename = "postId")]
+    pub post_id: String,
+    #[serde(rename = "replyCount")]
+    pub reply_count: u32,
+    pub replies: Vec<DixReplyNode>,
+    /// Replies dropped because their signature did not verify
+    #[serde(rename = "hiddenCount")]
+    pub hidden_count: u32,
+}
+
+/// A user in a follow list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixFollowUser {
+    #[serde(rename = "publicKey")]
+    pub public_key: String,
+    pub handle: Option<String>,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    #[serde(rename = "avatarUrl")]
+    pub avatar_url: Option<String>,
+}
+
+/// Signed record deleting a post
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixTombstone {
+    #[serde(rename = "postId")]
+    pub post_id: String,
+    #[serde(rename = "authorPublicKey")]
+    pub author_public_key: String,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: String,
+    pub signature: String,
+}
+
+/// Signed record replacing a post's text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixEdit {
+    #[serde(rename = "postId")]
+    pub post_id: String,
+    #[serde(rename = "authorPublicKey")]
+    pub author_public_key: String,
+    pub text: String,
+    #[serde(rename = "editedAt")]
+    pub edited_at: String,
+    pub signature: String,
+}
+
+impl DixEdit {
+    /// Apply the edit to a post by the same author
+    pub fn apply_to(&self, post: &mut DixPost) {
+        if post.id != self.post_id || post.author.public_key != self.author_public_key {
+            return;
+        }
+        post.content.tags = extract_tags(&self.text);
+        post.content.mentions = extract_mentions(&self.text);
+        post.content.text = self.text.clone();
+        post.meta.edited_at = Some(self.edited_at.clone());
+        post.meta.edit_signature = Some(self.signature.clone());
+    }
+}
+
+/// A locally saved post; stored encrypted and never sent to the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixBookmark {
+    pub post: DixPost,
+    pub collection: Option<String>,
+    #[serde(rename = "bookmarkedAt")]
+    pub bookmarked_at: i64,
+}
+
+/// Client-side mute filters, applied before posts reach the UI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DixFilters {
+    #[serde(rename = "mutedWords", default)]
+    pub muted_words: Vec<String>,
+    #[serde(rename = "mutedAuthors", default)]
+    pub muted_authors: Vec<String>,
+}
+
+impl DixFilters {
+    /// Whether a post should be hidden
+    pub fn hides(&self, post: &DixPost) -> bool {
+        if self.muted_authors.contains(&post.author.public_key) {
+            return true;
+        }
+        if self.muted_words.is_empty() {
+            return false;
+        }
+
+        let text = post.content.text.to_lowercase();
+        let words: Vec<&str> = text
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '#' || c == '@'))
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        self.muted_words.iter().any(|muted| {
+            if muted.contains(' ') {
+                // Phrases match anywhere in the text
+                text.contains(muted.as_str())
+            } else {
+                words.iter().any(|w| {
+                    *w == muted || w.trim_start_matches(['#', '@']) == muted
+                })
+            }
+        })
+    }
+}
+
+/// What changed when a timeline page was merged into the local cache
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DixSyncDelta {
+    #[serde(rename = "newPosts")]
+    pub new_posts: u32,
+    #[serde(rename = "updatedPosts")]
+    pub updated_posts: u32,
+    /// Ids of posts seen for the first time
+    #[serde(rename = "newPostIds")]
+    pub new_post_ids: Vec<String>,
+}
+
+impl DixSyncDelta {
+    pub fn is_empty(&self) -> bool {
+        self.new_posts == 0 && self.updated_posts == 0
+    }
+}
+
 // ===========================================
 // SERVICE
 // ===========================================
@@ -109,20 +287,106 @@ pub struct DixService {
     // However, ApiClient is struct-based on one base_url.
     // Dix likely uses the same base_url.
     api: Arc<ApiClient>,
+    /// Blurhash placeholders computed at upload, by URL
+    blurhash_cache: std::sync::Mutex<HashMap<String, String>>,
+    filters: std::sync::RwLock<DixFilters>,
 }
 
 impl DixService {
     pub fn new(identity: Arc<Mutex<IdentityManager>>, api: Arc<ApiClient>) -> Self {
-        Self { identity, api }
+        Self {
+            identity,
+            api,
+            blurhash_cache: std::sync::Mutex::new(HashMap::new()),
+            filters: std::sync::RwLock::new(DixFilters::default()),
+        }
+    }
+
+    pub fn filters(&self) -> DixFilters {
+        self.filters.read().unwrap().clone()
+    }
+
+    pub fn set_filters(&self, filters: DixFilters) {
+        *self.filters.write().unwrap() = filters;
+    }
+
+    /// Drop posts hidden by the mute filters
+    pub fn apply_filters(&self, posts: &mut Vec<DixPost>) {
+        let filters = self.filters.read().unwrap();
+        posts.retain(|p| !filters.hides(p));
+    }
+
+    /// Compress, sign and upload image attachments
+    async fn upload_images(&self, images: Vec<DixImageUpload>) -> Result<Vec<DixMedia>, String> {
+        use base64::Engine;
+
+        let mut uploaded = Vec::with_capacity(images.len());
+
+        for image in images {
+            let source = base64::engine::general_purpose::STANDARD
+                .decode(image.data.trim())
+                .map_err(|e| format!("Invalid image data: {}", e))?;
+
+            let prepared = tauri::async_runtime::spawn_blocking(move || media::prepare_image(&source))
+                .await
+                .map_err(|e| e.to_string())??;
+
+            let (public_key, signature) = {
+                let identity = self.identity.lock().await;
+                let public_key = identity.public_key_hex().ok_or("No identity")?;
+                let signature = identity.sign_string(&prepared.hash).ok_or("Failed to sign media")?;
+                (public_key, signature)
+            };
+
+            let url = self.api
+                .upload_media(prepared.bytes, prepared.mime_type, &prepared.hash, &public_key, &signature)
+                .await
+                .map_err(|e| e.to_string())?;
+            self.blurhash_cache.lock().unwrap().insert(url.clone(), prepared.blurhash.clone());
+
+            uploaded.push(DixMedia {
+                media_type: "image".to_string(),
+                url,
+                alt: image.alt,
+                hash: Some(prepared.hash),
+                blurhash: Some(prepared.blurhash),
+                width: Some(prepared.width),
+                height: Some(prepared.height),
+            });
+        }
+
+        Ok(uploaded)
+    }
+
+    /// Fill in blurhash placeholders for images the server sent without one
+    ///
+    /// Only placeholders computed when we uploaded the image are used;
+    /// timeline loads never download media to make one.
+    fn fill_blurhashes(&self, posts: &mut [DixPost]) {
+        let cache = self.blurhash_cache.lock().unwrap();
+        for media in posts.iter_mut().flat_map(|p| p.content.media.iter_mut()) {
+            if media.blurhash.is_none() {
+                media.blurhash = cache.get(&media.url).cloned();
+            }
+        }
     }
 
     /// Create and publish a new DIX post
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_post(
         &self,
         text: String,
-        media: Vec<DixMedia>,
+        mut media: Vec<DixMedia>,
+        images: Vec<DixImageUpload>,
         reply_to_id: Option<String>,
+        poll: Option<Poll>,
+        proximity: Option<ProximityAttestation>,
+        quoted_message: Option<QuotedMessage>,
     ) -> Result<DixPost, String> {
+        // Upload attachments first so their hashes can be signed
+        media.extend(self.upload_images(images).await?);
+        let media_hashes = media_hashes(&media);
+
         let identity = self.identity.lock().await;
         
         // 1. Get identity info
@@ -138,22 +402,17 @@ impl DixService {
         let created_at = chrono::Utc::now().to_rfc3339();
         
         // 4. Create canonical JSON for signing (CRITICAL: must match server/flutter)
-        // Fields: id, facet_id, author_public_key, content, created_at, reply_to_id (if present)
-        let mut signed_map = serde_json::Map::new();
-        signed_map.insert("id".to_string(), json!(post_id));
-        signed_map.insert("facet_id".to_string(), json!("dix"));
-        signed_map.insert("author_public_key".to_string(), json!(public_key));
-        signed_map.insert("content".to_string(), json!(text));
-        signed_map.insert("created_at".to_string(), json!(created_at));
-        
-        if let Some(rid) = &reply_to_id {
-            signed_map.insert("reply_to_id".to_string(), json!(rid));
-        }
-        
-        let signed_data = serde_json::Value::Object(signed_map);
+        let signed_data = post_signing_payload(
+            &post_id,
+            &public_key,
+            &text,
+            &created_at,
+            reply_to_id.as_deref(),
+            &media_hashes,
+        );
         
         let canonical_message = generate_canonical_json(&signed_data);
-        println!("📝 [DIX] Signing Canonical Message: {}", canonical_message);
+        tracing::debug!("Signing DIX post {}", post_id);
         
         // 5. Sign
         let signature = identity.sign_string(&canonical_message)
@@ -173,9 +432,13 @@ impl DixService {
             "media": media,
             "created_at": created_at,
             "tags": tags,
-            "mentions": vec![] as Vec<String>, // TODO: Extract from text
+            "mentions": &mentions,
+            "media_hashes": media_hashes,
             "signature": signature,
-            "reply_to_id": reply_to_id
+            "reply_to_id": reply_to_id,
+            "poll": poll,
+            "proximity": proximity,
+            "quoted_message": quoted_message
         });
 
         let response = self.api.client().post(&url)
@@ -190,7 +453,7 @@ impl DixService {
         }
         
         // Log success
-        println!("✅ Dix Post published: {}", post_id);
+        tracing::info!("DIX post published: {}", post_id);
         
         // Return the post object
         Ok(DixPost {
@@ -212,6 +475,9 @@ impl DixService {
                 media,
                 links: vec![],
                 location: None,
+                poll,
+                proximity,
+                quoted_message,
             },
             engagement: DixPostEngagement {
                 likes: 0,
@@ -225,6 +491,8 @@ impl DixService {
                 trust_score_at_post: 0,
                 breadcrumbs_at_post: 0,
                 created_at,
+                edited_at: None,
+                edit_signature: None,
             },
             thread: reply_to_id.map(|rid| DixPostThread {
                 reply_to_id: Some(rid),
@@ -233,6 +501,131 @@ impl DixService {
         })
     }
     
+    /// Publish a signed tombstone deleting one of our posts
+    pub async fn delete_post(&self, post: &DixPost) -> Result<DixTombstone, String> {
+        let identity = self.identity.lock().await;
+        let public_key = identity.public_key_hex().ok_or("No identity")?;
+        if post.author.public_key != public_key {
+            return Err("Only the author can delete a post".to_string());
+        }
+        let post_id = post.id.as_str();
+        let deleted_at = chrono::Utc::now().to_rfc3339();
+
+        let payload = tombstone_signing_payload(post_id, &public_key, &deleted_at);
+        let signature = identity.sign_string(&generate_canonical_json(&payload))
+            .ok_or("Failed to sign tombstone")?;
+        drop(identity);
+
+        let tombstone = DixTombstone {
+            post_id: post_id.to_string(),
+            author_public_key: public_key,
+            deleted_at,
+            signature,
+        };
+
+        let url = format!("{}/web/dix/delete", self.api.base_url());
+        let response = self.api.client().post(&url)
+            .json(&json!({
+                "post_id": tombstone.post_id,
+                "author_public_key": tombstone.author_public_key,
+                "deleted_at": tombstone.deleted_at,
+                "signature": tombstone.signature,
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!("Server returned error: {}", error_text));
+        }
+
+        tracing::info!("DIX post deleted: {}", post_id);
+        Ok(tombstone)
+    }
+
+    /// Publish a signed edit replacing the text of one of our posts
+    pub async fn edit_post(&self, post: &DixPost, text: String) -> Result<DixEdit, String> {
+        let identity = self.identity.lock().await;
+        let public_key = identity.public_key_hex().ok_or("No identity")?;
+        if post.author.public_key != public_key {
+            return Err("Only the author can edit a post".to_string());
+        }
+        let edited_at = chrono::Utc::now().to_rfc3339();
+
+        let media_hashes = media_hashes(&post.content.media);
+        let payload = edit_signing_payload(&post.id, &public_key, &text, &edited_at, &media_hashes);
+        let signature = identity.sign_string(&generate_canonical_json(&payload))
+            .ok_or("Failed to sign edit")?;
+        drop(identity);
+
+        let edit = DixEdit {
+            post_id: post.id.clone(),
+            author_public_key: public_key,
+            text,
+            edited_at,
+            signature,
+        };
+
+        let url = format!("{}/web/dix/edit", self.api.base_url());
+        let response = self.api.client().post(&url)
+            .json(&json!({
+                "post_id": edit.post_id,
+                "author_public_key": edit.author_public_key,
+                "content": edit.text,
+                "tags": extract_tags(&edit.text),
+                "mentions": extract_mentions(&edit.text),
+                "media_hashes": media_hashes,
+                "edited_at": edit.edited_at,
+                "signature": edit.signature,
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!("Server returned error: {}", error_text));
+        }
+
+        tracing::info!("DIX post edited: {}", edit.post_id);
+        Ok(edit)
+    }
+
+    /// Publish a signed reply to a post
+    pub async fn reply_to_post(
+        &self,
+        parent_id: &str,
+        text: String,
+        media: Vec<DixMedia>,
+    ) -> Result<DixPost, String> {
+        if text.trim().is_empty() && media.is_empty() {
+            return Err("Reply cannot be empty".to_string());
+        }
+        self.create_post(text, media, vec![], Some(parent_id.to_string()), None, None, None).await
+    }
+
+    /// Fetch the replies to a post as a comment tree.
+    ///
+    /// Replies whose signature does not verify are dropped before display.
+    pub async fn get_replies(&self, post_id: &str) -> Result<DixReplies, String> {
+        let data = self.get_post(post_id).await?;
+
+        let total = data.replies.len();
+        let verified: Vec<DixPost> = data.replies.into_iter().filter(verify_post).collect();
+        let hidden_count = (total - verified.len()) as u32;
+        if hidden_count > 0 {
+            tracing::warn!("Dropped {} DIX replies with invalid signatures", hidden_count);
+        }
+
+        Ok(DixReplies {
+            post_id: post_id.to_string(),
+            reply_count: data.reply_count.saturating_sub(hidden_count),
+            replies: build_reply_tree(post_id, verified),
+            hidden_count,
+        })
+    }
+
     pub async fn get_timeline(&self, limit: u32, offset: u32) -> Result<Vec<DixPost>, String> {
         let base_url = self.api.base_url();
         let url = format!("{}/web/dix/timeline?limit={}&offset={}", base_url, limit, offset);
@@ -247,7 +640,83 @@ impl DixService {
         if !wrapper.success {
              return Err(wrapper.error.unwrap_or("Unknown error".into()));
         }
-        Ok(wrapper.data.map(|d| d.posts).ok_or("No data returned")?)
+        let mut posts = wrapper.data.map(|d| d.posts).ok_or("No data returned")?;
+        self.apply_filters(&mut posts);
+        self.fill_blurhashes(&mut posts);
+        Ok(posts)
+    }
+
+    /// Timeline restricted to posts by the given authors
+    pub async fn get_following_timeline(
+        &self,
+        limit: u32,
+        offset: u32,
+        following: &[String],
+    ) -> Result<Vec<DixPost>, String> {
+        if following.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let base_url = self.api.base_url();
+        let url = format!(
+            "{}/web/dix/timeline?limit={}&offset={}&authors={}",
+            base_url,
+            limit,
+            offset,
+            following.join(",")
+        );
+
+        let client = reqwest::Client::new();
+        let res = client.get(&url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let wrapper: DixResponse = res.json().await.map_err(|e| e.to_string())?;
+        if !wrapper.success {
+             return Err(wrapper.error.unwrap_or("Unknown error".into()));
+        }
+        let mut posts = wrapper.data.map(|d| d.posts).ok_or("No data returned")?;
+
+        // Don't rely on the server honouring the author filter
+        posts.retain(|p| following.contains(&p.author.public_key));
+
+        self.apply_filters(&mut posts);
+        self.fill_blurhashes(&mut posts);
+        Ok(posts)
+    }
+
+    /// Posts tagged with a hashtag
+    pub async fn get_posts_by_hashtag(&self, tag: &str, limit: u32, offset: u32) -> Result<Vec<DixPost>, String> {
+        let tag = normalize_tag(tag);
+        let url = format!("{}/web/dix/tag/{}?limit={}&offset={}", self.api.base_url(), tag, limit, offset);
+        self.fetch_posts(&url).await
+    }
+
+    /// Posts mentioning a handle
+    pub async fn get_mentions(&self, handle: &str, limit: u32, offset: u32) -> Result<Vec<DixPost>, String> {
+        let handle = normalize_tag(handle);
+        let url = format!("{}/web/dix/mentions/{}?limit={}&offset={}", self.api.base_url(), handle, limit, offset);
+        let mut posts = self.fetch_posts(&url).await?;
+        posts.retain(|p| mentions_handle(p, &handle));
+        Ok(posts)
+    }
+
+    async fn fetch_posts(&self, url: &str) -> Result<Vec<DixPost>, String> {
+        let client = reqwest::Client::new();
+        let res = client.get(url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let wrapper: DixResponse = res.json().await.map_err(|e| e.to_string())?;
+        if !wrapper.success {
+             return Err(wrapper.error.unwrap_or("Unknown error".into()));
+        }
+        let mut posts = wrapper.data.map(|d| d.posts).ok_or("No data returned")?;
+        self.apply_filters(&mut posts);
+        self.fill_blurhashes(&mut posts);
+        Ok(posts)
     }
 
     pub async fn get_post(&self, post_id: &str) -> Result<DixPostData, String> {
@@ -286,7 +755,7 @@ impl DixService {
 
         if !response.status().is_success() {
              let error_text = response.text().await.unwrap_or_default();
-             println!("❌ [DIX] Like Error: {}", error_text);
+             tracing::error!("DIX like failed: {}", error_text);
              if error_text.contains("Already liked") {
                  return Ok(());
              }
@@ -313,7 +782,7 @@ impl DixService {
 
         if !response.status().is_success() {
               let error_text = response.text().await.unwrap_or_default();
-              println!("❌ [DIX] Repost Error: {}", error_text);
+              tracing::error!("DIX repost failed: {}", error_text);
               if error_text.contains("Already reposted") {
                   return Ok(());
               }
@@ -323,6 +792,69 @@ impl DixService {
         Ok(())
     }
 
+    pub async fn follow_user(&self, target_public_key: &str, public_key: &str, signature: &str) -> Result<(), String> {
+        self.send_follow("follow", target_public_key, public_key, signature).await
+    }
+
+    pub async fn unfollow_user(&self, target_public_key: &str, public_key: &str, signature: &str) -> Result<(), String> {
+        self.send_follow("unfollow", target_public_key, public_key, signature).await
+    }
+
+    async fn send_follow(&self, action: &str, target_public_key: &str, public_key: &str, signature: &str) -> Result<(), String> {
+        let url = format!("{}/web/dix/{}", self.api.base_url(), action);
+        let payload = serde_json::json!({
+            "target_public_key": target_public_key,
+            "author_public_key": public_key,
+            "signature": signature
+        });
+
+        let client = reqwest::Client::new();
+        let response = client.post(&url)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            tracing::error!("DIX {} failed: {}", action, error_text);
+            if error_text.contains("Already following") || error_text.contains("Not following") {
+                return Ok(());
+            }
+            return Err(format!("Server returned error: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    /// Users that `public_key` follows
+    pub async fn get_following(&self, public_key: &str) -> Result<Vec<DixFollowUser>, String> {
+        self.get_follow_list(public_key, "following").await
+    }
+
+    /// Users following `public_key`
+    pub async fn get_followers(&self, public_key: &str) -> Result<Vec<DixFollowUser>, String> {
+        self.get_follow_list(public_key, "followers").await
+    }
+
+    async fn get_follow_list(&self, public_key: &str, list: &str) -> Result<Vec<DixFollowUser>, String> {
+        let url = format!("{}/web/dix/pk/{}/{}", self.api.base_url(), public_key, list);
+
+        let client = reqwest::Client::new();
+        let res = client.get(&url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let wrapper: DixFollowResponse = res.json().await.map_err(|e| e.to_string())?;
+
+        if !wrapper.success {
+             return Err(wrapper.error.unwrap_or("Unknown error".into()));
+        }
+
+        Ok(wrapper.data.map(|d| d.users).ok_or("No data returned")?)
+    }
+
     pub async fn get_posts_by_user(&self, public_key: &str) -> Result<DixUserData, String> {
         let base_url = self.api.base_url();
         let url = format!("{}/web/dix/pk/{}", base_url, public_key);
@@ -378,28 +910,223 @@ struct DixUserResponse {
     error: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct DixFollowResponse {
+    success: bool,
+    data: Option<DixFollowData>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DixFollowData {
+    users: Vec<DixFollowUser>,
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct DixUserData {
     pub user: DixPostAuthor,
     pub posts: Vec<DixPost>,
 }
 
-fn extract_tags(text: &str) -> Vec<String> {
-    // Simple regex replacement
-    // In Rust we might need the regex crate, which is in Cargo.toml
-    use regex::Regex;
-    let re = Regex::new(r"#([a-zA-Z][a-zA-Z0-9_]*)").unwrap();
-    re.captures_iter(text)
-        .map(|cap| cap[1].to_string().to_lowercase())
-        .collect()
+/// Content hashes of attached media, in attachment order
+fn media_hashes(media: &[DixMedia]) -> Vec<String> {
+    media.iter().filter_map(|m| m.hash.clone()).collect()
 }
 
-fn extract_mentions(text: &str) -> Vec<String> {
-    use regex::Regex;
-    let re = Regex::new(r"@([a-zA-Z][a-zA-Z0-9_]*)").unwrap();
-    re.captures_iter(text)
-        .map(|cap| cap[1].to_string().to_lowercase())
-        .collect()
+/// Fields covered by a post signature:
+/// id, facet_id, author_public_key, content, created_at,
+/// reply_to_id (if present), media_hashes (if any media)
+fn post_signing_payload(
+    post_id: &str,
+    author_public_key: &str,
+    text: &str,
+    created_at: &str,
+    reply_to_id: Option<&str>,
+    media_hashes: &[String],
+) -> serde_json::Value {
+    let mut signed_map = serde_json::Map::new();
+    signed_map.insert("id".to_string(), json!(post_id));
+    signed_map.insert("facet_id".to_string(), json!("dix"));
+    signed_map.insert("author_public_key".to_string(), json!(author_public_key));
+    signed_map.insert("content".to_string(), json!(text));
+    signed_map.insert("created_at".to_string(), json!(created_at));
+
+    if let Some(rid) = reply_to_id {
+        signed_map.insert("reply_to_id".to_string(), json!(rid));
+    }
+
+    if !media_hashes.is_empty() {
+        signed_map.insert("media_hashes".to_string(), json!(media_hashes));
+    }
+
+    serde_json::Value::Object(signed_map)
+}
+
+/// Fields covered by a tombstone signature
+fn tombstone_signing_payload(post_id: &str, author_public_key: &str, deleted_at: &str) -> serde_json::Value {
+    json!({
+        "type": "tombstone",
+        "post_id": post_id,
+        "author_public_key": author_public_key,
+        "deleted_at": deleted_at,
+    })
+}
+
+/// Fields covered by an edit signature: the new text plus the
+/// original media hashes (if any), which edits can't change
+fn edit_signing_payload(
+    post_id: &str,
+    author_public_key: &str,
+    text: &str,
+    edited_at: &str,
+    media_hashes: &[String],
+) -> serde_json::Value {
+    let mut signed_map = serde_json::Map::new();
+    signed_map.insert("type".to_string(), json!("edit"));
+    signed_map.insert("post_id".to_string(), json!(post_id));
+    signed_map.insert("author_public_key".to_string(), json!(author_public_key));
+    signed_map.insert("content".to_string(), json!(text));
+    signed_map.insert("edited_at".to_string(), json!(edited_at));
+
+    if !media_hashes.is_empty() {
+        signed_map.insert("media_hashes".to_string(), json!(media_hashes));
+    }
+
+    serde_json::Value::Object(signed_map)
+}
+
+/// Verify a tombstone was signed by the post's author
+///
+/// Tombstones are only made by `delete_post` and stored locally; other
+/// authors' deletions reach us as posts the server no longer returns.
+pub fn verify_tombstone(tombstone: &DixTombstone) -> bool {
+    let payload = tombstone_signing_payload(
+        &tombstone.post_id,
+        &tombstone.author_public_key,
+        &tombstone.deleted_at,
+    );
+    let message = generate_canonical_json(&payload);
+
+    verify_signature_hex(&tombstone.author_public_key, message.as_bytes(), &tombstone.signature)
+        .unwrap_or(false)
+}
+
+/// Verify a post's author signature (the edit signature, if edited), that
+/// any poll or proximity proof on it is the author's, and that any quoted
+/// message is signed by whoever wrote it
+pub fn verify_post(post: &DixPost) -> bool {
+    let poll_valid = post.content.poll.as_ref().is_none_or(|poll| {
+        poll.creator.eq_ignore_ascii_case(&post.author.public_key) && polls::verify_poll(poll).is_ok()
+    });
+    let proximity_valid = post.content.proximity.as_ref().is_none_or(|proof| {
+        proof.involves(&post.author.public_key) && gns_crypto_core::verify_attestation(proof).is_ok()
+    });
+    let quote_valid = post.content.quoted_message.as_ref().is_none_or(share::verify_quoted_message);
+    if !poll_valid || !proximity_valid || !quote_valid {
+        return false;
+    }
+
+    if let (Some(edited_at), Some(edit_signature)) = (&post.meta.edited_at, &post.meta.edit_signature) {
+        let payload = edit_signing_payload(
+            &post.id,
+            &post.author.public_key,
+            &post.content.text,
+            edited_at,
+            &media_hashes(&post.content.media),
+        );
+        let message = generate_canonical_json(&payload);
+        return verify_signature_hex(&post.author.public_key, message.as_bytes(), edit_signature)
+            .unwrap_or(false);
+    }
+
+    let reply_to_id = post.thread.as_ref().and_then(|t| t.reply_to_id.as_deref());
+    let payload = post_signing_payload(
+        &post.id,
+        &post.author.public_key,
+        &post.content.text,
+        &post.meta.created_at,
+        reply_to_id,
+        &media_hashes(&post.content.media),
+    );
+    let message = generate_canonical_json(&payload);
+
+    verify_signature_hex(&post.author.public_key, message.as_bytes(), &post.meta.signature)
+        .unwrap_or(false)
+}
+
+/// Arrange flat replies into a tree under `root_id`, oldest first.
+/// Replies whose parent is missing are attached to the root.
+fn build_reply_tree(root_id: &str, replies: Vec<DixPost>) -> Vec<DixReplyNode> {
+    use std::collections::{HashMap, HashSet};
+
+    let ids: HashSet<String> = replies.iter().map(|p| p.id.clone()).collect();
+    let mut by_parent: HashMap<String, Vec<DixPost>> = HashMap::new();
+
+    for reply in replies {
+        let parent = reply
+            .thread
+            .as_ref()
+            .and_then(|t| t.reply_to_id.clone())
+            .filter(|pid| ids.contains(pid) && pid != &reply.id)
+            .unwrap_or_else(|| root_id.to_string());
+        by_parent.entry(parent).or_default().push(reply);
+    }
+
+    fn attach(parent_id: &str, by_parent: &mut HashMap<String, Vec<DixPost>>) -> Vec<DixReplyNode> {
+        let mut children = by_parent.remove(parent_id).unwrap_or_default();
+        children.sort_by(|a, b| a.meta.created_at.cmp(&b.meta.created_at));
+        children
+            .into_iter()
+            .map(|post| {
+                let nested = attach(&post.id, by_parent);
+                DixReplyNode { post, children: nested }
+            })
+            .collect()
+    }
+
+    attach(root_id, &mut by_parent)
+}
+
+static HASHTAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^\w#])#([A-Za-z][A-Za-z0-9_]{0,49})\b").unwrap()
+});
+
+static MENTION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    // Same charset and length as handles; skips emails like a@b.com
+    Regex::new(r"(?:^|[^\w@])@([A-Za-z0-9_]{3,20})\b").unwrap()
+});
+
+/// Hashtags in a post body, lowercased and without duplicates
+pub fn extract_tags(text: &str) -> Vec<String> {
+    unique_captures(&HASHTAG_REGEX, text)
+}
+
+/// Handles mentioned in a post body, lowercased and without duplicates
+pub fn extract_mentions(text: &str) -> Vec<String> {
+    unique_captures(&MENTION_REGEX, text)
+}
+
+fn unique_captures(re: &Regex, text: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for cap in re.captures_iter(text) {
+        let value = cap[1].to_lowercase();
+        if !found.contains(&value) {
+            found.push(value);
+        }
+    }
+    found
+}
+
+/// Normalize user input like "#Rust" or "@Alice" to a tag or handle
+pub fn normalize_tag(value: &str) -> String {
+    value.trim().trim_start_matches(['#', '@']).to_lowercase()
+}
+
+/// Whether a post mentions the given handle
+pub fn mentions_handle(post: &DixPost, handle: &str) -> bool {
+    let handle = normalize_tag(handle);
+    post.content.mentions.iter().any(|m| normalize_tag(m) == handle)
+        || extract_mentions(&post.content.text).contains(&handle)
 }
 
 /// Start with simple canonical JSON (lexicographical key order)
@@ -435,3 +1162,167 @@ fn generate_canonical_json(value: &serde_json::Value) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_post(identity: &GnsIdentity, id: &str, text: &str, reply_to_id: Option<&str>, created_at: &str) -> DixPost {
+        let public_key = identity.public_key_hex();
+        let payload = post_signing_payload(id, &public_key, text, created_at, reply_to_id, &[]);
+        let signature = hex::encode(identity.sign_bytes(generate_canonical_json(&payload).as_bytes()));
+
+        DixPost {
+            id: id.to_string(),
+            author: DixPostAuthor {
+                public_key,
+                handle: None,
+                display_name: None,
+                avatar_url: None,
+                trust_score: 0,
+                breadcrumb_count: 0,
+                is_verified: false,
+            },
+            facet: "dix".into(),
+            content: DixPostContent {
+                text: text.to_string(),
+                tags: vec![],
+                mentions: vec![],
+                media: vec![],
+                links: vec![],
+                location: None,
+                poll: None,
+                proximity: None,
+                quoted_message: None,
+            },
+            engagement: DixPostEngagement { likes: 0, replies: 0, reposts: 0, quotes: 0, views: 0 },
+            meta: DixPostMeta {
+                signature,
+                trust_score_at_post: 0,
+                breadcrumbs_at_post: 0,
+                created_at: created_at.to_string(),
+                edited_at: None,
+                edit_signature: None,
+            },
+            thread: reply_to_id.map(|rid| DixPostThread {
+                reply_to_id: Some(rid.to_string()),
+                quote_of_id: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_verify_post() {
+        let identity = GnsIdentity::generate();
+        let mut post = signed_post(&identity, "p1", "hello", Some("root"), "2024-01-01T00:00:00Z");
+        assert!(verify_post(&post));
+
+        post.content.text = "tampered".to_string();
+        assert!(!verify_post(&post));
+    }
+
+    #[test]
+    fn test_media_hash_is_signed() {
+        let identity = GnsIdentity::generate();
+        let mut post = signed_post(&identity, "p1", "look", None, "2024-01-01T00:00:00Z");
+
+        // Attaching media the author didn't sign invalidates the post
+        post.content.media.push(DixMedia {
+            media_type: "image".to_string(),
+            url: "https://example.com/a.jpg".to_string(),
+            alt: None,
+            hash: Some("ab".repeat(32)),
+            blurhash: None,
+            width: None,
+            height: None,
+        });
+        assert!(!verify_post(&post));
+    }
+
+    #[test]
+    fn test_edit_and_tombstone_signatures() {
+        let identity = GnsIdentity::generate();
+        let public_key = identity.public_key_hex();
+        let mut post = signed_post(&identity, "p1", "helo", None, "2024-01-01T00:00:00Z");
+
+        let edited_at = "2024-01-01T00:05:00Z";
+        let payload = edit_signing_payload("p1", &public_key, "hello #fixed", edited_at, &[]);
+        let edit = DixEdit {
+            post_id: "p1".to_string(),
+            author_public_key: public_key.clone(),
+            text: "hello #fixed".to_string(),
+            edited_at: edited_at.to_string(),
+            signature: hex::encode(identity.sign_bytes(generate_canonical_json(&payload).as_bytes())),
+        };
+        edit.apply_to(&mut post);
+        assert_eq!(post.content.tags, vec!["fixed"]);
+        assert!(verify_post(&post));
+
+        let payload = tombstone_signing_payload("p1", &public_key, edited_at);
+        let mut tombstone = DixTombstone {
+            post_id: "p1".to_string(),
+            author_public_key: public_key,
+            deleted_at: edited_at.to_string(),
+            signature: hex::encode(identity.sign_bytes(generate_canonical_json(&payload).as_bytes())),
+        };
+        assert!(verify_tombstone(&tombstone));
+
+        tombstone.post_id = "p2".to_string();
+        assert!(!verify_tombstone(&tombstone));
+    }
+
+    #[test]
+    fn test_filters() {
+        let identity = GnsIdentity::generate();
+        let post = signed_post(&identity, "p1", "Spoilers for #Finale tonight", None, "2024-01-01T00:00:00Z");
+
+        let mut filters = DixFilters::default();
+        assert!(!filters.hides(&post));
+
+        filters.muted_words = vec!["spoil".to_string()];
+        assert!(!filters.hides(&post));
+
+        filters.muted_words = vec!["finale".to_string()];
+        assert!(filters.hides(&post));
+
+        filters.muted_words = vec!["for #finale".to_string()];
+        assert!(filters.hides(&post));
+
+        filters.muted_words.clear();
+        filters.muted_authors = vec![identity.public_key_hex()];
+        assert!(filters.hides(&post));
+    }
+
+    #[test]
+    fn test_extract_tags_and_mentions() {
+        let text = "#Rust and #rust at @Alice's place, mail bob@example.com #2024 @al #gns_dev";
+        assert_eq!(extract_tags(text), vec!["rust", "gns_dev"]);
+        assert_eq!(extract_mentions(text), vec!["alice"]);
+    }
+
+    #[test]
+    fn test_mentions_handle() {
+        let identity = GnsIdentity::generate();
+        let post = signed_post(&identity, "p1", "hey @Alice", None, "2024-01-01T00:00:00Z");
+        assert!(mentions_handle(&post, "@alice"));
+        assert!(!mentions_handle(&post, "bob"));
+    }
+
+    #[test]
+    fn test_build_reply_tree() {
+        let identity = GnsIdentity::generate();
+        let replies = vec![
+            signed_post(&identity, "b", "second", Some("root"), "2024-01-01T00:00:02Z"),
+            signed_post(&identity, "a", "first", Some("root"), "2024-01-01T00:00:01Z"),
+            signed_post(&identity, "c", "nested", Some("a"), "2024-01-01T00:00:03Z"),
+            signed_post(&identity, "d", "orphan", Some("missing"), "2024-01-01T00:00:04Z"),
+        ];
+
+        let tree = build_reply_tree("root", replies);
+
+        let top: Vec<&str> = tree.iter().map(|n| n.post.id.as_str()).collect();
+        assert_eq!(top, vec!["a", "b", "d"]);
+        assert_eq!(tree[0].children.len(), 1);
+        assert_eq!(tree[0].children[0].post.id, "c");
+    }
+}
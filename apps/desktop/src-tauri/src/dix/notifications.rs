@@ -0,0 +1,421 @@
+//! Notifications - Likes, reposts, replies, follows and mentions
+//!
+//! The Dix API has no notification endpoint, so the poller works them
+//! out from what it does serve: our posts (whose engagement counts are
+//! compared with the last poll), the replies to posts whose reply count
+//! went up, our followers and posts mentioning our handle. Each thing
+//! seen is one row of activity, keyed so a later poll can't record it
+//! twice. Likes and reposts only show up as counts, so their rows stand
+//! for several people and name no one.
+//!
+//! The feed folds activity on the same post into one entry ("3 people
+//! liked your post"), read and unread kept apart. `notifications_updated`
+//! carries the new feed whenever a poll finds something or entries are
+//! marked read.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::{verify_post, DixPost, DixPostEngagement};
+use crate::AppState;
+
+/// Between polls
+const POLL_INTERVAL: Duration = Duration::from_secs(2 * 60);
+
+/// Mentions fetched per poll
+const MENTIONS_PER_POLL: u32 = 20;
+
+/// Activity rows read when building the feed
+pub const FEED_LIMIT: u32 = 500;
+
+/// Settings key holding the identity whose existing activity has been
+/// recorded as read, so the first poll doesn't announce old likes
+pub const BASELINE_SETTING: &str = "dix_notifications_baseline";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Like,
+    Repost,
+    Reply,
+    Follow,
+    Mention,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Like => "like",
+            NotificationKind::Repost => "repost",
+            NotificationKind::Reply => "reply",
+            NotificationKind::Follow => "follow",
+            NotificationKind::Mention => "mention",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "like" => Some(NotificationKind::Like),
+            "repost" => Some(NotificationKind::Repost),
+            "reply" => Some(NotificationKind::Reply),
+            "follow" => Some(NotificationKind::Follow),
+            "mention" => Some(NotificationKind::Mention),
+            _ => None,
+        }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            NotificationKind::Like => "liked your post",
+            NotificationKind::Repost => "reposted your post",
+            NotificationKind::Reply => "replied to your post",
+            NotificationKind::Follow => "followed you",
+            NotificationKind::Mention => "mentioned you",
+        }
+    }
+}
+
+/// One thing that happened
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DixActivity {
+    /// Row id; 0 until stored
+    pub id: i64,
+    /// What it is, so it's only recorded once
+    pub source_id: String,
+    pub kind: NotificationKind,
+    /// Our post it concerns, or the mentioning post
+    pub post_id: Option<String>,
+    pub actor_public_key: Option<String>,
+    pub actor_handle: Option<String>,
+    /// How many people it stands for
+    pub count: u32,
+    pub created_at: i64,
+    pub read: bool,
+}
+
+impl DixActivity {
+    fn new(source_id: String, kind: NotificationKind, post_id: Option<String>, count: u32, created_at: i64) -> Self {
+        Self {
+            id: 0,
+            source_id,
+            kind,
+            post_id,
+            actor_public_key: None,
+            actor_handle: None,
+            count,
+            created_at,
+            read: false,
+        }
+    }
+
+    fn by(mut self, public_key: &str, handle: Option<String>) -> Self {
+        self.actor_public_key = Some(public_key.to_lowercase());
+        self.actor_handle = handle;
+        self
+    }
+
+    fn actor_name(&self) -> Option<String> {
+        match (&self.actor_handle, &self.actor_public_key) {
+            (Some(handle), _) => Some(format!("@{}", handle.trim_start_matches('@'))),
+            (None, Some(pk)) => Some(format!("{}…", &pk[..pk.len().min(8)])),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Engagement counts as of the last poll
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EngagementCounts {
+    pub likes: i32,
+    pub reposts: i32,
+    pub replies: i32,
+}
+
+impl From<&DixPostEngagement> for EngagementCounts {
+    fn from(engagement: &DixPostEngagement) -> Self {
+        Self {
+            likes: engagement.likes,
+            reposts: engagement.reposts,
+            replies: engagement.replies,
+        }
+    }
+}
+
+/// A feed entry: activity of one kind on one post, folded together
+#[derive(Debug, Clone, Serialize)]
+pub struct DixNotification {
+    pub kind: NotificationKind,
+    pub post_id: Option<String>,
+    /// The activity rows behind it, for marking it read
+    pub activity_ids: Vec<i64>,
+    /// People we can name, newest first
+    pub actors: Vec<String>,
+    pub count: u32,
+    pub summary: String,
+    pub latest_at: i64,
+    pub read: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NotificationFeed {
+    pub notifications: Vec<DixNotification>,
+    /// Unread entries
+    pub unread: u32,
+}
+
+/// Likes and reposts since `seen`; nothing for a count that went down
+pub fn engagement_activity(post_id: &str, seen: EngagementCounts, now: EngagementCounts, at: i64) -> Vec<DixActivity> {
+    [
+        (NotificationKind::Like, seen.likes, now.likes),
+        (NotificationKind::Repost, seen.reposts, now.reposts),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| after > before)
+    .map(|(kind, before, after)| {
+        let source_id = format!("{}:{}:{}", kind.as_str(), post_id, after);
+        DixActivity::new(source_id, kind, Some(post_id.to_string()), (after - before) as u32, at)
+    })
+    .collect()
+}
+
+/// A reply to one of our posts
+pub fn reply_activity(parent_id: &str, reply: &DixPost) -> DixActivity {
+    DixActivity::new(
+        format!("reply:{}", reply.id),
+        NotificationKind::Reply,
+        Some(parent_id.to_string()),
+        1,
+        post_time(reply),
+    )
+    .by(&reply.author.public_key, reply.author.handle.clone())
+}
+
+/// A post mentioning us
+pub fn mention_activity(post: &DixPost) -> DixActivity {
+    DixActivity::new(
+        format!("mention:{}", post.id),
+        NotificationKind::Mention,
+        Some(post.id.clone()),
+        1,
+        post_time(post),
+    )
+    .by(&post.author.public_key, post.author.handle.clone())
+}
+
+/// Someone following us
+pub fn follow_activity(public_key: &str, handle: Option<String>, at: i64) -> DixActivity {
+    DixActivity::new(format!("follow:{}", public_key.to_lowercase()), NotificationKind::Follow, None, 1, at)
+        .by(public_key, handle)
+}
+
+fn post_time(post: &DixPost) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(&post.meta.created_at)
+        .map(|t| t.timestamp_millis())
+        .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis())
+}
+
+/// Fold activity (newest first) into feed entries, newest first.
+/// Mentions stay one per post; follows fold together.
+pub fn build_feed(activity: &[DixActivity]) -> NotificationFeed {
+    let mut order: Vec<(NotificationKind, Option<String>, bool)> = Vec::new();
+    let mut groups: HashMap<(NotificationKind, Option<String>, bool), Vec<&DixActivity>> = HashMap::new();
+    for item in activity {
+        let post_id = match item.kind {
+            NotificationKind::Follow => None,
+            _ => item.post_id.clone(),
+        };
+        let key = (item.kind, post_id, item.read);
+        if !groups.contains_key(&key) {
+            order.push(key.clone());
+        }
+        groups.entry(key).or_default().push(item);
+    }
+
+    let mut notifications: Vec<DixNotification> = order
+        .into_iter()
+        .map(|key| {
+            let items = &groups[&key];
+            let (kind, post_id, read) = key;
+            let mut actors: Vec<String> = Vec::new();
+            for name in items.iter().filter_map(|i| i.actor_name()) {
+                if !actors.contains(&name) {
+                    actors.push(name);
+                }
+            }
+            let count = items.iter().map(|i| i.count).sum();
+            DixNotification {
+                kind,
+                post_id,
+                activity_ids: items.iter().map(|i| i.id).collect(),
+                summary: summarize(kind, &actors, count),
+                actors,
+                count,
+                latest_at: items.iter().map(|i| i.created_at).max().unwrap_or(0),
+                read,
+            }
+        })
+        .collect();
+    notifications.sort_by_key(|n| std::cmp::Reverse(n.latest_at));
+
+    NotificationFeed {
+        unread: notifications.iter().filter(|n| !n.read).count() as u32,
+        notifications,
+    }
+}
+
+fn summarize(kind: NotificationKind, actors: &[String], count: u32) -> String {
+    let verb = kind.verb();
+    match (actors.first(), count) {
+        (Some(actor), 0 | 1) => format!("{} {}", actor, verb),
+        (Some(actor), 2) => format!("{} and 1 other {}", actor, verb),
+        (Some(actor), n) => format!("{} and {} others {}", actor, n - 1, verb),
+        (None, 0 | 1) => format!("Someone {}", verb),
+        (None, n) => format!("{} people {}", n, verb),
+    }
+}
+
+/// Poll for new activity every few minutes while there's an identity
+pub fn start_notification_poller(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = poll(&app_handle).await {
+                tracing::debug!("Dix notification poll failed: {}", e);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Record whatever happened since the last poll and tell the UI if any
+/// of it is new
+async fn poll(app_handle: &AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let (public_key, handle) = {
+        let identity = state.identity.lock().await;
+        let Some(public_key) = identity.public_key_hex() else {
+            return Ok(());
+        };
+        (public_key, identity.cached_handle())
+    };
+    let pk = public_key.clone();
+    let baseline_done = state
+        .database
+        .call(move |db| db.get_setting(BASELINE_SETTING).is_some_and(|p| p == pk))
+        .await;
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let posts = state.dix.get_posts_by_user(&public_key).await?.posts;
+    let ids: Vec<String> = posts.iter().map(|p| p.id.clone()).collect();
+    let seen = state
+        .database
+        .call(move |db| db.get_dix_engagement_seen(&ids))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut activity = Vec::new();
+    let mut counts = Vec::new();
+    for post in &posts {
+        let current = EngagementCounts::from(&post.engagement);
+        let before = seen.get(&post.id).copied().unwrap_or_default();
+        activity.extend(engagement_activity(&post.id, before, current, now));
+        // The first poll only takes note; no need to fetch every thread
+        if baseline_done && current.replies > before.replies {
+            let replies = state.dix.get_post(&post.id).await?.replies;
+            activity.extend(
+                replies
+                    .iter()
+                    .filter(|r| !r.author.public_key.eq_ignore_ascii_case(&public_key) && verify_post(r))
+                    .map(|r| reply_activity(&post.id, r)),
+            );
+        }
+        counts.push((post.id.clone(), current));
+    }
+
+    for follower in state.dix.get_followers(&public_key).await? {
+        activity.push(follow_activity(&follower.public_key, follower.handle, now));
+    }
+    if let Some(handle) = handle {
+        let mentions = state.dix.get_mentions(&handle, MENTIONS_PER_POLL, 0).await?;
+        // Cached so the unified inbox can show what was said
+        let cached = mentions.clone();
+        if let Err(e) = state.database.call(move |db| db.upsert_dix_posts(&cached)).await {
+            tracing::warn!("Failed to cache mentions: {}", e);
+        }
+        activity.extend(
+            mentions
+                .iter()
+                .filter(|p| !p.author.public_key.eq_ignore_ascii_case(&public_key))
+                .map(mention_activity),
+        );
+    }
+
+    let feed = state
+        .database
+        .call(move |db| {
+            let added = db.record_dix_activity(&activity, !baseline_done)?;
+            db.set_dix_engagement_seen(&counts)?;
+            if !baseline_done {
+                db.set_setting(BASELINE_SETTING, &public_key)?;
+            }
+            if added == 0 || !baseline_done {
+                return Ok(None);
+            }
+            Ok::<_, crate::storage::DatabaseError>(Some(build_feed(&db.list_dix_activity(FEED_LIMIT)?)))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(feed) = feed {
+        let _ = app_handle.emit("notifications_updated", &feed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn like(id: i64, post_id: &str, count: u32, at: i64, read: bool) -> DixActivity {
+        DixActivity {
+            id,
+            read,
+            ..DixActivity::new(format!("like:{}:{}", post_id, id), NotificationKind::Like, Some(post_id.to_string()), count, at)
+        }
+    }
+
+    #[test]
+    fn test_engagement_activity() {
+        let seen = EngagementCounts { likes: 2, reposts: 1, replies: 0 };
+        let now = EngagementCounts { likes: 5, reposts: 0, replies: 1 };
+        let activity = engagement_activity("p1", seen, now, 1_000);
+        // Replies are recorded one by one, and an unrepost is no news
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].kind, NotificationKind::Like);
+        assert_eq!(activity[0].count, 3);
+        assert_eq!(activity[0].source_id, "like:p1:5");
+    }
+
+    #[test]
+    fn test_build_feed() {
+        let activity = vec![
+            follow_activity(&"ab".repeat(32), Some("alice".to_string()), 5_000),
+            like(3, "p1", 2, 4_000, false),
+            follow_activity(&"cd".repeat(32), None, 3_000),
+            like(2, "p1", 1, 2_000, false),
+            like(1, "p1", 4, 1_000, true),
+        ];
+        let feed = build_feed(&activity);
+        assert_eq!(feed.notifications.len(), 3);
+        assert_eq!(feed.unread, 2);
+
+        assert_eq!(feed.notifications[0].summary, "@alice and 1 other followed you");
+        assert_eq!(feed.notifications[0].actors, vec!["@alice".to_string(), "cdcdcdcd…".to_string()]);
+        assert_eq!(feed.notifications[1].summary, "3 people liked your post");
+        assert_eq!(feed.notifications[1].activity_ids, vec![3, 2]);
+        // Read activity isn't folded into the unread entry
+        assert!(feed.notifications[2].read);
+        assert_eq!(feed.notifications[2].count, 4);
+    }
+}
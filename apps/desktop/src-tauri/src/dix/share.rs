@@ -0,0 +1,103 @@
+//! Sharing between Dix and chats
+//!
+//! A post shared into a thread travels as a `gns/dix-ref` message holding
+//! a snapshot of the post, author signature included, so the recipient
+//! can check it without asking the server.
+//!
+//! The other way, a chat message can only be quoted on Dix if its sender
+//! agreed: sending a chat payload with `dix_quote_consent: true` has
+//! `send_message` replace the flag with the sender's signature over the
+//! text. That signature travels with the quote on the post, and
+//! `verify_post` checks it like a poll's.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{generate_canonical_json, DixPost};
+use gns_crypto_core::signing::verify_signature_hex;
+
+/// Payload type for a post shared into a thread
+pub const DIX_REF_PAYLOAD_TYPE: &str = "gns/dix-ref";
+
+/// Chat payload field holding the sender's consent to be quoted on Dix
+pub const QUOTE_CONSENT_FIELD: &str = "dix_quote_consent";
+
+/// The `gns/dix-ref` payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DixRef {
+    pub post: DixPost,
+    /// What the sharer said about it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// A sender's consent to have a message's text quoted on Dix
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuoteConsent {
+    /// When the consent was given (ms)
+    pub signed_at: i64,
+    pub signature: String,
+}
+
+/// A chat message quoted in a post, signed by whoever wrote it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotedMessage {
+    pub author: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_handle: Option<String>,
+    pub text: String,
+    pub signed_at: i64,
+    pub signature: String,
+}
+
+impl QuotedMessage {
+    pub fn new(author: &str, author_handle: Option<String>, text: &str, consent: QuoteConsent) -> Self {
+        Self {
+            author: author.to_lowercase(),
+            author_handle,
+            text: text.to_string(),
+            signed_at: consent.signed_at,
+            signature: consent.signature,
+        }
+    }
+}
+
+/// What the sender signs to let `text` be quoted
+pub fn quote_signing_message(author: &str, text: &str, signed_at: i64) -> String {
+    generate_canonical_json(&json!({
+        "type": "dix_quote_consent",
+        "author": author.to_lowercase(),
+        "text": text,
+        "signed_at": signed_at,
+    }))
+}
+
+/// Check the quoted text is what its author agreed to have quoted
+pub fn verify_quoted_message(quote: &QuotedMessage) -> bool {
+    let message = quote_signing_message(&quote.author, &quote.text, quote.signed_at);
+    verify_signature_hex(&quote.author, message.as_bytes(), &quote.signature).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::GnsIdentity;
+
+    #[test]
+    fn test_quote_consent() {
+        let author = GnsIdentity::generate();
+        let pk = author.public_key_hex();
+        let message = quote_signing_message(&pk, "See you at noon", 1_000);
+        let consent = QuoteConsent {
+            signed_at: 1_000,
+            signature: hex::encode(author.sign(message.as_bytes()).to_bytes()),
+        };
+
+        let quote = QuotedMessage::new(&pk, None, "See you at noon", consent.clone());
+        assert!(verify_quoted_message(&quote));
+        // Consent covers that text only
+        assert!(!verify_quoted_message(&QuotedMessage::new(&pk, None, "See you at one", consent.clone())));
+        let other = GnsIdentity::generate().public_key_hex();
+        assert!(!verify_quoted_message(&QuotedMessage::new(&other, None, "See you at noon", consent)));
+    }
+}
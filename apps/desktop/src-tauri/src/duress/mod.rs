@@ -0,0 +1,58 @@
+//! Duress - A second PIN that unlocks a decoy identity
+//!
+//! For users who may be forced to unlock the app. The duress PIN unlocks a
+//! separate identity with its own empty database, indistinguishable in the
+//! UI from the real one; the real PIN switches back. The decoy's keychain
+//! entries and database file have neutral names so storage doesn't
+//! advertise which is which.
+
+use crate::storage::Database;
+use crate::AppState;
+
+/// Which of the local identities is active
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Persona {
+    #[default]
+    Primary,
+    Decoy,
+}
+
+impl Persona {
+    /// Appended to keychain entry names and the database file name
+    pub fn storage_suffix(self) -> &'static str {
+        match self {
+            Self::Primary => "",
+            Self::Decoy => ".2",
+        }
+    }
+}
+
+/// Make `persona` the active identity and database
+///
+/// The relay is dropped so the keepalive reconnects as the new identity,
+/// and LAN discovery moves to the new identity's key.
+pub async fn switch_persona(state: &AppState, persona: Persona) -> Result<(), String> {
+    let mut identity = state.identity.lock().await;
+    if identity.persona() == persona {
+        return Ok(());
+    }
+
+    state
+        .database
+        .call(move |db| Database::open_persona(persona).map(|opened| *db = opened))
+        .await
+        .map_err(|e| e.to_string())?;
+    identity.switch_persona(persona).map_err(|e| e.to_string())?;
+    let public_key = identity.public_key_hex();
+    drop(identity);
+    state.lan.set_public_key(public_key);
+
+    // Anything queued was signed by the other identity
+    {
+        let relay = state.relay.lock().await;
+        let _ = relay.disconnect().await;
+        relay.clear_outbox();
+    }
+    state.relay_keepalive.wake();
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! In-process directory API: answers identity and handle lookups for the
+//! identities a test registers.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use gns_crypto_core::GnsIdentity;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Directory records, by `/identities/<pk>` and `/handles/<handle>` path
+type Records = Arc<Mutex<HashMap<String, serde_json::Value>>>;
+
+pub struct MockApi {
+    addr: SocketAddr,
+    records: Records,
+}
+
+impl MockApi {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api = Self {
+            addr: listener.local_addr().unwrap(),
+            records: Records::default(),
+        };
+        let records = api.records.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, records.clone()));
+            }
+        });
+        api
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Publish an identity under a handle
+    pub fn register(&self, identity: &GnsIdentity, handle: &str) {
+        let record = serde_json::json!({
+            "public_key": identity.public_key_hex(),
+            "encryption_key": identity.encryption_key_hex(),
+            "handle": handle,
+        });
+        let mut records = self.records.lock().unwrap();
+        records.insert(format!("/identities/{}", identity.public_key_hex()), record.clone());
+        records.insert(format!("/handles/{}", handle), record);
+    }
+}
+
+async fn serve(mut stream: TcpStream, records: Records) {
+    // GET requests only; everything we need is in the request line
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 16 * 1024 {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    let path = head.split_whitespace().nth(1).unwrap_or_default();
+
+    let record = records.lock().unwrap().get(path).cloned();
+    let (status, body) = match record {
+        Some(data) => ("200 OK", serde_json::json!({ "success": true, "data": data })),
+        None => ("404 Not Found", serde_json::json!({ "success": false })),
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
@@ -0,0 +1,129 @@
+//! In-process relay: routes envelope frames between connected identities
+//! and acks them the way the production relay does.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::network::frame::RelayFrame;
+
+type Clients = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<String>>>>;
+
+pub struct MockRelay {
+    addr: SocketAddr,
+    clients: Clients,
+    /// Every frame received from any client
+    frames: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+impl MockRelay {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let relay = Self {
+            addr: listener.local_addr().unwrap(),
+            clients: Clients::default(),
+            frames: Arc::default(),
+        };
+        let (clients, frames) = (relay.clients.clone(), relay.frames.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, clients.clone(), frames.clone()));
+            }
+        });
+        relay
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}/ws", self.addr)
+    }
+
+    pub fn is_connected(&self, public_key: &str) -> bool {
+        self.clients.lock().unwrap().contains_key(public_key)
+    }
+
+    /// Frames of one type received so far
+    pub fn frames_of(&self, frame_type: &str) -> Vec<serde_json::Value> {
+        self.frames
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|frame| frame["type"] == frame_type)
+            .cloned()
+            .collect()
+    }
+}
+
+// The handshake callback's error type is tungstenite's, not ours
+#[allow(clippy::result_large_err)]
+async fn serve(stream: TcpStream, clients: Clients, frames: Arc<Mutex<Vec<serde_json::Value>>>) {
+    // The client's key comes in the query string: `?pk=...&device=...`
+    let mut public_key = String::new();
+    let callback = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        public_key = request
+            .uri()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("pk="))
+            .unwrap_or_default()
+            .to_string();
+        Ok(response)
+    };
+    let Ok(ws) = tokio_tungstenite::accept_hdr_async(stream, callback).await else {
+        return;
+    };
+    let (mut write, mut read) = ws.split();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let _ = tx.send(RelayFrame::Welcome { public_key: public_key.clone() }.to_json());
+    clients.lock().unwrap().insert(public_key.clone(), tx.clone());
+    tokio::spawn(async move {
+        while let Some(text) = rx.recv().await {
+            if write.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(message)) = read.next().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) else {
+            continue;
+        };
+        frames.lock().unwrap().push(json.clone());
+
+        let Ok(RelayFrame::Message { id, envelope }) = serde_json::from_value::<RelayFrame>(json) else {
+            continue;
+        };
+        let id = id.unwrap_or_else(|| envelope.id.clone());
+        let ack = |status: &str| {
+            RelayFrame::Ack {
+                id: id.clone(),
+                status: status.to_string(),
+                error: None,
+            }
+            .to_json()
+        };
+        let _ = tx.send(ack("relayed"));
+
+        let forward = RelayFrame::Message { id: None, envelope: envelope.clone() }.to_json();
+        let delivered = envelope
+            .to_public_keys
+            .iter()
+            .filter_map(|pk| clients.lock().unwrap().get(pk).cloned())
+            .filter(|client| client.send(forward.clone()).is_ok())
+            .count();
+        if delivered > 0 {
+            let _ = tx.send(ack("delivered"));
+        }
+    }
+    clients.lock().unwrap().remove(&public_key);
+}
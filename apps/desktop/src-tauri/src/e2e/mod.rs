@@ -0,0 +1,240 @@
+//! End-to-End Tests - Two devices talking through a mock relay
+//!
+//! Built only by `cargo test --features e2e`. Each test starts an
+//! in-process relay and directory API, then runs two app instances on
+//! Tauri's mock runtime (in-memory databases, no keychain) through
+//! send → relay → message handler → storage, asserting on what each side
+//! stored and emitted.
+
+mod mock_api;
+mod mock_relay;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gns_crypto_core::GnsIdentity;
+use serde_json::{json, Value};
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::{App, Listener, Manager};
+
+use crate::app_lock::{AppLock, LockConfig};
+use crate::attachments::AttachmentCache;
+use crate::commands::messaging::{send_message, SendResult};
+use crate::crypto::IdentityManager;
+use crate::deep_link::DeepLinkQueue;
+use crate::dix::DixService;
+use crate::lan::LanTransport;
+use crate::local_api::LocalApi;
+use crate::rules::ScriptRequests;
+use crate::location::sync::BreadcrumbSync;
+use crate::network::keepalive::RelayKeepalive;
+use crate::network::{ApiClient, IncomingMessage, RelayConnection};
+use crate::presence::PresenceTracker;
+use crate::rate_limit::RateLimiter;
+use crate::stellar::StellarService;
+use crate::storage::{Database, DatabaseHandle};
+use crate::AppState;
+use mock_api::MockApi;
+use mock_relay::MockRelay;
+
+/// Events the UI listens for that tests assert on
+const EVENTS: &[&str] = &["new_message", "contact_request", "message_status", "envelope_rejected"];
+
+/// How long to wait for something to arrive
+const WAIT: Duration = Duration::from_secs(10);
+
+/// One running app instance
+struct TestPeer {
+    app: App<MockRuntime>,
+    public_key: String,
+    events: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl TestPeer {
+    async fn start(handle: &str, relay: &MockRelay, api: &MockApi) -> Self {
+        let identity = GnsIdentity::generate();
+        let public_key = identity.public_key_hex();
+        api.register(&identity, handle);
+
+        let attachments = std::env::temp_dir().join(format!("gns-e2e-{}", uuid::Uuid::new_v4()));
+        let database = DatabaseHandle::spawn(Database::open_in_memory().unwrap()).unwrap();
+        let identity = Arc::new(tokio::sync::Mutex::new(IdentityManager::with_identity(identity)));
+        let api = Arc::new(ApiClient::new(&api.url()).unwrap());
+        let relay_connection = RelayConnection::new(&relay.url()).unwrap().with_store(database.clone());
+        let state = AppState {
+            identity: identity.clone(),
+            database: database.clone(),
+            api: api.clone(),
+            relay: Arc::new(tokio::sync::Mutex::new(relay_connection)),
+            stellar: Arc::new(tokio::sync::Mutex::new(StellarService::mainnet())),
+            dix: Arc::new(DixService::new(identity.clone(), api)),
+            breadcrumb_sync: Arc::new(BreadcrumbSync::new()),
+            relay_keepalive: Arc::new(RelayKeepalive::new()),
+            deep_links: Arc::new(DeepLinkQueue::new()),
+            attachments: Arc::new(AttachmentCache::new(attachments, 1024 * 1024)),
+            app_lock: Arc::new(AppLock::new(LockConfig::default())),
+            rate_limiter: Arc::new(RateLimiter::new()),
+            presence: Arc::new(PresenceTracker::new()),
+            lan: Arc::new(LanTransport::new(false)),
+            local_api: Arc::new(LocalApi::new(false)),
+            rule_scripts: Arc::new(ScriptRequests::new()),
+        };
+        let relay_handle = state.relay.clone();
+
+        let app = mock_builder().build(mock_context(noop_assets())).unwrap();
+        app.manage(state);
+
+        let events: Arc<Mutex<Vec<(String, Value)>>> = Arc::default();
+        for event in EVENTS {
+            let events = events.clone();
+            let name = event.to_string();
+            app.listen_any(*event, move |e| {
+                let payload = serde_json::from_str(e.payload()).unwrap_or(Value::Null);
+                events.lock().unwrap().push((name.clone(), payload));
+            });
+        }
+
+        // Wired up the way `run()` does it
+        let (incoming_tx, incoming_rx) = tokio::sync::mpsc::channel::<IncomingMessage>(32);
+        crate::message_handler::start_message_handler(
+            app.handle().clone(),
+            identity,
+            database,
+            relay_handle.clone(),
+            incoming_rx,
+        );
+        let connection = relay_handle.lock().await.clone_with_incoming_channel(incoming_tx);
+        connection.connect(&public_key).await.unwrap();
+
+        let peer = Self {
+            app,
+            public_key,
+            events,
+        };
+        wait_until(|| relay.is_connected(&peer.public_key)).await;
+        peer
+    }
+
+    fn state(&self) -> tauri::State<'_, AppState> {
+        self.app.state::<AppState>()
+    }
+
+    async fn send_text(&self, to: &TestPeer, text: &str) -> SendResult {
+        send_message(
+            None,
+            Some(to.public_key.clone()),
+            "text/plain".to_string(),
+            json!({ "text": text }),
+            None,
+            None,
+            self.app.handle().clone(),
+            self.state(),
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Wait for an event whose payload satisfies `matches`
+    async fn expect_event(&self, name: &str, matches: impl Fn(&Value) -> bool) -> Value {
+        let found = || {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(event, payload)| event == name && matches(payload))
+                .map(|(_, payload)| payload.clone())
+        };
+        wait_until(|| found().is_some()).await;
+        found().unwrap()
+    }
+
+    fn saw_event(&self, name: &str) -> bool {
+        self.events.lock().unwrap().iter().any(|(event, _)| event == name)
+    }
+
+    /// Treat `peer` as a verified contact
+    async fn add_contact(&self, peer: &TestPeer, api: &MockApi) {
+        let info = ApiClient::new(&api.url()).unwrap().get_identity(&peer.public_key).await.unwrap().unwrap();
+        let pk = peer.public_key.clone();
+        self.state()
+            .database
+            .call(move |db| {
+                db.record_contact_key(&pk, &info.encryption_key, 0)?;
+                db.set_contact_verified(&pk, true, 0)
+            })
+            .await
+            .unwrap();
+    }
+}
+
+async fn wait_until(done: impl Fn() -> bool) {
+    let deadline = tokio::time::Instant::now() + WAIT;
+    while !done() {
+        assert!(tokio::time::Instant::now() < deadline, "timed out");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_message_to_contact() {
+    let (relay, api) = (MockRelay::start().await, MockApi::start().await);
+    let alice = TestPeer::start("alice", &relay, &api).await;
+    let bob = TestPeer::start("bob", &relay, &api).await;
+    bob.add_contact(&alice, &api).await;
+
+    let sent = alice.send_text(&bob, "hello bob").await;
+
+    // Bob stores and announces it
+    let event = bob.expect_event("new_message", |e| e["id"] == sent.message_id).await;
+    assert_eq!(event["from_public_key"], alice.public_key);
+    assert_eq!(event["payload"]["text"], "hello bob");
+    assert_eq!(event["is_junk"], false);
+    let thread_id = event["thread_id"].as_str().unwrap().to_string();
+    let tid = thread_id.clone();
+    let messages = bob.state().database.call(move |db| db.get_messages(&tid, 10)).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert!(!messages[0].is_outgoing);
+
+    // Alice sees the relay's acks move the message on
+    alice.expect_event("message_status", |e| e["id"] == sent.message_id).await;
+    let id = sent.message_id.clone();
+    let stored = alice.state().database.call(move |db| db.get_message(&id)).await.unwrap().unwrap();
+    assert!(["relayed", "delivered"].contains(&stored.status.as_str()), "{}", stored.status);
+    assert_eq!(relay.frames_of("message").len(), 1);
+
+    // Alice wrote first, so Bob's reply goes straight to her inbox
+    let reply = bob.send_text(&alice, "hi alice").await;
+    let event = alice.expect_event("new_message", |e| e["id"] == reply.message_id).await;
+    assert_eq!(event["thread_id"], thread_id.as_str());
+    assert!(!alice.saw_event("contact_request"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_first_contact_is_held() {
+    let (relay, api) = (MockRelay::start().await, MockApi::start().await);
+    let alice = TestPeer::start("alice", &relay, &api).await;
+    let bob = TestPeer::start("bob", &relay, &api).await;
+
+    let sent = alice.send_text(&bob, "hello stranger").await;
+
+    let event = bob.expect_event("contact_request", |e| e["id"] == sent.message_id).await;
+    assert_eq!(event["is_request"], true);
+    assert!(!bob.saw_event("new_message"));
+
+    let requests = bob.state().database.call(|db| db.list_contact_requests()).await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].public_key, alice.public_key);
+    assert_eq!(requests[0].message_count, 1);
+
+    // Accepting releases the held message into a thread
+    let (pk, key) = (alice.public_key.clone(), "ab".repeat(32));
+    let threads = bob
+        .state()
+        .database
+        .call(move |db| db.accept_contact_request(&pk, &key, 1))
+        .await
+        .unwrap();
+    let tid = threads[0].clone();
+    let messages = bob.state().database.call(move |db| db.get_messages(&tid, 10)).await.unwrap();
+    assert_eq!(messages[0].payload["text"], "hello stranger");
+}
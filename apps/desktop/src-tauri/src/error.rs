@@ -0,0 +1,226 @@
+//! App Error - The error every command returns
+//!
+//! Commands used to flatten failures into strings, which left the UI
+//! matching on message text to tell "offline" from "no identity". An
+//! `AppError` reaches the frontend as
+//! `{ "code": "offline", "message": "...", "context": {...} }`: `code` is
+//! stable and safe to branch on, `message` is for display, and `context`
+//! carries the details of the variant, or null.
+//!
+//! The module errors convert into it, so `?` keeps working in commands.
+//! Plain strings become `other`.
+
+use gns_crypto_core::CryptoError;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+
+use crate::commands::handles::HandleError;
+use crate::crypto::IdentityError;
+use crate::network::NetworkError;
+use crate::resolver::ResolveError;
+use crate::stellar::StellarError;
+use crate::storage::DatabaseError;
+use crate::validation::ValidationError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("No identity configured")]
+    NoIdentity,
+
+    #[error("Offline: {reason}")]
+    Offline { reason: String },
+
+    #[error("{reason}")]
+    InvalidHandle { reason: String },
+
+    #[error("{what} not found")]
+    NotFound { what: String },
+
+    #[error("{0}")]
+    Validation(#[from] ValidationError),
+
+    #[error("Server error: {reason}")]
+    Server { reason: String },
+
+    #[error("Database error: {reason}")]
+    Database { reason: String },
+
+    #[error("Crypto error: {reason}")]
+    Crypto { reason: String },
+
+    #[error("Payment error: {reason}")]
+    Payment { reason: String },
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AppError {
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Self::NotFound { what: what.into() }
+    }
+
+    /// Stable code the UI can branch on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoIdentity => "no_identity",
+            Self::Offline { .. } => "offline",
+            Self::InvalidHandle { .. } => "invalid_handle",
+            Self::NotFound { .. } => "not_found",
+            Self::Validation(_) => "validation",
+            Self::Server { .. } => "server",
+            Self::Database { .. } => "database",
+            Self::Crypto { .. } => "crypto",
+            Self::Payment { .. } => "payment",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// Details of the variant, null when there are none
+    pub fn context(&self) -> Value {
+        match self {
+            Self::NoIdentity | Self::Other(_) => Value::Null,
+            Self::NotFound { what } => json!({ "what": what }),
+            Self::Validation(e) => serde_json::to_value(e).unwrap_or(Value::Null),
+            Self::Offline { reason }
+            | Self::InvalidHandle { reason }
+            | Self::Server { reason }
+            | Self::Database { reason }
+            | Self::Crypto { reason }
+            | Self::Payment { reason } => json!({ "reason": reason }),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 3)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("context", &self.context())?;
+        error.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+impl From<NetworkError> for AppError {
+    fn from(e: NetworkError) -> Self {
+        match e {
+            NetworkError::ConnectionError(_) | NetworkError::RequestError(_) | NetworkError::NotConnected => {
+                Self::Offline { reason: e.to_string() }
+            }
+            NetworkError::ClientError(_) | NetworkError::ApiError(_) | NetworkError::ParseError(_) => {
+                Self::Server { reason: e.to_string() }
+            }
+        }
+    }
+}
+
+impl From<DatabaseError> for AppError {
+    fn from(e: DatabaseError) -> Self {
+        Self::Database { reason: e.to_string() }
+    }
+}
+
+impl From<IdentityError> for AppError {
+    fn from(e: IdentityError) -> Self {
+        match e {
+            IdentityError::NoIdentity => Self::NoIdentity,
+            IdentityError::KeychainError(_) | IdentityError::InvalidKey(_) => Self::Crypto { reason: e.to_string() },
+        }
+    }
+}
+
+impl From<CryptoError> for AppError {
+    fn from(e: CryptoError) -> Self {
+        Self::Crypto { reason: e.to_string() }
+    }
+}
+
+impl From<HandleError> for AppError {
+    fn from(e: HandleError) -> Self {
+        match e {
+            HandleError::NetworkError(reason) => Self::Offline { reason },
+            e => Self::InvalidHandle { reason: e.to_string() },
+        }
+    }
+}
+
+impl From<StellarError> for AppError {
+    fn from(e: StellarError) -> Self {
+        match e {
+            StellarError::NetworkError(reason) => Self::Offline { reason },
+            StellarError::AccountNotFound => Self::not_found("Account"),
+            e => Self::Payment { reason: e.to_string() },
+        }
+    }
+}
+
+impl From<ResolveError> for AppError {
+    fn from(e: ResolveError) -> Self {
+        match e {
+            ResolveError::Network(e) => e.into(),
+            ResolveError::InvalidRecord(_) => Self::Server { reason: e.to_string() },
+            ResolveError::Cache(reason) => Self::Database { reason },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_error_serialization() {
+        let offline = AppError::from(NetworkError::ConnectionError("timed out".to_string()));
+        assert_eq!(
+            serde_json::to_value(&offline).unwrap(),
+            json!({
+                "code": "offline",
+                "message": "Offline: Connection error: timed out",
+                "context": { "reason": "Connection error: timed out" },
+            })
+        );
+
+        let missing = AppError::from(IdentityError::NoIdentity);
+        assert_eq!(serde_json::to_value(&missing).unwrap()["code"], "no_identity");
+        assert_eq!(serde_json::to_value(&missing).unwrap()["context"], Value::Null);
+
+        let handle = AppError::from(HandleError::Reserved);
+        assert_eq!(handle.code(), "invalid_handle");
+        assert_eq!(handle.to_string(), "This handle is reserved");
+
+        let invalid = AppError::from(ValidationError::MissingField { field: "text".to_string() });
+        assert_eq!(
+            invalid.context(),
+            json!({ "code": "missing_field", "field": "text" })
+        );
+
+        assert_eq!(AppError::not_found("Thread").to_string(), "Thread not found");
+        assert_eq!(AppError::from("Label too long").code(), "other");
+    }
+}
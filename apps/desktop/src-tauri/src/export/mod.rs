@@ -0,0 +1,370 @@
+//! Export Module - Message export formats
+//!
+//! Renders stored threads as JSON, Markdown or EML, and bundles multiple
+//! files (per-message EML, attachments) into a zip archive. Writing the
+//! result to disk is left to the commands. `payments` renders Stellar
+//! payment history the same way.
+
+pub mod payments;
+
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::commands::messaging::{Message, ThreadPreview};
+
+/// Domain used for GNS handles in email addresses
+const EMAIL_DOMAIN: &str = "gcrumbs.com";
+
+/// Bump when the JSON export layout changes
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Zip archive (JSON + attachments) sealed to the user's own key
+    Encrypted,
+    Json,
+    Markdown,
+    /// One .eml file per message (email threads only)
+    Eml,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Encrypted => "gnsarchive",
+            Self::Json => "json",
+            Self::Markdown => "md",
+            Self::Eml => "zip",
+        }
+    }
+}
+
+/// A thread and its messages, oldest first
+#[derive(Serialize)]
+pub struct ExportedThread {
+    pub thread: ThreadPreview,
+    pub messages: Vec<Message>,
+}
+
+impl ExportedThread {
+    pub fn is_email(&self) -> bool {
+        self.thread.subject.is_some() || self.messages.iter().any(is_email)
+    }
+
+    pub fn title(&self) -> String {
+        if let Some(subject) = &self.thread.subject {
+            return subject.clone();
+        }
+        match &self.thread.participant_handle {
+            Some(handle) => format!("Conversation with @{}", handle),
+            None => format!("Conversation with {}", short_key(&self.thread.participant_public_key)),
+        }
+    }
+}
+
+/// An attachment referenced by a message payload
+#[derive(Debug, Clone)]
+pub struct AttachmentRef {
+    pub message_id: String,
+    pub filename: String,
+    pub url: String,
+}
+
+pub fn is_email(message: &Message) -> bool {
+    message.payload_type == "email" || message.payload.get("subject").is_some()
+}
+
+/// Attachments listed in a message payload that can be downloaded
+pub fn attachments_of(message: &Message) -> Vec<AttachmentRef> {
+    let Some(list) = message.payload.get("attachments").and_then(|a| a.as_array()) else {
+        return vec![];
+    };
+
+    list.iter()
+        .enumerate()
+        .filter_map(|(i, a)| {
+            let url = a.get("url")?.as_str()?.to_string();
+            let filename = a
+                .get("filename")
+                .and_then(|f| f.as_str())
+                .map(sanitize_file_name)
+                .unwrap_or_else(|| format!("attachment-{}", i + 1));
+            Some(AttachmentRef {
+                message_id: message.id.clone(),
+                filename,
+                url,
+            })
+        })
+        .collect()
+}
+
+/// Text body of a message, whatever its payload type
+pub fn message_text(message: &Message) -> String {
+    ["body", "text"]
+        .iter()
+        .find_map(|k| message.payload.get(*k).and_then(|v| v.as_str()))
+        .map(String::from)
+        .unwrap_or_else(|| message.payload.to_string())
+}
+
+// ==================== Renderers ====================
+
+pub fn render_json(threads: &[ExportedThread]) -> Result<Vec<u8>, String> {
+    let export = json!({
+        "format": "gns-messages",
+        "version": EXPORT_VERSION,
+        "exportedAt": chrono::Utc::now().to_rfc3339(),
+        "threads": threads,
+    });
+    serde_json::to_vec_pretty(&export).map_err(|e| e.to_string())
+}
+
+pub fn render_markdown(threads: &[ExportedThread], own_handle: Option<&str>) -> String {
+    let mut out = String::new();
+
+    for thread in threads {
+        out.push_str(&format!("# {}\n\n", thread.title()));
+
+        for message in &thread.messages {
+            out.push_str(&format!(
+                "**{}** · {}\n\n",
+                sender_name(message, &thread.thread, own_handle),
+                format_timestamp(message.timestamp)
+            ));
+            for line in message_text(message).lines() {
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// A single message as an RFC 5322 email
+pub fn render_eml(message: &Message, thread: &ThreadPreview, own_handle: Option<&str>) -> String {
+    let own_address = address_for(own_handle, "me");
+    let other_address = match message.payload.get("from").and_then(|f| f.as_str()) {
+        Some(from) if !message.is_outgoing => header_text(from),
+        _ => address_for(thread.participant_handle.as_deref(), &thread.participant_public_key),
+    };
+    let (from, to) = if message.is_outgoing {
+        (own_address, other_address)
+    } else {
+        (other_address, own_address)
+    };
+
+    let subject = message
+        .payload
+        .get("subject")
+        .and_then(|s| s.as_str())
+        .or(thread.subject.as_deref())
+        .unwrap_or("(No subject)");
+
+    let date = chrono::DateTime::from_timestamp_millis(message.timestamp)
+        .map(|d| d.to_rfc2822())
+        .unwrap_or_default();
+
+    let body = message_text(message).replace("\r\n", "\n").replace('\n', "\r\n");
+
+    let mut eml = String::new();
+    eml.push_str(&format!("From: {}\r\n", from));
+    eml.push_str(&format!("To: {}\r\n", to));
+    eml.push_str(&format!("Subject: {}\r\n", encode_header(subject)));
+    eml.push_str(&format!("Date: {}\r\n", date));
+    eml.push_str(&format!("Message-ID: <{}@{}>\r\n", id_token(&message.id), EMAIL_DOMAIN));
+    if let Some(reply_to) = &message.reply_to_id {
+        eml.push_str(&format!("In-Reply-To: <{}@{}>\r\n", id_token(reply_to), EMAIL_DOMAIN));
+    }
+    eml.push_str("MIME-Version: 1.0\r\n");
+    eml.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+    eml.push_str("Content-Transfer-Encoding: 8bit\r\n");
+    eml.push_str("\r\n");
+    eml.push_str(&body);
+    eml.push_str("\r\n");
+    eml
+}
+
+/// Bundle files into a zip archive
+pub fn build_zip(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, bytes) in files {
+        writer.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+        writer.write_all(bytes).map_err(|e| e.to_string())?;
+    }
+
+    let cursor = writer.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+/// Make a string safe to use as a file name
+pub fn sanitize_file_name(name: &str) -> String {
+    let clean: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ' ') { c } else { '_' })
+        .collect();
+    let clean = clean.trim().trim_start_matches('.').to_string();
+    if clean.is_empty() {
+        "untitled".to_string()
+    } else {
+        clean.chars().take(100).collect()
+    }
+}
+
+// ==================== Helpers ====================
+
+fn sender_name(message: &Message, thread: &ThreadPreview, own_handle: Option<&str>) -> String {
+    if message.is_outgoing {
+        return own_handle.map(|h| format!("@{}", h)).unwrap_or_else(|| "Me".to_string());
+    }
+    message
+        .from_handle
+        .as_deref()
+        .or(thread.participant_handle.as_deref())
+        .map(|h| format!("@{}", h))
+        .unwrap_or_else(|| short_key(&message.from_public_key))
+}
+
+fn address_for(handle: Option<&str>, fallback: &str) -> String {
+    let address = match handle {
+        Some(h) if h.contains('@') => h.to_string(),
+        Some(h) => format!("{}@{}", h, EMAIL_DOMAIN),
+        None => format!("{}@{}", short_key(fallback), EMAIL_DOMAIN),
+    };
+    header_text(&address)
+}
+
+/// A remote string made safe for a header line: control characters,
+/// CR/LF included, become spaces so they can't start new headers
+fn header_text(value: &str) -> String {
+    value.chars().map(|c| if c.is_control() { ' ' } else { c }).collect()
+}
+
+/// An id made safe for `Message-ID` and `In-Reply-To`
+fn id_token(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect()
+}
+
+/// RFC 2047 encode a header value unless it is printable ASCII, so CR/LF
+/// and other control characters never reach the header raw
+fn encode_header(value: &str) -> String {
+    use base64::Engine;
+
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        value.to_string()
+    } else {
+        format!(
+            "=?utf-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(value.as_bytes())
+        )
+    }
+}
+
+fn format_timestamp(timestamp_ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|d| d.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn short_key(public_key: &str) -> String {
+    public_key.chars().take(16).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, outgoing: bool, payload: serde_json::Value) -> Message {
+        Message {
+            id: id.to_string(),
+            thread_id: "t1".to_string(),
+            from_public_key: "ab".repeat(32),
+            from_handle: if outgoing { None } else { Some("bob".to_string()) },
+            payload_type: "text/plain".to_string(),
+            payload,
+            timestamp: 1_704_067_200_000,
+            is_outgoing: outgoing,
+            status: "sent".to_string(),
+            reply_to_id: None,
+            is_starred: false,
+            forwarded_from_id: None,
+            quote_status: None,
+            reactions: vec![],
+        }
+    }
+
+    fn thread(subject: Option<&str>) -> ThreadPreview {
+        ThreadPreview {
+            id: "t1".to_string(),
+            participant_public_key: "ab".repeat(32),
+            participant_handle: Some("bob".to_string()),
+            last_message_preview: None,
+            last_message_at: 1_704_067_200_000,
+            unread_count: 0,
+            is_pinned: false,
+            is_muted: false,
+            subject: subject.map(String::from),
+            is_junk: false,
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let exported = ExportedThread {
+            thread: thread(None),
+            messages: vec![
+                message("m1", false, json!({"text": "hi"})),
+                message("m2", true, json!({"text": "hello"})),
+            ],
+        };
+        let md = render_markdown(&[exported], Some("alice"));
+        assert!(md.starts_with("# Conversation with @bob\n"));
+        assert!(md.contains("**@bob** · 2024-01-01 00:00 UTC\n\nhi\n"));
+        assert!(md.contains("**@alice**"));
+    }
+
+    #[test]
+    fn test_render_eml() {
+        let msg = message("m1", true, json!({"subject": "Café", "body": "line1\nline2"}));
+        let eml = render_eml(&msg, &thread(Some("Café")), Some("alice"));
+        assert!(eml.starts_with("From: alice@gcrumbs.com\r\nTo: bob@gcrumbs.com\r\n"));
+        assert!(eml.contains("Subject: =?utf-8?B?Q2Fmw6k=?=\r\n"));
+        assert!(eml.ends_with("\r\n\r\nline1\r\nline2\r\n"));
+    }
+
+    #[test]
+    fn test_eml_header_injection() {
+        let mut msg = message("m1\r\nBcc: eve@example.com", false, json!({
+            "subject": "Hi\r\nBcc: eve@example.com",
+            "from": "mallory@example.com\r\nX-Injected: 1",
+        }));
+        msg.reply_to_id = Some("m0>\r\nX-Injected: 2".to_string());
+        let eml = render_eml(&msg, &thread(None), Some("alice"));
+        let headers = eml.split("\r\n\r\n").next().unwrap();
+        assert!(headers.lines().all(|line| !line.starts_with("Bcc:") && !line.starts_with("X-Injected:")));
+        assert!(headers.contains("From: mallory@example.com  X-Injected: 1\r\n"));
+        assert!(headers.contains("Message-ID: <m1__Bcc__eve_example.com@"));
+    }
+
+    #[test]
+    fn test_attachments_and_file_names() {
+        let msg = message("m1", false, json!({"attachments": [
+            {"filename": "../secret.pdf", "url": "https://example.com/a"},
+            {"filename": "no-url.txt"},
+        ]}));
+        let attachments = attachments_of(&msg);
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].filename, "_secret.pdf");
+        // Message ids come from the sender and name zip entries too
+        assert_eq!(sanitize_file_name("../../m1"), "_.._m1");
+    }
+}
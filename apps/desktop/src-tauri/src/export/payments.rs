@@ -0,0 +1,138 @@
+//! Payment history export
+//!
+//! Stellar payments with their fiat value on the day they happened, as
+//! CSV for spreadsheets and tax tools, or JSON.
+
+use serde::{Deserialize, Serialize};
+
+use crate::stellar::PaymentHistoryItem;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentExportFormat {
+    Csv,
+    Json,
+}
+
+impl PaymentExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// One exported payment
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentRow {
+    /// RFC 3339, as Horizon reports it
+    pub created_at: String,
+    /// `sent` or `received`
+    pub direction: String,
+    pub asset_code: String,
+    pub amount: String,
+    /// The other side of the payment
+    pub counterparty: String,
+    pub tx_hash: String,
+    /// Fiat price of one unit on the day; None when unknown
+    pub unit_price: Option<f64>,
+    pub fiat_value: Option<f64>,
+    pub currency: String,
+}
+
+impl PaymentRow {
+    pub fn new(payment: &PaymentHistoryItem, unit_price: Option<f64>, currency: &str) -> Self {
+        let counterparty = if payment.direction == "sent" {
+            &payment.to_address
+        } else {
+            &payment.from_address
+        };
+        let fiat_value = unit_price
+            .zip(payment.amount.parse::<f64>().ok())
+            .map(|(price, amount)| (price * amount * 100.0).round() / 100.0);
+        Self {
+            created_at: payment.created_at.clone(),
+            direction: payment.direction.clone(),
+            asset_code: payment.asset_code.clone(),
+            amount: payment.amount.clone(),
+            counterparty: counterparty.clone(),
+            tx_hash: payment.tx_hash.clone(),
+            unit_price,
+            fiat_value,
+            currency: currency.to_uppercase(),
+        }
+    }
+}
+
+pub fn render(rows: &[PaymentRow], format: PaymentExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        PaymentExportFormat::Csv => Ok(render_csv(rows).into_bytes()),
+        PaymentExportFormat::Json => serde_json::to_vec_pretty(rows).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn render_csv(rows: &[PaymentRow]) -> String {
+    let mut csv = String::from("date,direction,asset,amount,counterparty,transaction,unit_price,fiat_value,currency\r\n");
+    for row in rows {
+        let fields = [
+            row.created_at.clone(),
+            row.direction.clone(),
+            row.asset_code.clone(),
+            row.amount.clone(),
+            row.counterparty.clone(),
+            row.tx_hash.clone(),
+            row.unit_price.map(|p| p.to_string()).unwrap_or_default(),
+            row.fiat_value.map(|v| format!("{:.2}", v)).unwrap_or_default(),
+            row.currency.clone(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payment(direction: &str, amount: &str, asset_code: &str) -> PaymentHistoryItem {
+        PaymentHistoryItem {
+            id: "1".to_string(),
+            tx_hash: "abc".to_string(),
+            created_at: "2024-03-05T10:00:00Z".to_string(),
+            direction: direction.to_string(),
+            amount: amount.to_string(),
+            asset_code: asset_code.to_string(),
+            from_address: "GFROM".to_string(),
+            to_address: "GTO".to_string(),
+            memo: None,
+        }
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let rows = [
+            PaymentRow::new(&payment("received", "100.0000000", "XLM"), Some(0.12), "usd"),
+            PaymentRow::new(&payment("sent", "5.0000000", "GNS"), None, "usd"),
+        ];
+        assert_eq!(rows[0].fiat_value, Some(12.0));
+        assert_eq!(rows[1].counterparty, "GTO");
+
+        let csv = render_csv(&rows);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "2024-03-05T10:00:00Z,received,XLM,100.0000000,GFROM,abc,0.12,12.00,USD");
+        assert_eq!(lines[2], "2024-03-05T10:00:00Z,sent,GNS,5.0000000,GTO,abc,,,USD");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
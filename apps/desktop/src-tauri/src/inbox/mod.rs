@@ -0,0 +1,163 @@
+//! Unified Inbox - Chats, email, contact requests and Dix mentions in one list
+//!
+//! The home screen shows everything waiting for the user in one list
+//! rather than asking four commands. Each source is turned into an
+//! `InboxItem` tagged with its kind. Items are ranked pinned first, then
+//! by latest activity, and served a page at a time.
+
+use serde::Serialize;
+
+use crate::commands::messaging::ThreadPreview;
+use crate::contact_requests::ContactRequest;
+use crate::dix::notifications::DixActivity;
+use crate::dix::DixPost;
+
+/// Longest preview text, in characters
+const PREVIEW_CHARS: usize = 140;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InboxItemKind {
+    Chat,
+    Email,
+    ContactRequest,
+    DixMention,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InboxItem {
+    pub kind: InboxItemKind,
+    /// Thread id, the requester's public key, or the mentioning post's id
+    pub id: String,
+    pub public_key: String,
+    pub handle: Option<String>,
+    /// Email subject
+    pub subject: Option<String>,
+    pub preview: Option<String>,
+    pub at: i64,
+    pub unread: u32,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InboxPage {
+    pub items: Vec<InboxItem>,
+    pub has_more: bool,
+}
+
+impl From<ThreadPreview> for InboxItem {
+    fn from(thread: ThreadPreview) -> Self {
+        Self {
+            kind: if thread.subject.is_some() {
+                InboxItemKind::Email
+            } else {
+                InboxItemKind::Chat
+            },
+            id: thread.id,
+            public_key: thread.participant_public_key,
+            handle: thread.participant_handle,
+            subject: thread.subject,
+            preview: thread.last_message_preview.map(|p| shorten(&p)),
+            at: thread.last_message_at,
+            unread: thread.unread_count,
+            pinned: thread.is_pinned,
+        }
+    }
+}
+
+impl From<ContactRequest> for InboxItem {
+    fn from(request: ContactRequest) -> Self {
+        Self {
+            kind: InboxItemKind::ContactRequest,
+            id: request.public_key.clone(),
+            public_key: request.public_key,
+            handle: request.handle,
+            subject: None,
+            preview: request.preview.map(|p| shorten(&p)),
+            at: request.updated_at,
+            unread: request.message_count,
+            pinned: false,
+        }
+    }
+}
+
+impl InboxItem {
+    /// A mention found by the notification poller, with the post if it's
+    /// cached; None for other activity
+    pub fn from_mention(activity: DixActivity, post: Option<&DixPost>) -> Option<Self> {
+        Some(Self {
+            kind: InboxItemKind::DixMention,
+            id: activity.post_id?,
+            public_key: activity.actor_public_key.unwrap_or_default(),
+            handle: activity.actor_handle,
+            subject: None,
+            preview: post.map(|p| shorten(&p.content.text)),
+            at: activity.created_at,
+            unread: u32::from(!activity.read),
+            pinned: false,
+        })
+    }
+}
+
+/// Rank items and cut out one page. Each source should have been asked
+/// for `offset + limit` items; `sources_truncated` says whether any of
+/// them had more than that.
+pub fn page(mut items: Vec<InboxItem>, offset: usize, limit: usize, sources_truncated: bool) -> InboxPage {
+    items.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.at.cmp(&a.at)));
+    let has_more = sources_truncated || items.len() > offset + limit;
+    InboxPage {
+        items: items.into_iter().skip(offset).take(limit).collect(),
+        has_more,
+    }
+}
+
+fn shorten(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= PREVIEW_CHARS {
+        return text;
+    }
+    let mut short: String = text.chars().take(PREVIEW_CHARS - 1).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: InboxItemKind, id: &str, at: i64, pinned: bool) -> InboxItem {
+        InboxItem {
+            kind,
+            id: id.to_string(),
+            public_key: "ab".repeat(32),
+            handle: None,
+            subject: None,
+            preview: None,
+            at,
+            unread: 0,
+            pinned,
+        }
+    }
+
+    #[test]
+    fn test_page() {
+        let items = vec![
+            item(InboxItemKind::Chat, "old-pinned", 100, true),
+            item(InboxItemKind::DixMention, "mention", 400, false),
+            item(InboxItemKind::ContactRequest, "request", 300, false),
+            item(InboxItemKind::Email, "email", 200, false),
+        ];
+        let first = page(items.clone(), 0, 2, false);
+        let ids: Vec<&str> = first.items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["old-pinned", "mention"]);
+        assert!(first.has_more);
+
+        let last = page(items, 2, 2, false);
+        let ids: Vec<&str> = last.items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["request", "email"]);
+        assert!(!last.has_more);
+
+        assert_eq!(shorten("a  b\nc"), "a b c");
+        assert_eq!(shorten(&"x".repeat(200)).chars().count(), PREVIEW_CHARS);
+    }
+}
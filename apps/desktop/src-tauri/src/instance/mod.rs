@@ -0,0 +1,41 @@
+//! Instance lock - Lets other processes see the app is running
+//!
+//! The app holds an exclusive lock on a file in its data directory for as
+//! long as it runs. `gns-cli` checks it before opening a relay connection
+//! of its own, which would compete with the app's for incoming messages.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Kept open, and so locked, until the process exits
+static HELD: OnceLock<File> = OnceLock::new();
+
+fn lock_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("gns-browser").join("app.lock"))
+}
+
+fn open_lock_file() -> Option<File> {
+    let path = lock_path()?;
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    OpenOptions::new().create(true).truncate(false).write(true).open(path).ok()
+}
+
+/// Mark this process as the running app; false if another one already is
+pub fn acquire() -> bool {
+    let Some(file) = open_lock_file() else {
+        return false;
+    };
+    match file.try_lock() {
+        Ok(()) => HELD.set(file).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Whether another process holds the instance lock
+pub fn is_running() -> bool {
+    let Some(file) = open_lock_file() else {
+        return false;
+    };
+    matches!(file.try_lock(), Err(TryLockError::WouldBlock))
+}
@@ -0,0 +1,188 @@
+//! Invoices - Payment requests carried in envelopes
+//!
+//! An invoice is a `gns/invoice` message asking the recipient to pay
+//! `amount` of `asset` to the issuer's Stellar account before
+//! `expires_at` (ms since the epoch). Paying it tags the Stellar
+//! transaction with `invoice_memo(id)` and answers with a
+//! `gns/invoice.paid` message carrying the transaction hash. The notice
+//! alone proves nothing: the issuer looks the transaction up on Horizon
+//! and only marks the invoice paid if it really settles it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::stellar::PaymentHistoryItem;
+
+/// Payload type for a payment request
+pub const INVOICE_PAYLOAD_TYPE: &str = "gns/invoice";
+
+/// Payload type for the payer's notice that an invoice was paid
+pub const INVOICE_PAID_PAYLOAD_TYPE: &str = "gns/invoice.paid";
+
+/// Assets an invoice can ask for; the wallet only sends GNS so far
+pub const INVOICE_ASSETS: &[&str] = &["GNS"];
+
+/// Longest invoice memo, in characters
+pub const MAX_INVOICE_MEMO: usize = 200;
+
+/// Longest time an invoice can stay open
+pub const MAX_INVOICE_LIFETIME_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+
+/// Longest invoice id; `invoice_memo` has to fit Stellar's 28 byte text memo
+const MAX_INVOICE_ID: usize = 24;
+
+/// Slack when comparing amounts, one stroop
+const AMOUNT_EPSILON: f64 = 0.000_000_1;
+
+/// The invoice payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Invoice {
+    pub id: String,
+    /// Public key of who is asking to be paid; payments go to its Stellar
+    /// account
+    pub issuer: String,
+    pub amount: f64,
+    pub asset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub expires_at: i64,
+}
+
+/// The payer's notice that an invoice was paid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvoicePaid {
+    pub invoice_id: String,
+    pub tx_hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    Pending,
+    Paid,
+    Expired,
+}
+
+/// An invoice we sent or received, with where it stands
+#[derive(Debug, Clone, Serialize)]
+pub struct InvoiceRecord {
+    #[serde(flatten)]
+    pub invoice: Invoice,
+    pub thread_id: Option<String>,
+    /// True for invoices we issued
+    pub outgoing: bool,
+    pub status: InvoiceStatus,
+    pub tx_hash: Option<String>,
+    pub paid_at: Option<i64>,
+}
+
+/// A new invoice id, short enough for the transaction memo
+pub fn new_invoice_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+/// The Stellar text memo a payment of this invoice carries
+pub fn invoice_memo(invoice_id: &str) -> String {
+    format!("inv:{}", invoice_id)
+}
+
+/// Check an invoice's fields make sense
+pub fn check_invoice(invoice: &Invoice) -> Result<(), String> {
+    if invoice.id.is_empty()
+        || invoice.id.len() > MAX_INVOICE_ID
+        || !invoice.id.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(format!("Invoice ids must be 1-{} letters or digits", MAX_INVOICE_ID));
+    }
+    if !invoice.amount.is_finite() || invoice.amount <= 0.0 {
+        return Err("Invoice amount must be positive".to_string());
+    }
+    if !INVOICE_ASSETS.contains(&invoice.asset.as_str()) {
+        return Err(format!("Invoices can only ask for {}", INVOICE_ASSETS.join(", ")));
+    }
+    if invoice.memo.as_ref().is_some_and(|m| m.chars().count() > MAX_INVOICE_MEMO) {
+        return Err(format!("Invoice memos can be at most {} characters", MAX_INVOICE_MEMO));
+    }
+    if invoice.expires_at <= 0 {
+        return Err("Invoice has no expiry".to_string());
+    }
+    Ok(())
+}
+
+/// Check that the payments of one transaction, as seen from `pay_to`,
+/// settle `invoice`: the memo is the invoice's, and enough of the right
+/// asset reached `pay_to` before the invoice expired
+pub fn check_settles(invoice: &Invoice, pay_to: &str, payments: &[PaymentHistoryItem]) -> Result<(), String> {
+    let memo = invoice_memo(&invoice.id);
+    let payment = payments
+        .iter()
+        .find(|p| p.to_address == pay_to && p.asset_code == invoice.asset)
+        .ok_or("Transaction pays nothing to the invoice's account")?;
+    if payment.memo.as_deref() != Some(memo.as_str()) {
+        return Err("Transaction memo doesn't match the invoice".to_string());
+    }
+    let amount: f64 = payment.amount.parse().unwrap_or(0.0);
+    if amount + AMOUNT_EPSILON < invoice.amount {
+        return Err(format!("Transaction pays {} {}, the invoice asks for {}", payment.amount, invoice.asset, invoice.amount));
+    }
+    let paid_at = chrono::DateTime::parse_from_rfc3339(&payment.created_at)
+        .map_err(|e| e.to_string())?
+        .timestamp_millis();
+    if paid_at > invoice.expires_at {
+        return Err("Transaction came after the invoice expired".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invoice() -> Invoice {
+        Invoice {
+            id: "0123456789abcdef".to_string(),
+            issuer: "ab".repeat(32),
+            amount: 12.5,
+            asset: "GNS".to_string(),
+            memo: Some("Dinner".to_string()),
+            // 2024-03-05T12:00:00Z
+            expires_at: 1_709_640_000_000,
+        }
+    }
+
+    fn payment(to: &str, amount: &str, memo: &str, created_at: &str) -> PaymentHistoryItem {
+        PaymentHistoryItem {
+            id: "1".to_string(),
+            tx_hash: "cd".repeat(32),
+            created_at: created_at.to_string(),
+            direction: "received".to_string(),
+            amount: amount.to_string(),
+            asset_code: "GNS".to_string(),
+            from_address: "GPAYER".to_string(),
+            to_address: to.to_string(),
+            memo: Some(memo.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_check_invoice() {
+        assert!(check_invoice(&invoice()).is_ok());
+        assert!(invoice_memo(&new_invoice_id()).len() <= 28);
+        assert!(check_invoice(&Invoice { amount: -1.0, ..invoice() }).is_err());
+        assert!(check_invoice(&Invoice { asset: "XLM".to_string(), ..invoice() }).is_err());
+        assert!(check_invoice(&Invoice { id: "x".repeat(25), ..invoice() }).is_err());
+    }
+
+    #[test]
+    fn test_check_settles() {
+        let invoice = invoice();
+        let memo = invoice_memo(&invoice.id);
+        let on_time = "2024-03-05T10:00:00Z";
+        assert!(check_settles(&invoice, "GISSUER", &[payment("GISSUER", "12.5000000", &memo, on_time)]).is_ok());
+
+        // Wrong account, too little, wrong memo, too late
+        assert!(check_settles(&invoice, "GISSUER", &[payment("GOTHER", "12.5000000", &memo, on_time)]).is_err());
+        assert!(check_settles(&invoice, "GISSUER", &[payment("GISSUER", "12.0000000", &memo, on_time)]).is_err());
+        assert!(check_settles(&invoice, "GISSUER", &[payment("GISSUER", "12.5000000", "inv:other", on_time)]).is_err());
+        assert!(check_settles(&invoice, "GISSUER", &[payment("GISSUER", "12.5000000", &memo, "2024-03-05T13:00:00Z")]).is_err());
+    }
+}
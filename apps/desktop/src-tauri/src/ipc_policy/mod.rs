@@ -0,0 +1,97 @@
+//! IPC Policy - Private keys stay in Rust
+//!
+//! The webview never holds key material. Anything it needs signed or
+//! decrypted goes through `sign_payload` / `decrypt_payload`, and the
+//! only way a key leaves the app is the passphrase-encrypted backup
+//! (`export_identity_backup`).
+//!
+//! `guard` enforces the inbound half: command arguments that look like
+//! key material are refused, except on the restore commands that exist
+//! to receive one. That keeps a future command (or a compromised page)
+//! from quietly passing keys across the bridge.
+
+use serde_json::Value;
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::Runtime;
+
+/// Error returned for commands refused by the policy
+pub const POLICY_ERROR: &str = "Key material is not accepted over IPC";
+
+/// Commands that take key material from the user to restore an identity
+const KEY_INPUT_COMMANDS: &[&str] = &["import_identity"];
+
+/// Argument names that carry keys, compared lowercase with `_` removed
+const KEY_ARG_NAMES: &[&str] = &[
+    "privatekey",
+    "privatekeyhex",
+    "privatekeybytes",
+    "secretkey",
+    "seed",
+    "seedhex",
+    "mnemonic",
+];
+
+/// Whether `command` may be invoked with `args`
+pub fn permits(command: &str, args: &Value) -> bool {
+    KEY_INPUT_COMMANDS.contains(&command) || !carries_key(args)
+}
+
+/// Look for a key-named field anywhere in the arguments
+fn carries_key(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.iter().any(|(name, value)| is_key_name(name) || carries_key(value)),
+        Value::Array(items) => items.iter().any(carries_key),
+        _ => false,
+    }
+}
+
+fn is_key_name(name: &str) -> bool {
+    let normalized: String = name
+        .chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    KEY_ARG_NAMES.contains(&normalized.as_str())
+}
+
+/// Wrap the command handler so it refuses key material in arguments
+pub fn guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let permitted = match invoke.message.payload() {
+            InvokeBody::Json(args) => permits(invoke.message.command(), args),
+            InvokeBody::Raw(_) => true,
+        };
+        if !permitted {
+            tracing::warn!("Refused {}: key material in arguments", invoke.message.command());
+            invoke.resolver.reject(POLICY_ERROR);
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_plain_arguments_pass() {
+        assert!(permits("sign_payload", &json!({ "payload": "hello", "encoding": "utf8" })));
+        assert!(permits("send_message", &json!({ "payload": { "text": "my seed is safe" } })));
+    }
+
+    #[test]
+    fn test_key_arguments_are_refused() {
+        assert!(!permits("sign_payload", &json!({ "payload": "x", "privateKeyHex": "ab" })));
+        assert!(!permits("send_gns", &json!({ "request": { "private_key": "ab" } })));
+        assert!(!permits("anything", &json!([{ "Mnemonic": "abandon" }])));
+    }
+
+    #[test]
+    fn test_restore_commands_may_take_keys() {
+        assert!(permits("import_identity", &json!({ "privateKeyHex": "ab" })));
+    }
+}
@@ -0,0 +1,297 @@
+//! mDNS / DNS-SD - Just enough to find other GNS devices on the LAN
+//!
+//! Devices advertise a `_gns._tcp.local` service instance with an SRV
+//! record for the listener port and a TXT record `pk=<public key>`. We
+//! ask with a PTR query and answer queries for the service type. The
+//! peer's address is taken from the packet's source rather than from A
+//! records, so no host names are published.
+
+use std::net::Ipv4Addr;
+
+/// mDNS multicast group and port
+pub const MDNS_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+pub const MDNS_PORT: u16 = 5353;
+
+/// DNS-SD service type for GNS devices
+pub const SERVICE: &str = "_gns._tcp.local";
+
+/// How long others may cache our records (seconds)
+const RECORD_TTL: u32 = 120;
+
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+/// Cache-flush bit on unique records
+const CLASS_FLUSH: u16 = 0x8000;
+
+/// A GNS device found on the LAN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    pub public_key: String,
+    pub port: u16,
+}
+
+/// PTR query for the GNS service
+pub fn query() -> Vec<u8> {
+    let mut packet = header(0, 1, 0);
+    write_name(&mut packet, SERVICE);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    packet
+}
+
+/// Response advertising our service instance
+pub fn announcement(public_key: &str, port: u16) -> Vec<u8> {
+    let instance = instance_name(public_key);
+    let mut packet = header(0x8400, 0, 3);
+
+    let mut ptr = Vec::new();
+    write_name(&mut ptr, &instance);
+    write_record(&mut packet, SERVICE, TYPE_PTR, CLASS_IN, &ptr);
+
+    let mut srv = Vec::new();
+    srv.extend_from_slice(&[0, 0, 0, 0]); // priority, weight
+    srv.extend_from_slice(&port.to_be_bytes());
+    write_name(&mut srv, &format!("{}.local", &instance[..instance.find('.').unwrap_or(instance.len())]));
+    write_record(&mut packet, &instance, TYPE_SRV, CLASS_IN | CLASS_FLUSH, &srv);
+
+    let entry = format!("pk={}", public_key);
+    let mut txt = vec![entry.len() as u8];
+    txt.extend_from_slice(entry.as_bytes());
+    write_record(&mut packet, &instance, TYPE_TXT, CLASS_IN | CLASS_FLUSH, &txt);
+
+    packet
+}
+
+/// Does this packet ask for the GNS service?
+pub fn is_query(packet: &[u8]) -> bool {
+    let Some(counts) = Counts::read(packet) else {
+        return false;
+    };
+    if counts.flags & 0x8000 != 0 {
+        return false;
+    }
+    let mut offset = 12;
+    for _ in 0..counts.questions {
+        let Some((name, next)) = read_name(packet, offset) else {
+            return false;
+        };
+        let Some(qtype) = read_u16(packet, next) else {
+            return false;
+        };
+        if name.eq_ignore_ascii_case(SERVICE) && (qtype == TYPE_PTR || qtype == 255) {
+            return true;
+        }
+        offset = next + 4;
+    }
+    false
+}
+
+/// GNS devices advertised in a response packet
+pub fn parse_announcements(packet: &[u8]) -> Vec<Announcement> {
+    let Some(counts) = Counts::read(packet) else {
+        return Vec::new();
+    };
+    if counts.flags & 0x8000 == 0 {
+        return Vec::new();
+    }
+
+    let mut offset = 12;
+    for _ in 0..counts.questions {
+        let Some((_, next)) = read_name(packet, offset) else {
+            return Vec::new();
+        };
+        offset = next + 4;
+    }
+
+    // SRV ports and TXT keys, by instance name
+    let mut ports = Vec::new();
+    let mut keys = Vec::new();
+    for _ in 0..counts.records {
+        let Some((name, next)) = read_name(packet, offset) else {
+            break;
+        };
+        let (Some(rtype), Some(len)) = (read_u16(packet, next), read_u16(packet, next + 8)) else {
+            break;
+        };
+        let start = next + 10;
+        let Some(rdata) = packet.get(start..start + len as usize) else {
+            break;
+        };
+        offset = start + len as usize;
+
+        let name = name.to_ascii_lowercase();
+        if !name.ends_with(&format!(".{}", SERVICE)) {
+            continue;
+        }
+        match rtype {
+            TYPE_SRV if rdata.len() >= 6 => ports.push((name, u16::from_be_bytes([rdata[4], rdata[5]]))),
+            TYPE_TXT => {
+                if let Some(pk) = txt_entries(rdata).find_map(|entry| entry.strip_prefix("pk=").map(str::to_string)) {
+                    keys.push((name, pk));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    keys.into_iter()
+        .filter(|(_, pk)| pk.len() == 64 && pk.bytes().all(|b| b.is_ascii_hexdigit()))
+        .filter_map(|(name, pk)| {
+            let port = ports.iter().find(|(n, _)| *n == name)?.1;
+            Some(Announcement {
+                public_key: pk.to_lowercase(),
+                port,
+            })
+        })
+        .collect()
+}
+
+/// `gns-<key prefix>._gns._tcp.local`
+fn instance_name(public_key: &str) -> String {
+    format!("gns-{}.{}", &public_key[..16.min(public_key.len())], SERVICE)
+}
+
+struct Counts {
+    flags: u16,
+    questions: u16,
+    /// Answers, authority and additional records together
+    records: u16,
+}
+
+impl Counts {
+    fn read(packet: &[u8]) -> Option<Self> {
+        let answers = read_u16(packet, 6)?;
+        let authority = read_u16(packet, 8)?;
+        let additional = read_u16(packet, 10)?;
+        Some(Self {
+            flags: read_u16(packet, 2)?,
+            questions: read_u16(packet, 4)?,
+            records: answers.saturating_add(authority).saturating_add(additional),
+        })
+    }
+}
+
+fn header(flags: u16, questions: u16, answers: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(512);
+    packet.extend_from_slice(&0u16.to_be_bytes());
+    packet.extend_from_slice(&flags.to_be_bytes());
+    packet.extend_from_slice(&questions.to_be_bytes());
+    packet.extend_from_slice(&answers.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0]);
+    packet
+}
+
+fn write_record(packet: &mut Vec<u8>, name: &str, rtype: u16, class: u16, rdata: &[u8]) {
+    write_name(packet, name);
+    packet.extend_from_slice(&rtype.to_be_bytes());
+    packet.extend_from_slice(&class.to_be_bytes());
+    packet.extend_from_slice(&RECORD_TTL.to_be_bytes());
+    packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    packet.extend_from_slice(rdata);
+}
+
+/// Uncompressed; our names are short
+fn write_name(packet: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|l| !l.is_empty()) {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label);
+    }
+    packet.push(0);
+}
+
+/// A possibly compressed name, and the offset just past it
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    // Bounds pointer loops
+    for _ in 0..128 {
+        let len = *packet.get(offset)? as usize;
+        match len {
+            0 => {
+                let name = labels.join(".");
+                return Some((name, end.unwrap_or(offset + 1)));
+            }
+            l if l & 0xC0 == 0xC0 => {
+                let pointer = ((l & 0x3F) << 8) | *packet.get(offset + 1)? as usize;
+                end.get_or_insert(offset + 2);
+                offset = pointer;
+            }
+            l if l < 64 => {
+                let label = packet.get(offset + 1..offset + 1 + l)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + l;
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn read_u16(packet: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*packet.get(offset)?, *packet.get(offset + 1)?]))
+}
+
+fn txt_entries(rdata: &[u8]) -> impl Iterator<Item = &str> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let len = *rdata.get(offset)? as usize;
+        let entry = rdata.get(offset + 1..offset + 1 + len)?;
+        offset += 1 + len;
+        Some(std::str::from_utf8(entry).unwrap_or_default())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announcement_round_trip() {
+        let pk = "ab".repeat(32);
+        let packet = announcement(&pk, 47_000);
+        assert_eq!(parse_announcements(&packet), vec![Announcement { public_key: pk, port: 47_000 }]);
+        assert!(!is_query(&packet));
+
+        assert!(is_query(&query()));
+        assert!(parse_announcements(&query()).is_empty());
+    }
+
+    #[test]
+    fn test_compressed_names() {
+        // Responders usually point back at names already in the packet
+        let pk = "cd".repeat(32);
+        let mut packet = header(0x8400, 0, 2);
+        let instance_at = packet.len();
+        let instance = instance_name(&pk);
+        write_record(&mut packet, &instance, TYPE_SRV, CLASS_IN, &[0, 0, 0, 0, 0x1F, 0x90, 0]);
+        packet.extend_from_slice(&[0xC0, instance_at as u8]);
+        let entry = format!("pk={}", pk);
+        let mut txt = vec![entry.len() as u8];
+        txt.extend_from_slice(entry.as_bytes());
+        packet.extend_from_slice(&TYPE_TXT.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet.extend_from_slice(&RECORD_TTL.to_be_bytes());
+        packet.extend_from_slice(&(txt.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&txt);
+
+        assert_eq!(parse_announcements(&packet), vec![Announcement { public_key: pk, port: 8080 }]);
+    }
+
+    #[test]
+    fn test_malformed_packets() {
+        assert!(parse_announcements(&[]).is_empty());
+        assert!(!is_query(&[0; 11]));
+
+        // A pointer to itself
+        let mut packet = header(0x8400, 0, 1);
+        packet.extend_from_slice(&[0xC0, 12]);
+        assert!(parse_announcements(&packet).is_empty());
+
+        let mut truncated = announcement(&"ab".repeat(32), 1);
+        truncated.truncate(truncated.len() - 10);
+        assert!(parse_announcements(&truncated).is_empty());
+    }
+}
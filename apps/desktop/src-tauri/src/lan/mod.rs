@@ -0,0 +1,503 @@
+//! LAN Delivery - Direct envelope exchange with devices on the same network
+//!
+//! When enabled, we advertise ourselves over mDNS and listen on a TCP port
+//! for the same signed envelopes the relay carries. Sending to a contact
+//! seen on the LAN goes straight to their device; if there is no such
+//! device, or the direct send fails, the envelope goes through the relay
+//! as usual. Received envelopes join the relay's incoming channel, so the
+//! message handler treats them like any other.
+//!
+//! Frames on the socket are JSON, each prefixed with its length as a
+//! big-endian u32. mDNS announcements are unauthenticated, so a sender
+//! first challenges the device with a fresh nonce and only sends once it
+//! proves it holds the advertised key. Envelopes then go as `RelayFrame`s,
+//! and each is answered with an ack signed over the nonce. Anything short
+//! of a verified `delivered` ack falls back to the relay, so a device
+//! pretending to be a contact can't swallow their messages.
+//!
+//! Off by default: advertising puts our public key on the local network.
+
+pub mod mdns;
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use gns_crypto_core::signing::verify_signature_hex;
+use gns_crypto_core::GnsEnvelope;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::{mpsc, Mutex as AsyncMutex, Notify};
+
+use crate::crypto::IdentityManager;
+use crate::network::ack::AckStatus;
+use crate::network::frame::RelayFrame;
+use crate::network::IncomingMessage;
+use crate::validation::MAX_WIRE_PAYLOAD_BYTES;
+
+/// How often we query for and re-announce devices
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A device not heard from for this long is forgotten (ms)
+const PEER_TTL_MS: i64 = 2 * 60 * 1000;
+
+/// Direct sends slower than this fall back to the relay
+const SEND_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Largest frame accepted from a peer; an envelope plus framing
+const MAX_FRAME_BYTES: usize = MAX_WIRE_PAYLOAD_BYTES + 64 * 1024;
+
+/// Prefix of everything signed on the LAN, so these signatures can't
+/// stand in for any other GNS statement
+const PROOF_CONTEXT: &str = "gns-lan-v1";
+
+/// Handshake and ack frames; envelopes travel as `RelayFrame`s
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LanFrame {
+    /// The sender asks the device to prove it holds the advertised key
+    Challenge { nonce: String },
+    Proof {
+        #[serde(rename = "publicKey")]
+        public_key: String,
+        signature: String,
+    },
+    /// Receipt for one envelope, signed over the connection's nonce
+    Ack { id: String, status: String, signature: String },
+}
+
+impl LanFrame {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+fn proof_message(nonce: &str) -> Vec<u8> {
+    format!("{}:proof:{}", PROOF_CONTEXT, nonce).into_bytes()
+}
+
+fn ack_message(nonce: &str, id: &str, status: &str) -> Vec<u8> {
+    format!("{}:ack:{}:{}:{}", PROOF_CONTEXT, nonce, id, status).into_bytes()
+}
+
+fn invalid_data(reason: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// A device seen on the LAN
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LanPeer {
+    pub public_key: String,
+    pub address: String,
+    pub seen_at: i64,
+}
+
+#[derive(Debug, Clone)]
+struct Peer {
+    addr: SocketAddr,
+    seen_at: i64,
+}
+
+pub struct LanTransport {
+    enabled: AtomicBool,
+    /// Port our listener is bound to; 0 until it is
+    port: AtomicU16,
+    peers: Mutex<HashMap<String, Peer>>,
+    /// Key we announce; follows the active persona
+    public_key: Mutex<Option<String>>,
+    /// Wakes discovery to announce a new key straight away
+    rekeyed: Notify,
+}
+
+impl LanTransport {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            port: AtomicU16::new(0),
+            peers: Mutex::new(HashMap::new()),
+            public_key: Mutex::new(None),
+            rekeyed: Notify::new(),
+        }
+    }
+
+    fn public_key(&self) -> Option<String> {
+        self.public_key.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Announce `public_key` from now on, or nothing if None. Called when
+    /// the identity changes, so the LAN never hears the other persona's key.
+    pub fn set_public_key(&self, public_key: Option<String>) {
+        *self.public_key.lock().unwrap_or_else(|e| e.into_inner()) = public_key;
+        self.rekeyed.notify_one();
+    }
+
+    fn peers(&self) -> std::sync::MutexGuard<'_, HashMap<String, Peer>> {
+        self.peers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Stop advertising and forget peers when disabled
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.peers().clear();
+        }
+    }
+
+    /// Record a device heard on the LAN
+    pub fn saw_peer(&self, public_key: &str, addr: SocketAddr, now: i64) {
+        let mut peers = self.peers();
+        peers.retain(|_, peer| now - peer.seen_at < PEER_TTL_MS);
+        peers.insert(public_key.to_lowercase(), Peer { addr, seen_at: now });
+    }
+
+    /// Where to reach a contact directly, if they're on the LAN
+    pub fn peer_addr(&self, public_key: &str, now: i64) -> Option<SocketAddr> {
+        if !self.is_enabled() {
+            return None;
+        }
+        self.peers()
+            .get(&public_key.to_lowercase())
+            .filter(|peer| now - peer.seen_at < PEER_TTL_MS)
+            .map(|peer| peer.addr)
+    }
+
+    /// Devices currently seen, for the UI
+    pub fn list_peers(&self, now: i64) -> Vec<LanPeer> {
+        let mut peers: Vec<LanPeer> = self
+            .peers()
+            .iter()
+            .filter(|(_, peer)| now - peer.seen_at < PEER_TTL_MS)
+            .map(|(public_key, peer)| LanPeer {
+                public_key: public_key.clone(),
+                address: peer.addr.to_string(),
+                seen_at: peer.seen_at,
+            })
+            .collect();
+        peers.sort_by_key(|peer| std::cmp::Reverse(peer.seen_at));
+        peers
+    }
+
+    /// Try delivering straight to the recipient's device. None means use
+    /// the relay: no device on the LAN, it couldn't be reached or prove
+    /// it's the recipient, or it didn't take the envelope.
+    pub async fn deliver(&self, recipient_pk: &str, envelope: &GnsEnvelope) -> Option<AckStatus> {
+        let addr = self.peer_addr(recipient_pk, chrono::Utc::now().timestamp_millis())?;
+        match tokio::time::timeout(SEND_TIMEOUT, send_envelope(addr, recipient_pk, envelope)).await {
+            Ok(Ok(AckStatus::Delivered)) => {
+                tracing::info!("Delivered {} over the LAN", envelope.id);
+                Some(AckStatus::Delivered)
+            }
+            Ok(Ok(status)) => {
+                tracing::debug!("LAN device at {} didn't take {}: {:?}", addr, envelope.id, status);
+                None
+            }
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                tracing::warn!("LAN device at {} isn't {}: {}", addr, recipient_pk.get(..16).unwrap_or(recipient_pk), e);
+                self.peers().remove(&recipient_pk.to_lowercase());
+                None
+            }
+            Ok(Err(e)) => {
+                tracing::debug!("LAN delivery to {} failed: {}", addr, e);
+                None
+            }
+            Err(_) => {
+                tracing::debug!("LAN delivery to {} timed out", addr);
+                None
+            }
+        }
+    }
+
+    /// Start the listener and mDNS discovery for our identity, which signs
+    /// our proofs and acks. Envelopes received go to `incoming_tx`.
+    pub fn start(
+        self: Arc<Self>,
+        identity: Arc<AsyncMutex<IdentityManager>>,
+        public_key: String,
+        incoming_tx: mpsc::Sender<IncomingMessage>,
+    ) {
+        self.set_public_key(Some(public_key));
+        let transport = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let listener = match TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::warn!("LAN delivery unavailable, could not listen: {}", e);
+                    return;
+                }
+            };
+            let port = listener.local_addr().map(|addr| addr.port()).unwrap_or(0);
+            transport.port.store(port, Ordering::Relaxed);
+            tracing::info!("LAN listener on port {}", port);
+
+            tauri::async_runtime::spawn(transport.clone().discover());
+
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::warn!("LAN accept failed: {}", e);
+                        continue;
+                    }
+                };
+                if !transport.is_enabled() {
+                    continue;
+                }
+                let (tx, identity) = (incoming_tx.clone(), identity.clone());
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = receive(stream, &identity, tx).await {
+                        tracing::debug!("LAN connection from {} ended: {}", addr, e);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Answer queries for our service and collect other devices' answers
+    async fn discover(self: Arc<Self>) {
+        let socket = match mdns_socket() {
+            Ok(socket) => socket,
+            Err(e) => {
+                tracing::warn!("LAN discovery unavailable: {}", e);
+                return;
+            }
+        };
+        let group = SocketAddr::V4(SocketAddrV4::new(mdns::MDNS_ADDR, mdns::MDNS_PORT));
+        let mut interval = tokio::time::interval(DISCOVERY_INTERVAL);
+        let mut buf = vec![0u8; 9000];
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Some(public_key) = self.public_key().filter(|_| self.is_enabled()) {
+                        let port = self.port.load(Ordering::Relaxed);
+                        let _ = socket.send_to(&mdns::query(), group).await;
+                        let _ = socket.send_to(&mdns::announcement(&public_key, port), group).await;
+                    }
+                }
+                _ = self.rekeyed.notified() => {
+                    if let Some(public_key) = self.public_key().filter(|_| self.is_enabled()) {
+                        let port = self.port.load(Ordering::Relaxed);
+                        let _ = socket.send_to(&mdns::announcement(&public_key, port), group).await;
+                    }
+                }
+                received = socket.recv_from(&mut buf) => {
+                    let Ok((len, from)) = received else {
+                        continue;
+                    };
+                    let Some(public_key) = self.public_key().filter(|_| self.is_enabled()) else {
+                        continue;
+                    };
+                    let packet = &buf[..len];
+                    if mdns::is_query(packet) {
+                        let port = self.port.load(Ordering::Relaxed);
+                        let _ = socket.send_to(&mdns::announcement(&public_key, port), group).await;
+                        continue;
+                    }
+                    let now = chrono::Utc::now().timestamp_millis();
+                    for found in mdns::parse_announcements(packet) {
+                        if found.public_key != public_key && found.port != 0 {
+                            self.saw_peer(&found.public_key, SocketAddr::new(from.ip(), found.port), now);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Port 5353 is usually shared with the system's own responder
+fn mdns_socket() -> std::io::Result<UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    socket.set_reuse_port(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, mdns::MDNS_PORT)).into())?;
+    socket.join_multicast_v4(&mdns::MDNS_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_loop_v4(false)?;
+    UdpSocket::from_std(socket.into())
+}
+
+fn signed_by(public_key: &str, message: &[u8], signature: &str) -> bool {
+    verify_signature_hex(public_key, message, signature).unwrap_or(false)
+}
+
+/// Sign with our identity, giving our key and the signature
+async fn sign(identity: &AsyncMutex<IdentityManager>, message: &[u8]) -> std::io::Result<(String, String)> {
+    let identity = identity.lock().await;
+    let identity = identity.get_identity().ok_or_else(|| invalid_data("No identity to sign with"))?;
+    Ok((identity.public_key_hex(), hex::encode(identity.sign_bytes(message))))
+}
+
+/// Have the device prove it holds `recipient_pk`, send one envelope and
+/// wait for its signed ack
+async fn send_envelope(addr: SocketAddr, recipient_pk: &str, envelope: &GnsEnvelope) -> std::io::Result<AckStatus> {
+    let mut stream = TcpStream::connect(addr).await?;
+    let mut nonce = [0u8; 32];
+    OsRng.fill_bytes(&mut nonce);
+    let nonce = hex::encode(nonce);
+    write_frame(&mut stream, &LanFrame::Challenge { nonce: nonce.clone() }.to_json()).await?;
+
+    let reply = read_frame(&mut stream).await?;
+    match serde_json::from_str::<LanFrame>(&reply) {
+        Ok(LanFrame::Proof { public_key, signature })
+            if public_key.eq_ignore_ascii_case(recipient_pk) && signed_by(recipient_pk, &proof_message(&nonce), &signature) => {}
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "No proof of the advertised key",
+            ))
+        }
+    }
+
+    write_frame(&mut stream, &RelayFrame::envelope(envelope).to_json()).await?;
+    let reply = read_frame(&mut stream).await?;
+    match serde_json::from_str::<LanFrame>(&reply) {
+        Ok(LanFrame::Ack { id, status, signature })
+            if id == envelope.id && signed_by(recipient_pk, &ack_message(&nonce, &id, &status), &signature) =>
+        {
+            Ok(AckStatus::parse(&status, None))
+        }
+        _ => Err(invalid_data("Expected a signed ack")),
+    }
+}
+
+/// Prove our key to a peer, then take envelopes from it until it closes
+async fn receive(
+    mut stream: TcpStream,
+    identity: &AsyncMutex<IdentityManager>,
+    incoming_tx: mpsc::Sender<IncomingMessage>,
+) -> std::io::Result<()> {
+    let nonce = match serde_json::from_str::<LanFrame>(&read_frame(&mut stream).await?) {
+        Ok(LanFrame::Challenge { nonce }) if nonce.len() == 64 && nonce.chars().all(|c| c.is_ascii_hexdigit()) => nonce,
+        _ => return Err(invalid_data("Expected a challenge")),
+    };
+    let (public_key, signature) = sign(identity, &proof_message(&nonce)).await?;
+    write_frame(&mut stream, &LanFrame::Proof { public_key, signature }.to_json()).await?;
+
+    loop {
+        let text = read_frame(&mut stream).await?;
+        let envelope = match serde_json::from_str::<RelayFrame>(&text) {
+            Ok(RelayFrame::Message { envelope, .. }) if envelope.validate().is_ok() => envelope,
+            _ => return Err(invalid_data("Expected an envelope")),
+        };
+        let (id, status) = (envelope.id.clone(), "delivered".to_string());
+        if incoming_tx.send(IncomingMessage::Envelope(envelope)).await.is_err() {
+            return Ok(());
+        }
+        crate::metrics::METRICS.message_queued();
+        let (_, signature) = sign(identity, &ack_message(&nonce, &id, &status)).await?;
+        write_frame(&mut stream, &LanFrame::Ack { id, status, signature }.to_json()).await?;
+    }
+}
+
+async fn write_frame<W: AsyncWrite + Unpin>(stream: &mut W, text: &str) -> std::io::Result<()> {
+    stream.write_all(&(text.len() as u32).to_be_bytes()).await?;
+    stream.write_all(text.as_bytes()).await?;
+    stream.flush().await
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(stream: &mut R) -> std::io::Result<String> {
+    let len = stream.read_u32().await? as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(invalid_data("Frame too large"));
+    }
+    let mut buf = vec![0u8; len];
+    stream.read_exact(&mut buf).await?;
+    String::from_utf8(buf).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::GnsIdentity;
+
+    fn envelope() -> GnsEnvelope {
+        serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "fromPublicKey": "ab".repeat(32),
+            "toPublicKeys": ["cd".repeat(32)],
+            "payloadType": "text/plain",
+            "timestamp": 1_700_000_000_000i64,
+            "encryptedPayload": {
+                "ephemeralPublicKey": "ef".repeat(32),
+                "nonce": "00".repeat(12),
+                "ciphertext": "11".repeat(40),
+            },
+            "signature": "22".repeat(64),
+        }))
+        .unwrap()
+    }
+
+    /// A device listening as `identity`, returning its address
+    async fn device(identity: GnsIdentity) -> (SocketAddr, mpsc::Receiver<IncomingMessage>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel(4);
+        let identity = AsyncMutex::new(IdentityManager::with_identity(identity));
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = receive(stream, &identity, tx).await;
+        });
+        (addr, rx)
+    }
+
+    #[tokio::test]
+    async fn test_direct_delivery() {
+        let recipient = GnsIdentity::generate();
+        let pk = recipient.public_key_hex();
+        let (addr, mut rx) = device(recipient).await;
+
+        let transport = LanTransport::new(true);
+        assert_eq!(transport.deliver(&pk, &envelope()).await, None);
+
+        transport.saw_peer(&pk, addr, chrono::Utc::now().timestamp_millis());
+        assert_eq!(transport.deliver(&pk, &envelope()).await, Some(AckStatus::Delivered));
+        assert!(matches!(rx.recv().await, Some(IncomingMessage::Envelope(e)) if e.id == "m1"));
+    }
+
+    #[tokio::test]
+    async fn test_impostor_falls_back() {
+        // Announces the recipient's key but holds a different one
+        let (addr, mut rx) = device(GnsIdentity::generate()).await;
+        let transport = LanTransport::new(true);
+        let pk = GnsIdentity::generate().public_key_hex();
+        transport.saw_peer(&pk, addr, chrono::Utc::now().timestamp_millis());
+
+        assert_eq!(transport.deliver(&pk, &envelope()).await, None);
+        assert!(rx.recv().await.is_none());
+        // Not tried again
+        assert_eq!(transport.peer_addr(&pk, chrono::Utc::now().timestamp_millis()), None);
+    }
+
+    #[test]
+    fn test_peers_expire() {
+        let transport = LanTransport::new(true);
+        let addr: SocketAddr = "192.168.1.20:4000".parse().unwrap();
+        transport.saw_peer("AB", addr, 0);
+        assert_eq!(transport.peer_addr("ab", 1_000), Some(addr));
+        assert_eq!(transport.peer_addr("ab", PEER_TTL_MS), None);
+        assert_eq!(transport.list_peers(1_000).len(), 1);
+
+        transport.set_enabled(false);
+        assert_eq!(transport.peer_addr("ab", 1_000), None);
+        assert!(transport.list_peers(1_000).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame() {
+        let (mut a, mut b) = tokio::io::duplex(64);
+        a.write_all(&(MAX_FRAME_BYTES as u32 + 1).to_be_bytes()).await.unwrap();
+        assert!(read_frame(&mut b).await.is_err());
+    }
+}
@@ -1,53 +1,177 @@
 //! GNS Browser - Shared Library for Desktop and Mobile
+//!
+//! The whole app: state, storage, network, the message handler and every
+//! command. Mobile builds enter through `run()`, and the desktop binary
+//! (`main.rs`) only calls it, so a feature lands once for both.
+//!
+//! This crate is the shared app core for now. It has not been split out
+//! into a separate `gns-app-core` crate: the iOS project in `gen/apple`
+//! links this crate's staticlib (as `libapp.a`) and `generate_context!`
+//! has to stay in the crate that owns `tauri.conf.json`. Moving the
+//! modules into their own crate would need both of those changed first.
 
 use std::sync::Arc;
 use tauri::Manager;
 use tokio::sync::Mutex;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 // Re-export modules
+pub mod app_lock;
+pub mod attachments;
+pub mod calendar;
+pub mod capabilities;
+pub mod channels;
+pub mod cli;
 pub mod commands;
+pub mod contact_requests;
+pub mod contacts;
 pub mod crypto;
+pub mod deep_link;
+pub mod duress;
+pub mod error;
+pub mod inbox;
+pub mod instance;
+pub mod invoices;
+pub mod lan;
+pub mod local_api;
 pub mod location;
+pub mod logging;
 pub mod message_handler;
+pub mod metrics;
+pub mod native;
 pub mod network;
+pub mod payloads;
+pub mod polls;
+pub mod presence;
+pub mod proximity;
+pub mod push;
 pub mod stellar;
 pub mod storage;
 pub mod dix;
+pub mod export;
+pub mod ipc_policy;
+pub mod qr;
+pub mod quotes;
+pub mod rules;
+pub mod rate_limit;
+pub mod resolver;
+pub mod trust;
+pub mod spam;
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+pub mod shortcut;
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+pub mod tray;
+pub mod verifications;
+pub mod validation;
+pub mod wipe;
 
+#[cfg(all(test, feature = "e2e"))]
+mod e2e;
+
+use crate::app_lock::AppLock;
+use crate::lan::LanTransport;
+use crate::local_api::LocalApi;
+use crate::presence::PresenceTracker;
+use crate::rate_limit::RateLimiter;
+use crate::rules::ScriptRequests;
+use crate::attachments::AttachmentCache;
 use crate::crypto::IdentityManager;
-use crate::network::{ApiClient, RelayConnection};
+use crate::deep_link::DeepLinkQueue;
+use crate::network::{keepalive::RelayKeepalive, ApiClient, RelayConnection};
 use crate::stellar::StellarService;
-use crate::storage::Database;
+use crate::storage::{Database, DatabaseHandle};
 use crate::dix::DixService;
+use crate::location::sync::BreadcrumbSync;
 
 #[cfg(any(target_os = "ios", target_os = "android"))]
 use crate::location::BreadcrumbCollector;
 
 /// Application state shared across all commands
 pub struct AppState {
+    /// Identity manager (keychain access)
     pub identity: Arc<Mutex<IdentityManager>>,
-    pub database: Arc<Mutex<Database>>,
+
+    /// Local database
+    pub database: DatabaseHandle,
+
+    /// API client for GNS backend
     pub api: Arc<ApiClient>,
+
+    /// WebSocket relay connection
     pub relay: Arc<Mutex<RelayConnection>>,
+
+    /// Stellar network service
     pub stellar: Arc<Mutex<StellarService>>,
+
+    /// Dix service
     pub dix: Arc<DixService>,
+
+    /// Background breadcrumb uploader
+    pub breadcrumb_sync: Arc<BreadcrumbSync>,
+
+    /// Reconnects the relay across app lifecycle changes
+    pub relay_keepalive: Arc<RelayKeepalive>,
+
+    /// Deep links waiting for the UI to load
+    pub deep_links: Arc<DeepLinkQueue>,
+    pub attachments: Arc<AttachmentCache>,
+    pub app_lock: Arc<AppLock>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub presence: Arc<PresenceTracker>,
+    pub lan: Arc<LanTransport>,
+    pub local_api: Arc<LocalApi>,
+    /// Script runs from rules waiting for confirmation
+    pub rule_scripts: Arc<ScriptRequests>,
+
+    /// Breadcrumb collector (mobile only)
     #[cfg(any(target_os = "ios", target_os = "android"))]
     pub breadcrumb_collector: Arc<Mutex<BreadcrumbCollector>>,
 }
 
 /// Initialize application state
 fn setup_app_state() -> Result<AppState, Box<dyn std::error::Error>> {
-    let database = Arc::new(Mutex::new(Database::open()?));
-    let identity = Arc::new(Mutex::new(IdentityManager::new()?));
+    let database = Database::open()?;
+    let identity = IdentityManager::new()?;
+
+    let mut stellar = StellarService::mainnet();
+    if let Some(pk) = identity.public_key_hex() {
+        stellar.set_signing_config(database.get_hardware_signing(&pk));
+    }
+    let dix_filters = identity
+        .public_key_hex()
+        .map(|pk| database.get_dix_filters(&pk))
+        .unwrap_or_default();
+    let upload_paused = database.get_breadcrumb_upload_paused();
+    let app_lock = Arc::new(AppLock::new(database.get_app_lock_config()));
+    let lan_enabled = database.get_lan_delivery();
+    let local_api_enabled = database.get_local_api();
+    let doh_url = database.get_dns_over_https();
+
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    let privacy_zones = database.get_privacy_zones();
+
+    let database = DatabaseHandle::spawn(database)?;
+    let identity = Arc::new(Mutex::new(identity));
     let api = Arc::new(ApiClient::new("https://gns-browser-production.up.railway.app")?);
-    let relay = Arc::new(Mutex::new(RelayConnection::new("wss://gns-browser-production.up.railway.app")?));
-    let stellar = Arc::new(Mutex::new(StellarService::mainnet()));
+    api.set_doh_url(doh_url);
+    let relay = RelayConnection::new("wss://gns-browser-production.up.railway.app")?.with_store(database.clone());
+    let relay = Arc::new(Mutex::new(relay));
+    let stellar = Arc::new(Mutex::new(stellar));
 
     let dix = Arc::new(DixService::new(identity.clone(), api.clone()));
+    dix.set_filters(dix_filters);
+
+    let breadcrumb_sync = Arc::new(BreadcrumbSync::new());
+    breadcrumb_sync.set_paused(upload_paused);
+    let relay_keepalive = Arc::new(RelayKeepalive::new());
+    let deep_links = Arc::new(DeepLinkQueue::new());
+    let attachments = Arc::new(AttachmentCache::open()?);
 
     #[cfg(any(target_os = "ios", target_os = "android"))]
-    let breadcrumb_collector = Arc::new(Mutex::new(BreadcrumbCollector::new()));
+    let breadcrumb_collector = {
+        let mut collector = BreadcrumbCollector::new();
+        collector.set_privacy_zones(&privacy_zones);
+        Arc::new(Mutex::new(collector))
+    };
 
     Ok(AppState {
         identity,
@@ -56,56 +180,127 @@ fn setup_app_state() -> Result<AppState, Box<dyn std::error::Error>> {
         relay,
         stellar,
         dix,
+        breadcrumb_sync,
+        relay_keepalive,
+        deep_links,
+        attachments,
+        app_lock,
+        rate_limiter: Arc::new(RateLimiter::new()),
+        presence: Arc::new(PresenceTracker::new()),
+        lan: Arc::new(LanTransport::new(lan_enabled)),
+        local_api: Arc::new(LocalApi::new(local_api_enabled)),
+        rule_scripts: Arc::new(ScriptRequests::new()),
         #[cfg(any(target_os = "ios", target_os = "android"))]
         breadcrumb_collector,
     })
 }
 
 /// Setup deep link handler
-fn setup_deep_links(_app_handle: tauri::AppHandle) {
-    #[cfg(any(target_os = "ios", target_os = "android"))]
-    {
-        tracing::info!("Deep link handler registered for mobile");
+fn setup_deep_links(app_handle: tauri::AppHandle) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Installers and mobile builds register the schemes from tauri.conf.json;
+    // Linux and Windows dev builds have to do it at runtime
+    #[cfg(any(target_os = "linux", all(debug_assertions, target_os = "windows")))]
+    if let Err(e) = app_handle.deep_link().register_all() {
+        tracing::warn!("Failed to register deep link schemes: {}", e);
     }
 
-    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
-    {
-        tracing::info!("Deep link handler registered for desktop");
+    // The link that launched the app, if any
+    if let Ok(Some(urls)) = app_handle.deep_link().get_current() {
+        let state = app_handle.state::<AppState>();
+        for url in urls {
+            let _ = deep_link::dispatch(&app_handle, &state.deep_links, url.as_str());
+        }
+    }
+
+    // Links opened while running (on desktop, forwarded by single-instance)
+    let handle = app_handle.clone();
+    app_handle.deep_link().on_open_url(move |event| {
+        let state = handle.state::<AppState>();
+        for url in event.urls() {
+            let _ = deep_link::dispatch(&handle, &state.deep_links, url.as_str());
+        }
+    });
+
+    tracing::info!("Deep link handler registered");
+}
+
+/// A second launch: bring the running window forward and pass on its
+/// arguments (deep links in them reach `on_open_url` on their own)
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn handle_second_instance(app_handle: &tauri::AppHandle, argv: Vec<String>, cwd: String) {
+    use tauri::Emitter;
+
+    tracing::info!("Second instance launched with {:?}", argv);
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+
+    let args: Vec<String> = argv.into_iter().skip(1).collect();
+    if !args.is_empty() {
+        let _ = app_handle.emit("second_instance", serde_json::json!({
+            "args": args,
+            "cwd": cwd,
+        }));
     }
 }
 
 // Mobile entry point
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    println!("🔥 [RUST] GNS Browser run() called");
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "gns_browser=debug,tauri=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    tracing::error!("🔥 [RUST] Tracing initialized");
+    // Initialize logging (console, rotating files, in-app capture)
+    let _log_guard = logging::init();
+
     tracing::info!("Starting GNS Browser...");
 
-    let builder = tauri::Builder::default()
+    let builder = tauri::Builder::default();
+
+    // Must be the first plugin; a second launch focuses this instance and
+    // hands it its arguments and links instead of starting another relay
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+        handle_second_instance(app, argv, cwd);
+    }));
+
+    // Quick-compose hotkey; the shortcut itself is registered in setup
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    let builder = builder.plugin(
+        tauri_plugin_global_shortcut::Builder::new()
+            .with_handler(|app, _shortcut, event| {
+                if event.state() == tauri_plugin_global_shortcut::ShortcutState::Pressed {
+                    shortcut::open_quick_compose(app);
+                }
+            })
+            .build(),
+    );
+
+    let builder = builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_notification::init())
-        .plugin(tauri_plugin_dialog::init());
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_deep_link::init());
 
     // Add geolocation plugin for mobile platforms
     #[cfg(any(target_os = "ios", target_os = "android"))]
-    let builder = builder.plugin(tauri_plugin_geolocation::init());
+    let builder = builder
+        .plugin(tauri_plugin_geolocation::init())
+        .plugin(tauri_plugin_biometric::init());
 
     builder
         .setup(|app| {
-            tracing::error!("🔥 [RUST] Setup block entered");
             tracing::info!("Setting up application...");
 
+            // Keeps gns-cli from opening a second relay connection
+            if !instance::acquire() {
+                tracing::warn!("Could not take the instance lock");
+            }
+
             let state = setup_app_state()?;
             
             let public_key = {
@@ -113,10 +308,8 @@ pub fn run() {
                 identity.public_key_hex()
             };
             
-            if let Some(ref pk) = public_key {
-                tracing::error!("🔥 [RUST] Public Key found: {}", pk);
-            } else {
-                tracing::error!("🔥 [RUST] NO PUBLIC KEY FOUND - Message handler will NOT start");
+            if public_key.is_none() {
+                tracing::info!("No identity configured; relay and message handler not started");
             }
 
             let encryption_key = {
@@ -139,14 +332,86 @@ pub fn run() {
                 (state.database.clone(), state.breadcrumb_collector.clone())
             };
 
+            // Upload breadcrumbs in the background
+            state.breadcrumb_sync.clone().start(
+                state.identity.clone(),
+                state.database.clone(),
+                state.api.clone(),
+            );
+
+            // Vacuum and check the database while the app is idle
+            let keepalive_for_maintenance = state.relay_keepalive.clone();
+            storage::start_maintenance_scheduler(state.database.clone(), move || {
+                let last_activity = metrics::METRICS.snapshot().last_envelope_at;
+                storage::is_idle(
+                    keepalive_for_maintenance.is_foreground(),
+                    last_activity,
+                    chrono::Utc::now().timestamp_millis(),
+                )
+            });
+
+            // Apply the message retention policy
+            storage::start_retention_pruner(state.database.clone());
+
+            // Tell open views which threads and messages changed
+            storage::start_change_events(app.handle().clone(), &state.database);
+
+            // Lock again after the idle timeout
+            app_lock::start_auto_lock(app.handle().clone(), state.app_lock.clone());
+
             let identity_for_handler = state.identity.clone();
+            let identity_for_keepalive = state.identity.clone();
+            let identity_for_lan = state.identity.clone();
             let database_for_handler = state.database.clone();
+            let keepalive = state.relay_keepalive.clone();
+            let api = state.api.clone();
+            let database = state.database.clone();
+            let lan = state.lan.clone();
+            let local_api = state.local_api.clone();
 
             app.manage(state);
 
+            // JSON-RPC for local tools, if opted in
+            if local_api.is_enabled() {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = local_api.start(app_handle).await {
+                        tracing::warn!("Local API unavailable: {}", e);
+                    }
+                });
+            }
+
             setup_deep_links(app.handle().clone());
 
-            if let Some(pk) = public_key {
+            // Pushes and background tasks reach the app without the webview
+            native::install(app.handle());
+
+            #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+            tray::setup_tray(app.handle())?;
+
+            #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+            {
+                let database = app.state::<AppState>().database.clone();
+                let shortcut = tauri::async_runtime::block_on(database.call(|db| db.get_quick_compose_shortcut()));
+                if let Err(e) = shortcut::register_quick_compose(app.handle(), shortcut.as_deref()) {
+                    tracing::warn!("{}", e);
+                }
+            }
+
+            // Notify about incoming payments as they land
+            stellar::stream::start_payment_watcher(app.handle().clone());
+
+            // Likes, replies, follows and mentions for the notification feed
+            dix::notifications::start_notification_poller(app.handle().clone());
+
+            // Resume an unfinished handle claim
+            let claim_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let state = claim_handle.state::<AppState>();
+                commands::commands_handle::advance_claim(&claim_handle, &state).await;
+            });
+
+            if public_key.is_some() {
                 let app_handle = app.handle().clone();
                 
                 tauri::async_runtime::spawn(async move {
@@ -165,15 +430,17 @@ pub fn run() {
                     // Create relay instance with channel attached
                     let relay_instance = {
                         let guard = relay.lock().await;
-                        guard.clone_with_incoming_channel(incoming_tx)
+                        guard.clone_with_incoming_channel(incoming_tx.clone())
                     };
                     
-                    // Connect using the instance that has the channel
-                    if let Err(e) = relay_instance.connect(&pk).await {
-                        tracing::error!("Failed to connect to relay: {}", e);
-                    } else {
-                        tracing::info!("Connected to WebSocket relay");
+                    // Take envelopes directly from devices on the LAN
+                    if let Some(pk) = public_key {
+                        lan.start(identity_for_lan, pk, incoming_tx.clone());
                     }
+
+                    // Connect using the instance that has the channel, and
+                    // keep it connected across background/foreground
+                    keepalive.start(relay_instance, identity_for_keepalive, api, database, incoming_tx);
                 });
             }
 
@@ -181,15 +448,14 @@ pub fn run() {
             #[cfg(any(target_os = "ios", target_os = "android"))]
             {
                 tauri::async_runtime::spawn(async move {
-                    let db = db_clone.lock().await;
-                    let should_collect = db.get_collection_enabled();
-                    drop(db);
+                    let should_collect = db_clone.call(|db| db.get_collection_enabled()).await;
                     
                     if should_collect {
                         let mut collector = collector_clone.lock().await;
                         if let Err(e) = collector.start() {
                             tracing::error!("Failed to auto-start breadcrumb collection: {}", e);
                         } else {
+                            crate::native::set_motion_updates(true);
                             tracing::info!("📍 Auto-started breadcrumb collection");
                         }
                     }
@@ -199,7 +465,7 @@ pub fn run() {
             tracing::info!("Application setup complete");
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(app_lock::guard(ipc_policy::guard(tauri::generate_handler![
             // Identity commands
             commands::identity::get_public_key,
             commands::identity::get_encryption_key,
@@ -208,24 +474,57 @@ pub fn run() {
             commands::identity::generate_identity,
             commands::identity::import_identity,
             commands::identity::export_identity_backup,
+            commands::identity::import_identity_backup,
             commands::identity::delete_identity,
-            commands::identity::sign_string,
+            commands::identity::generate_identity_qr,
+            commands::identity::parse_identity_qr,
+            commands::identity::sign_payload,
+            commands::identity::decrypt_payload,
+            // Secure Storage
+            commands::secure_storage::secure_store,
+            commands::secure_storage::secure_get,
+            commands::secure_storage::secure_delete,
             // Handle commands
             commands::commands_handle::create_identity_with_handle,
             commands::commands_handle::check_handle_available,
             commands::commands_handle::claim_handle,
+            commands::commands_handle::preview_claim_proof,
             commands::commands_handle::publish_identity,
+            commands::commands_handle::get_claim_progress,
+            commands::records::get_published_record,
+            commands::records::publish_custom_record,
+            commands::records::list_record_modules,
+            commands::records::register_module,
+            commands::records::register_endpoint,
+            commands::commands_handle::release_handle,
+            commands::commands_handle::transfer_handle,
+            commands::commands_handle::get_profile,
+            commands::commands_handle::update_profile,
             // Messaging commands
             commands::messaging::send_message,
             commands::messaging::get_threads,
+            commands::inbox::get_unified_inbox,
             commands::messaging::get_thread,
             commands::messaging::get_messages,
             commands::messaging::mark_thread_read,
             commands::messaging::delete_thread,
             commands::messaging::delete_message,
+            commands::messaging::star_message,
+            commands::messaging::unstar_message,
+            commands::messaging::get_starred_messages,
             commands::messaging::add_reaction,
+            commands::messaging::get_thread_sealed_sender,
+            commands::messaging::set_thread_sealed_sender,
+            commands::messaging::get_compression_threshold,
+            commands::messaging::set_compression_threshold,
+            commands::messaging::get_link_previews,
+            commands::messaging::set_link_previews,
             commands::messaging::save_sent_email_message,
             commands::messaging::request_message_decryption,
+            commands::messaging::quick_send,
+            commands::messaging::get_rate_limit_state,
+            commands::messaging::accept_request,
+            commands::messaging::mark_spam,
             commands::messaging::resolve_handle,
             // Breadcrumb commands
             commands::breadcrumbs::get_breadcrumb_count,
@@ -234,22 +533,48 @@ pub fn run() {
             commands::breadcrumbs::drop_breadcrumb,
             commands::breadcrumbs::list_breadcrumbs,
             commands::breadcrumbs::restore_breadcrumbs,
+            commands::breadcrumbs::get_breadcrumb_heatmap,
+            commands::breadcrumbs::get_trust_score,
+            commands::breadcrumbs::add_privacy_zone,
+            commands::breadcrumbs::remove_privacy_zone,
+            commands::breadcrumbs::list_privacy_zones,
+            commands::breadcrumbs::report_motion_activity,
+            commands::breadcrumbs::should_collect_breadcrumb,
+            commands::breadcrumbs::set_device_conditions,
+            commands::breadcrumbs::sync_breadcrumbs_now,
+            commands::breadcrumbs::get_breadcrumb_upload_paused,
+            commands::breadcrumbs::set_breadcrumb_upload_paused,
             // Network commands
             commands::network::get_connection_status,
             commands::network::reconnect,
+            commands::network::get_dns_over_https,
+            commands::network::set_dns_over_https,
+            commands::network::set_app_foreground,
+            commands::network::run_background_fetch,
             // Stellar/GNS Token commands
             commands::stellar::get_stellar_address,
             commands::stellar::get_stellar_explorer_url,
             commands::stellar::get_stellar_balances,
             commands::stellar::claim_gns_tokens,
+            commands::stellar::get_earnings_summary,
             commands::stellar::create_gns_trustline,
+            commands::stellar::preview_transaction,
             commands::stellar::send_gns,
             commands::stellar::fund_testnet_account,
             commands::stellar::get_payment_history,
+            commands::stellar::export_payment_history,
+            commands::stellar::get_price_source,
+            commands::stellar::set_price_source,
+            commands::stellar::list_hardware_wallets,
+            commands::stellar::sign_with_hardware,
+            commands::stellar::get_hardware_signing,
+            commands::stellar::set_hardware_signing,
             // Utility commands
             commands::utils::get_app_version,
             commands::utils::open_external_url,
             commands::utils::get_offline_status,
+            commands::utils::get_quick_compose_shortcut,
+            commands::utils::set_quick_compose_shortcut,
             // Dix commands
             commands::dix::create_post,
             commands::dix::get_timeline,
@@ -257,7 +582,132 @@ pub fn run() {
             commands::dix::repost_post,
             commands::dix::get_post,
             commands::dix::get_posts_by_user,
-        ])
+            commands::dix::reply_to_post,
+            commands::dix::get_replies,
+            commands::dix::follow_user,
+            commands::dix::unfollow_user,
+            commands::dix::get_following,
+            commands::dix::get_followers,
+            commands::dix::get_posts_by_hashtag,
+            commands::dix::get_mentions,
+            commands::dix::bookmark_post,
+            commands::dix::unbookmark_post,
+            commands::dix::get_bookmarks,
+            commands::dix::get_bookmark_collections,
+            commands::dix::delete_post,
+            commands::dix::edit_post,
+            commands::dix::get_filters,
+            commands::dix::add_muted_word,
+            commands::dix::remove_muted_word,
+            commands::dix::mute_author,
+            commands::dix::unmute_author,
+            commands::dix::share_post_to_thread,
+            commands::dix::quote_message_to_dix,
+            commands::dix::get_notifications,
+            commands::dix::mark_notifications_read,
+            // Report commands
+            commands::reports::report_content,
+            commands::reports::get_reports,
+            // Export commands
+            commands::export::export_thread,
+            commands::export::export_all_messages,
+            commands::export::export_app_data,
+            commands::export::import_app_data,
+            // Verification commands
+            commands::verifications::create_verification_challenge,
+            commands::verifications::submit_verification,
+            commands::verifications::list_verifications,
+            // Safety number commands
+            commands::safety::get_safety_number,
+            commands::safety::set_contact_verified,
+            commands::safety::get_contact_key,
+            commands::safety::refresh_contact_keys,
+            // Contact request commands
+            commands::contact_requests::list_contact_requests,
+            commands::contact_requests::accept_contact_request,
+            commands::contact_requests::decline_contact_request,
+            commands::contacts::import_device_contacts,
+            commands::contacts::list_contacts,
+            commands::contacts::remove_contact,
+            commands::labels::create_label,
+            commands::labels::list_labels,
+            commands::labels::delete_label,
+            commands::labels::assign_label,
+            commands::labels::get_threads_by_label,
+            commands::channels::create_channel,
+            commands::channels::publish_to_channel,
+            commands::channels::subscribe_channel,
+            commands::channels::get_channel_posts,
+            commands::polls::create_poll,
+            commands::polls::vote_poll,
+            commands::polls::get_poll_results,
+            commands::calendar::send_event_invite,
+            commands::calendar::respond_to_invite,
+            commands::calendar::get_agenda,
+            commands::calendar::export_ics,
+            commands::invoices::send_invoice,
+            commands::invoices::pay_invoice,
+            commands::invoices::get_invoices,
+            commands::location::share_location,
+            commands::location::update_live_location,
+            commands::location::stop_location_share,
+            commands::location::get_live_locations,
+            commands::proximity::prove_proximity,
+            commands::proximity::get_proximity_attestations,
+            commands::breadcrumbs::audit_breadcrumbs,
+            commands::breadcrumbs::trajectory_stats,
+            // Presence commands
+            commands::presence::get_presence,
+            commands::presence::get_share_presence,
+            commands::presence::set_share_presence,
+            // LAN delivery commands
+            commands::lan::get_lan_delivery,
+            commands::lan::set_lan_delivery,
+            commands::lan::list_lan_peers,
+            // Local API commands
+            commands::local_api::get_local_api,
+            commands::local_api::set_local_api,
+            // Rules commands
+            commands::rules::create_rule,
+            commands::rules::list_rules,
+            commands::rules::delete_rule,
+            commands::rules::get_rule_script_requests,
+            commands::rules::confirm_rule_script,
+            // Push commands
+            commands::push::register_push_token,
+            commands::push::unregister_push_token,
+            commands::push::get_push_registration,
+            commands::push::handle_push_notification,
+            // Deep link commands
+            commands::deep_links::register_pending_deep_link,
+            commands::deep_links::take_pending_deep_links,
+            // Diagnostics commands
+            commands::diagnostics::get_recent_logs,
+            commands::diagnostics::get_log_settings,
+            commands::diagnostics::set_log_level,
+            commands::diagnostics::get_app_health,
+            commands::diagnostics::export_diagnostics,
+            // Maintenance commands
+            commands::maintenance::get_db_stats,
+            commands::maintenance::run_db_maintenance,
+            commands::maintenance::get_retention_policy,
+            commands::maintenance::set_retention_policy,
+            // Attachment commands
+            commands::attachments::get_attachment,
+            commands::attachments::get_storage_usage,
+            commands::attachments::clear_attachment_cache,
+            // App lock commands
+            commands::app_lock::get_app_lock_status,
+            commands::app_lock::set_app_lock,
+            commands::app_lock::lock_app,
+            commands::app_lock::unlock_app,
+            commands::app_lock::unlock_app_biometric,
+            commands::app_lock::set_duress_pin,
+            // Wipe commands
+            commands::wipe::wipe_all_data,
+            commands::wipe::get_remote_wipe_enabled,
+            commands::wipe::set_remote_wipe_enabled,
+        ])))
         .run(tauri::generate_context!())
         .expect("Error while running GNS Browser");
 }
@@ -0,0 +1,399 @@
+//! Local API - JSON-RPC over WebSocket for tools on this machine
+//!
+//! When enabled, the app listens on `127.0.0.1` for WebSocket connections
+//! carrying JSON-RPC 2.0 requests, so bots, scripts and browser extensions
+//! can use the running app. Only a few commands are exposed: sending a
+//! message, listing threads, and resolving a handle. They run exactly as
+//! when the UI calls them.
+//!
+//! A connection must present the session token, either as
+//! `Authorization: Bearer <token>` or as `?token=<token>` (browsers can't
+//! set WebSocket headers). The token is new each time the server starts
+//! and is shown in settings. App errors come back in the JSON-RPC error's
+//! `data` as `{ code, message, context }`. While the app is locked every
+//! call is refused with code -32001.
+//!
+//! Off by default.
+
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::app_lock::{AppLock, LOCKED_ERROR};
+use crate::commands::messaging::{self, HandleInfo, SendResult, ThreadPreview};
+use crate::error::AppError;
+use crate::AppState;
+
+/// Port the server listens on
+pub const LOCAL_API_PORT: u16 = 7865;
+
+/// Methods a client may call
+pub const METHODS: &[&str] = &["send_message", "get_threads", "resolve_handle"];
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// A command failed; `data` is the `AppError`
+const APP_ERROR: i64 = -32000;
+/// The app is locked; nothing runs until the user unlocks it
+const LOCKED: i64 = -32001;
+
+/// What the settings screen shows
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalApiStatus {
+    pub enabled: bool,
+    /// Where to connect, while the server is running
+    pub url: Option<String>,
+    /// Token for this session, while the server is running
+    pub token: Option<String>,
+}
+
+pub struct LocalApi {
+    enabled: AtomicBool,
+    /// Port the listener is bound to; 0 while stopped
+    port: AtomicU16,
+    token: Mutex<Option<String>>,
+    /// Dropping it stops the listener and its connections
+    stop: Mutex<Option<watch::Sender<()>>>,
+}
+
+impl LocalApi {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            port: AtomicU16::new(0),
+            token: Mutex::new(None),
+            stop: Mutex::new(None),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn status(&self) -> LocalApiStatus {
+        let port = self.port.load(Ordering::Relaxed);
+        let token = self.token();
+        LocalApiStatus {
+            enabled: self.is_enabled(),
+            url: (port != 0 && token.is_some()).then(|| format!("ws://127.0.0.1:{}", port)),
+            token: token.filter(|_| port != 0),
+        }
+    }
+
+    /// Listen with a fresh token, unless already listening
+    pub async fn start(self: Arc<Self>, app_handle: AppHandle) -> Result<(), String> {
+        if self.port.load(Ordering::Relaxed) != 0 {
+            return Ok(());
+        }
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, LOCAL_API_PORT))
+            .await
+            .map_err(|e| format!("Could not listen on port {}: {}", LOCAL_API_PORT, e))?;
+
+        let (stop_tx, mut stop) = watch::channel(());
+        *self.stop.lock().unwrap_or_else(|e| e.into_inner()) = Some(stop_tx);
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = Some(new_token());
+        self.enabled.store(true, Ordering::Relaxed);
+        self.port.store(LOCAL_API_PORT, Ordering::Relaxed);
+        tracing::info!("Local API listening on 127.0.0.1:{}", LOCAL_API_PORT);
+
+        let api = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    _ = stop.changed() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            tracing::warn!("Local API accept failed: {}", e);
+                            continue;
+                        }
+                    },
+                };
+                let Some(token) = api.token() else {
+                    break;
+                };
+                let (app_handle, mut stop) = (app_handle.clone(), stop.clone());
+                tauri::async_runtime::spawn(async move {
+                    tokio::select! {
+                        _ = stop.changed() => {}
+                        _ = serve(stream, token, app_handle) => {}
+                    }
+                });
+            }
+            tracing::info!("Local API stopped");
+        });
+        Ok(())
+    }
+
+    /// Close the listener and every connection, and forget the token
+    pub fn stop(&self) {
+        self.stop.lock().unwrap_or_else(|e| e.into_inner()).take();
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.port.store(0, Ordering::Relaxed);
+    }
+
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+        self.stop();
+    }
+}
+
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// The token a handshake presents, from the header or the query string
+pub fn presented_token(request: &Request) -> Option<String> {
+    let header = request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = request
+        .uri()
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="));
+    header.or(query).map(|token| token.trim().to_string())
+}
+
+/// Compare without leaking how much of the token matched
+fn token_matches(presented: &str, token: &str) -> bool {
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+// The handshake callback's error type is tungstenite's, not ours
+#[allow(clippy::result_large_err)]
+async fn serve(stream: TcpStream, token: String, app_handle: AppHandle) {
+    let callback = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        match presented_token(request) {
+            Some(presented) if token_matches(&presented, &token) => Ok(response),
+            _ => {
+                let mut denied = ErrorResponse::new(Some("Invalid or missing token".to_string()));
+                *denied.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(denied)
+            }
+        }
+    };
+    let Ok(ws) = tokio_tungstenite::accept_hdr_async(stream, callback).await else {
+        tracing::debug!("Local API connection refused");
+        return;
+    };
+    let (mut write, mut read) = ws.split();
+
+    while let Some(Ok(message)) = read.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        if let Some(reply) = handle_request(&app_handle, &text).await {
+            if write.send(Message::Text(reply.to_string())).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// A method call; `params` as the command takes them
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+enum Call {
+    SendMessage {
+        recipient_handle: Option<String>,
+        recipient_public_key: Option<String>,
+        #[serde(default = "default_payload_type")]
+        payload_type: String,
+        payload: Value,
+        thread_id: Option<String>,
+        reply_to_id: Option<String>,
+    },
+    GetThreads {
+        include_archived: Option<bool>,
+        limit: Option<u32>,
+        junk: Option<bool>,
+    },
+    ResolveHandle {
+        handle: String,
+    },
+}
+
+fn default_payload_type() -> String {
+    "text/plain".to_string()
+}
+
+/// Parse a request, checking the method against `METHODS`. Err is the
+/// error response to send.
+fn parse_request(text: &str) -> Result<(Option<Value>, Call), Value> {
+    let request: Value = serde_json::from_str(text).map_err(|e| rpc_error(Value::Null, PARSE_ERROR, &e.to_string()))?;
+    let id = request.get("id").cloned();
+    let reply_id = id.clone().unwrap_or(Value::Null);
+    if request["jsonrpc"] != "2.0" {
+        return Err(rpc_error(reply_id, INVALID_REQUEST, "Expected a JSON-RPC 2.0 request object"));
+    }
+    let method = request["method"]
+        .as_str()
+        .ok_or_else(|| rpc_error(reply_id.clone(), INVALID_REQUEST, "Missing method"))?;
+    if !METHODS.contains(&method) {
+        return Err(rpc_error(reply_id, METHOD_NOT_FOUND, &format!("Unknown method: {}", method)));
+    }
+    let params = match &request["params"] {
+        Value::Null => json!({}),
+        params => params.clone(),
+    };
+    let call = serde_json::from_value(json!({ "method": method, "params": params }))
+        .map_err(|e| rpc_error(reply_id, INVALID_PARAMS, &e.to_string()))?;
+    Ok((id, call))
+}
+
+/// Refuse calls while the app is locked, like the app's own commands
+fn check_unlocked(app_lock: &AppLock, id: &Option<Value>) -> Result<(), Value> {
+    if app_lock.is_locked() {
+        return Err(rpc_error(id.clone().unwrap_or(Value::Null), LOCKED, LOCKED_ERROR));
+    }
+    Ok(())
+}
+
+/// Answer one request; None for notifications, which get no reply
+async fn handle_request(app_handle: &AppHandle, text: &str) -> Option<Value> {
+    let (id, call) = match parse_request(text) {
+        Ok(parsed) => parsed,
+        Err(error) => return Some(error),
+    };
+    if let Err(error) = check_unlocked(&app_handle.state::<AppState>().app_lock, &id) {
+        return id.map(|_| error);
+    }
+    let result = dispatch(app_handle, call).await;
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": APP_ERROR, "message": e.to_string(), "data": e },
+        }),
+    })
+}
+
+async fn dispatch(app_handle: &AppHandle, call: Call) -> Result<Value, AppError> {
+    let state = app_handle.state::<AppState>();
+    match call {
+        Call::SendMessage { recipient_handle, recipient_public_key, payload_type, payload, thread_id, reply_to_id } => {
+            if payload_type == crate::wipe::REMOTE_WIPE_PAYLOAD_TYPE {
+                return Err("Remote wipes can't be sent through the local API".into());
+            }
+            let sent: SendResult = messaging::send_message(
+                recipient_handle,
+                recipient_public_key,
+                payload_type,
+                payload,
+                thread_id,
+                reply_to_id,
+                app_handle.clone(),
+                state,
+            )
+            .await?;
+            Ok(serde_json::to_value(sent)?)
+        }
+        Call::GetThreads { include_archived, limit, junk } => {
+            let threads: Vec<ThreadPreview> = messaging::get_threads(include_archived, limit, junk, state).await?;
+            Ok(serde_json::to_value(threads)?)
+        }
+        Call::ResolveHandle { handle } => {
+            let info: Option<HandleInfo> = messaging::resolve_handle(handle, state).await?;
+            Ok(serde_json::to_value(info)?)
+        }
+    }
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let (id, call) = parse_request(
+            r#"{"jsonrpc":"2.0","id":1,"method":"send_message","params":{"recipient_handle":"alice","payload":{"text":"hi"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(id, Some(json!(1)));
+        assert!(matches!(call, Call::SendMessage { payload_type, .. } if payload_type == "text/plain"));
+
+        let (id, call) = parse_request(r#"{"jsonrpc":"2.0","method":"get_threads"}"#).unwrap();
+        assert_eq!(id, None);
+        assert!(matches!(call, Call::GetThreads { limit: None, .. }));
+
+        let error = |text: &str| parse_request(text).unwrap_err()["error"]["code"].clone();
+        assert_eq!(error("{"), json!(PARSE_ERROR));
+        assert_eq!(error(r#"{"id":1,"method":"get_threads"}"#), json!(INVALID_REQUEST));
+        assert_eq!(error(r#"{"jsonrpc":"2.0","id":1,"method":"wipe_device"}"#), json!(METHOD_NOT_FOUND));
+        assert_eq!(error(r#"{"jsonrpc":"2.0","id":1,"method":"resolve_handle","params":{}}"#), json!(INVALID_PARAMS));
+    }
+
+    #[test]
+    fn test_refused_while_locked() {
+        let locked = AppLock::new(crate::app_lock::LockConfig {
+            pin_hash: Some("$argon2id$placeholder".to_string()),
+            ..Default::default()
+        });
+        let error = check_unlocked(&locked, &Some(json!(7))).unwrap_err();
+        assert_eq!(error["id"], json!(7));
+        assert_eq!(error["error"]["code"], json!(LOCKED));
+        assert_eq!(error["error"]["message"], json!(LOCKED_ERROR));
+
+        let unlocked = AppLock::new(crate::app_lock::LockConfig::default());
+        assert!(check_unlocked(&unlocked, &Some(json!(7))).is_ok());
+    }
+
+    #[test]
+    fn test_presented_token() {
+        let request = Request::builder()
+            .uri("ws://127.0.0.1:7865/?token=abc")
+            .body(())
+            .unwrap();
+        assert_eq!(presented_token(&request).as_deref(), Some("abc"));
+
+        let request = Request::builder()
+            .uri("ws://127.0.0.1:7865/")
+            .header("Authorization", "Bearer def")
+            .body(())
+            .unwrap();
+        assert_eq!(presented_token(&request).as_deref(), Some("def"));
+        assert!(token_matches("def", "def"));
+        assert!(!token_matches("dee", "def"));
+        assert!(!token_matches("de", "def"));
+
+        let request = Request::builder().uri("ws://127.0.0.1:7865/").body(()).unwrap();
+        assert_eq!(presented_token(&request), None);
+    }
+}
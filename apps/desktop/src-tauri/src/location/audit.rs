@@ -0,0 +1,135 @@
+//! Breadcrumb Audit - Re-checking stored breadcrumbs
+//!
+//! Every stored breadcrumb should be ours and verify. Rows signed by
+//! another identity (an import over an existing database) or altered on
+//! disk are rejected by the server, and a claim built on them fails. The
+//! audit finds them and, if asked, moves them out of the breadcrumbs
+//! table into quarantine.
+
+use gns_crypto_core::Breadcrumb;
+use serde::Serialize;
+
+/// Clock skew allowed before a timestamp counts as in the future (seconds)
+const MAX_CLOCK_SKEW_SECONDS: i64 = 10 * 60;
+
+/// What's wrong with a stored breadcrumb
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// The signature doesn't verify
+    InvalidSignature,
+    /// Validly signed, but by another identity
+    ForeignKey,
+    /// Signed later than now, so the device clock was wrong
+    FutureTimestamp,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::InvalidSignature => "invalid_signature",
+            AnomalyKind::ForeignKey => "foreign_key",
+            AnomalyKind::FutureTimestamp => "future_timestamp",
+        }
+    }
+
+    /// Whether rows with this anomaly are quarantined
+    pub fn quarantines(&self) -> bool {
+        matches!(self, AnomalyKind::InvalidSignature | AnomalyKind::ForeignKey)
+    }
+}
+
+/// One problem row
+#[derive(Debug, Clone, Serialize)]
+pub struct BreadcrumbAnomaly {
+    pub id: i64,
+    pub h3_index: String,
+    pub timestamp: i64,
+    pub kind: AnomalyKind,
+}
+
+/// The audit result
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    pub checked: u32,
+    pub valid: u32,
+    pub anomalies: Vec<BreadcrumbAnomaly>,
+    /// Rows moved to quarantine by this audit
+    pub quarantined: u32,
+}
+
+/// Check stored rows against `public_key`; `now` is in Unix seconds like
+/// breadcrumb timestamps
+pub fn audit(rows: &[(i64, Breadcrumb)], public_key: &str, now: i64) -> AuditReport {
+    let mut report = AuditReport::default();
+    for (id, breadcrumb) in rows {
+        report.checked += 1;
+        // Rows stored before signers were recorded are checked against our key
+        let signed = if breadcrumb.public_key.is_empty() {
+            Breadcrumb {
+                public_key: public_key.to_string(),
+                ..breadcrumb.clone()
+            }
+        } else {
+            breadcrumb.clone()
+        };
+        let kind = if !signed.verify().unwrap_or(false) {
+            Some(AnomalyKind::InvalidSignature)
+        } else if !signed.public_key.eq_ignore_ascii_case(public_key) {
+            Some(AnomalyKind::ForeignKey)
+        } else if breadcrumb.timestamp > now + MAX_CLOCK_SKEW_SECONDS {
+            Some(AnomalyKind::FutureTimestamp)
+        } else {
+            None
+        };
+        match kind {
+            Some(kind) => report.anomalies.push(BreadcrumbAnomaly {
+                id: *id,
+                h3_index: breadcrumb.h3_index.clone(),
+                timestamp: breadcrumb.timestamp,
+                kind,
+            }),
+            None => report.valid += 1,
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::{create_breadcrumb, GnsIdentity};
+
+    #[test]
+    fn test_audit() {
+        let me = GnsIdentity::generate();
+        let other = GnsIdentity::generate();
+        let mine = create_breadcrumb(&me, 52.52, 13.405, None, None).unwrap();
+        let foreign = create_breadcrumb(&other, 52.52, 13.405, None, None).unwrap();
+        let mut tampered = mine.clone();
+        tampered.h3_index = foreign.h3_index.clone() + "0";
+        let now = mine.timestamp;
+
+        let mut legacy = mine.clone();
+        legacy.public_key = String::new();
+        let mut unknown = foreign.clone();
+        unknown.public_key = String::new();
+
+        let rows = [(1, mine.clone()), (2, foreign), (3, tampered), (4, legacy), (5, unknown)];
+        let report = audit(&rows, &me.public_key_hex(), now);
+        assert_eq!(report.checked, 5);
+        assert_eq!(report.valid, 2);
+        let kinds: Vec<_> = report.anomalies.iter().map(|a| (a.id, a.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (2, AnomalyKind::ForeignKey),
+                (3, AnomalyKind::InvalidSignature),
+                (5, AnomalyKind::InvalidSignature)
+            ]
+        );
+
+        let report = audit(&[(1, mine)], &me.public_key_hex(), now - 3600);
+        assert_eq!(report.anomalies[0].kind, AnomalyKind::FutureTimestamp);
+    }
+}
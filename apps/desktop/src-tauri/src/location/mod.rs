@@ -2,10 +2,31 @@
 //!
 //! Handles GPS location collection and breadcrumb creation.
 //! Only active on mobile platforms (iOS/Android).
-
+//!
+//! In motion-aware mode a breadcrumb is only created once the device has
+//! moved into a new H3 cell. On iOS, CoreMotion activity updates run while
+//! collection is enabled and reach the collector through
+//! `native::gns_report_motion`. Android has no Activity Recognition bridge
+//! yet, as the tree has no Android project. Until a signal arrives, the
+//! activity stays `Unknown` and every 10-minute interval asks for a fix,
+//! keeping it only if it lands in a new cell.
+
+pub mod audit;
+pub mod share;
+pub mod sync;
+
+use gns_crypto_core::breadcrumb::{lat_lng_to_h3, DEFAULT_H3_RESOLUTION};
 use gns_crypto_core::{create_breadcrumb, Breadcrumb, GnsIdentity};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::time::{Duration, Instant};
 
+/// Privacy zone radius bounds in meters
+pub const MIN_ZONE_RADIUS_M: f64 = 50.0;
+pub const MAX_ZONE_RADIUS_M: f64 = 2000.0;
+
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
 /// Collection strategy based on user lifecycle
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CollectionStrategy {
@@ -33,6 +54,83 @@ impl std::fmt::Display for CollectionStrategy {
     }
 }
 
+/// Activity as the OS classifies it (CoreMotion on iOS, Activity Recognition on Android)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MotionActivity {
+    Stationary,
+    Walking,
+    Running,
+    Cycling,
+    Automotive,
+    /// No signal yet, or the OS isn't confident
+    Unknown,
+}
+
+impl MotionActivity {
+    pub fn is_moving(&self) -> bool {
+        !matches!(self, MotionActivity::Stationary)
+    }
+}
+
+/// A place (home, work) where no breadcrumbs are created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyZone {
+    pub id: String,
+    pub name: String,
+
+    /// H3 cells at the breadcrumb resolution covered by the zone
+    pub cells: Vec<String>,
+    pub created_at: i64,
+}
+
+impl PrivacyZone {
+    /// Build a zone covering a circle around a point
+    pub fn around(name: &str, latitude: f64, longitude: f64, radius_meters: f64) -> Result<Self, CollectorError> {
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            cells: zone_cells(latitude, longitude, radius_meters)?,
+            created_at: chrono::Utc::now().timestamp(),
+        })
+    }
+}
+
+/// H3 cells whose area overlaps a circle, sampled at half the cell size
+pub fn zone_cells(latitude: f64, longitude: f64, radius_meters: f64) -> Result<Vec<String>, CollectorError> {
+    let radius = radius_meters.clamp(MIN_ZONE_RADIUS_M, MAX_ZONE_RADIUS_M);
+    let lat_span = radius / METERS_PER_DEGREE;
+    let lng_span = radius / (METERS_PER_DEGREE * latitude.to_radians().cos().max(0.01));
+    let step = 0.0005;
+
+    let to_cell = |lat: f64, lng: f64| {
+        lat_lng_to_h3(lat, lng, DEFAULT_H3_RESOLUTION)
+            .map_err(|e| CollectorError::LocationError(e.to_string()))
+    };
+
+    let mut cells = HashSet::new();
+    cells.insert(to_cell(latitude, longitude)?);
+
+    let lat_steps = (lat_span / step).ceil() as i64;
+    let lng_steps = (lng_span / step).ceil() as i64;
+    for i in -lat_steps..=lat_steps {
+        for j in -lng_steps..=lng_steps {
+            let dlat = i as f64 * step;
+            let dlng = j as f64 * step;
+            if (dlat / lat_span).powi(2) + (dlng / lng_span).powi(2) > 1.0 {
+                continue;
+            }
+            let lat = (latitude + dlat).clamp(-90.0, 90.0);
+            let lng = (longitude + dlng).clamp(-180.0, 180.0);
+            cells.insert(to_cell(lat, lng)?);
+        }
+    }
+
+    let mut cells: Vec<String> = cells.into_iter().collect();
+    cells.sort();
+    Ok(cells)
+}
+
 /// Breadcrumb collector
 pub struct BreadcrumbCollector {
     /// Current collection strategy
@@ -55,6 +153,18 @@ pub struct BreadcrumbCollector {
 
     /// Is device charging
     is_charging: bool,
+
+    /// Cells where collection pauses
+    privacy_cells: HashSet<String>,
+
+    /// Latest OS activity signal
+    motion: MotionActivity,
+
+    /// Has the device moved since the last breadcrumb
+    moved_since_collection: bool,
+
+    /// Cell of the last breadcrumb
+    last_cell: Option<String>,
 }
 
 impl BreadcrumbCollector {
@@ -68,6 +178,10 @@ impl BreadcrumbCollector {
             handle_claimed: false,
             battery_level: 1.0,
             is_charging: false,
+            privacy_cells: HashSet::new(),
+            motion: MotionActivity::Unknown,
+            moved_since_collection: true,
+            last_cell: None,
         }
     }
 
@@ -173,6 +287,71 @@ impl BreadcrumbCollector {
     pub fn record_collection(&mut self) {
         self.last_collection = Some(Instant::now());
         self.breadcrumb_count += 1;
+        self.moved_since_collection = false;
+    }
+
+    /// Record a successful collection in a cell
+    pub fn record_collection_at(&mut self, h3_cell: &str) {
+        self.record_collection();
+        self.last_cell = Some(h3_cell.to_string());
+    }
+
+    /// Feed an OS motion signal. `significant_motion` is the one-shot
+    /// significant-change / significant-motion trigger.
+    pub fn report_motion(&mut self, activity: MotionActivity, significant_motion: bool) {
+        if activity != self.motion {
+            tracing::debug!("Motion activity: {:?} -> {:?}", self.motion, activity);
+        }
+        self.motion = activity;
+        if activity.is_moving() || significant_motion {
+            self.moved_since_collection = true;
+        }
+    }
+
+    /// Latest OS activity signal
+    pub fn motion_activity(&self) -> MotionActivity {
+        self.motion
+    }
+
+    /// Is a location fix worth requesting? In motion-aware mode a stationary
+    /// device skips the GPS entirely; without any motion signal, every
+    /// interval asks.
+    pub fn needs_location_fix(&self) -> bool {
+        if !self.should_collect() {
+            return false;
+        }
+        self.strategy != CollectionStrategy::MotionAware
+            || self.moved_since_collection
+            || self.motion == MotionActivity::Unknown
+    }
+
+    /// Should a breadcrumb be created at this fix? In motion-aware mode only
+    /// when the device reached a new cell.
+    pub fn should_collect_at(&self, latitude: f64, longitude: f64) -> bool {
+        if !self.needs_location_fix() || self.in_privacy_zone(latitude, longitude) {
+            return false;
+        }
+        if self.strategy != CollectionStrategy::MotionAware {
+            return true;
+        }
+        match (&self.last_cell, lat_lng_to_h3(latitude, longitude, DEFAULT_H3_RESOLUTION)) {
+            (Some(last), Ok(cell)) => *last != cell,
+            (None, Ok(_)) => true,
+            (_, Err(_)) => false,
+        }
+    }
+
+    /// Replace the privacy zones collection pauses in
+    pub fn set_privacy_zones(&mut self, zones: &[PrivacyZone]) {
+        self.privacy_cells = zones.iter().flat_map(|z| z.cells.iter().cloned()).collect();
+    }
+
+    /// Is this location inside a privacy zone?
+    pub fn in_privacy_zone(&self, latitude: f64, longitude: f64) -> bool {
+        !self.privacy_cells.is_empty()
+            && lat_lng_to_h3(latitude, longitude, DEFAULT_H3_RESOLUTION)
+                .map(|cell| self.privacy_cells.contains(&cell))
+                .unwrap_or(false)
     }
 
     /// Create a breadcrumb from coordinates
@@ -182,6 +361,10 @@ impl BreadcrumbCollector {
         latitude: f64,
         longitude: f64,
     ) -> Result<Breadcrumb, CollectorError> {
+        if self.in_privacy_zone(latitude, longitude) {
+            return Err(CollectorError::InPrivacyZone);
+        }
+
         create_breadcrumb(identity, latitude, longitude, None, None)
             .map_err(|e| CollectorError::CryptoError(e.to_string()))
     }
@@ -204,4 +387,57 @@ pub enum CollectorError {
 
     #[error("Permission denied")]
     PermissionDenied,
+
+    #[error("Location is inside a privacy zone")]
+    InPrivacyZone,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_privacy_zone_pauses_collection() {
+        let zone = PrivacyZone::around("Home", 52.5200, 13.4050, 200.0).unwrap();
+        assert!(zone.cells.len() > 1);
+
+        let mut collector = BreadcrumbCollector::new();
+        collector.set_privacy_zones(&[zone]);
+        assert!(collector.in_privacy_zone(52.5205, 13.4055));
+        assert!(!collector.in_privacy_zone(52.5300, 13.4050));
+
+        let identity = GnsIdentity::generate();
+        assert!(matches!(
+            collector.create_breadcrumb(&identity, 52.5200, 13.4050),
+            Err(CollectorError::InPrivacyZone)
+        ));
+        assert!(collector.create_breadcrumb(&identity, 48.8566, 2.3522).is_ok());
+    }
+
+    #[test]
+    fn test_motion_aware_collection() {
+        let mut collector = BreadcrumbCollector::new();
+        collector.start().unwrap();
+        collector.update_state(150, true, 0.8, false);
+        assert_eq!(collector.current_strategy(), CollectionStrategy::MotionAware);
+
+        let cell = lat_lng_to_h3(52.5200, 13.4050, DEFAULT_H3_RESOLUTION).unwrap();
+        collector.record_collection_at(&cell);
+        // Force the interval to have elapsed
+        collector.last_collection = Some(Instant::now() - Duration::from_secs(601));
+
+        // No motion signal yet: ask for a fix, keep it only in a new cell
+        assert!(collector.needs_location_fix());
+        assert!(!collector.should_collect_at(52.5200, 13.4050));
+
+        // Stationary: no GPS fix needed
+        collector.report_motion(MotionActivity::Stationary, false);
+        assert!(!collector.needs_location_fix());
+
+        // Moving, but still in the same cell
+        collector.report_motion(MotionActivity::Walking, false);
+        assert!(collector.needs_location_fix());
+        assert!(!collector.should_collect_at(52.5200, 13.4050));
+        assert!(collector.should_collect_at(52.5300, 13.4050));
+    }
 }
@@ -0,0 +1,128 @@
+//! Location Sharing - H3 cells sent in envelopes
+//!
+//! A `gns/location` message carries an H3 cell, never raw coordinates,
+//! quantized the same way breadcrumbs are: at `DEFAULT_H3_RESOLUTION` or
+//! coarser. A share is either a one-off (the current cell, or a cell
+//! pinned on the map) or live until `live_until`, in which case later
+//! messages with the same `share_id` move it. Only the first message of
+//! a share is stored in the thread; updates just replace the cell kept
+//! for the share.
+
+use gns_crypto_core::breadcrumb::{h3_to_lat_lng, h3_to_parent, lat_lng_to_h3, DEFAULT_H3_RESOLUTION};
+use serde::{Deserialize, Serialize};
+
+use super::PrivacyZone;
+
+/// Payload type for a shared location
+pub const LOCATION_PAYLOAD_TYPE: &str = "gns/location";
+
+/// Longest a live share can run
+pub const MAX_LIVE_MINUTES: u32 = 8 * 60;
+
+/// Longest label on a pinned location
+pub const MAX_LOCATION_LABEL: usize = 100;
+
+/// The payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocationShare {
+    pub share_id: String,
+    pub h3_cell: String,
+    pub resolution: u8,
+    /// Set for live shares; an update with this in the past ends the share
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_until: Option<i64>,
+    /// When the sender was in this cell (ms)
+    pub updated_at: i64,
+    /// What a pinned location is ("Meet here")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// A share we sent or received, with its latest cell
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedLocation {
+    #[serde(flatten)]
+    pub share: LocationShare,
+    pub thread_id: Option<String>,
+    /// The other side of the share
+    pub peer_public_key: String,
+    pub outgoing: bool,
+    /// Center of the cell, for the map
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl SharedLocation {
+    pub fn new(share: LocationShare, thread_id: Option<String>, peer_public_key: String, outgoing: bool) -> Self {
+        let (latitude, longitude) = h3_to_lat_lng(&share.h3_cell).unwrap_or_default();
+        Self {
+            share,
+            thread_id,
+            peer_public_key,
+            outgoing,
+            latitude,
+            longitude,
+        }
+    }
+
+    pub fn is_live(&self, now: i64) -> bool {
+        self.share.live_until.is_some_and(|until| until > now)
+    }
+}
+
+/// The cell shared for a position: the breadcrumb cell, or its parent when
+/// a coarser `resolution` is asked for
+pub fn quantize(latitude: f64, longitude: f64, resolution: u8) -> Result<String, String> {
+    let cell = lat_lng_to_h3(latitude, longitude, DEFAULT_H3_RESOLUTION).map_err(|e| e.to_string())?;
+    h3_to_parent(&cell, resolution.min(DEFAULT_H3_RESOLUTION)).map_err(|e| e.to_string())
+}
+
+/// Re-quantize a pinned cell, so a finer cell picked on the map isn't
+/// shared more precisely than a live position would be
+pub fn quantize_cell(h3_cell: &str, resolution: u8) -> Result<String, String> {
+    let (latitude, longitude) = h3_to_lat_lng(h3_cell).map_err(|e| e.to_string())?;
+    quantize(latitude, longitude, resolution)
+}
+
+/// The privacy zone a position falls in, if any
+pub fn privacy_zone_at(zones: &[PrivacyZone], latitude: f64, longitude: f64) -> Option<&PrivacyZone> {
+    let cell = lat_lng_to_h3(latitude, longitude, DEFAULT_H3_RESOLUTION).ok()?;
+    zones.iter().find(|zone| zone.cells.contains(&cell))
+}
+
+/// Check a received share's cell and label
+pub fn check_share(share: &LocationShare) -> Result<(), String> {
+    if share.resolution > DEFAULT_H3_RESOLUTION {
+        return Err(format!("Locations are shared at resolution {} or coarser", DEFAULT_H3_RESOLUTION));
+    }
+    h3_to_lat_lng(&share.h3_cell).map_err(|e| e.to_string())?;
+    if share.label.as_ref().is_some_and(|label| label.chars().count() > MAX_LOCATION_LABEL) {
+        return Err(format!("Location labels are at most {} characters", MAX_LOCATION_LABEL));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantize() {
+        let cell = quantize(52.5200, 13.4050, 15).unwrap();
+        assert_eq!(cell, lat_lng_to_h3(52.5200, 13.4050, DEFAULT_H3_RESOLUTION).unwrap());
+        assert_eq!(quantize_cell(&cell, DEFAULT_H3_RESOLUTION).unwrap(), cell);
+
+        let coarse = quantize(52.5200, 13.4050, 5).unwrap();
+        assert_eq!(coarse, h3_to_parent(&cell, 5).unwrap());
+        assert!(quantize(91.0, 0.0, 7).is_err());
+        assert!(quantize_cell("not a cell", 7).is_err());
+    }
+
+    #[test]
+    fn test_privacy_zone() {
+        let zone = PrivacyZone::around("Home", 52.5200, 13.4050, 200.0).unwrap();
+        let zones = vec![zone];
+        assert_eq!(privacy_zone_at(&zones, 52.5200, 13.4050).map(|z| z.name.as_str()), Some("Home"));
+        assert!(privacy_zone_at(&zones, 48.8566, 2.3522).is_none());
+    }
+}
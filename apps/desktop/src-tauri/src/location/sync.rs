@@ -0,0 +1,254 @@
+//! Breadcrumb Upload - Batched background sync
+//!
+//! Uploads unsynced breadcrumbs in gzip-compressed batches, each payload
+//! encrypted to our own key so the backup is end-to-end. The scheduler
+//! waits out metered connections and low battery, and backs off
+//! exponentially after failures.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, Notify};
+
+use crate::crypto::IdentityManager;
+use crate::network::ApiClient;
+use crate::storage::DatabaseHandle;
+
+/// Breadcrumbs per upload request
+pub const UPLOAD_BATCH_SIZE: u32 = 200;
+
+/// Time between regular sync passes
+const SYNC_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// First retry delay, doubled per consecutive failure
+const RETRY_BASE: Duration = Duration::from_secs(30);
+const RETRY_MAX: Duration = Duration::from_secs(60 * 60);
+
+/// Below this battery level uploads wait for a charger
+const MIN_BATTERY_LEVEL: f32 = 0.20;
+
+/// Device state reported by the frontend / native layer
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct DeviceConditions {
+    pub metered: bool,
+    pub battery_level: f32,
+    pub is_charging: bool,
+}
+
+impl Default for DeviceConditions {
+    fn default() -> Self {
+        Self {
+            metered: false,
+            battery_level: 1.0,
+            is_charging: false,
+        }
+    }
+}
+
+impl DeviceConditions {
+    /// Can a background upload run now?
+    pub fn allows_upload(&self) -> bool {
+        !self.metered && (self.is_charging || self.battery_level >= MIN_BATTERY_LEVEL)
+    }
+}
+
+/// Delay before the next attempt after `failures` consecutive failures
+pub fn backoff_delay(failures: u32) -> Duration {
+    if failures == 0 {
+        return SYNC_INTERVAL;
+    }
+    RETRY_BASE
+        .saturating_mul(1 << (failures - 1).min(16))
+        .min(RETRY_MAX)
+}
+
+/// Background breadcrumb uploader
+pub struct BreadcrumbSync {
+    conditions: RwLock<DeviceConditions>,
+    paused: AtomicBool,
+    wake: Notify,
+}
+
+impl BreadcrumbSync {
+    pub fn new() -> Self {
+        Self {
+            conditions: RwLock::new(DeviceConditions::default()),
+            paused: AtomicBool::new(false),
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn conditions(&self) -> DeviceConditions {
+        *self.conditions.read().unwrap()
+    }
+
+    /// Update device state; wakes the scheduler if uploads became possible
+    pub fn set_conditions(&self, conditions: DeviceConditions) {
+        let was_allowed = self.conditions().allows_upload();
+        *self.conditions.write().unwrap() = conditions;
+        if conditions.allows_upload() && !was_allowed {
+            self.wake.notify_one();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume uploads; resuming wakes the scheduler
+    pub fn set_paused(&self, paused: bool) {
+        let was_paused = self.paused.swap(paused, Ordering::Relaxed);
+        if was_paused && !paused {
+            self.wake.notify_one();
+        }
+    }
+
+    /// Start the scheduler task
+    pub fn start(
+        self: Arc<Self>,
+        identity: Arc<Mutex<IdentityManager>>,
+        database: DatabaseHandle,
+        api: Arc<ApiClient>,
+    ) {
+        tauri::async_runtime::spawn(async move {
+            tracing::info!("Breadcrumb sync started");
+            let mut failures = 0u32;
+
+            loop {
+                if self.is_paused() {
+                    tracing::debug!("Breadcrumb upload paused");
+                } else if self.conditions().allows_upload() {
+                    match sync_breadcrumbs(&identity, &database, &api).await {
+                        Ok(0) => failures = 0,
+                        Ok(count) => {
+                            tracing::info!("☁️ Uploaded {} breadcrumbs", count);
+                            failures = 0;
+                        }
+                        Err(e) => {
+                            failures += 1;
+                            tracing::warn!("Breadcrumb upload failed (attempt {}): {}", failures, e);
+                        }
+                    }
+                } else {
+                    tracing::debug!("Breadcrumb upload deferred: {:?}", self.conditions());
+                }
+
+                let _ = tokio::time::timeout(backoff_delay(failures), self.wake.notified()).await;
+            }
+        });
+    }
+}
+
+impl Default for BreadcrumbSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Upload all unsynced breadcrumbs. Returns how many were uploaded.
+pub async fn sync_breadcrumbs(
+    identity: &Arc<Mutex<IdentityManager>>,
+    database: &DatabaseHandle,
+    api: &ApiClient,
+) -> Result<u32, String> {
+    let Some(public_key) = identity.lock().await.public_key_hex() else {
+        return Ok(0);
+    };
+
+    // Rows stored before signers were recorded upload once they verify as ours
+    let key = public_key.clone();
+    let adopted = database
+        .call(move |db| db.adopt_legacy_breadcrumbs(&key))
+        .await
+        .map_err(|e| e.to_string())?;
+    if adopted > 0 {
+        tracing::info!("Recorded signer on {} older breadcrumbs", adopted);
+    }
+
+    let mut uploaded = 0;
+    loop {
+        let key = public_key.clone();
+        let batch = database
+            .call(move |db| db.get_unsynced_breadcrumbs(&key, UPLOAD_BATCH_SIZE))
+            .await
+            .map_err(|e| e.to_string())?;
+        if batch.is_empty() {
+            break;
+        }
+
+        let (body, signature) = {
+            let identity = identity.lock().await;
+
+            // Payloads are sealed to our own key; the server only sees ciphertext
+            let items = batch
+                .iter()
+                .map(|(_, b)| {
+                    let json = serde_json::to_vec(b).map_err(|e| e.to_string())?;
+                    let sealed = identity.seal_for_self(&json).ok_or("Failed to encrypt breadcrumb")?;
+                    Ok(serde_json::json!({
+                        "payload": sealed,
+                        "signature": b.signature,
+                    }))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+
+            let body = serde_json::to_vec(&serde_json::json!({
+                "pk_root": public_key,
+                "breadcrumbs": items,
+            }))
+            .map_err(|e| e.to_string())?;
+
+            // Sign the uncompressed body so the server can verify after inflating
+            let body_hash = hex::encode(Sha256::digest(&body));
+            let signature = identity.sign_string(&body_hash).ok_or("No identity found")?;
+            (body, signature)
+        };
+
+        api.upload_breadcrumb_batch(&public_key, gzip(&body)?, &signature)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let ids: Vec<i64> = batch.iter().map(|(id, _)| *id).collect();
+        let count = ids.len() as u32;
+        database
+            .call(move |db| db.mark_breadcrumbs_synced(&ids))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        uploaded += count;
+        if count < UPLOAD_BATCH_SIZE {
+            break;
+        }
+    }
+
+    crate::metrics::METRICS.breadcrumbs_synced();
+    Ok(uploaded)
+}
+
+fn gzip(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upload_conditions_and_backoff() {
+        assert!(DeviceConditions::default().allows_upload());
+        assert!(!DeviceConditions { metered: true, ..Default::default() }.allows_upload());
+        let low = DeviceConditions { battery_level: 0.1, ..Default::default() };
+        assert!(!low.allows_upload());
+        assert!(DeviceConditions { is_charging: true, ..low }.allows_upload());
+
+        assert_eq!(backoff_delay(0), SYNC_INTERVAL);
+        assert_eq!(backoff_delay(1), RETRY_BASE);
+        assert_eq!(backoff_delay(3), RETRY_BASE * 4);
+        assert_eq!(backoff_delay(40), RETRY_MAX);
+    }
+}
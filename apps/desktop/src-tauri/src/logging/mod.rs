@@ -0,0 +1,329 @@
+//! Logging - Console, rotating files, and in-app capture
+//!
+//! Log lines go to stdout, to daily-rotated files under the app data dir
+//! (the last `MAX_LOG_FILES` days are kept), and to an in-memory ring
+//! buffer the UI reads with `get_recent_logs`. Diagnostics exports pass
+//! everything through `redact` first, so bundles users attach to bug
+//! reports carry no keys or message bodies.
+//!
+//! Live output is redacted too while `redaction_enabled()` (the default in
+//! release builds): sensitive fields print as `[redacted]` and message
+//! text goes through `redact`. `set_level` and `set_redaction` change both
+//! at runtime.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, OnceLock};
+
+use regex::Regex;
+use serde::Serialize;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::FormatFields;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Days of log files kept on disk
+pub const MAX_LOG_FILES: usize = 7;
+
+/// Log file names are `gns-browser.<date>.log`
+pub const LOG_FILE_PREFIX: &str = "gns-browser";
+
+/// Lines kept in memory for `get_recent_logs`
+const RECENT_CAPACITY: usize = 1000;
+
+/// Filter used when `RUST_LOG` isn't set
+const DEFAULT_FILTER: &str = "gns_browser=debug,tauri=info";
+
+/// Fields whose values never reach the logs while redaction is on
+const SENSITIVE_FIELDS: &[&str] = &[
+    "payload",
+    "plaintext",
+    "text",
+    "body",
+    "content",
+    "private_key",
+    "secret",
+    "seed",
+    "mnemonic",
+    "passphrase",
+    "pin",
+];
+
+static RECENT: LazyLock<Mutex<VecDeque<LogEntry>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)));
+
+/// Redact live log output; on by default in release builds
+static REDACT: AtomicBool = AtomicBool::new(!cfg!(debug_assertions));
+
+/// Swaps the level filter at runtime; set by `init`
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// One captured log line
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+/// Current filter and redaction setting
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSettings {
+    /// `EnvFilter` directives, e.g. `gns_browser=debug,tauri=info`
+    pub filter: String,
+    pub redact: bool,
+}
+
+/// Directory the rotating log files are written to
+pub fn log_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("gns-browser").join("logs"))
+}
+
+/// Install the global subscriber
+///
+/// The returned guard flushes the file writer; keep it alive for the life
+/// of the app.
+pub fn init() -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| DEFAULT_FILTER.into());
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = FILTER.set(handle);
+
+    let appender = log_dir().and_then(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .ok()
+    });
+    let (file_layer, guard) = match appender {
+        Some(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .fmt_fields(RedactingFields)
+                .with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().fmt_fields(RedactingFields))
+        .with(file_layer)
+        .with(RecentLogsLayer)
+        .init();
+
+    guard
+}
+
+/// Current filter and redaction setting
+pub fn settings() -> LogSettings {
+    LogSettings {
+        filter: FILTER
+            .get()
+            .and_then(|handle| handle.with_current(|filter| filter.to_string()).ok())
+            .unwrap_or_else(|| DEFAULT_FILTER.to_string()),
+        redact: redaction_enabled(),
+    }
+}
+
+/// Change what gets logged
+///
+/// `level` is either a bare level ("info", "debug", ...), applied to this
+/// app's own logs, or full `EnvFilter` directives.
+pub fn set_level(level: &str) -> Result<(), String> {
+    let directives = match level.parse::<Level>() {
+        Ok(level) => format!("gns_browser={},tauri=info", level.as_str().to_lowercase()),
+        Err(_) => level.to_string(),
+    };
+    let filter = EnvFilter::try_new(&directives).map_err(|e| format!("Invalid log level: {}", e))?;
+    FILTER
+        .get()
+        .ok_or("Logging is not initialized")?
+        .reload(filter)
+        .map_err(|e| e.to_string())
+}
+
+pub fn redaction_enabled() -> bool {
+    REDACT.load(Ordering::Relaxed)
+}
+
+pub fn set_redaction(enabled: bool) {
+    REDACT.store(enabled, Ordering::Relaxed);
+}
+
+/// Most recent captured lines at `min_level` or above, oldest first
+pub fn recent_logs(limit: usize, min_level: Option<Level>) -> Vec<LogEntry> {
+    let recent = RECENT.lock().unwrap();
+    let mut entries: Vec<LogEntry> = recent
+        .iter()
+        .rev()
+        .filter(|entry| match min_level {
+            Some(min) => entry.level.parse::<Level>().map(|l| l <= min).unwrap_or(true),
+            None => true,
+        })
+        .take(limit)
+        .cloned()
+        .collect();
+    entries.reverse();
+    entries
+}
+
+/// Log files on disk, oldest first
+pub fn log_files() -> Vec<PathBuf> {
+    let Some(dir) = log_dir() else {
+        return Vec::new();
+    };
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = read_dir
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Delete the log files and the in-memory buffer, returning files removed
+pub fn clear_logs() -> u32 {
+    RECENT.lock().unwrap().clear();
+    log_files()
+        .into_iter()
+        .filter(|path| std::fs::remove_file(path).is_ok())
+        .count() as u32
+}
+
+static HEX_KEY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b[0-9a-fA-F]{64,}\b").unwrap());
+
+static SENSITIVE_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)"(text|body|subject|plaintext|decryptedText|decrypted_text|mnemonic|phrase|private_key|privateKey|secret)"\s*:\s*(String\()?"(?:[^"\\]|\\.)*"\)?"#,
+    )
+    .unwrap()
+});
+
+/// Strip keys and message content from a log line
+///
+/// Long hex strings (keys, signatures) keep only their first 8 characters
+/// so lines can still be correlated; body-like JSON fields are blanked.
+pub fn redact(line: &str) -> String {
+    let line = SENSITIVE_FIELD.replace_all(line, r#""$1": "[redacted]""#);
+    HEX_KEY
+        .replace_all(&line, |caps: &regex::Captures| format!("{}…", &caps[0][..8]))
+        .into_owned()
+}
+
+/// Copies every event into the in-memory ring buffer
+struct RecentLogsLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLogsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let entry = LogEntry {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.finish(),
+        };
+
+        let mut recent = RECENT.lock().unwrap();
+        if recent.len() >= RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+}
+
+/// Formats event and span fields, redacting them while redaction is on
+struct RedactingFields;
+
+impl<'writer> FormatFields<'writer> for RedactingFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'writer>, fields: R) -> std::fmt::Result {
+        let mut visitor = MessageVisitor::default();
+        fields.record(&mut visitor);
+        writer.write_str(visitor.finish().trim_start())
+    }
+}
+
+/// Collects fields into one line: the message, then ` name=value` pairs
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if redaction_enabled() {
+            redact(&self.message)
+        } else {
+            self.message
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else if redaction_enabled() && SENSITIVE_FIELDS.contains(&field.name()) {
+            let _ = write!(self.message, " {}=[redacted]", field.name());
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let key = "ab".repeat(32);
+        assert_eq!(redact(&format!("from {}", key)), "from abababab…");
+
+        assert_eq!(
+            redact(r#"payload {"subject":"Hi","text":"meet at \"noon\"","id":"m1"}"#),
+            r#"payload {"subject": "[redacted]","text": "[redacted]","id":"m1"}"#
+        );
+        assert_eq!(
+            redact(r#"Object {"text": String("secret plans")}"#),
+            r#"Object {"text": "[redacted]"}"#
+        );
+        assert_eq!(redact("Connected to relay"), "Connected to relay");
+    }
+
+    #[test]
+    fn test_sensitive_fields_are_redacted() {
+        let subscriber = tracing_subscriber::registry().with(RecentLogsLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            set_redaction(true);
+            tracing::info!(payload = "meet at noon", id = 7, "Stored message");
+        });
+
+        let entry = recent_logs(1, None).pop().unwrap();
+        assert_eq!(entry.message, "Stored message payload=[redacted] id=7");
+    }
+}
@@ -2,14 +2,31 @@
 //!
 //! Receives envelopes from WebSocket, decrypts them, stores in DB, and emits UI events.
 
+use crate::app_lock;
+use crate::calendar::{EventInvite, EventRsvp, EVENT_PAYLOAD_TYPE, EVENT_RSVP_PAYLOAD_TYPE};
+use crate::channels::{self, ChannelPost};
+use crate::contact_requests::HeldMessage;
 use crate::crypto::IdentityManager;
+use crate::invoices::{self, Invoice, InvoicePaid, INVOICE_PAID_PAYLOAD_TYPE, INVOICE_PAYLOAD_TYPE};
+use crate::metrics::METRICS;
+use crate::network::ack::AckStatus;
+use crate::network::frame::RelayFrame;
 use crate::network::{IncomingMessage, RelayConnection};
-use crate::storage::Database;
+use crate::payloads;
+use crate::location::share::{LocationShare, SharedLocation, LOCATION_PAYLOAD_TYPE};
+use crate::polls::{self, Poll, PollVote, POLL_PAYLOAD_TYPE, POLL_VOTE_PAYLOAD_TYPE};
+use crate::proximity::{self, ProximityRequest, PROXIMITY_PAYLOAD_TYPE};
+use crate::presence;
+use crate::quotes::{self, QuoteStatus, QUOTE_DIGEST_FIELD};
+use crate::rate_limit::Admission;
+use crate::spam::{self, SenderSignals};
+use crate::stellar::StellarService;
+use crate::storage::{direct_thread_id, DatabaseError, DatabaseHandle, SyncedMessage};
+use crate::validation::{check_envelope_size, ValidationError};
 use gns_crypto_core::{open_envelope, GnsEnvelope};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
 use tokio::sync::{mpsc, Mutex};
-use sha2::Digest;
 
 /// Incoming message payload for UI
 #[derive(Debug, Clone, serde::Serialize)]
@@ -22,26 +39,109 @@ pub struct IncomingMessageEvent {
     pub payload: serde_json::Value,
     pub timestamp: i64,
     pub signature_valid: bool,
+    /// The thread is in the junk folder
+    pub is_junk: bool,
+    /// Held as a contact request until the user accepts the sender
+    pub is_request: bool,
+    /// For replies carrying a quote digest, whether it matched our copy
+    pub quote_status: Option<QuoteStatus>,
+}
+
+/// Where a new message goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    Inbox,
+    Junk,
+    Request,
+}
+
+/// Check a streamed channel post against the channel we follow and cache it
+async fn handle_channel_post<R: Runtime>(app_handle: &AppHandle<R>, database: &DatabaseHandle, post: ChannelPost) {
+    let channel_id = post.channel_id.clone();
+    let Some(channel) = database.call(move |db| db.get_channel(&channel_id)).await else {
+        tracing::debug!("Post for unknown channel {}", post.channel_id);
+        return;
+    };
+    if !channel.subscribed {
+        return;
+    }
+    if let Err(e) = channels::verify_post(&post, &channel.info) {
+        tracing::warn!("Dropping post {} in channel {}: {}", post.id, post.channel_id, e);
+        return;
+    }
+
+    let saved = post.clone();
+    match database.call(move |db| db.save_channel_post(&saved)).await {
+        Ok(true) => {
+            app_lock::emit(app_handle, "channel_post", &post);
+        }
+        Ok(false) => {}
+        Err(e) => tracing::error!("Failed to save channel post: {}", e),
+    }
+}
+
+/// Fetch what was posted to followed channels while we were disconnected
+fn catch_up_channels<R: Runtime>(app_handle: &AppHandle<R>, database: &DatabaseHandle, followed: Vec<channels::ChannelInfo>) {
+    let Some(state) = app_handle.try_state::<crate::AppState>() else {
+        return;
+    };
+    let (app_handle, database, api) = (app_handle.clone(), database.clone(), state.api.clone());
+    tauri::async_runtime::spawn(async move {
+        for channel in followed {
+            match channels::catch_up(&api, &database, &channel).await {
+                Ok(posts) => {
+                    for post in posts {
+                        app_lock::emit(&app_handle, "channel_post", &post);
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to catch up on channel {}: {}", channel.id, e),
+            }
+        }
+    });
 }
 
 /// Start the message handler task
-pub fn start_message_handler(
-    app_handle: AppHandle,
+pub fn start_message_handler<R: Runtime>(
+    app_handle: AppHandle<R>,
     identity: Arc<Mutex<IdentityManager>>,
-    database: Arc<Mutex<Database>>,
+    database: DatabaseHandle,
     relay: Arc<Mutex<RelayConnection>>,
     mut incoming_rx: mpsc::Receiver<IncomingMessage>,
 ) {
     tauri::async_runtime::spawn(async move {
         tracing::info!("Message handler started");
 
-        while let Some(msg) = incoming_rx.recv().await {
+        // Taken off the channel while collecting a sync batch
+        let mut next = None;
+        loop {
+            let msg = match next.take() {
+                Some(msg) => msg,
+                None => match incoming_rx.recv().await {
+                    Some(msg) => {
+                        METRICS.message_handled();
+                        msg
+                    }
+                    None => break,
+                },
+            };
             match msg {
                 IncomingMessage::Envelope(envelope) => {
-                    handle_envelope(&app_handle, &identity, &database, &relay, envelope).await;
+                    handle_envelope(&app_handle, &identity, &database, &relay, *envelope).await;
                 }
                 IncomingMessage::Welcome { public_key } => {
                     tracing::info!("Welcome received for {}", &public_key[..16]);
+                    if let Some(state) = app_handle.try_state::<crate::AppState>() {
+                        state.presence.mark_all_offline(chrono::Utc::now().timestamp_millis());
+                    }
+                    let relay_guard = relay.lock().await;
+                    if let Err(e) = presence::announce(&relay_guard, &database).await {
+                        tracing::warn!("Failed to announce presence: {}", e);
+                    }
+                    match channels::announce(&relay_guard, &database).await {
+                        Ok(followed) if !followed.is_empty() => catch_up_channels(&app_handle, &database, followed),
+                        Ok(_) => {}
+                        Err(e) => tracing::warn!("Failed to subscribe to channels: {}", e),
+                    }
                 }
                 IncomingMessage::ConnectionStatus { mobile, browsers } => {
                     tracing::debug!("Connection status: mobile={}, browsers={}", mobile, browsers);
@@ -58,16 +158,11 @@ pub fn start_message_handler(
                     if let Some(gns_id) = identity_guard.get_identity() {
                         let my_pk = gns_id.public_key_hex();
                         
-                        // Calculate Thread ID (deterministic)
-                        let mut keys = vec![my_pk.as_str(), conversation_with.as_str()];
-                        keys.sort();
-                        let thread_id = format!("direct_{}", &keys.join("_")[..32]);
+                        let thread_id = direct_thread_id(&my_pk, &conversation_with);
                         
                         // Fetch messages from DB
-                        let result: Result<Vec<crate::commands::messaging::Message>, _> = {
-                            let db = database.lock().await;
-                            db.get_messages(&thread_id, limit)
-                        };
+                        let result: Result<Vec<crate::commands::messaging::Message>, _> =
+                            database.call(move |db| db.get_messages(&thread_id, limit)).await;
 
                         if let Ok(messages) = result {
                             let relay_guard = relay.lock().await;
@@ -75,19 +170,19 @@ pub fn start_message_handler(
                                 let text = msg.payload.get("text").and_then(|t| t.as_str()).unwrap_or("");
                                 if text.is_empty() { continue; }
 
-                                let sync_event = serde_json::json!({
-                                    "type": "message_synced",
-                                    "to": [my_pk],
-                                    "messageId": msg.id,
-                                    "conversationWith": conversation_with,
-                                    "decryptedText": text,
-                                    "direction": if msg.is_outgoing { "outgoing" } else { "incoming" },
-                                    "timestamp": msg.timestamp,
-                                    "fromHandle": msg.from_handle
-                                });
+                                let sync_event = RelayFrame::MessageSynced {
+                                    to: vec![my_pk.clone()],
+                                    message_id: msg.id.clone(),
+                                    conversation_with: conversation_with.clone(),
+                                    decrypted_text: text.to_string(),
+                                    direction: if msg.is_outgoing { "outgoing" } else { "incoming" }.to_string(),
+                                    timestamp: Some(msg.timestamp),
+                                    from_handle: msg.from_handle.clone(),
+                                    payload: None,
+                                };
 
                                 // Send each as individual sync event
-                                if let Err(e) = relay_guard.send_raw(&sync_event.to_string()).await {
+                                if let Err(e) = relay_guard.send_frame(&sync_event).await {
                                     tracing::error!("Failed to stream sync message: {}", e);
                                     break;
                                 }
@@ -108,33 +203,31 @@ pub fn start_message_handler(
                          let relay_guard = relay.lock().await;
 
                          // Fetch messages from DB scope
-                         let messages_to_sync: Vec<crate::commands::messaging::Message> = {
-                             let db = database.lock().await;
-                             let mut msgs = Vec::new();
-                             for msg_id in &message_ids {
-                                 if let Ok(Some(msg)) = db.get_message(msg_id) {
-                                     msgs.push(msg);
-                                 }
-                             }
-                             msgs
-                         };
+                         let messages_to_sync: Vec<crate::commands::messaging::Message> = database
+                             .call(move |db| {
+                                 message_ids
+                                     .iter()
+                                     .filter_map(|msg_id| db.get_message(msg_id).ok().flatten())
+                                     .collect()
+                             })
+                             .await;
 
                          for msg in messages_to_sync {
                             let text = msg.payload.get("text").and_then(|t| t.as_str()).unwrap_or("");
                             if text.is_empty() { continue; }
 
-                            let sync_event = serde_json::json!({
-                                "type": "message_synced",
-                                "to": [requester_pk.clone()], // Send specifically to requester
-                                "messageId": msg.id,
-                                "conversationWith": conversation_with,
-                                "decryptedText": text,
-                                "direction": if msg.is_outgoing { "outgoing" } else { "incoming" },
-                                "timestamp": msg.timestamp,
-                                "fromHandle": msg.from_handle
-                            });
-
-                            if let Err(e) = relay_guard.send_raw(&sync_event.to_string()).await {
+                            let sync_event = RelayFrame::MessageSynced {
+                                to: vec![requester_pk.clone()], // Send specifically to requester
+                                message_id: msg.id.clone(),
+                                conversation_with: conversation_with.clone(),
+                                decrypted_text: text.to_string(),
+                                direction: if msg.is_outgoing { "outgoing" } else { "incoming" }.to_string(),
+                                timestamp: Some(msg.timestamp),
+                                from_handle: msg.from_handle.clone(),
+                                payload: None,
+                            };
+
+                            if let Err(e) = relay_guard.send_frame(&sync_event).await {
                                 tracing::error!("Failed to sync message {}: {}", msg.id, e);
                             } else {
                                 tracing::debug!("Synced message {} to requester", msg.id);
@@ -148,12 +241,15 @@ pub fn start_message_handler(
                     let identity_guard = identity.lock().await;
                     if let Some(gns_id) = identity_guard.get_identity() {
                          let my_pk = gns_id.public_key_hex();
-                         let mut db = database.lock().await;
-                         if let Err(e) = db.save_browser_sent_message(&message_id, &to_pk, &plaintext, timestamp, &my_pk) {
+                         let (id, to, text) = (message_id.clone(), to_pk.clone(), plaintext.clone());
+                         let saved = database
+                             .call(move |db| db.save_browser_sent_message(&id, &to, &text, timestamp, &my_pk))
+                             .await;
+                         if let Err(e) = saved {
                              tracing::error!("Failed to save browser message: {}", e);
                          } else {
                             // Emit to UI
-                            let _ = app_handle.emit("message_synced", serde_json::json!({
+                            app_lock::emit(&app_handle, "message_synced", serde_json::json!({
                                 "id": message_id,
                                 "to_pk": to_pk,
                                 "text": plaintext,
@@ -163,80 +259,41 @@ pub fn start_message_handler(
                          }
                     }
                 }
+                IncomingMessage::Ack { message_id, status } => {
+                    apply_ack(&app_handle, &database, &message_id, &status).await;
+                }
                 IncomingMessage::ReadReceipt { message_id, timestamp: _ } => {
-                    let mut db = database.lock().await;
-                    if let Err(e) = db.mark_message_read(&message_id) {
+                    let id = message_id.clone();
+                    if let Err(e) = database.call(move |db| db.mark_message_read(&id)).await {
                         tracing::error!("Failed to mark message read: {}", e);
                     } else {
                         let _ = app_handle.emit("message_read", serde_json::json!({ "id": message_id }));
                     }
                 }
                 IncomingMessage::MessageSynced { message_id, conversation_with, decrypted_text, direction, timestamp, from_handle } => {
-                    tracing::info!("Syncing mobile message: {}", &message_id);
-
-                    let identity_guard = identity.lock().await;
-                     if let Some(_) = identity_guard.get_identity() { // Just check we have identity
-                        let mut db = database.lock().await;
-
-                        // TODO: Refactor `save_browser_sent_message` or create `save_synced_message`?
-                        // `save_received_message` expects an envelope. We don't have one.
-                        // We have pure content.
-                        // We should reuse `save_browser_sent_message` but it assumes outgoing.
-                        // If direction is incoming, we need a way to store "Decrypted Incoming" without envelope source.
-                        // Actually, `save_received_message` requires payload type.
-
-                        // Let's create a new DB method or reuse logic? 
-                        // For now, let's treat it as a "Browser Sent" message if outgoing, 
-                        // and if incoming... well, the DB schema might expect an envelope ID.
-                        // `message_synced` gives us the original ID.
-
-                        // Wait, if it's INCOMING, it implies I am the recipient.
-                        // `save_received_message` does: INSERT INTO messages ...
-                        // If I call `save_received_message`:
-                        // - thread_id: need to generate or use provided?
-                        // - from_pk: We need to know WHO sent it. `conversation_with` is the OTHER person.
-                        // If incoming, `conversation_with` == Sender.
-                        // If outgoing, `conversation_with` == Recipient.
-                        
-                        let (from_pk, is_outgoing) = if direction == "outgoing" {
-                             (String::new(), true) // Placeholder (unused in outgoing path)
-                        } else {
-                             (conversation_with.clone(), false)
-                        };
-
-                        let my_pk = identity_guard.get_identity().map(|i| i.public_key_hex()).unwrap_or_default();
-
-                        if is_outgoing {
-                             if let Err(e) = db.save_browser_sent_message(&message_id, &conversation_with, &decrypted_text, timestamp, &my_pk) {
-                                 tracing::error!("Failed to save synced outgoing message: {}", e);
-                             }
-                        } else {
-                            // Incoming!
-                            // Persist to DB using new method
-                             if let Err(e) = db.save_synced_incoming_message(&message_id, &from_pk, &decrypted_text, timestamp, from_handle.as_deref(), &my_pk) {
-                                 tracing::error!("Failed to save synced incoming message: {}", e);
-                             }
-                        }
-                        
-                        // Emit to UI
-                        // Emit 'message_synced' for specific sync listeners
-                        let _ = app_handle.emit("message_synced", serde_json::json!({
-                            "id": message_id,
-                            "conversationWith": conversation_with,
-                            "text": decrypted_text,
-                            "direction": direction,
-                            "timestamp": timestamp,
-                            "fromHandle": from_handle
-                        }));
-
-                        // Emit 'new_message' to trigger generic UI updates (like EmailList refresh)
-                        // Payload doesn't need to match generic event perfectly if UI just refetches
-                        let _ = app_handle.emit("new_message", serde_json::json!({
-                            "id": message_id,
-                            "payload_type": "email", // Assume email for now
-                            "timestamp": timestamp
-                        }));
-                     }
+                    let first = SyncedMessage {
+                        id: message_id,
+                        conversation_with,
+                        text: decrypted_text,
+                        timestamp,
+                        from_handle,
+                        is_outgoing: direction == "outgoing",
+                    };
+                    let (batch, after) = collect_sync_batch(first, &mut incoming_rx);
+                    next = after;
+                    handle_synced_messages(&app_handle, &identity, &database, batch).await;
+                }
+                IncomingMessage::Presence { public_key, online, last_seen } => {
+                    let Some(state) = app_handle.try_state::<crate::AppState>() else {
+                        continue;
+                    };
+                    let now = chrono::Utc::now().timestamp_millis();
+                    if let Some(presence) = state.presence.update(&public_key, online, last_seen, now) {
+                        let _ = app_handle.emit("presence_changed", &presence);
+                    }
+                }
+                IncomingMessage::ChannelPost(post) => {
+                    handle_channel_post(&app_handle, &database, *post).await;
                 }
                 IncomingMessage::Unknown(text) => {
                     tracing::trace!("Unknown message type: {}", &text[..text.len().min(100)]);
@@ -248,25 +305,102 @@ pub fn start_message_handler(
     });
 }
 
+/// Synced messages saved per transaction
+const SYNC_BATCH_SIZE: usize = 200;
+
+/// Gather the synced messages already queued behind `first`, up to
+/// `SYNC_BATCH_SIZE`. Also returns the message that ended the run, if any.
+fn collect_sync_batch(
+    first: SyncedMessage,
+    incoming_rx: &mut mpsc::Receiver<IncomingMessage>,
+) -> (Vec<SyncedMessage>, Option<IncomingMessage>) {
+    let mut batch = vec![first];
+    while batch.len() < SYNC_BATCH_SIZE {
+        let Ok(msg) = incoming_rx.try_recv() else {
+            break;
+        };
+        METRICS.message_handled();
+        match msg {
+            IncomingMessage::MessageSynced { message_id, conversation_with, decrypted_text, direction, timestamp, from_handle } => {
+                batch.push(SyncedMessage {
+                    id: message_id,
+                    conversation_with,
+                    text: decrypted_text,
+                    timestamp,
+                    from_handle,
+                    is_outgoing: direction == "outgoing",
+                });
+            }
+            other => return (batch, Some(other)),
+        }
+    }
+    (batch, None)
+}
+
+/// Store messages mirrored from our other devices and tell the UI
+async fn handle_synced_messages<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    identity: &Arc<Mutex<IdentityManager>>,
+    database: &DatabaseHandle,
+    batch: Vec<SyncedMessage>,
+) {
+    tracing::info!("Syncing {} messages from another device", batch.len());
+
+    let Some(my_pk) = identity.lock().await.get_identity().map(|i| i.public_key_hex()) else {
+        return;
+    };
+
+    let saved = batch.clone();
+    if let Err(e) = database.call(move |db| db.save_synced_messages(&saved, &my_pk)).await {
+        tracing::error!("Failed to save synced messages: {}", e);
+        METRICS.storage_error();
+    }
+
+    for message in batch {
+        // Emit 'message_synced' for specific sync listeners
+        app_lock::emit(app_handle, "message_synced", serde_json::json!({
+            "id": message.id,
+            "conversationWith": message.conversation_with,
+            "text": message.text,
+            "direction": if message.is_outgoing { "outgoing" } else { "incoming" },
+            "timestamp": message.timestamp,
+            "fromHandle": message.from_handle
+        }));
+
+        // Emit 'new_message' to trigger generic UI updates (like EmailList refresh)
+        // Payload doesn't need to match generic event perfectly if UI just refetches
+        app_lock::emit(app_handle, "new_message", serde_json::json!({
+            "id": message.id,
+            "payload_type": "email", // Assume email for now
+            "timestamp": message.timestamp
+        }));
+    }
+}
+
 /// Handle an incoming envelope
-async fn handle_envelope(
-    app_handle: &AppHandle,
+///
+/// Returns the stored message, or `None` if it couldn't be opened.
+pub(crate) async fn handle_envelope<R: Runtime>(
+    app_handle: &AppHandle<R>,
     identity: &Arc<Mutex<IdentityManager>>,
-    database: &Arc<Mutex<Database>>,
+    database: &DatabaseHandle,
     relay: &Arc<Mutex<RelayConnection>>,
     envelope: GnsEnvelope,
-) {
-    println!("🔥 [RUST] handle_envelope called: {}", envelope.id);
-    println!("🔥 [RUST] Envelope Sender: {}", envelope.from_public_key);
+) -> Option<IncomingMessageEvent> {
     tracing::info!("Processing envelope {} from {}", envelope.id, &envelope.from_public_key[..16]);
 
+    if let Err(e) = check_envelope_size(&envelope) {
+        reject_envelope(app_handle, &envelope.id, &envelope.from_public_key, e);
+        return None;
+    }
+
     // Get our identity for decryption
     let identity_guard = identity.lock().await;
     let gns_identity = match identity_guard.get_identity() {
         Some(id) => id,
         None => {
             tracing::error!("No identity available for decryption");
-            return;
+            return None;
         }
     };
 
@@ -275,87 +409,213 @@ async fn handle_envelope(
         Ok(o) => o,
         Err(e) => {
             tracing::error!("Failed to open envelope: {}", e);
-            return;
+            METRICS.decryption_failed();
+            return None;
         }
     };
+    METRICS.envelope_processed();
 
     if !opened.signature_valid {
         tracing::warn!("Envelope {} has invalid signature!", envelope.id);
         // Still process it but mark as unverified
     }
 
-    // Parse the payload
-    let payload: serde_json::Value = match serde_json::from_slice(&opened.payload) {
-        Ok(p) => p,
+    // Our own devices are never limited, but anyone can claim to be one
+    let own_device = opened.signature_valid && opened.from_public_key == gns_identity.public_key_hex();
+    if !own_device && !admit_sender(app_handle, &opened.from_public_key) {
+        return None;
+    }
+
+    let payloads = payloads::registry();
+    let payload = match payloads.decode(&opened.payload_type, &opened.payload) {
+        Ok(payload) => payload,
         Err(e) => {
-            // If not JSON, treat as plain text
-            tracing::debug!("Payload is not JSON, treating as text: {}", e);
-            serde_json::json!({
-                "text": String::from_utf8_lossy(&opened.payload).to_string()
-            })
+            reject_envelope(app_handle, &envelope.id, &opened.from_public_key, e);
+            return None;
         }
     };
 
+    // Remote wipes are acted on, never stored
+    if opened.payload_type == crate::wipe::REMOTE_WIPE_PAYLOAD_TYPE {
+        let my_pk = gns_identity.public_key_hex();
+        // Wiping clears the identity, which needs this lock
+        drop(identity_guard);
+        tracing::warn!("Received remote wipe envelope {}", envelope.id);
+        crate::wipe::handle_remote_wipe(app_handle, &my_pk, &opened).await;
+        return None;
+    }
+
+    // Votes go into the creator's tally, not the thread
+    if opened.payload_type == POLL_VOTE_PAYLOAD_TYPE {
+        let my_pk = gns_identity.public_key_hex();
+        drop(identity_guard);
+        tally_poll_vote(app_handle, database, &my_pk, &opened.from_public_key, payload).await;
+        return None;
+    }
+
+    // Live location updates move the share instead of adding messages
+    if opened.payload_type == LOCATION_PAYLOAD_TYPE
+        && move_location_share(app_handle, database, &opened.from_public_key, opened.signature_valid, &payload).await
+    {
+        return None;
+    }
+
+    // Proximity claims complete a proof rather than add messages
+    if opened.payload_type == PROXIMITY_PAYLOAD_TYPE {
+        let my_pk = gns_identity.public_key_hex();
+        drop(identity_guard);
+        handle_proximity_claim(app_handle, database, &my_pk, &opened.from_public_key, opened.from_handle.as_deref(), payload).await;
+        return None;
+    }
+
     tracing::info!(
-        "Decrypted message from {}: {:?}",
-        opened.from_handle.as_deref().unwrap_or(&opened.from_public_key[..16]),
-        &payload
+        "Decrypted envelope {} ({}) from {}",
+        envelope.id,
+        opened.payload_type,
+        opened.from_handle.as_deref().unwrap_or(&opened.from_public_key[..16])
     );
 
-    // Generate thread ID if not present
-    // Generate thread ID logic
-    // Generate thread ID
-    // CRITICAL: For emails, we MUST use Subject Hashing to group inbound/outbound correctly.
-    // We intentionally ignore `opened.thread_id` from the server because the server groups by participants,
-    // whereas we want to group by Subject (like Gmail).
-    let thread_id = if opened.payload_type == "email" || opened.payload_type == "gns/email" {
-        // Email -> Group by Subject Hash
-        let subject = payload.get("subject").and_then(|s| s.as_str()).unwrap_or("");
-        
-        let s = normalize_subject(subject);
-        println!("🔥 [RUST] Subject Hashing. Original: '{}', Normalized: '{}'", subject, s);
-        if s.is_empty() {
-             opened.thread_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
-        } else {
-             let mut hasher = sha2::Sha256::new();
-             hasher.update(s.as_bytes());
-             let result = hasher.finalize();
-             let hash = hex::encode(result);
-             println!("🔥 [RUST] Generated Thread Hash: {}", hash);
-             hash
+    let my_pk = gns_identity.public_key_hex();
+
+    // The payload type's handler may pick the thread (emails group by
+    // subject); otherwise the envelope's, or the direct thread
+    let mut thread_id = payloads
+        .thread_id(&opened.payload_type, &payload, opened.thread_id.as_deref())
+        .or_else(|| opened.thread_id.clone())
+        .unwrap_or_else(|| direct_thread_id(&my_pk, &opened.from_public_key));
+
+    // The sender picks the thread id, and can't post into a thread with
+    // someone else; direct ids are easy to compute
+    if !own_device && is_foreign_thread(database, &thread_id, &opened.from_public_key).await {
+        tracing::warn!("Envelope {} names a thread {} belongs to someone else", envelope.id, thread_id);
+        thread_id = direct_thread_id(&my_pk, &opened.from_public_key);
+        if is_foreign_thread(database, &thread_id, &opened.from_public_key).await {
+            tracing::warn!("Dropping envelope {}: direct thread {} belongs to someone else", envelope.id, thread_id);
+            return None;
         }
-    } else if let Some(tid) = opened.thread_id.clone() {
-        // Explicit thread ID provided (Chat/Direct)
-        tid
+    }
+
+    tracing::debug!("Envelope {}: type={} thread={}", envelope.id, opened.payload_type, thread_id);
+
+    // Types that skip screening only do so from the email gateway, and our
+    // own devices are trusted; both need a valid signature
+    let trusted_gateway = !payloads.screen_sender(&opened.payload_type)
+        && opened.signature_valid
+        && is_email_gateway(app_handle, database, &opened.from_public_key).await;
+    let placement = if own_device || trusted_gateway {
+        Placement::Inbox
     } else {
-        // Direct message / Chat -> Deterministic based on participants
-        let my_pk = gns_identity.public_key_hex();
-        let other_pk = &opened.from_public_key;
-        let mut keys = vec![my_pk.as_str(), other_pk.as_str()];
-        keys.sort();
-        format!("direct_{}", &keys.join("_")[..32])
+        let text = payload.get("text").and_then(|t| t.as_str()).unwrap_or("");
+        classify_sender(app_handle, database, &thread_id, &opened, text).await
     };
+    let is_junk = placement == Placement::Junk;
+
+    if placement == Placement::Request {
+        let held = HeldMessage {
+            id: envelope.id.clone(),
+            thread_id: thread_id.clone(),
+            from_public_key: opened.from_public_key.clone(),
+            from_handle: opened.from_handle.clone(),
+            payload_type: opened.payload_type.clone(),
+            payload: payload.clone(),
+            timestamp: opened.timestamp,
+            signature_valid: opened.signature_valid,
+        };
+        if let Err(e) = database.call(move |db| db.hold_message(&held)).await {
+            tracing::error!("Failed to hold message {}: {}", envelope.id, e);
+            METRICS.storage_error();
+        }
 
-    println!("🔥 [RUST] Decrypted Message: Type={}", opened.payload_type);
-    println!("🔥 [RUST] Thread ID: {}", thread_id);
-    println!("🔥 [RUST] Sender Handle: {:?}", opened.from_handle);
+        let event = IncomingMessageEvent {
+            id: envelope.id.clone(),
+            thread_id: Some(thread_id),
+            from_public_key: opened.from_public_key,
+            from_handle: opened.from_handle,
+            payload_type: opened.payload_type,
+            payload,
+            timestamp: opened.timestamp,
+            signature_valid: opened.signature_valid,
+            is_junk: false,
+            is_request: true,
+            quote_status: None,
+        };
+        // Not synced to browsers until accepted
+        app_lock::emit(app_handle, "contact_request", &event);
+        tracing::info!("Message {} held as a contact request", envelope.id);
+        return Some(event);
+    }
+
+    // A reply's quote must match our own copy of the original
+    let claimed_digest = payload.get(QUOTE_DIGEST_FIELD).and_then(|d| d.as_str()).map(|d| d.to_string());
+    let quote_status = match (opened.reply_to_id.clone(), claimed_digest) {
+        (Some(reply_to), Some(claimed)) => {
+            let expected = database
+                .call(move |db| db.get_message(&reply_to).ok().flatten().map(|m| quotes::digest_of(&m)))
+                .await;
+            quotes::check_quote(Some(&claimed), expected.as_deref())
+        }
+        _ => None,
+    };
+    if quote_status == Some(QuoteStatus::Mismatch) {
+        tracing::warn!("Message {} quotes a message that differs from our copy", envelope.id);
+    }
 
     // Store in database
-    {
-        let mut db = database.lock().await;
-        if let Err(e) = db.save_received_message(
-            &envelope.id,
-            &thread_id,
-            &opened.from_public_key,
-            opened.from_handle.as_deref(),
-            &opened.payload_type,
-            &payload,
-            opened.timestamp,
-            opened.signature_valid,
-            None,
-        ) {
-            tracing::error!("Failed to save message to database: {}", e);
+    let saved = {
+        let (id, thread_id, from_pk, from_handle, payload_type, payload, reply_to_id) = (
+            envelope.id.clone(),
+            thread_id.clone(),
+            opened.from_public_key.clone(),
+            opened.from_handle.clone(),
+            opened.payload_type.clone(),
+            payload.clone(),
+            opened.reply_to_id.clone(),
+        );
+        let (timestamp, signature_valid) = (opened.timestamp, opened.signature_valid);
+        database
+            .call(move |db| {
+                db.save_received_message(
+                    &id,
+                    &thread_id,
+                    &from_pk,
+                    from_handle.as_deref(),
+                    &payload_type,
+                    &payload,
+                    timestamp,
+                    signature_valid,
+                    reply_to_id,
+                )?;
+                if let Some(status) = quote_status {
+                    db.set_quote_status(&id, status)?;
+                }
+                if is_junk {
+                    db.set_thread_junk(&thread_id, true)?;
+                }
+                Ok::<_, DatabaseError>(())
+            })
+            .await
+    };
+    if let Err(e) = saved {
+        tracing::error!("Failed to save message to database: {}", e);
+        METRICS.storage_error();
+    }
+
+    if opened.payload_type == POLL_PAYLOAD_TYPE {
+        remember_poll(database, &opened.from_public_key, &thread_id, &payload).await;
+    } else if opened.payload_type == LOCATION_PAYLOAD_TYPE {
+        let shared = serde_json::from_value::<LocationShare>(payload.clone())
+            .map(|share| SharedLocation::new(share, Some(thread_id.clone()), opened.from_public_key.clone(), false));
+        if let Ok(shared) = shared {
+            if let Err(e) = database.call(move |db| db.save_location_share(&shared)).await {
+                tracing::error!("Failed to save location share: {}", e);
+            }
         }
+    } else if opened.payload_type == EVENT_PAYLOAD_TYPE || opened.payload_type == EVENT_RSVP_PAYLOAD_TYPE {
+        track_event(app_handle, database, &my_pk, &opened, &thread_id, &payload).await;
+    } else if opened.payload_type == INVOICE_PAYLOAD_TYPE {
+        remember_invoice(database, &opened, &thread_id, &payload).await;
+    } else if opened.payload_type == INVOICE_PAID_PAYLOAD_TYPE {
+        settle_invoice(app_handle, database, &gns_identity.public_key_hex(), &payload);
     }
 
     // Create event for UI
@@ -368,71 +628,540 @@ async fn handle_envelope(
         payload,
         timestamp: opened.timestamp,
         signature_valid: opened.signature_valid,
+        is_junk,
+        is_request: false,
+        quote_status,
     };
 
     // Emit to UI
-    if let Err(e) = app_handle.emit("new_message", &event) {
-        tracing::error!("Failed to emit new_message event: {}", e);
-    }
+    app_lock::emit(app_handle, "new_message", &event);
 
     tracing::info!("Message {} processed and emitted to UI", envelope.id);
 
+    check_contact_key(app_handle, database, &event);
+
+    if crate::rules::triggers_rules(&event, &gns_identity.public_key_hex()) {
+        crate::rules::apply(app_handle, database, &event);
+    }
+
     // Sync to Browser (Phase 1.5)
     // Forward decrypted content to any connected browsers
     {
         let relay_guard = relay.lock().await;
         // Construct sync event
-        let sync_event = serde_json::json!({
-            "type": "message_synced",
-            "to": [event.from_public_key], // Route to myself (User's devices) - Wait, from_public_key is Sender. 
-            // If Incoming: Sender is Echo. I want to route to ME.
-            // I need MY PK.
-            // event.from_public_key is ECHO.
-            // I need to inject MY PK.
-            // But I don't have MY PK in this scope easily?
-            // `gns_identity` is available in handle_envelope!
-            // Line 153: let gns_identity = ...
-            // So I can use gns_identity.public_key_hex().
-            "to": [gns_identity.public_key_hex()],
-            "messageId": envelope.id,
-            "conversationWith": event.from_public_key,
-            "decryptedText": event.payload.get("text").and_then(|t| t.as_str()).unwrap_or(""),
-            "direction": "incoming",
-            "timestamp": event.timestamp,
-            "fromHandle": event.from_handle
-        });
-
-        // Send raw JSON
-        if let Err(e) = relay_guard.send_raw(&sync_event.to_string()).await {
+        let sync_event = RelayFrame::MessageSynced {
+            // Route to our own devices
+            to: vec![gns_identity.public_key_hex()],
+            message_id: envelope.id.clone(),
+            conversation_with: event.from_public_key.clone(),
+            decrypted_text: event.payload.get("text").and_then(|t| t.as_str()).unwrap_or("").to_string(),
+            direction: "incoming".to_string(),
+            timestamp: Some(event.timestamp),
+            from_handle: event.from_handle.clone(),
+            payload: None,
+        };
+
+        if let Err(e) = relay_guard.send_frame(&sync_event).await {
              tracing::debug!("Failed to sync message to browser (likely no browsers connected): {}", e);
         } else {
              tracing::info!("Synced message {} to browser(s)", envelope.id);
         }
     }
+
+    Some(event)
+}
+
+/// Keep a poll sent in a thread so it can be voted on
+async fn remember_poll(database: &DatabaseHandle, from_public_key: &str, thread_id: &str, payload: &serde_json::Value) {
+    let Ok(poll) = serde_json::from_value::<Poll>(payload.clone()) else {
+        return;
+    };
+    if !poll.creator.eq_ignore_ascii_case(from_public_key) {
+        tracing::warn!("Poll {} was sent by someone other than its creator", poll.id);
+        return;
+    }
+    if let Err(e) = polls::verify_poll(&poll) {
+        tracing::warn!("Ignoring poll {}: {}", poll.id, e);
+        return;
+    }
+    let thread_id = thread_id.to_string();
+    if let Err(e) = database.call(move |db| db.save_poll(&poll, Some(&thread_id), None)).await {
+        tracing::error!("Failed to save poll: {}", e);
+    }
 }
 
-/// Normalize subject for threading (remove Re:, Fwd:, etc)
-pub fn normalize_subject(subject: &str) -> String {
-    let mut s = subject.trim().to_lowercase();
-    
-    // Loop until no more prefixes found
-    loop {
-        let original_len = s.len();
-        
-        // Remove prefixes
-        if s.starts_with("re:") {
-            s = s[3..].trim_start().to_string();
-        } else if s.starts_with("fwd:") {
-            s = s[4..].trim_start().to_string();
-        } else if s.starts_with("fw:") {
-            s = s[3..].trim_start().to_string();
+/// Apply an update to a location share we already hold from this sender
+/// and tell the UI; false when the share is new to us
+async fn move_location_share<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    database: &DatabaseHandle,
+    from_public_key: &str,
+    signature_valid: bool,
+    payload: &serde_json::Value,
+) -> bool {
+    let Ok(share) = serde_json::from_value::<LocationShare>(payload.clone()) else {
+        return false;
+    };
+    let from = from_public_key.to_string();
+    let moved = database
+        .call(move |db| {
+            let Some(known) = db.get_location_share(&share.share_id) else {
+                return Ok(None);
+            };
+            if known.outgoing || !known.peer_public_key.eq_ignore_ascii_case(&from) || !signature_valid {
+                // Someone else's share id, or a sender we can't verify; drop
+                // the update rather than store it
+                return Ok(Some(None));
+            }
+            let shared = SharedLocation::new(share, known.thread_id, from, false);
+            Ok::<_, DatabaseError>(Some(db.save_location_share(&shared)?.then_some(shared)))
+        })
+        .await;
+    match moved {
+        Ok(None) => false,
+        Ok(Some(shared)) => {
+            if let Some(shared) = shared {
+                app_lock::emit(app_handle, "location_updated", &shared);
+            }
+            true
         }
-        
-        // If length didn't change, we are done
-        if s.len() == original_len {
-            break;
+        Err(e) => {
+            tracing::error!("Failed to update location share: {}", e);
+            true
         }
     }
-    
-    s
+}
+
+/// Keep a peer's proximity claim and finish the proof if we've sent ours
+/// for the same window; otherwise ask the UI to prompt for our side
+async fn handle_proximity_claim<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    database: &DatabaseHandle,
+    my_pk: &str,
+    from_public_key: &str,
+    from_handle: Option<&str>,
+    payload: serde_json::Value,
+) {
+    let Ok(theirs) = serde_json::from_value::<ProximityRequest>(payload) else {
+        return;
+    };
+    if let Err(e) = proximity::check_claim(&theirs.claim, from_public_key, my_pk) {
+        tracing::warn!("Ignoring proximity claim from {}: {}", &from_public_key[..16], e);
+        return;
+    }
+
+    let (from, saved) = (from_public_key.to_string(), theirs.clone());
+    let ours = database
+        .call(move |db| {
+            db.save_proximity_claim(&from, false, &saved)?;
+            Ok::<_, DatabaseError>(db.get_proximity_claim(&from, true))
+        })
+        .await;
+    let ours = match ours {
+        Ok(ours) => ours.filter(|ours| {
+            (ours.claim.breadcrumb.timestamp - theirs.claim.breadcrumb.timestamp).abs()
+                <= gns_crypto_core::proximity::PROXIMITY_WINDOW_SECONDS
+        }),
+        Err(e) => {
+            tracing::error!("Failed to save proximity claim: {}", e);
+            return;
+        }
+    };
+    let Some(ours) = ours else {
+        app_lock::emit(app_handle, "proximity_requested", serde_json::json!({
+            "from_public_key": from_public_key,
+            "from_handle": from_handle,
+            "max_steps": theirs.max_steps,
+        }));
+        return;
+    };
+
+    match proximity::combine(&ours, &theirs, from_public_key) {
+        Ok(stored) => {
+            let saved = stored.clone();
+            if let Err(e) = database.call(move |db| db.save_proximity_attestation(&saved)).await {
+                tracing::error!("Failed to save proximity attestation: {}", e);
+            }
+            let _ = app_handle.emit("proximity_proven", &stored);
+        }
+        Err(reason) => {
+            let _ = app_handle.emit("proximity_failed", serde_json::json!({
+                "peer_public_key": from_public_key,
+                "reason": reason,
+            }));
+        }
+    }
+}
+
+/// Put a received invite in the events table, or record an answer to
+/// one of ours
+async fn track_event<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    database: &DatabaseHandle,
+    my_pk: &str,
+    opened: &gns_crypto_core::envelope::OpenedEnvelope,
+    thread_id: &str,
+    payload: &serde_json::Value,
+) {
+    let from_public_key = opened.from_public_key.as_str();
+    if opened.payload_type == EVENT_PAYLOAD_TYPE {
+        let Ok(invite) = serde_json::from_value::<EventInvite>(payload.clone()) else {
+            return;
+        };
+        if !invite.organizer.eq_ignore_ascii_case(from_public_key) {
+            tracing::warn!("Invite {} was sent by someone other than its organizer", invite.id);
+            return;
+        }
+        let thread_id = thread_id.to_string();
+        if let Err(e) = database.call(move |db| db.save_event(&invite, Some(&thread_id), None)).await {
+            tracing::error!("Failed to save invite: {}", e);
+        }
+        return;
+    }
+
+    let Ok(rsvp) = serde_json::from_value::<EventRsvp>(payload.clone()) else {
+        return;
+    };
+    // An answer is recorded under the sender's key, so it has to be theirs
+    if !opened.signature_valid {
+        tracing::warn!("Ignoring RSVP to {}: signature doesn't verify", rsvp.event_id);
+        return;
+    }
+    let (my_pk, from, timestamp) = (my_pk.to_string(), from_public_key.to_string(), opened.timestamp);
+    let recorded = database
+        .call(move |db| {
+            let Some(event) = db.get_event(&rsvp.event_id) else {
+                return Ok(None);
+            };
+            if !event.invite.organizer.eq_ignore_ascii_case(&my_pk) {
+                return Ok(None);
+            }
+            db.record_event_response(&rsvp.event_id, &from, rsvp.status, timestamp)?;
+            Ok::<_, DatabaseError>(db.get_event(&rsvp.event_id))
+        })
+        .await;
+    match recorded {
+        Ok(Some(event)) => {
+            app_lock::emit(app_handle, "event_rsvp", &event);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to record RSVP: {}", e),
+    }
+}
+
+/// Keep an invoice sent to us so it can be paid
+async fn remember_invoice(
+    database: &DatabaseHandle,
+    opened: &gns_crypto_core::envelope::OpenedEnvelope,
+    thread_id: &str,
+    payload: &serde_json::Value,
+) {
+    let Ok(invoice) = serde_json::from_value::<Invoice>(payload.clone()) else {
+        return;
+    };
+    // Paying it sends money to whoever the invoice names, so the issuer
+    // has to be the one who signed it
+    if !opened.signature_valid {
+        tracing::warn!("Ignoring invoice {}: signature doesn't verify", invoice.id);
+        return;
+    }
+    if !invoice.issuer.eq_ignore_ascii_case(&opened.from_public_key) {
+        tracing::warn!("Invoice {} was sent by someone other than its issuer", invoice.id);
+        return;
+    }
+    let thread_id = thread_id.to_string();
+    if let Err(e) = database.call(move |db| db.save_invoice(&invoice, Some(&thread_id), false)).await {
+        tracing::error!("Failed to save invoice: {}", e);
+    }
+}
+
+/// Check a paid notice for one of our invoices against its transaction
+/// on Horizon, and if it holds, mark the invoice paid and tell the UI.
+/// Runs in the background so Horizon doesn't hold up the message loop.
+fn settle_invoice<R: Runtime>(app_handle: &AppHandle<R>, database: &DatabaseHandle, my_pk: &str, payload: &serde_json::Value) {
+    let Ok(notice) = serde_json::from_value::<InvoicePaid>(payload.clone()) else {
+        return;
+    };
+    let Ok(pay_to) = StellarService::gns_key_to_stellar(my_pk) else {
+        return;
+    };
+    let (app_handle, database) = (app_handle.clone(), database.clone());
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app_handle.try_state::<crate::AppState>() else {
+            return;
+        };
+        let id = notice.invoice_id.clone();
+        let Some(record) = database.call(move |db| db.get_invoice(&id)).await else {
+            return;
+        };
+        if !record.outgoing || record.tx_hash.is_some() {
+            return;
+        }
+
+        // Not the shared service, so a slow Horizon doesn't block payments
+        let service = StellarService::new(state.stellar.lock().await.config().clone());
+        let settled = service
+            .get_transaction_payments(&notice.tx_hash, &pay_to)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|payments| invoices::check_settles(&record.invoice, &pay_to, &payments));
+        if let Err(e) = settled {
+            tracing::warn!("Transaction {} doesn't settle invoice {}: {}", notice.tx_hash, notice.invoice_id, e);
+            return;
+        }
+
+        let paid = database
+            .call(move |db| {
+                let paid_at = chrono::Utc::now().timestamp_millis();
+                if !db.mark_invoice_paid(&notice.invoice_id, &notice.tx_hash, paid_at)? {
+                    return Ok(None);
+                }
+                Ok::<_, DatabaseError>(db.get_invoice(&notice.invoice_id))
+            })
+            .await;
+        match paid {
+            Ok(Some(invoice)) => {
+                let _ = app_handle.emit("invoice_paid", &invoice);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::error!("Failed to mark invoice paid: {}", e),
+        }
+    });
+}
+
+/// Count a vote on one of our polls and tell the UI the new tally
+async fn tally_poll_vote<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    database: &DatabaseHandle,
+    my_pk: &str,
+    from_public_key: &str,
+    payload: serde_json::Value,
+) {
+    let Ok(vote) = serde_json::from_value::<PollVote>(payload) else {
+        return;
+    };
+    // A vote counts for whoever sent it, and only in our own polls
+    if vote.voter != from_public_key.to_lowercase() {
+        tracing::warn!("Vote on poll {} was sent on behalf of someone else", vote.poll_id);
+        return;
+    }
+    let poll_id = vote.poll_id.clone();
+    let Some(poll) = database.call(move |db| db.get_poll(&poll_id)).await else {
+        tracing::debug!("Vote for unknown poll {}", vote.poll_id);
+        return;
+    };
+    if !poll.creator.eq_ignore_ascii_case(my_pk) {
+        return;
+    }
+    if let Err(e) = polls::verify_vote(&vote, &poll) {
+        tracing::warn!("Ignoring vote on poll {}: {}", poll.id, e);
+        return;
+    }
+
+    let (my_pk, now) = (my_pk.to_string(), chrono::Utc::now().timestamp_millis());
+    let tallied = database
+        .call(move |db| {
+            if !db.record_poll_vote(&vote)? {
+                return Ok(None);
+            }
+            let votes = db.get_poll_votes(&poll.id)?;
+            Ok::<_, DatabaseError>(Some(polls::tally(&poll, &votes, &my_pk, now)))
+        })
+        .await;
+    match tallied {
+        Ok(Some(results)) => {
+            app_lock::emit(app_handle, "poll_updated", &results);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to record poll vote: {}", e),
+    }
+}
+
+/// Record a relay ack on an outgoing message, telling the UI if its
+/// status moved forward
+pub(crate) async fn apply_ack<R: Runtime>(app_handle: &AppHandle<R>, database: &DatabaseHandle, message_id: &str, status: &AckStatus) {
+    if let AckStatus::Rejected { reason } = status {
+        tracing::warn!("Relay rejected message {}: {}", message_id, reason);
+    }
+    let (id, new_status) = (message_id.to_string(), status.message_status());
+    match database.call(move |db| db.advance_message_status(&id, new_status)).await {
+        Ok(true) => {
+            let _ = app_handle.emit(
+                "message_status",
+                serde_json::json!({ "id": message_id, "status": new_status, "ack": status }),
+            );
+        }
+        Ok(false) => {}
+        Err(e) => tracing::error!("Failed to update status of {}: {}", message_id, e),
+    }
+}
+
+/// Whether the thread exists and is with someone other than `from`
+async fn is_foreign_thread(database: &DatabaseHandle, thread_id: &str, from: &str) -> bool {
+    let (tid, from) = (thread_id.to_string(), from.to_string());
+    database
+        .call(move |db| db.get_thread_participant(&tid).is_some_and(|p| !p.eq_ignore_ascii_case(&from)))
+        .await
+}
+
+/// Whether a key is the email gateway's, as the directory lists it
+async fn is_email_gateway<R: Runtime>(app_handle: &AppHandle<R>, database: &DatabaseHandle, public_key: &str) -> bool {
+    let Some(state) = app_handle.try_state::<crate::AppState>() else {
+        return false;
+    };
+    match crate::resolver::resolve_handle(&state.api, database, payloads::EMAIL_GATEWAY_HANDLE).await {
+        Ok(Some(gateway)) => gateway.info.public_key.eq_ignore_ascii_case(public_key),
+        Ok(None) => false,
+        Err(e) => {
+            tracing::debug!("Failed to look up the email gateway: {}", e);
+            false
+        }
+    }
+}
+
+/// Apply the per-sender rate limit, telling the UI when a sender is muted
+fn admit_sender<R: Runtime>(app_handle: &AppHandle<R>, from_public_key: &str) -> bool {
+    let Some(state) = app_handle.try_state::<crate::AppState>() else {
+        return true;
+    };
+    match state.rate_limiter.check(from_public_key, chrono::Utc::now().timestamp_millis()) {
+        Admission::Allow => true,
+        Admission::Drop => {
+            tracing::debug!("Dropped message from muted sender {}", from_public_key.get(..16).unwrap_or(from_public_key));
+            false
+        }
+        Admission::Muted { until } => {
+            tracing::warn!("Muted {} for flooding until {}", from_public_key.get(..16).unwrap_or(from_public_key), until);
+            let _ = app_handle.emit(
+                "sender_muted",
+                serde_json::json!({ "publicKey": from_public_key, "mutedUntil": until }),
+            );
+            false
+        }
+    }
+}
+
+/// Decide where a message goes
+///
+/// Existing threads keep their folder, if the sender is the one they are
+/// with. The first message of a thread from a stranger is scored, looking the sender up in the directory: junk
+/// goes to the junk folder, anything else is held as a contact request.
+async fn classify_sender<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    database: &DatabaseHandle,
+    thread_id: &str,
+    opened: &gns_crypto_core::envelope::OpenedEnvelope,
+    text: &str,
+) -> Placement {
+    let (tid, key) = (thread_id.to_string(), opened.from_public_key.clone());
+    let (existing, verdict, is_contact, pending) = database
+        .call(move |db| {
+            (
+                db.get_thread_junk(&tid, &key),
+                db.get_sender_verdict(&key),
+                db.is_contact(&key),
+                db.has_contact_request(&key),
+            )
+        })
+        .await;
+    match existing {
+        Some(true) => return Placement::Junk,
+        Some(false) => return Placement::Inbox,
+        None if pending => return Placement::Request,
+        None => {}
+    }
+
+    let mut signals = SenderSignals {
+        verdict,
+        is_contact,
+        signature_valid: opened.signature_valid,
+        claimed_handle: opened.from_handle.clone(),
+        ..Default::default()
+    };
+    if verdict.is_none() && !is_contact {
+        if let Some(state) = app_handle.try_state::<crate::AppState>() {
+            match state.api.get_identity(&opened.from_public_key).await {
+                Ok(info) => {
+                    signals.directory_checked = true;
+                    if let Some(info) = info {
+                        signals.registered_handle = info.handle;
+                        signals.is_verified = info.is_verified || !info.verifications.is_empty();
+                    }
+                }
+                Err(e) => tracing::debug!("Directory lookup for {} failed: {}", &opened.from_public_key[..16], e),
+            }
+        }
+    }
+
+    let score = spam::classify(&signals, text);
+    if score.junk {
+        tracing::info!("Thread {} sorted into junk ({}: {:?})", thread_id, score.score, score.reasons);
+        Placement::Junk
+    } else if verdict.is_none() && !is_contact {
+        Placement::Request
+    } else {
+        Placement::Inbox
+    }
+}
+
+/// Drop an envelope that failed validation, telling the UI why
+fn reject_envelope<R: Runtime>(app_handle: &AppHandle<R>, id: &str, from_public_key: &str, error: ValidationError) {
+    tracing::warn!("Rejected envelope {}: {}", id, error);
+    METRICS.envelope_rejected();
+    let _ = app_handle.emit(
+        "envelope_rejected",
+        serde_json::json!({
+            "id": id,
+            "fromPublicKey": from_public_key,
+            "error": error,
+        }),
+    );
+}
+
+/// Re-check a verified peer's key at most this often (ms)
+const KEY_CHECK_INTERVAL_MS: i64 = 60 * 60 * 1000;
+
+/// For verified peers, look up their current key in the background and
+/// emit `key_changed` if it no longer matches the verified one
+fn check_contact_key<R: Runtime>(app_handle: &AppHandle<R>, database: &DatabaseHandle, event: &IncomingMessageEvent) {
+    let app_handle = app_handle.clone();
+    let database = database.clone();
+    let public_key = event.from_public_key.clone();
+    let thread_id = event.thread_id.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let now = chrono::Utc::now().timestamp_millis();
+        let key = public_key.clone();
+        let due = database
+            .call(move |db| db.get_contact_key(&key))
+            .await
+            .is_some_and(|c| c.verified && c.key_changed_at.is_none() && now - c.checked_at > KEY_CHECK_INTERVAL_MS);
+        if !due {
+            return;
+        }
+
+        let api = app_handle.state::<crate::AppState>().api.clone();
+        let info = match api.get_identity(&public_key).await {
+            Ok(Some(info)) if !info.encryption_key.is_empty() => info,
+            Ok(_) => return,
+            Err(e) => {
+                tracing::debug!("Key check for {} failed: {}", &public_key[..16], e);
+                return;
+            }
+        };
+
+        let (key, encryption_key) = (public_key.clone(), info.encryption_key.clone());
+        let changed = database
+            .call(move |db| db.record_contact_key(&key, &encryption_key, now))
+            .await
+            .unwrap_or(false);
+        if changed {
+            tracing::warn!("⚠️ Key changed for verified contact {}", &public_key[..16]);
+            let _ = app_handle.emit("key_changed", serde_json::json!({
+                "public_key": public_key,
+                "thread_id": thread_id,
+                "encryption_key": info.encryption_key,
+            }));
+        }
+    });
 }
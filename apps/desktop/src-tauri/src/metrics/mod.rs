@@ -0,0 +1,159 @@
+//! Metrics - Process-wide counters for the health report
+//!
+//! The message handler, storage and background sync tasks bump these as
+//! they work; `get_app_health` combines a snapshot with live relay and
+//! database state so support can tell where a stuck client stopped.
+
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use serde::Serialize;
+
+/// Counters for the running app
+pub static METRICS: Metrics = Metrics::new();
+
+pub struct Metrics {
+    messages_queued: AtomicU64,
+    messages_handled: AtomicU64,
+    envelopes_processed: AtomicU64,
+    decryption_failures: AtomicU64,
+    envelopes_rejected: AtomicU64,
+    messages_stored: AtomicU64,
+    storage_errors: AtomicU64,
+    /// Unix timestamps in milliseconds, 0 when it hasn't happened yet
+    last_envelope_at: AtomicI64,
+    last_pending_drain_at: AtomicI64,
+    last_breadcrumb_sync_at: AtomicI64,
+}
+
+/// Point-in-time copy of the counters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MetricsSnapshot {
+    pub envelopes_processed: u64,
+    pub decryption_failures: u64,
+    /// Envelopes dropped by payload validation
+    pub envelopes_rejected: u64,
+    pub messages_stored: u64,
+    pub storage_errors: u64,
+    /// Relay messages waiting for the message handler
+    pub incoming_queue_depth: u64,
+    pub last_envelope_at: Option<i64>,
+    pub last_pending_drain_at: Option<i64>,
+    pub last_breadcrumb_sync_at: Option<i64>,
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Self {
+            messages_queued: AtomicU64::new(0),
+            messages_handled: AtomicU64::new(0),
+            envelopes_processed: AtomicU64::new(0),
+            decryption_failures: AtomicU64::new(0),
+            envelopes_rejected: AtomicU64::new(0),
+            messages_stored: AtomicU64::new(0),
+            storage_errors: AtomicU64::new(0),
+            last_envelope_at: AtomicI64::new(0),
+            last_pending_drain_at: AtomicI64::new(0),
+            last_breadcrumb_sync_at: AtomicI64::new(0),
+        }
+    }
+
+    /// A relay message was handed to the message handler's channel
+    pub fn message_queued(&self) {
+        self.messages_queued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The message handler took a message off its channel
+    pub fn message_handled(&self) {
+        self.messages_handled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An envelope was decrypted
+    pub fn envelope_processed(&self) {
+        self.envelopes_processed.fetch_add(1, Ordering::Relaxed);
+        self.last_envelope_at.store(now_ms(), Ordering::Relaxed);
+    }
+
+    pub fn decryption_failed(&self) {
+        self.decryption_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An envelope failed payload validation
+    pub fn envelope_rejected(&self) {
+        self.envelopes_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A sent or received message was written to the database
+    pub fn message_stored(&self) {
+        self.messages_stored.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn storage_error(&self) {
+        self.storage_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Messages queued on the server were fetched after a reconnect
+    pub fn pending_drained(&self) {
+        self.last_pending_drain_at.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// A breadcrumb upload pass finished
+    pub fn breadcrumbs_synced(&self) {
+        self.last_breadcrumb_sync_at.store(now_ms(), Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let queued = self.messages_queued.load(Ordering::Relaxed);
+        let handled = self.messages_handled.load(Ordering::Relaxed);
+        let timestamp = |value: &AtomicI64| Some(value.load(Ordering::Relaxed)).filter(|t| *t > 0);
+
+        MetricsSnapshot {
+            envelopes_processed: self.envelopes_processed.load(Ordering::Relaxed),
+            decryption_failures: self.decryption_failures.load(Ordering::Relaxed),
+            envelopes_rejected: self.envelopes_rejected.load(Ordering::Relaxed),
+            messages_stored: self.messages_stored.load(Ordering::Relaxed),
+            storage_errors: self.storage_errors.load(Ordering::Relaxed),
+            incoming_queue_depth: queued.saturating_sub(handled),
+            last_envelope_at: timestamp(&self.last_envelope_at),
+            last_pending_drain_at: timestamp(&self.last_pending_drain_at),
+            last_breadcrumb_sync_at: timestamp(&self.last_breadcrumb_sync_at),
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let metrics = Metrics::new();
+        assert_eq!(metrics.snapshot(), MetricsSnapshot::default());
+
+        metrics.message_queued();
+        metrics.message_queued();
+        metrics.message_handled();
+        metrics.envelope_processed();
+        metrics.decryption_failed();
+        metrics.envelope_rejected();
+        metrics.pending_drained();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.incoming_queue_depth, 1);
+        assert_eq!(snapshot.envelopes_processed, 1);
+        assert_eq!(snapshot.decryption_failures, 1);
+        assert_eq!(snapshot.envelopes_rejected, 1);
+        assert!(snapshot.last_envelope_at.is_some());
+        assert!(snapshot.last_pending_drain_at.is_some());
+        assert_eq!(snapshot.last_breadcrumb_sync_at, None);
+    }
+}
@@ -0,0 +1,192 @@
+//! Native Entry Points
+//!
+//! C functions the platform layer calls directly, without going through
+//! the webview's IPC. The iOS hooks in `gen/apple/Sources` use them for
+//! remote notifications, lifecycle changes, background tasks and motion
+//! activity. Work that the OS waits on reports back through a
+//! `(callback, context)` pair once it is done.
+
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::OnceLock;
+
+use tauri::{AppHandle, Manager};
+
+use crate::location::MotionActivity;
+use crate::push::{handle_push, PushPlatform};
+use crate::AppState;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+static MOTION_SWITCH: OnceLock<MotionSwitch> = OnceLock::new();
+
+/// Make the running app reachable from the entry points below
+pub fn install(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+/// Completion the caller passes in: `result` is a count, or -1 on failure
+pub type DoneCallback = extern "C" fn(context: *mut c_void, result: i32);
+
+/// Turns the OS motion activity updates on or off
+pub type MotionSwitch = extern "C" fn(on: bool);
+
+/// A completion carried across to the async runtime
+struct Done {
+    callback: DoneCallback,
+    context: *mut c_void,
+}
+
+// The context is opaque here and only ever handed back to the callback
+unsafe impl Send for Done {}
+
+impl Done {
+    fn call(self, result: i32) {
+        (self.callback)(self.context, result);
+    }
+}
+
+/// Copy a C string, `None` if it is null or not UTF-8
+unsafe fn read_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok().map(str::to_string)
+}
+
+/// Handle a remote notification's data, given as JSON
+///
+/// Fetches, decrypts and stores the envelope it names, then posts the
+/// local notification. `done` gets 1 if a new message was stored, 0 if it
+/// was already there, and -1 on failure or before the app has started.
+///
+/// # Safety
+///
+/// `data_json` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gns_handle_push(
+    data_json: *const c_char,
+    done: DoneCallback,
+    context: *mut c_void,
+) {
+    let done = Done { callback: done, context };
+    let data = read_str(data_json).and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok());
+    let (Some(app), Some(data)) = (APP.get(), data) else {
+        return done.call(-1);
+    };
+
+    tauri::async_runtime::spawn(async move {
+        match handle_push(app, &data).await {
+            Ok(event) => done.call(i32::from(event.is_some())),
+            Err(e) => {
+                tracing::warn!("Push not handled: {}", e);
+                done.call(-1);
+            }
+        }
+    });
+}
+
+/// Register the token the OS issued for remote notifications
+///
+/// `platform` is `"apns"` or `"fcm"`. `done` gets 0 once the backend has
+/// the token, or -1 on failure.
+///
+/// # Safety
+///
+/// `platform` and `token` must be null or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn gns_register_push_token(
+    platform: *const c_char,
+    token: *const c_char,
+    done: DoneCallback,
+    context: *mut c_void,
+) {
+    let done = Done { callback: done, context };
+    let platform = read_str(platform)
+        .and_then(|p| serde_json::from_value::<PushPlatform>(serde_json::Value::String(p)).ok());
+    let (Some(app), Some(platform), Some(token)) = (APP.get(), platform, read_str(token)) else {
+        return done.call(-1);
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        match crate::commands::push::register(&state, platform, token).await {
+            Ok(_) => done.call(0),
+            Err(e) => {
+                tracing::warn!("Push token not registered: {}", e);
+                done.call(-1);
+            }
+        }
+    });
+}
+
+/// Report whether the app is in the foreground, for the relay keepalive
+#[no_mangle]
+pub extern "C" fn gns_set_app_foreground(foreground: bool) {
+    if let Some(app) = APP.get() {
+        app.state::<AppState>().relay_keepalive.set_foreground(foreground);
+    }
+}
+
+/// Drain messages queued on the server, for a background task
+///
+/// `done` gets the number of new messages stored, or -1 on failure.
+#[no_mangle]
+pub extern "C" fn gns_run_background_fetch(done: DoneCallback, context: *mut c_void) {
+    let done = Done { callback: done, context };
+    let Some(app) = APP.get() else {
+        return done.call(-1);
+    };
+
+    tauri::async_runtime::spawn(async move {
+        match crate::commands::network::background_fetch(app).await {
+            Ok(received) => done.call(i32::try_from(received).unwrap_or(i32::MAX)),
+            Err(e) => {
+                tracing::warn!("Background fetch failed: {}", e);
+                done.call(-1);
+            }
+        }
+    });
+}
+
+/// Let the platform layer turn motion activity updates on and off
+///
+/// Called before the app starts. Updates only run while breadcrumb
+/// collection is enabled, so the OS motion prompt waits until then.
+#[no_mangle]
+pub extern "C" fn gns_set_motion_switch(switch: MotionSwitch) {
+    let _ = MOTION_SWITCH.set(switch);
+}
+
+/// Start or stop the OS motion activity updates, if the platform has them
+pub fn set_motion_updates(on: bool) {
+    if let Some(switch) = MOTION_SWITCH.get() {
+        switch(on);
+    }
+}
+
+/// Feed an OS motion signal to the breadcrumb collector
+///
+/// `activity` is a `MotionActivity` name such as `"walking"`;
+/// `significant_motion` is a one-shot significant-change trigger.
+///
+/// # Safety
+///
+/// `activity` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gns_report_motion(activity: *const c_char, significant_motion: bool) {
+    let activity = read_str(activity)
+        .and_then(|a| serde_json::from_value::<MotionActivity>(serde_json::Value::String(a)).ok())
+        .unwrap_or(MotionActivity::Unknown);
+    let Some(app) = APP.get() else {
+        return;
+    };
+
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        state.breadcrumb_collector.lock().await.report_motion(activity, significant_motion);
+    });
+
+    #[cfg(not(any(target_os = "ios", target_os = "android")))]
+    let _ = (app, activity, significant_motion);
+}
@@ -0,0 +1,144 @@
+//! Relay Acknowledgements - Delivery confirmation for outgoing envelopes
+//!
+//! Every envelope frame carries its envelope ID. The relay answers with an
+//! `ack` frame for that ID once it has stored the envelope, and again when
+//! a recipient device has received it. The sender waits for the first ack
+//! with a timeout; later acks arrive through the incoming channel.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+/// How long a send waits for the relay to acknowledge an envelope
+pub const RELAY_ACK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What the relay reported for an envelope
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AckStatus {
+    /// Stored by the relay
+    Relayed,
+    /// Received by at least one recipient device
+    Delivered,
+    /// Refused by the relay
+    Rejected { reason: String },
+}
+
+impl AckStatus {
+    pub fn parse(status: &str, reason: Option<&str>) -> Self {
+        match status {
+            "delivered" => Self::Delivered,
+            "rejected" | "error" => Self::Rejected {
+                reason: reason.unwrap_or("Rejected by relay").to_string(),
+            },
+            _ => Self::Relayed,
+        }
+    }
+
+    /// Value for the `messages.status` column
+    pub fn message_status(&self) -> &'static str {
+        match self {
+            Self::Relayed => "relayed",
+            Self::Delivered => "delivered",
+            Self::Rejected { .. } => "failed",
+        }
+    }
+}
+
+/// Sends waiting for their first ack, shared with the socket reader
+#[derive(Clone, Default)]
+pub struct PendingAcks {
+    waiters: Arc<Mutex<HashMap<String, oneshot::Sender<AckStatus>>>>,
+}
+
+impl PendingAcks {
+    pub fn register(&self, id: &str) -> PendingAck {
+        let (tx, rx) = oneshot::channel();
+        let mut waiters = self.waiters.lock().unwrap_or_else(|e| e.into_inner());
+        // Drop waiters whose sender stopped listening
+        waiters.retain(|_, tx| !tx.is_closed());
+        waiters.insert(id.to_string(), tx);
+        PendingAck {
+            id: id.to_string(),
+            rx,
+            acks: self.clone(),
+        }
+    }
+
+    /// Hand an ack to its waiting send; false if nobody was waiting
+    pub fn resolve(&self, id: &str, status: AckStatus) -> bool {
+        let waiter = self.waiters.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        waiter.is_some_and(|tx| tx.send(status).is_ok())
+    }
+
+    /// Give up on all waiters, e.g. when the socket closes
+    pub fn clear(&self) {
+        self.waiters.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    fn forget(&self, id: &str) {
+        self.waiters.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    }
+}
+
+/// The first ack for one sent envelope
+pub struct PendingAck {
+    id: String,
+    rx: oneshot::Receiver<AckStatus>,
+    acks: PendingAcks,
+}
+
+impl PendingAck {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Wait for the ack; None on timeout or if the connection dropped
+    pub async fn wait(self, timeout: Duration) -> Option<AckStatus> {
+        let Self { id, rx, acks } = self;
+        let result = tokio::time::timeout(timeout, rx).await;
+        acks.forget(&id);
+        result.ok().and_then(|r| r.ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ack_round_trip() {
+        let acks = PendingAcks::default();
+        let pending = acks.register("m1");
+        assert!(acks.resolve("m1", AckStatus::Relayed));
+        assert_eq!(pending.wait(Duration::from_millis(10)).await, Some(AckStatus::Relayed));
+
+        // Second ack for the same envelope has no waiter
+        assert!(!acks.resolve("m1", AckStatus::Delivered));
+    }
+
+    #[tokio::test]
+    async fn test_ack_timeout() {
+        let acks = PendingAcks::default();
+        let pending = acks.register("m1");
+        assert_eq!(pending.wait(Duration::from_millis(10)).await, None);
+        assert!(!acks.resolve("m1", AckStatus::Relayed));
+
+        let pending = acks.register("m2");
+        acks.clear();
+        assert_eq!(pending.wait(Duration::from_secs(5)).await, None);
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(AckStatus::parse("stored", None), AckStatus::Relayed);
+        assert_eq!(AckStatus::parse("delivered", None).message_status(), "delivered");
+        assert_eq!(
+            AckStatus::parse("rejected", Some("Payload too large")),
+            AckStatus::Rejected { reason: "Payload too large".to_string() }
+        );
+    }
+}
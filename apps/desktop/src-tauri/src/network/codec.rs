@@ -0,0 +1,223 @@
+//! Relay Frame Encoding - JSON text or MessagePack binary frames
+//!
+//! The client offers both encodings as WebSocket subprotocols. A relay that
+//! picks `gns.msgpack` gets binary MessagePack frames; one that picks
+//! nothing (older relays) gets the JSON text frames it always did. Frames
+//! carry the same fields either way, except that hex fields holding binary
+//! data (ciphertext, nonces, ephemeral keys, signatures) travel as raw
+//! bytes in MessagePack, which roughly halves an envelope.
+
+use serde::Serialize;
+use serde_json::Value as Json;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Subprotocol for MessagePack frames
+pub const MSGPACK_PROTOCOL: &str = "gns.msgpack";
+
+/// Offered in the handshake, preferred first
+pub const OFFERED_PROTOCOLS: &str = "gns.msgpack, gns.json";
+
+/// Hex fields sent as bytes in MessagePack
+const BINARY_FIELDS: &[&str] = &[
+    "ciphertext",
+    "kemCiphertext",
+    "kem_ciphertext",
+    "nonce",
+    "ephemeralPublicKey",
+    "ephemeral_public_key",
+    "signature",
+];
+
+/// How frames are encoded on the current connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameEncoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl FrameEncoding {
+    /// The encoding for the subprotocol the relay chose, if any
+    pub fn negotiated(protocol: Option<&str>) -> Self {
+        match protocol.map(str::trim) {
+            Some(MSGPACK_PROTOCOL) => Self::MessagePack,
+            _ => Self::Json,
+        }
+    }
+
+    /// Encode a JSON frame for the socket
+    pub fn encode(self, text: String) -> Message {
+        match self {
+            Self::Json => Message::Text(text),
+            Self::MessagePack => match serde_json::from_str::<Json>(&text) {
+                Ok(json) => Message::Binary(to_msgpack(&json)),
+                // Not ours to fix; let the relay see what was sent
+                Err(_) => Message::Text(text),
+            },
+        }
+    }
+}
+
+/// JSON frame as MessagePack
+pub fn to_msgpack(json: &Json) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    // Writing to a Vec can't fail
+    let _ = rmpv::encode::write_value(&mut bytes, &to_value(json, false));
+    bytes
+}
+
+/// MessagePack frame as JSON, with binary fields back as hex
+pub fn from_msgpack(mut bytes: &[u8]) -> Option<Json> {
+    rmpv::decode::read_value(&mut bytes).ok().map(from_value)
+}
+
+fn to_value(json: &Json, binary: bool) -> rmpv::Value {
+    match json {
+        Json::Null => rmpv::Value::Nil,
+        Json::Bool(b) => rmpv::Value::Boolean(*b),
+        Json::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => rmpv::Value::from(i),
+            (None, Some(u)) => rmpv::Value::from(u),
+            _ => rmpv::Value::F64(n.as_f64().unwrap_or_default()),
+        },
+        Json::String(s) if binary && is_lower_hex(s) => rmpv::Value::Binary(hex::decode(s).unwrap_or_default()),
+        Json::String(s) => rmpv::Value::from(s.as_str()),
+        Json::Array(items) => rmpv::Value::Array(items.iter().map(|item| to_value(item, false)).collect()),
+        Json::Object(fields) => rmpv::Value::Map(
+            fields
+                .iter()
+                .map(|(key, value)| {
+                    let binary = BINARY_FIELDS.contains(&key.as_str());
+                    (rmpv::Value::from(key.as_str()), to_value(value, binary))
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn from_value(value: rmpv::Value) -> Json {
+    match value {
+        rmpv::Value::Nil | rmpv::Value::Ext(..) => Json::Null,
+        rmpv::Value::Boolean(b) => Json::Bool(b),
+        rmpv::Value::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(i), _) => Json::from(i),
+            (None, Some(u)) => Json::from(u),
+            _ => Json::Null,
+        },
+        rmpv::Value::F32(f) => Json::from(f as f64),
+        rmpv::Value::F64(f) => Json::from(f),
+        rmpv::Value::String(s) => s.into_str().map(Json::String).unwrap_or(Json::Null),
+        rmpv::Value::Binary(bytes) => Json::String(hex::encode(bytes)),
+        rmpv::Value::Array(items) => Json::Array(items.into_iter().map(from_value).collect()),
+        rmpv::Value::Map(fields) => Json::Object(
+            fields
+                .into_iter()
+                .filter_map(|(key, value)| Some((key.as_str()?.to_string(), from_value(value))))
+                .collect(),
+        ),
+    }
+}
+
+/// Only lowercase hex round-trips exactly through bytes
+fn is_lower_hex(s: &str) -> bool {
+    !s.is_empty() && s.len().is_multiple_of(2) && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{parse_incoming_json, parse_incoming_message, IncomingMessage};
+    use std::time::Instant;
+
+    fn envelope_frame() -> Json {
+        serde_json::json!({
+            "type": "message",
+            "id": "3f0c9a52-6a0e-4b8e-9a0a-5d2f1c7e8b11",
+            "envelope": {
+                "id": "3f0c9a52-6a0e-4b8e-9a0a-5d2f1c7e8b11",
+                "fromPublicKey": "a".repeat(64),
+                "toPublicKeys": ["b".repeat(64)],
+                "payloadType": "text/plain",
+                "timestamp": 1_700_000_000_000i64,
+                "threadId": "direct_aaaa",
+                "encryptedPayload": {
+                    "ephemeralPublicKey": "c".repeat(64),
+                    "nonce": "d".repeat(24),
+                    "ciphertext": "0123456789abcdef".repeat(64),
+                },
+                "signature": "e".repeat(128),
+            }
+        })
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let frame = envelope_frame();
+        let bytes = to_msgpack(&frame);
+        assert_eq!(from_msgpack(&bytes), Some(frame.clone()));
+
+        // Binary fields shrink the frame well below its JSON size
+        let text = frame.to_string();
+        assert!(bytes.len() * 3 < text.len() * 2, "{} vs {}", bytes.len(), text.len());
+
+        // Non-hex values in binary fields stay strings
+        let odd = serde_json::json!({ "signature": "not hex", "nonce": "ABCD" });
+        assert_eq!(from_msgpack(&to_msgpack(&odd)), Some(odd));
+        assert_eq!(from_msgpack(b""), None);
+    }
+
+    #[test]
+    fn test_negotiation() {
+        assert_eq!(FrameEncoding::negotiated(Some("gns.msgpack")), FrameEncoding::MessagePack);
+        assert_eq!(FrameEncoding::negotiated(Some("gns.json")), FrameEncoding::Json);
+        assert_eq!(FrameEncoding::negotiated(None), FrameEncoding::Json);
+
+        let text = envelope_frame().to_string();
+        assert!(matches!(FrameEncoding::Json.encode(text.clone()), Message::Text(_)));
+        let Message::Binary(bytes) = FrameEncoding::MessagePack.encode(text) else {
+            panic!("expected a binary frame");
+        };
+        let parsed = parse_incoming_json(from_msgpack(&bytes).unwrap(), None);
+        assert!(matches!(parsed, IncomingMessage::Envelope(_)));
+    }
+
+    #[test]
+    fn test_malformed_envelope_is_dropped() {
+        let mut frame = envelope_frame();
+        assert!(matches!(parse_incoming_message(&frame.to_string()), IncomingMessage::Envelope(_)));
+
+        frame["envelope"]["encryptedPayload"]["nonce"] = "dd".into();
+        assert!(matches!(parse_incoming_message(&frame.to_string()), IncomingMessage::Unknown(_)));
+
+        // Same for a bare envelope without the frame around it
+        let bare = frame["envelope"].to_string();
+        assert!(matches!(parse_incoming_message(&bare), IncomingMessage::Unknown(_)));
+    }
+
+    /// `cargo test --release bench_parse_incoming -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_parse_incoming() {
+        const ROUNDS: u32 = 20_000;
+        let frame = envelope_frame();
+        let text = frame.to_string();
+        let bytes = to_msgpack(&frame);
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            std::hint::black_box(parse_incoming_message(std::hint::black_box(&text)));
+        }
+        let json = start.elapsed() / ROUNDS;
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            let decoded = from_msgpack(std::hint::black_box(&bytes)).unwrap();
+            std::hint::black_box(parse_incoming_json(decoded, None));
+        }
+        let msgpack = start.elapsed() / ROUNDS;
+
+        println!("JSON:        {} bytes, {:?} per frame", text.len(), json);
+        println!("MessagePack: {} bytes, {:?} per frame", bytes.len(), msgpack);
+    }
+}
@@ -0,0 +1,280 @@
+//! Federated Handles - `alice@example.org` resolved through the domain
+//!
+//! A domain vouches for its names in one of two places:
+//!
+//! - a TXT record at `alice._gns.example.org` of the form
+//!   `v=gns1 pk=<hex> ek=<hex> sig=<hex>`, looked up over DNS-over-HTTPS
+//!   when the user has chosen a resolver (`set_dns_over_https`); without
+//!   one, handles aren't sent to any third party and only the file below
+//!   is tried;
+//! - `https://example.org/.well-known/gns.json`, a `names` map from local
+//!   part to an entry carrying `handle`, `public_key`, `encryption_key`,
+//!   optional profile fields and a `signature`.
+//!
+//! Either way the keys must be self-signed: `sig` / `signature` is the
+//! Ed25519 signature of `public_key` over the canonical JSON of the entry
+//! (without the signature), and the entry's `handle` must be the full
+//! federated handle. The domain controls which key a name points at; the
+//! signature proves the key holder agreed to the name.
+
+use serde_json::{json, Value};
+
+use super::{ApiClient, IdentityInfo, NetworkError};
+use crate::commands::handles::canonical_json;
+use crate::verifications::verified_proofs;
+
+/// DNS TXT record type
+const TXT_TYPE: u64 = 16;
+
+/// A handle of the form `local@domain`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FederatedHandle {
+    pub local: String,
+    pub domain: String,
+}
+
+impl FederatedHandle {
+    /// Parse `alice@example.org` (a leading `@` is allowed); None for
+    /// plain GNS handles and anything that isn't a valid name and domain
+    pub fn parse(handle: &str) -> Option<Self> {
+        let (local, domain) = handle.trim_start_matches('@').split_once('@')?;
+        let (local, domain) = (local.to_lowercase(), domain.trim_end_matches('.').to_lowercase());
+
+        let local_ok = !local.is_empty()
+            && local.len() <= 63
+            && local
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'));
+        let domain_ok = domain.len() <= 253
+            && domain.contains('.')
+            && domain.split('.').all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            });
+
+        (local_ok && domain_ok).then_some(Self { local, domain })
+    }
+
+    pub fn full(&self) -> String {
+        format!("{}@{}", self.local, self.domain)
+    }
+
+    fn txt_name(&self) -> String {
+        format!("{}._gns.{}", self.local, self.domain)
+    }
+
+    fn well_known_url(&self) -> String {
+        format!("https://{}/.well-known/gns.json", self.domain)
+    }
+}
+
+impl ApiClient {
+    /// The DNS-over-HTTPS resolver TXT lookups go to, if any
+    pub fn doh_url(&self) -> Option<String> {
+        self.doh_url.read().unwrap().clone()
+    }
+
+    /// Use a DNS-over-HTTPS resolver answering in JSON
+    /// (`application/dns-json`), or none to skip TXT lookups
+    pub fn set_doh_url(&self, url: Option<String>) {
+        *self.doh_url.write().unwrap() = url;
+    }
+
+    /// Resolve a federated handle: DNS first, then the well-known file
+    pub async fn resolve_federated(&self, handle: &FederatedHandle) -> Result<Option<IdentityInfo>, NetworkError> {
+        match self.lookup_txt(&handle.txt_name()).await {
+            Ok(Some(txt)) => {
+                let entry = parse_txt(&txt, &handle.full())
+                    .ok_or_else(|| NetworkError::ParseError(format!("Malformed _gns record for {}", handle.full())))?;
+                return verify_entry(handle, &entry).map(Some);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!("DNS lookup for {} failed, trying well-known: {}", handle.full(), e),
+        }
+
+        let response = self.client.get(handle.well_known_url()).send().await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if response.status() == 404 {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("{} returned status: {}", handle.domain, response.status())));
+        }
+
+        let document: Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        match document["names"].get(&handle.local) {
+            Some(entry) => verify_entry(handle, entry).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// First `v=gns1` TXT record at `name`, quotes and chunking removed
+    async fn lookup_txt(&self, name: &str) -> Result<Option<String>, NetworkError> {
+        let Some(doh_url) = self.doh_url() else {
+            return Ok(None);
+        };
+        let response = self.client
+            .get(doh_url)
+            .query(&[("name", name), ("type", "TXT")])
+            .header("accept", "application/dns-json")
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("DNS resolver returned status: {}", response.status())));
+        }
+
+        let answer: Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        Ok(answer["Answer"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|record| record["type"].as_u64() == Some(TXT_TYPE))
+            .filter_map(|record| record["data"].as_str())
+            .map(unquote_txt)
+            .find(|txt| txt.starts_with("v=gns1")))
+    }
+}
+
+/// Join the quoted chunks of a TXT answer: `"v=gns1 " "pk=…"` → `v=gns1 pk=…`
+fn unquote_txt(data: &str) -> String {
+    if !data.starts_with('"') {
+        return data.to_string();
+    }
+    data.split('"').skip(1).step_by(2).collect()
+}
+
+/// Turn `v=gns1 pk=… ek=… sig=…` into an entry like the well-known one
+fn parse_txt(txt: &str, handle: &str) -> Option<Value> {
+    let field = |name: &str| {
+        txt.split(|c: char| c == ';' || c.is_whitespace())
+            .filter_map(|part| part.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    };
+
+    Some(json!({
+        "handle": handle,
+        "public_key": field("pk")?,
+        "encryption_key": field("ek")?,
+        "signature": field("sig")?,
+    }))
+}
+
+/// Check an entry's self-signature and that it names `handle`
+fn verify_entry(handle: &FederatedHandle, entry: &Value) -> Result<IdentityInfo, NetworkError> {
+    let invalid = |reason: &str| NetworkError::ParseError(format!("{}: {}", handle.full(), reason));
+
+    let mut statement = entry.clone();
+    let fields = statement.as_object_mut().ok_or_else(|| invalid("entry is not an object"))?;
+    let signature = fields
+        .remove("signature")
+        .and_then(|s| s.as_str().map(|s| s.to_string()))
+        .ok_or_else(|| invalid("entry is unsigned"))?;
+
+    let text = |name: &str| fields.get(name).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let public_key = text("public_key").ok_or_else(|| invalid("missing public_key"))?.to_lowercase();
+    let encryption_key = text("encryption_key").ok_or_else(|| invalid("missing encryption_key"))?;
+    if !text("handle").is_some_and(|h| h.eq_ignore_ascii_case(&handle.full())) {
+        return Err(invalid("entry is for another handle"));
+    }
+
+    let valid = gns_crypto_core::signing::verify_signature_hex(&public_key, canonical_json(&statement).as_bytes(), &signature)
+        .unwrap_or(false);
+    if !valid {
+        return Err(invalid("signature does not verify"));
+    }
+
+    Ok(IdentityInfo {
+        public_key,
+        encryption_key,
+        pq_encryption_key: entry["pq_encryption_key"].as_str().map(|s| s.to_string()),
+        handle: Some(handle.full()),
+        avatar_url: entry["avatar_url"].as_str().map(|s| s.to_string()),
+        display_name: entry["display_name"].as_str().map(|s| s.to_string()),
+        // Domain-vouched names are not checked by the GNS backend
+        is_verified: false,
+        verifications: verified_proofs(entry),
+        modules: crate::capabilities::module_ids(&entry["modules"]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::GnsIdentity;
+
+    fn alice() -> FederatedHandle {
+        FederatedHandle::parse("alice@example.org").unwrap()
+    }
+
+    fn sign(identity: &GnsIdentity, mut entry: Value) -> Value {
+        let signature = hex::encode(identity.sign_bytes(canonical_json(&entry).as_bytes()));
+        entry["signature"] = Value::String(signature);
+        entry
+    }
+
+    #[test]
+    fn test_parse_handles() {
+        assert_eq!(
+            FederatedHandle::parse("@Alice@Example.org."),
+            Some(FederatedHandle { local: "alice".to_string(), domain: "example.org".to_string() })
+        );
+        assert!(FederatedHandle::parse("alice").is_none());
+        assert!(FederatedHandle::parse("alice@localhost").is_none());
+        assert!(FederatedHandle::parse("al ice@example.org").is_none());
+        assert!(FederatedHandle::parse("alice@-bad.org").is_none());
+    }
+
+    #[test]
+    fn test_txt_record_roundtrip() {
+        let identity = GnsIdentity::generate();
+        let signed = sign(&identity, json!({
+            "handle": "alice@example.org",
+            "public_key": identity.public_key_hex(),
+            "encryption_key": identity.encryption_key_hex(),
+        }));
+        let data = format!(
+            "\"v=gns1 pk={} ek={} \" \"sig={}\"",
+            identity.public_key_hex(),
+            identity.encryption_key_hex(),
+            signed["signature"].as_str().unwrap()
+        );
+
+        let entry = parse_txt(&unquote_txt(&data), "alice@example.org").unwrap();
+        let info = verify_entry(&alice(), &entry).unwrap();
+        assert_eq!(info.public_key, identity.public_key_hex());
+        assert_eq!(info.handle.as_deref(), Some("alice@example.org"));
+        assert!(parse_txt("v=gns1 pk=ab", "alice@example.org").is_none());
+    }
+
+    #[test]
+    fn test_entries_must_be_self_signed_for_the_handle() {
+        let (identity, other) = (GnsIdentity::generate(), GnsIdentity::generate());
+        let entry = json!({
+            "handle": "alice@example.org",
+            "public_key": identity.public_key_hex(),
+            "encryption_key": identity.encryption_key_hex(),
+            "display_name": "Alice",
+        });
+
+        assert!(verify_entry(&alice(), &sign(&identity, entry.clone())).is_ok());
+        assert!(verify_entry(&alice(), &sign(&other, entry.clone())).is_err());
+        assert!(verify_entry(&alice(), &entry).is_err());
+
+        // Signed, but for a different name on the domain
+        let mut renamed = entry;
+        renamed["handle"] = json!("bob@example.org");
+        assert!(verify_entry(&alice(), &sign(&identity, renamed)).is_err());
+    }
+}
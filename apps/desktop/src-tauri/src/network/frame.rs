@@ -0,0 +1,342 @@
+//! Relay Frames - The WebSocket protocol as one typed enum
+//!
+//! Every frame exchanged with the relay is a JSON object tagged by `type`.
+//! Frames are parsed into and built from `RelayFrame`, so wire field names
+//! live here and nowhere else. Field names follow what the relay, mobile
+//! and browser clients already send, including the odd snake_case one.
+
+use gns_crypto_core::GnsEnvelope;
+use serde::{Deserialize, Serialize};
+
+use super::ack::AckStatus;
+use crate::channels::ChannelPost;
+use super::IncomingMessage;
+
+/// Messages sent per sync request when the browser doesn't say
+const DEFAULT_SYNC_LIMIT: u32 = 50;
+
+/// One relay frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayFrame {
+    /// An encrypted envelope; the relay acks by `id`
+    #[serde(alias = "envelope")]
+    Message {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(alias = "data")]
+        envelope: Box<GnsEnvelope>,
+    },
+    Welcome {
+        #[serde(rename = "publicKey", default)]
+        public_key: String,
+    },
+    ConnectionStatus {
+        #[serde(default)]
+        data: DeviceCounts,
+    },
+    /// A message a browser sent, for the mobile device to record
+    MessageSentFromBrowser {
+        #[serde(rename = "messageId", default)]
+        message_id: String,
+        #[serde(default)]
+        to_pk: String,
+        #[serde(default)]
+        plaintext: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timestamp: Option<i64>,
+    },
+    /// Decrypted message content forwarded to our other devices
+    MessageSynced {
+        /// Devices to route to; empty means all of ours
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        to: Vec<String>,
+        #[serde(rename = "messageId", default)]
+        message_id: String,
+        #[serde(rename = "conversationWith", default)]
+        conversation_with: String,
+        #[serde(rename = "decryptedText", default)]
+        decrypted_text: String,
+        #[serde(default = "default_direction")]
+        direction: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timestamp: Option<i64>,
+        #[serde(rename = "fromHandle", default, skip_serializing_if = "Option::is_none")]
+        from_handle: Option<String>,
+        /// Full payload, for content the text alone can't rebuild (email)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<serde_json::Value>,
+    },
+    ReadReceipt {
+        #[serde(rename = "messageId", default)]
+        message_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timestamp: Option<i64>,
+    },
+    /// Relay acknowledgement for an envelope we sent
+    Ack {
+        #[serde(alias = "messageId", default)]
+        id: String,
+        #[serde(default)]
+        status: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    RequestSync {
+        #[serde(rename = "conversationWith", default)]
+        conversation_with: String,
+        #[serde(default = "default_sync_limit")]
+        limit: u32,
+    },
+    RequestDecryption {
+        #[serde(rename = "messageIds", default)]
+        message_ids: Vec<String>,
+        #[serde(rename = "conversationWith", default)]
+        conversation_with: String,
+        /// Filled in by the relay on the way to us
+        #[serde(rename = "requester", default, skip_serializing_if = "String::is_empty")]
+        requester_pk: String,
+    },
+    /// A contact came online or went offline
+    Presence {
+        #[serde(rename = "publicKey", default)]
+        public_key: String,
+        #[serde(default)]
+        online: bool,
+        #[serde(rename = "lastSeen", default, skip_serializing_if = "Option::is_none")]
+        last_seen: Option<i64>,
+    },
+    /// Contacts whose presence we want to hear about
+    PresenceSubscribe {
+        #[serde(rename = "publicKeys", default)]
+        public_keys: Vec<String>,
+    },
+    /// Whether the relay may tell others when we're online
+    PresenceSettings {
+        #[serde(default)]
+        share: bool,
+    },
+    /// A new post in a channel we follow
+    ChannelPost {
+        post: Box<ChannelPost>,
+    },
+    /// Channels whose posts we want streamed to us
+    ChannelSubscribe {
+        #[serde(rename = "channelIds", default)]
+        channel_ids: Vec<String>,
+    },
+}
+
+/// Devices connected for our identity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceCounts {
+    #[serde(default)]
+    pub mobile: bool,
+    #[serde(default)]
+    pub browsers: u32,
+}
+
+fn default_direction() -> String {
+    "incoming".to_string()
+}
+
+fn default_sync_limit() -> u32 {
+    DEFAULT_SYNC_LIMIT
+}
+
+impl RelayFrame {
+    /// Frame for sending an envelope
+    pub fn envelope(envelope: &GnsEnvelope) -> Self {
+        Self::Message {
+            id: Some(envelope.id.clone()),
+            envelope: Box::new(envelope.clone()),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // Every variant is plain data; serializing can't fail
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl From<RelayFrame> for IncomingMessage {
+    fn from(frame: RelayFrame) -> Self {
+        let now = || chrono::Utc::now().timestamp_millis();
+        match frame {
+            RelayFrame::Message { envelope, .. } => Self::Envelope(envelope),
+            RelayFrame::Welcome { public_key } => Self::Welcome { public_key },
+            RelayFrame::ConnectionStatus { data } => Self::ConnectionStatus {
+                mobile: data.mobile,
+                browsers: data.browsers,
+            },
+            RelayFrame::MessageSentFromBrowser { message_id, to_pk, plaintext, timestamp } => {
+                Self::MessageSentFromBrowser {
+                    message_id,
+                    to_pk,
+                    plaintext,
+                    timestamp: timestamp.unwrap_or_else(now),
+                }
+            }
+            RelayFrame::MessageSynced {
+                message_id,
+                conversation_with,
+                decrypted_text,
+                direction,
+                timestamp,
+                from_handle,
+                ..
+            } => Self::MessageSynced {
+                message_id,
+                conversation_with,
+                decrypted_text,
+                direction,
+                timestamp: timestamp.unwrap_or_else(now),
+                from_handle,
+            },
+            RelayFrame::ReadReceipt { message_id, timestamp } => Self::ReadReceipt {
+                message_id,
+                timestamp: timestamp.unwrap_or_else(now),
+            },
+            RelayFrame::Ack { id, status, error } => Self::Ack {
+                message_id: id,
+                status: AckStatus::parse(&status, error.as_deref()),
+            },
+            RelayFrame::RequestSync { conversation_with, limit } => Self::RequestSync { conversation_with, limit },
+            RelayFrame::RequestDecryption { message_ids, conversation_with, requester_pk } => {
+                Self::RequestDecryption {
+                    message_ids,
+                    conversation_with,
+                    requester_pk,
+                }
+            }
+            RelayFrame::Presence { public_key, online, last_seen } => Self::Presence {
+                public_key,
+                online,
+                last_seen,
+            },
+            RelayFrame::ChannelPost { post } => Self::ChannelPost(post),
+            // Only ever sent by us
+            frame @ (RelayFrame::PresenceSubscribe { .. }
+            | RelayFrame::PresenceSettings { .. }
+            | RelayFrame::ChannelSubscribe { .. }) => Self::Unknown(frame.to_json()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// Parse a wire frame and serialize it back
+    fn round_trip(frame: Value) -> Value {
+        let parsed: RelayFrame = serde_json::from_value(frame).unwrap();
+        serde_json::from_str(&parsed.to_json()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let frames = [
+            json!({ "type": "welcome", "publicKey": "ab".repeat(32) }),
+            json!({ "type": "connection_status", "data": { "mobile": true, "browsers": 2 } }),
+            json!({
+                "type": "message_sent_from_browser",
+                "messageId": "m1",
+                "to_pk": "cd".repeat(32),
+                "plaintext": "hi",
+                "timestamp": 1_700_000_000_000i64,
+            }),
+            json!({
+                "type": "message_synced",
+                "to": ["ab".repeat(32)],
+                "messageId": "m1",
+                "conversationWith": "cd".repeat(32),
+                "decryptedText": "hi",
+                "direction": "outgoing",
+                "timestamp": 1_700_000_000_000i64,
+                "fromHandle": "alice",
+                "payload": { "subject": "Hello" },
+            }),
+            json!({ "type": "read_receipt", "messageId": "m1", "timestamp": 1 }),
+            json!({ "type": "ack", "id": "m1", "status": "rejected", "error": "Payload too large" }),
+            json!({ "type": "request_sync", "conversationWith": "cd".repeat(32), "limit": 20 }),
+            json!({
+                "type": "request_decryption",
+                "messageIds": ["m1", "m2"],
+                "conversationWith": "cd".repeat(32),
+                "requester": "ef".repeat(32),
+            }),
+            json!({ "type": "presence", "publicKey": "cd".repeat(32), "online": false, "lastSeen": 1_700_000_000_000i64 }),
+            json!({ "type": "presence_subscribe", "publicKeys": ["cd".repeat(32)] }),
+            json!({ "type": "presence_settings", "share": false }),
+            json!({ "type": "channel_subscribe", "channelIds": ["0f".repeat(16)] }),
+            json!({
+                "type": "channel_post",
+                "post": {
+                    "id": "p1",
+                    "channelId": "0f".repeat(16),
+                    "publisher": "ab".repeat(32),
+                    "payloadType": "text/plain",
+                    "payload": { "text": "hello" },
+                    "timestamp": 1_700_000_000_000i64,
+                    "signature": "22".repeat(64),
+                },
+            }),
+            json!({
+                "type": "message",
+                "id": "m1",
+                "envelope": {
+                    "id": "m1",
+                    "fromPublicKey": "ab".repeat(32),
+                    "toPublicKeys": ["cd".repeat(32)],
+                    "payloadType": "text/plain",
+                    "timestamp": 1_700_000_000_000i64,
+                    "encryptedPayload": {
+                        "ephemeralPublicKey": "ef".repeat(32),
+                        "nonce": "00".repeat(12),
+                        "ciphertext": "11".repeat(40),
+                    },
+                    "signature": "22".repeat(64),
+                },
+            }),
+        ];
+        for frame in frames {
+            assert_eq!(round_trip(frame.clone()), frame);
+        }
+    }
+
+    #[test]
+    fn test_lenient_fields() {
+        // Missing fields fall back the way the relay expects
+        let frame: RelayFrame = serde_json::from_value(json!({ "type": "request_sync" })).unwrap();
+        assert!(matches!(frame, RelayFrame::RequestSync { limit: 50, .. }));
+
+        let frame: RelayFrame = serde_json::from_value(json!({ "type": "message_synced", "messageId": "m1" })).unwrap();
+        let IncomingMessage::MessageSynced { direction, .. } = frame.into() else {
+            panic!("expected a synced message");
+        };
+        assert_eq!(direction, "incoming");
+
+        // Older relays ack by messageId
+        let frame: RelayFrame = serde_json::from_value(json!({ "type": "ack", "messageId": "m1", "status": "delivered" })).unwrap();
+        assert!(matches!(
+            frame.into(),
+            IncomingMessage::Ack { message_id, status: AckStatus::Delivered } if message_id == "m1"
+        ));
+
+        assert!(serde_json::from_value::<RelayFrame>(json!({ "type": "bogus" })).is_err());
+    }
+
+    #[test]
+    fn test_outgoing_frames() {
+        let frame = RelayFrame::RequestDecryption {
+            message_ids: vec!["m1".to_string()],
+            conversation_with: "cd".to_string(),
+            requester_pk: String::new(),
+        };
+        assert_eq!(
+            serde_json::from_str::<Value>(&frame.to_json()).unwrap(),
+            json!({ "type": "request_decryption", "messageIds": ["m1"], "conversationWith": "cd" })
+        );
+    }
+}
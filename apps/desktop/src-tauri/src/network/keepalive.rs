@@ -0,0 +1,183 @@
+//! Relay Keepalive - Keeps the WebSocket up across app lifecycle changes
+//!
+//! Mobile OSes kill sockets when the app is backgrounded. The keepalive
+//! task watches the relay and reconnects when it drops, checking often in
+//! the foreground and rarely in the background. After every reconnect it
+//! drains messages queued on the server while we were offline, so nothing
+//! waits for the next push or app launch.
+//!
+//! iOS gives no long-lived background execution. The app schedules a
+//! `com.gcrumbs.browser.relay-sync` refresh task when backgrounded, and
+//! its handler calls `native::gns_run_background_fetch`, which drains
+//! pending messages once and returns. Android has no foreground service
+//! yet, as the tree has no Android project; there the relay only lives as
+//! long as the OS keeps the process.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, Mutex, Notify};
+
+use super::{ApiClient, IncomingMessage, RelayConnection};
+use crate::crypto::IdentityManager;
+use crate::storage::DatabaseHandle;
+
+/// Time between connection checks while the app is visible
+const FOREGROUND_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Time between connection checks while backgrounded
+const BACKGROUND_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// First retry delay after a failed reconnect, doubled per failure
+const RETRY_BASE: Duration = Duration::from_secs(2);
+
+/// Delay before the next connection check
+pub fn check_interval(foreground: bool, failures: u32) -> Duration {
+    let interval = if foreground { FOREGROUND_INTERVAL } else { BACKGROUND_INTERVAL };
+    if failures == 0 {
+        return interval;
+    }
+    RETRY_BASE
+        .saturating_mul(1 << (failures - 1).min(16))
+        .min(interval)
+}
+
+/// Background relay reconnection task
+pub struct RelayKeepalive {
+    foreground: AtomicBool,
+    wake: Notify,
+}
+
+impl RelayKeepalive {
+    pub fn new() -> Self {
+        Self {
+            foreground: AtomicBool::new(true),
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn is_foreground(&self) -> bool {
+        self.foreground.load(Ordering::Relaxed)
+    }
+
+    /// Record an app lifecycle change; returning to the foreground checks
+    /// the connection immediately
+    pub fn set_foreground(&self, foreground: bool) {
+        let was_foreground = self.foreground.swap(foreground, Ordering::Relaxed);
+        if foreground && !was_foreground {
+            self.wake.notify_one();
+        }
+    }
+
+    /// Ask the task to check the connection now
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Start the keepalive task
+    ///
+    /// `relay` must be the instance carrying the message handler's channel,
+    /// and `incoming_tx` that same channel, for drained messages.
+    pub fn start(
+        self: Arc<Self>,
+        relay: RelayConnection,
+        identity: Arc<Mutex<IdentityManager>>,
+        api: Arc<ApiClient>,
+        database: DatabaseHandle,
+        incoming_tx: mpsc::Sender<IncomingMessage>,
+    ) {
+        tauri::async_runtime::spawn(async move {
+            tracing::info!("Relay keepalive started");
+            let mut failures = 0u32;
+
+            loop {
+                // Read the key each time; unlocking a different identity
+                // disconnects the relay so it comes back as that one
+                let public_key = identity.lock().await.public_key_hex();
+                if let (Some(public_key), false) = (public_key, relay.is_connected().await) {
+                    match relay.connect(&public_key).await {
+                        Ok(()) => {
+                            tracing::info!("Connected to WebSocket relay");
+                            failures = 0;
+                            match drain_pending(&public_key, &api, &database, &incoming_tx).await {
+                                Ok(0) => {}
+                                Ok(count) => tracing::info!("Drained {} pending messages", count),
+                                Err(e) => tracing::warn!("Failed to drain pending messages: {}", e),
+                            }
+                        }
+                        Err(e) => {
+                            failures += 1;
+                            tracing::warn!("Relay reconnect failed (attempt {}): {}", failures, e);
+                        }
+                    }
+                }
+
+                let delay = check_interval(self.is_foreground(), failures);
+                let _ = tokio::time::timeout(delay, self.wake.notified()).await;
+            }
+        });
+    }
+}
+
+impl Default for RelayKeepalive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hand messages queued on the server to the message handler, skipping
+/// ones we already have. Returns how many were queued.
+pub async fn drain_pending(
+    public_key: &str,
+    api: &ApiClient,
+    database: &DatabaseHandle,
+    incoming_tx: &mpsc::Sender<IncomingMessage>,
+) -> Result<u32, String> {
+    let envelopes = api
+        .fetch_pending_messages(public_key)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let envelopes: Vec<_> = database
+        .call(move |db| {
+            let mut unseen = Vec::new();
+            for envelope in envelopes {
+                if db.get_message(&envelope.id)?.is_none() {
+                    unseen.push(envelope);
+                }
+            }
+            Ok::<_, crate::storage::DatabaseError>(unseen)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut queued = 0;
+    for envelope in envelopes {
+        incoming_tx
+            .send(IncomingMessage::Envelope(Box::new(envelope)))
+            .await
+            .map_err(|e| e.to_string())?;
+        crate::metrics::METRICS.message_queued();
+        queued += 1;
+    }
+    crate::metrics::METRICS.pending_drained();
+    Ok(queued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_interval() {
+        assert_eq!(check_interval(true, 0), FOREGROUND_INTERVAL);
+        assert_eq!(check_interval(false, 0), BACKGROUND_INTERVAL);
+
+        // Retries back off but never wait longer than a regular check
+        assert_eq!(check_interval(false, 1), RETRY_BASE);
+        assert_eq!(check_interval(false, 3), RETRY_BASE * 4);
+        assert_eq!(check_interval(true, 10), FOREGROUND_INTERVAL);
+        assert_eq!(check_interval(false, 40), BACKGROUND_INTERVAL);
+    }
+}
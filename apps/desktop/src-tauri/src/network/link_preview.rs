@@ -0,0 +1,291 @@
+//! Link Previews - Title and image for links in outgoing messages
+//!
+//! When the user has previews turned on, the first link in an outgoing
+//! chat message is fetched here and the result embedded in the payload as
+//! `link_preview`, so the recipient sees it without fetching anything.
+//! Incoming messages are never fetched for: that would tell the linked
+//! site that (and when) we read the message.
+//!
+//! The fetcher is kept apart from the API client: its own HTTP client with
+//! no cookies, https only, a short timeout, a few redirects at most and
+//! never to private IP addresses or local host names, and only the first
+//! `MAX_PREVIEW_BYTES` of an HTML response are read.
+
+use std::net::IpAddr;
+use std::sync::{LazyLock, OnceLock};
+use std::time::Duration;
+
+use regex::Regex;
+use reqwest::{redirect, Client, Url};
+use serde::{Deserialize, Serialize};
+
+use super::NetworkError;
+
+/// Payload field holding the preview
+pub const LINK_PREVIEW_FIELD: &str = "link_preview";
+
+/// How much of a page is read looking for its metadata
+const MAX_PREVIEW_BYTES: usize = 256 * 1024;
+
+/// Give up on slow sites rather than hold up the send
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MAX_REDIRECTS: usize = 3;
+
+const MAX_TITLE_CHARS: usize = 200;
+const MAX_DESCRIPTION_CHARS: usize = 300;
+
+/// What the recipient sees for a link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkPreview {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+}
+
+static URL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https://[^\s<>"']+"#).unwrap());
+
+static META_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<meta\s[^>]*>").unwrap());
+
+static ATTR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)([a-z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+static TITLE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+
+/// First https link in a message, without trailing punctuation
+pub fn first_url(text: &str) -> Option<String> {
+    URL_REGEX.find_iter(text).find_map(|m| {
+        let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']']);
+        Url::parse(url).ok().filter(is_public).map(|_| url.to_string())
+    })
+}
+
+/// Fetch a preview for `url`; None if the page isn't HTML or has nothing to show
+pub async fn fetch_preview(url: &str) -> Result<Option<LinkPreview>, NetworkError> {
+    let parsed = Url::parse(url).map_err(|e| NetworkError::RequestError(e.to_string()))?;
+    if !is_public(&parsed) {
+        return Err(NetworkError::RequestError("Refusing to preview a private address".to_string()));
+    }
+
+    let mut response = client()?
+        .get(parsed.clone())
+        .header("accept", "text/html")
+        .send()
+        .await
+        .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    let is_html = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.to_ascii_lowercase().contains("text/html"));
+    if !is_html {
+        return Ok(None);
+    }
+
+    let final_url = response.url().clone();
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| NetworkError::RequestError(e.to_string()))?
+    {
+        body.extend_from_slice(&chunk);
+        if body.len() >= MAX_PREVIEW_BYTES {
+            body.truncate(MAX_PREVIEW_BYTES);
+            break;
+        }
+    }
+
+    Ok(parse_preview(&String::from_utf8_lossy(&body), &final_url))
+}
+
+/// The sandboxed client, built on first use
+fn client() -> Result<&'static Client, NetworkError> {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+
+    let policy = redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS || !is_public(attempt.url()) {
+            attempt.stop()
+        } else {
+            attempt.follow()
+        }
+    });
+    let client = Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(policy)
+        .https_only(true)
+        .user_agent("GNS-LinkPreview/1.0")
+        .build()
+        .map_err(|e| NetworkError::ClientError(e.to_string()))?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+/// https on a public host: no IP literals in private ranges, no local names
+fn is_public(url: &Url) -> bool {
+    if url.scheme() != "https" {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return is_public_ip(ip);
+    }
+
+    let domain = host.trim_end_matches('.').to_ascii_lowercase();
+    domain.contains('.')
+        && ![".local", ".internal", ".lan", ".home.arpa", ".localhost"]
+            .iter()
+            .any(|suffix| domain.ends_with(suffix))
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.octets()[0] == 100 && (64..128).contains(&ip.octets()[1]))
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback() || ip.is_unspecified() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80)
+                && ip.to_ipv4_mapped().is_none_or(|v4| is_public_ip(IpAddr::V4(v4)))
+        }
+    }
+}
+
+/// Pull OpenGraph metadata (or the `<title>`) out of a page
+fn parse_preview(html: &str, page_url: &Url) -> Option<LinkPreview> {
+    let mut title = None;
+    let mut description = None;
+    let mut image = None;
+    let mut site_name = None;
+
+    for tag in META_REGEX.find_iter(html) {
+        let mut key = None;
+        let mut content = None;
+        for attr in ATTR_REGEX.captures_iter(tag.as_str()) {
+            let value = attr.get(2).or_else(|| attr.get(3)).map(|v| v.as_str());
+            match attr[1].to_ascii_lowercase().as_str() {
+                "property" | "name" => key = value.map(|v| v.to_ascii_lowercase()),
+                "content" => content = value.map(decode_entities),
+                _ => {}
+            }
+        }
+        let (Some(key), Some(content)) = (key, content) else {
+            continue;
+        };
+        let slot = match key.as_str() {
+            "og:title" => &mut title,
+            "og:description" | "description" => &mut description,
+            "og:image" | "og:image:url" | "og:image:secure_url" => &mut image,
+            "og:site_name" => &mut site_name,
+            _ => continue,
+        };
+        if slot.is_none() && !content.trim().is_empty() {
+            *slot = Some(content.trim().to_string());
+        }
+    }
+
+    if title.is_none() {
+        title = TITLE_REGEX
+            .captures(html)
+            .map(|c| decode_entities(c[1].trim()))
+            .filter(|t| !t.is_empty());
+    }
+    let image_url = image
+        .and_then(|src| page_url.join(&src).ok())
+        .filter(|url| url.scheme() == "https")
+        .map(|url| url.to_string());
+
+    if title.is_none() && description.is_none() && image_url.is_none() {
+        return None;
+    }
+    Some(LinkPreview {
+        url: page_url.to_string(),
+        title: title.map(|t| truncate(&t, MAX_TITLE_CHARS)),
+        description: description.map(|d| truncate(&d, MAX_DESCRIPTION_CHARS)),
+        image_url,
+        site_name: site_name.map(|s| truncate(&s, MAX_TITLE_CHARS)),
+    })
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_url() {
+        assert_eq!(
+            first_url("see https://example.org/a?b=1. thanks").as_deref(),
+            Some("https://example.org/a?b=1")
+        );
+        assert_eq!(first_url("(https://example.org/x)").as_deref(), Some("https://example.org/x"));
+        assert_eq!(first_url("http://example.org plain http"), None);
+        assert_eq!(first_url("https://192.168.1.1/admin https://localhost/x"), None);
+    }
+
+    #[test]
+    fn test_private_hosts_are_refused() {
+        let public = |s: &str| is_public(&Url::parse(s).unwrap());
+        assert!(public("https://example.org"));
+        assert!(public("https://8.8.8.8/"));
+        assert!(!public("https://10.0.0.1/"));
+        assert!(!public("https://[::1]/"));
+        assert!(!public("https://[::ffff:127.0.0.1]/"));
+        assert!(!public("https://printer.local/"));
+        assert!(!public("https://intranet/"));
+    }
+
+    #[test]
+    fn test_parse_open_graph() {
+        let html = r#"<html><head>
+            <title>Fallback</title>
+            <meta property="og:title" content="Rust &amp; GNS">
+            <meta content='A short description' name="description">
+            <meta property="og:image" content="/img/cover.png" />
+        </head><body>...</body></html>"#;
+        let url = Url::parse("https://example.org/post").unwrap();
+        let preview = parse_preview(html, &url).unwrap();
+
+        assert_eq!(preview.title.as_deref(), Some("Rust & GNS"));
+        assert_eq!(preview.description.as_deref(), Some("A short description"));
+        assert_eq!(preview.image_url.as_deref(), Some("https://example.org/img/cover.png"));
+        assert!(parse_preview("<html><body>nothing</body></html>", &url).is_none());
+        assert_eq!(parse_preview("<title> Plain </title>", &url).unwrap().title.as_deref(), Some("Plain"));
+    }
+}
@@ -4,20 +4,40 @@
 //! 
 //! Updated: Added handle reservation, claiming, and record publishing
 
+pub mod ack;
+pub mod codec;
+pub mod federation;
+pub mod frame;
+pub mod keepalive;
+pub mod link_preview;
+pub mod outbox;
+
 use gns_crypto_core::{Breadcrumb, GnsEnvelope};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, Notify, RwLock};
 use futures_util::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, SEC_WEBSOCKET_PROTOCOL};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
+use ack::{AckStatus, PendingAck, PendingAcks};
+use codec::FrameEncoding;
+use frame::RelayFrame;
+use outbox::{Outbox, QueuedFrame, MAX_FRAME_RETRIES, MAX_QUEUED_FRAMES};
+use crate::channels::{ChannelInfo, ChannelPost};
+use crate::storage::DatabaseHandle;
+use crate::verifications::{verified_proofs, ProofStatement, VerificationStatus, VerifiedProof};
+
 // ==================== API Client ====================
 
 pub struct ApiClient {
     client: Client,
     base_url: String,
+    /// DNS-over-HTTPS resolver for federated handles, if the user set one
+    doh_url: std::sync::RwLock<Option<String>>,
 }
 
 impl ApiClient {
@@ -30,6 +50,7 @@ impl ApiClient {
         Ok(Self {
             client,
             base_url: base_url.to_string(),
+            doh_url: std::sync::RwLock::new(None),
         })
     }
 
@@ -43,7 +64,12 @@ impl ApiClient {
 
     // ==================== Identity/Handle Resolution ====================
 
+    /// Resolve a GNS handle, or a federated `name@domain` through its domain
     pub async fn resolve_handle(&self, handle: &str) -> Result<Option<IdentityInfo>, NetworkError> {
+        if let Some(federated) = federation::FederatedHandle::parse(handle) {
+            return self.resolve_federated(&federated).await;
+        }
+
         let clean_handle = handle.trim_start_matches('@').to_lowercase();
         let url = format!("{}/handles/{}", self.base_url, clean_handle);
 
@@ -64,10 +90,13 @@ impl ApiClient {
         Ok(Some(IdentityInfo {
             public_key: data["data"]["public_key"].as_str().unwrap_or_default().to_string(),
             encryption_key: data["data"]["encryption_key"].as_str().unwrap_or_default().to_string(),
+            pq_encryption_key: data["data"]["pq_encryption_key"].as_str().map(|s| s.to_string()),
             handle: data["data"]["handle"].as_str().map(|s| s.to_string()),
             avatar_url: data["data"]["avatar_url"].as_str().map(|s| s.to_string()),
             display_name: data["data"]["display_name"].as_str().map(|s| s.to_string()),
             is_verified: data["data"]["is_verified"].as_bool().unwrap_or(false),
+            verifications: verified_proofs(&data["data"]),
+            modules: crate::capabilities::module_ids(&data["data"]["modules"]),
         }))
     }
 
@@ -111,10 +140,13 @@ impl ApiClient {
         Ok(Some(IdentityInfo {
             public_key: data["data"]["public_key"].as_str().unwrap_or(public_key).to_string(),
             encryption_key: data["data"]["encryption_key"].as_str().unwrap_or_default().to_string(),
+            pq_encryption_key: data["data"]["pq_encryption_key"].as_str().map(|s| s.to_string()),
             handle: data["data"]["handle"].as_str().map(|s| s.to_string()),
             avatar_url: data["data"]["avatar_url"].as_str().map(|s| s.to_string()),
             display_name: data["data"]["display_name"].as_str().map(|s| s.to_string()),
             is_verified: data["data"]["is_verified"].as_bool().unwrap_or(false),
+            verifications: verified_proofs(&data["data"]),
+            modules: crate::capabilities::module_ids(&data["data"]["modules"]),
         }))
     }
 
@@ -216,6 +248,27 @@ impl ApiClient {
 
     // ==================== Handle Claiming ====================
 
+    /// Body of the claim request, as `claim_handle_with_proof` sends it
+    pub fn claim_request_body(
+        handle: &str,
+        public_key: &str,
+        proof: &ClaimProof,
+        signature: &str,
+        claimed_at: &str,
+    ) -> serde_json::Value {
+        json!({
+            "handle": handle.trim_start_matches('@').to_lowercase(),
+            "identity": public_key,
+            "proof": {
+                "breadcrumb_count": proof.breadcrumb_count,
+                "first_breadcrumb_at": proof.first_breadcrumb_at,
+                "trust_score": proof.trust_score,
+            },
+            "claimed_at": claimed_at,
+            "signature": signature,
+        })
+    }
+
     /// Claim a reserved handle (after collecting 100 breadcrumbs)
     /// PUT /aliases/{handle}
     pub async fn claim_handle_with_proof(
@@ -230,17 +283,7 @@ impl ApiClient {
 
         tracing::info!("Claiming handle @{} with {} breadcrumbs", clean_handle, proof.breadcrumb_count);
 
-        let request_body = json!({
-            "handle": clean_handle,
-            "identity": public_key,
-            "proof": {
-                "breadcrumb_count": proof.breadcrumb_count,
-                "first_breadcrumb_at": proof.first_breadcrumb_at,
-                "trust_score": proof.trust_score,
-            },
-            "claimed_at": chrono::Utc::now().to_rfc3339(),
-            "signature": signature,
-        });
+        let request_body = Self::claim_request_body(handle, public_key, proof, signature, &chrono::Utc::now().to_rfc3339());
 
         let response = self.client.put(&url)
             .json(&request_body)
@@ -276,6 +319,65 @@ impl ApiClient {
         }
     }
 
+    // ==================== Handle Release/Transfer ====================
+
+    /// Give up a claimed handle
+    /// POST /aliases/{handle}/release
+    pub async fn release_handle(
+        &self,
+        handle: &str,
+        statement: &serde_json::Value,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        let clean_handle = handle.trim_start_matches('@').to_lowercase();
+        let url = format!("{}/aliases/{}/release", self.base_url, clean_handle);
+        self.post_handle_statement(&url, statement, signature).await
+    }
+
+    /// Hand a claimed handle to another identity
+    /// POST /aliases/{handle}/transfer
+    pub async fn transfer_handle(
+        &self,
+        handle: &str,
+        statement: &serde_json::Value,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        let clean_handle = handle.trim_start_matches('@').to_lowercase();
+        let url = format!("{}/aliases/{}/transfer", self.base_url, clean_handle);
+        self.post_handle_statement(&url, statement, signature).await
+    }
+
+    async fn post_handle_statement(
+        &self,
+        url: &str,
+        statement: &serde_json::Value,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        let request_body = json!({
+            "statement": statement,
+            "signature": signature,
+        });
+
+        let response = self.client.post(url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        let status = response.status();
+        let data: serde_json::Value = response.json().await.unwrap_or_default();
+
+        if status.is_success() && data["success"].as_bool().unwrap_or(true) {
+            Ok(())
+        } else {
+            let error_msg = data["error"].as_str()
+                .or_else(|| data["message"].as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("API returned status: {}", status));
+            Err(NetworkError::ApiError(error_msg))
+        }
+    }
+
     /// Legacy claim_handle (kept for compatibility)
     pub async fn claim_handle(
         &self,
@@ -405,6 +507,213 @@ impl ApiClient {
         }
     }
 
+    /// Fetch the signed record currently published for an identity
+    /// GET /records/{public_key}
+    pub async fn get_record(&self, public_key: &str) -> Result<Option<PublishedRecord>, NetworkError> {
+        let url = format!("{}/records/{}", self.base_url, public_key);
+
+        let response = self.client.get(&url).send().await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("API returned status: {}", response.status())));
+        }
+
+        let data: serde_json::Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        let data = data.get("data").unwrap_or(&data);
+        Ok(Some(PublishedRecord {
+            record_json: data.get("record_json").unwrap_or(data).clone(),
+            signature: data["signature"].as_str().map(|s| s.to_string()),
+        }))
+    }
+
+    // ==================== Channels ====================
+
+    /// Register a channel's signed descriptor
+    /// POST /channels
+    pub async fn create_channel(&self, info: &ChannelInfo) -> Result<(), NetworkError> {
+        let url = format!("{}/channels", self.base_url);
+
+        let response = self.client.post(&url).json(info).send().await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(NetworkError::ApiError(format!("Failed to create channel: {}", error_text)));
+        }
+        Ok(())
+    }
+
+    /// A channel's signed descriptor
+    /// GET /channels/{id}
+    pub async fn get_channel(&self, channel_id: &str) -> Result<Option<ChannelInfo>, NetworkError> {
+        let url = format!("{}/channels/{}", self.base_url, channel_id);
+
+        let response = self.client.get(&url).send().await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("API returned status: {}", response.status())));
+        }
+
+        let data: serde_json::Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        serde_json::from_value(data.get("data").unwrap_or(&data).clone())
+            .map(Some)
+            .map_err(|e| NetworkError::ParseError(e.to_string()))
+    }
+
+    /// Publish a signed post; the relay streams it to subscribers
+    /// POST /channels/{id}/posts
+    pub async fn publish_channel_post(&self, post: &ChannelPost) -> Result<(), NetworkError> {
+        let url = format!("{}/channels/{}/posts", self.base_url, post.channel_id);
+
+        let response = self.client.post(&url).json(post).send().await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(NetworkError::ApiError(format!("Failed to publish post: {}", error_text)));
+        }
+        Ok(())
+    }
+
+    /// Posts in a channel after `since` (ms), oldest first
+    /// GET /channels/{id}/posts?since=&limit=
+    pub async fn fetch_channel_posts(
+        &self,
+        channel_id: &str,
+        since: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<ChannelPost>, NetworkError> {
+        let url = format!("{}/channels/{}/posts", self.base_url, channel_id);
+
+        let response = self.client.get(&url)
+            .query(&[("since", since.unwrap_or(0)), ("limit", i64::from(limit))])
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("API returned status: {}", response.status())));
+        }
+
+        let data: serde_json::Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        serde_json::from_value(data.get("data").unwrap_or(&data)["posts"].clone())
+            .map_err(|e| NetworkError::ParseError(e.to_string()))
+    }
+
+    // ==================== Contact Discovery ====================
+
+    /// Which identifier hashes belong to GNS identities
+    /// POST /contacts/discover
+    pub async fn discover_contacts(&self, hashes: &[String]) -> Result<Vec<DiscoveredContact>, NetworkError> {
+        let url = format!("{}/contacts/discover", self.base_url);
+
+        let response = self.client.post(&url)
+            .json(&json!({ "hashes": hashes }))
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("API returned status: {}", response.status())));
+        }
+
+        let data: serde_json::Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        serde_json::from_value(data.get("data").unwrap_or(&data)["matches"].clone())
+            .map_err(|e| NetworkError::ParseError(e.to_string()))
+    }
+
+    // ==================== Reports ====================
+
+    /// Submit a signed abuse report
+    /// POST /reports
+    pub async fn submit_report(
+        &self,
+        report_json: &serde_json::Value,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        let url = format!("{}/reports", self.base_url);
+
+        let request_body = json!({
+            "report": report_json,
+            "signature": signature,
+        });
+
+        let response = self.client.post(&url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            tracing::warn!("Failed to submit report: {}", error_text);
+            Err(NetworkError::ApiError(error_text))
+        }
+    }
+
+    // ==================== Verifications ====================
+
+    /// Submit a signed external proof for the backend to check
+    /// POST /verifications
+    pub async fn submit_verification(
+        &self,
+        statement: &ProofStatement,
+        signature: &str,
+        proof_url: &str,
+    ) -> Result<(VerificationStatus, Option<String>), NetworkError> {
+        let url = format!("{}/verifications", self.base_url);
+
+        let request_body = json!({
+            "statement": statement,
+            "statement_text": statement.to_text(),
+            "signature": signature,
+            "proof_url": proof_url,
+        });
+
+        let response = self.client.post(&url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        let status = response.status();
+        let data: serde_json::Value = response.json().await.unwrap_or_default();
+
+        if !status.is_success() {
+            let error_msg = data["error"].as_str()
+                .or_else(|| data["message"].as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("API returned status: {}", status));
+            return Err(NetworkError::ApiError(error_msg));
+        }
+
+        // The backend may check asynchronously and answer "pending"
+        let verification_status = data["data"]["status"].as_str()
+            .and_then(VerificationStatus::parse)
+            .unwrap_or(VerificationStatus::Pending);
+        let error = data["data"]["error"].as_str().map(|s| s.to_string());
+
+        Ok((verification_status, error))
+    }
+
     // ==================== Breadcrumb Sync ====================
 
     /// Upload breadcrumb to server
@@ -438,6 +747,35 @@ impl ApiClient {
         }
     }
 
+    /// Upload a gzip-compressed batch of breadcrumbs
+    /// POST /breadcrumbs/batch
+    pub async fn upload_breadcrumb_batch(
+        &self,
+        pk_root: &str,
+        gzipped_body: Vec<u8>,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        let url = format!("{}/breadcrumbs/batch", self.base_url);
+
+        let response = self.client.post(&url)
+            .header("Content-Type", "application/json")
+            .header("Content-Encoding", "gzip")
+            .header("X-GNS-PublicKey", pk_root)
+            .header("X-GNS-Signature", signature)
+            .body(gzipped_body)
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            tracing::warn!("Failed to upload breadcrumb batch: {}", error_text);
+            Err(NetworkError::ApiError(error_text))
+        }
+    }
+
     /// Fetch encrypted breadcrumbs from server
     /// GET /breadcrumbs/{pk}
     pub async fn fetch_breadcrumbs(&self, pk_root: &str) -> Result<Vec<serde_json::Value>, NetworkError> {
@@ -460,6 +798,66 @@ impl ApiClient {
         Ok(breadcrumbs)
     }
 
+    // ==================== Media ====================
+
+    /// Upload an image for a DIX post, returns its public URL
+    pub async fn upload_media(
+        &self,
+        bytes: Vec<u8>,
+        mime_type: &str,
+        content_hash: &str,
+        public_key: &str,
+        signature: &str,
+    ) -> Result<String, NetworkError> {
+        let url = format!("{}/web/dix/media", self.base_url);
+
+        let response = self.client.post(&url)
+            .header("Content-Type", mime_type)
+            .header("X-GNS-PublicKey", public_key)
+            .header("X-GNS-Content-Hash", content_hash)
+            .header("X-GNS-Signature", signature)
+            .body(bytes)
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(NetworkError::ApiError(format!("Media upload failed: {}", error_text)));
+        }
+
+        let data: serde_json::Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        data["data"]["url"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| NetworkError::ParseError("No media URL in response".to_string()))
+    }
+
+    /// Download media bytes (capped at `max_bytes`)
+    pub async fn fetch_media(&self, url: &str, max_bytes: usize) -> Result<Vec<u8>, NetworkError> {
+        let response = self.client.get(url)
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("HTTP {}", response.status())));
+        }
+        if response.content_length().unwrap_or(0) as usize > max_bytes {
+            return Err(NetworkError::ApiError("Media too large".to_string()));
+        }
+
+        let bytes = response.bytes().await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+        if bytes.len() > max_bytes {
+            return Err(NetworkError::ApiError("Media too large".to_string()));
+        }
+
+        Ok(bytes.to_vec())
+    }
+
     // ==================== Messaging ====================
 
     pub async fn send_envelope(&self, envelope: &GnsEnvelope) -> Result<(), NetworkError> {
@@ -493,6 +891,78 @@ impl ApiClient {
 
         Ok(envelopes)
     }
+
+    /// Fetch a single envelope by ID (e.g. one named in a push)
+    /// GET /messages/:id
+    pub async fn fetch_envelope(&self, envelope_id: &str) -> Result<Option<GnsEnvelope>, NetworkError> {
+        let url = format!("{}/messages/{}", self.base_url, envelope_id);
+
+        let response = self.client.get(&url).send().await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(NetworkError::ApiError(format!("API returned status: {}", response.status())));
+        }
+
+        let data: serde_json::Value = response.json().await
+            .map_err(|e| NetworkError::ParseError(e.to_string()))?;
+
+        let envelope = data.get("data").unwrap_or(&data);
+        serde_json::from_value(envelope.get("envelope").unwrap_or(envelope).clone())
+            .map(Some)
+            .map_err(|e| NetworkError::ParseError(e.to_string()))
+    }
+
+    // ==================== Push ====================
+
+    /// Register a device push token so the backend can wake us for new mail
+    /// POST /push/tokens
+    pub async fn register_push_token(
+        &self,
+        registration_json: &serde_json::Value,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        self.post_push(&format!("{}/push/tokens", self.base_url), registration_json, signature).await
+    }
+
+    /// Remove a device push token
+    /// POST /push/tokens/unregister
+    pub async fn unregister_push_token(
+        &self,
+        registration_json: &serde_json::Value,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        self.post_push(&format!("{}/push/tokens/unregister", self.base_url), registration_json, signature).await
+    }
+
+    async fn post_push(
+        &self,
+        url: &str,
+        registration_json: &serde_json::Value,
+        signature: &str,
+    ) -> Result<(), NetworkError> {
+        let request_body = json!({
+            "registration": registration_json,
+            "signature": signature,
+        });
+
+        let response = self.client.post(url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| NetworkError::RequestError(e.to_string()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            tracing::warn!("Push token request failed: {}", error_text);
+            Err(NetworkError::ApiError(error_text))
+        }
+    }
 }
 
 // ==================== WebSocket Relay ====================
@@ -509,7 +979,7 @@ pub enum ConnectionState {
 #[derive(Debug, Clone)]
 pub enum IncomingMessage {
     /// An encrypted envelope
-    Envelope(GnsEnvelope),
+    Envelope(Box<GnsEnvelope>),
     /// Connection status update
     ConnectionStatus { mobile: bool, browsers: u32 },
     /// Welcome message
@@ -530,6 +1000,11 @@ pub enum IncomingMessage {
         timestamp: i64,
         from_handle: Option<String>,
     },
+    /// Relay acknowledgement for an envelope we sent
+    Ack {
+        message_id: String,
+        status: AckStatus,
+    },
     /// Read receipt
     ReadReceipt {
         message_id: String,
@@ -545,6 +1020,14 @@ pub enum IncomingMessage {
         conversation_with: String,
         requester_pk: String,
     },
+    /// A contact's presence changed
+    Presence {
+        public_key: String,
+        online: bool,
+        last_seen: Option<i64>,
+    },
+    /// A post in a channel we follow
+    ChannelPost(Box<ChannelPost>),
     /// Unknown message type
     Unknown(String),
 }
@@ -553,8 +1036,18 @@ pub struct RelayConnection {
     url: String,
     state: Arc<RwLock<ConnectionState>>,
     last_message_time: Arc<RwLock<Option<i64>>>,
+    connected_since: Arc<RwLock<Option<i64>>>,
     reconnect_attempts: Arc<RwLock<u32>>,
-    sender: Arc<RwLock<Option<mpsc::Sender<String>>>>,
+    /// Frame encoding negotiated for the current connection
+    encoding: Arc<RwLock<FrameEncoding>>,
+    /// Stops the current connection's socket writer
+    stop_writer: Arc<RwLock<Option<Arc<Notify>>>>,
+    /// Ordered outgoing frames, kept across reconnects
+    outbox: Outbox,
+    /// Where unacked envelopes are persisted
+    store: Option<DatabaseHandle>,
+    /// Sends waiting for the relay's ack
+    acks: PendingAcks,
     /// Channel for incoming messages
     incoming_tx: Option<mpsc::Sender<IncomingMessage>>,
 }
@@ -575,12 +1068,23 @@ impl RelayConnection {
             url: ws_url,
             state: Arc::new(RwLock::new(ConnectionState::Disconnected)),
             last_message_time: Arc::new(RwLock::new(None)),
+            connected_since: Arc::new(RwLock::new(None)),
             reconnect_attempts: Arc::new(RwLock::new(0)),
-            sender: Arc::new(RwLock::new(None)),
+            encoding: Arc::new(RwLock::new(FrameEncoding::Json)),
+            stop_writer: Arc::new(RwLock::new(None)),
+            outbox: Outbox::default(),
+            store: None,
+            acks: PendingAcks::default(),
             incoming_tx: None,
         })
     }
 
+    /// Persist unacked envelopes in `database`
+    pub fn with_store(mut self, database: DatabaseHandle) -> Self {
+        self.store = Some(database);
+        self
+    }
+
     pub fn with_incoming_channel(mut self, tx: mpsc::Sender<IncomingMessage>) -> Self {
         self.incoming_tx = Some(tx);
         self
@@ -591,8 +1095,13 @@ impl RelayConnection {
             url: self.url.clone(),
             state: self.state.clone(),
             last_message_time: self.last_message_time.clone(),
+            connected_since: self.connected_since.clone(),
             reconnect_attempts: self.reconnect_attempts.clone(),
-            sender: self.sender.clone(),
+            encoding: self.encoding.clone(),
+            stop_writer: self.stop_writer.clone(),
+            outbox: self.outbox.clone(),
+            store: self.store.clone(),
+            acks: self.acks.clone(),
             incoming_tx: Some(tx),
         }
     }
@@ -613,6 +1122,35 @@ impl RelayConnection {
         *self.last_message_time.read().await
     }
 
+    /// Unix timestamp the current connection was opened, if connected
+    pub async fn connected_since(&self) -> Option<i64> {
+        if !self.is_connected().await {
+            return None;
+        }
+        *self.connected_since.read().await
+    }
+
+    /// Frames waiting to be written to the socket
+    pub async fn outgoing_queue_depth(&self) -> usize {
+        self.outbox.queued()
+    }
+
+    /// Frame encoding of the current (or last) connection
+    pub async fn frame_encoding(&self) -> FrameEncoding {
+        *self.encoding.read().await
+    }
+
+    /// Envelopes written to the socket that the relay hasn't acked
+    pub fn unacked_count(&self) -> usize {
+        self.outbox.unacked()
+    }
+
+    /// Forget every queued frame, e.g. when the identity changes
+    pub fn clear_outbox(&self) {
+        self.outbox.clear();
+        self.acks.clear();
+    }
+
     pub async fn reconnect_attempts(&self) -> u32 {
         *self.reconnect_attempts.read().await
     }
@@ -628,41 +1166,81 @@ impl RelayConnection {
 
         let url_with_auth = format!("{}?pk={}&device={}", self.url, public_key, device_type);
 
-        let (ws_stream, _) = connect_async(&url_with_auth).await.map_err(|e| {
+        // Offer MessagePack; relays that don't know it answer without a
+        // subprotocol and get JSON
+        let mut request = url_with_auth
+            .into_client_request()
+            .map_err(|e| NetworkError::ConnectionError(e.to_string()))?;
+        request
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(codec::OFFERED_PROTOCOLS));
+
+        let (ws_stream, response) = connect_async(request).await.map_err(|e| {
             tracing::error!("WebSocket connection failed: {}", e);
             NetworkError::ConnectionError(e.to_string())
         })?;
+        let encoding = FrameEncoding::negotiated(
+            response
+                .headers()
+                .get(SEC_WEBSOCKET_PROTOCOL)
+                .and_then(|v| v.to_str().ok()),
+        );
+        *self.encoding.write().await = encoding;
 
-        tracing::info!("WebSocket connected to {}", self.url);
+        tracing::info!("WebSocket connected to {} ({:?} frames)", self.url, encoding);
 
         let (mut write, mut read) = ws_stream.split();
-        let (tx, mut rx) = mpsc::channel::<String>(100);
-        *self.sender.write().await = Some(tx);
+
+        // Resend what the relay never acked, ahead of anything newer
+        let persisted = match &self.store {
+            Some(store) => store
+                .call(|db| db.take_outgoing_frames(MAX_FRAME_RETRIES))
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load unacked frames: {}", e);
+                    Vec::new()
+                }),
+            None => Vec::new(),
+        };
+        self.outbox.requeue(persisted);
+
+        let stop = Arc::new(Notify::new());
+        if let Some(previous) = self.stop_writer.write().await.replace(stop.clone()) {
+            previous.notify_one();
+        }
         *self.state.write().await = ConnectionState::Connected;
+        *self.connected_since.write().await = Some(chrono::Utc::now().timestamp());
         *self.reconnect_attempts.write().await = 0;
 
         let state = self.state.clone();
         let last_message_time = self.last_message_time.clone();
         let incoming_tx = self.incoming_tx.clone();
+        let acks = self.acks.clone();
+        let outbox = self.outbox.clone();
+        let store = self.store.clone();
+        let read_stop = stop.clone();
 
         let read_state = state.clone();
         tokio::spawn(async move {
             while let Some(msg) = read.next().await {
-                match msg {
+                let parsed = match msg {
                     Ok(Message::Text(text)) => {
-                        tracing::debug!("Received WebSocket message: {}", text);
-                        *last_message_time.write().await = Some(chrono::Utc::now().timestamp());
-                        
-                        // Parse the incoming message
-                        if let Some(ref tx) = incoming_tx {
-                            let parsed = parse_incoming_message(&text);
-                            if let Err(e) = tx.send(parsed).await {
-                                tracing::error!("Failed to send incoming message to channel: {}", e);
+                        tracing::trace!("WebSocket received {} text bytes", text.len());
+                        parse_incoming_message(&text)
+                    }
+                    Ok(Message::Binary(bytes)) => {
+                        tracing::trace!("WebSocket received {} binary bytes", bytes.len());
+                        match codec::from_msgpack(&bytes) {
+                            Some(json) => parse_incoming_json(json, None),
+                            None => {
+                                tracing::warn!("Dropped undecodable binary frame ({} bytes)", bytes.len());
+                                continue;
                             }
                         }
                     }
                     Ok(Message::Ping(_)) => {
                         tracing::trace!("Received ping");
+                        continue;
                     }
                     Ok(Message::Close(_)) => {
                         tracing::info!("WebSocket closed by server");
@@ -674,19 +1252,49 @@ impl RelayConnection {
                         *read_state.write().await = ConnectionState::Disconnected;
                         break;
                     }
-                    _ => {}
+                    _ => continue,
+                };
+                *last_message_time.write().await = Some(chrono::Utc::now().timestamp());
+
+                // First acks go straight to the waiting send
+                if let IncomingMessage::Ack { message_id, status } = &parsed {
+                    outbox.acked(message_id);
+                    if let Some(store) = &store {
+                        let id = message_id.clone();
+                        if let Err(e) = store.call(move |db| db.remove_outgoing_frame(&id)).await {
+                            tracing::warn!("Failed to clear acked frame: {}", e);
+                        }
+                    }
+                    if acks.resolve(message_id, status.clone()) {
+                        continue;
+                    }
+                }
+                if let Some(ref tx) = incoming_tx {
+                    match tx.send(parsed).await {
+                        Ok(()) => crate::metrics::METRICS.message_queued(),
+                        Err(e) => tracing::error!("Failed to send incoming message to channel: {}", e),
+                    }
                 }
             }
+            read_stop.notify_one();
         });
 
+        // The only writer for this connection, draining the outbox in order
         let write_state = state.clone();
+        let outbox = self.outbox.clone();
         tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
-                if write.send(Message::Text(msg)).await.is_err() {
+            loop {
+                let frame = tokio::select! {
+                    frame = outbox.next() => frame,
+                    _ = stop.notified() => break,
+                };
+                if write.send(encoding.encode(frame.text.clone())).await.is_err() {
                     tracing::error!("Failed to send WebSocket message");
+                    outbox.unwritten(frame);
                     *write_state.write().await = ConnectionState::Disconnected;
                     break;
                 }
+                outbox.written(frame);
             }
         });
 
@@ -696,7 +1304,9 @@ impl RelayConnection {
     pub async fn disconnect(&self) -> Result<(), NetworkError> {
         tracing::info!("Disconnecting from relay");
         *self.state.write().await = ConnectionState::Disconnected;
-        *self.sender.write().await = None;
+        if let Some(stop) = self.stop_writer.write().await.take() {
+            stop.notify_one();
+        }
         Ok(())
     }
 
@@ -712,154 +1322,106 @@ impl RelayConnection {
         self.connect(public_key).await
     }
 
-    pub async fn send_envelope(&self, envelope: &GnsEnvelope) -> Result<(), NetworkError> {
-        let sender = self.sender.read().await;
-        if let Some(tx) = sender.as_ref() {
-            // Wrap envelope in message format (matches Flutter/server expectation)
-            let wrapped = serde_json::json!({
-                "type": "message",
-                "envelope": envelope
-            });
-            let json = serde_json::to_string(&wrapped)
-                .map_err(|e| NetworkError::ParseError(e.to_string()))?;
-            
-            // Debug: log what we're sending
-            tracing::debug!("Sending WebSocket message: {}", &json[..json.len().min(500)]);
-            
-            tx.send(json).await.map_err(|_| NetworkError::NotConnected)?;
-            Ok(())
-        } else {
-            Err(NetworkError::NotConnected)
+    /// Queue an envelope for the relay, persisting it until acked; queued
+    /// envelopes are sent in order once connected. The returned ack
+    /// resolves when the relay acknowledges it.
+    pub async fn send_envelope(&self, envelope: &GnsEnvelope) -> Result<PendingAck, NetworkError> {
+        // Wrap envelope in message format (matches Flutter/server expectation);
+        // the relay acks by `id`
+        let json = RelayFrame::envelope(envelope).to_json();
+
+        // Debug: log what we're sending
+        tracing::debug!("Sending WebSocket message: {}", &json[..json.len().min(500)]);
+
+        if self.outbox.queued() >= MAX_QUEUED_FRAMES {
+            return Err(NetworkError::ConnectionError("Send queue is full".to_string()));
         }
+        // Persisted before queueing so an ack can't beat it to the database
+        if let Some(store) = &self.store {
+            let (id, frame, now) = (envelope.id.clone(), json.clone(), chrono::Utc::now().timestamp_millis());
+            if let Err(e) = store.call(move |db| db.queue_outgoing_frame(&id, &frame, now)).await {
+                tracing::warn!("Failed to persist envelope {}: {}", envelope.id, e);
+            }
+        }
+
+        let ack = self.acks.register(&envelope.id);
+        self.outbox.push(QueuedFrame::envelope(&envelope.id, json));
+        Ok(ack)
     }
 
+    /// Queue a frame behind everything already queued. Only while
+    /// connected; these frames aren't persisted.
     pub async fn send_raw(&self, message: &str) -> Result<(), NetworkError> {
-        let sender = self.sender.read().await;
-        if let Some(tx) = sender.as_ref() {
-            tx.send(message.to_string()).await.map_err(|_| NetworkError::NotConnected)?;
-            Ok(())
-        } else {
-            Err(NetworkError::NotConnected)
+        if !self.is_connected().await {
+            return Err(NetworkError::NotConnected);
+        }
+        if !self.outbox.push(QueuedFrame::raw(message.to_string())) {
+            return Err(NetworkError::ConnectionError("Send queue is full".to_string()));
         }
+        Ok(())
+    }
+
+    /// Queue a non-envelope frame, e.g. browser sync
+    pub async fn send_frame(&self, frame: &RelayFrame) -> Result<(), NetworkError> {
+        self.send_raw(&frame.to_json()).await
     }
 
     pub async fn send_decryption_request(&self, message_ids: Vec<String>, conversation_with: &str) -> Result<(), NetworkError> {
-        let payload = json!({
-            "type": "request_decryption",
-            "messageIds": message_ids,
-            "conversationWith": conversation_with
-        });
-        
-        self.send_raw(&payload.to_string()).await
+        self.send_frame(&RelayFrame::RequestDecryption {
+            message_ids,
+            conversation_with: conversation_with.to_string(),
+            requester_pk: String::new(),
+        })
+        .await
     }
 
     pub async fn send_sync_request(&self, conversation_with: &str, limit: u32) -> Result<(), NetworkError> {
-        let payload = json!({
-            "type": "request_sync",
-            "conversationWith": conversation_with,
-            "limit": limit
-        });
-        
-        self.send_raw(&payload.to_string()).await
+        self.send_frame(&RelayFrame::RequestSync {
+            conversation_with: conversation_with.to_string(),
+            limit,
+        })
+        .await
     }
 }
 
 /// Parse incoming WebSocket message into typed enum
-fn parse_incoming_message(text: &str) -> IncomingMessage {
-    // Truncate log for privacy/size
-    let log_len = std::cmp::min(text.len(), 300);
-    println!("🔥 [RUST] WebSocket received: {}", &text[..log_len]);
+///
+/// Public for the fuzz targets; never panics on malformed input.
+pub fn parse_incoming_message(text: &str) -> IncomingMessage {
+    tracing::trace!("WebSocket received {} bytes", text.len());
     
     // Try to parse as JSON
-    let json: serde_json::Value = match serde_json::from_str(text) {
-        Ok(v) => v,
-        Err(_) => return IncomingMessage::Unknown(text.to_string()),
+    match serde_json::from_str(text) {
+        Ok(json) => parse_incoming_json(json, Some(text)),
+        Err(_) => IncomingMessage::Unknown(text.to_string()),
+    }
+}
+
+/// Parse a decoded frame; `text` is the raw frame, if it was JSON text
+fn parse_incoming_json(json: serde_json::Value, text: Option<&str>) -> IncomingMessage {
+    let unknown = |json: &serde_json::Value| {
+        IncomingMessage::Unknown(text.map(String::from).unwrap_or_else(|| json.to_string()))
     };
 
-    // Check message type
-    let msg_type = json["type"].as_str().unwrap_or("");
-
-    match msg_type {
-        "welcome" => {
-            let public_key = json["publicKey"].as_str().unwrap_or_default().to_string();
-            IncomingMessage::Welcome { public_key }
-        }
-        "connection_status" => {
-            let mobile = json["data"]["mobile"].as_bool().unwrap_or(false);
-            let browsers = json["data"]["browsers"].as_u64().unwrap_or(0) as u32;
-            IncomingMessage::ConnectionStatus { mobile, browsers }
-        }
-        "message_sent_from_browser" => {
-            IncomingMessage::MessageSentFromBrowser {
-                message_id: json["messageId"].as_str().unwrap_or_default().to_string(),
-                to_pk: json["to_pk"].as_str().unwrap_or_default().to_string(),
-                plaintext: json["plaintext"].as_str().unwrap_or_default().to_string(),
-                timestamp: json["timestamp"].as_i64().unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
-            }
+    match RelayFrame::deserialize(&json) {
+        Ok(RelayFrame::Message { envelope, .. }) if envelope.validate().is_err() => {
+            tracing::warn!("Dropping malformed envelope {}", envelope.id);
+            unknown(&json)
         }
-        "request_decryption" => {
-            let ids: Vec<String> = json["messageIds"]
-                .as_array()
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default();
-                
-            IncomingMessage::RequestDecryption {
-                message_ids: ids,
-                conversation_with: json["conversationWith"].as_str().unwrap_or_default().to_string(),
-                requester_pk: json["requester"].as_str().unwrap_or_default().to_string(),
-            }
-        }
-        "message_synced" => {
-            IncomingMessage::MessageSynced {
-                message_id: json["messageId"].as_str().unwrap_or_default().to_string(),
-                conversation_with: json["conversationWith"].as_str().unwrap_or_default().to_string(),
-                decrypted_text: json["decryptedText"].as_str().unwrap_or_default().to_string(),
-                direction: json["direction"].as_str().unwrap_or("incoming").to_string(),
-                timestamp: json["timestamp"].as_i64().unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
-                from_handle: json["fromHandle"].as_str().map(|s| s.to_string()),
-            }
-        }
-        "read_receipt" => {
-            IncomingMessage::ReadReceipt {
-                message_id: json["messageId"].as_str().unwrap_or_default().to_string(),
-                timestamp: json["timestamp"].as_i64().unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
-            }
-        }
-        "request_sync" => {
-            IncomingMessage::RequestSync {
-                conversation_with: json["conversationWith"].as_str().unwrap_or_default().to_string(),
-                limit: json["limit"].as_u64().unwrap_or(50) as u32,
-            }
-        }
-        "envelope" | "message" => {
-            // Try to parse the envelope from data field or root
-            let envelope_json = if json["data"].is_object() {
-                &json["data"]
-            } else if json["envelope"].is_object() {
-                &json["envelope"]
-            } else {
-                &json
-            };
-            
-            match serde_json::from_value::<GnsEnvelope>(envelope_json.clone()) {
-                Ok(envelope) => IncomingMessage::Envelope(envelope),
-                Err(e) => {
-                    tracing::warn!("Failed to parse envelope: {}", e);
-                    IncomingMessage::Unknown(text.to_string())
-                }
-            }
-        }
-        _ => {
-            // Maybe it's a raw envelope without type field
-            if json["encrypted_payload"].is_object() && json["from_public_key"].is_string() {
-                match serde_json::from_value::<GnsEnvelope>(json) {
-                    Ok(envelope) => IncomingMessage::Envelope(envelope),
-                    Err(_) => IncomingMessage::Unknown(text.to_string()),
+        Ok(frame) => frame.into(),
+        // Maybe it's a raw envelope, with or without a type field
+        Err(e) => match json["type"].as_str() {
+            None | Some("envelope") | Some("message") => match GnsEnvelope::from_value_lenient(json.clone()) {
+                Ok(envelope) => IncomingMessage::Envelope(Box::new(envelope)),
+                Err(_) => {
+                    if json.get("type").is_some() {
+                        tracing::warn!("Failed to parse envelope: {}", e);
+                    }
+                    unknown(&json)
                 }
-            } else {
-                IncomingMessage::Unknown(text.to_string())
-            }
-        }
+            },
+            Some(_) => unknown(&json),
+        },
     }
 }
 
@@ -869,10 +1431,36 @@ fn parse_incoming_message(text: &str) -> IncomingMessage {
 pub struct IdentityInfo {
     pub public_key: String,
     pub encryption_key: String,
+    /// ML-KEM public key, when the identity supports hybrid encryption
+    #[serde(default)]
+    pub pq_encryption_key: Option<String>,
     pub handle: Option<String>,
     pub avatar_url: Option<String>,
     pub display_name: Option<String>,
     pub is_verified: bool,
+    /// External accounts whose proofs the backend validated
+    #[serde(default)]
+    pub verifications: Vec<VerifiedProof>,
+    /// Module ids advertised in the record; see `crate::capabilities`
+    #[serde(default)]
+    pub modules: Vec<String>,
+}
+
+/// An identifier hash the server matched to an identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredContact {
+    pub hash: String,
+    pub public_key: String,
+    #[serde(default)]
+    pub handle: Option<String>,
+}
+
+/// A record as published under `/records/{public_key}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedRecord {
+    pub record_json: serde_json::Value,
+    /// Ed25519 signature over the canonical JSON of `record_json` (hex)
+    pub signature: Option<String>,
 }
 
 /// Result of checking handle availability
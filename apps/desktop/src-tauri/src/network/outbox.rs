@@ -0,0 +1,195 @@
+//! Relay Outbox - Ordered outgoing frame queue
+//!
+//! Every frame bound for the relay goes through one FIFO queue drained by
+//! a single socket writer, so frames reach the relay in the order they
+//! were sent and messages within a thread can't overtake each other.
+//!
+//! The queue outlives connections. Envelope frames stay tracked after
+//! they are written until the relay acks them; on reconnect, unacked
+//! envelopes go back to the front of the queue ahead of anything newer.
+//! Envelopes are also persisted (in `pending_messages`) so they survive a
+//! restart. Other frames (browser sync, requests) are kept in memory only.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+/// Most frames held in memory before new ones are refused
+pub const MAX_QUEUED_FRAMES: usize = 1000;
+
+/// Times a persisted envelope is replayed before it is given up on
+pub const MAX_FRAME_RETRIES: u32 = 10;
+
+/// One frame waiting for the socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedFrame {
+    /// Envelope ID for envelope frames, which the relay acks
+    pub id: Option<String>,
+    pub text: String,
+}
+
+impl QueuedFrame {
+    pub fn envelope(id: &str, text: String) -> Self {
+        Self {
+            id: Some(id.to_string()),
+            text,
+        }
+    }
+
+    pub fn raw(text: String) -> Self {
+        Self { id: None, text }
+    }
+}
+
+#[derive(Default)]
+struct Queues {
+    queued: VecDeque<QueuedFrame>,
+    /// Written envelopes the relay hasn't acked, oldest first
+    unacked: Vec<QueuedFrame>,
+}
+
+/// Shared between every clone of a `RelayConnection`
+#[derive(Clone, Default)]
+pub struct Outbox {
+    queues: Arc<Mutex<Queues>>,
+    ready: Arc<Notify>,
+}
+
+impl Outbox {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queues> {
+        self.queues.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Append a frame; false if the queue is full
+    pub fn push(&self, frame: QueuedFrame) -> bool {
+        let mut queues = self.lock();
+        if queues.queued.len() >= MAX_QUEUED_FRAMES {
+            return false;
+        }
+        queues.queued.push_back(frame);
+        drop(queues);
+        self.ready.notify_one();
+        true
+    }
+
+    /// Wait for the next frame to write
+    pub async fn next(&self) -> QueuedFrame {
+        loop {
+            let ready = self.ready.notified();
+            if let Some(frame) = self.lock().queued.pop_front() {
+                return frame;
+            }
+            ready.await;
+        }
+    }
+
+    /// The frame was written; envelopes wait for their ack
+    pub fn written(&self, frame: QueuedFrame) {
+        if frame.id.is_some() {
+            self.lock().unacked.push(frame);
+        }
+    }
+
+    /// Writing failed; the frame goes back to the front
+    pub fn unwritten(&self, frame: QueuedFrame) {
+        self.lock().queued.push_front(frame);
+    }
+
+    /// The relay acked an envelope
+    pub fn acked(&self, id: &str) {
+        self.lock().unacked.retain(|frame| frame.id.as_deref() != Some(id));
+    }
+
+    /// Put unacked envelopes, then `persisted` ones not already queued
+    /// (left from an earlier run), back at the front of the queue
+    pub fn requeue(&self, persisted: Vec<QueuedFrame>) {
+        let mut queues = self.lock();
+        let unacked = std::mem::take(&mut queues.unacked);
+        let mut front: Vec<QueuedFrame> = persisted
+            .into_iter()
+            .filter(|frame| !unacked.contains(frame) && !queues.queued.contains(frame))
+            .collect();
+        front.extend(unacked);
+        for frame in front.into_iter().rev() {
+            queues.queued.push_front(frame);
+        }
+        let has_frames = !queues.queued.is_empty();
+        drop(queues);
+        if has_frames {
+            self.ready.notify_one();
+        }
+    }
+
+    /// Drop everything, e.g. when switching identity
+    pub fn clear(&self) {
+        *self.lock() = Queues::default();
+    }
+
+    /// Frames waiting to be written
+    pub fn queued(&self) -> usize {
+        self.lock().queued.len()
+    }
+
+    /// Envelopes written but not yet acked
+    pub fn unacked(&self) -> usize {
+        self.lock().unacked.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(id: &str) -> QueuedFrame {
+        QueuedFrame::envelope(id, format!("frame {}", id))
+    }
+
+    #[tokio::test]
+    async fn test_order_survives_reconnect() {
+        let outbox = Outbox::default();
+        outbox.push(envelope("a"));
+        outbox.push(QueuedFrame::raw("sync".to_string()));
+        outbox.push(envelope("b"));
+
+        // Connection 1 writes a, and the sync frame, then drops
+        let a = outbox.next().await;
+        outbox.written(a);
+        let sync = outbox.next().await;
+        outbox.written(sync);
+        assert_eq!((outbox.queued(), outbox.unacked()), (1, 1));
+
+        // Connection 2 resends a before b, then b
+        outbox.requeue(Vec::new());
+        assert_eq!(outbox.next().await, envelope("a"));
+        assert_eq!(outbox.next().await, envelope("b"));
+    }
+
+    #[tokio::test]
+    async fn test_acks_and_restore() {
+        let outbox = Outbox::default();
+        outbox.push(envelope("b"));
+        let b = outbox.next().await;
+        outbox.written(b);
+        outbox.acked("b");
+        assert_eq!(outbox.unacked(), 0);
+
+        // Frames persisted by an earlier run go first, without duplicates
+        outbox.push(envelope("c"));
+        outbox.requeue(vec![envelope("a"), envelope("c")]);
+        assert_eq!(outbox.next().await, envelope("a"));
+        assert_eq!(outbox.next().await, envelope("c"));
+        assert_eq!(outbox.queued(), 0);
+    }
+
+    #[test]
+    fn test_queue_limit() {
+        let outbox = Outbox::default();
+        for i in 0..MAX_QUEUED_FRAMES {
+            assert!(outbox.push(QueuedFrame::raw(i.to_string())));
+        }
+        assert!(!outbox.push(QueuedFrame::raw("full".to_string())));
+        outbox.clear();
+        assert_eq!(outbox.queued(), 0);
+    }
+}
@@ -0,0 +1,269 @@
+//! Payloads - Registry of handlers for payload types
+//!
+//! Every payload type has a handler deciding how its messages are decoded,
+//! which thread they are stored in, whether their sender is screened, and
+//! what notification they raise. The types in `PayloadKind` come built in;
+//! new ones (e.g. `gns/task`, `gns/receipt`) are added with
+//! `registry().register(..)` and are then accepted by `send_message` and
+//! the message handler like any other.
+//!
+//! Built-in types can't be replaced, so a registered handler never changes
+//! how existing messages are read.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::validation::{validate_payload, PayloadKind, ValidationError, MAX_PAYLOAD_BYTES};
+
+/// Directory handle of the email gateway, the only sender whose email
+/// skips screening
+pub const EMAIL_GATEWAY_HANDLE: &str = "email-gateway";
+
+/// How one payload type is read, stored and notified
+pub trait PayloadHandler: Send + Sync {
+    /// Check a decrypted payload and parse it
+    fn decode(&self, payload_type: &str, bytes: &[u8]) -> Result<Value, ValidationError>;
+
+    /// Thread to store the message in; None files it in the envelope's
+    /// thread, or the direct thread with the sender
+    fn thread_id(&self, _payload: &Value, _envelope_thread: Option<&str>) -> Option<String> {
+        None
+    }
+
+    /// Whether the sender goes through spam and contact request checks;
+    /// skipping them is only honoured for the email gateway
+    fn screen_sender(&self) -> bool {
+        true
+    }
+
+    /// Whether a new message raises a notification
+    fn notify(&self, _payload: &Value) -> bool {
+        true
+    }
+
+    /// Notification text; None shows a generic one
+    fn preview(&self, payload: &Value) -> Option<String> {
+        payload["text"].as_str().map(|s| s.to_string())
+    }
+}
+
+/// Types checked by `validate_payload`
+struct BuiltinHandler;
+
+impl PayloadHandler for BuiltinHandler {
+    fn decode(&self, payload_type: &str, bytes: &[u8]) -> Result<Value, ValidationError> {
+        validate_payload(payload_type, bytes)
+    }
+}
+
+/// Email from the gateway, threaded by subject like a mail client
+struct EmailHandler;
+
+impl PayloadHandler for EmailHandler {
+    fn decode(&self, payload_type: &str, bytes: &[u8]) -> Result<Value, ValidationError> {
+        validate_payload(payload_type, bytes)
+    }
+
+    // The server groups by participants, we want to group by subject
+    fn thread_id(&self, payload: &Value, envelope_thread: Option<&str>) -> Option<String> {
+        let subject = payload["subject"].as_str().unwrap_or("");
+        subject_thread_id(subject)
+            .or_else(|| envelope_thread.map(|t| t.to_string()))
+            .or_else(|| Some(uuid::Uuid::new_v4().to_string()))
+    }
+
+    // The gateway is trusted; the message handler checks it's the gateway
+    fn screen_sender(&self) -> bool {
+        false
+    }
+
+    fn preview(&self, payload: &Value) -> Option<String> {
+        payload["subject"].as_str().map(|s| s.to_string())
+    }
+}
+
+/// Handlers by payload type
+pub struct PayloadRegistry {
+    handlers: RwLock<HashMap<String, Arc<dyn PayloadHandler>>>,
+}
+
+static REGISTRY: LazyLock<PayloadRegistry> = LazyLock::new(PayloadRegistry::new);
+
+/// The app-wide registry
+pub fn registry() -> &'static PayloadRegistry {
+    &REGISTRY
+}
+
+impl PayloadRegistry {
+    pub fn new() -> Self {
+        Self {
+            handlers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Add a handler for a new payload type
+    pub fn register(&self, payload_type: &str, handler: Arc<dyn PayloadHandler>) -> Result<(), String> {
+        if payload_type.is_empty() {
+            return Err("Payload type is empty".to_string());
+        }
+        if PayloadKind::from_type(payload_type).is_some() {
+            return Err(format!("'{}' is a built-in payload type", payload_type));
+        }
+        let mut handlers = self.handlers.write().unwrap_or_else(|e| e.into_inner());
+        if handlers.contains_key(payload_type) {
+            return Err(format!("'{}' is already registered", payload_type));
+        }
+        handlers.insert(payload_type.to_string(), handler);
+        Ok(())
+    }
+
+    /// Handler for a payload type, None for types nobody handles
+    pub fn handler(&self, payload_type: &str) -> Option<Arc<dyn PayloadHandler>> {
+        match PayloadKind::from_type(payload_type) {
+            Some(PayloadKind::Email) => Some(Arc::new(EmailHandler)),
+            Some(_) => Some(Arc::new(BuiltinHandler)),
+            None => self
+                .handlers
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(payload_type)
+                .cloned(),
+        }
+    }
+
+    /// Check and parse a payload with its type's handler
+    pub fn decode(&self, payload_type: &str, bytes: &[u8]) -> Result<Value, ValidationError> {
+        if bytes.len() > MAX_PAYLOAD_BYTES {
+            return Err(ValidationError::TooLarge { size: bytes.len(), max: MAX_PAYLOAD_BYTES });
+        }
+        let handler = self.handler(payload_type).ok_or_else(|| ValidationError::UnknownType {
+            payload_type: payload_type.to_string(),
+        })?;
+        handler.decode(payload_type, bytes)
+    }
+
+    pub fn thread_id(&self, payload_type: &str, payload: &Value, envelope_thread: Option<&str>) -> Option<String> {
+        self.handler(payload_type)?.thread_id(payload, envelope_thread)
+    }
+
+    pub fn screen_sender(&self, payload_type: &str) -> bool {
+        self.handler(payload_type).map(|h| h.screen_sender()).unwrap_or(true)
+    }
+
+    pub fn notify(&self, payload_type: &str, payload: &Value) -> bool {
+        self.handler(payload_type).map(|h| h.notify(payload)).unwrap_or(true)
+    }
+
+    pub fn preview(&self, payload_type: &str, payload: &Value) -> Option<String> {
+        self.handler(payload_type)?.preview(payload)
+    }
+}
+
+impl Default for PayloadRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Normalize subject for threading (remove Re:, Fwd:, etc)
+pub fn normalize_subject(subject: &str) -> String {
+    let mut s = subject.trim().to_lowercase();
+
+    // Loop until no more prefixes found
+    loop {
+        let original_len = s.len();
+
+        // Remove prefixes
+        if s.starts_with("re:") {
+            s = s[3..].trim_start().to_string();
+        } else if s.starts_with("fwd:") {
+            s = s[4..].trim_start().to_string();
+        } else if s.starts_with("fw:") {
+            s = s[3..].trim_start().to_string();
+        }
+
+        // If length didn't change, we are done
+        if s.len() == original_len {
+            break;
+        }
+    }
+
+    s
+}
+
+/// Thread shared by every email with this subject, None when it is empty
+pub fn subject_thread_id(subject: &str) -> Option<String> {
+    let subject = normalize_subject(subject);
+    if subject.is_empty() {
+        return None;
+    }
+    Some(hex::encode(Sha256::digest(subject.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A to-do item, only notified when assigned to someone
+    struct TaskHandler;
+
+    impl PayloadHandler for TaskHandler {
+        fn decode(&self, _payload_type: &str, bytes: &[u8]) -> Result<Value, ValidationError> {
+            let payload: Value = serde_json::from_slice(bytes)
+                .map_err(|e| ValidationError::Malformed { reason: e.to_string() })?;
+            if !payload["title"].is_string() {
+                return Err(ValidationError::MissingField { field: "title".to_string() });
+            }
+            Ok(payload)
+        }
+
+        fn thread_id(&self, payload: &Value, _envelope_thread: Option<&str>) -> Option<String> {
+            payload["list"].as_str().map(|list| format!("tasks_{}", list))
+        }
+
+        fn notify(&self, payload: &Value) -> bool {
+            payload["assignee"].is_string()
+        }
+
+        fn preview(&self, payload: &Value) -> Option<String> {
+            payload["title"].as_str().map(|s| s.to_string())
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = PayloadRegistry::new();
+        let task = br#"{"title":"Ship it","list":"launch"}"#;
+        assert!(matches!(registry.decode("gns/task", task), Err(ValidationError::UnknownType { .. })));
+
+        registry.register("gns/task", Arc::new(TaskHandler)).unwrap();
+        assert!(registry.register("gns/task", Arc::new(TaskHandler)).is_err());
+        assert!(registry.register("gns/email", Arc::new(TaskHandler)).is_err());
+
+        let payload = registry.decode("gns/task", task).unwrap();
+        assert!(registry.decode("gns/task", br#"{"list":"launch"}"#).is_err());
+        assert_eq!(registry.thread_id("gns/task", &payload, Some("t1")).as_deref(), Some("tasks_launch"));
+        assert!(registry.screen_sender("gns/task"));
+        assert!(!registry.notify("gns/task", &payload));
+        assert_eq!(registry.preview("gns/task", &payload).as_deref(), Some("Ship it"));
+
+        // Built-ins keep their own rules
+        let email = registry.decode("email", br#"{"subject":"Re: Invoice"}"#).unwrap();
+        assert_eq!(registry.thread_id("email", &email, Some("t1")), subject_thread_id("Invoice"));
+        assert_eq!(registry.thread_id("email", &json!({}), Some("t1")).as_deref(), Some("t1"));
+        assert!(!registry.screen_sender("gns/email"));
+        assert_eq!(registry.thread_id("text/plain", &json!({"text": "hi"}), Some("t1")), None);
+        assert_eq!(registry.preview("text/plain", &json!({"text": "hi"})).as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn test_subject_thread_id() {
+        assert_eq!(normalize_subject("  RE: Fwd: fw: Lunch "), "lunch");
+        assert_eq!(subject_thread_id("Re: Lunch"), subject_thread_id("lunch"));
+        assert_eq!(subject_thread_id("Re: "), None);
+    }
+}
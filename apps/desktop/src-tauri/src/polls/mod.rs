@@ -0,0 +1,245 @@
+//! Polls - Questions with a fixed set of answers, in threads and Dix posts
+//!
+//! A poll is signed by its creator and travels as a `gns/poll` message in
+//! a thread or embedded in a Dix post. Voting sends a `gns/poll.vote`
+//! envelope to the creator, carrying a vote the voter signed separately so
+//! it stays attributable once tallied. The creator's client is the only
+//! one that sees every vote; other participants see their own choice.
+//!
+//! A voter may change their mind: the latest vote from each voter counts,
+//! and votes after the poll closes are ignored.
+
+use gns_crypto_core::GnsIdentity;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{sign_statement, verify_statement};
+
+/// Payload type for a poll sent in a thread
+pub const POLL_PAYLOAD_TYPE: &str = "gns/poll";
+
+/// Payload type for a vote sent to the poll's creator
+pub const POLL_VOTE_PAYLOAD_TYPE: &str = "gns/poll.vote";
+
+/// Most answers a poll can offer
+pub const MAX_POLL_OPTIONS: usize = 10;
+
+/// Longest question, in characters
+pub const MAX_QUESTION_CHARS: usize = 300;
+
+/// Longest answer, in characters
+pub const MAX_OPTION_CHARS: usize = 100;
+
+/// A signed poll
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Poll {
+    pub id: String,
+    pub creator: String,
+    pub question: String,
+    pub options: Vec<String>,
+    /// Voters may pick more than one answer
+    #[serde(default)]
+    pub multiple_choice: bool,
+    /// No votes count after this (ms)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closes_at: Option<i64>,
+    pub created_at: i64,
+    pub signature: String,
+}
+
+impl Poll {
+    pub fn is_closed(&self, now: i64) -> bool {
+        self.closes_at.is_some_and(|closes_at| now >= closes_at)
+    }
+}
+
+/// One voter's signed choice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollVote {
+    pub poll_id: String,
+    pub voter: String,
+    /// Indexes into the poll's options
+    pub choices: Vec<u32>,
+    pub timestamp: i64,
+    pub signature: String,
+}
+
+/// A poll's tally as far as we know it
+#[derive(Debug, Clone, Serialize)]
+pub struct PollResults {
+    pub poll: Poll,
+    /// Votes per option, in option order
+    pub counts: Vec<u32>,
+    pub voters: u32,
+    pub my_choices: Option<Vec<u32>>,
+    pub closed: bool,
+    /// We created the poll, so the tally is complete
+    pub is_creator: bool,
+}
+
+/// Check a question and its answers before creating a poll
+pub fn check_poll_text(question: &str, options: &[String]) -> Result<(), String> {
+    if question.trim().is_empty() || question.chars().count() > MAX_QUESTION_CHARS {
+        return Err(format!("Poll questions must be 1-{} characters", MAX_QUESTION_CHARS));
+    }
+    if !(2..=MAX_POLL_OPTIONS).contains(&options.len()) {
+        return Err(format!("Polls need 2-{} answers", MAX_POLL_OPTIONS));
+    }
+    if options
+        .iter()
+        .any(|o| o.trim().is_empty() || o.chars().count() > MAX_OPTION_CHARS)
+    {
+        return Err(format!("Poll answers must be 1-{} characters", MAX_OPTION_CHARS));
+    }
+    Ok(())
+}
+
+/// Create and sign a poll
+pub fn create_poll(
+    identity: &GnsIdentity,
+    question: &str,
+    options: Vec<String>,
+    multiple_choice: bool,
+    closes_at: Option<i64>,
+    now: i64,
+) -> Poll {
+    let mut poll = Poll {
+        id: uuid::Uuid::new_v4().to_string(),
+        creator: identity.public_key_hex(),
+        question: question.to_string(),
+        options,
+        multiple_choice,
+        closes_at,
+        created_at: now,
+        signature: String::new(),
+    };
+    poll.signature = sign_statement(identity, &poll);
+    poll
+}
+
+/// Sign a vote on `poll`, checking the choices fit it
+pub fn create_vote(identity: &GnsIdentity, poll: &Poll, mut choices: Vec<u32>, now: i64) -> Result<PollVote, String> {
+    choices.sort_unstable();
+    choices.dedup();
+    check_choices(poll, &choices)?;
+    let mut vote = PollVote {
+        poll_id: poll.id.clone(),
+        voter: identity.public_key_hex(),
+        choices,
+        timestamp: now,
+        signature: String::new(),
+    };
+    vote.signature = sign_statement(identity, &vote);
+    Ok(vote)
+}
+
+/// Check a poll's signature
+pub fn verify_poll(poll: &Poll) -> Result<(), String> {
+    check_poll_text(&poll.question, &poll.options)?;
+    if !verify_statement(poll, &poll.creator, &poll.signature) {
+        return Err("signature does not verify".to_string());
+    }
+    Ok(())
+}
+
+/// Check a vote is signed by its voter and valid for `poll`
+pub fn verify_vote(vote: &PollVote, poll: &Poll) -> Result<(), String> {
+    if vote.poll_id != poll.id {
+        return Err("vote is for another poll".to_string());
+    }
+    if poll.closes_at.is_some_and(|closes_at| vote.timestamp >= closes_at) {
+        return Err("poll had closed".to_string());
+    }
+    check_choices(poll, &vote.choices)?;
+    if !verify_statement(vote, &vote.voter, &vote.signature) {
+        return Err("signature does not verify".to_string());
+    }
+    Ok(())
+}
+
+fn check_choices(poll: &Poll, choices: &[u32]) -> Result<(), String> {
+    if choices.is_empty() {
+        return Err("no answer chosen".to_string());
+    }
+    if choices.len() > 1 && !poll.multiple_choice {
+        return Err("poll allows one answer".to_string());
+    }
+    if choices.iter().any(|&c| c as usize >= poll.options.len()) {
+        return Err("answer is not one of the poll's options".to_string());
+    }
+    Ok(())
+}
+
+/// Count votes; storage keeps one, the latest, per voter
+pub fn tally(poll: &Poll, votes: &[PollVote], my_key: &str, now: i64) -> PollResults {
+    let mut counts = vec![0u32; poll.options.len()];
+    for vote in votes {
+        for &choice in &vote.choices {
+            if let Some(count) = counts.get_mut(choice as usize) {
+                *count += 1;
+            }
+        }
+    }
+    PollResults {
+        poll: poll.clone(),
+        counts,
+        voters: votes.len() as u32,
+        my_choices: votes
+            .iter()
+            .find(|v| v.voter.eq_ignore_ascii_case(my_key))
+            .map(|v| v.choices.clone()),
+        closed: poll.is_closed(now),
+        is_creator: poll.creator.eq_ignore_ascii_case(my_key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_votes_verify_against_poll() {
+        let creator = GnsIdentity::generate();
+        let voter = GnsIdentity::generate();
+        let poll = create_poll(&creator, "Lunch?", options(&["Pizza", "Sushi"]), false, Some(10_000), 1_000);
+        assert!(verify_poll(&poll).is_ok());
+
+        let vote = create_vote(&voter, &poll, vec![1], 2_000).unwrap();
+        assert!(verify_vote(&vote, &poll).is_ok());
+
+        let mut changed = vote.clone();
+        changed.choices = vec![0];
+        assert!(verify_vote(&changed, &poll).is_err());
+
+        assert!(create_vote(&voter, &poll, vec![0, 1], 2_000).is_err());
+        assert!(create_vote(&voter, &poll, vec![2], 2_000).is_err());
+        assert!(verify_vote(&create_vote(&voter, &poll, vec![0], 10_000).unwrap(), &poll).is_err());
+
+        let mut edited = poll.clone();
+        edited.options[1] = "Tacos".to_string();
+        assert!(verify_poll(&edited).is_err());
+    }
+
+    #[test]
+    fn test_tally() {
+        let creator = GnsIdentity::generate();
+        let poll = create_poll(&creator, "Days?", options(&["Mon", "Tue", "Wed"]), true, None, 1_000);
+        let alice = GnsIdentity::generate();
+        let bob = GnsIdentity::generate();
+        let votes = vec![
+            create_vote(&alice, &poll, vec![2, 0, 2], 2_000).unwrap(),
+            create_vote(&bob, &poll, vec![0], 2_000).unwrap(),
+        ];
+        assert_eq!(votes[0].choices, vec![0, 2]);
+
+        let results = tally(&poll, &votes, &alice.public_key_hex(), 3_000);
+        assert_eq!(results.counts, vec![2, 0, 1]);
+        assert_eq!(results.voters, 2);
+        assert_eq!(results.my_choices, Some(vec![0, 2]));
+        assert!(!results.is_creator && !results.closed);
+        assert!(tally(&poll, &[], &creator.public_key_hex(), 3_000).is_creator);
+    }
+}
@@ -0,0 +1,149 @@
+//! Presence - Online status and last-seen times for contacts
+//!
+//! The relay knows which identities have a device connected. After each
+//! connect we tell it whether it may share our own presence, and which
+//! contacts we want to hear about; it then sends a `presence` frame
+//! whenever one of them comes online or goes offline. Statuses are held
+//! in memory only and treated as stale after a reconnect until the relay
+//! reports again.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::network::frame::RelayFrame;
+use crate::network::{NetworkError, RelayConnection};
+use crate::storage::DatabaseHandle;
+
+/// Most peers tracked; offline ones are forgotten past this
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// One peer's presence, for the UI
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Presence {
+    pub public_key: String,
+    pub online: bool,
+    /// Last time the peer was seen online (ms); None if never reported
+    pub last_seen: Option<i64>,
+}
+
+impl Presence {
+    fn unknown(public_key: &str) -> Self {
+        Self {
+            public_key: public_key.to_string(),
+            online: false,
+            last_seen: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PresenceTracker {
+    peers: Mutex<HashMap<String, Presence>>,
+}
+
+impl PresenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Presence>> {
+        self.peers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record a report from the relay; returns the new presence if it changed
+    pub fn update(&self, public_key: &str, online: bool, last_seen: Option<i64>, now: i64) -> Option<Presence> {
+        let public_key = public_key.to_lowercase();
+        let mut peers = self.lock();
+        let previous = peers.get(&public_key).cloned().unwrap_or_else(|| Presence::unknown(&public_key));
+
+        // Coming online or going offline, the peer was last seen now
+        let last_seen = last_seen.or(if online != previous.online { Some(now) } else { previous.last_seen });
+        let presence = Presence {
+            public_key: public_key.clone(),
+            online,
+            last_seen,
+        };
+        if peers.get(&public_key) == Some(&presence) {
+            return None;
+        }
+
+        if peers.len() >= MAX_TRACKED_PEERS && !peers.contains_key(&public_key) {
+            peers.retain(|_, peer| peer.online);
+        }
+        peers.insert(public_key, presence.clone());
+        Some(presence)
+    }
+
+    /// A peer's presence; offline and never seen if the relay hasn't said
+    pub fn get(&self, public_key: &str) -> Presence {
+        let public_key = public_key.to_lowercase();
+        self.lock()
+            .get(&public_key)
+            .cloned()
+            .unwrap_or_else(|| Presence::unknown(&public_key))
+    }
+
+    /// Statuses from an earlier connection can't be trusted; keep only
+    /// last-seen times
+    pub fn mark_all_offline(&self, now: i64) {
+        for peer in self.lock().values_mut() {
+            if peer.online {
+                peer.online = false;
+                peer.last_seen = Some(now);
+            }
+        }
+    }
+}
+
+/// Send our sharing preference and subscribe to our contacts' presence.
+/// Called once the relay has welcomed us.
+pub async fn announce(relay: &RelayConnection, database: &DatabaseHandle) -> Result<(), NetworkError> {
+    let (share, public_keys) = database
+        .call(|db| (db.get_share_presence(), db.contact_public_keys()))
+        .await;
+    relay.send_frame(&RelayFrame::PresenceSettings { share }).await?;
+    if !public_keys.is_empty() {
+        relay.send_frame(&RelayFrame::PresenceSubscribe { public_keys }).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presence_changes() {
+        let tracker = PresenceTracker::new();
+        let pk = "ab".repeat(32);
+        assert_eq!(tracker.get(&pk), Presence::unknown(&pk));
+
+        let online = tracker.update(&pk, true, None, 1_000).unwrap();
+        assert!(online.online);
+        assert_eq!(online.last_seen, Some(1_000));
+
+        // Repeats aren't changes
+        assert_eq!(tracker.update(&pk, true, None, 2_000), None);
+
+        // Going offline without a timestamp means last seen now
+        let offline = tracker.update(&pk.to_uppercase(), false, None, 5_000).unwrap();
+        assert_eq!((offline.online, offline.last_seen), (false, Some(5_000)));
+        assert_eq!(tracker.get(&pk), offline);
+
+        // The relay's last-seen time wins
+        assert_eq!(tracker.update(&pk, false, Some(4_000), 6_000).unwrap().last_seen, Some(4_000));
+    }
+
+    #[test]
+    fn test_reconnect_marks_offline() {
+        let tracker = PresenceTracker::new();
+        tracker.update("a", true, None, 1_000);
+        tracker.update("b", false, Some(500), 1_000);
+        tracker.mark_all_offline(3_000);
+        assert_eq!(tracker.get("a").last_seen, Some(3_000));
+        assert!(!tracker.get("a").online);
+        assert_eq!(tracker.get("b").last_seen, Some(500));
+    }
+}
@@ -0,0 +1,102 @@
+//! Proximity - Proving two users were together
+//!
+//! Each side of a proof signs a breadcrumb for the moment and a claim
+//! naming the other (see `gns_crypto_core::proximity`), and sends it as a
+//! `gns/proximity` envelope. Whichever side holds both claims first builds
+//! the attestation; since the claims are ordered by key, both end up with
+//! the same one. Claims aren't thread messages; the attestation is kept
+//! on its own and can be attached to a message or Dix post.
+
+use gns_crypto_core::proximity::{attest_proximity, ProximityAttestation, ProximityClaim};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Payload type for one side's claim
+pub const PROXIMITY_PAYLOAD_TYPE: &str = "gns/proximity";
+
+/// Payload field a proof is attached under in chat messages
+pub const PROXIMITY_FIELD: &str = "proximity";
+
+/// Largest grid distance a user can ask for
+pub const MAX_GRID_STEPS: u32 = 5;
+
+/// The payload: our claim and how close we're asking to prove
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProximityRequest {
+    pub claim: ProximityClaim,
+    pub max_steps: u32,
+}
+
+/// A finished proof, as stored
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredAttestation {
+    pub id: String,
+    pub peer_public_key: String,
+    pub attestation: ProximityAttestation,
+    pub created_at: i64,
+}
+
+/// Stable id for an attestation, the same on both sides
+pub fn attestation_id(attestation: &ProximityAttestation) -> String {
+    let [a, b] = &attestation.claims;
+    let digest = Sha256::digest(format!("gns-proximity:{}:{}", a.signature, b.signature).as_bytes());
+    hex::encode(&digest[..16])
+}
+
+/// Check a received claim is the sender's and names us
+pub fn check_claim(claim: &ProximityClaim, from_public_key: &str, my_public_key: &str) -> Result<(), String> {
+    if !claim.breadcrumb.public_key.eq_ignore_ascii_case(from_public_key) {
+        return Err("Claim was sent by someone other than its signer".to_string());
+    }
+    if !claim.peer_public_key.eq_ignore_ascii_case(my_public_key) {
+        return Err("Claim is for someone else".to_string());
+    }
+    claim.verify().map_err(|e| e.to_string())
+}
+
+/// Combine our claim with the peer's, at the stricter of the two distances
+pub fn combine(
+    ours: &ProximityRequest,
+    theirs: &ProximityRequest,
+    peer_public_key: &str,
+) -> Result<StoredAttestation, String> {
+    let max_steps = ours.max_steps.min(theirs.max_steps);
+    let attestation = attest_proximity(&ours.claim, &theirs.claim, max_steps).map_err(|e| e.to_string())?;
+    Ok(StoredAttestation {
+        id: attestation_id(&attestation),
+        peer_public_key: peer_public_key.to_lowercase(),
+        created_at: attestation.timestamp() * 1000,
+        attestation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::proximity::create_proximity_claim;
+    use gns_crypto_core::GnsIdentity;
+
+    #[test]
+    fn test_both_sides_agree() {
+        let alice = GnsIdentity::generate();
+        let bob = GnsIdentity::generate();
+        let (alice_pk, bob_pk) = (alice.public_key_hex(), bob.public_key_hex());
+        let from_alice = ProximityRequest {
+            claim: create_proximity_claim(&alice, 52.52, 13.405, &bob_pk).unwrap(),
+            max_steps: 3,
+        };
+        let from_bob = ProximityRequest {
+            claim: create_proximity_claim(&bob, 52.52, 13.405, &alice_pk).unwrap(),
+            max_steps: 1,
+        };
+        assert!(check_claim(&from_alice.claim, &alice_pk, &bob_pk).is_ok());
+        assert!(check_claim(&from_alice.claim, &bob_pk, &bob_pk).is_err());
+        assert!(check_claim(&from_alice.claim, &alice_pk, &alice_pk).is_err());
+
+        let at_alice = combine(&from_alice, &from_bob, &bob_pk).unwrap();
+        let at_bob = combine(&from_bob, &from_alice, &alice_pk).unwrap();
+        assert_eq!(at_alice.id, at_bob.id);
+        assert_eq!(at_alice.attestation, at_bob.attestation);
+        assert_eq!(at_alice.attestation.max_steps, 1);
+    }
+}
@@ -0,0 +1,163 @@
+//! Push Module - Background notification decryption
+//!
+//! Pushes from FCM/APNs carry only an envelope ID, never content. When one
+//! arrives, `handle_push` fetches the envelope, opens it with the local
+//! identity, stores it like a relay delivery, and posts a local
+//! notification naming the real sender. None of this touches the webview.
+//! On iOS the app delegate hands remote notifications and the APNs token
+//! straight to `native::gns_handle_push` and `native::gns_register_push_token`.
+//! There is no Android project in the tree yet, so FCM pushes only arrive
+//! through the `handle_push_notification` command for now.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::message_handler::{handle_envelope, IncomingMessageEvent};
+use crate::payloads;
+use crate::AppState;
+
+/// Longest notification body, in characters
+pub const PREVIEW_CHARS: usize = 120;
+
+/// Push service a device token belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushPlatform {
+    Fcm,
+    Apns,
+}
+
+impl PushPlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fcm => "fcm",
+            Self::Apns => "apns",
+        }
+    }
+}
+
+/// The token registered with the backend for this device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushRegistration {
+    pub platform: PushPlatform,
+    pub token: String,
+    pub registered_at: i64,
+}
+
+/// Envelope ID from a push's data, as FCM and APNs nest it differently
+pub fn envelope_id_from_push(data: &serde_json::Value) -> Option<String> {
+    let candidates = [data, &data["data"], &data["aps"], &data["gns"]];
+    candidates.iter().find_map(|value| {
+        ["envelope_id", "envelopeId", "message_id"]
+            .iter()
+            .find_map(|key| value[*key].as_str())
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string())
+    })
+}
+
+/// Notification title for a sender
+pub fn notification_title(from_handle: Option<&str>, from_public_key: &str) -> String {
+    match from_handle {
+        Some(handle) => format!("@{}", handle.trim_start_matches('@')),
+        None => format!("{}…", &from_public_key[..from_public_key.len().min(8)]),
+    }
+}
+
+/// Short plain-text preview of a decrypted payload
+pub fn notification_preview(payload_type: &str, payload: &serde_json::Value) -> String {
+    let text = payloads::registry().preview(payload_type, payload);
+
+    let Some(text) = text.map(|t| t.split_whitespace().collect::<Vec<_>>().join(" ")) else {
+        return "New message".to_string();
+    };
+    if text.is_empty() {
+        return "New message".to_string();
+    }
+    if text.chars().count() <= PREVIEW_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(PREVIEW_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Fetch, decrypt and store the envelope a push refers to, then notify
+///
+/// Returns the message, or `None` if it was already delivered (e.g. over
+/// the relay) and needs no notification.
+pub async fn handle_push(
+    app: &AppHandle,
+    data: &serde_json::Value,
+) -> Result<Option<IncomingMessageEvent>, String> {
+    let envelope_id = envelope_id_from_push(data).ok_or("Push has no envelope ID")?;
+    let state = app.state::<AppState>();
+
+    let id = envelope_id.clone();
+    let already_stored = state
+        .database
+        .call(move |db| db.get_message(&id))
+        .await
+        .map_err(|e| e.to_string())?
+        .is_some();
+    if already_stored {
+        return Ok(None);
+    }
+
+    let envelope = state
+        .api
+        .fetch_envelope(&envelope_id)
+        .await
+        .map_err(|e| format!("Failed to fetch envelope: {}", e))?
+        .ok_or("Envelope not found")?;
+
+    let event = handle_envelope(app, &state.identity, &state.database, &state.relay, envelope)
+        .await
+        .ok_or("Failed to open envelope")?;
+
+    // Junk arrives silently, as do types whose handler says so
+    if event.is_junk || !payloads::registry().notify(&event.payload_type, &event.payload) {
+        return Ok(Some(event));
+    }
+
+    let title = notification_title(event.from_handle.as_deref(), &event.from_public_key);
+    let body = notification_preview(&event.payload_type, &event.payload);
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!("Failed to show notification for {}: {}", envelope_id, e);
+    }
+
+    Ok(Some(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_envelope_id_from_push() {
+        assert_eq!(envelope_id_from_push(&json!({"envelope_id": "e1"})).as_deref(), Some("e1"));
+        assert_eq!(
+            envelope_id_from_push(&json!({"data": {"envelopeId": "e2"}})).as_deref(),
+            Some("e2")
+        );
+        assert_eq!(
+            envelope_id_from_push(&json!({"aps": {"alert": "x"}, "gns": {"message_id": "e3"}})).as_deref(),
+            Some("e3")
+        );
+        assert_eq!(envelope_id_from_push(&json!({"envelope_id": ""})), None);
+    }
+
+    #[test]
+    fn test_notification_text() {
+        assert_eq!(notification_title(Some("@alice"), "abcdef0123456789"), "@alice");
+        assert_eq!(notification_title(None, "abcdef0123456789"), "abcdef01…");
+
+        assert_eq!(notification_preview("text/plain", &json!({"text": "hi\n  there"})), "hi there");
+        assert_eq!(notification_preview("gns/email", &json!({"subject": "Invoice"})), "Invoice");
+        assert_eq!(notification_preview("image/png", &json!({})), "New message");
+
+        let long = notification_preview("text/plain", &json!({"text": "x".repeat(500)}));
+        assert_eq!(long.chars().count(), PREVIEW_CHARS);
+    }
+}
@@ -0,0 +1,89 @@
+//! QR Module - In-person identity exchange
+//!
+//! A QR code carries our signed identity card (signing key, encryption key
+//! and handle) as `gns:card:<base64url JSON>`. Scanning checks the card's
+//! self-signature, so a doctored code can't pair a handle with someone
+//! else's keys.
+
+use base64::Engine;
+use gns_crypto_core::IdentityCard;
+use std::io::Cursor;
+
+/// Prefix identifying a GNS identity QR payload
+pub const QR_PREFIX: &str = "gns:card:";
+
+/// Default rendered size in pixels
+pub const DEFAULT_QR_SIZE: u32 = 512;
+
+/// Serialize a card into a QR payload
+pub fn encode_card(card: &IdentityCard) -> Result<String, String> {
+    let json = card.to_json().map_err(|e| e.to_string())?;
+    Ok(format!(
+        "{}{}",
+        QR_PREFIX,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    ))
+}
+
+/// Parse a scanned payload and check the card is signed by its own key
+pub fn decode_card(payload: &str) -> Result<IdentityCard, String> {
+    let encoded = payload
+        .trim()
+        .strip_prefix(QR_PREFIX)
+        .ok_or("Not a GNS identity code")?;
+    let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|_| "Malformed identity code")?;
+    let card = IdentityCard::from_json(&String::from_utf8_lossy(&json)).map_err(|e| e.to_string())?;
+
+    if !is_hex_key(&card.public_key) || !is_hex_key(&card.encryption_key) {
+        return Err("Identity code has invalid keys".to_string());
+    }
+    match card.verify() {
+        Ok(true) => Ok(card),
+        Ok(false) => Err("Identity code signature is invalid".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Render a payload as a PNG at least `size` pixels square
+pub fn render_png(payload: &str, size: u32) -> Result<Vec<u8>, String> {
+    let code = qrcode::QrCode::new(payload.as_bytes()).map_err(|e| e.to_string())?;
+    let image = code
+        .render::<image::Luma<u8>>()
+        .min_dimensions(size, size)
+        .build();
+
+    let mut png = Vec::new();
+    image::DynamicImage::ImageLuma8(image)
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+fn is_hex_key(key: &str) -> bool {
+    key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::GnsIdentity;
+
+    #[test]
+    fn test_qr_payload_roundtrip() {
+        let identity = GnsIdentity::generate();
+        let card = IdentityCard::create(&identity, Some("alice".to_string()), None, None, None).unwrap();
+
+        let payload = encode_card(&card).unwrap();
+        assert_eq!(decode_card(&payload).unwrap(), card);
+
+        let mut forged = card.clone();
+        forged.encryption_key = GnsIdentity::generate().encryption_key_hex();
+        assert!(decode_card(&encode_card(&forged).unwrap()).is_err());
+        assert!(decode_card("https://example.com").is_err());
+
+        let png = render_png(&payload, 256).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
@@ -0,0 +1,104 @@
+//! Quotes - Integrity link between a reply and the message it quotes
+//!
+//! A reply carries `quote_digest` in its payload: SHA-256 over the
+//! canonical JSON of the quoted message's id, sender, type and payload.
+//! The payload is covered by the envelope signature, so the digest is
+//! signed by whoever sent the reply.
+//!
+//! On receipt the digest is recomputed from our own stored copy of the
+//! original. A mismatch means the reply quotes something other than what
+//! we have — an edited or fabricated quote — and is flagged to the UI
+//! rather than shown as a faithful quote.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::commands::handles::canonical_json;
+use crate::commands::messaging::Message;
+
+/// Payload field holding the digest of the quoted message
+pub const QUOTE_DIGEST_FIELD: &str = "quote_digest";
+
+/// Outcome of checking a reply's quote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteStatus {
+    /// Matches our copy of the original
+    Verified,
+    /// Differs from our copy of the original
+    Mismatch,
+    /// We don't have the original to compare against
+    Unavailable,
+}
+
+impl QuoteStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuoteStatus::Verified => "verified",
+            QuoteStatus::Mismatch => "mismatch",
+            QuoteStatus::Unavailable => "unavailable",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "verified" => Some(QuoteStatus::Verified),
+            "mismatch" => Some(QuoteStatus::Mismatch),
+            "unavailable" => Some(QuoteStatus::Unavailable),
+            _ => None,
+        }
+    }
+}
+
+/// Digest identifying a message's content (hex SHA-256)
+pub fn quote_digest(message_id: &str, from_public_key: &str, payload_type: &str, payload: &Value) -> String {
+    let quoted = json!({
+        "id": message_id,
+        "from": from_public_key.to_lowercase(),
+        "payload_type": payload_type,
+        "payload": payload,
+    });
+    hex::encode(Sha256::digest(canonical_json(&quoted).as_bytes()))
+}
+
+/// Digest of a stored message
+pub fn digest_of(message: &Message) -> String {
+    quote_digest(&message.id, &message.from_public_key, &message.payload_type, &message.payload)
+}
+
+/// Compare a reply's claimed digest with the one computed from our copy
+/// of the original (None if we don't have it). None when the reply makes
+/// no claim, as from older clients.
+pub fn check_quote(claimed: Option<&str>, expected: Option<&str>) -> Option<QuoteStatus> {
+    let claimed = claimed?;
+    Some(match expected {
+        Some(expected) if claimed.eq_ignore_ascii_case(expected) => QuoteStatus::Verified,
+        Some(_) => QuoteStatus::Mismatch,
+        None => QuoteStatus::Unavailable,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_ignores_key_order() {
+        let a = json!({ "text": "hi", "subject": "s" });
+        let b = json!({ "subject": "s", "text": "hi" });
+        assert_eq!(quote_digest("m1", "AB", "text", &a), quote_digest("m1", "ab", "text", &b));
+        assert_ne!(quote_digest("m1", "ab", "text", &a), quote_digest("m2", "ab", "text", &a));
+    }
+
+    #[test]
+    fn test_check_quote() {
+        let digest = quote_digest("m1", "ab", "text", &json!({ "text": "meet at 5" }));
+        let tampered = quote_digest("m1", "ab", "text", &json!({ "text": "meet at 6" }));
+
+        assert_eq!(check_quote(Some(&digest), Some(&digest)), Some(QuoteStatus::Verified));
+        assert_eq!(check_quote(Some(&tampered), Some(&digest)), Some(QuoteStatus::Mismatch));
+        assert_eq!(check_quote(Some(&digest), None), Some(QuoteStatus::Unavailable));
+        assert_eq!(check_quote(None, Some(&digest)), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! Rate Limit - Per-sender flood protection for incoming messages
+//!
+//! Each sender gets a token bucket: a burst of `BURST` messages, refilled
+//! at `REFILL_PER_SEC`. A sender who empties their bucket is muted, and
+//! everything from them is dropped until the mute expires. Repeat
+//! offenders are muted for longer each time.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+/// Messages a sender can deliver in a burst
+const BURST: f64 = 30.0;
+
+/// Steady-state messages per second
+const REFILL_PER_SEC: f64 = 0.5;
+
+/// First mute, doubled for each repeat
+const BASE_MUTE_MS: i64 = 10 * 60 * 1000;
+const MAX_MUTE_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Forget quiet senders once this many are tracked
+const MAX_TRACKED_SENDERS: usize = 10_000;
+
+/// A sender quiet and unmuted for this long starts over
+const STRIKE_RESET_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Outcome of checking one incoming message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Allow,
+    /// Sender just got muted until this time (ms)
+    Muted { until: i64 },
+    /// Sender is already muted
+    Drop,
+}
+
+/// One sender's limiter state, for the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct SenderLimit {
+    pub public_key: String,
+    pub tokens: f64,
+    pub strikes: u32,
+    pub muted_until: Option<i64>,
+    pub dropped: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: f64,
+    updated_at: i64,
+    strikes: u32,
+    muted_until: i64,
+    dropped: u64,
+}
+
+impl Bucket {
+    fn new(now: i64) -> Self {
+        Self {
+            tokens: BURST,
+            updated_at: now,
+            strikes: 0,
+            muted_until: 0,
+            dropped: 0,
+        }
+    }
+
+    fn refill(&mut self, now: i64) {
+        let elapsed = (now - self.updated_at).max(0) as f64 / 1000.0;
+        self.tokens = (self.tokens + elapsed * REFILL_PER_SEC).min(BURST);
+        self.updated_at = now;
+    }
+
+    /// Quiet long enough to forget
+    fn is_idle(&self, now: i64) -> bool {
+        now - self.muted_until.max(self.updated_at) >= STRIKE_RESET_MS
+    }
+}
+
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a token for a message from `sender`
+    pub fn check(&self, sender: &str, now: i64) -> Admission {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_TRACKED_SENDERS {
+            buckets.retain(|_, bucket| !bucket.is_idle(now));
+        }
+        let bucket = buckets.entry(sender.to_lowercase()).or_insert_with(|| Bucket::new(now));
+
+        if now < bucket.muted_until {
+            bucket.dropped += 1;
+            return Admission::Drop;
+        }
+        if bucket.strikes > 0 && now - bucket.muted_until >= STRIKE_RESET_MS {
+            bucket.strikes = 0;
+        }
+
+        bucket.refill(now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Admission::Allow;
+        }
+
+        bucket.muted_until = now + mute_ms(bucket.strikes);
+        bucket.strikes += 1;
+        bucket.dropped += 1;
+        Admission::Muted { until: bucket.muted_until }
+    }
+
+    /// Senders that are muted or have used part of their burst
+    pub fn state(&self, now: i64) -> Vec<SenderLimit> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.retain(|_, bucket| !bucket.is_idle(now));
+
+        let mut senders: Vec<SenderLimit> = buckets
+            .iter_mut()
+            .filter_map(|(public_key, bucket)| {
+                bucket.refill(now);
+                let muted_until = Some(bucket.muted_until).filter(|until| *until > now);
+                (muted_until.is_some() || bucket.tokens < BURST).then(|| SenderLimit {
+                    public_key: public_key.clone(),
+                    tokens: bucket.tokens,
+                    strikes: bucket.strikes,
+                    muted_until,
+                    dropped: bucket.dropped,
+                })
+            })
+            .collect();
+        senders.sort_by(|a, b| b.muted_until.cmp(&a.muted_until).then(a.tokens.total_cmp(&b.tokens)));
+        senders
+    }
+}
+
+fn mute_ms(strikes: u32) -> i64 {
+    BASE_MUTE_MS.saturating_mul(1i64 << strikes.min(16)).min(MAX_MUTE_MS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_is_muted() {
+        let limiter = RateLimiter::new();
+        let now = 1_700_000_000_000;
+
+        for _ in 0..BURST as usize {
+            assert_eq!(limiter.check("aa", now), Admission::Allow);
+        }
+        assert_eq!(limiter.check("AA", now), Admission::Muted { until: now + BASE_MUTE_MS });
+        assert_eq!(limiter.check("aa", now + 1000), Admission::Drop);
+        // Other senders are unaffected
+        assert_eq!(limiter.check("bb", now), Admission::Allow);
+
+        let state = limiter.state(now + 1000);
+        assert_eq!(state[0].public_key, "aa");
+        assert_eq!(state[0].dropped, 2);
+
+        // After the mute the bucket has refilled; a second flood mutes longer
+        let later = now + BASE_MUTE_MS;
+        assert_eq!(limiter.check("aa", later), Admission::Allow);
+        for _ in 0..BURST as usize {
+            limiter.check("aa", later);
+        }
+        assert_eq!(limiter.check("aa", later), Admission::Drop);
+        assert_eq!(limiter.state(later)[0].muted_until, Some(later + 2 * BASE_MUTE_MS));
+    }
+
+    #[test]
+    fn test_steady_rate_is_allowed() {
+        let limiter = RateLimiter::new();
+        let now = 1_700_000_000_000;
+        let interval = (1000.0 / REFILL_PER_SEC) as i64;
+        for i in 0..200 {
+            assert_eq!(limiter.check("aa", now + i * interval), Admission::Allow);
+        }
+        assert!(limiter.state(now + STRIKE_RESET_MS * 2).is_empty());
+    }
+
+    #[test]
+    fn test_mute_backoff() {
+        assert_eq!(mute_ms(0), BASE_MUTE_MS);
+        assert_eq!(mute_ms(1), BASE_MUTE_MS * 2);
+        assert_eq!(mute_ms(30), MAX_MUTE_MS);
+    }
+}
@@ -0,0 +1,248 @@
+//! Resolver - Cached handle and identity lookups
+//!
+//! Sending needs the recipient's keys, which live behind `/handles/{h}`
+//! and `/identities/{pk}`. Lookups go through a local cache instead:
+//!
+//! - fresh entries (younger than `FRESH_TTL_MS`) are used as-is;
+//! - stale entries are used immediately and refreshed in the background,
+//!   so sends to known contacts keep working offline;
+//! - misses are fetched, and only then do network errors reach the caller.
+//!
+//! Every fetch is checked against the signed record at `/records/{pk}`:
+//! a record whose signature fails, or whose keys or handle disagree with
+//! the directory answer, is an error and never reaches the cache.
+//! Identities without a published record are cached but marked
+//! unverified.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::commands::handles::canonical_json;
+use crate::network::federation::FederatedHandle;
+use crate::network::{ApiClient, IdentityInfo, NetworkError, PublishedRecord};
+use crate::storage::DatabaseHandle;
+
+/// How long a lookup is used without refreshing (1 hour)
+pub const FRESH_TTL_MS: i64 = 60 * 60 * 1000;
+
+/// A cached directory answer
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedIdentity {
+    pub info: IdentityInfo,
+    /// Keys matched a validly signed published record
+    pub record_verified: bool,
+    /// When the directory was last asked (ms)
+    pub fetched_at: i64,
+}
+
+impl CachedIdentity {
+    pub fn is_fresh(&self, now: i64) -> bool {
+        now - self.fetched_at < FRESH_TTL_MS
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("{0}")]
+    Network(#[from] NetworkError),
+    #[error("Published record rejected: {0}")]
+    InvalidRecord(String),
+    #[error("Could not cache lookup: {0}")]
+    Cache(String),
+}
+
+/// What to look up
+#[derive(Debug, Clone)]
+enum Lookup {
+    Handle(String),
+    PublicKey(String),
+}
+
+/// Resolve a handle, from the cache when possible
+pub async fn resolve_handle(
+    api: &Arc<ApiClient>,
+    database: &DatabaseHandle,
+    handle: &str,
+) -> Result<Option<CachedIdentity>, ResolveError> {
+    let key = handle.to_string();
+    let cached = database.call(move |db| db.cached_handle(&key)).await;
+    resolve(api, database, Lookup::Handle(handle.to_string()), cached).await
+}
+
+/// Resolve a public key, from the cache when possible
+pub async fn resolve_identity(
+    api: &Arc<ApiClient>,
+    database: &DatabaseHandle,
+    public_key: &str,
+) -> Result<Option<CachedIdentity>, ResolveError> {
+    let key = public_key.to_string();
+    let cached = database.call(move |db| db.cached_identity(&key)).await;
+    resolve(api, database, Lookup::PublicKey(public_key.to_string()), cached).await
+}
+
+/// Ask the directory again, bypassing the cache
+pub async fn refresh_identity(
+    api: &ApiClient,
+    database: &DatabaseHandle,
+    public_key: &str,
+) -> Result<Option<CachedIdentity>, ResolveError> {
+    fetch_and_cache(api, database, &Lookup::PublicKey(public_key.to_string())).await
+}
+
+async fn resolve(
+    api: &Arc<ApiClient>,
+    database: &DatabaseHandle,
+    lookup: Lookup,
+    cached: Option<CachedIdentity>,
+) -> Result<Option<CachedIdentity>, ResolveError> {
+    let Some(cached) = cached else {
+        return fetch_and_cache(api, database, &lookup).await;
+    };
+
+    if !cached.is_fresh(chrono::Utc::now().timestamp_millis()) {
+        let (api, database) = (api.clone(), database.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = fetch_and_cache(&api, &database, &lookup).await {
+                tracing::debug!("Background refresh of {:?} failed: {}", lookup, e);
+            }
+        });
+    }
+    Ok(Some(cached))
+}
+
+async fn fetch_and_cache(
+    api: &ApiClient,
+    database: &DatabaseHandle,
+    lookup: &Lookup,
+) -> Result<Option<CachedIdentity>, ResolveError> {
+    let info = match lookup {
+        Lookup::Handle(handle) => api.resolve_handle(handle).await?,
+        Lookup::PublicKey(public_key) => api.get_identity(public_key).await?,
+    };
+    let Some(mut info) = info else {
+        return Ok(None);
+    };
+    if let Lookup::PublicKey(public_key) = lookup {
+        // Directory answers don't always echo the key back
+        if info.public_key.is_empty() {
+            info.public_key = public_key.clone();
+        }
+    }
+
+    let record = api.get_record(&info.public_key.to_lowercase()).await?;
+    // Federated names are vouched for by their domain, not the record
+    let expected_handle = match lookup {
+        Lookup::Handle(handle) if FederatedHandle::parse(handle).is_none() => Some(handle.as_str()),
+        _ => None,
+    };
+    let record_verified = match record {
+        Some(record) => {
+            check_record(&info, &record, expected_handle).map_err(ResolveError::InvalidRecord)?;
+            true
+        }
+        None => false,
+    };
+
+    let cached = CachedIdentity {
+        info,
+        record_verified,
+        fetched_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let entry = cached.clone();
+    database
+        .call(move |db| db.cache_resolution(&entry.info, entry.record_verified, entry.fetched_at))
+        .await
+        .map_err(|e| ResolveError::Cache(e.to_string()))?;
+    Ok(Some(cached))
+}
+
+/// Check a directory answer against the record its identity signed
+fn check_record(info: &IdentityInfo, record: &PublishedRecord, handle: Option<&str>) -> Result<(), String> {
+    let public_key = info.public_key.to_lowercase();
+    let signature = record.signature.as_deref().ok_or("record is unsigned")?;
+    let valid = gns_crypto_core::signing::verify_signature_hex(
+        &public_key,
+        canonical_json(&record.record_json).as_bytes(),
+        signature,
+    )
+    .unwrap_or(false);
+    if !valid {
+        return Err("signature does not verify".to_string());
+    }
+
+    let field = |name: &str| record.record_json.get(name).and_then(|v| v.as_str());
+    if field("identity").is_some_and(|pk| !pk.eq_ignore_ascii_case(&public_key)) {
+        return Err("record belongs to another identity".to_string());
+    }
+    if !field("encryption_key").is_some_and(|key| key.eq_ignore_ascii_case(&info.encryption_key)) {
+        return Err("encryption key differs from the record".to_string());
+    }
+    if let Some(handle) = handle {
+        let claimed = field("handle").map(|h| h.trim_start_matches('@'));
+        if !claimed.is_some_and(|h| h.eq_ignore_ascii_case(handle.trim_start_matches('@'))) {
+            return Err("handle is not claimed in the record".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::GnsIdentity;
+    use serde_json::json;
+
+    fn signed(identity: &GnsIdentity, record: serde_json::Value) -> PublishedRecord {
+        let signature = hex::encode(identity.sign_bytes(canonical_json(&record).as_bytes()));
+        PublishedRecord {
+            record_json: record,
+            signature: Some(signature),
+        }
+    }
+
+    fn info_for(identity: &GnsIdentity) -> IdentityInfo {
+        IdentityInfo {
+            public_key: identity.public_key_hex(),
+            encryption_key: identity.encryption_key_hex(),
+            pq_encryption_key: None,
+            handle: Some("alice".to_string()),
+            avatar_url: None,
+            display_name: None,
+            is_verified: false,
+            verifications: Vec::new(),
+            modules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_matching_record_passes() {
+        let alice = GnsIdentity::generate();
+        let record = signed(&alice, json!({
+            "identity": alice.public_key_hex(),
+            "encryption_key": alice.encryption_key_hex(),
+            "handle": "alice",
+        }));
+        assert!(check_record(&info_for(&alice), &record, Some("@Alice")).is_ok());
+    }
+
+    #[test]
+    fn test_mismatched_records_are_rejected() {
+        let (alice, mallory) = (GnsIdentity::generate(), GnsIdentity::generate());
+        let info = info_for(&alice);
+
+        // Directory hands out a key the record doesn't carry
+        let swapped = signed(&alice, json!({ "encryption_key": mallory.encryption_key_hex() }));
+        assert!(check_record(&info, &swapped, None).is_err());
+
+        // Signed by someone else
+        let forged = signed(&mallory, json!({ "encryption_key": alice.encryption_key_hex() }));
+        assert!(check_record(&info, &forged, None).is_err());
+
+        // Handle not claimed by this identity
+        let other = signed(&alice, json!({ "encryption_key": alice.encryption_key_hex(), "handle": "bob" }));
+        assert!(check_record(&info, &other, Some("alice")).is_err());
+        assert!(check_record(&info, &other, None).is_ok());
+    }
+}
@@ -0,0 +1,487 @@
+//! Rules - Automations run on incoming messages
+//!
+//! A rule pairs a trigger (who sent it, what payload type, a keyword in
+//! it) with actions: reply automatically, put the thread under a label,
+//! forward the message, raise a notification, or run a local script. The
+//! message handler hands every new inbox message to `apply`; junk,
+//! contact requests, our own devices' messages and messages whose
+//! signature doesn't verify never trigger rules, since their sender could
+//! be anyone.
+//!
+//! Replies and forwards made by a rule are marked with `AUTOMATED_FIELD`
+//! and never trigger replies or forwards in turn, so two clients with
+//! auto-replies don't answer each other forever. Scripts never run
+//! unattended: each run waits for the user to confirm it.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::message_handler::IncomingMessageEvent;
+use crate::storage::DatabaseHandle;
+use crate::AppState;
+
+/// Payload field marking a message sent by a rule
+pub const AUTOMATED_FIELD: &str = "automated";
+
+/// Longest rule name, in characters
+pub const MAX_RULE_NAME: usize = 60;
+
+/// Most actions one rule can take
+pub const MAX_ACTIONS: usize = 5;
+
+/// Longest auto-reply, in characters
+const MAX_REPLY_CHARS: usize = 2000;
+
+/// Unconfirmed script runs are dropped after this (ms)
+const SCRIPT_REQUEST_TTL_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A confirmed script is killed if it runs longer than this
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub trigger: RuleTrigger,
+    pub actions: Vec<RuleAction>,
+    pub created_at: i64,
+}
+
+/// Conditions a message must meet; every one given has to match
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleTrigger {
+    /// Sender's handle or public key
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub payload_type: Option<String>,
+    /// Found in the text, or an email's subject or body, ignoring case
+    #[serde(default)]
+    pub keyword: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    AutoReply { text: String },
+    MoveToLabel { label_id: String },
+    /// Send the message on to a handle or public key
+    Forward { to: String },
+    /// Notify even if the thread is muted; `title` replaces the sender
+    Notify {
+        #[serde(default)]
+        title: Option<String>,
+    },
+    /// Run an executable with the message as JSON on stdin, once the user
+    /// confirms
+    RunScript { path: String },
+}
+
+impl RuleTrigger {
+    pub fn matches(&self, event: &IncomingMessageEvent) -> bool {
+        if let Some(from) = &self.from {
+            let from = from.trim().trim_start_matches('@');
+            let sender = from.eq_ignore_ascii_case(&event.from_public_key)
+                || event
+                    .from_handle
+                    .as_deref()
+                    .is_some_and(|handle| from.eq_ignore_ascii_case(handle.trim_start_matches('@')));
+            if !sender {
+                return false;
+            }
+        }
+        if self.payload_type.as_ref().is_some_and(|t| *t != event.payload_type) {
+            return false;
+        }
+        if let Some(keyword) = &self.keyword {
+            let keyword = keyword.to_lowercase();
+            return ["text", "subject", "body"]
+                .iter()
+                .filter_map(|field| event.payload[*field].as_str())
+                .any(|text| text.to_lowercase().contains(&keyword));
+        }
+        true
+    }
+}
+
+impl Rule {
+    /// Check a rule before saving it; labels are checked by the caller
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_RULE_NAME {
+            return Err(format!("Rule names must be 1-{} characters", MAX_RULE_NAME));
+        }
+        let trigger = &self.trigger;
+        let conditions = [&trigger.from, &trigger.payload_type, &trigger.keyword];
+        if conditions.iter().any(|c| c.as_deref().is_some_and(|c| c.trim().is_empty())) {
+            return Err("Trigger conditions must not be empty".to_string());
+        }
+        if conditions.iter().all(|c| c.is_none()) {
+            return Err("A rule needs at least one trigger condition".to_string());
+        }
+        if self.actions.is_empty() || self.actions.len() > MAX_ACTIONS {
+            return Err(format!("A rule takes 1-{} actions", MAX_ACTIONS));
+        }
+        for action in &self.actions {
+            match action {
+                RuleAction::AutoReply { text } => {
+                    if text.trim().is_empty() || text.chars().count() > MAX_REPLY_CHARS {
+                        return Err(format!("Auto-replies must be 1-{} characters", MAX_REPLY_CHARS));
+                    }
+                }
+                RuleAction::Forward { to } => {
+                    if to.trim().trim_start_matches('@').is_empty() {
+                        return Err("Forwarding needs a recipient".to_string());
+                    }
+                }
+                RuleAction::RunScript { path } => {
+                    if !std::path::Path::new(path).is_absolute() {
+                        return Err("Script paths must be absolute".to_string());
+                    }
+                }
+                RuleAction::MoveToLabel { .. } | RuleAction::Notify { .. } => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a rule sent this message
+pub fn is_automated(event: &IncomingMessageEvent) -> bool {
+    event.payload[AUTOMATED_FIELD] == Value::Bool(true)
+}
+
+/// Whether a message is one rules run on
+pub fn triggers_rules(event: &IncomingMessageEvent, my_public_key: &str) -> bool {
+    event.signature_valid
+        && !event.is_junk
+        && !event.is_request
+        && !event.from_public_key.eq_ignore_ascii_case(my_public_key)
+}
+
+/// Run every enabled rule the message triggers, in the background
+pub fn apply<R: Runtime>(app_handle: &AppHandle<R>, database: &DatabaseHandle, event: &IncomingMessageEvent) {
+    let (app_handle, database, event) = (app_handle.clone(), database.clone(), event.clone());
+    tauri::async_runtime::spawn(async move {
+        let rules = match database.call(|db| db.list_rules()).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::warn!("Failed to load rules: {}", e);
+                return;
+            }
+        };
+        for rule in rules.iter().filter(|rule| rule.enabled && rule.trigger.matches(&event)) {
+            tracing::info!("Rule \"{}\" matched message {}", rule.name, event.id);
+            let _ = app_handle.emit("rule_matched", serde_json::json!({
+                "rule_id": rule.id,
+                "message_id": event.id,
+            }));
+            for action in &rule.actions {
+                if let Err(e) = run_action(&app_handle, &database, rule, action, &event).await {
+                    tracing::warn!("Rule \"{}\" failed: {}", rule.name, e);
+                }
+            }
+        }
+    });
+}
+
+async fn run_action<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    database: &DatabaseHandle,
+    rule: &Rule,
+    action: &RuleAction,
+    event: &IncomingMessageEvent,
+) -> Result<(), String> {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return Ok(());
+    };
+    match action {
+        RuleAction::AutoReply { text } => {
+            if is_automated(event) {
+                return Ok(());
+            }
+            crate::commands::messaging::send_message(
+                None,
+                Some(event.from_public_key.clone()),
+                "text/plain".to_string(),
+                serde_json::json!({ "text": text, AUTOMATED_FIELD: true }),
+                event.thread_id.clone(),
+                Some(event.id.clone()),
+                app_handle.clone(),
+                state,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        RuleAction::MoveToLabel { label_id } => {
+            let (thread_id, label_id) = (event.thread_id.clone().unwrap_or_default(), label_id.clone());
+            database
+                .call(move |db| {
+                    if db.get_label(&label_id).is_none() {
+                        return Err("Label not found".to_string());
+                    }
+                    db.set_thread_label(&thread_id, &label_id, true).map_err(|e| e.to_string())
+                })
+                .await?;
+        }
+        RuleAction::Forward { to } => {
+            if is_automated(event) {
+                return Ok(());
+            }
+            let to = to.trim().trim_start_matches('@');
+            let is_public_key = to.len() == 64 && to.chars().all(|c| c.is_ascii_hexdigit());
+            let (handle, public_key) = if is_public_key {
+                (None, Some(to.to_string()))
+            } else {
+                (Some(to.to_lowercase()), None)
+            };
+            let mut payload = event.payload.clone();
+            if let Some(fields) = payload.as_object_mut() {
+                fields.insert(AUTOMATED_FIELD.to_string(), Value::Bool(true));
+            }
+            let sent = crate::commands::messaging::send_message(
+                handle,
+                public_key,
+                event.payload_type.clone(),
+                payload,
+                None,
+                None,
+                app_handle.clone(),
+                state,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            let original = event.id.clone();
+            database
+                .call(move |db| db.set_forwarded_from(&sent.message_id, &original))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        RuleAction::Notify { title } => {
+            let title = title
+                .clone()
+                .unwrap_or_else(|| crate::push::notification_title(event.from_handle.as_deref(), &event.from_public_key));
+            let body = crate::push::notification_preview(&event.payload_type, &event.payload);
+            app_handle
+                .notification()
+                .builder()
+                .title(title)
+                .body(body)
+                .show()
+                .map_err(|e| e.to_string())?;
+        }
+        RuleAction::RunScript { path } => {
+            let request = state.rule_scripts.request(rule, path, event, chrono::Utc::now().timestamp_millis())?;
+            let _ = app_handle.emit("rule_script_confirm", &request);
+        }
+    }
+    Ok(())
+}
+
+/// A script run waiting for the user to allow it
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptRequest {
+    pub id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub path: String,
+    pub message_id: String,
+    pub from_public_key: String,
+    pub from_handle: Option<String>,
+    pub requested_at: i64,
+    /// The message, written to the script's stdin
+    #[serde(skip)]
+    input: String,
+}
+
+/// How a confirmed script finished
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptOutcome {
+    /// None if it was killed or timed out
+    pub exit_code: Option<i32>,
+}
+
+/// Script runs waiting for confirmation
+#[derive(Default)]
+pub struct ScriptRequests {
+    pending: Mutex<HashMap<String, ScriptRequest>>,
+}
+
+impl ScriptRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, HashMap<String, ScriptRequest>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue a run for confirmation
+    pub fn request(&self, rule: &Rule, path: &str, event: &IncomingMessageEvent, now: i64) -> Result<ScriptRequest, String> {
+        let request = ScriptRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            path: path.to_string(),
+            message_id: event.id.clone(),
+            from_public_key: event.from_public_key.clone(),
+            from_handle: event.from_handle.clone(),
+            requested_at: now,
+            input: serde_json::to_string(event).map_err(|e| e.to_string())?,
+        };
+        let mut pending = self.pending();
+        pending.retain(|_, r| now - r.requested_at < SCRIPT_REQUEST_TTL_MS);
+        pending.insert(request.id.clone(), request.clone());
+        Ok(request)
+    }
+
+    /// Runs still waiting, oldest first
+    pub fn list(&self, now: i64) -> Vec<ScriptRequest> {
+        let mut requests: Vec<ScriptRequest> = self
+            .pending()
+            .values()
+            .filter(|r| now - r.requested_at < SCRIPT_REQUEST_TTL_MS)
+            .cloned()
+            .collect();
+        requests.sort_by_key(|r| r.requested_at);
+        requests
+    }
+
+    /// Remove a request, to run or to drop it
+    pub fn take(&self, id: &str, now: i64) -> Option<ScriptRequest> {
+        self.pending()
+            .remove(id)
+            .filter(|r| now - r.requested_at < SCRIPT_REQUEST_TTL_MS)
+    }
+}
+
+/// Run a confirmed script with the message on stdin
+pub async fn run_script(request: &ScriptRequest) -> Result<ScriptOutcome, String> {
+    tracing::info!("Running script {} for rule \"{}\"", request.path, request.rule_name);
+    let mut child = tokio::process::Command::new(&request.path)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", request.path, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A script that doesn't read its input is fine
+        let _ = stdin.write_all(request.input.as_bytes()).await;
+    }
+    let exit_code = match tokio::time::timeout(SCRIPT_TIMEOUT, child.wait()).await {
+        Ok(status) => status.map_err(|e| e.to_string())?.code(),
+        Err(_) => {
+            tracing::warn!("Script {} timed out", request.path);
+            None
+        }
+    };
+    Ok(ScriptOutcome { exit_code })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(payload: Value) -> IncomingMessageEvent {
+        IncomingMessageEvent {
+            id: "m1".to_string(),
+            thread_id: Some("t1".to_string()),
+            from_public_key: "ab".repeat(32),
+            from_handle: Some("alice".to_string()),
+            payload_type: "text/plain".to_string(),
+            payload,
+            timestamp: 1_000,
+            signature_valid: true,
+            is_junk: false,
+            is_request: false,
+            quote_status: None,
+        }
+    }
+
+    fn rule(trigger: RuleTrigger, actions: Vec<RuleAction>) -> Rule {
+        Rule {
+            id: "r1".to_string(),
+            name: "Invoices".to_string(),
+            enabled: true,
+            trigger,
+            actions,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_trigger_matches() {
+        let message = event(json!({ "text": "Your INVOICE is ready" }));
+        let trigger = |from: Option<&str>, payload_type: Option<&str>, keyword: Option<&str>| RuleTrigger {
+            from: from.map(String::from),
+            payload_type: payload_type.map(String::from),
+            keyword: keyword.map(String::from),
+        };
+
+        assert!(trigger(Some("@Alice"), None, None).matches(&message));
+        assert!(trigger(Some(&"AB".repeat(32)), None, None).matches(&message));
+        assert!(!trigger(Some("bob"), None, None).matches(&message));
+        assert!(trigger(None, Some("text/plain"), Some("invoice")).matches(&message));
+        assert!(!trigger(None, Some("gns/email"), Some("invoice")).matches(&message));
+        assert!(!trigger(Some("alice"), None, Some("refund")).matches(&message));
+
+        let email = event(json!({ "subject": "Hello", "body": "invoice attached" }));
+        assert!(trigger(None, None, Some("Invoice")).matches(&email));
+    }
+
+    #[test]
+    fn test_triggers_rules() {
+        let my_pk = "cd".repeat(32);
+        let message = event(json!({ "text": "invoice" }));
+        assert!(triggers_rules(&message, &my_pk));
+
+        // A forged sender matches `from: alice` but must not fire anything
+        let forged = IncomingMessageEvent { signature_valid: false, ..message.clone() };
+        assert!(RuleTrigger { from: Some("alice".to_string()), ..Default::default() }.matches(&forged));
+        assert!(!triggers_rules(&forged, &my_pk));
+
+        assert!(!triggers_rules(&IncomingMessageEvent { is_junk: true, ..message.clone() }, &my_pk));
+        assert!(!triggers_rules(&IncomingMessageEvent { is_request: true, ..message.clone() }, &my_pk));
+        assert!(!triggers_rules(&message, &message.from_public_key));
+    }
+
+    #[test]
+    fn test_validate() {
+        let keyword = RuleTrigger { keyword: Some("invoice".to_string()), ..Default::default() };
+        let notify = RuleAction::Notify { title: None };
+        assert!(rule(keyword.clone(), vec![notify.clone()]).validate().is_ok());
+
+        assert!(rule(RuleTrigger::default(), vec![notify.clone()]).validate().is_err());
+        assert!(rule(RuleTrigger { keyword: Some(" ".to_string()), ..Default::default() }, vec![notify])
+            .validate()
+            .is_err());
+        assert!(rule(keyword.clone(), vec![]).validate().is_err());
+        assert!(rule(keyword.clone(), vec![RuleAction::AutoReply { text: "".to_string() }]).validate().is_err());
+        assert!(rule(keyword, vec![RuleAction::RunScript { path: "notify.sh".to_string() }]).validate().is_err());
+
+        let action: RuleAction = serde_json::from_value(json!({ "type": "move_to_label", "label_id": "l1" })).unwrap();
+        assert_eq!(action, RuleAction::MoveToLabel { label_id: "l1".to_string() });
+    }
+
+    #[test]
+    fn test_script_requests() {
+        let requests = ScriptRequests::new();
+        let rule = rule(RuleTrigger::default(), vec![]);
+        let request = requests.request(&rule, "/usr/local/bin/notify", &event(json!({ "text": "hi" })), 1_000).unwrap();
+        assert_eq!(requests.list(2_000).len(), 1);
+
+        assert!(requests.take(&request.id, 1_000 + SCRIPT_REQUEST_TTL_MS).is_none());
+        let request = requests.request(&rule, "/usr/local/bin/notify", &event(json!({})), 1_000).unwrap();
+        assert_eq!(requests.take(&request.id, 2_000).unwrap().path, "/usr/local/bin/notify");
+        assert!(requests.list(2_000).is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+//! Global Shortcut - Quick-compose hotkey (desktop only)
+//!
+//! A configurable system-wide hotkey opens a small always-on-top compose
+//! window that sends with `quick_send`, without bringing up or navigating
+//! the main window.
+
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+pub const QUICK_COMPOSE_LABEL: &str = "quick-compose";
+
+/// Parse a shortcut like `CommandOrControl+Shift+M`
+pub fn parse_shortcut(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .trim()
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", shortcut, e))
+}
+
+/// Replace the quick-compose hotkey; `None` just removes it
+pub fn register_quick_compose(app: &AppHandle, shortcut: Option<&str>) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| e.to_string())?;
+
+    if let Some(shortcut) = shortcut {
+        shortcuts
+            .register(parse_shortcut(shortcut)?)
+            .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut, e))?;
+        tracing::info!("Quick compose shortcut: {}", shortcut);
+    }
+    Ok(())
+}
+
+/// Show the quick-compose window, creating it on first use
+pub fn open_quick_compose(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(QUICK_COMPOSE_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+
+    let result = WebviewWindowBuilder::new(
+        app,
+        QUICK_COMPOSE_LABEL,
+        WebviewUrl::App("index.html#/quick-compose".into()),
+    )
+    .title("Quick message")
+    .inner_size(420.0, 260.0)
+    .resizable(false)
+    .always_on_top(true)
+    .center()
+    .build();
+
+    if let Err(e) = result {
+        tracing::error!("Failed to open quick compose window: {}", e);
+    }
+}
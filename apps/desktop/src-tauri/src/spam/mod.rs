@@ -0,0 +1,185 @@
+//! Spam - Sort first messages from strangers into requests / junk
+//!
+//! A new thread from someone who isn't a contact is scored from what we
+//! know about the sender: whether the directory has a handle for the key,
+//! whether the claimed handle matches it, verified accounts, and a few
+//! first-contact content heuristics. Threads scoring `JUNK_THRESHOLD` or
+//! more start in the junk folder. The user's own verdicts (`accept_request`
+//! / `mark_spam`) override the score for that sender from then on.
+
+use serde::{Deserialize, Serialize};
+
+/// Score at which a new thread goes to junk
+pub const JUNK_THRESHOLD: u32 = 40;
+
+/// The user's standing decision about a sender
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SenderVerdict {
+    Accepted,
+    Spam,
+}
+
+impl SenderVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Spam => "spam",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "accepted" => Some(Self::Accepted),
+            "spam" => Some(Self::Spam),
+            _ => None,
+        }
+    }
+}
+
+/// What we know about the sender of a first message
+#[derive(Debug, Clone, Default)]
+pub struct SenderSignals {
+    pub verdict: Option<SenderVerdict>,
+    /// We've written to them or verified their key
+    pub is_contact: bool,
+    pub signature_valid: bool,
+    /// Whether the directory lookup succeeded; a failed lookup isn't held
+    /// against the sender
+    pub directory_checked: bool,
+    /// Handle the directory has for this key
+    pub registered_handle: Option<String>,
+    /// Handle the envelope claims
+    pub claimed_handle: Option<String>,
+    /// Directory-verified identity or accounts
+    pub is_verified: bool,
+}
+
+/// Classifier result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpamScore {
+    pub score: u32,
+    pub junk: bool,
+    pub reasons: Vec<&'static str>,
+}
+
+/// Score a first message from a sender
+pub fn classify(signals: &SenderSignals, text: &str) -> SpamScore {
+    match signals.verdict {
+        Some(SenderVerdict::Spam) => return verdict(100, "marked as spam"),
+        Some(SenderVerdict::Accepted) => return verdict(0, "accepted"),
+        None if signals.is_contact => return verdict(0, "contact"),
+        None => {}
+    }
+
+    let mut score = 0;
+    let mut reasons = Vec::new();
+    let mut add = |points, reason| {
+        score += points;
+        reasons.push(reason);
+    };
+
+    if !signals.signature_valid {
+        add(50, "invalid signature");
+    }
+    if signals.directory_checked {
+        match (&signals.registered_handle, &signals.claimed_handle) {
+            (None, _) => add(25, "no registered handle"),
+            (Some(registered), Some(claimed)) if !same_handle(registered, claimed) => {
+                add(40, "claimed handle doesn't match")
+            }
+            _ => {}
+        }
+        if !signals.is_verified {
+            add(20, "no verified accounts");
+        }
+    }
+
+    let links = count_links(text);
+    if links > 0 {
+        add(15 * links.min(2), "contains links");
+    }
+    if is_shouting(text) {
+        add(10, "mostly capitals");
+    }
+
+    SpamScore {
+        score,
+        junk: score >= JUNK_THRESHOLD,
+        reasons,
+    }
+}
+
+fn verdict(score: u32, reason: &'static str) -> SpamScore {
+    SpamScore {
+        score,
+        junk: score >= JUNK_THRESHOLD,
+        reasons: vec![reason],
+    }
+}
+
+fn same_handle(a: &str, b: &str) -> bool {
+    a.trim_start_matches('@').eq_ignore_ascii_case(b.trim_start_matches('@'))
+}
+
+fn count_links(text: &str) -> u32 {
+    text.split_whitespace()
+        .filter(|word| {
+            let word = word.to_ascii_lowercase();
+            word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+        })
+        .count() as u32
+}
+
+fn is_shouting(text: &str) -> bool {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    letters.len() >= 20 && letters.iter().filter(|c| c.is_uppercase()).count() * 10 >= letters.len() * 7
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stranger() -> SenderSignals {
+        SenderSignals {
+            signature_valid: true,
+            directory_checked: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_contacts_and_verdicts() {
+        let contact = SenderSignals { is_contact: true, ..stranger() };
+        assert!(!classify(&contact, "http://a http://b").junk);
+
+        let spammer = SenderSignals { is_contact: true, verdict: Some(SenderVerdict::Spam), ..stranger() };
+        assert!(classify(&spammer, "hi").junk);
+    }
+
+    #[test]
+    fn test_strangers() {
+        // No handle and nothing verified: zero trust signal
+        let score = classify(&stranger(), "hello");
+        assert!(score.junk);
+        assert_eq!(score.reasons, vec!["no registered handle", "no verified accounts"]);
+
+        let with_handle = SenderSignals { registered_handle: Some("alice".to_string()), ..stranger() };
+        assert!(!classify(&with_handle, "hello").junk);
+        assert!(classify(&with_handle, "WIN NOW http://x.io www.y.io").junk);
+
+        let spoofed = SenderSignals { claimed_handle: Some("@bank".to_string()), ..with_handle.clone() };
+        assert!(classify(&spoofed, "hello").junk);
+
+        // Directory unreachable: judge on content alone
+        let offline = SenderSignals { directory_checked: false, ..stranger() };
+        assert!(!classify(&offline, "hello").junk);
+    }
+
+    #[test]
+    fn test_heuristics() {
+        assert_eq!(count_links("see https://a.io and WWW.b.io"), 2);
+        assert!(is_shouting("CLAIM YOUR FREE TOKENS TODAY"));
+        assert!(!is_shouting("OK"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Earnings Ledger - Which breadcrumb epochs have been rewarded
+//!
+//! GNS rewards are earned per completed epoch of breadcrumbs (see
+//! `gns_crypto_core::epoch`). Each successful `claim_gns_tokens` records
+//! the epochs it covered, so a claim with no newly completed epochs is
+//! refused locally instead of reaching the backend as a double-claim.
+//!
+//! Epochs are matched by Merkle root rather than position: a restore
+//! merge, quarantine or pruning renumbers them, but the same breadcrumbs
+//! always hash to the same root.
+
+use std::collections::HashSet;
+
+use gns_crypto_core::epoch::EPOCH_SIZE;
+use gns_crypto_core::EpochRoot;
+use serde::Serialize;
+
+/// One recorded claim
+#[derive(Debug, Clone, Serialize)]
+pub struct GnsClaim {
+    pub id: i64,
+    pub tx_hash: Option<String>,
+    /// GNS in the claimable balances at the time of the claim
+    pub amount: f64,
+    pub epochs: Vec<u64>,
+    pub claimed_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EarningsSummary {
+    pub total_claimed: f64,
+    pub claims: Vec<GnsClaim>,
+    pub rewarded_epochs: u32,
+    /// Completed epochs not yet claimed
+    pub unclaimed_epochs: u32,
+    /// Breadcrumbs still needed to complete the current epoch
+    pub breadcrumbs_to_next_epoch: u32,
+}
+
+/// Completed epochs whose root hasn't been rewarded yet
+pub fn unrewarded_epochs(epochs: &[EpochRoot], rewarded: &HashSet<String>) -> Vec<EpochRoot> {
+    epochs
+        .iter()
+        .filter(|e| e.breadcrumb_count as usize == EPOCH_SIZE && !rewarded.contains(&e.root))
+        .cloned()
+        .collect()
+}
+
+/// Breadcrumbs still needed before the next epoch completes
+pub fn breadcrumbs_to_next_epoch(breadcrumb_count: usize) -> u32 {
+    (EPOCH_SIZE - breadcrumb_count % EPOCH_SIZE) as u32
+}
+
+/// Why a claim was refused locally
+pub fn nothing_to_claim(rewarded: &HashSet<String>, breadcrumb_count: usize) -> String {
+    let remaining = breadcrumbs_to_next_epoch(breadcrumb_count);
+    if rewarded.is_empty() {
+        format!("No completed breadcrumb epoch to claim yet: {} more breadcrumbs needed", remaining)
+    } else {
+        format!(
+            "Already claimed: all {} completed breadcrumb epochs were rewarded. The next one completes after {} more breadcrumbs",
+            rewarded.len(),
+            remaining
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn epoch(epoch: u64, breadcrumb_count: u32) -> EpochRoot {
+        EpochRoot {
+            epoch,
+            root: format!("{:064x}", epoch),
+            breadcrumb_count,
+            start_time: 0,
+            end_time: 0,
+        }
+    }
+
+    fn roots(epochs: &[EpochRoot]) -> HashSet<String> {
+        epochs.iter().map(|e| e.root.clone()).collect()
+    }
+
+    #[test]
+    fn test_unrewarded_epochs() {
+        let epochs = [epoch(0, 100), epoch(1, 100), epoch(2, 40)];
+        let rewarded = roots(&epochs[..1]);
+        let open: Vec<u64> = unrewarded_epochs(&epochs, &rewarded).iter().map(|e| e.epoch).collect();
+        // The partial epoch isn't claimable yet
+        assert_eq!(open, vec![1]);
+        assert!(unrewarded_epochs(&epochs, &roots(&epochs[..2])).is_empty());
+        assert_eq!(breadcrumbs_to_next_epoch(240), 60);
+        assert!(nothing_to_claim(&roots(&epochs[..2]), 240).starts_with("Already claimed"));
+    }
+
+    #[test]
+    fn test_renumbered_epochs_stay_rewarded() {
+        let rewarded = roots(&[epoch(1, 100)]);
+        // After a merge the rewarded breadcrumbs now form epoch 2
+        let shifted = EpochRoot { epoch: 2, ..epoch(1, 100) };
+        let epochs = [epoch(0, 100), epoch(5, 100), shifted];
+        let open: Vec<u64> = unrewarded_epochs(&epochs, &rewarded).iter().map(|e| e.epoch).collect();
+        assert_eq!(open, vec![0, 5]);
+    }
+}
@@ -0,0 +1,419 @@
+//! Transaction Signers
+//!
+//! Abstracts over who produces the Ed25519 signature for a Stellar
+//! transaction:
+//! - `InAppSigner` uses the GNS identity key held in the keychain
+//! - `LedgerSigner` talks to the Ledger Stellar app over USB/HID (desktop only)
+//!
+//! Which signer is used is decided per account by `HardwareSigningConfig`.
+
+// APDU helpers are only reachable from the desktop Ledger transport
+#![cfg_attr(any(target_os = "ios", target_os = "android"), allow(dead_code))]
+
+use serde::{Deserialize, Serialize};
+use gns_crypto_core::GnsIdentity;
+
+use super::StellarError;
+
+/// Ledger USB vendor id
+pub const LEDGER_VENDOR_ID: u16 = 0x2c97;
+
+/// Default BIP32 account used by the Ledger Stellar app (m/44'/148'/0')
+pub const DEFAULT_ACCOUNT_INDEX: u32 = 0;
+
+// ==================== SIGNER TRAIT ====================
+
+/// Something that can sign a Stellar transaction signature payload
+pub trait TransactionSigner {
+    /// Raw Ed25519 public key of the signing account
+    fn public_key(&self) -> Result<[u8; 32], StellarError>;
+
+    /// Sign a transaction.
+    ///
+    /// `payload` is the XDR-encoded `TransactionSignaturePayload` and
+    /// `payload_hash` its SHA-256. In-app keys sign the hash directly;
+    /// hardware devices receive the full payload so they can display
+    /// the transaction details before the user confirms.
+    fn sign_payload(&self, payload: &[u8], payload_hash: &[u8; 32]) -> Result<[u8; 64], StellarError>;
+}
+
+// ==================== CONFIGURATION ====================
+
+/// Per-account signing preference
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HardwareSigningConfig {
+    /// Sign Stellar transactions with a hardware wallet instead of the in-app key
+    pub use_hardware: bool,
+    /// HID path of the preferred device (first Ledger found if None)
+    pub device_id: Option<String>,
+    /// Ledger account index (m/44'/148'/index')
+    #[serde(default)]
+    pub account_index: u32,
+}
+
+/// A connected hardware wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareWalletInfo {
+    pub device_id: String,
+    pub vendor: String,
+    pub model: String,
+    pub serial_number: Option<String>,
+}
+
+// ==================== IN-APP SIGNER ====================
+
+/// Signs with the GNS identity key
+pub struct InAppSigner {
+    identity: GnsIdentity,
+}
+
+impl InAppSigner {
+    pub fn from_private_key_bytes(private_key_bytes: &[u8]) -> Result<Self, StellarError> {
+        let identity = GnsIdentity::from_hex(&hex::encode(private_key_bytes))
+            .map_err(|_| StellarError::Validation("Invalid identity".to_string()))?;
+        Ok(Self { identity })
+    }
+}
+
+impl TransactionSigner for InAppSigner {
+    fn public_key(&self) -> Result<[u8; 32], StellarError> {
+        Ok(self.identity.public_key_bytes())
+    }
+
+    fn sign_payload(&self, _payload: &[u8], payload_hash: &[u8; 32]) -> Result<[u8; 64], StellarError> {
+        // Stellar signs the SHA256 of the signature payload, not the payload itself
+        Ok(self.identity.sign_bytes(payload_hash))
+    }
+}
+
+// ==================== LEDGER APDU ====================
+
+const CLA: u8 = 0xe0;
+const INS_GET_PK: u8 = 0x02;
+const INS_SIGN_TX: u8 = 0x04;
+const P1_FIRST: u8 = 0x00;
+const P1_MORE: u8 = 0x80;
+const P2_LAST: u8 = 0x00;
+const P2_MORE: u8 = 0x80;
+const P2_NO_CONFIRM: u8 = 0x00;
+
+/// Max APDU data length accepted by the Stellar app
+const APDU_MAX_DATA: usize = 150;
+
+/// HID packet size used by Ledger devices
+const HID_PACKET_SIZE: usize = 64;
+const HID_CHANNEL: u16 = 0x0101;
+const HID_TAG_APDU: u8 = 0x05;
+
+const SW_OK: u16 = 0x9000;
+const SW_DENIED: u16 = 0x6985;
+
+/// Encode the BIP32 path m/44'/148'/account' as expected by the Stellar app
+fn bip32_path(account_index: u32) -> Vec<u8> {
+    const HARDENED: u32 = 0x8000_0000;
+    let components = [44 | HARDENED, 148 | HARDENED, account_index | HARDENED];
+
+    let mut out = Vec::with_capacity(1 + components.len() * 4);
+    out.push(components.len() as u8);
+    for c in components {
+        out.extend_from_slice(&c.to_be_bytes());
+    }
+    out
+}
+
+fn build_apdu(ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![CLA, ins, p1, p2, data.len() as u8];
+    apdu.extend_from_slice(data);
+    apdu
+}
+
+/// Split a transaction payload into the APDU sequence for INS_SIGN_TX.
+/// The first chunk is prefixed with the derivation path.
+fn sign_tx_apdus(account_index: u32, payload: &[u8]) -> Vec<Vec<u8>> {
+    let mut data = bip32_path(account_index);
+    data.extend_from_slice(payload);
+
+    let chunks: Vec<&[u8]> = data.chunks(APDU_MAX_DATA).collect();
+    let last = chunks.len() - 1;
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let p1 = if i == 0 { P1_FIRST } else { P1_MORE };
+            let p2 = if i == last { P2_LAST } else { P2_MORE };
+            build_apdu(INS_SIGN_TX, p1, p2, chunk)
+        })
+        .collect()
+}
+
+/// Frame an APDU into Ledger HID packets
+fn frame_apdu(apdu: &[u8]) -> Vec<[u8; HID_PACKET_SIZE]> {
+    let mut packets = Vec::new();
+    let mut offset = 0;
+    let mut sequence: u16 = 0;
+
+    while offset < apdu.len() || sequence == 0 {
+        let mut packet = [0u8; HID_PACKET_SIZE];
+        packet[0..2].copy_from_slice(&HID_CHANNEL.to_be_bytes());
+        packet[2] = HID_TAG_APDU;
+        packet[3..5].copy_from_slice(&sequence.to_be_bytes());
+
+        let mut header = 5;
+        if sequence == 0 {
+            packet[5..7].copy_from_slice(&(apdu.len() as u16).to_be_bytes());
+            header = 7;
+        }
+
+        let take = (HID_PACKET_SIZE - header).min(apdu.len() - offset);
+        packet[header..header + take].copy_from_slice(&apdu[offset..offset + take]);
+
+        offset += take;
+        sequence += 1;
+        packets.push(packet);
+    }
+
+    packets
+}
+
+/// Split a device response into data and status word
+fn split_status(response: &[u8]) -> Result<(&[u8], u16), StellarError> {
+    if response.len() < 2 {
+        return Err(StellarError::HardwareWallet("Truncated device response".to_string()));
+    }
+    let (data, sw) = response.split_at(response.len() - 2);
+    Ok((data, u16::from_be_bytes([sw[0], sw[1]])))
+}
+
+fn check_status(sw: u16) -> Result<(), StellarError> {
+    match sw {
+        SW_OK => Ok(()),
+        SW_DENIED => Err(StellarError::HardwareWallet("Transaction rejected on device".to_string())),
+        0x6d00 | 0x6e00 | 0x6511 => Err(StellarError::HardwareWallet(
+            "Open the Stellar app on your Ledger".to_string(),
+        )),
+        0x5515 => Err(StellarError::HardwareWallet("Ledger is locked".to_string())),
+        other => Err(StellarError::HardwareWallet(format!("Device error 0x{:04x}", other))),
+    }
+}
+
+// ==================== LEDGER SIGNER (DESKTOP) ====================
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+mod ledger {
+    use super::*;
+    use hidapi::{HidApi, HidDevice};
+
+    const READ_TIMEOUT_MS: i32 = 60_000;
+
+    /// List connected Ledger devices
+    pub fn list_devices() -> Result<Vec<HardwareWalletInfo>, StellarError> {
+        let api = HidApi::new().map_err(|e| StellarError::HardwareWallet(e.to_string()))?;
+
+        Ok(api
+            .device_list()
+            .filter(|d| d.vendor_id() == LEDGER_VENDOR_ID)
+            // The APDU interface is interface 0 / usage page 0xffa0
+            .filter(|d| d.interface_number() == 0 || d.usage_page() == 0xffa0)
+            .map(|d| HardwareWalletInfo {
+                device_id: d.path().to_string_lossy().into_owned(),
+                vendor: d.manufacturer_string().unwrap_or("Ledger").to_string(),
+                model: d.product_string().unwrap_or("Ledger").to_string(),
+                serial_number: d.serial_number().map(|s| s.to_string()),
+            })
+            .collect())
+    }
+
+    /// Signs through the Ledger Stellar app
+    pub struct LedgerSigner {
+        device: HidDevice,
+        account_index: u32,
+    }
+
+    impl LedgerSigner {
+        /// Open a device by id, or the first Ledger found
+        pub fn open(device_id: Option<&str>, account_index: u32) -> Result<Self, StellarError> {
+            let api = HidApi::new().map_err(|e| StellarError::HardwareWallet(e.to_string()))?;
+
+            let path = match device_id {
+                Some(id) => std::ffi::CString::new(id)
+                    .map_err(|_| StellarError::Validation("Invalid device id".to_string()))?,
+                None => {
+                    let first = list_devices()?
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| StellarError::HardwareWallet("No Ledger device connected".to_string()))?;
+                    std::ffi::CString::new(first.device_id)
+                        .map_err(|_| StellarError::Validation("Invalid device id".to_string()))?
+                }
+            };
+
+            let device = api
+                .open_path(&path)
+                .map_err(|e| StellarError::HardwareWallet(format!("Failed to open device: {}", e)))?;
+
+            Ok(Self { device, account_index })
+        }
+
+        fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>, StellarError> {
+            for packet in frame_apdu(apdu) {
+                // hidapi expects a leading report id
+                let mut report = Vec::with_capacity(HID_PACKET_SIZE + 1);
+                report.push(0x00);
+                report.extend_from_slice(&packet);
+                self.device
+                    .write(&report)
+                    .map_err(|e| StellarError::HardwareWallet(format!("HID write failed: {}", e)))?;
+            }
+
+            let mut response = Vec::new();
+            let mut expected_len: Option<usize> = None;
+            let mut sequence: u16 = 0;
+
+            loop {
+                let mut packet = [0u8; HID_PACKET_SIZE];
+                let read = self
+                    .device
+                    .read_timeout(&mut packet, READ_TIMEOUT_MS)
+                    .map_err(|e| StellarError::HardwareWallet(format!("HID read failed: {}", e)))?;
+                if read == 0 {
+                    return Err(StellarError::HardwareWallet("Timed out waiting for device".to_string()));
+                }
+                if packet[2] != HID_TAG_APDU || u16::from_be_bytes([packet[3], packet[4]]) != sequence {
+                    return Err(StellarError::HardwareWallet("Unexpected HID packet".to_string()));
+                }
+
+                let body = if sequence == 0 {
+                    expected_len = Some(u16::from_be_bytes([packet[5], packet[6]]) as usize);
+                    &packet[7..]
+                } else {
+                    &packet[5..]
+                };
+
+                let total = expected_len.unwrap_or(0);
+                let take = (total - response.len()).min(body.len());
+                response.extend_from_slice(&body[..take]);
+                sequence += 1;
+
+                if response.len() >= total {
+                    return Ok(response);
+                }
+            }
+        }
+    }
+
+    impl TransactionSigner for LedgerSigner {
+        fn public_key(&self) -> Result<[u8; 32], StellarError> {
+            let apdu = build_apdu(INS_GET_PK, 0x00, P2_NO_CONFIRM, &bip32_path(self.account_index));
+            let response = self.exchange(&apdu)?;
+            let (data, sw) = split_status(&response)?;
+            check_status(sw)?;
+
+            data.get(..32)
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| StellarError::HardwareWallet("Invalid public key from device".to_string()))
+        }
+
+        fn sign_payload(&self, payload: &[u8], _payload_hash: &[u8; 32]) -> Result<[u8; 64], StellarError> {
+            let mut signature = None;
+
+            for apdu in sign_tx_apdus(self.account_index, payload) {
+                let response = self.exchange(&apdu)?;
+                let (data, sw) = split_status(&response)?;
+                check_status(sw)?;
+                if !data.is_empty() {
+                    signature = Some(data.to_vec());
+                }
+            }
+
+            signature
+                .and_then(|s| s.try_into().ok())
+                .ok_or_else(|| StellarError::HardwareWallet("Invalid signature from device".to_string()))
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+pub use ledger::{list_devices, LedgerSigner};
+
+/// Hardware wallets are not available over USB on mobile
+#[cfg(any(target_os = "ios", target_os = "android"))]
+pub fn list_devices() -> Result<Vec<HardwareWalletInfo>, StellarError> {
+    Ok(vec![])
+}
+
+/// Open the signer described by a config (desktop only)
+pub fn open_hardware_signer(config: &HardwareSigningConfig) -> Result<Box<dyn TransactionSigner>, StellarError> {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        let signer = LedgerSigner::open(config.device_id.as_deref(), config.account_index)?;
+        Ok(Box::new(signer))
+    }
+
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    {
+        let _ = config;
+        Err(StellarError::HardwareWallet(
+            "Hardware wallets are only supported on desktop".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bip32_path() {
+        let path = bip32_path(0);
+        assert_eq!(path.len(), 13);
+        assert_eq!(path[0], 3);
+        assert_eq!(&path[1..5], &[0x80, 0x00, 0x00, 0x2c]);
+        assert_eq!(&path[5..9], &[0x80, 0x00, 0x00, 0x94]);
+        assert_eq!(&path[9..13], &[0x80, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_sign_tx_apdus_chunking() {
+        let payload = vec![0xAB; 300];
+        let apdus = sign_tx_apdus(0, &payload);
+
+        // 13 path bytes + 300 payload bytes = 313 -> 150 + 150 + 13
+        assert_eq!(apdus.len(), 3);
+        assert_eq!(&apdus[0][..4], &[CLA, INS_SIGN_TX, P1_FIRST, P2_MORE]);
+        assert_eq!(&apdus[1][..4], &[CLA, INS_SIGN_TX, P1_MORE, P2_MORE]);
+        assert_eq!(&apdus[2][..4], &[CLA, INS_SIGN_TX, P1_MORE, P2_LAST]);
+        assert_eq!(apdus[2][4], 13);
+    }
+
+    #[test]
+    fn test_frame_apdu() {
+        let apdu = vec![0x11; 100];
+        let packets = frame_apdu(&apdu);
+
+        // 57 bytes in the first packet, 59 in each following one
+        assert_eq!(packets.len(), 2);
+        assert_eq!(&packets[0][..7], &[0x01, 0x01, 0x05, 0x00, 0x00, 0x00, 100]);
+        assert_eq!(&packets[1][..5], &[0x01, 0x01, 0x05, 0x00, 0x01]);
+        assert_eq!(packets[1][5 + 42], 0x11);
+        assert_eq!(packets[1][5 + 43], 0x00);
+    }
+
+    #[test]
+    fn test_status_words() {
+        assert!(check_status(SW_OK).is_ok());
+        assert!(check_status(SW_DENIED).is_err());
+
+        let (data, sw) = split_status(&[0xAA, 0x90, 0x00]).unwrap();
+        assert_eq!(data, &[0xAA]);
+        assert_eq!(sw, SW_OK);
+        assert!(split_status(&[0x90]).is_err());
+    }
+
+    #[test]
+    fn test_in_app_signer_matches_identity() {
+        let identity = GnsIdentity::generate();
+        let signer = InAppSigner::from_private_key_bytes(identity.private_key_bytes().expose_secret()).unwrap();
+        assert_eq!(signer.public_key().unwrap(), identity.public_key_bytes());
+    }
+}
@@ -3,10 +3,17 @@
 //! Converts GNS Ed25519 keys to Stellar addresses and handles:
 //! - Balance queries via Horizon REST API
 //! - Trustline creation
-//! - GNS token transfers
-//! - Claimable balance claims
+//! - GNS token transfers, previewed before they're sent
+//! - Claimable balance claims, recorded against breadcrumb epochs
+//! - Streaming incoming payments
+//! - Transaction signing with the in-app key or a hardware wallet
 
 pub mod backend;
+pub mod earnings;
+pub mod hardware;
+pub mod preview;
+pub mod prices;
+pub mod stream;
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -18,6 +25,8 @@ use std::convert::TryInto; // For array conversion
 use base64::Engine; // Import Engine trait
 
 pub use backend::StellarBackendClient;
+pub use hardware::{HardwareSigningConfig, HardwareWalletInfo, InAppSigner, TransactionSigner};
+pub use preview::{PreviewStore, TransactionPreview};
 
 // ==================== CONFIGURATION ====================
 
@@ -62,6 +71,12 @@ impl StellarConfig {
     }
 }
 
+/// Payments per Horizon page when reading a date range
+const HISTORY_PAGE_SIZE: u32 = 200;
+
+/// Pages read before giving up on a date range
+const MAX_HISTORY_PAGES: u32 = 50;
+
 // ==================== DATA TYPES ====================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -182,9 +197,18 @@ struct HorizonPaymentsEmbedded {
     records: Vec<HorizonPayment>,
 }
 
+#[derive(Debug, Deserialize)]
+struct HorizonTransaction {
+    successful: bool,
+    memo_type: String,
+    memo: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct HorizonPayment {
     id: String,
+    #[serde(default)]
+    paging_token: String,
     transaction_hash: String,
     created_at: String,
     #[serde(rename = "type")]
@@ -197,12 +221,60 @@ struct HorizonPayment {
     asset_type: Option<String>,
 }
 
+impl HorizonPayment {
+    /// The history entry for a payment or account creation, as seen from
+    /// `stellar_address`; None for other operation types
+    fn into_history_item(self, stellar_address: &str) -> Option<PaymentHistoryItem> {
+        if self.payment_type != "payment" && self.payment_type != "create_account" {
+            return None;
+        }
+
+        let direction = if self.from.as_deref() == Some(stellar_address) {
+            "sent".to_string()
+        } else {
+            "received".to_string()
+        };
+
+        let amount = if self.payment_type == "create_account" {
+            self.starting_balance.unwrap_or_default()
+        } else {
+            self.amount.unwrap_or_default()
+        };
+
+        let asset_code = if self.payment_type == "create_account" {
+            "XLM".to_string()
+        } else {
+            self.asset_code.unwrap_or_else(|| {
+                if self.asset_type.as_deref() == Some("native") {
+                    "XLM".to_string()
+                } else {
+                    "Unknown".to_string()
+                }
+            })
+        };
+
+        Some(PaymentHistoryItem {
+            id: self.id,
+            tx_hash: self.transaction_hash,
+            created_at: self.created_at,
+            direction,
+            amount,
+            asset_code,
+            from_address: self.from.unwrap_or_default(),
+            to_address: self.to.unwrap_or_default(),
+            memo: None,
+        })
+    }
+}
+
 // ==================== STELLAR SERVICE ====================
 
 pub struct StellarService {
     config: StellarConfig,
     client: Client,
     backend: StellarBackendClient,
+    signing: HardwareSigningConfig,
+    previews: PreviewStore,
 }
 
 impl StellarService {
@@ -211,6 +283,8 @@ impl StellarService {
             client: Client::new(),
             backend: StellarBackendClient::new(config.backend_url.as_deref()),
             config,
+            signing: HardwareSigningConfig::default(),
+            previews: PreviewStore::default(),
         }
     }
 
@@ -226,6 +300,33 @@ impl StellarService {
         &self.config
     }
 
+    /// Signing preference for the active account
+    pub fn signing_config(&self) -> &HardwareSigningConfig {
+        &self.signing
+    }
+
+    /// Switch the active account between in-app and hardware signing
+    pub fn set_signing_config(&mut self, signing: HardwareSigningConfig) {
+        self.signing = signing;
+    }
+
+    /// A copy with the same network and signer, for calls that wait on
+    /// the network or a hardware wallet without holding the shared service
+    pub fn detached(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            backend: StellarBackendClient::new(self.config.backend_url.as_deref()),
+            config: self.config.clone(),
+            signing: self.signing.clone(),
+            previews: PreviewStore::default(),
+        }
+    }
+
+    /// Transaction previews waiting for the user to confirm
+    pub fn previews(&mut self) -> &mut PreviewStore {
+        &mut self.previews
+    }
+
     // ==================== KEY CONVERSION ====================
 
     /// Convert GNS hex public key (32 bytes Ed25519) to Stellar G... address
@@ -442,44 +543,98 @@ impl StellarService {
             .map_err(|e| StellarError::ParseError(e.to_string()))?;
 
         Ok(data.embedded.records.into_iter()
-            .filter(|p| p.payment_type == "payment" || p.payment_type == "create_account")
-            .map(|p| {
-                let direction = if p.from.as_deref() == Some(stellar_address) {
-                    "sent".to_string()
-                } else {
-                    "received".to_string()
-                };
-
-                let amount = if p.payment_type == "create_account" {
-                    p.starting_balance.unwrap_or_default()
-                } else {
-                    p.amount.unwrap_or_default()
-                };
+            .filter_map(|p| p.into_history_item(stellar_address))
+            .collect())
+    }
 
-                let asset_code = if p.payment_type == "create_account" {
-                    "XLM".to_string()
-                } else {
-                    p.asset_code.unwrap_or_else(|| {
-                        if p.asset_type.as_deref() == Some("native") {
-                            "XLM".to_string()
-                        } else {
-                            "Unknown".to_string()
-                        }
-                    })
-                };
-
-                PaymentHistoryItem {
-                    id: p.id,
-                    tx_hash: p.transaction_hash,
-                    created_at: p.created_at,
-                    direction,
-                    amount,
-                    asset_code,
-                    from_address: p.from.unwrap_or_default(),
-                    to_address: p.to.unwrap_or_default(),
-                    memo: None,
+    /// Every payment created between `since` and `until` (RFC 3339, either
+    /// open), newest first, paging through Horizon as far as needed
+    pub async fn get_payments_between(
+        &self,
+        stellar_address: &str,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<Vec<PaymentHistoryItem>, StellarError> {
+        let since = since.map(parse_time).transpose()?;
+        let until = until.map(parse_time).transpose()?;
+        let mut payments = Vec::new();
+        let mut cursor = String::new();
+
+        for _ in 0..MAX_HISTORY_PAGES {
+            let url = format!(
+                "{}/accounts/{}/payments?limit={}&order=desc&cursor={}",
+                self.config.horizon_url, stellar_address, HISTORY_PAGE_SIZE, cursor
+            );
+            let response = self.client.get(&url).send().await
+                .map_err(|e| StellarError::NetworkError(e.to_string()))?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                // No account yet, so no history
+                return Ok(payments);
+            }
+            if !response.status().is_success() {
+                return Err(StellarError::NetworkError(format!("Horizon returned {}", response.status())));
+            }
+            let data: HorizonPaymentsResponse = response.json().await
+                .map_err(|e| StellarError::ParseError(e.to_string()))?;
+
+            let page_len = data.embedded.records.len();
+            for record in data.embedded.records {
+                cursor = record.paging_token.clone();
+                let created = parse_time(&record.created_at)?;
+                if since.is_some_and(|since| created < since) {
+                    return Ok(payments);
                 }
-            })
+                if until.is_some_and(|until| created > until) {
+                    continue;
+                }
+                payments.extend(record.into_history_item(stellar_address));
+            }
+            if page_len < HISTORY_PAGE_SIZE as usize {
+                return Ok(payments);
+            }
+        }
+        Err(StellarError::Validation(format!(
+            "More than {} payments in range, narrow the dates",
+            MAX_HISTORY_PAGES * HISTORY_PAGE_SIZE
+        )))
+    }
+
+    /// The payments made by one transaction, as seen from
+    /// `stellar_address`, each carrying the transaction's text memo; empty
+    /// if the transaction failed
+    pub async fn get_transaction_payments(
+        &self,
+        tx_hash: &str,
+        stellar_address: &str,
+    ) -> Result<Vec<PaymentHistoryItem>, StellarError> {
+        if tx_hash.len() != 64 || hex::decode(tx_hash).is_err() {
+            return Err(StellarError::Validation("Transaction hashes are 32 bytes of hex".to_string()));
+        }
+        let url = format!("{}/transactions/{}", self.config.horizon_url, tx_hash);
+        let response = self.client.get(&url).send().await
+            .map_err(|e| StellarError::NetworkError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(StellarError::NetworkError(format!("Horizon returned {}", response.status())));
+        }
+        let transaction: HorizonTransaction = response.json().await
+            .map_err(|e| StellarError::ParseError(e.to_string()))?;
+        if !transaction.successful {
+            return Ok(vec![]);
+        }
+        let memo = transaction.memo.filter(|_| transaction.memo_type == "text");
+
+        let url = format!("{}/transactions/{}/payments?limit={}", self.config.horizon_url, tx_hash, HISTORY_PAGE_SIZE);
+        let response = self.client.get(&url).send().await
+            .map_err(|e| StellarError::NetworkError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(StellarError::NetworkError(format!("Horizon returned {}", response.status())));
+        }
+        let data: HorizonPaymentsResponse = response.json().await
+            .map_err(|e| StellarError::ParseError(e.to_string()))?;
+
+        Ok(data.embedded.records.into_iter()
+            .filter_map(|p| p.into_history_item(stellar_address))
+            .map(|p| PaymentHistoryItem { memo: memo.clone(), ..p })
             .collect())
     }
 
@@ -508,10 +663,8 @@ impl StellarService {
         public_key_hex: &str,
         private_key_bytes: &[u8],
     ) -> Result<TransactionResult, StellarError> {
-        let private_key_hex = hex::encode(private_key_bytes);
-        
         // Reconstruct identity for signing (since we have the seed/bytes)
-        let identity = GnsIdentity::from_hex(&private_key_hex)
+        let identity = GnsIdentity::from_slice(private_key_bytes)
             .map_err(|e| StellarError::InvalidKeyLength(e.to_string().len()))?; // Rough mapping
             // Note: Ideally we'd map to a generic "KeyError", but using what we have.
 
@@ -531,7 +684,7 @@ impl StellarService {
                 } else if response.error.as_deref() == Some("SIGN_REQUIRED") {
                      // Get XDR, sign it, and resubmit
                      if let Some(xdr) = response.hash {
-                        let signed_xdr = self.sign_transaction(&xdr, private_key_bytes)?;
+                        let signed_xdr = self.sign_transaction(&xdr, private_key_bytes).await?;
                         
                         // Re-create sign_fn because it's consumed or we need a fresh one? 
                         // Actually Fn is OK.
@@ -570,6 +723,7 @@ impl StellarService {
     }
 
     /// Send GNS tokens via backend
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_gns(
         &self,
         sender_public_key: &str,
@@ -581,9 +735,9 @@ impl StellarService {
         // wait, backend.send_gns has recipient_stellar_address OR recipient_public_key.
         recipient_input: &str, // This could be address or public key
         amount: f64,
+        memo: Option<&str>,
     ) -> Result<TransactionResult, StellarError> {
-        let private_key_hex = hex::encode(sender_private_key);
-        let identity = GnsIdentity::from_hex(&private_key_hex)
+        let identity = GnsIdentity::from_slice(sender_private_key)
             .map_err(|e| StellarError::InvalidKeyLength(e.to_string().len()))?;
 
         let sign_fn = |msg: &str| {
@@ -604,7 +758,7 @@ impl StellarService {
             recipient_address, 
             recipient_pk, 
             amount, 
-            None, 
+            memo, 
             sender_public_key, 
             network,
             None,
@@ -617,7 +771,7 @@ impl StellarService {
                       Ok(TransactionResult { success: true, hash: response.hash, error: None })
                   } else if response.error.as_deref() == Some("SIGN_REQUIRED") {
                        if let Some(xdr) = response.hash {
-                           let signed_xdr = self.sign_transaction(&xdr, sender_private_key)?;
+                           let signed_xdr = self.sign_transaction(&xdr, sender_private_key).await?;
 
                            let sign_fn_2 = |msg: &str| {
                                 let signature = identity.sign(msg.as_bytes());
@@ -628,7 +782,7 @@ impl StellarService {
                                 recipient_address, 
                                 recipient_pk, 
                                 amount, 
-                                None, 
+                                memo, 
                                 sender_public_key, 
                                 network,
                                 Some(&signed_xdr),
@@ -656,8 +810,7 @@ impl StellarService {
         public_key_hex: &str,
         private_key_bytes: &[u8],
     ) -> Result<TransactionResult, StellarError> {
-        let private_key_hex = hex::encode(private_key_bytes);
-        let identity = GnsIdentity::from_hex(&private_key_hex)
+        let identity = GnsIdentity::from_slice(private_key_bytes)
             .map_err(|e| StellarError::InvalidKeyLength(e.to_string().len()))?;
 
         let sign_fn = |msg: &str| {
@@ -676,7 +829,7 @@ impl StellarService {
                   } else if response.error.as_deref() == Some("SIGN_REQUIRED") || response.error.as_deref() == Some("COSIGN_REQUIRED") {
                        // Note: COSIGN_REQUIRED uses same mechanism
                        if let Some(xdr) = response.hash {
-                            let signed_xdr = self.sign_transaction(&xdr, private_key_bytes)?;
+                            let signed_xdr = self.sign_transaction(&xdr, private_key_bytes).await?;
                             
                             let sign_fn_2 = |msg: &str| {
                                 let signature = identity.sign(msg.as_bytes());
@@ -702,11 +855,74 @@ impl StellarService {
 
     // ==================== SIGNING HELPER ====================
 
-    /// Parse, sign, and re-serialize a transaction XDR
-    fn sign_transaction(
+    /// Sign a transaction XDR with the account's configured signer.
+    ///
+    /// Uses the hardware wallet when enabled for this account, otherwise
+    /// the in-app identity key. Device I/O runs on the blocking pool, since
+    /// the wallet waits for the user to confirm. The device account has to
+    /// be the transaction's source, or its signature wouldn't authorize it.
+    async fn sign_transaction(
         &self,
         xdr_base64: &str,
         private_key_bytes: &[u8],
+    ) -> Result<String, StellarError> {
+        if self.signing.use_hardware {
+            let source = Self::source_account_key(xdr_base64)?;
+            let signing = self.signing.clone();
+            let passphrase = self.config.network_passphrase.clone();
+            let xdr = xdr_base64.to_string();
+            tauri::async_runtime::spawn_blocking(move || {
+                let signer = hardware::open_hardware_signer(&signing)?;
+                if signer.public_key()? != source {
+                    return Err(StellarError::HardwareWallet(
+                        "The hardware wallet account isn't this transaction's source account".to_string(),
+                    ));
+                }
+                Self::sign_xdr(&passphrase, &xdr, signer.as_ref())
+            })
+            .await
+            .map_err(|e| StellarError::HardwareWallet(e.to_string()))?
+        } else {
+            let signer = InAppSigner::from_private_key_bytes(private_key_bytes)?;
+            self.sign_transaction_with(xdr_base64, &signer)
+        }
+    }
+
+    /// Parse, sign, and re-serialize a transaction XDR
+    pub fn sign_transaction_with(
+        &self,
+        xdr_base64: &str,
+        signer: &dyn TransactionSigner,
+    ) -> Result<String, StellarError> {
+        Self::sign_xdr(&self.config.network_passphrase, xdr_base64, signer)
+    }
+
+    /// Raw key of the account a transaction is sent from: the source
+    /// account, or the fee source of a fee bump
+    fn source_account_key(xdr_base64: &str) -> Result<[u8; 32], StellarError> {
+        use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+        use stellar_xdr::curr::{Limits, MuxedAccount, ReadXdr, TransactionEnvelope};
+
+        let xdr_bytes = BASE64_STANDARD.decode(xdr_base64)
+            .map_err(|e| StellarError::Validation(format!("Invalid base64 XDR: {}", e)))?;
+        let envelope = TransactionEnvelope::from_xdr(&xdr_bytes, Limits::none())
+            .map_err(|e| StellarError::Validation(format!("Invalid XDR: {}", e)))?;
+        let source = match envelope {
+            TransactionEnvelope::Tx(v1) => v1.tx.source_account,
+            TransactionEnvelope::TxFeeBump(v1) => v1.tx.fee_source,
+            _ => return Err(StellarError::Validation("Unsupported transaction type".to_string())),
+        };
+        Ok(match source {
+            MuxedAccount::Ed25519(key) => key.0,
+            MuxedAccount::MuxedEd25519(muxed) => muxed.ed25519.0,
+        })
+    }
+
+    /// `sign_transaction_with` for a given network, usable off the service
+    pub fn sign_xdr(
+        network_passphrase: &str,
+        xdr_base64: &str,
+        signer: &dyn TransactionSigner,
     ) -> Result<String, StellarError> {
         use stellar_xdr::curr::{
             Hash, Limits, TransactionEnvelope, TransactionSignaturePayload,
@@ -726,7 +942,6 @@ impl StellarService {
             .map_err(|e| StellarError::Validation(format!("Invalid XDR: {}", e)))?;
 
         // 3. Prepare Network ID
-        let network_passphrase = &self.config.network_passphrase;
         let network_hash = Sha256::digest(network_passphrase.as_bytes());
         let network_id = Hash(network_hash.into());
 
@@ -745,26 +960,18 @@ impl StellarService {
         // 5. Hash Payload
         let payload_bytes = payload.to_xdr(Limits::none())
              .map_err(|e| StellarError::Validation(format!("XDR encoding error: {}", e)))?;
-        let payload_hash = Sha256::digest(&payload_bytes);
+        let payload_hash: [u8; 32] = Sha256::digest(&payload_bytes).into();
 
-        // 6. Sign Hash
-        let private_key_hex = hex::encode(private_key_bytes);
-        let identity = GnsIdentity::from_hex(&private_key_hex)
-            .map_err(|_| StellarError::Validation("Invalid identity".to_string()))?;
-        
-        // Note: GnsIdentity::sign typically signs the message bytes (Ed25519). 
-        // Stellar requires signing the SHA256 hash of the payload.
-        // We pass the hash as the message.
-        let signature_bytes = identity.sign(&payload_hash);
-        let signature_vec = signature_bytes.to_bytes().to_vec();
+        // 6. Sign (hardware signers may prompt the user here)
+        let signature_bytes = signer.sign_payload(&payload_bytes, &payload_hash)?;
 
         // 7. Add signature to envelope
-        let pub_key_bytes = identity.public_key_bytes();
+        let pub_key_bytes = signer.public_key()?;
         let hint_bytes: [u8; 4] = pub_key_bytes[28..32].try_into().unwrap();
         
         let decorated_sig = DecoratedSignature {
             hint: SignatureHint(hint_bytes),
-            signature: Signature(signature_vec.try_into().map_err(|_| StellarError::Validation("Signature length mismatch".to_string()))?),
+            signature: Signature(signature_bytes.to_vec().try_into().map_err(|_| StellarError::Validation("Signature length mismatch".to_string()))?),
         };
         
         match &mut envelope {
@@ -831,10 +1038,17 @@ pub enum StellarError {
 
     #[error("Validation error: {0}")]
     Validation(String),
+
+    #[error("Hardware wallet error: {0}")]
+    HardwareWallet(String),
 }
 
 // ==================== HELPER FUNCTIONS ====================
 
+fn parse_time(value: &str) -> Result<chrono::DateTime<chrono::FixedOffset>, StellarError> {
+    chrono::DateTime::parse_from_rfc3339(value).map_err(|e| StellarError::ParseError(e.to_string()))
+}
+
 /// CRC16-XModem checksum (used by Stellar for address encoding)
 fn crc16_xmodem(data: &[u8]) -> u16 {
     let mut crc: u16 = 0;
@@ -899,6 +1113,32 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_source_account_key() {
+        use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+        use stellar_xdr::curr::{
+            Limits, Memo, MuxedAccount, Preconditions, SequenceNumber, Transaction, TransactionEnvelope,
+            TransactionExt, TransactionV1Envelope, Uint256, VecM, WriteXdr,
+        };
+
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: Transaction {
+                source_account: MuxedAccount::Ed25519(Uint256([7; 32])),
+                fee: 100,
+                seq_num: SequenceNumber(1),
+                cond: Preconditions::None,
+                memo: Memo::None,
+                operations: VecM::default(),
+                ext: TransactionExt::V0,
+            },
+            signatures: VecM::default(),
+        });
+        let xdr = BASE64_STANDARD.encode(envelope.to_xdr(Limits::none()).unwrap());
+
+        assert_eq!(StellarService::source_account_key(&xdr).unwrap(), [7; 32]);
+        assert!(StellarService::source_account_key("not xdr").is_err());
+    }
+
     #[test]
     fn test_crc16_xmodem() {
         // Test vector - just verify it produces a value
@@ -0,0 +1,215 @@
+//! Transaction Preview - What a send will do, before it's submitted
+//!
+//! `preview_transaction` lays out the operations a GNS send will submit,
+//! the network fee and the balances left afterwards, and hands back a
+//! single-use token. `send_gns` only submits with a token issued for that
+//! exact recipient and amount, so nothing goes out that the user hasn't
+//! been shown.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::{StellarBalances, StellarError, StellarService};
+
+/// How long a preview can be confirmed for
+const PREVIEW_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Fee per operation when Horizon doesn't report one (stroops)
+const DEFAULT_BASE_FEE_STROOPS: u64 = 100;
+
+const STROOPS_PER_XLM: f64 = 10_000_000.0;
+
+/// One operation the transaction will contain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreviewOperation {
+    /// Horizon operation type, e.g. `payment`
+    pub kind: String,
+    pub source: String,
+    pub destination: String,
+    pub asset_code: String,
+    pub asset_issuer: Option<String>,
+    pub amount: f64,
+}
+
+/// What a send will do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionPreview {
+    /// Pass to `send_gns` to submit this transaction
+    pub token: String,
+    pub operations: Vec<PreviewOperation>,
+    pub fee_xlm: f64,
+    pub xlm_before: f64,
+    pub xlm_after: f64,
+    pub gns_before: f64,
+    pub gns_after: f64,
+    /// Reasons the transaction would fail; it can't be confirmed while
+    /// there are any
+    pub problems: Vec<String>,
+    pub expires_at: i64,
+}
+
+impl TransactionPreview {
+    pub fn is_submittable(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Build the preview of a GNS payment of `amount` from `sender` to
+/// `recipient_address`; `recipient_has_trustline` is None when their
+/// account doesn't exist
+pub fn payment_preview(
+    service: &StellarService,
+    sender: &StellarBalances,
+    recipient_address: &str,
+    recipient_has_trustline: Option<bool>,
+    amount: f64,
+    fee_stroops: u64,
+) -> TransactionPreview {
+    let config = service.config();
+    let operations = vec![PreviewOperation {
+        kind: "payment".to_string(),
+        source: sender.stellar_address.clone(),
+        destination: recipient_address.to_string(),
+        asset_code: config.gns_token_code.clone(),
+        asset_issuer: Some(config.gns_issuer.clone()),
+        amount,
+    }];
+    let fee_xlm = (fee_stroops * operations.len() as u64) as f64 / STROOPS_PER_XLM;
+
+    let mut problems = Vec::new();
+    if amount.is_nan() || amount <= 0.0 {
+        problems.push("Amount must be greater than zero".to_string());
+    }
+    if recipient_address == sender.stellar_address {
+        problems.push("Cannot send to yourself".to_string());
+    }
+    if !sender.account_exists {
+        problems.push("Your Stellar account isn't funded yet".to_string());
+    }
+    if amount > sender.gns_balance {
+        problems.push(format!("Insufficient GNS: have {}, need {}", sender.gns_balance, amount));
+    }
+    if fee_xlm > sender.xlm_balance {
+        problems.push(format!("Insufficient XLM for the fee of {}", fee_xlm));
+    }
+    match recipient_has_trustline {
+        None => problems.push("The recipient's Stellar account doesn't exist".to_string()),
+        Some(false) => problems.push("The recipient can't hold GNS yet (no trustline)".to_string()),
+        Some(true) => {}
+    }
+
+    TransactionPreview {
+        token: uuid::Uuid::new_v4().to_string(),
+        operations,
+        fee_xlm,
+        xlm_before: sender.xlm_balance,
+        xlm_after: sender.xlm_balance - fee_xlm,
+        gns_before: sender.gns_balance,
+        gns_after: sender.gns_balance - amount,
+        problems,
+        expires_at: chrono::Utc::now().timestamp_millis() + PREVIEW_TTL.as_millis() as i64,
+    }
+}
+
+/// Previews waiting for confirmation
+#[derive(Default)]
+pub struct PreviewStore {
+    pending: HashMap<String, (TransactionPreview, Instant)>,
+}
+
+impl PreviewStore {
+    /// Remember a preview until it's confirmed or expires
+    pub fn insert(&mut self, preview: TransactionPreview) {
+        self.pending.retain(|_, (_, issued)| issued.elapsed() < PREVIEW_TTL);
+        self.pending.insert(preview.token.clone(), (preview, Instant::now()));
+    }
+
+    /// Use up `token`, checking it previewed a payment of `amount` to
+    /// `recipient_address`
+    pub fn confirm(&mut self, token: &str, recipient_address: &str, amount: f64) -> Result<TransactionPreview, StellarError> {
+        let (preview, issued) = self
+            .pending
+            .remove(token)
+            .ok_or_else(|| StellarError::Validation("Unknown or already used preview".to_string()))?;
+        if issued.elapsed() >= PREVIEW_TTL {
+            return Err(StellarError::Validation("Preview expired, review the transaction again".to_string()));
+        }
+        let matches = preview
+            .operations
+            .iter()
+            .any(|op| op.destination == recipient_address && op.amount == amount);
+        if !matches {
+            return Err(StellarError::Validation("Transaction doesn't match the preview".to_string()));
+        }
+        if !preview.is_submittable() {
+            return Err(StellarError::Validation(preview.problems.join("; ")));
+        }
+        Ok(preview)
+    }
+}
+
+impl StellarService {
+    /// Fee per operation in stroops, from recent ledgers
+    pub async fn fee_per_operation(&self) -> u64 {
+        let url = format!("{}/fee_stats", self.config.horizon_url);
+        let stats: Option<serde_json::Value> = match self.client.get(&url).send().await {
+            Ok(response) => response.json().await.ok(),
+            Err(_) => None,
+        };
+        stats
+            .and_then(|s| s["fee_charged"]["p50"].as_str().and_then(|f| f.parse().ok()))
+            .unwrap_or(DEFAULT_BASE_FEE_STROOPS)
+    }
+
+    /// Whether `stellar_address` can receive GNS; None if the account
+    /// doesn't exist
+    pub async fn recipient_trustline(&self, stellar_address: &str) -> Option<bool> {
+        if !self.account_exists(stellar_address).await {
+            return None;
+        }
+        Some(self.has_gns_trustline(stellar_address).await.unwrap_or(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sender(xlm: f64, gns: f64) -> StellarBalances {
+        StellarBalances {
+            stellar_address: "GSENDER".to_string(),
+            account_exists: true,
+            xlm_balance: xlm,
+            gns_balance: gns,
+            has_trustline: true,
+            claimable_gns: vec![],
+        }
+    }
+
+    #[test]
+    fn test_preview_and_confirm() {
+        let service = StellarService::testnet();
+        let preview = payment_preview(&service, &sender(2.0, 50.0), "GRECIPIENT", Some(true), 10.0, 100);
+        assert!(preview.is_submittable());
+        assert_eq!(preview.fee_xlm, 0.00001);
+        assert_eq!(preview.gns_after, 40.0);
+        assert_eq!(preview.operations[0].destination, "GRECIPIENT");
+
+        let mut store = PreviewStore::default();
+        store.insert(preview.clone());
+        assert!(store.confirm(&preview.token, "GRECIPIENT", 11.0).is_err());
+        // A mismatch still uses the token up
+        assert!(store.confirm(&preview.token, "GRECIPIENT", 10.0).is_err());
+
+        store.insert(preview.clone());
+        assert!(store.confirm(&preview.token, "GRECIPIENT", 10.0).is_ok());
+        assert!(store.confirm(&preview.token, "GRECIPIENT", 10.0).is_err());
+
+        let broke = payment_preview(&service, &sender(2.0, 5.0), "GRECIPIENT", Some(false), 10.0, 100);
+        assert_eq!(broke.problems.len(), 2);
+        store.insert(broke.clone());
+        assert!(store.confirm(&broke.token, "GRECIPIENT", 10.0).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Prices - Fiat value of assets on a given day
+//!
+//! Used to value payment history for export. The price API is
+//! configurable: `url` is a template with `{coin}`, `{date}` (DD-MM-YYYY)
+//! and `{currency}` placeholders, and `price_pointer` is a JSON pointer
+//! to the price in the response. The default is CoinGecko's daily
+//! history. Assets without a coin id (GNS until it's listed) aren't
+//! valued. Prices are cached per day in `price_cache`.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::StellarError;
+
+/// Settings key the configured source is stored under
+pub const PRICE_SOURCE_SETTING: &str = "price_source";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceSource {
+    pub url: String,
+    pub price_pointer: String,
+    /// Lowercase fiat code, e.g. `usd`
+    pub currency: String,
+    /// Asset code to the API's coin id
+    pub coins: BTreeMap<String, String>,
+}
+
+impl Default for PriceSource {
+    fn default() -> Self {
+        Self {
+            url: "https://api.coingecko.com/api/v3/coins/{coin}/history?date={date}&localization=false".to_string(),
+            price_pointer: "/market_data/current_price/{currency}".to_string(),
+            currency: "usd".to_string(),
+            coins: BTreeMap::from([("XLM".to_string(), "stellar".to_string())]),
+        }
+    }
+}
+
+impl PriceSource {
+    /// Request URL for an asset's price on `date`; None if the asset has
+    /// no coin id
+    pub fn url_for(&self, asset_code: &str, date: NaiveDate) -> Option<String> {
+        let coin = self.coins.get(asset_code)?;
+        Some(
+            self.url
+                .replace("{coin}", coin)
+                .replace("{date}", &date.format("%d-%m-%Y").to_string())
+                .replace("{currency}", &self.currency),
+        )
+    }
+
+    /// The price in a response body
+    pub fn parse_price(&self, response: &serde_json::Value) -> Option<f64> {
+        let value = response.pointer(&self.price_pointer.replace("{currency}", &self.currency))?;
+        value.as_f64().or_else(|| value.as_str()?.parse().ok())
+    }
+
+    /// Fetch an asset's price on `date`; Ok(None) when the source has no
+    /// price for it
+    pub async fn fetch(&self, client: &Client, asset_code: &str, date: NaiveDate) -> Result<Option<f64>, StellarError> {
+        let Some(url) = self.url_for(asset_code, date) else {
+            return Ok(None);
+        };
+        let response = client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| StellarError::NetworkError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(StellarError::NetworkError(format!("Price API returned {}", response.status())));
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| StellarError::ParseError(e.to_string()))?;
+        Ok(self.parse_price(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_price_source() {
+        let source = PriceSource::default();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!(
+            source.url_for("XLM", date).unwrap(),
+            "https://api.coingecko.com/api/v3/coins/stellar/history?date=05-03-2024&localization=false"
+        );
+        assert_eq!(source.url_for("GNS", date), None);
+
+        let body = json!({"market_data": {"current_price": {"usd": 0.1234, "eur": "0.11"}}});
+        assert_eq!(source.parse_price(&body), Some(0.1234));
+        let eur = PriceSource {
+            currency: "eur".to_string(),
+            ..source
+        };
+        assert_eq!(eur.parse_price(&body), Some(0.11));
+        assert_eq!(eur.parse_price(&json!({})), None);
+    }
+}
@@ -0,0 +1,215 @@
+//! Payment Stream - Watching the account for incoming payments
+//!
+//! Horizon serves `/accounts/{id}/payments` as server-sent events when
+//! asked for `text/event-stream`. The watcher keeps that stream open for
+//! the identity's Stellar address and, for each payment received, emits
+//! `payment_received` and posts a notification, so the wallet doesn't
+//! need a manual refresh. Streams end now and then (Horizon closes idle
+//! ones); the watcher reconnects from the last event it saw.
+
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use super::{HorizonPayment, PaymentHistoryItem, StellarError, StellarService};
+use crate::AppState;
+
+/// Wait before reconnecting after a failure, doubled per failure
+const RETRY_BASE: Duration = Duration::from_secs(5);
+
+/// Longest wait between reconnects
+const RETRY_MAX: Duration = Duration::from_secs(5 * 60);
+
+/// Wait while there's no identity to watch
+const NO_IDENTITY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One server-sent event
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    pub id: Option<String>,
+    pub data: String,
+}
+
+/// Splits a server-sent event stream into events as chunks arrive
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: String,
+    id: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Feed a chunk, returning the events it completed
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.push_str(&String::from_utf8_lossy(chunk));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=end).collect();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        id: self.id.take(),
+                        data: self.data.join("\n"),
+                    });
+                    self.data.clear();
+                }
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "id" => self.id = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                // Comments, `event` and `retry` aren't used
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+impl StellarService {
+    /// Stream payments to and from `stellar_address` after `cursor` ("now"
+    /// for only new ones), calling `on_payment` for each
+    ///
+    /// Returns the cursor to resume from once the server ends the stream.
+    pub async fn stream_payments(
+        &self,
+        stellar_address: &str,
+        cursor: &str,
+        mut on_payment: impl FnMut(PaymentHistoryItem),
+    ) -> Result<String, StellarError> {
+        let url = format!(
+            "{}/accounts/{}/payments?cursor={}",
+            self.config.horizon_url, stellar_address, cursor
+        );
+
+        let mut response = self
+            .client
+            .get(&url)
+            .header("Accept", "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| StellarError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(StellarError::NetworkError(format!("Horizon returned {}", response.status())));
+        }
+
+        let mut cursor = cursor.to_string();
+        let mut decoder = SseDecoder::default();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| StellarError::NetworkError(e.to_string()))?
+        {
+            for event in decoder.push(&chunk) {
+                // The opening "hello" has no id and isn't a record
+                let Some(id) = event.id else {
+                    continue;
+                };
+                cursor = id;
+                if let Some(item) = serde_json::from_str::<HorizonPayment>(&event.data)
+                    .ok()
+                    .and_then(|p| p.into_history_item(stellar_address))
+                {
+                    on_payment(item);
+                }
+            }
+        }
+        Ok(cursor)
+    }
+}
+
+/// Watch the current identity's account for incoming payments for as long
+/// as the app runs
+pub fn start_payment_watcher(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        let mut watching: Option<String> = None;
+        let mut cursor = "now".to_string();
+        let mut failures = 0u32;
+
+        loop {
+            let Some(address) = state
+                .identity
+                .lock()
+                .await
+                .public_key_hex()
+                .and_then(|pk| StellarService::gns_key_to_stellar(&pk).ok())
+            else {
+                tokio::time::sleep(NO_IDENTITY_INTERVAL).await;
+                continue;
+            };
+            // A new identity starts from now rather than its history
+            if watching.as_deref() != Some(address.as_str()) {
+                watching = Some(address.clone());
+                cursor = "now".to_string();
+            }
+
+            // Built per connection so a network switch is picked up, and
+            // so the shared service isn't held for the stream's lifetime
+            let service = StellarService::new(state.stellar.lock().await.config().clone());
+            let result = service
+                .stream_payments(&address, &cursor, |payment| {
+                    if payment.direction == "received" {
+                        notify_payment(&app_handle, &payment);
+                    }
+                })
+                .await;
+
+            match result {
+                Ok(next) => {
+                    cursor = next;
+                    failures = 0;
+                }
+                Err(e) => {
+                    let delay = RETRY_BASE.saturating_mul(1 << failures.min(16)).min(RETRY_MAX);
+                    failures += 1;
+                    tracing::debug!("Payment stream failed ({}), retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    });
+}
+
+fn notify_payment(app_handle: &AppHandle, payment: &PaymentHistoryItem) {
+    tracing::info!("💰 Received {} {}", payment.amount, payment.asset_code);
+    let _ = app_handle.emit("payment_received", payment);
+
+    let from = &payment.from_address[..payment.from_address.len().min(8)];
+    let body = format!("{} {} from {}…", payment.amount, payment.asset_code, from);
+    if let Err(e) = app_handle.notification().builder().title("Payment received").body(body).show() {
+        tracing::warn!("Failed to show payment notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_decoder() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"retry: 1000\nevent: open\ndata: \"hel").is_empty());
+        let events = decoder.push(b"lo\"\n\nid: 123-1\r\ndata: {\"a\":1}\n\n: keepalive\n\nid: 124");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    id: None,
+                    data: "\"hello\"".to_string()
+                },
+                SseEvent {
+                    id: Some("123-1".to_string()),
+                    data: "{\"a\":1}".to_string()
+                },
+            ]
+        );
+        let events = decoder.push(b"-2\ndata: x\n\n");
+        assert_eq!(events[0].id.as_deref(), Some("124-2"));
+    }
+}
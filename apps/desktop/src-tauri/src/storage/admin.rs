@@ -0,0 +1,304 @@
+//! Storage admin
+//!
+//! Size reporting and maintenance for the local database: VACUUM, integrity
+//! and index checks, and a scheduler that runs them while the app is idle.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use super::{Database, DatabaseError, DatabaseHandle};
+use crate::duress::Persona;
+
+/// Minimum time between automatic maintenance runs
+const MAINTENANCE_INTERVAL_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// How often the scheduler checks whether maintenance is due
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// No incoming messages for this long counts as idle
+const IDLE_AFTER_MS: i64 = 30 * 60 * 1000;
+
+/// Tables a wipe leaves alone: the schema version, and the remote-wipe
+/// mark that stops the same wipe being replayed
+const KEPT_ON_WIPE: &[&str] = &["schema_version", "remote_wipe_mark"];
+
+const LAST_MAINTENANCE_KEY: &str = "last_db_maintenance";
+
+/// Row count for one table
+#[derive(Debug, Clone, Serialize)]
+pub struct TableStats {
+    pub name: String,
+    pub rows: u64,
+}
+
+/// Database size and contents
+#[derive(Debug, Clone, Serialize)]
+pub struct DbStats {
+    pub file_size_bytes: u64,
+    /// Space VACUUM would give back
+    pub free_bytes: u64,
+    pub tables: Vec<TableStats>,
+    pub index_count: u32,
+    /// Unix timestamp in milliseconds of the last maintenance run
+    pub last_maintenance_at: Option<i64>,
+}
+
+/// Outcome of a maintenance run
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    /// Problems reported by `PRAGMA integrity_check`; empty when healthy
+    pub integrity_errors: Vec<String>,
+    /// Indexes that were missing and have been recreated
+    pub recreated_indexes: Vec<String>,
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub duration_ms: u64,
+}
+
+/// Is automatic maintenance due?
+pub fn maintenance_due(last_run: Option<i64>, now: i64) -> bool {
+    last_run.is_none_or(|last| now - last >= MAINTENANCE_INTERVAL_MS)
+}
+
+/// Is the app idle enough for maintenance to lock the database?
+///
+/// True when backgrounded, or when no message has arrived recently.
+pub fn is_idle(foreground: bool, last_activity: Option<i64>, now: i64) -> bool {
+    !foreground || last_activity.is_none_or(|last| now - last >= IDLE_AFTER_MS)
+}
+
+impl Database {
+    /// Size, free space and per-table row counts
+    pub fn get_db_stats(&self) -> Result<DbStats, DatabaseError> {
+        let mut tables = Vec::new();
+        for name in self.schema_names("table")? {
+            // Names come from sqlite_master, not user input
+            let rows: i64 = self
+                .conn
+                .query_row(&format!("SELECT COUNT(*) FROM \"{}\"", name), [], |row| row.get(0))
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            tables.push(TableStats { name, rows: rows as u64 });
+        }
+
+        Ok(DbStats {
+            file_size_bytes: self.size_bytes()?,
+            free_bytes: self.free_bytes()?,
+            tables,
+            index_count: self.schema_names("index")?.len() as u32,
+            last_maintenance_at: self.get_setting(LAST_MAINTENANCE_KEY).and_then(|v| v.parse().ok()),
+        })
+    }
+
+    /// Check integrity, recreate missing indexes, then VACUUM
+    pub fn run_maintenance(&mut self) -> Result<MaintenanceReport, DatabaseError> {
+        let started = Instant::now();
+        let size_before_bytes = self.size_bytes()?;
+
+        let integrity_errors = self.integrity_check()?;
+        if !integrity_errors.is_empty() {
+            tracing::error!("Database integrity check failed: {:?}", integrity_errors);
+        }
+
+        // Every index is declared with IF NOT EXISTS, so re-running the
+        // schema setup restores any that were dropped
+        let indexes_before = self.schema_names("index")?;
+        self.initialize_tables()?;
+        let recreated_indexes: Vec<String> = self
+            .schema_names("index")?
+            .into_iter()
+            .filter(|name| !indexes_before.contains(name))
+            .collect();
+        if !recreated_indexes.is_empty() {
+            tracing::warn!("Recreated missing indexes: {:?}", recreated_indexes);
+        }
+
+        // VACUUM on a corrupt database can make things worse
+        if integrity_errors.is_empty() {
+            self.conn
+                .execute_batch("VACUUM; PRAGMA optimize;")
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+
+        self.set_setting(
+            LAST_MAINTENANCE_KEY,
+            &chrono::Utc::now().timestamp_millis().to_string(),
+        )?;
+
+        let report = MaintenanceReport {
+            integrity_errors,
+            recreated_indexes,
+            size_before_bytes,
+            size_after_bytes: self.size_bytes()?,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        tracing::info!(
+            "Database maintenance done in {}ms ({} -> {} bytes)",
+            report.duration_ms,
+            report.size_before_bytes,
+            report.size_after_bytes
+        );
+        Ok(report)
+    }
+
+    /// Delete every row of every table and scrub the freed pages
+    ///
+    /// `secure_delete` overwrites deleted content with zeros, VACUUM rebuilds
+    /// the file without the free pages, and the WAL is truncated so no old
+    /// frames survive. Only the schema and `KEPT_ON_WIPE` are kept.
+    pub fn wipe(&mut self) -> Result<(), DatabaseError> {
+        self.conn
+            .pragma_update(None, "secure_delete", true)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let tables = self.schema_names("table")?;
+        self.in_transaction(|db| {
+            for name in tables.iter().filter(|name| !KEPT_ON_WIPE.contains(&name.as_str())) {
+                // Names come from sqlite_master, not user input
+                db.conn
+                    .execute(&format!("DELETE FROM \"{}\"", name), [])
+                    .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            }
+            Ok(())
+        })?;
+
+        self.conn
+            .execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        tracing::info!("Database wiped");
+        Ok(())
+    }
+
+    /// Wipe this database, which is `active`'s, and the other persona's if
+    /// it exists, leaving the primary database open
+    pub fn wipe_personas(&mut self, active: Persona) -> Result<(), DatabaseError> {
+        self.wipe()?;
+        for persona in [Persona::Primary, Persona::Decoy] {
+            // Opening would create the file, and with it a trace of a decoy
+            if persona != active && Self::database_path(persona)?.exists() {
+                Self::open_persona(persona)?.wipe()?;
+            }
+        }
+        if active != Persona::Primary {
+            *self = Self::open_persona(Persona::Primary)?;
+        }
+        Ok(())
+    }
+
+    /// Bytes in pages freed by deletes and not yet reused
+    pub(super) fn free_bytes(&self) -> Result<u64, DatabaseError> {
+        let free_bytes: i64 = self
+            .conn
+            .query_row(
+                "SELECT freelist_count * page_size FROM pragma_freelist_count(), pragma_page_size()",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(free_bytes as u64)
+    }
+
+    fn integrity_check(&self) -> Result<Vec<String>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare("PRAGMA integrity_check")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let results = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter(|line| line != "ok")
+            .collect();
+        Ok(results)
+    }
+
+    /// Names of user tables or indexes, excluding SQLite internals
+    fn schema_names(&self, kind: &str) -> Result<Vec<String>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = ? AND name NOT LIKE 'sqlite_%' ORDER BY name")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let names = stmt
+            .query_map([kind], |row| row.get::<_, String>(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(names)
+    }
+}
+
+/// Run maintenance in the background once it is due and `is_idle` says the
+/// user isn't active
+pub fn start_maintenance_scheduler<F>(database: DatabaseHandle, is_idle: F)
+where
+    F: Fn() -> bool + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SCHEDULER_INTERVAL).await;
+
+            let last_run = database
+                .call(|db| db.get_setting(LAST_MAINTENANCE_KEY))
+                .await
+                .and_then(|v| v.parse().ok());
+            if !maintenance_due(last_run, chrono::Utc::now().timestamp_millis()) || !is_idle() {
+                continue;
+            }
+
+            tracing::info!("Running scheduled database maintenance");
+            if let Err(e) = database.call(|db| db.run_maintenance()).await {
+                tracing::error!("Scheduled database maintenance failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintenance_due() {
+        let now = 1_700_000_000_000;
+        assert!(maintenance_due(None, now));
+        assert!(!maintenance_due(Some(now - 1000), now));
+        assert!(maintenance_due(Some(now - MAINTENANCE_INTERVAL_MS), now));
+    }
+
+    #[test]
+    fn test_wipe() {
+        let mut db = Database {
+            conn: rusqlite::Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        db.set_setting("remote_wipe_enabled", "true").unwrap();
+        let message = crate::storage::SyncedMessage {
+            id: "m1".to_string(),
+            conversation_with: "b".repeat(64),
+            text: "hi".to_string(),
+            timestamp: 0,
+            from_handle: None,
+            is_outgoing: false,
+        };
+        db.save_synced_messages(&[message], &"a".repeat(64)).unwrap();
+        db.set_remote_wipe_mark(1_700_000_000_000).unwrap();
+
+        db.wipe().unwrap();
+        let stats = db.get_db_stats().unwrap();
+        assert!(stats.tables.iter().all(|t| t.rows == 0 || KEPT_ON_WIPE.contains(&t.name.as_str())));
+        assert_eq!(db.schema_version().unwrap(), crate::storage::SCHEMA_VERSION);
+        assert!(db.get_setting("remote_wipe_enabled").is_none());
+        assert_eq!(db.get_remote_wipe_mark(), 1_700_000_000_000);
+    }
+
+    #[test]
+    fn test_is_idle() {
+        let now = 1_700_000_000_000;
+        assert!(is_idle(false, Some(now), now));
+        assert!(is_idle(true, None, now));
+        assert!(!is_idle(true, Some(now - 1000), now));
+        assert!(is_idle(true, Some(now - IDLE_AFTER_MS), now));
+    }
+}
@@ -0,0 +1,109 @@
+//! Breadcrumb Audit
+//!
+//! Reading every breadcrumb row for `crate::location::audit`, and the
+//! quarantine that rows failing it are moved to.
+
+use rusqlite::params;
+
+use super::{breadcrumb_from_row, Database, DatabaseError, BREADCRUMB_COLUMNS};
+use gns_crypto_core::Breadcrumb;
+
+impl Database {
+    pub(super) fn initialize_breadcrumb_audit_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS breadcrumb_quarantine (
+                id INTEGER PRIMARY KEY,
+                h3_index TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                signature TEXT NOT NULL,
+                prev_hash TEXT,
+                synced_at INTEGER,
+                reason TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Every breadcrumb with its row id, oldest first
+    pub fn get_breadcrumbs_for_audit(&self) -> Result<Vec<(i64, Breadcrumb)>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT id, {} FROM breadcrumbs ORDER BY timestamp ASC", BREADCRUMB_COLUMNS))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let breadcrumbs = stmt
+            .query_map([], |row| Ok((row.get(0)?, breadcrumb_from_row(row, 1)?)))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        breadcrumbs
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Move breadcrumb rows into quarantine with the reason for each;
+    /// returns how many were moved
+    pub fn quarantine_breadcrumbs(&mut self, rows: &[(i64, &str)]) -> Result<u32, DatabaseError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let mut moved = 0;
+        for (id, reason) in rows {
+            tx.execute(
+                r#"
+                INSERT OR REPLACE INTO breadcrumb_quarantine
+                    (id, h3_index, timestamp, public_key, signature, resolution, prev_hash, synced_at, reason, quarantined_at)
+                SELECT id, h3_index, timestamp, public_key, signature, resolution, prev_hash, synced_at, ?2, ?3
+                FROM breadcrumbs WHERE id = ?1
+                "#,
+                params![id, reason, now],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            moved += tx
+                .execute("DELETE FROM breadcrumbs WHERE id = ?", params![id])
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))? as u32;
+        }
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(moved)
+    }
+
+    pub fn count_quarantined_breadcrumbs(&self) -> Result<u32, DatabaseError> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM breadcrumb_quarantine", [], |row| row.get(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::{create_breadcrumb, GnsIdentity};
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_quarantine() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let identity = GnsIdentity::generate();
+        let mut breadcrumb = create_breadcrumb(&identity, 52.52, 13.405, None, None).unwrap();
+        db.save_breadcrumb(&breadcrumb).unwrap();
+        breadcrumb.timestamp += 60;
+        db.save_breadcrumb(&breadcrumb).unwrap();
+
+        let rows = db.get_breadcrumbs_for_audit().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(db.quarantine_breadcrumbs(&[(rows[1].0, "invalid_signature")]).unwrap(), 1);
+        assert_eq!(db.count_breadcrumbs().unwrap(), 1);
+        assert_eq!(db.count_quarantined_breadcrumbs().unwrap(), 1);
+        // Already moved
+        assert_eq!(db.quarantine_breadcrumbs(&[(rows[1].0, "invalid_signature")]).unwrap(), 0);
+    }
+}
@@ -0,0 +1,215 @@
+//! Calendar
+//!
+//! Event invites, our answer to each, and answers to the ones we sent.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::calendar::{CalendarEvent, EventInvite, EventResponse, RsvpStatus};
+
+impl Database {
+    pub(super) fn initialize_calendar_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS events (
+                id TEXT PRIMARY KEY,
+                invite_json TEXT NOT NULL,
+                organizer TEXT NOT NULL,
+                start_at INTEGER NOT NULL,
+                end_at INTEGER NOT NULL,
+                thread_id TEXT,
+                my_status TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_events_start ON events(start_at);
+
+            CREATE TABLE IF NOT EXISTS event_responses (
+                event_id TEXT NOT NULL,
+                responder TEXT NOT NULL,
+                status TEXT NOT NULL,
+                responded_at INTEGER NOT NULL,
+                PRIMARY KEY (event_id, responder)
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Store an invite; a second copy of the same invite is ignored
+    pub fn save_event(
+        &mut self,
+        invite: &EventInvite,
+        thread_id: Option<&str>,
+        my_status: Option<RsvpStatus>,
+    ) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT OR IGNORE INTO events (id, invite_json, organizer, start_at, end_at, thread_id, my_status)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+                params![
+                    invite.id,
+                    serde_json::to_string(invite).unwrap_or_default(),
+                    invite.organizer.to_lowercase(),
+                    invite.start,
+                    invite.end,
+                    thread_id,
+                    my_status.map(|s| s.as_str()),
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_event(&self, event_id: &str) -> Option<CalendarEvent> {
+        let event = self
+            .conn
+            .query_row(
+                "SELECT invite_json, thread_id, my_status FROM events WHERE id = ?",
+                params![event_id],
+                event_from_row,
+            )
+            .optional()
+            .ok()
+            .flatten()
+            .flatten()?;
+        Some(self.with_responses(event))
+    }
+
+    /// Record our answer to an invite; false if we don't have it
+    pub fn set_event_status(&mut self, event_id: &str, status: RsvpStatus) -> Result<bool, DatabaseError> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE events SET my_status = ? WHERE id = ?",
+                params![status.as_str(), event_id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(updated > 0)
+    }
+
+    /// Record someone's answer to an invite we organize
+    pub fn record_event_response(
+        &mut self,
+        event_id: &str,
+        responder: &str,
+        status: RsvpStatus,
+        responded_at: i64,
+    ) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT INTO event_responses (event_id, responder, status, responded_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT(event_id, responder) DO UPDATE SET
+                    status = excluded.status,
+                    responded_at = excluded.responded_at
+                WHERE excluded.responded_at >= event_responses.responded_at
+                "#,
+                params![event_id, responder.to_lowercase(), status.as_str(), responded_at],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Accepted and tentative events overlapping `[from, to)`, by start time
+    pub fn get_agenda(&self, from: i64, to: i64) -> Result<Vec<CalendarEvent>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT invite_json, thread_id, my_status FROM events
+                WHERE my_status IN ('accepted', 'tentative') AND end_at >= ?1 AND start_at < ?2
+                ORDER BY start_at
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let events: Vec<CalendarEvent> = stmt
+            .query_map(params![from, to], event_from_row)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok().flatten())
+            .collect();
+        Ok(events.into_iter().map(|event| self.with_responses(event)).collect())
+    }
+
+    fn with_responses(&self, mut event: CalendarEvent) -> CalendarEvent {
+        event.responses = self
+            .conn
+            .prepare_cached("SELECT responder, status, responded_at FROM event_responses WHERE event_id = ?")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![event.invite.id], |row| {
+                    let status: String = row.get(1)?;
+                    let (public_key, responded_at) = (row.get(0)?, row.get(2)?);
+                    Ok(RsvpStatus::parse(&status).map(|status| EventResponse {
+                        public_key,
+                        status,
+                        responded_at,
+                    }))
+                })?
+                .collect::<Result<Vec<_>, _>>()
+            })
+            .map(|responses| responses.into_iter().flatten().collect())
+            .unwrap_or_default();
+        event
+    }
+}
+
+/// Map `invite_json, thread_id, my_status`; None if the invite doesn't parse
+fn event_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<CalendarEvent>> {
+    let invite_json: String = row.get(0)?;
+    let my_status: Option<String> = row.get(2)?;
+    Ok(serde_json::from_str(&invite_json).ok().map(|invite| CalendarEvent {
+        invite,
+        thread_id: row.get(1).ok().flatten(),
+        my_status: my_status.as_deref().and_then(RsvpStatus::parse),
+        responses: Vec::new(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn invite(id: &str, start: i64) -> EventInvite {
+        EventInvite {
+            id: id.to_string(),
+            organizer: "ab".repeat(32),
+            title: format!("Event {}", id),
+            start,
+            end: start + 1_000,
+            location: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_agenda() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        db.save_event(&invite("e1", 10_000), Some("t1"), None).unwrap();
+        db.save_event(&invite("e2", 5_000), Some("t1"), None).unwrap();
+        db.save_event(&invite("e3", 7_000), None, Some(RsvpStatus::Accepted)).unwrap();
+        assert!(db.get_agenda(0, 20_000).unwrap().iter().all(|e| e.invite.id == "e3"));
+
+        assert!(db.set_event_status("e1", RsvpStatus::Accepted).unwrap());
+        assert!(db.set_event_status("e2", RsvpStatus::Declined).unwrap());
+        assert!(!db.set_event_status("missing", RsvpStatus::Accepted).unwrap());
+        let agenda = db.get_agenda(0, 20_000).unwrap();
+        assert_eq!(agenda.iter().map(|e| e.invite.id.as_str()).collect::<Vec<_>>(), vec!["e3", "e1"]);
+        assert!(db.get_agenda(12_000, 20_000).unwrap().is_empty());
+
+        let guest = "cd".repeat(32);
+        db.record_event_response("e3", &guest, RsvpStatus::Tentative, 2).unwrap();
+        db.record_event_response("e3", &guest, RsvpStatus::Declined, 1).unwrap();
+        let responses = db.get_event("e3").unwrap().responses;
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].status, RsvpStatus::Tentative);
+    }
+}
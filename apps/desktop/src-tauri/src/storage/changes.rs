@@ -0,0 +1,81 @@
+//! Change events
+//!
+//! Writes that change what an open thread view shows record a
+//! `StorageChange`. Recording happens on the database thread, so changes
+//! are buffered there and `DatabaseHandle` publishes them once the job
+//! that made them has finished; a rolled back `in_transaction` drops its
+//! own. `start_change_events` forwards them to the UI as
+//! `thread_updated` and `message_inserted` events, so views can refresh
+//! the one thread or message that changed instead of whole lists.
+
+use std::cell::RefCell;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+use super::DatabaseHandle;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum StorageChange {
+    /// The thread's row changed: new activity, unread count, folder, or
+    /// it was deleted
+    ThreadUpdated { thread_id: String },
+    MessageInserted { thread_id: String, message_id: String },
+}
+
+impl StorageChange {
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::ThreadUpdated { .. } => "thread_updated",
+            Self::MessageInserted { .. } => "message_inserted",
+        }
+    }
+}
+
+thread_local! {
+    static PENDING: RefCell<Vec<StorageChange>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(super) fn record(change: StorageChange) {
+    PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        // A batch touches the same thread over and over
+        if !pending.contains(&change) {
+            pending.push(change);
+        }
+    });
+}
+
+/// How many changes are buffered, to roll back to
+pub(super) fn mark() -> usize {
+    PENDING.with(|pending| pending.borrow().len())
+}
+
+pub(super) fn rollback(mark: usize) {
+    PENDING.with(|pending| pending.borrow_mut().truncate(mark));
+}
+
+/// Changes recorded on this thread since the last call
+pub(super) fn take() -> Vec<StorageChange> {
+    PENDING.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
+
+/// Publish storage changes as UI events
+pub fn start_change_events(app_handle: AppHandle, database: &DatabaseHandle) {
+    let mut changes = database.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match changes.recv().await {
+                Ok(change) => {
+                    let _ = app_handle.emit(change.event_name(), &change);
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Dropped {} storage change events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,233 @@
+//! Channels
+//!
+//! Channels we publish or follow, and their cached posts.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::channels::{Channel, ChannelInfo, ChannelPost};
+
+impl Database {
+    pub(super) fn initialize_channel_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS channels (
+                id TEXT PRIMARY KEY,
+                publisher TEXT NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT,
+                created_at INTEGER NOT NULL,
+                nonce TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                is_own INTEGER NOT NULL DEFAULT 0,
+                subscribed INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS channel_posts (
+                id TEXT PRIMARY KEY,
+                channel_id TEXT NOT NULL,
+                publisher TEXT NOT NULL,
+                payload_type TEXT NOT NULL,
+                payload_json TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                signature TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_channel_posts_channel ON channel_posts(channel_id, timestamp DESC);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Store a channel's descriptor; flags only ever get set here, so a
+    /// refreshed descriptor keeps an earlier subscription
+    pub fn save_channel(&mut self, info: &ChannelInfo, is_own: bool, subscribed: bool) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT INTO channels (id, publisher, name, description, created_at, nonce, signature, is_own, subscribed)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(id) DO UPDATE SET
+                    name = excluded.name,
+                    description = excluded.description,
+                    signature = excluded.signature,
+                    is_own = is_own OR excluded.is_own,
+                    subscribed = subscribed OR excluded.subscribed
+                "#,
+                params![
+                    info.id,
+                    info.publisher.to_lowercase(),
+                    info.name,
+                    info.description,
+                    info.created_at,
+                    info.nonce,
+                    info.signature,
+                    is_own,
+                    subscribed,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_channel(&self, channel_id: &str) -> Option<Channel> {
+        self.conn
+            .query_row(
+                r#"
+                SELECT id, publisher, name, description, created_at, nonce, signature, is_own, subscribed
+                FROM channels WHERE id = ?
+                "#,
+                params![channel_id],
+                channel_from_row,
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    /// Descriptors of the channels we follow
+    pub fn subscribed_channels(&self) -> Vec<ChannelInfo> {
+        self.conn
+            .prepare(
+                r#"
+                SELECT id, publisher, name, description, created_at, nonce, signature, is_own, subscribed
+                FROM channels WHERE subscribed = 1
+                "#,
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| channel_from_row(row).map(|c| c.info))?
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Cache a post; false if we already had it
+    pub fn save_channel_post(&mut self, post: &ChannelPost) -> Result<bool, DatabaseError> {
+        let inserted = self
+            .conn
+            .execute(
+                r#"
+                INSERT OR IGNORE INTO channel_posts (id, channel_id, publisher, payload_type, payload_json, timestamp, signature)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+                params![
+                    post.id,
+                    post.channel_id,
+                    post.publisher.to_lowercase(),
+                    post.payload_type,
+                    post.payload.to_string(),
+                    post.timestamp,
+                    post.signature,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(inserted > 0)
+    }
+
+    /// Cached posts in a channel, newest first, before `before` (ms) if given
+    pub fn get_channel_posts(
+        &self,
+        channel_id: &str,
+        limit: u32,
+        before: Option<i64>,
+    ) -> Result<Vec<ChannelPost>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT id, channel_id, publisher, payload_type, payload_json, timestamp, signature
+                FROM channel_posts
+                WHERE channel_id = ?1 AND (?2 IS NULL OR timestamp < ?2)
+                ORDER BY timestamp DESC LIMIT ?3
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let posts = stmt
+            .query_map(params![channel_id, before, limit], |row| {
+                let payload_json: String = row.get(4)?;
+                Ok(ChannelPost {
+                    id: row.get(0)?,
+                    channel_id: row.get(1)?,
+                    publisher: row.get(2)?,
+                    payload_type: row.get(3)?,
+                    payload: serde_json::from_str(&payload_json).unwrap_or_default(),
+                    timestamp: row.get(5)?,
+                    signature: row.get(6)?,
+                })
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        posts
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Timestamp of the newest cached post in a channel
+    pub fn latest_channel_post_time(&self, channel_id: &str) -> Option<i64> {
+        self.conn
+            .query_row(
+                "SELECT MAX(timestamp) FROM channel_posts WHERE channel_id = ?",
+                params![channel_id],
+                |row| row.get(0),
+            )
+            .ok()
+            .flatten()
+    }
+}
+
+fn channel_from_row(row: &rusqlite::Row) -> rusqlite::Result<Channel> {
+    Ok(Channel {
+        info: ChannelInfo {
+            id: row.get(0)?,
+            publisher: row.get(1)?,
+            name: row.get(2)?,
+            description: row.get(3)?,
+            created_at: row.get(4)?,
+            nonce: row.get(5)?,
+            signature: row.get(6)?,
+        },
+        is_own: row.get(7)?,
+        subscribed: row.get(8)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels;
+    use gns_crypto_core::GnsIdentity;
+    use rusqlite::Connection;
+    use serde_json::json;
+
+    #[test]
+    fn test_channel_posts() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let identity = GnsIdentity::generate();
+        let info = channels::create_channel(&identity, "News", None, 1_000);
+
+        db.save_channel(&info, false, true).unwrap();
+        // Storing the descriptor again doesn't drop the subscription
+        db.save_channel(&info, false, false).unwrap();
+        assert!(db.get_channel(&info.id).unwrap().subscribed);
+        assert_eq!(db.subscribed_channels(), vec![info.clone()]);
+        assert_eq!(db.latest_channel_post_time(&info.id), None);
+
+        for (n, timestamp) in [(1, 2_000), (2, 3_000), (3, 4_000)] {
+            let post = channels::create_post(&identity, &info.id, "text/plain", json!({ "text": n.to_string() }), timestamp);
+            assert!(db.save_channel_post(&post).unwrap());
+            assert!(!db.save_channel_post(&post).unwrap());
+        }
+
+        assert_eq!(db.latest_channel_post_time(&info.id), Some(4_000));
+        let posts = db.get_channel_posts(&info.id, 10, None).unwrap();
+        assert_eq!(posts.iter().map(|p| p.timestamp).collect::<Vec<_>>(), vec![4_000, 3_000, 2_000]);
+        assert!(channels::verify_post(&posts[0], &info).is_ok());
+        assert_eq!(db.get_channel_posts(&info.id, 1, Some(4_000)).unwrap()[0].timestamp, 3_000);
+    }
+}
@@ -0,0 +1,98 @@
+//! Handle claim workflow
+//!
+//! One row per identity tracking its handle from reservation to published
+//! record, so an interrupted claim can be resumed.
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::commands::handles::{ClaimStage, ClaimWorkflow};
+
+impl Database {
+    /// Create claim workflow tables
+    pub(super) fn initialize_claim_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS claim_workflow (
+                public_key TEXT PRIMARY KEY,
+                handle TEXT NOT NULL,
+                stage TEXT NOT NULL,
+                network_reserved INTEGER NOT NULL DEFAULT 0,
+                reserved_at INTEGER NOT NULL,
+                expires_at INTEGER,
+                claimed_at INTEGER,
+                published_at INTEGER,
+                last_error TEXT,
+                updated_at INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Claim workflow for an identity
+    pub fn get_claim_workflow(&self, public_key: &str) -> Option<ClaimWorkflow> {
+        self.conn
+            .query_row(
+                r#"
+                SELECT public_key, handle, stage, network_reserved, reserved_at, expires_at,
+                       claimed_at, published_at, last_error, updated_at
+                FROM claim_workflow WHERE public_key = ?
+                "#,
+                params![public_key],
+                |row| {
+                    let stage: String = row.get(2)?;
+                    Ok(ClaimWorkflow {
+                        public_key: row.get(0)?,
+                        handle: row.get(1)?,
+                        stage: ClaimStage::parse(&stage).unwrap_or(ClaimStage::Collecting),
+                        network_reserved: row.get(3)?,
+                        reserved_at: row.get(4)?,
+                        expires_at: row.get(5)?,
+                        claimed_at: row.get(6)?,
+                        published_at: row.get(7)?,
+                        last_error: row.get(8)?,
+                        updated_at: row.get(9)?,
+                    })
+                },
+            )
+            .ok()
+    }
+
+    /// Insert or replace an identity's claim workflow
+    pub fn save_claim_workflow(&mut self, workflow: &ClaimWorkflow) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT OR REPLACE INTO claim_workflow
+                    (public_key, handle, stage, network_reserved, reserved_at, expires_at,
+                     claimed_at, published_at, last_error, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                "#,
+                params![
+                    workflow.public_key,
+                    workflow.handle,
+                    workflow.stage.as_str(),
+                    workflow.network_reserved,
+                    workflow.reserved_at,
+                    workflow.expires_at,
+                    workflow.claimed_at,
+                    workflow.published_at,
+                    workflow.last_error,
+                    workflow.updated_at,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Forget an identity's claim workflow
+    pub fn delete_claim_workflow(&mut self, public_key: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute("DELETE FROM claim_workflow WHERE public_key = ?", params![public_key])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! Contact keys
+//!
+//! Last known encryption key per peer, and the key the user verified with
+//! a safety number. A peer whose key differs from the verified one is
+//! flagged until the user verifies again.
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::commands::safety::ContactKey;
+
+impl Database {
+    /// Create contact key tables
+    pub(super) fn initialize_contact_key_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS contact_keys (
+                public_key TEXT PRIMARY KEY,
+                encryption_key TEXT NOT NULL,
+                verified_encryption_key TEXT,
+                verified_at INTEGER,
+                key_changed_at INTEGER,
+                checked_at INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Known keys for a peer
+    pub fn get_contact_key(&self, public_key: &str) -> Option<ContactKey> {
+        self.conn
+            .query_row(
+                r#"
+                SELECT public_key, encryption_key, verified_encryption_key, verified_at,
+                       key_changed_at, checked_at
+                FROM contact_keys WHERE public_key = ?
+                "#,
+                params![public_key],
+                |row| {
+                    let verified_key: Option<String> = row.get(2)?;
+                    Ok(ContactKey {
+                        public_key: row.get(0)?,
+                        encryption_key: row.get(1)?,
+                        verified: verified_key.is_some(),
+                        verified_at: row.get(3)?,
+                        key_changed_at: row.get(4)?,
+                        checked_at: row.get(5)?,
+                    })
+                },
+            )
+            .ok()
+    }
+
+    /// Record a peer's current encryption key.
+    /// Returns true when this newly differs from the key the user verified.
+    pub fn record_contact_key(
+        &mut self,
+        public_key: &str,
+        encryption_key: &str,
+        now: i64,
+    ) -> Result<bool, DatabaseError> {
+        let verified_key: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT verified_encryption_key FROM contact_keys WHERE public_key = ? AND key_changed_at IS NULL",
+                params![public_key],
+                |row| row.get(0),
+            )
+            .unwrap_or(None);
+        let changed = verified_key.is_some_and(|k| !k.eq_ignore_ascii_case(encryption_key));
+
+        self.conn
+            .execute(
+                r#"
+                INSERT INTO contact_keys (public_key, encryption_key, checked_at)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(public_key) DO UPDATE SET
+                    encryption_key = excluded.encryption_key,
+                    checked_at = excluded.checked_at,
+                    key_changed_at = CASE WHEN ?4 THEN ?3 ELSE contact_keys.key_changed_at END
+                "#,
+                params![public_key, encryption_key, now, changed],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(changed)
+    }
+
+    /// Mark the peer's current key as verified, or clear verification
+    pub fn set_contact_verified(
+        &mut self,
+        public_key: &str,
+        verified: bool,
+        now: i64,
+    ) -> Result<(), DatabaseError> {
+        let result = if verified {
+            self.conn.execute(
+                r#"
+                UPDATE contact_keys
+                SET verified_encryption_key = encryption_key, verified_at = ?2, key_changed_at = NULL
+                WHERE public_key = ?1
+                "#,
+                params![public_key, now],
+            )
+        } else {
+            self.conn.execute(
+                r#"
+                UPDATE contact_keys
+                SET verified_encryption_key = NULL, verified_at = NULL, key_changed_at = NULL
+                WHERE public_key = ?1
+                "#,
+                params![public_key],
+            )
+        };
+        result.map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+//! Contact requests
+//!
+//! Senders awaiting the user's consent, and the messages held for them.
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::contact_requests::{ContactRequest, HeldMessage};
+use crate::spam::SenderVerdict;
+
+impl Database {
+    pub(super) fn initialize_contact_request_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS contact_requests (
+                public_key TEXT PRIMARY KEY,
+                handle TEXT,
+                encryption_key TEXT,
+                received_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS held_messages (
+                id TEXT PRIMARY KEY,
+                public_key TEXT NOT NULL REFERENCES contact_requests(public_key) ON DELETE CASCADE,
+                thread_id TEXT NOT NULL,
+                from_handle TEXT,
+                payload_type TEXT NOT NULL,
+                payload_json TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                signature_valid INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_held_messages_key ON held_messages(public_key, timestamp);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn has_contact_request(&self, public_key: &str) -> bool {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM contact_requests WHERE public_key = ?)",
+                params![public_key],
+                |row| row.get(0),
+            )
+            .unwrap_or(false)
+    }
+
+    /// Hold a message, opening a request for its sender if needed
+    pub fn hold_message(&mut self, message: &HeldMessage) -> Result<(), DatabaseError> {
+        let encryption_key = message.offered_encryption_key();
+        self.in_transaction(|db| {
+            db.conn
+                .execute(
+                    r#"
+                    INSERT INTO contact_requests (public_key, handle, encryption_key, received_at, updated_at)
+                    VALUES (?1, ?2, ?3, ?4, ?4)
+                    ON CONFLICT(public_key) DO UPDATE SET
+                        handle = COALESCE(excluded.handle, contact_requests.handle),
+                        encryption_key = COALESCE(excluded.encryption_key, contact_requests.encryption_key),
+                        updated_at = MAX(contact_requests.updated_at, excluded.updated_at)
+                    "#,
+                    params![message.from_public_key, message.from_handle, encryption_key, message.timestamp],
+                )
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            db.conn
+                .execute(
+                    r#"
+                    INSERT OR IGNORE INTO held_messages
+                    (id, public_key, thread_id, from_handle, payload_type, payload_json, timestamp, signature_valid)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                    params![
+                        message.id,
+                        message.from_public_key,
+                        message.thread_id,
+                        message.from_handle,
+                        message.payload_type,
+                        serde_json::to_string(&message.payload).unwrap_or_default(),
+                        message.timestamp,
+                        message.signature_valid,
+                    ],
+                )
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            Ok(())
+        })
+    }
+
+    /// Pending requests, most recently active first
+    pub fn list_contact_requests(&self) -> Result<Vec<ContactRequest>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT r.public_key, r.handle, r.encryption_key, r.received_at, r.updated_at,
+                       (SELECT COUNT(*) FROM held_messages h WHERE h.public_key = r.public_key),
+                       (SELECT h.payload_json FROM held_messages h WHERE h.public_key = r.public_key
+                        ORDER BY h.timestamp LIMIT 1)
+                FROM contact_requests r
+                ORDER BY r.updated_at DESC
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let requests = stmt
+            .query_map([], |row| {
+                let payload: Option<String> = row.get(6)?;
+                Ok(ContactRequest {
+                    public_key: row.get(0)?,
+                    handle: row.get(1)?,
+                    encryption_key: row.get(2)?,
+                    received_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                    message_count: row.get(5)?,
+                    preview: payload
+                        .and_then(|p| serde_json::from_str::<serde_json::Value>(&p).ok())
+                        .and_then(|p| p.get("text").and_then(|t| t.as_str()).map(String::from)),
+                })
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(requests)
+    }
+
+    pub fn get_contact_request(&self, public_key: &str) -> Result<Option<ContactRequest>, DatabaseError> {
+        Ok(self
+            .list_contact_requests()?
+            .into_iter()
+            .find(|r| r.public_key == public_key))
+    }
+
+    /// Accept a sender: pin `encryption_key` as verified, release their
+    /// held messages into threads, and close the request. Returns the
+    /// threads that received messages.
+    pub fn accept_contact_request(
+        &mut self,
+        public_key: &str,
+        encryption_key: &str,
+        now: i64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        let held = self.held_messages(public_key)?;
+        self.in_transaction(|db| {
+            let mut thread_ids: Vec<String> = Vec::new();
+            for message in &held {
+                db.save_received_message(
+                    &message.id,
+                    &message.thread_id,
+                    &message.from_public_key,
+                    message.from_handle.as_deref(),
+                    &message.payload_type,
+                    &message.payload,
+                    message.timestamp,
+                    message.signature_valid,
+                    None,
+                )?;
+                if !thread_ids.contains(&message.thread_id) {
+                    thread_ids.push(message.thread_id.clone());
+                }
+            }
+
+            db.record_contact_key(public_key, encryption_key, now)?;
+            db.set_contact_verified(public_key, true, now)?;
+            db.set_sender_verdict(public_key, SenderVerdict::Accepted, now)?;
+            db.delete_contact_request(public_key)?;
+            Ok(thread_ids)
+        })
+    }
+
+    /// Drop a sender's held messages and junk anything further from them
+    pub fn decline_contact_request(&mut self, public_key: &str, now: i64) -> Result<(), DatabaseError> {
+        self.in_transaction(|db| {
+            db.set_sender_verdict(public_key, SenderVerdict::Spam, now)?;
+            db.delete_contact_request(public_key)
+        })
+    }
+
+    fn delete_contact_request(&mut self, public_key: &str) -> Result<(), DatabaseError> {
+        // Explicit so this doesn't depend on foreign key enforcement
+        self.conn
+            .execute("DELETE FROM held_messages WHERE public_key = ?", params![public_key])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let deleted = self
+            .conn
+            .execute("DELETE FROM contact_requests WHERE public_key = ?", params![public_key])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        if deleted == 0 {
+            return Err(DatabaseError::SqliteError(format!("No contact request from {}", public_key)));
+        }
+        Ok(())
+    }
+
+    fn held_messages(&self, public_key: &str) -> Result<Vec<HeldMessage>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT id, thread_id, from_handle, payload_type, payload_json, timestamp, signature_valid
+                FROM held_messages WHERE public_key = ? ORDER BY timestamp
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let messages = stmt
+            .query_map(params![public_key], |row| {
+                let payload: String = row.get(4)?;
+                Ok(HeldMessage {
+                    id: row.get(0)?,
+                    thread_id: row.get(1)?,
+                    from_public_key: public_key.to_string(),
+                    from_handle: row.get(2)?,
+                    payload_type: row.get(3)?,
+                    payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null),
+                    timestamp: row.get(5)?,
+                    signature_valid: row.get(6)?,
+                })
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contact_requests::CONTACT_REQUEST_PAYLOAD_TYPE;
+    use rusqlite::Connection;
+
+    fn held(id: &str, from: &str, payload_type: &str, payload: serde_json::Value, timestamp: i64) -> HeldMessage {
+        HeldMessage {
+            id: id.to_string(),
+            thread_id: format!("direct_{}", from),
+            from_public_key: from.to_string(),
+            from_handle: Some("@stranger".to_string()),
+            payload_type: payload_type.to_string(),
+            payload,
+            timestamp,
+            signature_valid: true,
+        }
+    }
+
+    #[test]
+    fn test_contact_requests() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let (alice, bob) = ("a".repeat(64), "b".repeat(64));
+        let key = "e".repeat(64);
+
+        let intro = serde_json::json!({ "text": "Hi, we met at the meetup", "encryption_key": key });
+        db.hold_message(&held("m1", &alice, CONTACT_REQUEST_PAYLOAD_TYPE, intro, 1)).unwrap();
+        db.hold_message(&held("m2", &alice, "text/plain", serde_json::json!({ "text": "hello?" }), 2))
+            .unwrap();
+        db.hold_message(&held("m3", &bob, "text/plain", serde_json::json!({ "text": "buy" }), 3))
+            .unwrap();
+
+        let requests = db.list_contact_requests().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].public_key, bob);
+        let request = db.get_contact_request(&alice).unwrap().unwrap();
+        assert_eq!(request.message_count, 2);
+        assert_eq!(request.encryption_key.as_deref(), Some(key.as_str()));
+        assert_eq!(request.preview.as_deref(), Some("Hi, we met at the meetup"));
+        // Held messages aren't in any thread yet
+        assert!(db.get_threads(true, None, 10).unwrap().is_empty());
+
+        let threads = db.accept_contact_request(&alice, &key, 10).unwrap();
+        assert_eq!(threads, vec![format!("direct_{}", alice)]);
+        assert!(db.get_message("m2").unwrap().is_some());
+        assert!(db.get_contact_key(&alice).unwrap().verified);
+        assert!(db.is_contact(&alice));
+        assert!(!db.has_contact_request(&alice));
+
+        db.decline_contact_request(&bob, 11).unwrap();
+        assert_eq!(db.get_sender_verdict(&bob), Some(SenderVerdict::Spam));
+        assert!(db.list_contact_requests().unwrap().is_empty());
+        assert!(db.decline_contact_request(&bob, 12).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Contact book
+//!
+//! Identities the user knows, with where they came from.
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::contacts::Contact;
+
+impl Database {
+    pub(super) fn initialize_contact_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS contacts (
+                public_key TEXT PRIMARY KEY,
+                handle TEXT,
+                display_name TEXT,
+                source TEXT NOT NULL,
+                added_at INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Add a contact; one already in the book keeps its name and source,
+    /// and only picks up a newer handle. Returns true when newly added.
+    pub fn add_contact(&mut self, contact: &Contact) -> Result<bool, DatabaseError> {
+        let existed = self.has_contact(&contact.public_key);
+        self.conn
+            .execute(
+                r#"
+                INSERT INTO contacts (public_key, handle, display_name, source, added_at)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(public_key) DO UPDATE SET
+                    handle = COALESCE(excluded.handle, contacts.handle)
+                "#,
+                params![
+                    contact.public_key,
+                    contact.handle,
+                    contact.display_name,
+                    contact.source,
+                    contact.added_at
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(!existed)
+    }
+
+    pub fn has_contact(&self, public_key: &str) -> bool {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM contacts WHERE public_key = ?)",
+                params![public_key],
+                |row| row.get(0),
+            )
+            .unwrap_or(false)
+    }
+
+    /// The contact book, by name then handle
+    pub fn list_contacts(&self) -> Result<Vec<Contact>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r#"
+                SELECT public_key, handle, display_name, source, added_at FROM contacts
+                ORDER BY COALESCE(display_name, handle, public_key) COLLATE NOCASE
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let contacts = stmt
+            .query_map([], |row| {
+                Ok(Contact {
+                    public_key: row.get(0)?,
+                    handle: row.get(1)?,
+                    display_name: row.get(2)?,
+                    source: row.get(3)?,
+                    added_at: row.get(4)?,
+                })
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(contacts)
+    }
+
+    pub fn remove_contact(&mut self, public_key: &str) -> Result<bool, DatabaseError> {
+        let removed = self
+            .conn
+            .execute("DELETE FROM contacts WHERE public_key = ?", params![public_key])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(removed > 0)
+    }
+}
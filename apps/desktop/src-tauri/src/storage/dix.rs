@@ -0,0 +1,492 @@
+//! Dix local cache
+//!
+//! Local state for the microblogging feed that the server either doesn't
+//! know about yet (our own fresh replies) or shouldn't need to be asked
+//! for again, including a copy of recently seen timeline posts so the feed
+//! opens instantly and works offline, and bookmarks (sealed to our own key
+//! by the caller, so only the post id is stored in the clear).
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::dix::{DixEdit, DixFilters, DixFollowUser, DixPost, DixSyncDelta, DixTombstone};
+
+impl Database {
+    /// Create Dix tables
+    pub(super) fn initialize_dix_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS dix_reply_counts (
+                post_id TEXT PRIMARY KEY,
+                reply_count INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS dix_following (
+                owner_public_key TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                handle TEXT,
+                display_name TEXT,
+                avatar_url TEXT,
+                followed_at INTEGER NOT NULL,
+                PRIMARY KEY (owner_public_key, public_key)
+            );
+
+            CREATE TABLE IF NOT EXISTS dix_posts (
+                id TEXT PRIMARY KEY,
+                author_public_key TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                post_json TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_dix_posts_created ON dix_posts(created_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_dix_posts_author ON dix_posts(author_public_key, created_at DESC);
+
+            CREATE TABLE IF NOT EXISTS dix_tombstones (
+                post_id TEXT PRIMARY KEY,
+                author_public_key TEXT NOT NULL,
+                deleted_at TEXT NOT NULL,
+                signature TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS dix_bookmarks (
+                owner_public_key TEXT NOT NULL,
+                post_id TEXT NOT NULL,
+                sealed TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (owner_public_key, post_id)
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    // ==================== Reply Counts ====================
+
+    /// Get the cached reply count for a post
+    pub fn get_dix_reply_count(&self, post_id: &str) -> Option<u32> {
+        self.conn
+            .query_row(
+                "SELECT reply_count FROM dix_reply_counts WHERE post_id = ?",
+                params![post_id],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    /// Store the reply count reported by the server
+    pub fn set_dix_reply_count(&mut self, post_id: &str, count: u32) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO dix_reply_counts (post_id, reply_count, updated_at) VALUES (?, ?, ?)",
+                params![post_id, count, chrono::Utc::now().timestamp_millis()],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Bump the cached reply count after we reply to a post
+    pub fn increment_dix_reply_count(&mut self, post_id: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT INTO dix_reply_counts (post_id, reply_count, updated_at)
+                VALUES (?1, 1, ?2)
+                ON CONFLICT(post_id) DO UPDATE SET
+                    reply_count = reply_count + 1,
+                    updated_at = ?2
+                "#,
+                params![post_id, chrono::Utc::now().timestamp_millis()],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    // ==================== Following ====================
+
+    /// Cached follow list for an identity
+    pub fn get_dix_following(&self, owner_public_key: &str) -> Result<Vec<DixFollowUser>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT public_key, handle, display_name, avatar_url FROM dix_following
+                 WHERE owner_public_key = ? ORDER BY followed_at DESC",
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let users = stmt
+            .query_map(params![owner_public_key], |row| {
+                Ok(DixFollowUser {
+                    public_key: row.get(0)?,
+                    handle: row.get(1)?,
+                    display_name: row.get(2)?,
+                    avatar_url: row.get(3)?,
+                })
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(users)
+    }
+
+    /// Add a user to the cached follow list
+    pub fn add_dix_following(&mut self, owner_public_key: &str, user: &DixFollowUser) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT OR REPLACE INTO dix_following
+                (owner_public_key, public_key, handle, display_name, avatar_url, followed_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+                params![
+                    owner_public_key,
+                    user.public_key,
+                    user.handle,
+                    user.display_name,
+                    user.avatar_url,
+                    chrono::Utc::now().timestamp_millis(),
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Remove a user from the cached follow list
+    pub fn remove_dix_following(&mut self, owner_public_key: &str, public_key: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "DELETE FROM dix_following WHERE owner_public_key = ? AND public_key = ?",
+                params![owner_public_key, public_key],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Replace the cached follow list with the server's copy
+    pub fn replace_dix_following(&mut self, owner_public_key: &str, users: &[DixFollowUser]) -> Result<(), DatabaseError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        tx.execute(
+            "DELETE FROM dix_following WHERE owner_public_key = ?",
+            params![owner_public_key],
+        )
+        .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        // Keep the server's ordering (newest first) when read back
+        for (i, user) in users.iter().enumerate() {
+            tx.execute(
+                r#"
+                INSERT OR REPLACE INTO dix_following
+                (owner_public_key, public_key, handle, display_name, avatar_url, followed_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+                params![
+                    owner_public_key,
+                    user.public_key,
+                    user.handle,
+                    user.display_name,
+                    user.avatar_url,
+                    now - i as i64,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    // ==================== Timeline Cache ====================
+
+    /// A page of cached timeline posts, newest first, optionally limited to some authors
+    pub fn get_cached_dix_timeline(
+        &self,
+        limit: u32,
+        offset: u32,
+        authors: Option<&[String]>,
+    ) -> Result<Vec<DixPost>, DatabaseError> {
+        let mut sql = String::from("SELECT post_json FROM dix_posts");
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        if let Some(authors) = authors {
+            if authors.is_empty() {
+                return Ok(vec![]);
+            }
+            let placeholders = vec!["?"; authors.len()].join(", ");
+            sql.push_str(&format!(" WHERE author_public_key IN ({})", placeholders));
+            values.extend(authors.iter().cloned().map(rusqlite::types::Value::Text));
+        }
+
+        sql.push_str(" ORDER BY created_at DESC LIMIT ? OFFSET ?");
+        values.push(rusqlite::types::Value::Integer(limit as i64));
+        values.push(rusqlite::types::Value::Integer(offset as i64));
+
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let posts = stmt
+            .query_map(rusqlite::params_from_iter(values), |row| row.get::<_, String>(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+
+        Ok(posts)
+    }
+
+    /// A single cached post
+    pub fn get_cached_dix_post(&self, post_id: &str) -> Option<DixPost> {
+        let json: String = self
+            .conn
+            .query_row(
+                "SELECT post_json FROM dix_posts WHERE id = ?",
+                params![post_id],
+                |row| row.get(0),
+            )
+            .ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Merge fetched posts into the cache, reporting which ones are new or changed
+    pub fn upsert_dix_posts(&mut self, posts: &[DixPost]) -> Result<DixSyncDelta, DatabaseError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut delta = DixSyncDelta::default();
+
+        for post in posts {
+            let deleted: bool = tx
+                .query_row(
+                    "SELECT 1 FROM dix_tombstones WHERE post_id = ? AND author_public_key = ?",
+                    params![post.id, post.author.public_key],
+                    |_| Ok(true),
+                )
+                .unwrap_or(false);
+            if deleted {
+                continue;
+            }
+
+            let json = serde_json::to_string(post).unwrap_or_default();
+
+            let existing: Option<String> = tx
+                .query_row(
+                    "SELECT post_json FROM dix_posts WHERE id = ?",
+                    params![post.id],
+                    |row| row.get(0),
+                )
+                .ok();
+
+            match existing {
+                None => {
+                    delta.new_posts += 1;
+                    delta.new_post_ids.push(post.id.clone());
+                }
+                Some(ref old) if *old != json => delta.updated_posts += 1,
+                Some(_) => {}
+            }
+
+            tx.execute(
+                r#"
+                INSERT OR REPLACE INTO dix_posts (id, author_public_key, created_at, post_json, fetched_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+                params![post.id, post.author.public_key, post.meta.created_at, json, now],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(delta)
+    }
+
+    // ==================== Tombstones & Edits ====================
+
+    /// Record a tombstone from `delete_post` and drop the post from the cache
+    pub fn apply_dix_tombstone(&mut self, tombstone: &DixTombstone) -> Result<(), DatabaseError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        tx.execute(
+            r#"
+            INSERT OR REPLACE INTO dix_tombstones (post_id, author_public_key, deleted_at, signature)
+            VALUES (?, ?, ?, ?)
+            "#,
+            params![
+                tombstone.post_id,
+                tombstone.author_public_key,
+                tombstone.deleted_at,
+                tombstone.signature,
+            ],
+        )
+        .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        // Only the author can delete; a tombstone for someone else's post is ignored
+        tx.execute(
+            "DELETE FROM dix_posts WHERE id = ? AND author_public_key = ?",
+            params![tombstone.post_id, tombstone.author_public_key],
+        )
+        .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Drop posts that have a local tombstone from their author
+    pub fn remove_tombstoned_dix_posts(&self, posts: &mut Vec<DixPost>) {
+        posts.retain(|post| {
+            self.conn
+                .query_row(
+                    "SELECT 1 FROM dix_tombstones WHERE post_id = ? AND author_public_key = ?",
+                    params![post.id, post.author.public_key],
+                    |_| Ok(()),
+                )
+                .is_err()
+        });
+    }
+
+    /// Apply a verified edit to the cached copy of a post
+    pub fn apply_dix_edit(&mut self, edit: &DixEdit) -> Result<Option<DixPost>, DatabaseError> {
+        let Some(mut post) = self.get_cached_dix_post(&edit.post_id) else {
+            return Ok(None);
+        };
+
+        edit.apply_to(&mut post);
+
+        self.conn
+            .execute(
+                "UPDATE dix_posts SET post_json = ? WHERE id = ?",
+                params![serde_json::to_string(&post).unwrap_or_default(), post.id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        Ok(Some(post))
+    }
+
+    // ==================== Filters ====================
+
+    /// Mute filters for an identity
+    pub fn get_dix_filters(&self, public_key: &str) -> DixFilters {
+        self.get_setting(&format!("dix_filters:{}", public_key))
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Store mute filters for an identity
+    pub fn set_dix_filters(&mut self, public_key: &str, filters: &DixFilters) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(filters)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting(&format!("dix_filters:{}", public_key), &json)
+    }
+
+    // ==================== Bookmarks ====================
+
+    /// Store a sealed bookmark, replacing any existing one for the post
+    pub fn save_dix_bookmark(&mut self, owner_public_key: &str, post_id: &str, sealed: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT INTO dix_bookmarks (owner_public_key, post_id, sealed, created_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT(owner_public_key, post_id) DO UPDATE SET sealed = ?3
+                "#,
+                params![owner_public_key, post_id, sealed, chrono::Utc::now().timestamp_millis()],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Remove a bookmark. Returns whether one existed.
+    pub fn delete_dix_bookmark(&mut self, owner_public_key: &str, post_id: &str) -> Result<bool, DatabaseError> {
+        let deleted = self
+            .conn
+            .execute(
+                "DELETE FROM dix_bookmarks WHERE owner_public_key = ? AND post_id = ?",
+                params![owner_public_key, post_id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(deleted > 0)
+    }
+
+    /// All sealed bookmarks for an identity, newest first
+    pub fn get_dix_bookmarks(&self, owner_public_key: &str) -> Result<Vec<String>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT sealed FROM dix_bookmarks WHERE owner_public_key = ? ORDER BY created_at DESC")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let sealed = stmt
+            .query_map(params![owner_public_key], |row| row.get(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(sealed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn user(public_key: &str, handle: &str) -> DixFollowUser {
+        DixFollowUser {
+            public_key: public_key.to_string(),
+            handle: Some(handle.to_string()),
+            display_name: None,
+            avatar_url: None,
+        }
+    }
+
+    fn keys(users: &[DixFollowUser]) -> Vec<&str> {
+        users.iter().map(|u| u.public_key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_following_cache() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let (me, other) = ("a".repeat(64), "f".repeat(64));
+
+        db.add_dix_following(&me, &user("b1", "bob")).unwrap();
+        db.add_dix_following(&me, &user("c1", "carol")).unwrap();
+        // Following again updates the entry instead of adding another
+        db.add_dix_following(&me, &user("b1", "bobby")).unwrap();
+        let following = db.get_dix_following(&me).unwrap();
+        assert_eq!(following.len(), 2);
+        assert!(following.iter().any(|u| u.handle.as_deref() == Some("bobby")));
+        // Lists are kept per identity
+        assert!(db.get_dix_following(&other).unwrap().is_empty());
+
+        db.remove_dix_following(&me, "b1").unwrap();
+        db.remove_dix_following(&other, "c1").unwrap();
+        assert_eq!(keys(&db.get_dix_following(&me).unwrap()), ["c1"]);
+
+        // The server's copy replaces the cache and keeps its order
+        db.add_dix_following(&other, &user("b1", "bob")).unwrap();
+        db.replace_dix_following(&me, &[user("d1", "dave"), user("b1", "bob"), user("e1", "erin")])
+            .unwrap();
+        assert_eq!(keys(&db.get_dix_following(&me).unwrap()), ["d1", "b1", "e1"]);
+        assert_eq!(keys(&db.get_dix_following(&other).unwrap()), ["b1"]);
+
+        db.replace_dix_following(&me, &[]).unwrap();
+        assert!(db.get_dix_following(&me).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+//! Dix notifications
+//!
+//! Activity on our posts and profile found by the notification poller,
+//! with its read state, and the engagement counts the next poll compares
+//! against. See `crate::dix::notifications`.
+
+use std::collections::HashMap;
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::dix::notifications::{DixActivity, EngagementCounts, NotificationKind};
+
+impl Database {
+    pub(super) fn initialize_dix_notification_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS dix_activity (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source_id TEXT NOT NULL UNIQUE,
+                kind TEXT NOT NULL,
+                post_id TEXT,
+                actor_public_key TEXT,
+                actor_handle TEXT,
+                count INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                read INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_dix_activity_created ON dix_activity(created_at DESC);
+
+            CREATE TABLE IF NOT EXISTS dix_engagement_seen (
+                post_id TEXT PRIMARY KEY,
+                likes INTEGER NOT NULL,
+                reposts INTEGER NOT NULL,
+                replies INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Record activity not seen before; returns how many rows were new
+    pub fn record_dix_activity(&mut self, activity: &[DixActivity], read: bool) -> Result<usize, DatabaseError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let mut added = 0;
+        for item in activity {
+            added += tx
+                .execute(
+                    r#"
+                    INSERT OR IGNORE INTO dix_activity
+                        (source_id, kind, post_id, actor_public_key, actor_handle, count, created_at, read)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                    params![
+                        item.source_id,
+                        item.kind.as_str(),
+                        item.post_id,
+                        item.actor_public_key,
+                        item.actor_handle,
+                        item.count,
+                        item.created_at,
+                        read,
+                    ],
+                )
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(added)
+    }
+
+    /// Most recent activity, newest first
+    pub fn list_dix_activity(&self, limit: u32) -> Result<Vec<DixActivity>, DatabaseError> {
+        self.query_dix_activity(None, limit)
+    }
+
+    /// Most recent mentions of us, newest first
+    pub fn list_dix_mentions(&self, limit: u32) -> Result<Vec<DixActivity>, DatabaseError> {
+        self.query_dix_activity(Some(NotificationKind::Mention), limit)
+    }
+
+    fn query_dix_activity(&self, kind: Option<NotificationKind>, limit: u32) -> Result<Vec<DixActivity>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT id, source_id, kind, post_id, actor_public_key, actor_handle, count, created_at, read
+                FROM dix_activity WHERE ?1 IS NULL OR kind = ?1
+                ORDER BY created_at DESC, id DESC LIMIT ?2
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let activity = stmt
+            .query_map(params![kind.map(|k| k.as_str()), limit], |row| {
+                let kind: String = row.get(2)?;
+                Ok(NotificationKind::parse(&kind).map(|kind| DixActivity {
+                    id: row.get(0).unwrap_or_default(),
+                    source_id: row.get(1).unwrap_or_default(),
+                    kind,
+                    post_id: row.get(3).ok().flatten(),
+                    actor_public_key: row.get(4).ok().flatten(),
+                    actor_handle: row.get(5).ok().flatten(),
+                    count: row.get(6).unwrap_or(1),
+                    created_at: row.get(7).unwrap_or_default(),
+                    read: row.get(8).unwrap_or_default(),
+                }))
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok().flatten())
+            .collect();
+        Ok(activity)
+    }
+
+    /// Mark activity read, all of it when `ids` is None; returns how many
+    /// rows changed
+    pub fn mark_dix_activity_read(&mut self, ids: Option<&[i64]>) -> Result<usize, DatabaseError> {
+        let Some(ids) = ids else {
+            return self
+                .conn
+                .execute("UPDATE dix_activity SET read = 1 WHERE read = 0", [])
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()));
+        };
+        let mut changed = 0;
+        for id in ids {
+            changed += self
+                .conn
+                .execute("UPDATE dix_activity SET read = 1 WHERE id = ? AND read = 0", params![id])
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+        Ok(changed)
+    }
+
+    /// Engagement counts from the last poll for the given posts
+    pub fn get_dix_engagement_seen(&self, post_ids: &[String]) -> Result<HashMap<String, EngagementCounts>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT likes, reposts, replies FROM dix_engagement_seen WHERE post_id = ?")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let mut seen = HashMap::new();
+        for post_id in post_ids {
+            let counts = stmt
+                .query_row(params![post_id], |row| {
+                    Ok(EngagementCounts {
+                        likes: row.get(0)?,
+                        reposts: row.get(1)?,
+                        replies: row.get(2)?,
+                    })
+                })
+                .ok();
+            if let Some(counts) = counts {
+                seen.insert(post_id.clone(), counts);
+            }
+        }
+        Ok(seen)
+    }
+
+    pub fn set_dix_engagement_seen(&mut self, counts: &[(String, EngagementCounts)]) -> Result<(), DatabaseError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        for (post_id, counts) in counts {
+            tx.execute(
+                "INSERT OR REPLACE INTO dix_engagement_seen (post_id, likes, reposts, replies) VALUES (?, ?, ?, ?)",
+                params![post_id, counts.likes, counts.reposts, counts.replies],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dix::notifications::{build_feed, engagement_activity, follow_activity};
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_dix_activity() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+
+        let counts = EngagementCounts { likes: 3, reposts: 0, replies: 0 };
+        let likes = engagement_activity("p1", EngagementCounts::default(), counts, 1_000);
+        assert_eq!(db.record_dix_activity(&likes, false).unwrap(), 1);
+        // The same activity found again isn't new
+        assert_eq!(db.record_dix_activity(&likes, false).unwrap(), 0);
+        let follow = follow_activity(&"ab".repeat(32), Some("alice".to_string()), 2_000);
+        assert_eq!(db.record_dix_activity(&[follow], true).unwrap(), 1);
+
+        let activity = db.list_dix_activity(10).unwrap();
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0].kind, NotificationKind::Follow);
+        assert_eq!(build_feed(&activity).unread, 1);
+        assert!(db.list_dix_mentions(10).unwrap().is_empty());
+
+        assert_eq!(db.mark_dix_activity_read(Some(&[activity[1].id])).unwrap(), 1);
+        assert_eq!(db.mark_dix_activity_read(None).unwrap(), 0);
+
+        db.set_dix_engagement_seen(&[("p1".to_string(), counts)]).unwrap();
+        let seen = db.get_dix_engagement_seen(&["p1".to_string(), "p2".to_string()]).unwrap();
+        assert_eq!(seen.get("p1"), Some(&counts));
+        assert!(!seen.contains_key("p2"));
+    }
+}
@@ -0,0 +1,167 @@
+//! GNS Earnings
+//!
+//! Claims made with `claim_gns_tokens` and the breadcrumb epochs each one
+//! rewarded. See `crate::stellar::earnings`.
+
+use std::collections::HashSet;
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::stellar::earnings::GnsClaim;
+use gns_crypto_core::EpochRoot;
+
+impl Database {
+    pub(super) fn initialize_earnings_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS gns_claims (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                public_key TEXT NOT NULL,
+                tx_hash TEXT,
+                amount REAL NOT NULL,
+                claimed_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS gns_rewarded_epochs (
+                public_key TEXT NOT NULL,
+                epoch INTEGER NOT NULL,
+                root TEXT NOT NULL,
+                breadcrumb_count INTEGER NOT NULL,
+                claim_id INTEGER NOT NULL,
+                PRIMARY KEY (public_key, epoch)
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Record a successful claim and the epochs it rewarded; fails without
+    /// recording anything if one of them was already rewarded
+    pub fn record_gns_claim(
+        &mut self,
+        public_key: &str,
+        tx_hash: Option<&str>,
+        amount: f64,
+        epochs: &[EpochRoot],
+    ) -> Result<i64, DatabaseError> {
+        let public_key = public_key.to_lowercase();
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        tx.execute(
+            "INSERT INTO gns_claims (public_key, tx_hash, amount, claimed_at) VALUES (?, ?, ?, ?)",
+            params![public_key, tx_hash, amount, chrono::Utc::now().timestamp_millis()],
+        )
+        .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let claim_id = tx.last_insert_rowid();
+        for epoch in epochs {
+            tx.execute(
+                r#"
+                INSERT INTO gns_rewarded_epochs (public_key, epoch, root, breadcrumb_count, claim_id)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+                params![public_key, epoch.epoch as i64, epoch.root, epoch.breadcrumb_count, claim_id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(claim_id)
+    }
+
+    /// Merkle roots of the epochs rewarded so far
+    pub fn get_rewarded_epochs(&self, public_key: &str) -> Result<HashSet<String>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT root FROM gns_rewarded_epochs WHERE public_key = ?")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let roots = stmt
+            .query_map(params![public_key.to_lowercase()], |row| row.get::<_, String>(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        roots
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Claims by `public_key` with the epochs each rewarded, newest first
+    pub fn list_gns_claims(&self, public_key: &str) -> Result<Vec<GnsClaim>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT c.id, c.tx_hash, c.amount, c.claimed_at, GROUP_CONCAT(e.epoch)
+                FROM gns_claims c
+                LEFT JOIN gns_rewarded_epochs e ON e.claim_id = c.id
+                WHERE c.public_key = ?
+                GROUP BY c.id
+                ORDER BY c.claimed_at DESC, c.id DESC
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let claims = stmt
+            .query_map(params![public_key.to_lowercase()], |row| {
+                let epochs: Option<String> = row.get(4)?;
+                let mut epochs: Vec<u64> = epochs
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter_map(|e| e.parse().ok())
+                    .collect();
+                epochs.sort_unstable();
+                Ok(GnsClaim {
+                    id: row.get(0)?,
+                    tx_hash: row.get(1)?,
+                    amount: row.get(2)?,
+                    claimed_at: row.get(3)?,
+                    epochs,
+                })
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        claims
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn epoch(epoch: u64) -> EpochRoot {
+        EpochRoot {
+            epoch,
+            root: format!("{:064x}", epoch),
+            breadcrumb_count: 100,
+            start_time: 0,
+            end_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_claims_are_recorded_once() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let pk = "AB".repeat(32);
+
+        db.record_gns_claim(&pk, Some("tx1"), 50.0, &[epoch(0), epoch(1)]).unwrap();
+        assert_eq!(db.get_rewarded_epochs(&pk).unwrap(), [epoch(0).root, epoch(1).root].into());
+        // Epoch 1 again fails and leaves no partial claim behind
+        assert!(db.record_gns_claim(&pk, Some("tx2"), 25.0, &[epoch(1), epoch(2)]).is_err());
+        db.record_gns_claim(&pk, Some("tx3"), 25.0, &[epoch(2)]).unwrap();
+
+        let claims = db.list_gns_claims(&pk.to_lowercase()).unwrap();
+        assert_eq!(claims.len(), 2);
+        assert_eq!(claims[0].tx_hash.as_deref(), Some("tx3"));
+        assert_eq!(claims[1].epochs, vec![0, 1]);
+        assert!(db.list_gns_claims(&"cd".repeat(32)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+//! Database thread
+//!
+//! rusqlite calls block, so the connection lives on a dedicated thread and
+//! async code sends it jobs through `DatabaseHandle::call`. A slow query
+//! then only delays other database work instead of holding a lock that
+//! stalls the async runtime and every IPC command behind it.
+//!
+//! After each job the thread publishes the storage changes the job made;
+//! see `super::changes`.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+
+use tokio::sync::{broadcast, oneshot};
+
+use super::changes::{self, StorageChange};
+use super::{Database, DatabaseError};
+
+/// Changes buffered for a subscriber that falls behind
+const CHANGE_CAPACITY: usize = 256;
+
+type Job = Box<dyn FnOnce(&mut Database) + Send>;
+
+/// Cheap, cloneable handle to the database thread
+#[derive(Clone)]
+pub struct DatabaseHandle {
+    jobs: mpsc::Sender<Job>,
+    changes: broadcast::Sender<StorageChange>,
+}
+
+impl DatabaseHandle {
+    /// Open the database and start its thread
+    pub fn open() -> Result<Self, DatabaseError> {
+        Self::spawn(Database::open()?)
+    }
+
+    /// Move an open database onto its own thread
+    pub fn spawn(mut database: Database) -> Result<Self, DatabaseError> {
+        let (jobs, rx) = mpsc::channel::<Job>();
+        let (changes, _) = broadcast::channel(CHANGE_CAPACITY);
+        let publish = changes.clone();
+        std::thread::Builder::new()
+            .name("gns-database".to_string())
+            .spawn(move || {
+                for job in rx {
+                    // A panicking job drops its reply sender, failing only
+                    // its own caller; keep serving everyone else
+                    let _ = std::panic::catch_unwind(AssertUnwindSafe(|| job(&mut database)));
+                    for change in changes::take() {
+                        let _ = publish.send(change);
+                    }
+                }
+            })
+            .map_err(|e| DatabaseError::IoError(e.to_string()))?;
+
+        Ok(Self { jobs, changes })
+    }
+
+    /// Storage changes made from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<StorageChange> {
+        self.changes.subscribe()
+    }
+
+    /// Run `f` on the database thread and wait for its result
+    ///
+    /// Jobs run one at a time in the order they were sent, so a multi-step
+    /// closure sees a consistent database.
+    pub async fn call<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Database) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        let job: Job = Box::new(move |database| {
+            let _ = reply.send(f(database));
+        });
+        self.jobs.send(job).expect("database thread has stopped");
+        result.await.expect("database job panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[tokio::test]
+    async fn test_call_survives_panicking_job() {
+        let handle = DatabaseHandle::spawn(Database {
+            conn: Connection::open_in_memory().unwrap(),
+        })
+        .unwrap();
+
+        let caller = handle.clone();
+        let panicked = tokio::spawn(async move { caller.call(|_| -> () { panic!("boom") }).await }).await;
+        assert!(panicked.is_err());
+
+        let migrated = handle.call(|db| db.migrate().and_then(|_| db.schema_version())).await;
+        assert_eq!(migrated.unwrap(), crate::storage::SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_publishes_changes() {
+        let handle = DatabaseHandle::spawn(Database {
+            conn: Connection::open_in_memory().unwrap(),
+        })
+        .unwrap();
+        handle.call(|db| db.migrate()).await.unwrap();
+        let mut changes = handle.subscribe();
+
+        let saved = handle
+            .call(|db| {
+                let text = serde_json::json!({ "text": "hi" });
+                db.save_received_message("m1", "t1", &"ab".repeat(32), None, "text/plain", &text, 1_000, true, None)?;
+                // A failed batch publishes nothing
+                let failed = db.in_transaction(|db| {
+                    db.save_received_message("m2", "t2", &"cd".repeat(32), None, "text/plain", &text, 2_000, true, None)?;
+                    Err::<(), _>(DatabaseError::SqliteError("rolled back".to_string()))
+                });
+                assert!(failed.is_err());
+                db.mark_thread_read("t1")
+            })
+            .await;
+        saved.unwrap();
+
+        let thread = StorageChange::ThreadUpdated { thread_id: "t1".to_string() };
+        let message = StorageChange::MessageInserted {
+            thread_id: "t1".to_string(),
+            message_id: "m1".to_string(),
+        };
+        assert_eq!(changes.recv().await.unwrap(), thread);
+        assert_eq!(changes.recv().await.unwrap(), message);
+        assert!(changes.try_recv().is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Invoices
+//!
+//! Payment requests we sent or received and whether they've been paid.
+//! Expiry isn't stored; a pending invoice past `expires_at` reads as
+//! expired.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::invoices::{Invoice, InvoiceRecord, InvoiceStatus};
+
+impl Database {
+    pub(super) fn initialize_invoice_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS invoices (
+                id TEXT PRIMARY KEY,
+                invoice_json TEXT NOT NULL,
+                issuer TEXT NOT NULL,
+                thread_id TEXT,
+                outgoing INTEGER NOT NULL,
+                tx_hash TEXT,
+                paid_at INTEGER,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_invoices_thread ON invoices(thread_id);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Store an invoice; a second copy of the same invoice is ignored
+    pub fn save_invoice(&mut self, invoice: &Invoice, thread_id: Option<&str>, outgoing: bool) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT OR IGNORE INTO invoices (id, invoice_json, issuer, thread_id, outgoing, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+                params![
+                    invoice.id,
+                    serde_json::to_string(invoice).unwrap_or_default(),
+                    invoice.issuer.to_lowercase(),
+                    thread_id,
+                    outgoing,
+                    chrono::Utc::now().timestamp_millis(),
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_invoice(&self, invoice_id: &str) -> Option<InvoiceRecord> {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.conn
+            .query_row(
+                "SELECT invoice_json, thread_id, outgoing, tx_hash, paid_at FROM invoices WHERE id = ?",
+                params![invoice_id],
+                |row| invoice_from_row(row, now),
+            )
+            .optional()
+            .ok()
+            .flatten()
+            .flatten()
+    }
+
+    /// Invoices in a thread, or all of them, newest first
+    pub fn list_invoices(&self, thread_id: Option<&str>) -> Result<Vec<InvoiceRecord>, DatabaseError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT invoice_json, thread_id, outgoing, tx_hash, paid_at FROM invoices
+                WHERE ?1 IS NULL OR thread_id = ?1
+                ORDER BY created_at DESC
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let invoices = stmt
+            .query_map(params![thread_id], |row| invoice_from_row(row, now))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok().flatten())
+            .collect();
+        Ok(invoices)
+    }
+
+    /// Mark an unpaid invoice paid by `tx_hash`; false if it's unknown or
+    /// already paid
+    pub fn mark_invoice_paid(&mut self, invoice_id: &str, tx_hash: &str, paid_at: i64) -> Result<bool, DatabaseError> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE invoices SET tx_hash = ?, paid_at = ? WHERE id = ? AND tx_hash IS NULL",
+                params![tx_hash, paid_at, invoice_id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(updated > 0)
+    }
+}
+
+fn invoice_from_row(row: &rusqlite::Row, now: i64) -> rusqlite::Result<Option<InvoiceRecord>> {
+    let invoice_json: String = row.get(0)?;
+    let tx_hash: Option<String> = row.get(3)?;
+    let Ok(invoice) = serde_json::from_str::<Invoice>(&invoice_json) else {
+        return Ok(None);
+    };
+    let status = if tx_hash.is_some() {
+        InvoiceStatus::Paid
+    } else if invoice.expires_at < now {
+        InvoiceStatus::Expired
+    } else {
+        InvoiceStatus::Pending
+    };
+    Ok(Some(InvoiceRecord {
+        invoice,
+        thread_id: row.get(1)?,
+        outgoing: row.get(2)?,
+        status,
+        tx_hash,
+        paid_at: row.get(4)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn invoice(id: &str, expires_at: i64) -> Invoice {
+        Invoice {
+            id: id.to_string(),
+            issuer: "AB".repeat(32),
+            amount: 5.0,
+            asset: "GNS".to_string(),
+            memo: None,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_invoice_status() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let later = chrono::Utc::now().timestamp_millis() + 60_000;
+
+        db.save_invoice(&invoice("open", later), Some("t1"), true).unwrap();
+        db.save_invoice(&invoice("old", 1_000), Some("t1"), false).unwrap();
+        db.save_invoice(&invoice("other", later), Some("t2"), false).unwrap();
+        assert_eq!(db.get_invoice("open").unwrap().status, InvoiceStatus::Pending);
+        assert_eq!(db.get_invoice("old").unwrap().status, InvoiceStatus::Expired);
+        assert_eq!(db.list_invoices(Some("t1")).unwrap().len(), 2);
+        assert_eq!(db.list_invoices(None).unwrap().len(), 3);
+
+        assert!(db.mark_invoice_paid("open", "tx1", 2_000).unwrap());
+        // Paid once only
+        assert!(!db.mark_invoice_paid("open", "tx2", 3_000).unwrap());
+        assert!(!db.mark_invoice_paid("missing", "tx1", 2_000).unwrap());
+        let paid = db.get_invoice("open").unwrap();
+        assert_eq!(paid.status, InvoiceStatus::Paid);
+        assert_eq!(paid.tx_hash.as_deref(), Some("tx1"));
+        assert!(paid.outgoing);
+    }
+}
@@ -0,0 +1,212 @@
+//! Labels
+//!
+//! User-defined labels and which threads carry them.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{thread_from_row, Database, DatabaseError};
+use crate::commands::labels::Label;
+use crate::commands::messaging::ThreadPreview;
+
+impl Database {
+    pub(super) fn initialize_label_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS labels (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                color TEXT,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS thread_labels (
+                thread_id TEXT NOT NULL,
+                label_id TEXT NOT NULL,
+                PRIMARY KEY (thread_id, label_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_thread_labels_label ON thread_labels(label_id);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn create_label(&mut self, label: &Label) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "INSERT INTO labels (id, name, color, created_at) VALUES (?, ?, ?, ?)",
+                params![label.id, label.name, label.color, label.created_at],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_label(&self, label_id: &str) -> Option<Label> {
+        self.conn
+            .query_row(
+                r#"
+                SELECT l.id, l.name, l.color, l.created_at,
+                       (SELECT COUNT(*) FROM thread_labels tl WHERE tl.label_id = l.id)
+                FROM labels l WHERE l.id = ?
+                "#,
+                params![label_id],
+                label_from_row,
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    pub fn label_name_taken(&self, name: &str) -> bool {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM labels WHERE name = ?)",
+                params![name],
+                |row| row.get(0),
+            )
+            .unwrap_or(false)
+    }
+
+    /// All labels by name, with how many threads carry each
+    pub fn list_labels(&self) -> Result<Vec<Label>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r#"
+                SELECT l.id, l.name, l.color, l.created_at,
+                       (SELECT COUNT(*) FROM thread_labels tl WHERE tl.label_id = l.id)
+                FROM labels l ORDER BY l.name COLLATE NOCASE
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let labels = stmt
+            .query_map([], label_from_row)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(labels)
+    }
+
+    /// Delete a label; threads keep their messages and other labels
+    pub fn delete_label(&mut self, label_id: &str) -> Result<bool, DatabaseError> {
+        self.in_transaction(|db| {
+            db.conn
+                .execute("DELETE FROM thread_labels WHERE label_id = ?", params![label_id])
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            let removed = db
+                .conn
+                .execute("DELETE FROM labels WHERE id = ?", params![label_id])
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            Ok(removed > 0)
+        })
+    }
+
+    /// Put a label on a thread, or take it off
+    pub fn set_thread_label(&mut self, thread_id: &str, label_id: &str, assigned: bool) -> Result<(), DatabaseError> {
+        let sql = if assigned {
+            "INSERT OR IGNORE INTO thread_labels (thread_id, label_id) VALUES (?, ?)"
+        } else {
+            "DELETE FROM thread_labels WHERE thread_id = ? AND label_id = ?"
+        };
+        self.conn
+            .execute(sql, params![thread_id, label_id])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Ids of the labels on a thread
+    pub fn get_thread_labels(&self, thread_id: &str) -> Vec<String> {
+        self.conn
+            .prepare("SELECT label_id FROM thread_labels WHERE thread_id = ?")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![thread_id], |row| row.get(0))?
+                    .collect::<Result<Vec<String>, _>>()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Threads carrying a label, by last activity
+    pub fn get_threads_by_label(
+        &self,
+        label_id: &str,
+        include_archived: bool,
+        limit: u32,
+    ) -> Result<Vec<ThreadPreview>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+            SELECT t.*,
+                   (SELECT payload_json FROM messages m WHERE m.thread_id = t.id ORDER BY timestamp DESC LIMIT 1) as last_payload
+            FROM threads t
+            JOIN thread_labels tl ON tl.thread_id = t.id
+            WHERE tl.label_id = ?1 AND (?2 OR is_archived = 0)
+            ORDER BY last_message_at DESC LIMIT ?3
+            "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let threads = stmt
+            .query_map(params![label_id, include_archived, limit], thread_from_row)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        threads
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+}
+
+fn label_from_row(row: &rusqlite::Row) -> rusqlite::Result<Label> {
+    Ok(Label {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        created_at: row.get(3)?,
+        thread_count: row.get(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn label(id: &str, name: &str) -> Label {
+        Label {
+            id: id.to_string(),
+            name: name.to_string(),
+            color: None,
+            created_at: 0,
+            thread_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_threads_by_label() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let (work, home) = ("t-work".to_string(), "t-home".to_string());
+        db.get_or_create_thread(&work, "aa", Some("alice"), None).unwrap();
+        db.get_or_create_thread(&home, "bb", Some("bob"), None).unwrap();
+
+        db.create_label(&label("l1", "Work")).unwrap();
+        assert!(db.label_name_taken("work"));
+        db.set_thread_label(&work, "l1", true).unwrap();
+        db.set_thread_label(&work, "l1", true).unwrap();
+
+        let threads = db.get_threads_by_label("l1", false, 50).unwrap();
+        assert_eq!(threads.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec![work.as_str()]);
+        assert_eq!(db.get_label("l1").unwrap().thread_count, 1);
+        assert!(db.get_threads_by_label("l1", false, 50).unwrap().iter().all(|t| t.id != home));
+
+        db.delete_thread(&work).unwrap();
+        assert_eq!(db.get_label("l1").unwrap().thread_count, 0);
+        assert!(db.delete_label("l1").unwrap());
+        assert!(db.list_labels().unwrap().is_empty());
+    }
+}
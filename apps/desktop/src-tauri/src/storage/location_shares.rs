@@ -0,0 +1,149 @@
+//! Location Shares
+//!
+//! The latest cell of each location share we sent or received.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::location::share::SharedLocation;
+
+impl Database {
+    pub(super) fn initialize_location_share_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS location_shares (
+                share_id TEXT PRIMARY KEY,
+                share_json TEXT NOT NULL,
+                peer_public_key TEXT NOT NULL,
+                outgoing INTEGER NOT NULL,
+                thread_id TEXT,
+                live_until INTEGER,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_location_shares_live ON location_shares(live_until);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Store a share, or move it to a newer cell; an update only applies
+    /// to the same peer and direction. Returns whether anything changed.
+    pub fn save_location_share(&mut self, shared: &SharedLocation) -> Result<bool, DatabaseError> {
+        let share = &shared.share;
+        let changed = self
+            .conn
+            .execute(
+                r#"
+                INSERT INTO location_shares
+                    (share_id, share_json, peer_public_key, outgoing, thread_id, live_until, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(share_id) DO UPDATE SET
+                    share_json = excluded.share_json,
+                    live_until = excluded.live_until,
+                    updated_at = excluded.updated_at
+                WHERE excluded.updated_at > location_shares.updated_at
+                    AND excluded.peer_public_key = location_shares.peer_public_key
+                    AND excluded.outgoing = location_shares.outgoing
+                "#,
+                params![
+                    share.share_id,
+                    serde_json::to_string(share).unwrap_or_default(),
+                    shared.peer_public_key.to_lowercase(),
+                    shared.outgoing,
+                    shared.thread_id,
+                    share.live_until,
+                    share.updated_at,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(changed > 0)
+    }
+
+    pub fn get_location_share(&self, share_id: &str) -> Option<SharedLocation> {
+        self.conn
+            .query_row(
+                "SELECT share_json, thread_id, peer_public_key, outgoing FROM location_shares WHERE share_id = ?",
+                params![share_id],
+                shared_location_from_row,
+            )
+            .optional()
+            .ok()
+            .flatten()
+            .flatten()
+    }
+
+    /// Shares still live at `now`, newest first; only ours when `outgoing`
+    /// is set, only others' when it's cleared
+    pub fn live_location_shares(&self, now: i64, outgoing: Option<bool>) -> Result<Vec<SharedLocation>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT share_json, thread_id, peer_public_key, outgoing FROM location_shares
+                WHERE live_until > ?1 AND (?2 IS NULL OR outgoing = ?2)
+                ORDER BY updated_at DESC
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let shares = stmt
+            .query_map(params![now, outgoing], shared_location_from_row)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok().flatten())
+            .collect();
+        Ok(shares)
+    }
+}
+
+/// Map `share_json, thread_id, peer_public_key, outgoing`; None if the
+/// share doesn't parse
+fn shared_location_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<SharedLocation>> {
+    let share_json: String = row.get(0)?;
+    let (thread_id, peer_public_key, outgoing) = (row.get(1)?, row.get(2)?, row.get(3)?);
+    Ok(serde_json::from_str(&share_json)
+        .ok()
+        .map(|share| SharedLocation::new(share, thread_id, peer_public_key, outgoing)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::share::{quantize, LocationShare};
+    use rusqlite::Connection;
+
+    fn shared(cell: &str, updated_at: i64, peer: &str) -> SharedLocation {
+        let share = LocationShare {
+            share_id: "s1".to_string(),
+            h3_cell: cell.to_string(),
+            resolution: 7,
+            live_until: Some(10_000),
+            updated_at,
+            label: None,
+        };
+        SharedLocation::new(share, Some("t1".to_string()), peer.to_string(), false)
+    }
+
+    #[test]
+    fn test_latest_cell_per_share() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let (berlin, paris) = (quantize(52.52, 13.405, 7).unwrap(), quantize(48.8566, 2.3522, 7).unwrap());
+        let peer = "ab".repeat(32);
+
+        assert!(db.save_location_share(&shared(&berlin, 1_000, &peer)).unwrap());
+        assert!(db.save_location_share(&shared(&paris, 3_000, &peer)).unwrap());
+        // Late or foreign updates don't move the share
+        assert!(!db.save_location_share(&shared(&berlin, 2_000, &peer)).unwrap());
+        assert!(!db.save_location_share(&shared(&berlin, 4_000, &"cd".repeat(32))).unwrap());
+        assert_eq!(db.get_location_share("s1").unwrap().share.h3_cell, paris);
+
+        assert_eq!(db.live_location_shares(5_000, Some(false)).unwrap().len(), 1);
+        assert!(db.live_location_shares(5_000, Some(true)).unwrap().is_empty());
+        assert!(db.live_location_shares(10_000, None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+//! Schema migrations
+//!
+//! `initialize_tables` creates the baseline schema; every later change is a
+//! numbered migration here. Each runs in its own transaction and is recorded
+//! in `schema_version`, so an interrupted upgrade resumes where it stopped.
+//! To change the schema, append a migration — never edit a shipped one.
+
+use rusqlite::{params, Connection};
+
+use super::{Database, DatabaseError};
+
+/// A single schema change
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+/// Ordered by version, starting at 1
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Message reply, star and forward columns, thread subjects, breadcrumb sync tracking",
+        up: |conn| {
+            add_column_if_missing(conn, "messages", "reply_to_id", "TEXT")?;
+            add_column_if_missing(conn, "messages", "is_starred", "INTEGER DEFAULT 0")?;
+            add_column_if_missing(conn, "messages", "forwarded_from_id", "TEXT")?;
+            add_column_if_missing(conn, "threads", "subject", "TEXT")?;
+            add_column_if_missing(conn, "breadcrumbs", "synced_at", "INTEGER")
+        },
+    },
+    Migration {
+        version: 2,
+        description: "Junk flag on threads",
+        up: |conn| add_column_if_missing(conn, "threads", "is_junk", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        version: 3,
+        description: "Index on starred messages",
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_messages_starred ON messages(timestamp DESC) WHERE is_starred = 1",
+            )
+        },
+    },
+    Migration {
+        version: 4,
+        description: "Quote integrity status on replies",
+        up: |conn| add_column_if_missing(conn, "messages", "quote_status", "TEXT"),
+    },
+    Migration {
+        version: 5,
+        description: "Signer and resolution on breadcrumbs, so rows hold the complete signed record",
+        up: |conn| {
+            // Older rows keep a NULL signer until adopted by the identity that signed them
+            add_column_if_missing(conn, "breadcrumbs", "public_key", "TEXT")?;
+            add_column_if_missing(conn, "breadcrumbs", "resolution", "INTEGER NOT NULL DEFAULT 7")?;
+            add_column_if_missing(conn, "breadcrumb_quarantine", "public_key", "TEXT")?;
+            add_column_if_missing(conn, "breadcrumb_quarantine", "resolution", "INTEGER NOT NULL DEFAULT 7")
+        },
+    },
+    Migration {
+        version: 6,
+        description: "Rewarded epochs keyed by Merkle root, since positions shift when breadcrumbs are merged or pruned",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE gns_rewarded_epochs_by_root (
+                    public_key TEXT NOT NULL,
+                    epoch INTEGER NOT NULL,
+                    root TEXT NOT NULL,
+                    breadcrumb_count INTEGER NOT NULL,
+                    claim_id INTEGER NOT NULL,
+                    PRIMARY KEY (public_key, root)
+                );
+                INSERT OR IGNORE INTO gns_rewarded_epochs_by_root
+                    SELECT public_key, epoch, root, breadcrumb_count, claim_id FROM gns_rewarded_epochs;
+                DROP TABLE gns_rewarded_epochs;
+                ALTER TABLE gns_rewarded_epochs_by_root RENAME TO gns_rewarded_epochs;
+                "#,
+            )
+        },
+    },
+    Migration {
+        version: 7,
+        description: "Timestamp of the last remote wipe acted on, kept through wipes so it can't be replayed",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS remote_wipe_mark (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    wiped_through INTEGER NOT NULL
+                );
+                "#,
+            )
+        },
+    },
+];
+
+/// Schema version this build writes
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Add a column unless an earlier, unversioned build already did
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?", table),
+        params![column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Bring the schema up to `SCHEMA_VERSION`
+    ///
+    /// Refuses to open a database written by a newer build rather than
+    /// risk corrupting columns this build doesn't know about.
+    pub(super) fn migrate(&mut self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let current = self.schema_version()?;
+        if current > SCHEMA_VERSION {
+            return Err(DatabaseError::SchemaTooNew {
+                found: current,
+                supported: SCHEMA_VERSION,
+            });
+        }
+
+        self.initialize_tables()?;
+
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+            tracing::info!("Applying schema migration {}: {}", migration.version, migration.description);
+
+            let tx = self
+                .conn
+                .transaction()
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            (migration.up)(&tx).map_err(|e| {
+                DatabaseError::SqliteError(format!("Migration {} failed: {}", migration.version, e))
+            })?;
+            tx.execute(
+                "INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)",
+                params![migration.version, migration.description, chrono::Utc::now().timestamp_millis()],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Highest migration applied, 0 for a database that predates versioning
+    pub fn schema_version(&self) -> Result<u32, DatabaseError> {
+        self.conn
+            .query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |row| row.get(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as u32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+
+        // Re-running is a no-op
+        db.migrate().unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+
+        db.conn
+            .execute(
+                "INSERT INTO schema_version (version, description, applied_at) VALUES (?, 'future', 0)",
+                params![SCHEMA_VERSION + 1],
+            )
+            .unwrap();
+        assert!(matches!(db.migrate(), Err(DatabaseError::SchemaTooNew { .. })));
+    }
+}
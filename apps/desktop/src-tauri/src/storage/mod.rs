@@ -2,21 +2,158 @@
 //!
 //! SQLite database for storing messages, threads, and breadcrumbs.
 
+mod admin;
+mod breadcrumb_audit;
+mod calendar;
+mod changes;
+mod channels;
+mod claims;
+mod contact_keys;
+mod contacts;
+mod contact_requests;
+mod dix;
+mod dix_notifications;
+mod earnings;
+mod handle;
+mod invoices;
+mod labels;
+mod location_shares;
+mod migrations;
+mod outbox;
+mod polls;
+mod prices;
+mod proximity;
+mod reports;
+mod resolution_cache;
+mod retention;
+mod rules;
+mod spam;
+mod transfer;
+
 use gns_crypto_core::{Breadcrumb, GnsEnvelope};
 use rusqlite::{params, Connection};
 use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::app_lock::LockConfig;
+use crate::commands::commands_handle::Profile;
+use crate::duress::Persona;
 use crate::commands::messaging::{Message, ThreadPreview, Reaction};
+use crate::quotes::QuoteStatus;
+use crate::location::PrivacyZone;
+use crate::stellar::HardwareSigningConfig;
+use crate::verifications::Verification;
+
+pub use admin::{is_idle, start_maintenance_scheduler, DbStats, MaintenanceReport, TableStats};
+pub use changes::{start_change_events, StorageChange};
+pub use handle::DatabaseHandle;
+pub use migrations::SCHEMA_VERSION;
+pub use retention::{start_retention_pruner, PruneReport, RetentionPolicy};
+pub use transfer::TransferRow;
+
+/// How long a write waits on another connection's lock before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Prepared statements kept per connection
+const STATEMENT_CACHE_CAPACITY: usize = 64;
 
 /// Local database
 pub struct Database {
     conn: Connection,
 }
 
+/// A decrypted message mirrored from another of our devices
+#[derive(Debug, Clone)]
+pub struct SyncedMessage {
+    pub id: String,
+    /// The other participant
+    pub conversation_with: String,
+    pub text: String,
+    pub timestamp: i64,
+    pub from_handle: Option<String>,
+    pub is_outgoing: bool,
+}
+
+/// Thread for a one-to-one conversation, the same on both sides
+///
+/// Both keys go in whole; a prefix of the pair would give everyone whose
+/// key sorts after ours the same thread.
+pub fn direct_thread_id(a: &str, b: &str) -> String {
+    let mut keys = [a.to_ascii_lowercase(), b.to_ascii_lowercase()];
+    keys.sort();
+    format!("direct_{}_{}", keys[0], keys[1])
+}
+
+/// Map a `SELECT t.*, ... as last_payload FROM threads t` row
+fn thread_from_row(row: &rusqlite::Row) -> rusqlite::Result<ThreadPreview> {
+    let last_payload: Option<String> = row.get("last_payload").ok();
+    let preview = last_payload.and_then(|p| {
+        serde_json::from_str::<serde_json::Value>(&p)
+            .ok()
+            .and_then(|v| v["text"].as_str().map(|s| s.to_string()))
+    });
+
+    Ok(ThreadPreview {
+        id: row.get(0)?,
+        participant_public_key: row.get(1)?,
+        participant_handle: row.get(2)?,
+        last_message_preview: preview,
+        last_message_at: row.get(3)?,
+        unread_count: row.get(4)?,
+        is_pinned: row.get::<_, i32>(5)? == 1,
+        is_muted: row.get::<_, i32>(6)? == 1,
+        subject: row.get("subject").ok(),
+        is_junk: row.get::<_, i32>("is_junk")? == 1,
+    })
+}
+
+/// Columns read by `message_from_row`
+const MESSAGE_COLUMNS: &str = "id, thread_id, from_public_key, from_handle, payload_type, payload_json, timestamp, is_outgoing, status, reply_to_id, is_starred, forwarded_from_id, quote_status";
+
+/// Map a `SELECT {MESSAGE_COLUMNS} FROM messages` row; reactions are left empty
+fn message_from_row(row: &rusqlite::Row) -> rusqlite::Result<Message> {
+    let payload_str: String = row.get(5)?;
+    let payload_json: serde_json::Value = serde_json::from_str(&payload_str).unwrap_or_default();
+
+    Ok(Message {
+        id: row.get(0)?,
+        thread_id: row.get(1)?,
+        from_public_key: row.get(2)?,
+        from_handle: row.get(3)?,
+        payload_type: row.get(4)?,
+        payload: payload_json,
+        timestamp: row.get(6)?,
+        is_outgoing: row.get(7)?,
+        status: row.get(8)?,
+        reply_to_id: row.get(9)?,
+        is_starred: row.get(10).unwrap_or(false),
+        forwarded_from_id: row.get(11)?,
+        quote_status: row
+            .get::<_, Option<String>>(12)?
+            .and_then(|s| QuoteStatus::parse(&s)),
+        reactions: Vec::new(),
+    })
+}
+
 impl Database {
     /// Open or create the database
     pub fn open() -> Result<Self, DatabaseError> {
-        let path = Self::database_path()?;
+        Self::open_persona(Persona::Primary)
+    }
+
+    /// A fresh, migrated database that lives only in memory
+    #[cfg(all(test, feature = "e2e"))]
+    pub(crate) fn open_in_memory() -> Result<Self, DatabaseError> {
+        let mut db = Self {
+            conn: Connection::open_in_memory().map_err(|e| DatabaseError::SqliteError(e.to_string()))?,
+        };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// Open the database belonging to `persona`
+    pub fn open_persona(persona: Persona) -> Result<Self, DatabaseError> {
+        let path = Self::database_path(persona)?;
 
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
@@ -25,22 +162,66 @@ impl Database {
 
         let conn =
             Connection::open(&path).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Self::configure(&conn).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
-        let db = Self { conn };
-        db.initialize_tables()?;
+        let mut db = Self { conn };
+        db.migrate()?;
 
         Ok(db)
     }
 
+    /// WAL lets readers proceed during a write and turns each commit into an
+    /// append, which keeps large history syncs from stalling the UI
+    fn configure(conn: &Connection) -> rusqlite::Result<()> {
+        let mode: String = conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+        if !mode.eq_ignore_ascii_case("wal") {
+            tracing::warn!("SQLite refused WAL journaling, using {}", mode);
+        }
+        // Safe with WAL: a crash can lose the last commits but not corrupt
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        Ok(())
+    }
+
+    /// Run `f` in a single transaction, rolling back if it fails
+    ///
+    /// Batching writes this way costs one commit instead of one per row.
+    /// Uses a savepoint, so calls may nest.
+    pub fn in_transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, DatabaseError>,
+    ) -> Result<T, DatabaseError> {
+        self.conn
+            .execute_batch("SAVEPOINT batch")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let recorded = changes::mark();
+        match f(self) {
+            Ok(value) => {
+                self.conn
+                    .execute_batch("RELEASE batch")
+                    .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK TO batch; RELEASE batch");
+                changes::rollback(recorded);
+                Err(e)
+            }
+        }
+    }
+
     /// Get the database file path
-    fn database_path() -> Result<PathBuf, DatabaseError> {
+    fn database_path(persona: Persona) -> Result<PathBuf, DatabaseError> {
         let data_dir = dirs::data_dir()
             .ok_or_else(|| DatabaseError::IoError("Could not find data directory".to_string()))?;
 
-        Ok(data_dir.join("gns-browser").join("gns.db"))
+        Ok(data_dir
+            .join("gns-browser")
+            .join(format!("gns{}.db", persona.storage_suffix())))
     }
 
-    /// Initialize database tables
+    /// Create the baseline schema; later changes go in `migrations`
     fn initialize_tables(&self) -> Result<(), DatabaseError> {
         self.conn
             .execute_batch(
@@ -111,12 +292,26 @@ impl Database {
             )
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
-        // Migrations
-        let _ = self.conn.execute("ALTER TABLE messages ADD COLUMN reply_to_id TEXT", []);
-        let _ = self.conn.execute("ALTER TABLE messages ADD COLUMN is_starred INTEGER DEFAULT 0", []);
-        let _ = self.conn.execute("ALTER TABLE messages ADD COLUMN forwarded_from_id TEXT", []);
-        // Migration for subject column
-        let _ = self.conn.execute("ALTER TABLE threads ADD COLUMN subject TEXT", []);
+        self.initialize_dix_tables()?;
+        self.initialize_report_tables()?;
+        self.initialize_claim_tables()?;
+        self.initialize_contact_key_tables()?;
+        self.initialize_spam_tables()?;
+        self.initialize_contact_request_tables()?;
+        self.initialize_resolution_cache_tables()?;
+        self.initialize_contact_tables()?;
+        self.initialize_label_tables()?;
+        self.initialize_channel_tables()?;
+        self.initialize_poll_tables()?;
+        self.initialize_calendar_tables()?;
+        self.initialize_location_share_tables()?;
+        self.initialize_proximity_tables()?;
+        self.initialize_breadcrumb_audit_tables()?;
+        self.initialize_earnings_tables()?;
+        self.initialize_price_tables()?;
+        self.initialize_invoice_tables()?;
+        self.initialize_dix_notification_tables()?;
+        self.initialize_rule_tables()?;
 
         Ok(())
     }
@@ -132,7 +327,7 @@ impl Database {
         subject: Option<&str>,
     ) -> Result<(), DatabaseError> {
         self.conn
-            .execute(
+            .prepare_cached(
                 r#"
                 INSERT INTO threads (id, participant_public_key, participant_handle, last_message_at, unread_count, subject)
                 VALUES (?, ?, ?, ?, 0, ?)
@@ -140,15 +335,18 @@ impl Database {
                     participant_handle = COALESCE(excluded.participant_handle, threads.participant_handle),
                     subject = COALESCE(threads.subject, excluded.subject) 
                 "#,
-                params![
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
                     thread_id,
                     participant_public_key,
                     participant_handle,
                     chrono::Utc::now().timestamp_millis(),
                     subject
-                ],
-            )
+                ])
+            })
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        changes::record(StorageChange::ThreadUpdated { thread_id: thread_id.to_string() });
         Ok(())
     }
 
@@ -159,74 +357,44 @@ impl Database {
         timestamp: i64,
         is_incoming: bool,
     ) -> Result<(), DatabaseError> {
-        if is_incoming {
+        let sql = if is_incoming {
             // Increment unread count for incoming messages
-            self.conn
-                .execute(
-                    "UPDATE threads SET last_message_at = ?, unread_count = unread_count + 1 WHERE id = ?",
-                    params![timestamp, thread_id],
-                )
-                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            "UPDATE threads SET last_message_at = ?, unread_count = unread_count + 1 WHERE id = ?"
         } else {
-            self.conn
-                .execute(
-                    "UPDATE threads SET last_message_at = ? WHERE id = ?",
-                    params![timestamp, thread_id],
-                )
-                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
-        }
+            "UPDATE threads SET last_message_at = ? WHERE id = ?"
+        };
+        self.conn
+            .prepare_cached(sql)
+            .and_then(|mut stmt| stmt.execute(params![timestamp, thread_id]))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        changes::record(StorageChange::ThreadUpdated { thread_id: thread_id.to_string() });
         Ok(())
     }
 
     /// Get all threads
+    /// Threads by last activity; `junk` picks the inbox (false), the
+    /// requests / junk folder (true) or both (None)
     pub fn get_threads(
         &self,
         include_archived: bool,
+        junk: Option<bool>,
         limit: u32,
     ) -> Result<Vec<ThreadPreview>, DatabaseError> {
-        let sql = if include_archived {
-            r#"
-            SELECT t.*, 
-                   (SELECT payload_json FROM messages m WHERE m.thread_id = t.id ORDER BY timestamp DESC LIMIT 1) as last_payload
-            FROM threads t 
-            ORDER BY last_message_at DESC LIMIT ?
-            "#
-        } else {
-            r#"
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
             SELECT t.*, 
                    (SELECT payload_json FROM messages m WHERE m.thread_id = t.id ORDER BY timestamp DESC LIMIT 1) as last_payload
             FROM threads t 
-            WHERE is_archived = 0 
-            ORDER BY last_message_at DESC LIMIT ?
-            "#
-        };
-
-        let mut stmt = self
-            .conn
-            .prepare(sql)
+            WHERE (?1 OR is_archived = 0) AND (?2 IS NULL OR is_junk = ?2)
+            ORDER BY last_message_at DESC LIMIT ?3
+            "#,
+            )
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         let threads = stmt
-            .query_map([limit], |row| {
-                let last_payload: Option<String> = row.get(9).ok();
-                let preview = last_payload.and_then(|p| {
-                    serde_json::from_str::<serde_json::Value>(&p)
-                        .ok()
-                        .and_then(|v| v["text"].as_str().map(|s| s.to_string()))
-                });
-
-                Ok(ThreadPreview {
-                    id: row.get(0)?,
-                    participant_public_key: row.get(1)?,
-                    participant_handle: row.get(2)?,
-                    last_message_preview: preview,
-                    last_message_at: row.get(3)?,
-                    unread_count: row.get(4)?,
-                    is_pinned: row.get::<_, i32>(5)? == 1,
-                    is_muted: row.get::<_, i32>(6)? == 1,
-                    subject: row.get(8).ok(),
-                })
-            })
+            .query_map(params![include_archived, junk, limit], thread_from_row)
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         threads
@@ -249,26 +417,7 @@ impl Database {
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         let mut rows = stmt
-            .query_map([thread_id], |row| {
-                let last_payload: Option<String> = row.get(9).ok();
-                let preview = last_payload.and_then(|p| {
-                    serde_json::from_str::<serde_json::Value>(&p)
-                        .ok()
-                        .and_then(|v| v["text"].as_str().map(|s| s.to_string()))
-                });
-
-                Ok(ThreadPreview {
-                    id: row.get(0)?,
-                    participant_public_key: row.get(1)?,
-                    participant_handle: row.get(2)?,
-                    last_message_preview: preview,
-                    last_message_at: row.get(3)?,
-                    unread_count: row.get(4)?,
-                    is_pinned: row.get::<_, i32>(5)? == 1,
-                    is_muted: row.get::<_, i32>(6)? == 1,
-                    subject: row.get(8).ok(),
-                })
-            })
+            .query_map([thread_id], thread_from_row)
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         if let Some(row) = rows.next() {
@@ -286,6 +435,7 @@ impl Database {
                 params![thread_id],
             )
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        changes::record(StorageChange::ThreadUpdated { thread_id: thread_id.to_string() });
         Ok(())
     }
 
@@ -297,9 +447,13 @@ impl Database {
                 params![thread_id],
             )
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.conn
+            .execute("DELETE FROM thread_labels WHERE thread_id = ?", params![thread_id])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
         self.conn
             .execute("DELETE FROM threads WHERE id = ?", params![thread_id])
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        changes::record(StorageChange::ThreadUpdated { thread_id: thread_id.to_string() });
         Ok(())
     }
 
@@ -324,33 +478,14 @@ impl Database {
     ) -> Result<Vec<Message>, DatabaseError> {
         let mut stmt = self
             .conn
-            .prepare(
-                "SELECT id, thread_id, from_public_key, from_handle, payload_type, payload_json, timestamp, is_outgoing, status, reply_to_id, is_starred, forwarded_from_id FROM messages WHERE thread_id = ? ORDER BY timestamp DESC LIMIT ?",
-            )
+            .prepare_cached(&format!(
+                "SELECT {} FROM messages WHERE thread_id = ? ORDER BY timestamp DESC LIMIT ?",
+                MESSAGE_COLUMNS
+            ))
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         let mut messages = stmt
-            .query_map(params![thread_id, limit], |row| {
-                let payload_str: String = row.get(5)?;
-                let payload_json: serde_json::Value =
-                    serde_json::from_str(&payload_str).unwrap_or_default();
-                
-                Ok(Message {
-                    id: row.get(0)?,
-                    thread_id: row.get(1)?,
-                    from_public_key: row.get(2)?,
-                    from_handle: row.get(3)?,
-                    payload_type: row.get(4)?,
-                    payload: payload_json,
-                    timestamp: row.get(6)?,
-                    is_outgoing: row.get(7)?,
-                    status: row.get(8)?,
-                    reply_to_id: row.get(9)?,
-                    is_starred: row.get(10).unwrap_or(false),
-                    forwarded_from_id: row.get(11)?,
-                    reactions: Vec::new(),
-                })
-            })
+            .query_map(params![thread_id, limit], message_from_row)
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
@@ -359,7 +494,7 @@ impl Database {
         for message in &mut messages {
             let mut r_stmt = self
                 .conn
-                .prepare("SELECT emoji, from_public_key FROM reactions WHERE message_id = ?")
+                .prepare_cached("SELECT emoji, from_public_key FROM reactions WHERE message_id = ?")
                 .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
             let reactions = r_stmt
@@ -383,33 +518,11 @@ impl Database {
     pub fn get_message(&self, message_id: &str) -> Result<Option<Message>, DatabaseError> {
         let mut stmt = self
             .conn
-            .prepare(
-                "SELECT id, thread_id, from_public_key, from_handle, payload_type, payload_json, timestamp, is_outgoing, status, reply_to_id, is_starred, forwarded_from_id FROM messages WHERE id = ?",
-            )
+            .prepare_cached(&format!("SELECT {} FROM messages WHERE id = ?", MESSAGE_COLUMNS))
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         let mut rows = stmt
-            .query_map(params![message_id], |row| {
-                let payload_str: String = row.get(5)?;
-                let payload_json: serde_json::Value =
-                    serde_json::from_str(&payload_str).unwrap_or_default();
-                
-                Ok(Message {
-                    id: row.get(0)?,
-                    thread_id: row.get(1)?,
-                    from_public_key: row.get(2)?,
-                    from_handle: row.get(3)?,
-                    payload_type: row.get(4)?,
-                    payload: payload_json,
-                    timestamp: row.get(6)?,
-                    is_outgoing: row.get(7)?,
-                    status: row.get(8)?,
-                    reply_to_id: row.get(9)?,
-                    is_starred: row.get(10).unwrap_or(false),
-                    forwarded_from_id: row.get(11)?,
-                    reactions: Vec::new(),
-                })
-            })
+            .query_map(params![message_id], message_from_row)
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         if let Some(row) = rows.next() {
@@ -418,6 +531,60 @@ impl Database {
             Ok(None)
         }
     }
+
+    /// Star or unstar a message; false if there is no such message
+    pub fn set_message_starred(&mut self, message_id: &str, starred: bool) -> Result<bool, DatabaseError> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE messages SET is_starred = ? WHERE id = ?",
+                params![starred, message_id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(updated > 0)
+    }
+
+    /// Record how a reply's quote checked out
+    pub fn set_quote_status(&mut self, message_id: &str, status: QuoteStatus) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "UPDATE messages SET quote_status = ? WHERE id = ?",
+                params![status.as_str(), message_id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Mark a sent message as a forward of another
+    pub fn set_forwarded_from(&mut self, message_id: &str, original_id: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "UPDATE messages SET forwarded_from_id = ? WHERE id = ?",
+                params![original_id, message_id],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Starred messages across all threads, newest first, optionally only
+    /// those older than `before` (ms) for paging
+    pub fn get_starred_messages(&self, limit: u32, before: Option<i64>) -> Result<Vec<Message>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(&format!(
+                "SELECT {} FROM messages WHERE is_starred = 1 AND (?1 IS NULL OR timestamp < ?1) ORDER BY timestamp DESC LIMIT ?2",
+                MESSAGE_COLUMNS
+            ))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let messages = stmt
+            .query_map(params![before, limit], message_from_row)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(messages)
+    }
+
     /// Save a sent message
     pub fn save_sent_message(
         &mut self,
@@ -433,14 +600,10 @@ impl Database {
             .unwrap_or_else(|_| serde_json::json!({"text": String::from_utf8_lossy(payload).to_string()}));
 
         // Determine thread ID
-        let thread_id = envelope.thread_id.clone().unwrap_or_else(|| {
-            // Match message_handler.rs logic: direct_{sorted_keys}
-            let my_pk = &envelope.from_public_key;
-            let other_pk = &envelope.to_public_keys[0];
-            let mut keys = vec![my_pk.as_str(), other_pk.as_str()];
-            keys.sort();
-            format!("direct_{}", &keys.join("_")[..32])
-        });
+        let thread_id = envelope
+            .thread_id
+            .clone()
+            .unwrap_or_else(|| direct_thread_id(&envelope.from_public_key, &envelope.to_public_keys[0]));
 
         // Extract subject if available (for email threads)
         let subject = payload_json.get("subject").and_then(|s| s.as_str());
@@ -451,13 +614,15 @@ impl Database {
 
         // Insert message
         self.conn
-            .execute(
+            .prepare_cached(
                 r#"
                 INSERT OR REPLACE INTO messages 
                 (id, thread_id, from_public_key, from_handle, payload_type, payload_json, timestamp, is_outgoing, status, signature_valid, reply_to_id)
                 VALUES (?, ?, ?, ?, ?, ?, ?, 1, 'sent', 1, ?)
                 "#,
-                params![
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
                     envelope.id,
                     thread_id,
                     envelope.from_public_key,
@@ -466,13 +631,19 @@ impl Database {
                     serde_json::to_string(&payload_json).unwrap_or_default(),
                     envelope.timestamp,
                     reply_to_id,
-                ],
-            )
+                ])
+            })
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
+        changes::record(StorageChange::MessageInserted {
+            thread_id: thread_id.clone(),
+            message_id: envelope.id.clone(),
+        });
+
         // Update thread
         self.update_thread_for_message(&thread_id, envelope.timestamp, false)?;
 
+        crate::metrics::METRICS.message_stored();
         Ok(())
     }
 
@@ -499,13 +670,15 @@ impl Database {
 
         // Insert message
         self.conn
-            .execute(
+            .prepare_cached(
                 r#"
                 INSERT OR REPLACE INTO messages 
                 (id, thread_id, from_public_key, from_handle, payload_type, payload_json, timestamp, is_outgoing, status, signature_valid, reply_to_id)
                 VALUES (?, ?, ?, ?, ?, ?, ?, 0, 'received', ?, ?)
                 "#,
-                params![
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
                     message_id,
                     thread_id,
                     from_public_key,
@@ -515,13 +688,19 @@ impl Database {
                     timestamp,
                     if signature_valid { 1 } else { 0 },
                     reply_to_id,
-                ],
-            )
+                ])
+            })
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
+        changes::record(StorageChange::MessageInserted {
+            thread_id: thread_id.to_string(),
+            message_id: message_id.to_string(),
+        });
+
         // Update thread with incremented unread
         self.update_thread_for_message(thread_id, timestamp, true)?;
 
+        crate::metrics::METRICS.message_stored();
         Ok(())
     }
 
@@ -555,9 +734,7 @@ impl Database {
         // Determine thread ID (Direct Message fallback style)
         // Note: This relies on participants. If emails need Subject grouping, 
         // we are limited here until Mobile sends Subject.
-        let mut keys = vec![my_pk, from_pk];
-        keys.sort();
-        let thread_id = format!("direct_{}", &keys.join("_")[..32]);
+        let thread_id = direct_thread_id(my_pk, from_pk);
         
         // Get or create thread
         self.get_or_create_thread(&thread_id, from_pk, from_handle, None)?;
@@ -565,22 +742,29 @@ impl Database {
         // Insert Message
         let payload_json = serde_json::json!({ "text": text });
         
-        self.conn.execute(
+        self.conn.prepare_cached(
             r#"
             INSERT OR REPLACE INTO messages 
             (id, thread_id, from_public_key, from_handle, payload_type, payload_json, timestamp, is_outgoing, status, signature_valid)
             VALUES (?, ?, ?, ?, 'text', ?, ?, 0, 'received', 1)
             "#,
-            params![
+        )
+        .and_then(|mut stmt| {
+            stmt.execute(params![
                 message_id,
                 thread_id,
                 from_pk,
                 from_handle,
                 serde_json::to_string(&payload_json).unwrap_or_default(),
                 timestamp,
-            ],
-        ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            ])
+        }).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
         
+        changes::record(StorageChange::MessageInserted {
+            thread_id: thread_id.clone(),
+            message_id: message_id.to_string(),
+        });
+
         // Update Thread
         self.update_thread_for_message(&thread_id, timestamp, true)?;
         
@@ -597,10 +781,7 @@ impl Database {
         my_pk: &str,
     ) -> Result<(), DatabaseError> {
         // Determine thread ID
-        // Match message_handler.rs logic: direct_{sorted_keys}
-        let mut keys = vec![my_pk, to_pk];
-        keys.sort();
-        let thread_id = format!("direct_{}", &keys.join("_")[..32]);
+        let thread_id = direct_thread_id(my_pk, to_pk);
 
         // Get or create thread
         self.get_or_create_thread(&thread_id, to_pk, None, None)?;
@@ -609,28 +790,65 @@ impl Database {
 
         // Insert message
         self.conn
-            .execute(
+            .prepare_cached(
                 r#"
                 INSERT OR REPLACE INTO messages 
                 (id, thread_id, from_public_key, payload_type, payload_json, timestamp, is_outgoing, status, signature_valid)
                 VALUES (?, ?, ?, 'text', ?, ?, 1, 'sent', 1)
                 "#,
-                params![
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
                     message_id,
                     thread_id,
                     my_pk,
                     serde_json::to_string(&payload_json).unwrap_or_default(),
                     timestamp,
-                ],
-            )
+                ])
+            })
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
+        changes::record(StorageChange::MessageInserted {
+            thread_id: thread_id.clone(),
+            message_id: message_id.to_string(),
+        });
+
         // Update thread
         self.update_thread_for_message(&thread_id, timestamp, false)?;
 
         Ok(())
     }
 
+    /// Save a run of synced messages in one transaction
+    ///
+    /// A history sync delivers hundreds of these back to back; committing
+    /// each separately is what made large syncs stall.
+    pub fn save_synced_messages(&mut self, messages: &[SyncedMessage], my_pk: &str) -> Result<(), DatabaseError> {
+        self.in_transaction(|db| {
+            for message in messages {
+                if message.is_outgoing {
+                    db.save_browser_sent_message(
+                        &message.id,
+                        &message.conversation_with,
+                        &message.text,
+                        message.timestamp,
+                        my_pk,
+                    )?;
+                } else {
+                    db.save_synced_incoming_message(
+                        &message.id,
+                        &message.conversation_with,
+                        &message.text,
+                        message.timestamp,
+                        message.from_handle.as_deref(),
+                        my_pk,
+                    )?;
+                }
+            }
+            Ok(())
+        })
+    }
+
     /// Mark a message as read (acknowledged)
     pub fn mark_message_read(&mut self, message_id: &str) -> Result<(), DatabaseError> {
         self.conn
@@ -642,6 +860,25 @@ impl Database {
         Ok(())
     }
 
+    /// Move an outgoing message's delivery status forward
+    /// (sent → relayed → delivered → read); never backwards, and `failed`
+    /// only replaces `sent`. Returns whether it changed.
+    pub fn advance_message_status(&mut self, message_id: &str, status: &str) -> Result<bool, DatabaseError> {
+        let changed = self
+            .conn
+            .execute(
+                r#"
+                UPDATE messages SET status = ?2
+                WHERE id = ?1 AND is_outgoing = 1
+                  AND (CASE status WHEN 'relayed' THEN 1 WHEN 'delivered' THEN 2 WHEN 'read' THEN 3 ELSE 0 END)
+                    < (CASE ?2 WHEN 'relayed' THEN 1 WHEN 'failed' THEN 1 WHEN 'delivered' THEN 2 WHEN 'read' THEN 3 ELSE 0 END)
+                "#,
+                params![message_id, status],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(changed > 0)
+    }
+
     /// Count pending messages
     pub fn count_pending_messages(&self) -> Result<u32, DatabaseError> {
         let count: i64 = self
@@ -654,6 +891,20 @@ impl Database {
         Ok(count as u32)
     }
 
+    /// Size of the database file in bytes
+    pub fn size_bytes(&self) -> Result<u64, DatabaseError> {
+        let size: i64 = self
+            .conn
+            .query_row(
+                "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        Ok(size as u64)
+    }
+
     // ==================== Breadcrumb Operations ====================
 
     /// Count breadcrumbs
@@ -706,20 +957,11 @@ impl Database {
     /// Get breadcrumbs with pagination
     pub fn get_breadcrumbs(&self, limit: u32, offset: u32) -> Result<Vec<Breadcrumb>, DatabaseError> {
         let mut stmt = self.conn.prepare(
-            "SELECT h3_index, timestamp, signature, prev_hash FROM breadcrumbs ORDER BY timestamp DESC LIMIT ? OFFSET ?"
+            &format!("SELECT {} FROM breadcrumbs ORDER BY timestamp DESC LIMIT ? OFFSET ?", BREADCRUMB_COLUMNS)
         ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         let breadcrumbs = stmt
-            .query_map([limit, offset], |row| {
-                Ok(Breadcrumb {
-                    h3_index: row.get(0)?,
-                    timestamp: row.get(1)?,
-                    public_key: String::new(),
-                    signature: row.get(2)?,
-                    resolution: 7,
-                    prev_hash: row.get(3)?,
-                })
-            })
+            .query_map([limit, offset], |row| breadcrumb_from_row(row, 0))
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
 
         breadcrumbs
@@ -729,13 +971,177 @@ impl Database {
 
     /// Save a breadcrumb
     pub fn save_breadcrumb(&mut self, breadcrumb: &Breadcrumb) -> Result<(), DatabaseError> {
-        self.conn.execute(
-            "INSERT OR IGNORE INTO breadcrumbs (h3_index, timestamp, signature, prev_hash) VALUES (?, ?, ?, ?)",
-            params![breadcrumb.h3_index, breadcrumb.timestamp, breadcrumb.signature, breadcrumb.prev_hash],
-        ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.insert_breadcrumb(breadcrumb, None)?;
         Ok(())
     }
 
+    /// Save a breadcrumb that already exists on the server.
+    /// Returns false if it was already stored.
+    pub fn save_synced_breadcrumb(&mut self, breadcrumb: &Breadcrumb) -> Result<bool, DatabaseError> {
+        self.insert_breadcrumb(breadcrumb, Some(chrono::Utc::now().timestamp_millis()))
+    }
+
+    /// Store the complete signed record; false if (h3_index, timestamp) is taken
+    fn insert_breadcrumb(&mut self, breadcrumb: &Breadcrumb, synced_at: Option<i64>) -> Result<bool, DatabaseError> {
+        let inserted = self.conn.execute(
+            r#"
+            INSERT OR IGNORE INTO breadcrumbs (h3_index, timestamp, public_key, signature, resolution, prev_hash, synced_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            params![
+                breadcrumb.h3_index,
+                breadcrumb.timestamp,
+                Some(breadcrumb.public_key.to_lowercase()).filter(|pk| !pk.is_empty()),
+                breadcrumb.signature,
+                breadcrumb.resolution,
+                breadcrumb.prev_hash,
+                synced_at,
+            ],
+        ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(inserted > 0)
+    }
+
+    /// Record `public_key` as the signer of older rows stored without one,
+    /// where their signature verifies against it; returns how many were
+    /// adopted. Rows that don't verify keep no signer and are never
+    /// uploaded or used in claims.
+    pub fn adopt_legacy_breadcrumbs(&mut self, public_key: &str) -> Result<u32, DatabaseError> {
+        let legacy = {
+            let mut stmt = self.conn.prepare(
+                &format!("SELECT id, {} FROM breadcrumbs WHERE public_key IS NULL", BREADCRUMB_COLUMNS)
+            ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, i64>(0)?, breadcrumb_from_row(row, 1)?)))
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+        };
+        if legacy.is_empty() {
+            return Ok(0);
+        }
+
+        let public_key = public_key.to_lowercase();
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let mut adopted = 0;
+        for (id, mut breadcrumb) in legacy {
+            breadcrumb.public_key = public_key.clone();
+            if breadcrumb.verify().unwrap_or(false) {
+                tx.execute("UPDATE breadcrumbs SET public_key = ? WHERE id = ?", params![public_key, id])
+                    .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+                adopted += 1;
+            }
+        }
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(adopted)
+    }
+
+    /// Every breadcrumb signed by `public_key`, oldest first, after
+    /// adopting legacy rows that verify against it. These are the rows
+    /// claims and rewards are based on.
+    pub fn get_signed_breadcrumbs(&mut self, public_key: &str) -> Result<Vec<Breadcrumb>, DatabaseError> {
+        self.adopt_legacy_breadcrumbs(public_key)?;
+        let mut stmt = self.conn.prepare(
+            &format!("SELECT {} FROM breadcrumbs WHERE public_key = ? ORDER BY timestamp ASC", BREADCRUMB_COLUMNS)
+        ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let breadcrumbs = stmt
+            .query_map(params![public_key.to_lowercase()], |row| breadcrumb_from_row(row, 0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        breadcrumbs
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Breadcrumbs signed by `public_key` not yet uploaded, oldest first,
+    /// with their row ids
+    pub fn get_unsynced_breadcrumbs(&self, public_key: &str, limit: u32) -> Result<Vec<(i64, Breadcrumb)>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            &format!(
+                "SELECT id, {} FROM breadcrumbs WHERE synced_at IS NULL AND public_key = ? ORDER BY timestamp ASC LIMIT ?",
+                BREADCRUMB_COLUMNS
+            )
+        ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let breadcrumbs = stmt
+            .query_map(params![public_key.to_lowercase(), limit], |row| {
+                Ok((row.get(0)?, breadcrumb_from_row(row, 1)?))
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        breadcrumbs
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Count breadcrumbs waiting for upload
+    pub fn count_unsynced_breadcrumbs(&self) -> Result<u32, DatabaseError> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM breadcrumbs WHERE synced_at IS NULL AND public_key IS NOT NULL", [], |row| row.get(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        Ok(count as u32)
+    }
+
+    /// Mark breadcrumbs as uploaded
+    pub fn mark_breadcrumbs_synced(&mut self, ids: &[i64]) -> Result<(), DatabaseError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let now = chrono::Utc::now().timestamp_millis();
+        for id in ids {
+            tx.execute("UPDATE breadcrumbs SET synced_at = ? WHERE id = ?", params![now, id])
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Cell and timestamp of every breadcrumb, for trust scoring
+    pub fn get_breadcrumb_history(&self) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT h3_index, timestamp FROM breadcrumbs ORDER BY timestamp ASC"
+        ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let history = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        history
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Per-cell visit counts and first/last timestamps, optionally within a time range
+    pub fn get_breadcrumb_cell_stats(
+        &self,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> Result<Vec<(String, u32, i64, i64)>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT h3_index, COUNT(*), MIN(timestamp), MAX(timestamp)
+            FROM breadcrumbs
+            WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp <= ?2)
+            GROUP BY h3_index
+            "#,
+        ).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let stats = stmt
+            .query_map(params![since, until], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u32, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        stats
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
     // ==================== Sync State ====================
 
     /// Get last sync time
@@ -763,6 +1169,136 @@ impl Database {
         Ok(())
     }
 
+    // ==================== Settings ====================
+
+    /// Get a stored setting
+    pub fn get_setting(&self, key: &str) -> Option<String> {
+        self.conn
+            .query_row(
+                "SELECT value FROM sync_state WHERE key = ?",
+                params![key],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    /// Store a setting
+    pub fn set_setting(&mut self, key: &str, value: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?, ?)",
+                params![key, value],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Get the Stellar signing preference for an account
+    pub fn get_hardware_signing(&self, public_key: &str) -> HardwareSigningConfig {
+        self.get_setting(&format!("stellar_signer:{}", public_key))
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Set the Stellar signing preference for an account
+    pub fn set_hardware_signing(
+        &mut self,
+        public_key: &str,
+        config: &HardwareSigningConfig,
+    ) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting(&format!("stellar_signer:{}", public_key), &json)
+    }
+
+    /// PIN / biometric lock settings
+    pub fn get_app_lock_config(&self) -> LockConfig {
+        self.get_setting("app_lock")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_app_lock_config(&mut self, config: &LockConfig) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting("app_lock", &json)
+    }
+
+    /// Erase this device on a signed wipe from another of our devices
+    pub fn get_remote_wipe_enabled(&self) -> bool {
+        self.get_setting("remote_wipe_enabled")
+            .map(|v| v == "true")
+            .unwrap_or(false)
+    }
+
+    pub fn set_remote_wipe_enabled(&mut self, enabled: bool) -> Result<(), DatabaseError> {
+        self.set_setting("remote_wipe_enabled", if enabled { "true" } else { "false" })
+    }
+
+    /// Timestamp of the newest remote wipe acted on, 0 if none; survives
+    /// the wipe itself
+    pub fn get_remote_wipe_mark(&self) -> i64 {
+        self.conn
+            .query_row("SELECT wiped_through FROM remote_wipe_mark WHERE id = 1", [], |row| row.get(0))
+            .unwrap_or(0)
+    }
+
+    pub fn set_remote_wipe_mark(&mut self, wiped_through: i64) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO remote_wipe_mark (id, wiped_through) VALUES (1, ?)",
+                params![wiped_through],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Let the relay tell contacts when we're online (on by default)
+    pub fn get_share_presence(&self) -> bool {
+        self.get_setting("share_presence").as_deref() != Some("false")
+    }
+
+    pub fn set_share_presence(&mut self, share: bool) -> Result<(), DatabaseError> {
+        self.set_setting("share_presence", if share { "true" } else { "false" })
+    }
+
+    /// Fetch previews for links in outgoing messages (off by default;
+    /// the linked site sees our address)
+    pub fn get_link_previews(&self) -> bool {
+        self.get_setting("link_previews").as_deref() == Some("true")
+    }
+
+    pub fn set_link_previews(&mut self, enabled: bool) -> Result<(), DatabaseError> {
+        self.set_setting("link_previews", if enabled { "true" } else { "false" })
+    }
+
+    /// Deliver directly to contacts on the same network (off by default;
+    /// advertises our public key on the LAN)
+    pub fn get_lan_delivery(&self) -> bool {
+        self.get_setting("lan_delivery").as_deref() == Some("true")
+    }
+
+    pub fn set_lan_delivery(&mut self, enabled: bool) -> Result<(), DatabaseError> {
+        self.set_setting("lan_delivery", if enabled { "true" } else { "false" })
+    }
+
+    pub fn get_local_api(&self) -> bool {
+        self.get_setting("local_api").as_deref() == Some("true")
+    }
+
+    pub fn set_local_api(&mut self, enabled: bool) -> Result<(), DatabaseError> {
+        self.set_setting("local_api", if enabled { "true" } else { "false" })
+    }
+
+    /// DNS-over-HTTPS resolver for federated handles; None when unset
+    pub fn get_dns_over_https(&self) -> Option<String> {
+        self.get_setting("dns_over_https").filter(|url| !url.is_empty())
+    }
+
+    pub fn set_dns_over_https(&mut self, url: Option<&str>) -> Result<(), DatabaseError> {
+        self.set_setting("dns_over_https", url.unwrap_or_default())
+    }
+
     /// Clear all data from database
     pub fn clear_all(&mut self) -> Result<(), DatabaseError> {
         tracing::info!("🗑️ Clearing all database data...");
@@ -772,6 +1308,12 @@ impl Database {
         self.conn.execute("DELETE FROM threads", [])
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
         let _ = self.conn.execute("DELETE FROM breadcrumbs", []);
+        let _ = self.conn.execute("DELETE FROM breadcrumb_quarantine", []);
+        // Rewarded epochs are numbered from the first breadcrumb
+        let _ = self.conn.execute("DELETE FROM gns_rewarded_epochs", []);
+        let _ = self.conn.execute("DELETE FROM gns_claims", []);
+        let _ = self.conn.execute("DELETE FROM dix_activity", []);
+        let _ = self.conn.execute("DELETE FROM dix_engagement_seen", []);
         self.conn.execute("VACUUM", [])
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
         
@@ -805,6 +1347,158 @@ impl Database {
             .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
         Ok(())
     }
+
+    /// Get privacy zones where collection pauses
+    pub fn get_privacy_zones(&self) -> Vec<PrivacyZone> {
+        self.get_setting("privacy_zones")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Store privacy zones
+    pub fn set_privacy_zones(&mut self, zones: &[PrivacyZone]) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(zones)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting("privacy_zones", &json)
+    }
+
+    /// Threads whose outgoing messages use sealed sender
+    pub fn get_sealed_sender_threads(&self) -> Vec<String> {
+        self.get_setting("sealed_sender_threads")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_sealed_sender_thread(&self, thread_id: &str) -> bool {
+        self.get_sealed_sender_threads().iter().any(|t| t == thread_id)
+    }
+
+    /// Opt a thread in or out of sealed sender
+    pub fn set_sealed_sender_thread(&mut self, thread_id: &str, enabled: bool) -> Result<(), DatabaseError> {
+        let mut threads = self.get_sealed_sender_threads();
+        threads.retain(|t| t != thread_id);
+        if enabled {
+            threads.push(thread_id.to_string());
+        }
+        let json = serde_json::to_string(&threads)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting("sealed_sender_threads", &json)
+    }
+
+    /// Payload size (bytes) from which outgoing messages are compressed;
+    /// `None` when compression is turned off
+    pub fn get_compression_threshold(&self) -> Option<usize> {
+        match self.get_setting("compression_threshold").and_then(|v| v.parse().ok()) {
+            Some(0) => None,
+            Some(threshold) => Some(threshold),
+            None => Some(gns_crypto_core::DEFAULT_COMPRESSION_THRESHOLD),
+        }
+    }
+
+    /// Set the compression threshold; `None` turns compression off
+    pub fn set_compression_threshold(&mut self, threshold: Option<usize>) -> Result<(), DatabaseError> {
+        self.set_setting("compression_threshold", &threshold.unwrap_or(0).to_string())
+    }
+
+    /// The push token registered for this device, if any
+    pub fn get_push_registration(&self) -> Option<crate::push::PushRegistration> {
+        self.get_setting("push_registration")
+            .and_then(|json| serde_json::from_str(&json).ok())
+    }
+
+    /// Store (or clear) the registered push token
+    pub fn set_push_registration(
+        &mut self,
+        registration: Option<&crate::push::PushRegistration>,
+    ) -> Result<(), DatabaseError> {
+        let json = match registration {
+            Some(r) => serde_json::to_string(r).map_err(|e| DatabaseError::SqliteError(e.to_string()))?,
+            None => String::new(),
+        };
+        self.set_setting("push_registration", &json)
+    }
+
+    /// Modules registered for the identity record, besides the built-ins
+    pub fn get_record_modules(&self) -> Vec<crate::capabilities::RecordModule> {
+        self.get_setting("record_modules")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_record_modules(&mut self, modules: &[crate::capabilities::RecordModule]) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(modules).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting("record_modules", &json)
+    }
+
+    /// Endpoints registered for the identity record
+    pub fn get_record_endpoints(&self) -> Vec<crate::capabilities::RecordEndpoint> {
+        self.get_setting("record_endpoints")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_record_endpoints(&mut self, endpoints: &[crate::capabilities::RecordEndpoint]) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(endpoints).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting("record_endpoints", &json)
+    }
+
+    /// Whether the user paused breadcrumb uploads
+    pub fn get_breadcrumb_upload_paused(&self) -> bool {
+        self.get_setting("breadcrumb_upload_paused").as_deref() == Some("true")
+    }
+
+    pub fn set_breadcrumb_upload_paused(&mut self, paused: bool) -> Result<(), DatabaseError> {
+        self.set_setting("breadcrumb_upload_paused", if paused { "true" } else { "false" })
+    }
+
+    /// Unread messages across all threads
+    pub fn get_total_unread(&self) -> Result<u32, DatabaseError> {
+        self.conn
+            .query_row("SELECT COALESCE(SUM(unread_count), 0) FROM threads", [], |row| row.get(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    /// Global shortcut for the quick-compose window; `None` when disabled
+    /// (defaults to Ctrl/Cmd+Shift+M)
+    pub fn get_quick_compose_shortcut(&self) -> Option<String> {
+        match self.get_setting("quick_compose_shortcut") {
+            Some(shortcut) if shortcut.is_empty() => None,
+            Some(shortcut) => Some(shortcut),
+            None => Some("CommandOrControl+Shift+M".to_string()),
+        }
+    }
+
+    pub fn set_quick_compose_shortcut(&mut self, shortcut: Option<&str>) -> Result<(), DatabaseError> {
+        self.set_setting("quick_compose_shortcut", shortcut.unwrap_or(""))
+    }
+
+    /// Get our submitted external proofs
+    pub fn get_verifications(&self) -> Vec<Verification> {
+        self.get_setting("verifications")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Store our submitted external proofs
+    pub fn set_verifications(&mut self, verifications: &[Verification]) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(verifications)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting("verifications", &json)
+    }
+
+    /// Get our cached public profile
+    pub fn get_profile(&self) -> Profile {
+        self.get_setting("profile")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Cache our public profile for offline display
+    pub fn set_profile(&mut self, profile: &Profile) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(profile)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting("profile", &json)
+    }
 }
 
 /// Database errors
@@ -818,4 +1512,151 @@ pub enum DatabaseError {
 
     #[error("Encryption error: {0}")]
     EncryptionError(String),
+
+    #[error("Database schema version {found} is newer than this app supports ({supported}); please update the app")]
+    SchemaTooNew { found: u32, supported: u32 },
+}
+
+
+/// Breadcrumb columns in the order `breadcrumb_from_row` reads them
+const BREADCRUMB_COLUMNS: &str = "h3_index, timestamp, public_key, signature, resolution, prev_hash";
+
+/// Map `BREADCRUMB_COLUMNS` starting at column `start`; rows stored before
+/// signers were recorded have an empty public key
+fn breadcrumb_from_row(row: &rusqlite::Row, start: usize) -> rusqlite::Result<Breadcrumb> {
+    Ok(Breadcrumb {
+        h3_index: row.get(start)?,
+        timestamp: row.get(start + 1)?,
+        public_key: row.get::<_, Option<String>>(start + 2)?.unwrap_or_default(),
+        signature: row.get(start + 3)?,
+        resolution: row.get(start + 4)?,
+        prev_hash: row.get(start + 5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synced(id: &str, is_outgoing: bool) -> SyncedMessage {
+        SyncedMessage {
+            id: id.to_string(),
+            conversation_with: "b".repeat(64),
+            text: format!("message {}", id),
+            timestamp: 1_700_000_000_000,
+            from_handle: None,
+            is_outgoing,
+        }
+    }
+
+    #[test]
+    fn test_save_synced_messages() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let my_pk = "a".repeat(64);
+
+        let batch: Vec<_> = (0..5).map(|i| synced(&i.to_string(), i % 2 == 0)).collect();
+        db.save_synced_messages(&batch, &my_pk).unwrap();
+
+        let thread = db.get_threads(true, None, 10).unwrap().remove(0);
+        assert_eq!(thread.unread_count, 2);
+        assert_eq!(db.get_messages(&thread.id, 10).unwrap().len(), 5);
+
+        // A failure part way through leaves nothing behind
+        let failed = db.in_transaction(|db| {
+            db.save_synced_messages(&[synced("5", false)], &my_pk)?;
+            assert!(db.get_message("5")?.is_some());
+            Err::<(), _>(DatabaseError::IoError("interrupted".to_string()))
+        });
+        assert!(failed.is_err());
+        assert!(db.get_message("5").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_direct_thread_ids_are_per_pair() {
+        let me = format!("0{}", "a".repeat(63));
+        let (bob, carol) = ("b".repeat(64), "c".repeat(64));
+        assert_eq!(direct_thread_id(&me, &bob), direct_thread_id(&bob.to_uppercase(), &me));
+        // Both sort after us, so a prefix of the pair would be the same
+        assert_ne!(direct_thread_id(&me, &bob), direct_thread_id(&me, &carol));
+    }
+
+    #[test]
+    fn test_breadcrumbs_keep_signer() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let me = gns_crypto_core::GnsIdentity::generate();
+        let other = gns_crypto_core::GnsIdentity::generate();
+        let mine = gns_crypto_core::create_breadcrumb(&me, 52.52, 13.405, Some(6), None).unwrap();
+        db.save_breadcrumb(&mine).unwrap();
+        assert_eq!(db.get_breadcrumbs(10, 0).unwrap(), vec![mine.clone()]);
+
+        // Rows from before signers were stored, one ours and one not
+        let legacy = gns_crypto_core::create_breadcrumb(&me, 48.85, 2.35, None, None).unwrap();
+        let foreign = gns_crypto_core::create_breadcrumb(&other, 40.71, -74.0, None, None).unwrap();
+        for b in [&legacy, &foreign] {
+            db.conn
+                .execute(
+                    "INSERT INTO breadcrumbs (h3_index, timestamp, signature, prev_hash) VALUES (?, ?, ?, ?)",
+                    params![b.h3_index, b.timestamp, b.signature, b.prev_hash],
+                )
+                .unwrap();
+        }
+        assert_eq!(db.get_unsynced_breadcrumbs(&me.public_key_hex(), 10).unwrap().len(), 1);
+
+        assert_eq!(db.adopt_legacy_breadcrumbs(&me.public_key_hex()).unwrap(), 1);
+        let unsynced = db.get_unsynced_breadcrumbs(&me.public_key_hex(), 10).unwrap();
+        assert_eq!(unsynced.len(), 2);
+        assert!(unsynced.iter().all(|(_, b)| b.verify().unwrap()));
+        assert_eq!(db.count_unsynced_breadcrumbs().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_advance_message_status() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        db.save_synced_messages(&[synced("out", true), synced("in", false)], &"a".repeat(64))
+            .unwrap();
+        let status = |db: &Database, id: &str| db.get_message(id).unwrap().unwrap().status;
+
+        assert!(db.advance_message_status("out", "delivered").unwrap());
+        // A late relay ack doesn't undo delivery
+        assert!(!db.advance_message_status("out", "relayed").unwrap());
+        assert!(!db.advance_message_status("out", "failed").unwrap());
+        assert_eq!(status(&db, "out"), "delivered");
+
+        assert!(!db.advance_message_status("in", "relayed").unwrap());
+    }
+
+    #[test]
+    fn test_starred_messages() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let batch: Vec<_> = (0..3)
+            .map(|i| SyncedMessage {
+                timestamp: 1_700_000_000_000 + i,
+                ..synced(&i.to_string(), false)
+            })
+            .collect();
+        db.save_synced_messages(&batch, &"a".repeat(64)).unwrap();
+
+        assert!(db.set_message_starred("0", true).unwrap());
+        assert!(db.set_message_starred("2", true).unwrap());
+        assert!(!db.set_message_starred("missing", true).unwrap());
+
+        let ids = |messages: Vec<Message>| messages.into_iter().map(|m| m.id).collect::<Vec<_>>();
+        assert_eq!(ids(db.get_starred_messages(10, None).unwrap()), vec!["2", "0"]);
+        assert_eq!(ids(db.get_starred_messages(10, Some(1_700_000_000_002)).unwrap()), vec!["0"]);
+
+        db.set_message_starred("2", false).unwrap();
+        assert_eq!(ids(db.get_starred_messages(10, None).unwrap()), vec!["0"]);
+    }
 }
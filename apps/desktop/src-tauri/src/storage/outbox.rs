@@ -0,0 +1,82 @@
+//! Relay outbox
+//!
+//! Envelope frames waiting for the relay's ack, kept in `pending_messages`
+//! so they are resent after a restart.
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::network::outbox::QueuedFrame;
+
+impl Database {
+    pub fn queue_outgoing_frame(&mut self, id: &str, frame: &str, now: i64) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO pending_messages (id, envelope_json, created_at) VALUES (?, ?, ?)",
+                params![id, frame, now],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn remove_outgoing_frame(&mut self, id: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute("DELETE FROM pending_messages WHERE id = ?", params![id])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Frames to resend on connecting, oldest first. Each call counts as a
+    /// retry; frames retried `max_retries` times are dropped.
+    pub fn take_outgoing_frames(&mut self, max_retries: u32) -> Result<Vec<QueuedFrame>, DatabaseError> {
+        self.in_transaction(|db| {
+            db.conn
+                .execute(
+                    "DELETE FROM pending_messages WHERE retry_count >= ?",
+                    params![max_retries],
+                )
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            db.conn
+                .execute("UPDATE pending_messages SET retry_count = retry_count + 1", [])
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+            let mut stmt = db
+                .conn
+                .prepare_cached("SELECT id, envelope_json FROM pending_messages ORDER BY created_at, rowid")
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            let frames = stmt
+                .query_map([], |row| Ok(QueuedFrame::envelope(&row.get::<_, String>(0)?, row.get(1)?)))
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            Ok(frames)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_outgoing_frames() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+
+        db.queue_outgoing_frame("b", "frame b", 2).unwrap();
+        db.queue_outgoing_frame("a", "frame a", 1).unwrap();
+        db.queue_outgoing_frame("c", "frame c", 3).unwrap();
+        db.remove_outgoing_frame("c").unwrap();
+        assert_eq!(db.count_pending_messages().unwrap(), 2);
+
+        let frames = db.take_outgoing_frames(2).unwrap();
+        assert_eq!(frames, vec![QueuedFrame::envelope("a", "frame a".to_string()), QueuedFrame::envelope("b", "frame b".to_string())]);
+
+        // Given up on after the retry limit
+        assert_eq!(db.take_outgoing_frames(2).unwrap().len(), 2);
+        assert!(db.take_outgoing_frames(2).unwrap().is_empty());
+    }
+}
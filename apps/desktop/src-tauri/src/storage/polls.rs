@@ -0,0 +1,142 @@
+//! Polls
+//!
+//! Polls we created or were shown, and the votes we know of.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::polls::{Poll, PollVote};
+
+impl Database {
+    pub(super) fn initialize_poll_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS polls (
+                id TEXT PRIMARY KEY,
+                poll_json TEXT NOT NULL,
+                creator TEXT NOT NULL,
+                thread_id TEXT,
+                dix_post_id TEXT,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS poll_votes (
+                poll_id TEXT NOT NULL,
+                voter TEXT NOT NULL,
+                choices_json TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                signature TEXT NOT NULL,
+                PRIMARY KEY (poll_id, voter)
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Remember a poll and where it was posted; the first copy wins
+    pub fn save_poll(&mut self, poll: &Poll, thread_id: Option<&str>, dix_post_id: Option<&str>) -> Result<(), DatabaseError> {
+        let poll_json = serde_json::to_string(poll).unwrap_or_default();
+        self.conn
+            .execute(
+                r#"
+                INSERT OR IGNORE INTO polls (id, poll_json, creator, thread_id, dix_post_id, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+                params![poll.id, poll_json, poll.creator.to_lowercase(), thread_id, dix_post_id, poll.created_at],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_poll(&self, poll_id: &str) -> Option<Poll> {
+        self.conn
+            .query_row("SELECT poll_json FROM polls WHERE id = ?", params![poll_id], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+    }
+
+    /// Record a voter's vote unless we already hold a later one from them;
+    /// returns whether it was recorded
+    pub fn record_poll_vote(&mut self, vote: &PollVote) -> Result<bool, DatabaseError> {
+        let choices_json = serde_json::to_string(&vote.choices).unwrap_or_default();
+        let changed = self
+            .conn
+            .execute(
+                r#"
+                INSERT INTO poll_votes (poll_id, voter, choices_json, timestamp, signature)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(poll_id, voter) DO UPDATE SET
+                    choices_json = excluded.choices_json,
+                    timestamp = excluded.timestamp,
+                    signature = excluded.signature
+                WHERE excluded.timestamp > poll_votes.timestamp
+                "#,
+                params![vote.poll_id, vote.voter, choices_json, vote.timestamp, vote.signature],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(changed > 0)
+    }
+
+    /// Each voter's latest vote on a poll
+    pub fn get_poll_votes(&self, poll_id: &str) -> Result<Vec<PollVote>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT voter, choices_json, timestamp, signature FROM poll_votes WHERE poll_id = ?")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let votes = stmt
+            .query_map(params![poll_id], |row| {
+                let choices_json: String = row.get(1)?;
+                Ok(PollVote {
+                    poll_id: poll_id.to_string(),
+                    voter: row.get(0)?,
+                    choices: serde_json::from_str(&choices_json).unwrap_or_default(),
+                    timestamp: row.get(2)?,
+                    signature: row.get(3)?,
+                })
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        votes
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polls;
+    use gns_crypto_core::GnsIdentity;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_latest_vote_counts() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let creator = GnsIdentity::generate();
+        let voter = GnsIdentity::generate();
+        let options = vec!["Yes".to_string(), "No".to_string()];
+        let poll = polls::create_poll(&creator, "Ship it?", options, false, None, 1_000);
+        db.save_poll(&poll, Some("t1"), None).unwrap();
+        assert_eq!(db.get_poll(&poll.id), Some(poll.clone()));
+
+        let first = polls::create_vote(&voter, &poll, vec![0], 2_000).unwrap();
+        let second = polls::create_vote(&voter, &poll, vec![1], 3_000).unwrap();
+        assert!(db.record_poll_vote(&second).unwrap());
+        // An older vote arriving late doesn't replace the newer one
+        assert!(!db.record_poll_vote(&first).unwrap());
+
+        let votes = db.get_poll_votes(&poll.id).unwrap();
+        assert_eq!(votes, vec![second.clone()]);
+        assert!(polls::verify_vote(&votes[0], &poll).is_ok());
+    }
+}
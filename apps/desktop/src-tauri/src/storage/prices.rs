@@ -0,0 +1,93 @@
+//! Price Cache
+//!
+//! Daily fiat prices fetched for payment exports, so a re-export doesn't
+//! hit the price API again. Past days don't change, so entries never
+//! expire.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::stellar::prices::{PriceSource, PRICE_SOURCE_SETTING};
+
+impl Database {
+    pub(super) fn initialize_price_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS price_cache (
+                asset_code TEXT NOT NULL,
+                currency TEXT NOT NULL,
+                day TEXT NOT NULL,
+                price REAL NOT NULL,
+                fetched_at INTEGER NOT NULL,
+                PRIMARY KEY (asset_code, currency, day)
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Cached price for `day` (`YYYY-MM-DD`)
+    pub fn get_cached_price(&self, asset_code: &str, currency: &str, day: &str) -> Option<f64> {
+        self.conn
+            .query_row(
+                "SELECT price FROM price_cache WHERE asset_code = ? AND currency = ? AND day = ?",
+                params![asset_code, currency, day],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    pub fn cache_price(&mut self, asset_code: &str, currency: &str, day: &str, price: f64) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT OR REPLACE INTO price_cache (asset_code, currency, day, price, fetched_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+                params![asset_code, currency, day, price, chrono::Utc::now().timestamp_millis()],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The configured price API, or the default
+    pub fn get_price_source(&self) -> PriceSource {
+        self.get_setting(PRICE_SOURCE_SETTING)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_price_source(&mut self, source: &PriceSource) -> Result<(), DatabaseError> {
+        self.set_setting(PRICE_SOURCE_SETTING, &serde_json::to_string(source).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_price_cache() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        assert_eq!(db.get_cached_price("XLM", "usd", "2024-03-05"), None);
+        db.cache_price("XLM", "usd", "2024-03-05", 0.12).unwrap();
+        assert_eq!(db.get_cached_price("XLM", "usd", "2024-03-05"), Some(0.12));
+        assert_eq!(db.get_cached_price("XLM", "eur", "2024-03-05"), None);
+
+        assert_eq!(db.get_price_source(), PriceSource::default());
+        let source = PriceSource {
+            currency: "eur".to_string(),
+            ..Default::default()
+        };
+        db.set_price_source(&source).unwrap();
+        assert_eq!(db.get_price_source(), source);
+    }
+}
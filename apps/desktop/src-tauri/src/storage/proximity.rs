@@ -0,0 +1,184 @@
+//! Proximity
+//!
+//! The latest claim each way with each peer, and finished attestations.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::proximity::{ProximityRequest, StoredAttestation};
+
+impl Database {
+    pub(super) fn initialize_proximity_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS proximity_claims (
+                peer_public_key TEXT NOT NULL,
+                outgoing INTEGER NOT NULL,
+                request_json TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (peer_public_key, outgoing)
+            );
+
+            CREATE TABLE IF NOT EXISTS proximity_attestations (
+                id TEXT PRIMARY KEY,
+                peer_public_key TEXT NOT NULL,
+                attestation_json TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_proximity_attestations_peer
+                ON proximity_attestations(peer_public_key, created_at);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Keep the newest claim we sent to (`outgoing`) or got from a peer
+    pub fn save_proximity_claim(
+        &mut self,
+        peer_public_key: &str,
+        outgoing: bool,
+        request: &ProximityRequest,
+    ) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT INTO proximity_claims (peer_public_key, outgoing, request_json, created_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT(peer_public_key, outgoing) DO UPDATE SET
+                    request_json = excluded.request_json,
+                    created_at = excluded.created_at
+                WHERE excluded.created_at >= proximity_claims.created_at
+                "#,
+                params![
+                    peer_public_key.to_lowercase(),
+                    outgoing,
+                    serde_json::to_string(request).unwrap_or_default(),
+                    request.claim.breadcrumb.timestamp * 1000,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_proximity_claim(&self, peer_public_key: &str, outgoing: bool) -> Option<ProximityRequest> {
+        self.conn
+            .query_row(
+                "SELECT request_json FROM proximity_claims WHERE peer_public_key = ? AND outgoing = ?",
+                params![peer_public_key.to_lowercase(), outgoing],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+    }
+
+    /// Store a finished proof; the copy built by the other side is the same
+    /// one and is ignored
+    pub fn save_proximity_attestation(&mut self, stored: &StoredAttestation) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT OR IGNORE INTO proximity_attestations (id, peer_public_key, attestation_json, created_at)
+                VALUES (?, ?, ?, ?)
+                "#,
+                params![
+                    stored.id,
+                    stored.peer_public_key.to_lowercase(),
+                    serde_json::to_string(&stored.attestation).unwrap_or_default(),
+                    stored.created_at,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_proximity_attestation(&self, id: &str) -> Option<StoredAttestation> {
+        self.conn
+            .query_row(
+                "SELECT id, peer_public_key, attestation_json, created_at FROM proximity_attestations WHERE id = ?",
+                params![id],
+                attestation_from_row,
+            )
+            .optional()
+            .ok()
+            .flatten()
+            .flatten()
+    }
+
+    /// Proofs with everyone, or with one peer, newest first
+    pub fn list_proximity_attestations(&self, peer_public_key: Option<&str>) -> Result<Vec<StoredAttestation>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                r#"
+                SELECT id, peer_public_key, attestation_json, created_at FROM proximity_attestations
+                WHERE ?1 IS NULL OR peer_public_key = ?1
+                ORDER BY created_at DESC
+                "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let attestations = stmt
+            .query_map(params![peer_public_key.map(str::to_lowercase)], attestation_from_row)
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok().flatten())
+            .collect();
+        Ok(attestations)
+    }
+}
+
+/// Map `id, peer_public_key, attestation_json, created_at`; None if the
+/// attestation doesn't parse
+fn attestation_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<StoredAttestation>> {
+    let attestation_json: String = row.get(2)?;
+    let (id, peer_public_key, created_at) = (row.get(0)?, row.get(1)?, row.get(3)?);
+    Ok(serde_json::from_str(&attestation_json).ok().map(|attestation| StoredAttestation {
+        id,
+        peer_public_key,
+        attestation,
+        created_at,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proximity;
+    use gns_crypto_core::proximity::create_proximity_claim;
+    use gns_crypto_core::GnsIdentity;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_claims_and_attestations() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let alice = GnsIdentity::generate();
+        let bob = GnsIdentity::generate();
+        let bob_pk = bob.public_key_hex();
+        let ours = ProximityRequest {
+            claim: create_proximity_claim(&alice, 52.52, 13.405, &bob_pk).unwrap(),
+            max_steps: 1,
+        };
+        let theirs = ProximityRequest {
+            claim: create_proximity_claim(&bob, 52.52, 13.405, &alice.public_key_hex()).unwrap(),
+            max_steps: 1,
+        };
+        db.save_proximity_claim(&bob_pk, true, &ours).unwrap();
+        db.save_proximity_claim(&bob_pk, false, &theirs).unwrap();
+        assert_eq!(db.get_proximity_claim(&bob_pk.to_uppercase(), true), Some(ours.clone()));
+        assert_eq!(db.get_proximity_claim(&bob_pk, false), Some(theirs.clone()));
+
+        let stored = proximity::combine(&ours, &theirs, &bob_pk).unwrap();
+        db.save_proximity_attestation(&stored).unwrap();
+        db.save_proximity_attestation(&stored).unwrap();
+        assert_eq!(db.list_proximity_attestations(Some(&bob_pk)).unwrap().len(), 1);
+        assert!(db.list_proximity_attestations(Some(&alice.public_key_hex())).unwrap().is_empty());
+        assert_eq!(db.get_proximity_attestation(&stored.id).unwrap().attestation, stored.attestation);
+    }
+}
@@ -0,0 +1,67 @@
+//! Report log
+//!
+//! Local record of abuse reports the user has submitted.
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::commands::reports::ContentReport;
+
+impl Database {
+    /// Create report tables
+    pub(super) fn initialize_report_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS reports (
+                id TEXT PRIMARY KEY,
+                reporter_public_key TEXT NOT NULL,
+                report_json TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_reports_reporter ON reports(reporter_public_key, created_at DESC);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Log a submitted report
+    pub fn save_report(&mut self, report: &ContentReport, signature: &str) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                r#"
+                INSERT OR REPLACE INTO reports (id, reporter_public_key, report_json, signature, created_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+                params![
+                    report.id,
+                    report.reporter_public_key,
+                    serde_json::to_string(report).unwrap_or_default(),
+                    signature,
+                    report.created_at,
+                ],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Reports made by an identity, newest first
+    pub fn get_reports(&self, reporter_public_key: &str) -> Result<Vec<ContentReport>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT report_json FROM reports WHERE reporter_public_key = ? ORDER BY created_at DESC")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let reports = stmt
+            .query_map(params![reporter_public_key], |row| row.get::<_, String>(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+
+        Ok(reports)
+    }
+}
@@ -0,0 +1,157 @@
+//! Resolution cache
+//!
+//! Directory lookups (handle → identity, public key → identity) kept
+//! locally so sends to known contacts don't wait on, or need, the API.
+//! Freshness is decided by `crate::resolver`; this only stores entries.
+
+use rusqlite::{params, OptionalExtension};
+
+use super::{Database, DatabaseError};
+use crate::network::IdentityInfo;
+use crate::resolver::CachedIdentity;
+
+impl Database {
+    pub(super) fn initialize_resolution_cache_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS resolution_cache (
+                public_key TEXT PRIMARY KEY,
+                handle TEXT,
+                info_json TEXT NOT NULL,
+                record_verified INTEGER NOT NULL DEFAULT 0,
+                fetched_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_resolution_cache_handle ON resolution_cache(handle);
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Store a fresh lookup; a handle now pointing here is dropped from
+    /// whichever identity held it before
+    pub fn cache_resolution(
+        &mut self,
+        info: &IdentityInfo,
+        record_verified: bool,
+        now: i64,
+    ) -> Result<(), DatabaseError> {
+        let public_key = info.public_key.to_lowercase();
+        let handle = info.handle.as_deref().map(normalize_handle);
+        let json = serde_json::to_string(info).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        if let Some(handle) = &handle {
+            tx.execute(
+                "UPDATE resolution_cache SET handle = NULL WHERE handle = ? AND public_key != ?",
+                params![handle, public_key],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        }
+        tx.execute(
+            r#"
+            INSERT OR REPLACE INTO resolution_cache
+                (public_key, handle, info_json, record_verified, fetched_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+            params![public_key, handle, json, record_verified, now],
+        )
+        .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))
+    }
+
+    pub fn cached_identity(&self, public_key: &str) -> Option<CachedIdentity> {
+        self.cached_where("public_key = ?", &public_key.to_lowercase())
+    }
+
+    pub fn cached_handle(&self, handle: &str) -> Option<CachedIdentity> {
+        self.cached_where("handle = ?", &normalize_handle(handle))
+    }
+
+    /// Every identity in the cache
+    pub fn cached_public_keys(&self) -> Result<Vec<String>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT public_key FROM resolution_cache ORDER BY fetched_at")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let keys = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(keys)
+    }
+
+    fn cached_where(&self, condition: &str, value: &str) -> Option<CachedIdentity> {
+        let (json, record_verified, fetched_at): (String, bool, i64) = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT info_json, record_verified, fetched_at FROM resolution_cache WHERE {}",
+                    condition
+                ),
+                params![value],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .ok()
+            .flatten()?;
+
+        Some(CachedIdentity {
+            info: serde_json::from_str(&json).ok()?,
+            record_verified,
+            fetched_at,
+        })
+    }
+}
+
+fn normalize_handle(handle: &str) -> String {
+    handle.trim_start_matches('@').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn info(public_key: &str, handle: &str) -> IdentityInfo {
+        IdentityInfo {
+            public_key: public_key.to_string(),
+            encryption_key: "e".repeat(64),
+            pq_encryption_key: None,
+            handle: Some(handle.to_string()),
+            avatar_url: None,
+            display_name: None,
+            is_verified: false,
+            verifications: Vec::new(),
+            modules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_by_key_and_handle() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let (alice, mallory) = ("a".repeat(64), "b".repeat(64));
+
+        db.cache_resolution(&info(&alice, "Alice"), true, 100).unwrap();
+        let cached = db.cached_handle("@alice").unwrap();
+        assert_eq!(cached.info.public_key, alice);
+        assert!(cached.record_verified);
+        assert_eq!(cached.fetched_at, 100);
+        assert!(db.cached_identity(&alice.to_uppercase()).is_some());
+
+        // The handle moved; the old owner keeps its key entry only
+        db.cache_resolution(&info(&mallory, "alice"), false, 200).unwrap();
+        assert_eq!(db.cached_handle("alice").unwrap().info.public_key, mallory);
+        assert!(db.cached_identity(&alice).is_some());
+        assert_eq!(db.cached_public_keys().unwrap(), vec![alice, mallory]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Message retention
+//!
+//! Users can cap how much history is kept, by age or by count per thread.
+//! Starred messages are always kept. Attachments are referenced from the
+//! message payload, so they go with their message.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Database, DatabaseError, DatabaseHandle};
+
+/// Wait after startup before the first pruning pass
+const STARTUP_DELAY: Duration = Duration::from_secs(2 * 60);
+
+/// Time between pruning passes
+const PRUNE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const RETENTION_POLICY_KEY: &str = "retention_policy";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// How much message history to keep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RetentionPolicy {
+    #[default]
+    KeepForever,
+    /// Delete messages older than this many days
+    MaxAgeDays { days: u32 },
+    /// Keep only the newest messages in each thread
+    MaxPerThread { count: u32 },
+}
+
+impl RetentionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::MaxAgeDays { days: 0 } => Err("Retention must keep at least one day".to_string()),
+            Self::MaxPerThread { count: 0 } => Err("Retention must keep at least one message".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Messages older than this (ms) are pruned under an age policy
+    fn cutoff(&self, now: i64) -> Option<i64> {
+        match self {
+            Self::MaxAgeDays { days } => Some(now - i64::from(*days) * DAY_MS),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of a pruning pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    /// Their reactions are removed by the foreign key cascade
+    pub messages_deleted: u64,
+    /// Pages released for reuse; the file itself shrinks at the next VACUUM
+    pub reclaimed_bytes: u64,
+}
+
+impl Database {
+    pub fn get_retention_policy(&self) -> RetentionPolicy {
+        self.get_setting(RETENTION_POLICY_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_retention_policy(&mut self, policy: RetentionPolicy) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(&policy).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.set_setting(RETENTION_POLICY_KEY, &json)
+    }
+
+    /// Delete messages the policy no longer keeps
+    pub fn prune_messages(&mut self, policy: RetentionPolicy, now: i64) -> Result<PruneReport, DatabaseError> {
+        if policy == RetentionPolicy::KeepForever {
+            return Ok(PruneReport::default());
+        }
+
+        let free_before = self.free_bytes()?;
+        let messages_deleted = self.in_transaction(|db| {
+            let deleted = match policy {
+                RetentionPolicy::KeepForever => Ok(0),
+                RetentionPolicy::MaxAgeDays { .. } => db.conn.execute(
+                    "DELETE FROM messages WHERE is_starred = 0 AND timestamp < ?",
+                    [policy.cutoff(now)],
+                ),
+                RetentionPolicy::MaxPerThread { count } => db.conn.execute(
+                    r#"
+                    DELETE FROM messages WHERE id IN (
+                        SELECT id FROM (
+                            SELECT id, ROW_NUMBER() OVER (PARTITION BY thread_id ORDER BY timestamp DESC) AS position
+                            FROM messages WHERE is_starred = 0
+                        ) WHERE position > ?
+                    )
+                    "#,
+                    [count],
+                ),
+            }
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            Ok(deleted as u64)
+        })?;
+
+        Ok(PruneReport {
+            messages_deleted,
+            reclaimed_bytes: self.free_bytes()?.saturating_sub(free_before),
+        })
+    }
+}
+
+/// Apply the retention policy periodically in the background
+pub fn start_retention_pruner(database: DatabaseHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut delay = STARTUP_DELAY;
+        loop {
+            tokio::time::sleep(delay).await;
+            delay = PRUNE_INTERVAL;
+
+            let result = database
+                .call(|db| {
+                    let policy = db.get_retention_policy();
+                    db.prune_messages(policy, chrono::Utc::now().timestamp_millis())
+                })
+                .await;
+            match result {
+                Ok(report) if report.messages_deleted > 0 => tracing::info!(
+                    "Pruned {} messages, reclaimed {} bytes",
+                    report.messages_deleted,
+                    report.reclaimed_bytes
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Message pruning failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SyncedMessage;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_policy_json() {
+        let policy: RetentionPolicy = serde_json::from_str(r#"{"mode":"max_age_days","days":30}"#).unwrap();
+        assert_eq!(policy, RetentionPolicy::MaxAgeDays { days: 30 });
+        assert!(RetentionPolicy::MaxPerThread { count: 0 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_prune_messages() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+
+        let now = 1_700_000_000_000;
+        let batch: Vec<_> = (0..10)
+            .map(|i| SyncedMessage {
+                id: i.to_string(),
+                conversation_with: "b".repeat(64),
+                text: "hi".to_string(),
+                timestamp: now - i * DAY_MS,
+                from_handle: None,
+                is_outgoing: false,
+            })
+            .collect();
+        db.save_synced_messages(&batch, &"a".repeat(64)).unwrap();
+        db.save_reaction("9", &"b".repeat(64), "👍", now).unwrap();
+        db.conn.execute("UPDATE messages SET is_starred = 1 WHERE id = '8'", []).unwrap();
+
+        let report = db.prune_messages(RetentionPolicy::MaxAgeDays { days: 7 }, now).unwrap();
+        assert_eq!(report.messages_deleted, 1);
+        let reactions: i64 = db.conn.query_row("SELECT COUNT(*) FROM reactions", [], |row| row.get(0)).unwrap();
+        assert_eq!(reactions, 0);
+        assert!(db.get_message("8").unwrap().is_some());
+
+        let report = db.prune_messages(RetentionPolicy::MaxPerThread { count: 3 }, now).unwrap();
+        assert_eq!(report.messages_deleted, 5);
+        assert!(db.get_message("2").unwrap().is_some());
+        assert!(db.get_message("3").unwrap().is_none());
+    }
+}
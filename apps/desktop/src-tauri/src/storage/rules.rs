@@ -0,0 +1,122 @@
+//! Rules
+//!
+//! Automation rules, with their trigger and actions kept as JSON.
+
+use rusqlite::params;
+
+use super::{Database, DatabaseError};
+use crate::rules::Rule;
+
+impl Database {
+    pub(super) fn initialize_rule_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                trigger_json TEXT NOT NULL,
+                actions_json TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Insert a rule, or replace the one with its id
+    pub fn save_rule(&mut self, rule: &Rule) -> Result<(), DatabaseError> {
+        let trigger_json = serde_json::to_string(&rule.trigger).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        let actions_json = serde_json::to_string(&rule.actions).map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO rules (id, name, enabled, trigger_json, actions_json, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+                params![rule.id, rule.name, rule.enabled, trigger_json, actions_json, rule.created_at],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// All rules, oldest first, which is the order they run in
+    pub fn list_rules(&self) -> Result<Vec<Rule>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, name, enabled, trigger_json, actions_json, created_at FROM rules ORDER BY created_at ASC")
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            })
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        // A rule this version can't read is skipped rather than failing all
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, name, enabled, trigger_json, actions_json, created_at)| {
+                Some(Rule {
+                    id,
+                    name,
+                    enabled,
+                    trigger: serde_json::from_str(&trigger_json).ok()?,
+                    actions: serde_json::from_str(&actions_json).ok()?,
+                    created_at,
+                })
+            })
+            .collect())
+    }
+
+    pub fn delete_rule(&mut self, rule_id: &str) -> Result<bool, DatabaseError> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM rules WHERE id = ?", params![rule_id])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{RuleAction, RuleTrigger};
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_rules_roundtrip() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let mut rule = Rule {
+            id: "r1".to_string(),
+            name: "Invoices".to_string(),
+            enabled: true,
+            trigger: RuleTrigger { keyword: Some("invoice".to_string()), ..Default::default() },
+            actions: vec![RuleAction::MoveToLabel { label_id: "l1".to_string() }],
+            created_at: 1_000,
+        };
+        db.save_rule(&rule).unwrap();
+        rule.enabled = false;
+        db.save_rule(&rule).unwrap();
+
+        let rules = db.list_rules().unwrap();
+        assert_eq!(rules.len(), 1);
+        assert!(!rules[0].enabled);
+        assert_eq!(rules[0].trigger, rule.trigger);
+        assert_eq!(rules[0].actions, rule.actions);
+
+        assert!(db.delete_rule("r1").unwrap());
+        assert!(!db.delete_rule("r1").unwrap());
+    }
+}
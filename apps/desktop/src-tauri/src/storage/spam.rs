@@ -0,0 +1,184 @@
+//! Junk folder
+//!
+//! The junk flag on threads, and the user's standing verdict per sender.
+
+use rusqlite::params;
+
+use super::changes::{self, StorageChange};
+use super::{Database, DatabaseError};
+use crate::spam::SenderVerdict;
+
+impl Database {
+    pub(super) fn initialize_spam_tables(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .execute_batch(
+                r#"
+            CREATE TABLE IF NOT EXISTS sender_verdicts (
+                public_key TEXT PRIMARY KEY,
+                verdict TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+        "#,
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_sender_verdict(&self, public_key: &str) -> Option<SenderVerdict> {
+        self.conn
+            .query_row(
+                "SELECT verdict FROM sender_verdicts WHERE public_key = ?",
+                params![public_key],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+            .and_then(|verdict| SenderVerdict::parse(&verdict))
+    }
+
+    pub fn set_sender_verdict(&mut self, public_key: &str, verdict: SenderVerdict, now: i64) -> Result<(), DatabaseError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO sender_verdicts (public_key, verdict, updated_at) VALUES (?, ?, ?)",
+                params![public_key, verdict.as_str(), now],
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Have we written to this peer, or verified their key?
+    pub fn is_contact(&self, public_key: &str) -> bool {
+        self.conn
+            .query_row(
+                r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM messages m JOIN threads t ON t.id = m.thread_id
+                    WHERE t.participant_public_key = ?1 AND m.is_outgoing = 1
+                ) OR EXISTS (
+                    SELECT 1 FROM contact_keys WHERE public_key = ?1 AND verified_encryption_key IS NOT NULL
+                )
+                "#,
+                params![public_key],
+                |row| row.get(0),
+            )
+            .unwrap_or(false)
+    }
+
+    /// Everyone `is_contact` holds true for
+    pub fn contact_public_keys(&self) -> Vec<String> {
+        let Ok(mut stmt) = self.conn.prepare_cached(
+            r#"
+            SELECT DISTINCT t.participant_public_key FROM threads t JOIN messages m ON m.thread_id = t.id
+            WHERE m.is_outgoing = 1 AND t.participant_public_key IS NOT NULL
+            UNION
+            SELECT public_key FROM contact_keys WHERE verified_encryption_key IS NOT NULL
+            "#,
+        ) else {
+            return Vec::new();
+        };
+        stmt.query_map([], |row| row.get(0))
+            .map(|rows| rows.filter_map(Result::ok).collect())
+            .unwrap_or_default()
+    }
+
+    /// The thread's junk flag, or None if there is no thread with this
+    /// participant yet
+    pub fn get_thread_junk(&self, thread_id: &str, participant_public_key: &str) -> Option<bool> {
+        self.conn
+            .query_row(
+                "SELECT is_junk FROM threads WHERE id = ? AND participant_public_key = ? COLLATE NOCASE",
+                params![thread_id, participant_public_key],
+                |row| row.get::<_, i32>(0),
+            )
+            .ok()
+            .map(|junk| junk == 1)
+    }
+
+    /// Who the thread is with, or None if it doesn't exist yet
+    pub fn get_thread_participant(&self, thread_id: &str) -> Option<String> {
+        self.conn
+            .query_row(
+                "SELECT participant_public_key FROM threads WHERE id = ?",
+                params![thread_id],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    pub fn set_thread_junk(&mut self, thread_id: &str, junk: bool) -> Result<(), DatabaseError> {
+        self.conn
+            .execute("UPDATE threads SET is_junk = ? WHERE id = ?", params![junk, thread_id])
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        changes::record(StorageChange::ThreadUpdated { thread_id: thread_id.to_string() });
+        Ok(())
+    }
+
+    /// Record a verdict on a thread's sender and move all their threads
+    /// to match; returns the sender's key
+    pub fn judge_thread(&mut self, thread_id: &str, verdict: SenderVerdict, now: i64) -> Result<String, DatabaseError> {
+        let public_key: String = self
+            .conn
+            .query_row(
+                "SELECT participant_public_key FROM threads WHERE id = ?",
+                params![thread_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        self.in_transaction(|db| {
+            db.set_sender_verdict(&public_key, verdict, now)?;
+            db.conn
+                .execute(
+                    "UPDATE threads SET is_junk = ? WHERE participant_public_key = ?",
+                    params![verdict == SenderVerdict::Spam, public_key],
+                )
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            let mut stmt = db
+                .conn
+                .prepare("SELECT id FROM threads WHERE participant_public_key = ?")
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            let moved = stmt
+                .query_map(params![public_key], |row| row.get::<_, String>(0))
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            for thread_id in moved.flatten() {
+                changes::record(StorageChange::ThreadUpdated { thread_id });
+            }
+            Ok(())
+        })?;
+        Ok(public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_junk_threads() {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        let peer = "b".repeat(64);
+        let payload = serde_json::json!({ "text": "hi" });
+
+        db.save_received_message("m1", "t1", &peer, None, "text/plain", &payload, 1, true, None)
+            .unwrap();
+        assert_eq!(db.get_thread_junk("t1", &peer), Some(false));
+        assert_eq!(db.get_thread_junk("t2", &peer), None);
+        // Another sender naming the thread doesn't inherit its folder
+        assert_eq!(db.get_thread_junk("t1", &"c".repeat(64)), None);
+        assert_eq!(db.get_thread_participant("t1"), Some(peer.clone()));
+        assert!(!db.is_contact(&peer));
+        assert!(db.contact_public_keys().is_empty());
+
+        db.set_thread_junk("t1", true).unwrap();
+        assert!(db.get_threads(false, Some(false), 10).unwrap().is_empty());
+        assert_eq!(db.get_threads(false, Some(true), 10).unwrap().len(), 1);
+
+        assert_eq!(db.judge_thread("t1", SenderVerdict::Accepted, 2).unwrap(), peer);
+        assert_eq!(db.get_sender_verdict(&peer), Some(SenderVerdict::Accepted));
+        assert_eq!(db.get_thread_junk("t1", &peer), Some(false));
+        assert!(db.judge_thread("missing", SenderVerdict::Spam, 3).is_err());
+    }
+}
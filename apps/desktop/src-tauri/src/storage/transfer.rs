@@ -0,0 +1,268 @@
+//! Data transfer
+//!
+//! Dump and merge user data for device migration. Rows travel as JSON
+//! objects keyed by column name, so a backup from an older or newer schema
+//! still imports: unknown columns are dropped and missing ones take their
+//! defaults. Merging never overwrites local rows, so importing the same
+//! backup twice is a no-op. Settings that belong to this device (the app
+//! lock and its PIN hashes, servers and listeners, push tokens) never
+//! leave it and are never taken from a backup.
+
+use std::collections::BTreeMap;
+
+use rusqlite::types::Value;
+
+use super::{Database, DatabaseError};
+
+/// A table carried in an app data backup
+struct TransferTable {
+    name: &'static str,
+    /// Surrogate keys that must not be copied between databases
+    skip: &'static [&'static str],
+    /// Columns identifying a duplicate when the table has no natural key
+    dedupe: &'static [&'static str],
+    /// Rows whose `key` is one of these stay on the device; entries ending
+    /// in `:` match a prefix
+    local_keys: &'static [&'static str],
+}
+
+/// Settings that only make sense on the device that set them
+const DEVICE_LOCAL_SETTINGS: &[&str] = &[
+    "app_lock",
+    "local_api",
+    "lan_delivery",
+    "remote_wipe_enabled",
+    "push_registration",
+    "quick_compose_shortcut",
+    "last_sync",
+    "stellar_signer:",
+];
+
+impl TransferTable {
+    fn is_local(&self, row: &TransferRow) -> bool {
+        let Some(key) = row.get("key").and_then(|k| k.as_str()) else {
+            return false;
+        };
+        self.local_keys
+            .iter()
+            .any(|local| if local.ends_with(':') { key.starts_with(local) } else { key == *local })
+    }
+}
+
+/// User data worth migrating. Caches that refill from the network are left out.
+const TRANSFER_TABLES: &[TransferTable] = &[
+    TransferTable { name: "threads", skip: &[], dedupe: &[], local_keys: &[] },
+    TransferTable { name: "messages", skip: &[], dedupe: &[], local_keys: &[] },
+    TransferTable {
+        name: "reactions",
+        skip: &["id"],
+        dedupe: &["message_id", "from_public_key", "emoji", "timestamp"],
+        local_keys: &[],
+    },
+    TransferTable { name: "breadcrumbs", skip: &["id"], dedupe: &[], local_keys: &[] },
+    TransferTable { name: "sync_state", skip: &[], dedupe: &[], local_keys: DEVICE_LOCAL_SETTINGS },
+    TransferTable { name: "dix_following", skip: &[], dedupe: &[], local_keys: &[] },
+    TransferTable { name: "dix_bookmarks", skip: &[], dedupe: &[], local_keys: &[] },
+    TransferTable { name: "dix_tombstones", skip: &[], dedupe: &[], local_keys: &[] },
+    TransferTable { name: "reports", skip: &[], dedupe: &[], local_keys: &[] },
+    TransferTable { name: "claim_workflow", skip: &[], dedupe: &[], local_keys: &[] },
+    TransferTable { name: "contact_keys", skip: &[], dedupe: &[], local_keys: &[] },
+];
+
+pub type TransferRow = serde_json::Map<String, serde_json::Value>;
+
+impl Database {
+    /// Dump all transferable tables
+    pub fn export_tables(&self) -> Result<BTreeMap<String, Vec<TransferRow>>, DatabaseError> {
+        let mut tables = BTreeMap::new();
+
+        for table in TRANSFER_TABLES {
+            let columns: Vec<String> = self
+                .table_columns(table.name)?
+                .into_iter()
+                .filter(|c| !table.skip.contains(&c.as_str()))
+                .collect();
+            if columns.is_empty() {
+                continue;
+            }
+
+            let sql = format!("SELECT {} FROM {}", columns.join(", "), table.name);
+            let mut stmt = self
+                .conn
+                .prepare(&sql)
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+            let rows = stmt
+                .query_map([], |row| {
+                    let mut map = TransferRow::new();
+                    for (i, column) in columns.iter().enumerate() {
+                        map.insert(column.clone(), sql_to_json(row.get::<_, Value>(i)?));
+                    }
+                    Ok(map)
+                })
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            let rows = rows.into_iter().filter(|row| !table.is_local(row)).collect();
+
+            tables.insert(table.name.to_string(), rows);
+        }
+
+        Ok(tables)
+    }
+
+    /// Merge dumped tables, keeping local rows on conflict.
+    /// Returns the number of rows added per table.
+    pub fn import_tables(
+        &mut self,
+        tables: &BTreeMap<String, Vec<TransferRow>>,
+    ) -> Result<BTreeMap<String, usize>, DatabaseError> {
+        let mut added = BTreeMap::new();
+
+        let known_columns: Vec<(&TransferTable, Vec<String>)> = TRANSFER_TABLES
+            .iter()
+            .map(|t| self.table_columns(t.name).map(|c| (t, c)))
+            .collect::<Result<_, _>>()?;
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        // Parents first, so threads exist before their messages
+        for (table, local_columns) in known_columns {
+            let Some(rows) = tables.get(table.name) else {
+                continue;
+            };
+
+            let mut count = 0;
+            for row in rows.iter().filter(|row| !table.is_local(row)) {
+                // Only columns both sides know about; names come from PRAGMA, not the file
+                let columns: Vec<&String> = local_columns
+                    .iter()
+                    .filter(|c| !table.skip.contains(&c.as_str()) && row.contains_key(*c))
+                    .collect();
+                if columns.is_empty() {
+                    continue;
+                }
+
+                let mut values: Vec<Value> = columns.iter().map(|c| json_to_sql(&row[*c])).collect();
+                let placeholders = vec!["?"; columns.len()].join(", ");
+                let column_list = columns.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", ");
+
+                let sql = if table.dedupe.is_empty() {
+                    format!("INSERT OR IGNORE INTO {} ({}) VALUES ({})", table.name, column_list, placeholders)
+                } else {
+                    let condition = table
+                        .dedupe
+                        .iter()
+                        .map(|c| format!("{} IS ?", c))
+                        .collect::<Vec<_>>()
+                        .join(" AND ");
+                    values.extend(
+                        table
+                            .dedupe
+                            .iter()
+                            .map(|c| row.get(*c).map(json_to_sql).unwrap_or(Value::Null)),
+                    );
+                    format!(
+                        "INSERT INTO {0} ({1}) SELECT {2} WHERE NOT EXISTS (SELECT 1 FROM {0} WHERE {3})",
+                        table.name, column_list, placeholders, condition
+                    )
+                };
+
+                count += tx
+                    .execute(&sql, rusqlite::params_from_iter(values))
+                    .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+            }
+
+            added.insert(table.name.to_string(), count);
+        }
+
+        // Imported messages may be newer than what the thread list shows
+        tx.execute(
+            r#"
+            UPDATE threads SET last_message_at = MAX(last_message_at, COALESCE(
+                (SELECT MAX(timestamp) FROM messages WHERE messages.thread_id = threads.id), 0))
+            "#,
+            [],
+        )
+        .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        tx.commit().map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+        Ok(added)
+    }
+
+    fn table_columns(&self, table: &str) -> Result<Vec<String>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?;
+
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| DatabaseError::SqliteError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(columns)
+    }
+}
+
+fn sql_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => i.into(),
+        Value::Real(f) => f.into(),
+        Value::Text(s) => s.into(),
+        Value::Blob(b) => hex::encode(b).into(),
+    }
+}
+
+fn json_to_sql(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn database() -> Database {
+        let mut db = Database {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate().unwrap();
+        db
+    }
+
+    #[test]
+    fn test_device_settings_stay_local() {
+        let mut db = database();
+        db.set_setting("app_lock", r#"{"pin_hash":"$argon2id$x"}"#).unwrap();
+        db.set_setting("stellar_signer:abc", "{}").unwrap();
+        db.set_setting("privacy_zones", "[]").unwrap();
+
+        let tables = db.export_tables().unwrap();
+        let keys: Vec<&str> = tables["sync_state"].iter().filter_map(|row| row["key"].as_str()).collect();
+        assert_eq!(keys, vec!["privacy_zones"]);
+
+        // A backup that carries them anyway doesn't apply them
+        let mut tables = BTreeMap::new();
+        let row = |key: &str| serde_json::json!({ "key": key, "value": "x" }).as_object().unwrap().clone();
+        tables.insert("sync_state".to_string(), vec![row("app_lock"), row("local_api"), row("link_previews")]);
+        let mut fresh = database();
+        assert_eq!(fresh.import_tables(&tables).unwrap()["sync_state"], 1);
+        assert_eq!(fresh.get_setting("app_lock"), None);
+        assert_eq!(fresh.get_setting("link_previews").as_deref(), Some("x"));
+    }
+}
@@ -0,0 +1,176 @@
+//! System Tray - Desktop status icon and quick actions
+//!
+//! Shows relay connection state and the unread count, and offers quick
+//! actions. The menu is refreshed whenever the message handler emits
+//! `new_message`, and periodically to pick up connection changes and
+//! threads read elsewhere.
+
+use std::time::Duration;
+
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+
+use crate::AppState;
+
+const TRAY_ID: &str = "main";
+
+/// How often the tray re-reads connection state
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Menu items whose text changes
+struct TrayItems {
+    status: MenuItem<Wry>,
+    unread: MenuItem<Wry>,
+    upload: MenuItem<Wry>,
+}
+
+/// Tooltip and status lines for the current state
+pub fn status_text(connected: bool, unread: u32) -> (String, String, String) {
+    let status = if connected { "Connected" } else { "Offline" };
+    let unread_line = match unread {
+        0 => "No unread messages".to_string(),
+        1 => "1 unread message".to_string(),
+        n => format!("{} unread messages", n),
+    };
+    let tooltip = if unread > 0 {
+        format!("GNS Browser — {} ({} unread)", status, unread)
+    } else {
+        format!("GNS Browser — {}", status)
+    };
+    (status.to_string(), unread_line, tooltip)
+}
+
+fn upload_text(paused: bool) -> &'static str {
+    if paused {
+        "Resume breadcrumb upload"
+    } else {
+        "Pause breadcrumb upload"
+    }
+}
+
+/// Create the tray icon; call after the app state is managed
+pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
+    let paused = app.state::<AppState>().breadcrumb_sync.is_paused();
+
+    let status = MenuItem::with_id(app, "status", "Connecting…", false, None::<&str>)?;
+    let unread = MenuItem::with_id(app, "unread", "No unread messages", false, None::<&str>)?;
+    let new_message = MenuItem::with_id(app, "new_message", "New message", true, None::<&str>)?;
+    let upload = MenuItem::with_id(app, "toggle_upload", upload_text(paused), true, None::<&str>)?;
+    let reconnect = MenuItem::with_id(app, "reconnect", "Reconnect relay", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &unread,
+            &PredefinedMenuItem::separator(app)?,
+            &new_message,
+            &upload,
+            &reconnect,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("GNS Browser")
+        .menu(&menu)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    app.manage(TrayItems { status, unread, upload });
+
+    let handle = app.clone();
+    app.listen_any("new_message", move |_| {
+        let handle = handle.clone();
+        tauri::async_runtime::spawn(async move { refresh(&handle).await });
+    });
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            refresh(&handle).await;
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Update the status lines and tooltip
+pub async fn refresh(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let connected = state.relay.lock().await.is_connected().await;
+    let unread = state.database.call(|db| db.get_total_unread()).await.unwrap_or(0);
+    let paused = state.breadcrumb_sync.is_paused();
+
+    let (status, unread_line, tooltip) = status_text(connected, unread);
+    if let Some(items) = app.try_state::<TrayItems>() {
+        let _ = items.status.set_text(status);
+        let _ = items.unread.set_text(unread_line);
+        let _ = items.upload.set_text(upload_text(paused));
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "new_message" => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+            let _ = app.emit("compose_new_message", ());
+        }
+        "toggle_upload" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                let paused = !state.breadcrumb_sync.is_paused();
+                if let Err(e) = state.database.call(move |db| db.set_breadcrumb_upload_paused(paused)).await {
+                    tracing::error!("Failed to save breadcrumb upload setting: {}", e);
+                }
+                state.breadcrumb_sync.set_paused(paused);
+                refresh(&app).await;
+            });
+        }
+        "reconnect" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                let _ = state.relay.lock().await.disconnect().await;
+                state.relay_keepalive.wake();
+            });
+        }
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_text() {
+        let (status, unread, tooltip) = status_text(true, 0);
+        assert_eq!(status, "Connected");
+        assert_eq!(unread, "No unread messages");
+        assert_eq!(tooltip, "GNS Browser — Connected");
+
+        let (status, unread, tooltip) = status_text(false, 3);
+        assert_eq!(status, "Offline");
+        assert_eq!(unread, "3 unread messages");
+        assert_eq!(tooltip, "GNS Browser — Offline (3 unread)");
+
+        assert_eq!(status_text(true, 1).1, "1 unread message");
+    }
+}
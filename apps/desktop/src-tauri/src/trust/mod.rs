@@ -0,0 +1,177 @@
+//! Trust Module - Proof-of-Trajectory trust score
+//!
+//! Scores a breadcrumb history from 0 to 100. Four components, each capped:
+//! volume (how many breadcrumbs), diversity (unique cells), longevity (time
+//! span) and consistency (regular activity without impossible jumps).
+//! The same score is shown to the user and sent with handle claims;
+//! `trajectory` explains it.
+
+pub mod trajectory;
+
+use gns_crypto_core::breadcrumb::h3_to_lat_lng;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Component weights, summing to 100
+const VOLUME_WEIGHT: f64 = 30.0;
+const DIVERSITY_WEIGHT: f64 = 25.0;
+const LONGEVITY_WEIGHT: f64 = 25.0;
+const CONSISTENCY_WEIGHT: f64 = 20.0;
+
+/// Where each component saturates
+const VOLUME_TARGET: f64 = 200.0;
+const DIVERSITY_TARGET: f64 = 20.0;
+const LONGEVITY_TARGET_DAYS: f64 = 14.0;
+
+/// Faster than this between consecutive breadcrumbs is treated as a teleport
+const MAX_PLAUSIBLE_SPEED_KMH: f64 = 1000.0;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrustScore {
+    /// Overall score (0-100)
+    pub score: f64,
+
+    pub breadcrumb_count: u32,
+    pub unique_cells: u32,
+    pub span_days: f64,
+    pub active_days: u32,
+
+    /// Consecutive breadcrumbs implying impossible travel speed
+    pub implausible_jumps: u32,
+
+    pub breakdown: TrustBreakdown,
+}
+
+/// Points earned per component
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrustBreakdown {
+    pub volume: f64,
+    pub diversity: f64,
+    pub longevity: f64,
+    pub consistency: f64,
+}
+
+/// Compute the trust score from (h3_index, timestamp) pairs, timestamps in seconds
+pub fn compute_trust_score(breadcrumbs: &[(String, i64)]) -> TrustScore {
+    if breadcrumbs.is_empty() {
+        return TrustScore::default();
+    }
+
+    let mut sorted: Vec<&(String, i64)> = breadcrumbs.iter().collect();
+    sorted.sort_by_key(|(_, t)| *t);
+
+    let count = sorted.len() as u32;
+    let unique_cells = sorted.iter().map(|(h3, _)| h3.as_str()).collect::<HashSet<_>>().len() as u32;
+
+    let first = sorted.first().map(|(_, t)| *t).unwrap_or_default();
+    let last = sorted.last().map(|(_, t)| *t).unwrap_or_default();
+    let span_days = (last - first).max(0) as f64 / SECONDS_PER_DAY as f64;
+
+    let active_days = sorted
+        .iter()
+        .map(|(_, t)| t.div_euclid(SECONDS_PER_DAY))
+        .collect::<HashSet<_>>()
+        .len() as u32;
+
+    let implausible_jumps = sorted
+        .windows(2)
+        .filter(|pair| is_implausible_jump(pair[0], pair[1]))
+        .count() as u32;
+
+    // Share of calendar days in the span with activity, discounted by teleports
+    let calendar_days = (last.div_euclid(SECONDS_PER_DAY) - first.div_euclid(SECONDS_PER_DAY) + 1) as f64;
+    let regularity = active_days as f64 / calendar_days;
+    let plausibility = if count > 1 {
+        1.0 - implausible_jumps as f64 / (count - 1) as f64
+    } else {
+        1.0
+    };
+
+    let breakdown = TrustBreakdown {
+        volume: VOLUME_WEIGHT * (count as f64 / VOLUME_TARGET).min(1.0),
+        diversity: DIVERSITY_WEIGHT * (unique_cells as f64 / DIVERSITY_TARGET).min(1.0),
+        longevity: LONGEVITY_WEIGHT * (span_days / LONGEVITY_TARGET_DAYS).min(1.0),
+        // A single day of data says nothing about consistency yet
+        consistency: if calendar_days > 1.0 {
+            CONSISTENCY_WEIGHT * regularity * plausibility
+        } else {
+            0.0
+        },
+    };
+
+    let score = breakdown.volume + breakdown.diversity + breakdown.longevity + breakdown.consistency;
+
+    TrustScore {
+        score: (score * 10.0).round() / 10.0,
+        breadcrumb_count: count,
+        unique_cells,
+        span_days: (span_days * 10.0).round() / 10.0,
+        active_days,
+        implausible_jumps,
+        breakdown,
+    }
+}
+
+fn is_implausible_jump(a: &(String, i64), b: &(String, i64)) -> bool {
+    let (Ok(from), Ok(to)) = (h3_to_lat_lng(&a.0), h3_to_lat_lng(&b.0)) else {
+        return false;
+    };
+    let hours = (b.1 - a.1).max(1) as f64 / 3600.0;
+    haversine_km(from, to) / hours > MAX_PLAUSIBLE_SPEED_KMH
+}
+
+fn haversine_km((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    let dlat = (lat2 - lat1).to_radians();
+    let dlng = (lng2 - lng1).to_radians();
+    let a = (dlat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (dlng / 2.0).sin().powi(2);
+    6371.0 * 2.0 * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::breadcrumb::lat_lng_to_h3;
+
+    fn cell(lat: f64, lng: f64) -> String {
+        lat_lng_to_h3(lat, lng, 7).unwrap()
+    }
+
+    #[test]
+    fn test_trust_score_grows_with_history() {
+        assert_eq!(compute_trust_score(&[]).score, 0.0);
+
+        // Two weeks, a few breadcrumbs a day across nearby cells
+        let start = 1_704_067_200;
+        let history: Vec<(String, i64)> = (0..14 * 8)
+            .map(|i| (cell(52.52 + (i % 25) as f64 * 0.002, 13.405), start + i * 3 * 3600))
+            .collect();
+        let score = compute_trust_score(&history);
+        assert_eq!(score.implausible_jumps, 0);
+        assert!(score.score > 80.0, "score was {}", score.score);
+
+        let week: Vec<(String, i64)> = history.iter().take(56).cloned().collect();
+        assert!(compute_trust_score(&week).score < score.score);
+    }
+
+    #[test]
+    fn test_teleports_reduce_consistency() {
+        let start = 1_704_067_200;
+        let honest: Vec<(String, i64)> = (0..48)
+            .map(|i| (cell(52.52, 13.405 + (i % 2) as f64 * 0.01), start + i * 3600))
+            .collect();
+        let teleporting: Vec<(String, i64)> = (0..48)
+            .map(|i| {
+                let (lat, lng) = if i % 2 == 0 { (52.52, 13.405) } else { (40.71, -74.0) };
+                (cell(lat, lng), start + i * 3600)
+            })
+            .collect();
+
+        let honest = compute_trust_score(&honest);
+        let teleporting = compute_trust_score(&teleporting);
+        assert_eq!(teleporting.implausible_jumps, 47);
+        assert!(teleporting.breakdown.consistency < honest.breakdown.consistency);
+    }
+}
@@ -0,0 +1,240 @@
+//! Trajectory Stats - Where the trust score comes from
+//!
+//! Walks the same (h3_index, timestamp) history the score is computed
+//! from and lays out what it saw: distance moved each day, jumps too fast
+//! to be real travel, and stretches with no collection. Jumps and gaps
+//! are what pull the consistency component down, so they're flagged with
+//! a short explanation.
+
+use super::{haversine_km, MAX_PLAUSIBLE_SPEED_KMH, SECONDS_PER_DAY};
+use gns_crypto_core::breadcrumb::h3_to_lat_lng;
+use serde::Serialize;
+
+/// No breadcrumb for longer than this counts as a collection gap
+const GAP_THRESHOLD_SECONDS: i64 = SECONDS_PER_DAY;
+
+/// Distance moved on one UTC day
+#[derive(Debug, Clone, Serialize)]
+pub struct DayDistance {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub km: f64,
+    pub breadcrumbs: u32,
+}
+
+/// Consecutive breadcrumbs implying impossible travel speed
+#[derive(Debug, Clone, Serialize)]
+pub struct ImplausibleJump {
+    pub from_cell: String,
+    pub to_cell: String,
+    pub from_timestamp: i64,
+    pub to_timestamp: i64,
+    pub km: f64,
+    pub speed_kmh: f64,
+}
+
+/// A stretch without breadcrumbs
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectionGap {
+    pub from_timestamp: i64,
+    /// The next breadcrumb, or now if collection hasn't resumed
+    pub to_timestamp: i64,
+    pub hours: f64,
+    pub ongoing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrajectoryFlagKind {
+    ImplausibleJumps,
+    CollectionGaps,
+    CollectionStopped,
+}
+
+/// Something in the history that costs trust, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct TrajectoryFlag {
+    pub kind: TrajectoryFlagKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrajectoryStats {
+    pub breadcrumb_count: u32,
+    /// Plausible movement only; jumps aren't counted
+    pub total_km: f64,
+    /// Fastest plausible movement between breadcrumbs
+    pub top_speed_kmh: f64,
+    /// Days with breadcrumbs, oldest first
+    pub days: Vec<DayDistance>,
+    pub jumps: Vec<ImplausibleJump>,
+    pub gaps: Vec<CollectionGap>,
+    pub flags: Vec<TrajectoryFlag>,
+}
+
+/// Stats for (h3_index, timestamp) pairs, timestamps in seconds; `now` is
+/// used to spot collection that has stopped
+pub fn trajectory_stats(breadcrumbs: &[(String, i64)], now: i64) -> TrajectoryStats {
+    let mut sorted: Vec<&(String, i64)> = breadcrumbs.iter().collect();
+    sorted.sort_by_key(|(_, t)| *t);
+
+    let mut stats = TrajectoryStats {
+        breadcrumb_count: sorted.len() as u32,
+        ..Default::default()
+    };
+    let Some(first) = sorted.first() else {
+        return stats;
+    };
+    add_to_day(&mut stats.days, first.1, 0.0);
+
+    for pair in sorted.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let elapsed = b.1 - a.1;
+        if elapsed > GAP_THRESHOLD_SECONDS {
+            stats.gaps.push(gap(a.1, b.1, false));
+        }
+
+        // Cells that don't decode still count toward the day, with no distance
+        let km = match (h3_to_lat_lng(&a.0), h3_to_lat_lng(&b.0)) {
+            (Ok(from), Ok(to)) => haversine_km(from, to),
+            _ => 0.0,
+        };
+        let speed_kmh = km / (elapsed.max(1) as f64 / 3600.0);
+        if speed_kmh > MAX_PLAUSIBLE_SPEED_KMH {
+            stats.jumps.push(ImplausibleJump {
+                from_cell: a.0.clone(),
+                to_cell: b.0.clone(),
+                from_timestamp: a.1,
+                to_timestamp: b.1,
+                km: round1(km),
+                speed_kmh: round1(speed_kmh),
+            });
+            add_to_day(&mut stats.days, b.1, 0.0);
+        } else {
+            stats.total_km += km;
+            stats.top_speed_kmh = stats.top_speed_kmh.max(speed_kmh);
+            add_to_day(&mut stats.days, b.1, km);
+        }
+    }
+
+    let last = sorted.last().map(|(_, t)| *t).unwrap_or_default();
+    if now - last > GAP_THRESHOLD_SECONDS {
+        stats.gaps.push(gap(last, now, true));
+    }
+
+    stats.total_km = round1(stats.total_km);
+    stats.top_speed_kmh = round1(stats.top_speed_kmh);
+    for day in &mut stats.days {
+        day.km = round1(day.km);
+    }
+    stats.flags = flags(&stats);
+    stats
+}
+
+fn flags(stats: &TrajectoryStats) -> Vec<TrajectoryFlag> {
+    let mut flags = Vec::new();
+    if !stats.jumps.is_empty() {
+        flags.push(TrajectoryFlag {
+            kind: TrajectoryFlagKind::ImplausibleJumps,
+            message: format!(
+                "{} move(s) faster than {} km/h between breadcrumbs. These usually come from a spoofed or \
+                 mislocated position and lower the consistency score.",
+                stats.jumps.len(),
+                MAX_PLAUSIBLE_SPEED_KMH
+            ),
+        });
+    }
+    let past_gaps: Vec<&CollectionGap> = stats.gaps.iter().filter(|g| !g.ongoing).collect();
+    if !past_gaps.is_empty() {
+        let days = past_gaps.iter().map(|g| g.hours).sum::<f64>() / 24.0;
+        flags.push(TrajectoryFlag {
+            kind: TrajectoryFlagKind::CollectionGaps,
+            message: format!(
+                "{} gap(s) of more than a day without breadcrumbs, {:.1} days in total. Days without \
+                 activity lower the consistency score.",
+                past_gaps.len(),
+                days
+            ),
+        });
+    }
+    if let Some(gap) = stats.gaps.iter().find(|g| g.ongoing) {
+        flags.push(TrajectoryFlag {
+            kind: TrajectoryFlagKind::CollectionStopped,
+            message: format!(
+                "No breadcrumbs in the last {:.0} hours. Check that collection is enabled.",
+                gap.hours
+            ),
+        });
+    }
+    flags
+}
+
+fn add_to_day(days: &mut Vec<DayDistance>, timestamp: i64, km: f64) {
+    let day = chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.date_naive().to_string())
+        .unwrap_or_default();
+    match days.last_mut() {
+        Some(last) if last.day == day => {
+            last.km += km;
+            last.breadcrumbs += 1;
+        }
+        _ => days.push(DayDistance {
+            day,
+            km,
+            breadcrumbs: 1,
+        }),
+    }
+}
+
+fn gap(from: i64, to: i64, ongoing: bool) -> CollectionGap {
+    CollectionGap {
+        from_timestamp: from,
+        to_timestamp: to,
+        hours: round1((to - from) as f64 / 3600.0),
+        ongoing,
+    }
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trust::compute_trust_score;
+    use gns_crypto_core::breadcrumb::lat_lng_to_h3;
+
+    fn cell(lat: f64, lng: f64) -> String {
+        lat_lng_to_h3(lat, lng, 7).unwrap()
+    }
+
+    #[test]
+    fn test_trajectory_stats() {
+        let start = 1_704_067_200; // 2024-01-01 00:00 UTC
+        let mut history: Vec<(String, i64)> = (0..6)
+            .map(|i| (cell(52.52 + i as f64 * 0.02, 13.405), start + i * 3600))
+            .collect();
+        // New York for an hour, then nothing for two days
+        history.push((cell(40.71, -74.0), start + 6 * 3600));
+        history.push((cell(52.52, 13.405), start + 7 * 3600));
+        history.push((cell(52.52, 13.405), start + 2 * SECONDS_PER_DAY + 7 * 3600));
+
+        let stats = trajectory_stats(&history, start + 2 * SECONDS_PER_DAY + 8 * 3600);
+        assert_eq!(stats.breadcrumb_count, 9);
+        assert_eq!(stats.jumps.len(), compute_trust_score(&history).implausible_jumps as usize);
+        assert_eq!(stats.jumps.len(), 2);
+        assert!(stats.total_km > 8.0 && stats.total_km < 14.0, "total was {}", stats.total_km);
+        let days: Vec<_> = stats.days.iter().map(|d| (d.day.as_str(), d.breadcrumbs)).collect();
+        assert_eq!(days, vec![("2024-01-01", 8), ("2024-01-03", 1)]);
+
+        assert_eq!(stats.gaps.len(), 1);
+        assert_eq!(stats.gaps[0].hours, 48.0);
+        let kinds: Vec<_> = stats.flags.iter().map(|f| f.kind).collect();
+        assert_eq!(kinds, vec![TrajectoryFlagKind::ImplausibleJumps, TrajectoryFlagKind::CollectionGaps]);
+
+        let stopped = trajectory_stats(&history, start + 5 * SECONDS_PER_DAY);
+        assert!(stopped.gaps.last().unwrap().ongoing);
+        assert!(trajectory_stats(&[], start).flags.is_empty());
+    }
+}
@@ -0,0 +1,370 @@
+//! Validation - Size limits and per-type payload checks
+//!
+//! Every payload is checked before it is sent and after an incoming one is
+//! decrypted: it must be within the size limit, of a known payload type,
+//! and match that type's shape. Malformed envelopes are rejected with a
+//! `ValidationError` instead of failing somewhere deep in serde or the UI.
+
+use gns_crypto_core::encryption::PayloadWrapper;
+use gns_crypto_core::GnsEnvelope;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Largest encrypted payload accepted from the relay, checked before
+/// spending time on decryption
+pub const MAX_WIRE_PAYLOAD_BYTES: usize = 2 * 1024 * 1024;
+
+/// Largest decrypted payload; attachments travel by URL, so this is text
+pub const MAX_PAYLOAD_BYTES: usize = 1024 * 1024;
+
+/// Longest chat message text
+pub const MAX_TEXT_BYTES: usize = 64 * 1024;
+
+/// Longest URL in a link preview
+pub const MAX_URL_BYTES: usize = 2048;
+
+/// Longest title or description in a link preview
+pub const MAX_PREVIEW_TEXT_BYTES: usize = 1024;
+
+/// Longest reaction, enough for any emoji sequence
+pub const MAX_REACTION_BYTES: usize = 64;
+
+/// Known payload types and the shape their payload must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadKind {
+    Chat,
+    Email,
+    Payment,
+    Reaction,
+    RemoteWipe,
+    ContactRequest,
+    Poll,
+    PollVote,
+    Event,
+    EventRsvp,
+    Invoice,
+    InvoicePaid,
+    DixRef,
+    Location,
+    Proximity,
+}
+
+impl PayloadKind {
+    pub fn from_type(payload_type: &str) -> Option<Self> {
+        match payload_type {
+            "text/plain" | "gns/text.plain" => Some(Self::Chat),
+            "email" | "gns/email" => Some(Self::Email),
+            "payment" | "gns/payment" => Some(Self::Payment),
+            "reaction" | "gns/reaction" => Some(Self::Reaction),
+            crate::wipe::REMOTE_WIPE_PAYLOAD_TYPE => Some(Self::RemoteWipe),
+            crate::contact_requests::CONTACT_REQUEST_PAYLOAD_TYPE => Some(Self::ContactRequest),
+            crate::polls::POLL_PAYLOAD_TYPE => Some(Self::Poll),
+            crate::polls::POLL_VOTE_PAYLOAD_TYPE => Some(Self::PollVote),
+            crate::calendar::EVENT_PAYLOAD_TYPE => Some(Self::Event),
+            crate::calendar::EVENT_RSVP_PAYLOAD_TYPE => Some(Self::EventRsvp),
+            crate::invoices::INVOICE_PAYLOAD_TYPE => Some(Self::Invoice),
+            crate::invoices::INVOICE_PAID_PAYLOAD_TYPE => Some(Self::InvoicePaid),
+            crate::dix::share::DIX_REF_PAYLOAD_TYPE => Some(Self::DixRef),
+            crate::location::share::LOCATION_PAYLOAD_TYPE => Some(Self::Location),
+            crate::proximity::PROXIMITY_PAYLOAD_TYPE => Some(Self::Proximity),
+            _ => None,
+        }
+    }
+}
+
+/// Why a payload was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ValidationError {
+    #[error("Payload is {size} bytes, over the {max} byte limit")]
+    TooLarge { size: usize, max: usize },
+
+    #[error("Unsupported payload type '{payload_type}'")]
+    UnknownType { payload_type: String },
+
+    #[error("Payload is not valid JSON: {reason}")]
+    Malformed { reason: String },
+
+    #[error("Payload is missing '{field}'")]
+    MissingField { field: String },
+
+    #[error("Payload field '{field}' {reason}")]
+    InvalidField { field: String, reason: String },
+}
+
+/// Reject envelopes too big to be worth decrypting
+pub fn check_envelope_size(envelope: &GnsEnvelope) -> Result<(), ValidationError> {
+    let size = match &envelope.encrypted_payload {
+        PayloadWrapper::Object(payload) => payload.ciphertext.len(),
+        PayloadWrapper::String(payload) => payload.len(),
+    };
+    if size > MAX_WIRE_PAYLOAD_BYTES {
+        return Err(ValidationError::TooLarge { size, max: MAX_WIRE_PAYLOAD_BYTES });
+    }
+    Ok(())
+}
+
+/// Check a decrypted payload and parse it
+///
+/// Chat payloads that aren't JSON are accepted as plain text, as senders
+/// of `text/plain` may send the raw string.
+pub fn validate_payload(payload_type: &str, bytes: &[u8]) -> Result<Value, ValidationError> {
+    if bytes.len() > MAX_PAYLOAD_BYTES {
+        return Err(ValidationError::TooLarge { size: bytes.len(), max: MAX_PAYLOAD_BYTES });
+    }
+    let kind = PayloadKind::from_type(payload_type).ok_or_else(|| ValidationError::UnknownType {
+        payload_type: payload_type.to_string(),
+    })?;
+
+    let payload = match serde_json::from_slice::<Value>(bytes) {
+        Ok(payload) => payload,
+        Err(_) if kind == PayloadKind::Chat => match std::str::from_utf8(bytes) {
+            Ok(text) => serde_json::json!({ "text": text }),
+            Err(e) => return Err(ValidationError::Malformed { reason: e.to_string() }),
+        },
+        Err(e) => return Err(ValidationError::Malformed { reason: e.to_string() }),
+    };
+    let fields = payload.as_object().ok_or_else(|| ValidationError::Malformed {
+        reason: "expected a JSON object".to_string(),
+    })?;
+
+    match kind {
+        PayloadKind::Chat => {
+            if fields.contains_key("attachments") {
+                expect_array(fields, "attachments")?;
+                optional_string(fields, "text", MAX_TEXT_BYTES)?;
+            } else {
+                required_string(fields, "text", MAX_TEXT_BYTES)?;
+            }
+            if let Some(preview) = fields.get("link_preview") {
+                let preview = preview
+                    .as_object()
+                    .ok_or_else(|| invalid("link_preview", "must be an object"))?;
+                required_string(preview, "url", MAX_URL_BYTES)?;
+                for field in ["title", "description", "site_name"] {
+                    optional_string(preview, field, MAX_PREVIEW_TEXT_BYTES)?;
+                }
+                optional_string(preview, "image_url", MAX_URL_BYTES)?;
+            }
+            if let Some(consent) = fields.get(crate::dix::share::QUOTE_CONSENT_FIELD) {
+                serde_json::from_value::<crate::dix::share::QuoteConsent>(consent.clone())
+                    .map_err(|_| invalid(crate::dix::share::QUOTE_CONSENT_FIELD, "must be a signed quote consent"))?;
+            }
+            if let Some(proof) = fields.get(crate::proximity::PROXIMITY_FIELD) {
+                let proof: gns_crypto_core::ProximityAttestation = serde_json::from_value(proof.clone())
+                    .map_err(|_| invalid(crate::proximity::PROXIMITY_FIELD, "must be a proximity attestation"))?;
+                gns_crypto_core::verify_attestation(&proof)
+                    .map_err(|e| invalid(crate::proximity::PROXIMITY_FIELD, &e.to_string()))?;
+            }
+        }
+        PayloadKind::Email => {
+            for field in ["subject", "snippet", "from"] {
+                optional_string(fields, field, MAX_TEXT_BYTES)?;
+            }
+            optional_string(fields, "body", MAX_PAYLOAD_BYTES)?;
+        }
+        PayloadKind::Payment => {
+            let valid_amount = match fields.get("amount") {
+                None => return Err(missing("amount")),
+                Some(Value::String(s)) => s.parse::<f64>().is_ok_and(|a| a > 0.0),
+                Some(Value::Number(n)) => n.as_f64().is_some_and(|a| a > 0.0),
+                Some(_) => false,
+            };
+            if !valid_amount {
+                return Err(invalid("amount", "must be a positive number"));
+            }
+            optional_string(fields, "asset", 64)?;
+            optional_string(fields, "memo", 256)?;
+        }
+        PayloadKind::Reaction => {
+            if required_string(fields, "target_message_id", 256)?.is_empty() {
+                return Err(invalid("target_message_id", "must not be empty"));
+            }
+            required_string(fields, "emoji", MAX_REACTION_BYTES)?;
+        }
+        PayloadKind::RemoteWipe => {}
+        PayloadKind::ContactRequest => {
+            optional_string(fields, "text", MAX_TEXT_BYTES)?;
+            if let Some(key) = optional_string(fields, "encryption_key", 64)? {
+                if key.len() != 64 || hex::decode(key).is_err() {
+                    return Err(invalid("encryption_key", "must be 32 bytes of hex"));
+                }
+            }
+        }
+        // Signatures and option counts are checked by `crate::polls`
+        PayloadKind::Poll => {
+            for field in ["id", "creator", "signature"] {
+                required_string(fields, field, 256)?;
+            }
+            required_string(fields, "question", MAX_TEXT_BYTES)?;
+            expect_array(fields, "options")?;
+        }
+        PayloadKind::PollVote => {
+            for field in ["poll_id", "voter", "signature"] {
+                required_string(fields, field, 256)?;
+            }
+            expect_array(fields, "choices")?;
+        }
+        PayloadKind::Event => {
+            let invite: crate::calendar::EventInvite = serde_json::from_value(payload.clone())
+                .map_err(|e| ValidationError::Malformed { reason: e.to_string() })?;
+            crate::calendar::check_invite(&invite).map_err(|reason| ValidationError::Malformed { reason })?;
+            optional_string(fields, "location", MAX_PREVIEW_TEXT_BYTES)?;
+            optional_string(fields, "description", MAX_TEXT_BYTES)?;
+        }
+        PayloadKind::EventRsvp => {
+            required_string(fields, "event_id", 256)?;
+            let status = required_string(fields, "status", 16)?;
+            if crate::calendar::RsvpStatus::parse(status).is_none() {
+                return Err(invalid("status", "must be accepted, tentative or declined"));
+            }
+            optional_string(fields, "comment", MAX_TEXT_BYTES)?;
+        }
+        PayloadKind::Invoice => {
+            let invoice: crate::invoices::Invoice = serde_json::from_value(payload.clone())
+                .map_err(|e| ValidationError::Malformed { reason: e.to_string() })?;
+            crate::invoices::check_invoice(&invoice).map_err(|reason| ValidationError::Malformed { reason })?;
+        }
+        PayloadKind::InvoicePaid => {
+            required_string(fields, "invoice_id", 256)?;
+            let tx_hash = required_string(fields, "tx_hash", 64)?;
+            if tx_hash.len() != 64 || hex::decode(tx_hash).is_err() {
+                return Err(invalid("tx_hash", "must be 32 bytes of hex"));
+            }
+        }
+        // Checked against the author's signature, as the server isn't asked
+        PayloadKind::DixRef => {
+            let dix_ref: crate::dix::share::DixRef = serde_json::from_value(payload.clone())
+                .map_err(|e| ValidationError::Malformed { reason: e.to_string() })?;
+            if !crate::dix::verify_post(&dix_ref.post) {
+                return Err(invalid("post", "signature doesn't verify"));
+            }
+            optional_string(fields, "comment", MAX_TEXT_BYTES)?;
+        }
+        PayloadKind::Location => {
+            required_string(fields, "share_id", 256)?;
+            let share: crate::location::share::LocationShare = serde_json::from_value(payload.clone())
+                .map_err(|e| ValidationError::Malformed { reason: e.to_string() })?;
+            crate::location::share::check_share(&share).map_err(|reason| ValidationError::Malformed { reason })?;
+        }
+        // The claim's signatures are checked by `crate::proximity`
+        PayloadKind::Proximity => {
+            let request: crate::proximity::ProximityRequest = serde_json::from_value(payload.clone())
+                .map_err(|e| ValidationError::Malformed { reason: e.to_string() })?;
+            if request.max_steps > crate::proximity::MAX_GRID_STEPS {
+                return Err(invalid("max_steps", &format!("must be at most {}", crate::proximity::MAX_GRID_STEPS)));
+            }
+        }
+    }
+
+    Ok(payload)
+}
+
+fn required_string<'a>(fields: &'a Map<String, Value>, field: &str, max: usize) -> Result<&'a str, ValidationError> {
+    optional_string(fields, field, max)?.ok_or_else(|| missing(field))
+}
+
+fn optional_string<'a>(
+    fields: &'a Map<String, Value>,
+    field: &str,
+    max: usize,
+) -> Result<Option<&'a str>, ValidationError> {
+    match fields.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if s.len() > max => Err(invalid(field, &format!("is over {} bytes", max))),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(invalid(field, "must be a string")),
+    }
+}
+
+fn expect_array(fields: &Map<String, Value>, field: &str) -> Result<(), ValidationError> {
+    match fields.get(field) {
+        Some(Value::Array(_)) => Ok(()),
+        _ => Err(invalid(field, "must be an array")),
+    }
+}
+
+fn missing(field: &str) -> ValidationError {
+    ValidationError::MissingField { field: field.to_string() }
+}
+
+fn invalid(field: &str, reason: &str) -> ValidationError {
+    ValidationError::InvalidField {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_payloads() {
+        assert!(validate_payload("text/plain", br#"{"text":"hi"}"#).is_ok());
+        assert_eq!(validate_payload("text/plain", b"hi").unwrap()["text"], "hi");
+        assert!(validate_payload("text/plain", br#"{"attachments":[]}"#).is_ok());
+        assert_eq!(validate_payload("text/plain", b"{}"), Err(missing("text")));
+        assert!(matches!(
+            validate_payload("text/plain", br#"{"text":5}"#),
+            Err(ValidationError::InvalidField { .. })
+        ));
+        assert!(matches!(validate_payload("text/plain", &[0xff, 0xfe]), Err(ValidationError::Malformed { .. })));
+
+        let preview = br#"{"text":"https://example.org","link_preview":{"url":"https://example.org","title":"Example"}}"#;
+        assert!(validate_payload("text/plain", preview).is_ok());
+        assert!(matches!(
+            validate_payload("text/plain", br#"{"text":"We met","proximity":{"claims":[]}}"#),
+            Err(ValidationError::InvalidField { .. })
+        ));
+        assert!(validate_payload("text/plain", br#"{"text":"x","link_preview":{"title":"no url"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_typed_payloads() {
+        assert!(validate_payload("gns/email", br#"{"subject":"Hi","body":"..."}"#).is_ok());
+        assert!(matches!(validate_payload("gns/email", b"not json"), Err(ValidationError::Malformed { .. })));
+
+        assert!(validate_payload("payment", br#"{"amount":"2.5","asset":"GNS"}"#).is_ok());
+        assert!(validate_payload("payment", br#"{"amount":-1}"#).is_err());
+
+        assert!(validate_payload("reaction", r#"{"target_message_id":"m1","emoji":"👍"}"#.as_bytes()).is_ok());
+        assert_eq!(validate_payload("reaction", br#"{"emoji":"x"}"#), Err(missing("target_message_id")));
+
+        assert!(validate_payload("gns/contact-request", br#"{"text":"Hi"}"#).is_ok());
+        assert!(validate_payload("gns/contact-request", br#"{"encryption_key":"zz"}"#).is_err());
+
+        let poll = br#"{"id":"p1","creator":"ab","question":"Lunch?","options":["A","B"],"created_at":1,"signature":"00"}"#;
+        assert!(validate_payload("gns/poll", poll).is_ok());
+        assert!(validate_payload("gns/poll", br#"{"id":"p1","creator":"ab","question":"?","signature":"00"}"#).is_err());
+        assert!(validate_payload("gns/poll.vote", br#"{"poll_id":"p1","voter":"ab","choices":[0],"signature":"00"}"#).is_ok());
+
+        let event = br#"{"id":"e1","organizer":"ab","title":"Standup","start":1000,"end":2000}"#;
+        assert!(validate_payload("gns/event", event).is_ok());
+        assert!(validate_payload("gns/event", br#"{"id":"e1","organizer":"ab","title":"x","start":2,"end":1}"#).is_err());
+        assert!(validate_payload("gns/event.rsvp", br#"{"event_id":"e1","status":"maybe"}"#).is_err());
+
+        let invoice = br#"{"id":"0123456789abcdef","issuer":"ab","amount":5,"asset":"GNS","expires_at":1000}"#;
+        assert!(validate_payload("gns/invoice", invoice).is_ok());
+        assert!(validate_payload("gns/invoice", br#"{"id":"i1","issuer":"ab","amount":0,"asset":"GNS","expires_at":1000}"#).is_err());
+        assert!(validate_payload("gns/invoice.paid", br#"{"invoice_id":"i1","tx_hash":"abc"}"#).is_err());
+
+        let location = br#"{"share_id":"s1","h3_cell":"7000dac400083dbc","resolution":7,"updated_at":1}"#;
+        assert!(validate_payload("gns/location", location).is_ok());
+        assert!(validate_payload("gns/location", br#"{"share_id":"s1","h3_cell":"nowhere","resolution":7,"updated_at":1}"#).is_err());
+        assert!(validate_payload("gns/location", br#"{"share_id":"s1","h3_cell":"7000dac400083dbc","resolution":12,"updated_at":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(
+            validate_payload("application/x-unknown", b"{}"),
+            Err(ValidationError::UnknownType { payload_type: "application/x-unknown".to_string() })
+        );
+        let big = vec![b' '; MAX_PAYLOAD_BYTES + 1];
+        assert!(matches!(validate_payload("text/plain", &big), Err(ValidationError::TooLarge { .. })));
+
+        let error = serde_json::to_value(missing("text")).unwrap();
+        assert_eq!(error["code"], "missing_field");
+    }
+}
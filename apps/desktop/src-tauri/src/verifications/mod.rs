@@ -0,0 +1,340 @@
+//! Verifications Module - Signed external proofs
+//!
+//! A user proves control of a website or social account by publishing a
+//! statement signed with their identity key somewhere only the account owner
+//! can write: `https://<domain>/.well-known/gns-proof.txt` for websites, a
+//! gist or public post for social accounts. The backend fetches and checks
+//! the proof itself; resolved identities list the proofs it accepted.
+
+use gns_crypto_core::signing::verify_signature_hex;
+use serde::{Deserialize, Serialize};
+
+const PROOF_HEADER: &str = "-----BEGIN GNS PROOF-----";
+const SIGNATURE_HEADER: &str = "-----SIGNATURE-----";
+const PROOF_FOOTER: &str = "-----END GNS PROOF-----";
+
+/// Statement format version
+const PROOF_VERSION: u32 = 1;
+
+/// Where a website publishes its proof
+pub const WELL_KNOWN_PATH: &str = "/.well-known/gns-proof.txt";
+
+/// Kind of external account being proven
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationKind {
+    Website,
+    Github,
+    Twitter,
+    Mastodon,
+}
+
+impl VerificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Website => "website",
+            Self::Github => "github",
+            Self::Twitter => "twitter",
+            Self::Mastodon => "mastodon",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "website" => Some(Self::Website),
+            "github" => Some(Self::Github),
+            "twitter" | "x" => Some(Self::Twitter),
+            "mastodon" => Some(Self::Mastodon),
+            _ => None,
+        }
+    }
+
+    /// Normalize a domain / account name, rejecting malformed ones
+    pub fn normalize_target(&self, target: &str) -> Result<String, String> {
+        let target = target.trim().to_lowercase();
+
+        match self {
+            Self::Website => {
+                let domain = target
+                    .trim_start_matches("https://")
+                    .trim_start_matches("http://")
+                    .split(['/', '?', '#'])
+                    .next()
+                    .unwrap_or_default()
+                    .trim_end_matches('.');
+                let valid = domain.contains('.')
+                    && domain.len() <= 253
+                    && domain
+                        .split('.')
+                        .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+                if valid {
+                    Ok(domain.to_string())
+                } else {
+                    Err(format!("Invalid domain: {}", target))
+                }
+            }
+            Self::Github | Self::Twitter => {
+                let name = target.trim_start_matches('@');
+                let valid = (1..=39).contains(&name.len())
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                if valid {
+                    Ok(name.to_string())
+                } else {
+                    Err(format!("Invalid {} username: {}", self.as_str(), target))
+                }
+            }
+            Self::Mastodon => {
+                let account = target.trim_start_matches('@');
+                match account.split_once('@') {
+                    Some((user, instance)) if !user.is_empty() => {
+                        let instance = Self::Website.normalize_target(instance)?;
+                        Ok(format!("{}@{}", user, instance))
+                    }
+                    _ => Err(format!("Mastodon account must look like user@instance: {}", target)),
+                }
+            }
+        }
+    }
+
+    /// Fixed location of the proof, when the kind has one
+    pub fn proof_url(&self, target: &str) -> Option<String> {
+        match self {
+            Self::Website => Some(format!("https://{}{}", target, WELL_KNOWN_PATH)),
+            _ => None,
+        }
+    }
+
+    /// Is `url` a place only the owner of `target` could publish to?
+    pub fn accepts_proof_url(&self, target: &str, url: &str) -> bool {
+        let url = url.trim().to_lowercase();
+        match self {
+            Self::Website => Some(url) == self.proof_url(target),
+            Self::Github => {
+                url.starts_with(&format!("https://gist.github.com/{}/", target))
+                    || url.starts_with(&format!("https://gist.githubusercontent.com/{}/", target))
+            }
+            Self::Twitter => {
+                url.starts_with(&format!("https://twitter.com/{}/status/", target))
+                    || url.starts_with(&format!("https://x.com/{}/status/", target))
+            }
+            Self::Mastodon => match target.split_once('@') {
+                Some((user, instance)) => url.starts_with(&format!("https://{}/@{}/", instance, user)),
+                None => false,
+            },
+        }
+    }
+}
+
+/// What gets signed: this identity controls this account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofStatement {
+    pub kind: VerificationKind,
+    pub target: String,
+    pub public_key: String,
+    pub handle: Option<String>,
+    pub nonce: String,
+    /// Unix ms
+    pub created_at: i64,
+}
+
+impl ProofStatement {
+    pub fn new(kind: VerificationKind, target: &str, public_key: &str, handle: Option<String>) -> Self {
+        Self {
+            kind,
+            target: target.to_string(),
+            public_key: public_key.to_string(),
+            handle,
+            nonce: uuid::Uuid::new_v4().simple().to_string(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// Canonical text form; this exact text is what the signature covers
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!("gns-proof: {}", PROOF_VERSION),
+            format!("kind: {}", self.kind.as_str()),
+            format!("target: {}", self.target),
+            format!("identity: {}", self.public_key),
+        ];
+        if let Some(handle) = &self.handle {
+            lines.push(format!("handle: @{}", handle.trim_start_matches('@')));
+        }
+        lines.push(format!("nonce: {}", self.nonce));
+        lines.push(format!("created_at: {}", self.created_at));
+        lines.join("\n")
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let field = |name: &str| {
+            text.lines()
+                .filter_map(|line| line.trim().split_once(": "))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.trim().to_string())
+        };
+
+        if field("gns-proof")? != PROOF_VERSION.to_string() {
+            return None;
+        }
+
+        Some(Self {
+            kind: VerificationKind::parse(&field("kind")?)?,
+            target: field("target")?,
+            public_key: field("identity")?,
+            handle: field("handle").map(|h| h.trim_start_matches('@').to_string()),
+            nonce: field("nonce")?,
+            created_at: field("created_at")?.parse().ok()?,
+        })
+    }
+}
+
+/// The document the user publishes
+pub fn proof_document(statement: &ProofStatement, signature: &str) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n",
+        PROOF_HEADER,
+        statement.to_text(),
+        SIGNATURE_HEADER,
+        signature,
+        PROOF_FOOTER
+    )
+}
+
+/// Find a proof document in fetched content (pages may wrap it in markup)
+pub fn parse_proof_document(content: &str) -> Option<(ProofStatement, String)> {
+    let start = content.find(PROOF_HEADER)? + PROOF_HEADER.len();
+    let end = start + content[start..].find(PROOF_FOOTER)?;
+    let (statement, signature) = content[start..end].split_once(SIGNATURE_HEADER)?;
+
+    let signature: String = signature.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    Some((ProofStatement::from_text(statement)?, signature))
+}
+
+/// Check fetched content holds a proof for this account, signed by `public_key`
+pub fn verify_proof(
+    content: &str,
+    kind: VerificationKind,
+    target: &str,
+    public_key: &str,
+) -> Result<(ProofStatement, String), String> {
+    let (statement, signature) = parse_proof_document(content).ok_or("No GNS proof found at that URL")?;
+
+    if statement.kind != kind || statement.target != target {
+        return Err(format!(
+            "Proof is for {} {}, not {} {}",
+            statement.kind.as_str(),
+            statement.target,
+            kind.as_str(),
+            target
+        ));
+    }
+    if statement.public_key != public_key {
+        return Err("Proof was created for a different identity".to_string());
+    }
+
+    match verify_signature_hex(public_key, statement.to_text().as_bytes(), &signature) {
+        Ok(true) => Ok((statement, signature)),
+        _ => Err("Proof signature is invalid".to_string()),
+    }
+}
+
+/// A proof the backend accepted, as listed on a resolved identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifiedProof {
+    pub kind: VerificationKind,
+    pub target: String,
+    pub proof_url: String,
+    pub verified_at: Option<i64>,
+}
+
+/// Validated proofs from an identity API response (`data.verifications`)
+pub fn verified_proofs(data: &serde_json::Value) -> Vec<VerifiedProof> {
+    data["verifications"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter(|v| v["status"].as_str().unwrap_or("verified") == "verified")
+                .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Pending,
+    Verified,
+    Failed,
+}
+
+impl VerificationStatus {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(Self::Pending),
+            "verified" => Some(Self::Verified),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// One of our own submitted proofs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verification {
+    pub kind: VerificationKind,
+    pub target: String,
+    pub proof_url: String,
+    pub status: VerificationStatus,
+    pub error: Option<String>,
+    pub submitted_at: i64,
+    pub verified_at: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gns_crypto_core::GnsIdentity;
+
+    #[test]
+    fn test_proof_roundtrip_and_verify() {
+        let identity = GnsIdentity::generate();
+        let public_key = identity.public_key_hex();
+        let kind = VerificationKind::Website;
+        let target = kind.normalize_target("https://Example.com/about").unwrap();
+        assert_eq!(target, "example.com");
+
+        let statement = ProofStatement::new(kind, &target, &public_key, Some("alice".to_string()));
+        let signature = hex::encode(identity.sign_bytes(statement.to_text().as_bytes()));
+        let page = format!("<html><pre>{}</pre></html>", proof_document(&statement, &signature));
+
+        let (parsed, _) = verify_proof(&page, kind, &target, &public_key).unwrap();
+        assert_eq!(parsed, statement);
+
+        assert!(verify_proof(&page, kind, "other.com", &public_key).is_err());
+        let other = GnsIdentity::generate().public_key_hex();
+        assert!(verify_proof(&page, kind, &target, &other).is_err());
+        let tampered = page.replace("handle: @alice", "handle: @mallory");
+        assert!(verify_proof(&tampered, kind, &target, &public_key).is_err());
+    }
+
+    #[test]
+    fn test_proof_locations() {
+        let github = VerificationKind::Github;
+        let user = github.normalize_target("@Alice").unwrap();
+        assert!(github.accepts_proof_url(&user, "https://gist.github.com/alice/abc123"));
+        assert!(!github.accepts_proof_url(&user, "https://gist.github.com/mallory/abc123"));
+
+        let mastodon = VerificationKind::Mastodon;
+        let account = mastodon.normalize_target("@alice@Mastodon.social").unwrap();
+        assert_eq!(account, "alice@mastodon.social");
+        assert!(mastodon.accepts_proof_url(&account, "https://mastodon.social/@alice/1234"));
+
+        assert!(VerificationKind::Website.normalize_target("not a domain").is_err());
+        assert!(VerificationKind::Website.accepts_proof_url(
+            "example.com",
+            "https://example.com/.well-known/gns-proof.txt"
+        ));
+    }
+}
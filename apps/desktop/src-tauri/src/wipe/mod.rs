@@ -0,0 +1,206 @@
+//! Wipe - Erase everything this device knows about the user
+//!
+//! A panic wipe removes both personas' identities from the keychain, every
+//! table in both their databases (settings included), the attachment cache
+//! and the log files.
+//! Users can also opt in to remote wipe, so a lost device can be erased by
+//! a signed envelope from one of their other devices. Linked devices share
+//! the identity key, so "from my own device" means "signed by my own key".
+
+use gns_crypto_core::envelope::OpenedEnvelope;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::app_lock::LockConfig;
+use crate::duress::Persona;
+use crate::storage::{Database, DatabaseError};
+use crate::AppState;
+
+/// Payload type of a remote-wipe envelope
+pub const REMOTE_WIPE_PAYLOAD_TYPE: &str = "gns/remote-wipe";
+
+/// Remote wipes older than this are ignored, so a stale envelope left on
+/// the relay can't erase a freshly restored identity. Within that window,
+/// the database's remote-wipe mark stops the same wipe acting twice.
+const REMOTE_WIPE_MAX_AGE_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Allowed clock skew for remote wipes dated in the future
+const MAX_CLOCK_SKEW_MS: i64 = 5 * 60 * 1000;
+
+/// What a wipe removed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WipeReport {
+    pub identity_cleared: bool,
+    pub database_cleared: bool,
+    pub attachment_bytes: u64,
+    pub log_files: u32,
+}
+
+/// Erase all local data, then emit `data_wiped`
+///
+/// Every step is attempted even if an earlier one fails, so a partial
+/// failure still removes as much as possible.
+pub async fn wipe_all_data<R: Runtime>(app_handle: &AppHandle<R>, state: &AppState) -> WipeReport {
+    tracing::warn!("Wiping all local data");
+    let mut report = WipeReport::default();
+
+    {
+        let relay = state.relay.lock().await;
+        let _ = relay.disconnect().await;
+        relay.clear_outbox();
+    }
+
+    let active = {
+        let mut identity = state.identity.lock().await;
+        let active = identity.persona();
+        match identity.clear_all() {
+            Ok(()) => report.identity_cleared = true,
+            Err(e) => tracing::error!("Wipe: failed to clear identity: {}", e),
+        }
+        active
+    };
+    state.lan.set_public_key(None);
+
+    match state.database.call(move |db| db.wipe_personas(active)).await {
+        Ok(()) => report.database_cleared = true,
+        Err(e) => tracing::error!("Wipe: failed to clear database: {}", e),
+    }
+    state.app_lock.set_config(LockConfig::default());
+    state.app_lock.unlock();
+    state.app_lock.take_held();
+
+    let attachments = state.attachments.clone();
+    match tauri::async_runtime::spawn_blocking(move || attachments.clear()).await {
+        Ok(Ok(freed)) => report.attachment_bytes = freed,
+        Ok(Err(e)) => tracing::error!("Wipe: failed to clear attachment cache: {}", e),
+        Err(e) => tracing::error!("Wipe: attachment cleanup panicked: {}", e),
+    }
+
+    report.log_files = crate::logging::clear_logs();
+
+    if let Err(e) = app_handle.emit("data_wiped", &report) {
+        tracing::error!("Failed to emit data_wiped event: {}", e);
+    }
+    report
+}
+
+/// Should a remote-wipe envelope be honored?
+///
+/// It must carry a valid signature from our own key, be recent, and be
+/// newer than `wiped_through`, the last wipe acted on.
+pub fn check_remote_wipe(
+    my_public_key: &str,
+    opened: &OpenedEnvelope,
+    now: i64,
+    wiped_through: i64,
+) -> Result<(), &'static str> {
+    if !opened.signature_valid {
+        return Err("invalid signature");
+    }
+    if !opened.from_public_key.eq_ignore_ascii_case(my_public_key) {
+        return Err("not sent by one of our devices");
+    }
+    if now - opened.timestamp > REMOTE_WIPE_MAX_AGE_MS {
+        return Err("expired");
+    }
+    if opened.timestamp - now > MAX_CLOCK_SKEW_MS {
+        return Err("dated in the future");
+    }
+    if opened.timestamp <= wiped_through {
+        return Err("already acted on");
+    }
+    Ok(())
+}
+
+/// Run `f` on the primary database, which keeps the remote-wipe mark
+/// whichever persona is active
+async fn with_primary_database<T: Send + 'static>(
+    state: &AppState,
+    f: impl FnOnce(&mut Database) -> Result<T, DatabaseError> + Send + 'static,
+) -> Result<T, DatabaseError> {
+    let persona = state.identity.lock().await.persona();
+    state
+        .database
+        .call(move |db| match persona {
+            Persona::Primary => f(db),
+            Persona::Decoy => f(&mut Database::open_persona(Persona::Primary)?),
+        })
+        .await
+}
+
+/// Act on a remote-wipe envelope if the user has enabled remote wipe
+pub async fn handle_remote_wipe<R: Runtime>(app_handle: &AppHandle<R>, my_public_key: &str, opened: &OpenedEnvelope) {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+
+    if !state.database.call(|db| db.get_remote_wipe_enabled()).await {
+        tracing::warn!("Ignoring remote wipe: remote wipe is disabled");
+        return;
+    }
+    let wiped_through = with_primary_database(&state, |db| Ok(db.get_remote_wipe_mark()))
+        .await
+        .unwrap_or(i64::MAX);
+    if let Err(reason) = check_remote_wipe(my_public_key, opened, chrono::Utc::now().timestamp_millis(), wiped_through) {
+        tracing::warn!("Ignoring remote wipe: {}", reason);
+        return;
+    }
+
+    // Recorded first: a wipe that can't be marked could be replayed
+    let timestamp = opened.timestamp;
+    if let Err(e) = with_primary_database(&state, move |db| db.set_remote_wipe_mark(timestamp)).await {
+        tracing::error!("Ignoring remote wipe: failed to record it: {}", e);
+        return;
+    }
+
+    wipe_all_data(app_handle, &state).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wipe_from(from_public_key: &str, timestamp: i64, signature_valid: bool) -> OpenedEnvelope {
+        OpenedEnvelope {
+            from_public_key: from_public_key.to_string(),
+            from_handle: None,
+            payload_type: REMOTE_WIPE_PAYLOAD_TYPE.to_string(),
+            payload: b"{}".to_vec(),
+            signature_valid,
+            envelope_id: "env-1".to_string(),
+            timestamp,
+            thread_id: None,
+            reply_to_id: None,
+        }
+    }
+
+    #[test]
+    fn test_check_remote_wipe() {
+        let me = "a".repeat(64);
+        let now = 1_700_000_000_000;
+
+        assert!(check_remote_wipe(&me, &wipe_from(&me, now - 1000, true), now, 0).is_ok());
+        assert!(check_remote_wipe(&me, &wipe_from(&me.to_uppercase(), now, true), now, 0).is_ok());
+
+        assert_eq!(check_remote_wipe(&me, &wipe_from(&me, now, false), now, 0), Err("invalid signature"));
+        assert_eq!(
+            check_remote_wipe(&me, &wipe_from(&"b".repeat(64), now, true), now, 0),
+            Err("not sent by one of our devices")
+        );
+        assert_eq!(
+            check_remote_wipe(&me, &wipe_from(&me, now - REMOTE_WIPE_MAX_AGE_MS - 1, true), now, 0),
+            Err("expired")
+        );
+        assert_eq!(
+            check_remote_wipe(&me, &wipe_from(&me, now + MAX_CLOCK_SKEW_MS + 1, true), now, 0),
+            Err("dated in the future")
+        );
+
+        // A wipe already acted on can't be replayed, but a newer one works
+        assert_eq!(
+            check_remote_wipe(&me, &wipe_from(&me, now - 1000, true), now, now - 1000),
+            Err("already acted on")
+        );
+        assert!(check_remote_wipe(&me, &wipe_from(&me, now, true), now, now - 1000).is_ok());
+    }
+}
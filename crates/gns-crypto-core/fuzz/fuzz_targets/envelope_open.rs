@@ -0,0 +1,24 @@
+//! `open_envelope` on whatever the parsers accept
+//!
+//! ```text
+//! cargo +nightly fuzz run envelope_open
+//! ```
+
+#![no_main]
+
+use gns_crypto_core::{open_envelope, GnsEnvelope, GnsIdentity};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let recipient = GnsIdentity::from_bytes(&[0x02; 32]).expect("fixed seed is valid");
+
+    if let Ok(envelope) = GnsEnvelope::from_json(text) {
+        let _ = open_envelope(&recipient, &envelope);
+    }
+    if let Ok(envelope) = GnsEnvelope::parse_lenient(text) {
+        let _ = open_envelope(&recipient, &envelope);
+    }
+});
@@ -0,0 +1,24 @@
+//! `GnsEnvelope::from_json` and `parse_lenient` on arbitrary input
+//!
+//! ```text
+//! cargo +nightly fuzz run envelope_parse
+//! ```
+
+#![no_main]
+
+use gns_crypto_core::GnsEnvelope;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = GnsEnvelope::from_json(text);
+
+    if let Ok(envelope) = GnsEnvelope::parse_lenient(text) {
+        // Anything accepted is valid, and survives a round trip
+        envelope.validate().expect("parse_lenient returned an invalid envelope");
+        let wire = envelope.to_json().expect("accepted envelope serializes");
+        GnsEnvelope::parse_lenient(&wire).expect("serialized envelope parses again");
+    }
+});
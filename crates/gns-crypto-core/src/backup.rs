@@ -0,0 +1,339 @@
+//! Backup Module - Identity backup and restore
+//!
+//! Two complementary backup formats, identical on every platform:
+//! - **Mnemonic**: the 32-byte Ed25519 seed as a 24-word BIP39 phrase
+//!   (English wordlist) for writing down on paper
+//! - **Encrypted export**: the seed sealed with a passphrase-derived key
+//!   (Argon2id → ChaCha20-Poly1305) as a JSON document for file backups
+//!
+//! The X25519 encryption key is derived from the seed, so both formats
+//! restore the full identity.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::encryption::hex_bytes;
+use crate::errors::CryptoError;
+use crate::identity::GnsIdentity;
+use crate::secret::SecretString;
+
+/// Current encrypted backup format version
+pub const BACKUP_VERSION: u8 = 1;
+
+/// Argon2id memory cost in KiB (19 MiB, OWASP baseline)
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+
+/// Highest KDF costs accepted from a backup file, 4x what we export with.
+/// The file is untrusted; without a cap it could ask for gigabytes of
+/// memory or an endless hash.
+const MAX_ARGON2_MEMORY_KIB: u32 = 4 * ARGON2_MEMORY_KIB;
+const MAX_ARGON2_ITERATIONS: u32 = 4 * ARGON2_ITERATIONS;
+const MAX_ARGON2_PARALLELISM: u32 = 4 * ARGON2_PARALLELISM;
+
+/// Passphrase-encrypted identity export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedBackup {
+    /// Format version
+    pub version: u8,
+
+    /// Key derivation function ("argon2id")
+    pub kdf: String,
+
+    /// Argon2 memory cost (KiB)
+    pub memory_kib: u32,
+
+    /// Argon2 iterations
+    pub iterations: u32,
+
+    /// Argon2 parallelism
+    pub parallelism: u32,
+
+    /// KDF salt (16 bytes)
+    #[serde(with = "hex_bytes")]
+    pub salt: Vec<u8>,
+
+    /// ChaCha20-Poly1305 nonce (12 bytes)
+    #[serde(with = "hex_bytes")]
+    pub nonce: Vec<u8>,
+
+    /// Encrypted seed + authentication tag
+    #[serde(with = "hex_bytes")]
+    pub ciphertext: Vec<u8>,
+
+    /// Ed25519 public key of the backed-up identity (hex), for display
+    /// and to check the restore produced the expected identity
+    pub public_key: String,
+}
+
+// ==================== MNEMONIC ====================
+
+/// Encode an identity's seed as a 24-word BIP39 mnemonic
+pub fn identity_to_mnemonic(identity: &GnsIdentity) -> Result<SecretString, CryptoError> {
+    let seed = identity.private_key_bytes();
+    let mnemonic = bip39::Mnemonic::from_entropy(seed.expose_secret())
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+    Ok(SecretString::from(mnemonic.to_string()))
+}
+
+/// Restore an identity from a 24-word BIP39 mnemonic
+///
+/// Whitespace and letter case are normalized before parsing.
+pub fn identity_from_mnemonic(phrase: &str) -> Result<GnsIdentity, CryptoError> {
+    let normalized = phrase
+        .split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mnemonic = bip39::Mnemonic::parse_in(bip39::Language::English, &normalized)
+        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid mnemonic: {}", e)))?;
+
+    let mut entropy = mnemonic.to_entropy();
+    if entropy.len() != 32 {
+        let got = entropy.len();
+        entropy.zeroize();
+        return Err(CryptoError::InvalidKeyLength { expected: 32, got });
+    }
+
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&entropy);
+    entropy.zeroize();
+
+    let identity = GnsIdentity::from_bytes(&seed);
+    seed.zeroize();
+    identity
+}
+
+/// Check whether a phrase is a valid GNS mnemonic (24 words, valid checksum)
+pub fn validate_mnemonic(phrase: &str) -> bool {
+    identity_from_mnemonic(phrase).is_ok()
+}
+
+// ==================== ENCRYPTED EXPORT ====================
+
+/// Export an identity encrypted with a passphrase
+pub fn export_encrypted(
+    identity: &GnsIdentity,
+    passphrase: &str,
+) -> Result<EncryptedBackup, CryptoError> {
+    if passphrase.is_empty() {
+        return Err(CryptoError::KeyDerivationFailed(
+            "Passphrase must not be empty".to_string(),
+        ));
+    }
+
+    let mut salt = [0u8; 16];
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let mut key = derive_key(
+        passphrase,
+        &salt,
+        ARGON2_MEMORY_KIB,
+        ARGON2_ITERATIONS,
+        ARGON2_PARALLELISM,
+    )?;
+    let cipher = ChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
+    key.zeroize();
+
+    let seed = identity.private_key_bytes();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), seed.expose_secret())
+        .map_err(|e| CryptoError::EncryptionFailed(e.to_string()));
+
+    Ok(EncryptedBackup {
+        version: BACKUP_VERSION,
+        kdf: "argon2id".to_string(),
+        memory_kib: ARGON2_MEMORY_KIB,
+        iterations: ARGON2_ITERATIONS,
+        parallelism: ARGON2_PARALLELISM,
+        salt: salt.to_vec(),
+        nonce: nonce_bytes.to_vec(),
+        ciphertext: ciphertext?,
+        public_key: identity.public_key_hex(),
+    })
+}
+
+/// Restore an identity from a passphrase-encrypted export
+pub fn import_encrypted(
+    backup: &EncryptedBackup,
+    passphrase: &str,
+) -> Result<GnsIdentity, CryptoError> {
+    if backup.version != BACKUP_VERSION {
+        return Err(CryptoError::SerializationError(format!(
+            "Unsupported backup version: {}",
+            backup.version
+        )));
+    }
+    if backup.kdf != "argon2id" {
+        return Err(CryptoError::KeyDerivationFailed(format!(
+            "Unsupported KDF: {}",
+            backup.kdf
+        )));
+    }
+    if backup.nonce.len() != 12 {
+        return Err(CryptoError::InvalidNonceLength);
+    }
+    if backup.memory_kib > MAX_ARGON2_MEMORY_KIB
+        || backup.iterations > MAX_ARGON2_ITERATIONS
+        || backup.parallelism > MAX_ARGON2_PARALLELISM
+    {
+        return Err(CryptoError::KeyDerivationFailed(format!(
+            "Backup asks for too costly a key derivation ({} KiB, {} iterations, {} lanes)",
+            backup.memory_kib, backup.iterations, backup.parallelism
+        )));
+    }
+
+    let mut key = derive_key(
+        passphrase,
+        &backup.salt,
+        backup.memory_kib,
+        backup.iterations,
+        backup.parallelism,
+    )?;
+    let cipher = ChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?;
+    key.zeroize();
+
+    let mut seed = cipher
+        .decrypt(Nonce::from_slice(&backup.nonce), backup.ciphertext.as_slice())
+        .map_err(|_| CryptoError::DecryptionFailed("Wrong passphrase or corrupted backup".to_string()))?;
+
+    if seed.len() != 32 {
+        let got = seed.len();
+        seed.zeroize();
+        return Err(CryptoError::InvalidKeyLength { expected: 32, got });
+    }
+
+    let mut seed_bytes = [0u8; 32];
+    seed_bytes.copy_from_slice(&seed);
+    seed.zeroize();
+
+    let identity = GnsIdentity::from_bytes(&seed_bytes)?;
+    seed_bytes.zeroize();
+
+    if identity.public_key_hex() != backup.public_key.to_lowercase() {
+        return Err(CryptoError::InvalidKeyFormat(
+            "Backup public key does not match restored identity".to_string(),
+        ));
+    }
+
+    Ok(identity)
+}
+
+/// Derive a 32-byte key from a passphrase with Argon2id
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<[u8; 32], CryptoError> {
+    let params = Params::new(memory_kib, iterations, parallelism, Some(32))
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mnemonic_roundtrip() {
+        let identity = GnsIdentity::generate();
+        let phrase = identity_to_mnemonic(&identity).unwrap();
+        let phrase = phrase.expose_secret();
+
+        assert_eq!(phrase.split_whitespace().count(), 24);
+
+        let restored = identity_from_mnemonic(phrase).unwrap();
+        assert_eq!(restored.public_key_hex(), identity.public_key_hex());
+        assert_eq!(restored.encryption_key_hex(), identity.encryption_key_hex());
+    }
+
+    #[test]
+    fn test_mnemonic_normalization() {
+        let identity = GnsIdentity::generate();
+        let phrase = identity_to_mnemonic(&identity).unwrap();
+        let phrase = phrase.expose_secret();
+        let messy = format!("  {}  ", phrase.to_uppercase().replace(' ', "\n "));
+
+        let restored = identity_from_mnemonic(&messy).unwrap();
+        assert_eq!(restored.public_key_hex(), identity.public_key_hex());
+    }
+
+    #[test]
+    fn test_mnemonic_rejects_invalid_phrases() {
+        let identity = GnsIdentity::generate();
+        let phrase = identity_to_mnemonic(&identity).unwrap();
+        let phrase = phrase.expose_secret();
+
+        let mut words: Vec<&str> = phrase.split_whitespace().collect();
+        words[3] = "notaword";
+        assert!(!validate_mnemonic(&words.join(" ")));
+
+        // Valid 12-word BIP39 phrase, but only 16 bytes of entropy
+        let twelve = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert!(!validate_mnemonic(twelve));
+    }
+
+    #[test]
+    fn test_encrypted_export_roundtrip() {
+        let identity = GnsIdentity::generate();
+        let backup = export_encrypted(&identity, "correct horse battery staple").unwrap();
+
+        let json = serde_json::to_string(&backup).unwrap();
+        assert!(json.contains("\"kdf\":\"argon2id\""));
+        assert!(json.contains("\"publicKey\""));
+
+        let parsed: EncryptedBackup = serde_json::from_str(&json).unwrap();
+        let restored = import_encrypted(&parsed, "correct horse battery staple").unwrap();
+        assert_eq!(restored.public_key_hex(), identity.public_key_hex());
+    }
+
+    #[test]
+    fn test_encrypted_export_wrong_passphrase() {
+        let identity = GnsIdentity::generate();
+        let backup = export_encrypted(&identity, "passphrase").unwrap();
+
+        assert!(import_encrypted(&backup, "not the passphrase").is_err());
+        assert!(export_encrypted(&identity, "").is_err());
+    }
+
+    #[test]
+    fn test_encrypted_import_caps_kdf_costs() {
+        let identity = GnsIdentity::generate();
+        let backup = export_encrypted(&identity, "passphrase").unwrap();
+
+        let costly = [
+            EncryptedBackup { memory_kib: 4 * 1024 * 1024, ..backup.clone() },
+            EncryptedBackup { iterations: u32::MAX, ..backup.clone() },
+            EncryptedBackup { parallelism: 64, ..backup.clone() },
+        ];
+        for backup in &costly {
+            assert!(matches!(
+                import_encrypted(backup, "passphrase"),
+                Err(CryptoError::KeyDerivationFailed(_))
+            ));
+        }
+        assert!(import_encrypted(&backup, "passphrase").is_ok());
+    }
+}
@@ -0,0 +1,87 @@
+//! gns-vectors - Canonical test vector generator and checker
+//!
+//! Emits deterministic JSON test vectors from this crate so the Flutter,
+//! JS, and server implementations can check they stay wire-compatible,
+//! and checks vector files those implementations produce.
+//!
+//! ```text
+//! gns-vectors                # print to stdout
+//! gns-vectors --out FILE     # write to FILE
+//! gns-vectors --verify FILE  # recompute every vector in FILE, exit 1 on mismatch
+//! ```
+//!
+//! The published copy is `testvectors/vectors.json`; see
+//! `gns_crypto_core::testvectors` for what each section holds.
+
+use gns_crypto_core::testvectors;
+
+fn main() {
+    let mut out_path: Option<String> = None;
+    let mut verify_path: Option<String> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" | "-o" => out_path = args.next(),
+            "--verify" => verify_path = args.next(),
+            "--help" | "-h" => {
+                println!("Usage: gns-vectors [--out FILE | --verify FILE]");
+                return;
+            }
+            other => {
+                eprintln!("Unknown argument: {}", other);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Some(path) = verify_path {
+        verify(&path);
+        return;
+    }
+
+    let vectors = match testvectors::generate() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to generate vectors: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let text = serde_json::to_string_pretty(&vectors).expect("vectors are valid JSON");
+
+    match out_path {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, text + "\n") {
+                eprintln!("Failed to write {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        None => println!("{}", text),
+    }
+}
+
+fn verify(path: &str) {
+    let vectors = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    let report = testvectors::verify(&vectors);
+    for failure in &report.failures {
+        eprintln!("FAIL {}", failure);
+    }
+    println!(
+        "{} vectors checked, {} failed",
+        report.checked,
+        report.failures.len()
+    );
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
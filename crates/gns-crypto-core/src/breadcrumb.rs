@@ -30,7 +30,7 @@ use serde::{Deserialize, Serialize};
 pub const DEFAULT_H3_RESOLUTION: u8 = 7;
 
 /// A signed location proof
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Breadcrumb {
     /// H3 index (cell ID) as hex string
     pub h3_index: String,
@@ -141,21 +141,9 @@ pub fn create_breadcrumb_from_h3(
 
 /// Verify a breadcrumb's signature
 pub fn verify_breadcrumb(breadcrumb: &Breadcrumb) -> Result<bool, CryptoError> {
-    let signing_data = if let Some(ref prev) = breadcrumb.prev_hash {
-        format!(
-            "gns-breadcrumb-v1:{}:{}:{}:{}",
-            breadcrumb.h3_index, breadcrumb.timestamp, breadcrumb.public_key, prev
-        )
-    } else {
-        format!(
-            "gns-breadcrumb-v1:{}:{}:{}",
-            breadcrumb.h3_index, breadcrumb.timestamp, breadcrumb.public_key
-        )
-    };
-
     verify_signature_hex(
         &breadcrumb.public_key,
-        signing_data.as_bytes(),
+        breadcrumb.signing_data().as_bytes(),
         &breadcrumb.signature,
     )
 }
@@ -164,7 +152,7 @@ pub fn verify_breadcrumb(breadcrumb: &Breadcrumb) -> Result<bool, CryptoError> {
 ///
 /// This is a placeholder implementation. In production, use the h3o crate.
 /// For WASM compatibility, we may need to use a JS H3 library.
-fn lat_lng_to_h3(latitude: f64, longitude: f64, resolution: u8) -> Result<String, CryptoError> {
+pub fn lat_lng_to_h3(latitude: f64, longitude: f64, resolution: u8) -> Result<String, CryptoError> {
     // Validate inputs
     if !(-90.0..=90.0).contains(&latitude) {
         return Err(CryptoError::InvalidEnvelope(format!(
@@ -216,7 +204,61 @@ pub fn h3_grid_distance(h3_a: &str, h3_b: &str) -> Result<u32, CryptoError> {
     }
 }
 
+/// Split a placeholder H3 index into (resolution, quantized lat, quantized lng)
+fn decode_h3(h3: &str) -> Result<(u8, u64, u64), CryptoError> {
+    let index = u64::from_str_radix(h3, 16)
+        .map_err(|_| CryptoError::InvalidEnvelope("Invalid H3 index".to_string()))?;
+
+    let resolution = (index >> 60) as u8;
+    let lat_quantized = (index >> 32) & 0x0FFF_FFFF;
+    let lng_quantized = index & 0xFFFF_FFFF;
+
+    Ok((resolution, lat_quantized, lng_quantized))
+}
+
+/// Get the containing cell at a coarser resolution
+///
+/// Placeholder counterpart of `lat_lng_to_h3`: each coarser level snaps the
+/// quantized coordinates to a grid ~sqrt(7)x wider, like real H3 cells.
+/// In production, use h3o::CellIndex::parent.
+pub fn h3_to_parent(h3: &str, resolution: u8) -> Result<String, CryptoError> {
+    let (cell_resolution, lat_quantized, lng_quantized) = decode_h3(h3)?;
+    if resolution >= cell_resolution {
+        return Ok(h3.to_string());
+    }
+
+    let factor = 7f64.sqrt().powi((cell_resolution - resolution) as i32).round() as u64;
+    let lat = lat_quantized / factor * factor;
+    let lng = lng_quantized / factor * factor;
+    let index = (lat << 32) | lng | ((resolution as u64) << 60);
+
+    Ok(format!("{:016x}", index))
+}
+
+/// Approximate (latitude, longitude) of a cell, for drawing it on a map
+pub fn h3_to_lat_lng(h3: &str) -> Result<(f64, f64), CryptoError> {
+    let (_, lat_quantized, lng_quantized) = decode_h3(h3)?;
+    Ok((
+        lat_quantized as f64 / 1000.0 - 90.0,
+        lng_quantized as f64 / 1000.0 - 180.0,
+    ))
+}
+
 impl Breadcrumb {
+    /// The string covered by the signature
+    pub fn signing_data(&self) -> String {
+        match self.prev_hash {
+            Some(ref prev) => format!(
+                "gns-breadcrumb-v1:{}:{}:{}:{}",
+                self.h3_index, self.timestamp, self.public_key, prev
+            ),
+            None => format!(
+                "gns-breadcrumb-v1:{}:{}:{}",
+                self.h3_index, self.timestamp, self.public_key
+            ),
+        }
+    }
+
     /// Verify this breadcrumb's signature
     pub fn verify(&self) -> Result<bool, CryptoError> {
         verify_breadcrumb(self)
@@ -362,6 +404,20 @@ mod tests {
         assert_eq!(breadcrumb.signature, parsed.signature);
     }
 
+    #[test]
+    fn test_h3_parent_and_center() {
+        let cell = lat_lng_to_h3(52.5200, 13.4050, DEFAULT_H3_RESOLUTION).unwrap();
+        let nearby = lat_lng_to_h3(52.5210, 13.4120, DEFAULT_H3_RESOLUTION).unwrap();
+        assert_ne!(cell, nearby);
+
+        // Close cells share a coarser parent
+        assert_eq!(h3_to_parent(&cell, 3).unwrap(), h3_to_parent(&nearby, 3).unwrap());
+        assert_eq!(h3_to_parent(&cell, DEFAULT_H3_RESOLUTION).unwrap(), cell);
+
+        let (lat, lng) = h3_to_lat_lng(&cell).unwrap();
+        assert!((lat - 52.52).abs() < 0.01 && (lng - 13.405).abs() < 0.01);
+    }
+
     #[test]
     fn test_trajectory() {
         let identity = GnsIdentity::generate();
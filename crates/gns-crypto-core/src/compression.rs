@@ -0,0 +1,89 @@
+//! Payload Compression - zstd before encryption
+//!
+//! Large payloads (email bodies, rich JSON) are compressed before they are
+//! encrypted, since ciphertext doesn't compress. The envelope records the
+//! encoding in its signed `contentEncoding` header, and `open_envelope`
+//! decompresses transparently. Small payloads, and payloads that don't get
+//! smaller, are sent as-is.
+//!
+//! The zstd implementation is pure Rust, so the WASM build uses it too.
+
+use std::io::Read;
+
+use ruzstd::decoding::StreamingDecoder;
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+use crate::errors::CryptoError;
+
+/// `contentEncoding` value for zstd-compressed payloads
+pub const CONTENT_ENCODING_ZSTD: &str = "zstd";
+
+/// Payloads smaller than this are never compressed
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 4 * 1024;
+
+/// Upper bound on a decompressed payload, to refuse decompression bombs
+pub const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Compress `payload` if it is at least `threshold` bytes and shrinks
+///
+/// Returns `None` when the payload should be sent uncompressed.
+pub fn compress_payload(payload: &[u8], threshold: usize) -> Option<Vec<u8>> {
+    if payload.len() < threshold {
+        return None;
+    }
+
+    let compressed = compress_to_vec(payload, CompressionLevel::Fastest);
+    (compressed.len() < payload.len()).then_some(compressed)
+}
+
+/// Undo `compress_payload` for the given `contentEncoding`
+pub fn decompress_payload(data: &[u8], encoding: Option<&str>) -> Result<Vec<u8>, CryptoError> {
+    match encoding {
+        None => Ok(data.to_vec()),
+        Some(CONTENT_ENCODING_ZSTD) => {
+            let decoder = StreamingDecoder::new(data)
+                .map_err(|e| CryptoError::CompressionError(e.to_string()))?;
+
+            let mut out = Vec::new();
+            decoder
+                .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+                .read_to_end(&mut out)
+                .map_err(|e| CryptoError::CompressionError(e.to_string()))?;
+
+            if out.len() > MAX_DECOMPRESSED_SIZE {
+                return Err(CryptoError::CompressionError(format!(
+                    "Decompressed payload exceeds {} bytes",
+                    MAX_DECOMPRESSED_SIZE
+                )));
+            }
+            Ok(out)
+        }
+        Some(other) => Err(CryptoError::CompressionError(format!(
+            "Unsupported content encoding: {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_roundtrip_and_threshold() {
+        let large = "{\"body\":\"hello\"}".repeat(1000).into_bytes();
+        let compressed = compress_payload(&large, DEFAULT_COMPRESSION_THRESHOLD).unwrap();
+        assert!(compressed.len() < large.len());
+        assert_eq!(
+            decompress_payload(&compressed, Some(CONTENT_ENCODING_ZSTD)).unwrap(),
+            large
+        );
+
+        // Below the threshold, or not worth it
+        assert!(compress_payload(b"short", DEFAULT_COMPRESSION_THRESHOLD).is_none());
+        assert!(compress_payload(&[0x5a, 0x13, 0xc7], 0).is_none());
+
+        assert!(decompress_payload(b"abc", Some("br")).is_err());
+        assert_eq!(decompress_payload(b"abc", None).unwrap(), b"abc");
+    }
+}
@@ -15,10 +15,11 @@ use rand::rngs::OsRng;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey, StaticSecret};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 use zeroize::Zeroize;
 
 use crate::errors::CryptoError;
+use crate::identity::GnsIdentity;
 
 /// Encrypted payload structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +36,14 @@ pub struct EncryptedPayload {
     /// Encrypted data + authentication tag
     #[serde(with = "hex_bytes")]
     pub ciphertext: Vec<u8>,
+
+    /// ML-KEM ciphertext, present on post-quantum hybrid payloads
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "hex_bytes_opt"
+    )]
+    pub kem_ciphertext: Option<Vec<u8>>,
 }
 
 /// Wrapper to handle both legacy object and new string payload formats
@@ -55,8 +64,97 @@ pub fn encrypt_for_recipient(
     plaintext: &[u8],
     recipient_x25519_public: &[u8; 32],
 ) -> Result<EncryptedPayload, CryptoError> {
-    // Generate ephemeral keypair
-    let ephemeral_secret = EphemeralSecret::random_from_rng(OsRng);
+    // Generate ephemeral secret and random nonce
+    let mut ephemeral_secret = [0u8; 32];
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut ephemeral_secret);
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let result = encrypt_with_ephemeral(
+        plaintext,
+        recipient_x25519_public,
+        &ephemeral_secret,
+        &nonce_bytes,
+    );
+
+    ephemeral_secret.zeroize();
+    result
+}
+
+/// Encrypt with ML-KEM-768 + X25519 for a recipient that published a PQ key
+#[cfg(feature = "pq-hybrid")]
+pub fn encrypt_for_recipient_hybrid(
+    plaintext: &[u8],
+    recipient_x25519_public: &[u8; 32],
+    recipient_pq_public: &[u8],
+) -> Result<EncryptedPayload, CryptoError> {
+    let (kem_ciphertext, mut kem_shared) = crate::pq::encapsulate(recipient_pq_public)?;
+
+    let mut ephemeral_secret = [0u8; 32];
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut ephemeral_secret);
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let result = seal(
+        plaintext,
+        recipient_x25519_public,
+        &ephemeral_secret,
+        &nonce_bytes,
+        Some(&kem_shared),
+    );
+
+    ephemeral_secret.zeroize();
+    kem_shared.zeroize();
+    result.map(|payload| EncryptedPayload {
+        kem_ciphertext: Some(kem_ciphertext),
+        ..payload
+    })
+}
+
+/// Encrypt for a recipient, using the hybrid scheme when they published a
+/// PQ key and this build supports it, and plain X25519 otherwise
+pub fn encrypt_for_recipient_keys(
+    plaintext: &[u8],
+    recipient_x25519_public: &[u8; 32],
+    recipient_pq_public: Option<&[u8]>,
+) -> Result<EncryptedPayload, CryptoError> {
+    #[cfg(feature = "pq-hybrid")]
+    if let Some(pq_public) = recipient_pq_public {
+        return encrypt_for_recipient_hybrid(plaintext, recipient_x25519_public, pq_public);
+    }
+    #[cfg(not(feature = "pq-hybrid"))]
+    let _ = recipient_pq_public;
+
+    encrypt_for_recipient(plaintext, recipient_x25519_public)
+}
+
+/// Encrypt with a caller-supplied ephemeral secret and nonce
+///
+/// Only for deterministic test vectors. Reusing an ephemeral secret or
+/// nonce breaks confidentiality; use `encrypt_for_recipient` instead.
+pub fn encrypt_with_ephemeral(
+    plaintext: &[u8],
+    recipient_x25519_public: &[u8; 32],
+    ephemeral_secret: &[u8; 32],
+    nonce_bytes: &[u8; 12],
+) -> Result<EncryptedPayload, CryptoError> {
+    seal(
+        plaintext,
+        recipient_x25519_public,
+        ephemeral_secret,
+        nonce_bytes,
+        None,
+    )
+}
+
+fn seal(
+    plaintext: &[u8],
+    recipient_x25519_public: &[u8; 32],
+    ephemeral_secret: &[u8; 32],
+    nonce_bytes: &[u8; 12],
+    kem_shared: Option<&[u8; 32]>,
+) -> Result<EncryptedPayload, CryptoError> {
+    let ephemeral_secret = StaticSecret::from(*ephemeral_secret);
     let ephemeral_public = X25519PublicKey::from(&ephemeral_secret);
 
     // Perform ECDH
@@ -68,19 +166,15 @@ pub fn encrypt_for_recipient(
         shared_secret.as_bytes(),
         ephemeral_public.as_bytes(),
         recipient_x25519_public,
+        kem_shared,
     )?;
 
-    // Generate random nonce
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
     // Encrypt with ChaCha20-Poly1305
     let cipher = ChaCha20Poly1305::new_from_slice(&symmetric_key)
         .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
 
     let ciphertext = cipher
-        .encrypt(nonce, plaintext)
+        .encrypt(Nonce::from_slice(nonce_bytes), plaintext)
         .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
 
     // Zeroize symmetric key
@@ -90,13 +184,55 @@ pub fn encrypt_for_recipient(
         ephemeral_public_key: ephemeral_public.as_bytes().to_vec(),
         nonce: nonce_bytes.to_vec(),
         ciphertext,
+        kem_ciphertext: None,
     })
 }
 
 /// Decrypt data sent to us
+///
+/// Only handles X25519 payloads; hybrid payloads need the full identity,
+/// see `decrypt_for_identity`.
 pub fn decrypt_from_sender(
     our_x25519_secret: &[u8; 32],
     encrypted: &EncryptedPayload,
+) -> Result<Vec<u8>, CryptoError> {
+    if encrypted.kem_ciphertext.is_some() {
+        return Err(CryptoError::DecryptionFailed(
+            "Post-quantum payload needs the recipient identity".to_string(),
+        ));
+    }
+    open(our_x25519_secret, encrypted, None)
+}
+
+/// Decrypt data sent to an identity, classic or hybrid
+pub fn decrypt_for_identity(
+    identity: &GnsIdentity,
+    encrypted: &EncryptedPayload,
+) -> Result<Vec<u8>, CryptoError> {
+    let Some(kem_ciphertext) = &encrypted.kem_ciphertext else {
+        return open(identity.x25519_secret(), encrypted, None);
+    };
+
+    #[cfg(feature = "pq-hybrid")]
+    {
+        let mut kem_shared = crate::pq::decapsulate(&identity.seed_bytes(), kem_ciphertext)?;
+        let result = open(identity.x25519_secret(), encrypted, Some(&kem_shared));
+        kem_shared.zeroize();
+        result
+    }
+    #[cfg(not(feature = "pq-hybrid"))]
+    {
+        let _ = kem_ciphertext;
+        Err(CryptoError::DecryptionFailed(
+            "Post-quantum payloads require the pq-hybrid feature".to_string(),
+        ))
+    }
+}
+
+fn open(
+    our_x25519_secret: &[u8; 32],
+    encrypted: &EncryptedPayload,
+    kem_shared: Option<&[u8; 32]>,
 ) -> Result<Vec<u8>, CryptoError> {
     // Validate lengths
     if encrypted.ephemeral_public_key.len() != 32 {
@@ -110,8 +246,14 @@ pub fn decrypt_from_sender(
     }
 
     // Parse ephemeral public key
-    let ephemeral_public_bytes: [u8; 32] =
-        encrypted.ephemeral_public_key.clone().try_into().unwrap();
+    let ephemeral_public_bytes: [u8; 32] = encrypted
+        .ephemeral_public_key
+        .as_slice()
+        .try_into()
+        .map_err(|_| CryptoError::InvalidKeyLength {
+            expected: 32,
+            got: encrypted.ephemeral_public_key.len(),
+        })?;
     let ephemeral_public = X25519PublicKey::from(ephemeral_public_bytes);
 
     // Perform ECDH with our static secret
@@ -124,10 +266,15 @@ pub fn decrypt_from_sender(
         shared_secret.as_bytes(),
         &ephemeral_public_bytes,
         our_public.as_bytes(),
+        kem_shared,
     )?;
 
     // Parse nonce
-    let nonce_bytes: [u8; 12] = encrypted.nonce.clone().try_into().unwrap();
+    let nonce_bytes: [u8; 12] = encrypted
+        .nonce
+        .as_slice()
+        .try_into()
+        .map_err(|_| CryptoError::InvalidNonceLength)?;
     let nonce = Nonce::from_slice(&nonce_bytes);
 
     // Decrypt with ChaCha20-Poly1305
@@ -145,19 +292,31 @@ pub fn decrypt_from_sender(
 }
 
 /// Derive symmetric key from shared secret using HKDF-SHA256
+///
+/// Hybrid payloads append the ML-KEM shared secret to the input key
+/// material and use a separate info prefix.
 fn derive_symmetric_key(
     shared_secret: &[u8],
     ephemeral_public: &[u8],
     recipient_public: &[u8],
+    kem_shared: Option<&[u8; 32]>,
 ) -> Result<[u8; 32], CryptoError> {
     // Create info string for domain separation
     // Include both public keys to bind the key to this specific exchange
-    let mut info = Vec::with_capacity(64 + 17);
-    info.extend_from_slice(b"gns-envelope-v1:");
+    let mut info = Vec::with_capacity(64 + 19);
+    let mut ikm = shared_secret.to_vec();
+    match kem_shared {
+        Some(kem_shared) => {
+            info.extend_from_slice(b"gns-envelope-pq-v1:");
+            ikm.extend_from_slice(kem_shared);
+        }
+        None => info.extend_from_slice(b"gns-envelope-v1:"),
+    }
     info.extend_from_slice(ephemeral_public);
     info.extend_from_slice(recipient_public);
 
-    let hkdf = Hkdf::<Sha256>::new(None, shared_secret);
+    let hkdf = Hkdf::<Sha256>::new(None, &ikm);
+    ikm.zeroize();
     let mut key = [0u8; 32];
     hkdf.expand(&info, &mut key)
         .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
@@ -166,7 +325,7 @@ fn derive_symmetric_key(
 }
 
 /// Hex serialization helper for serde
-mod hex_bytes {
+pub(crate) mod hex_bytes {
     use serde::{Deserialize, Deserializer, Serializer};
 
     pub fn serialize<S>(bytes: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error>
@@ -185,6 +344,30 @@ mod hex_bytes {
     }
 }
 
+/// Hex serialization helper for optional byte fields
+pub(crate) mod hex_bytes_opt {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(bytes: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match bytes {
+            Some(bytes) => serializer.serialize_str(&hex::encode(bytes)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| hex::decode(&s).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,4 +446,42 @@ mod tests {
 
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
+
+    #[test]
+    fn test_hybrid_payload_needs_identity() {
+        let recipient = GnsIdentity::generate();
+        let mut encrypted =
+            encrypt_for_recipient_keys(b"hi", &recipient.encryption_public_key_bytes(), None)
+                .unwrap();
+        assert!(encrypted.kem_ciphertext.is_none());
+        assert!(!serde_json::to_string(&encrypted)
+            .unwrap()
+            .contains("kemCiphertext"));
+
+        encrypted.kem_ciphertext = Some(vec![0u8; 16]);
+        assert!(decrypt_from_sender(recipient.x25519_secret(), &encrypted).is_err());
+        assert!(recipient.decrypt(&encrypted).is_err());
+    }
+
+    #[cfg(feature = "pq-hybrid")]
+    #[test]
+    fn test_hybrid_roundtrip() {
+        let recipient = GnsIdentity::generate();
+        let pq_public = hex::decode(recipient.pq_public_key_hex().unwrap()).unwrap();
+
+        let encrypted = encrypt_for_recipient_keys(
+            b"post-quantum hello",
+            &recipient.encryption_public_key_bytes(),
+            Some(&pq_public),
+        )
+        .unwrap();
+        assert!(encrypted.kem_ciphertext.is_some());
+        assert_eq!(
+            recipient.decrypt(&encrypted).unwrap(),
+            b"post-quantum hello"
+        );
+
+        // The X25519 secret alone is not enough
+        assert!(decrypt_from_sender(recipient.x25519_secret(), &encrypted).is_err());
+    }
 }
@@ -17,6 +17,7 @@
 //! │ ├── to_public_keys: [Ed25519 pubkeys]   │
 //! │ ├── payload_type: MIME type             │
 //! │ ├── timestamp: Unix ms                  │
+//! │ ├── content_encoding: Optional "zstd"   │
 //! │ └── thread_id: Optional conversation ID │
 //! ├─────────────────────────────────────────┤
 //! │ Encrypted Payload                       │
@@ -31,13 +32,26 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::compression::{
+    compress_payload, decompress_payload, CONTENT_ENCODING_ZSTD, DEFAULT_COMPRESSION_THRESHOLD,
+};
 use crate::encryption::{
-    decrypt_from_sender, encrypt_for_recipient, EncryptedPayload, PayloadWrapper,
+    decrypt_for_identity, encrypt_for_recipient_keys, EncryptedPayload, PayloadWrapper,
 };
 use crate::errors::CryptoError;
 use crate::identity::GnsIdentity;
+use crate::sealed_sender::{open_sealed_envelope, SEALED_PAYLOAD_TYPE};
 use crate::signing::{canonicalize_for_signing, verify_signature_hex};
 
+/// Largest envelope JSON `GnsEnvelope::parse_lenient` will parse
+pub const MAX_ENVELOPE_JSON_BYTES: usize = 8 * 1024 * 1024;
+
+/// Most recipients a single envelope may address
+pub const MAX_RECIPIENTS: usize = 1024;
+
+/// Longest id, handle, payload type or thread reference accepted
+const MAX_FIELD_LEN: usize = 256;
+
 /// GNS Envelope - the message container
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -61,6 +75,10 @@ pub struct GnsEnvelope {
     /// Unix timestamp in milliseconds
     pub timestamp: i64,
 
+    /// Encoding applied to the plaintext before encryption (e.g., "zstd")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+
     /// Thread/conversation ID (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thread_id: Option<String>,
@@ -113,7 +131,40 @@ pub struct OpenedEnvelope {
     pub reply_to_id: Option<String>,
 }
 
+/// Options for `create_envelope_with_options`
+#[derive(Debug, Clone, Copy)]
+pub struct EnvelopeOptions<'a> {
+    /// Sender's @handle
+    pub sender_handle: Option<&'a str>,
+
+    /// Thread/conversation ID
+    pub thread_id: Option<&'a str>,
+
+    /// Reply-to message ID
+    pub reply_to_id: Option<&'a str>,
+
+    /// Recipient's ML-KEM public key (hex); hybrid encryption when present
+    pub recipient_pq_key_hex: Option<&'a str>,
+
+    /// Compress payloads at least this large; `None` disables compression
+    pub compression_threshold: Option<usize>,
+}
+
+impl Default for EnvelopeOptions<'_> {
+    fn default() -> Self {
+        Self {
+            sender_handle: None,
+            thread_id: None,
+            reply_to_id: None,
+            recipient_pq_key_hex: None,
+            compression_threshold: Some(DEFAULT_COMPRESSION_THRESHOLD),
+        }
+    }
+}
+
 /// Create a signed and encrypted envelope
+///
+/// Payloads over `DEFAULT_COMPRESSION_THRESHOLD` are zstd-compressed.
 pub fn create_envelope(
     sender: &GnsIdentity,
     recipient_public_key_hex: &str,
@@ -121,54 +172,59 @@ pub fn create_envelope(
     payload_type: &str,
     payload: &[u8],
 ) -> Result<GnsEnvelope, CryptoError> {
-    // Parse recipient encryption key
-    let recipient_enc_key_bytes = hex::decode(recipient_encryption_key_hex)?;
-    if recipient_enc_key_bytes.len() != 32 {
-        return Err(CryptoError::InvalidKeyLength {
-            expected: 32,
-            got: recipient_enc_key_bytes.len(),
-        });
-    }
-    let recipient_enc_key: [u8; 32] = recipient_enc_key_bytes.try_into().unwrap();
-
-    // Encrypt payload
-    let encrypted_payload = encrypt_for_recipient(payload, &recipient_enc_key)?;
-
-    // Generate envelope ID
-    let envelope_id = Uuid::new_v4().to_string();
-    let timestamp = chrono::Utc::now().timestamp_millis();
-
-    // Create header for signing (without signature)
-    let header = EnvelopeHeader {
-        id: envelope_id.clone(),
-        from_public_key: sender.public_key_hex(),
-        to_public_keys: vec![recipient_public_key_hex.to_string()],
-        payload_type: payload_type.to_string(),
-        timestamp,
-        encrypted_payload_hash: blake3::hash(&serde_json::to_vec(&encrypted_payload)?)
-            .to_hex()
-            .to_string(),
-    };
-
-    // Sign the header
-    let header_bytes = canonicalize_for_signing(&serde_json::to_value(&header)?);
-    let signature = sender.sign_bytes(&header_bytes);
-    let signature_hex = hex::encode(signature);
+    create_envelope_with_options(
+        sender,
+        recipient_public_key_hex,
+        recipient_encryption_key_hex,
+        payload_type,
+        payload,
+        &EnvelopeOptions::default(),
+    )
+}
 
-    Ok(GnsEnvelope {
-        id: envelope_id,
+/// Create a signed and encrypted envelope with explicit options
+pub fn create_envelope_with_options(
+    sender: &GnsIdentity,
+    recipient_public_key_hex: &str,
+    recipient_encryption_key_hex: &str,
+    payload_type: &str,
+    payload: &[u8],
+    options: &EnvelopeOptions,
+) -> Result<GnsEnvelope, CryptoError> {
+    // Parse recipient keys
+    let recipient_enc_key: [u8; 32] = hex::decode(recipient_encryption_key_hex)?
+        .try_into()
+        .map_err(|v: Vec<u8>| CryptoError::InvalidKeyLength { expected: 32, got: v.len() })?;
+    let recipient_pq_key = options.recipient_pq_key_hex.map(hex::decode).transpose()?;
+
+    // Compress, then encrypt
+    let compressed = options
+        .compression_threshold
+        .and_then(|threshold| compress_payload(payload, threshold));
+    let content_encoding = compressed.as_ref().map(|_| CONTENT_ENCODING_ZSTD.to_string());
+    let plaintext = compressed.as_deref().unwrap_or(payload);
+
+    let encrypted_payload =
+        encrypt_for_recipient_keys(plaintext, &recipient_enc_key, recipient_pq_key.as_deref())?;
+
+    let mut envelope = GnsEnvelope {
+        id: Uuid::new_v4().to_string(),
         from_public_key: sender.public_key_hex(),
-        from_handle: None, // Caller can set this
+        from_handle: options.sender_handle.map(String::from),
         to_public_keys: vec![recipient_public_key_hex.to_string()],
         payload_type: payload_type.to_string(),
-        timestamp,
-        thread_id: None,
-        reply_to_id: None,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        content_encoding,
+        thread_id: options.thread_id.map(String::from),
+        reply_to_id: options.reply_to_id.map(String::from),
         encrypted_payload: PayloadWrapper::Object(encrypted_payload),
         ephemeral_public_key: None,
         nonce: None,
-        signature: signature_hex,
-    })
+        signature: String::new(),
+    };
+
+    envelope.signature = hex::encode(sender.sign_bytes(&envelope.signing_input()?));
+    Ok(envelope)
 }
 
 /// Create envelope with additional metadata
@@ -183,62 +239,90 @@ pub fn create_envelope_with_metadata(
     thread_id: Option<&str>,
     reply_to_id: Option<&str>,
 ) -> Result<GnsEnvelope, CryptoError> {
-    let mut envelope = create_envelope(
+    create_envelope_with_options(
         sender,
         recipient_public_key_hex,
         recipient_encryption_key_hex,
         payload_type,
         payload,
-    )?;
-
-    envelope.from_handle = sender_handle.map(String::from);
-    envelope.thread_id = thread_id.map(String::from);
-    envelope.reply_to_id = reply_to_id.map(String::from);
-
-    // Re-sign with the new metadata
-    let header = EnvelopeHeader {
-        id: envelope.id.clone(),
-        from_public_key: envelope.from_public_key.clone(),
-        to_public_keys: envelope.to_public_keys.clone(),
-        payload_type: envelope.payload_type.clone(),
-        timestamp: envelope.timestamp,
-        encrypted_payload_hash: blake3::hash(&serde_json::to_vec(&envelope.encrypted_payload)?)
-            .to_hex()
-            .to_string(),
-    };
-
-    let header_bytes = canonicalize_for_signing(&serde_json::to_value(&header)?);
-    let signature = sender.sign_bytes(&header_bytes);
-    envelope.signature = hex::encode(signature);
+        &EnvelopeOptions {
+            sender_handle,
+            thread_id,
+            reply_to_id,
+            ..Default::default()
+        },
+    )
+}
 
-    Ok(envelope)
+/// Create envelope with metadata, using hybrid post-quantum encryption when
+/// the recipient published an ML-KEM key (and the `pq-hybrid` feature is on)
+#[allow(clippy::too_many_arguments)]
+pub fn create_hybrid_envelope_with_metadata(
+    sender: &GnsIdentity,
+    sender_handle: Option<&str>,
+    recipient_public_key_hex: &str,
+    recipient_encryption_key_hex: &str,
+    recipient_pq_key_hex: Option<&str>,
+    payload_type: &str,
+    payload: &[u8],
+    thread_id: Option<&str>,
+    reply_to_id: Option<&str>,
+) -> Result<GnsEnvelope, CryptoError> {
+    create_envelope_with_options(
+        sender,
+        recipient_public_key_hex,
+        recipient_encryption_key_hex,
+        payload_type,
+        payload,
+        &EnvelopeOptions {
+            sender_handle,
+            thread_id,
+            reply_to_id,
+            recipient_pq_key_hex,
+            ..Default::default()
+        },
+    )
 }
 
 /// Open (verify and decrypt) an envelope
+///
+/// Sealed-sender envelopes are detected by payload type and opened with
+/// the sender taken from inside the ciphertext.
 pub fn open_envelope(
     recipient: &GnsIdentity,
     envelope: &GnsEnvelope,
 ) -> Result<OpenedEnvelope, CryptoError> {
-    // Verify signature
-    let header = EnvelopeHeader {
-        id: envelope.id.clone(),
-        from_public_key: envelope.from_public_key.clone(),
-        to_public_keys: envelope.to_public_keys.clone(),
-        payload_type: envelope.payload_type.clone(),
-        timestamp: envelope.timestamp,
-        encrypted_payload_hash: blake3::hash(&serde_json::to_vec(&envelope.encrypted_payload)?)
-            .to_hex()
-            .to_string(),
-    };
+    if envelope.is_sealed() {
+        return open_sealed_envelope(recipient, envelope);
+    }
 
-    let header_bytes = canonicalize_for_signing(&serde_json::to_value(&header)?);
+    // Verify signature
     let signature_valid = verify_signature_hex(
         &envelope.from_public_key,
-        &header_bytes,
+        &envelope.signing_input()?,
         &envelope.signature,
     )?;
 
-    // Decrypt payload
+    let payload = decrypt_envelope_payload(recipient, envelope)?;
+
+    Ok(OpenedEnvelope {
+        from_public_key: envelope.from_public_key.clone(),
+        from_handle: envelope.from_handle.clone(),
+        payload_type: envelope.payload_type.clone(),
+        payload,
+        signature_valid,
+        envelope_id: envelope.id.clone(),
+        timestamp: envelope.timestamp,
+        thread_id: envelope.thread_id.clone(),
+        reply_to_id: envelope.reply_to_id.clone(),
+    })
+}
+
+/// Decrypt an envelope's payload in either wire format
+pub(crate) fn decrypt_envelope_payload(
+    recipient: &GnsIdentity,
+    envelope: &GnsEnvelope,
+) -> Result<Vec<u8>, CryptoError> {
     let encrypted_payload = match &envelope.encrypted_payload {
         PayloadWrapper::Object(obj) => obj.clone(),
         PayloadWrapper::String(ciphertext_hex) => {
@@ -252,11 +336,9 @@ pub fn open_envelope(
                 CryptoError::DecryptionFailed("Missing nonce for string payload".to_string())
             })?;
 
-            // Decode Hex strings to Vec<u8> since EncryptedPayload expects raw bytes (via hex_bytes module)
-            // Wait, EncryptedPayload struct uses #[serde(with="hex_bytes")] so it stores Vec<u8>.
-            // So we need to decode the hex strings here.
-
+            // EncryptedPayload holds raw bytes, so decode the hex fields here
             EncryptedPayload {
+                kem_ciphertext: None,
                 ciphertext: hex::decode(ciphertext_hex)
                     .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?,
                 ephemeral_public_key: hex::decode(ephemeral_key_hex)
@@ -267,19 +349,8 @@ pub fn open_envelope(
         }
     };
 
-    let payload = decrypt_from_sender(recipient.x25519_secret(), &encrypted_payload)?;
-
-    Ok(OpenedEnvelope {
-        from_public_key: envelope.from_public_key.clone(),
-        from_handle: envelope.from_handle.clone(),
-        payload_type: envelope.payload_type.clone(),
-        payload,
-        signature_valid,
-        envelope_id: envelope.id.clone(),
-        timestamp: envelope.timestamp,
-        thread_id: envelope.thread_id.clone(),
-        reply_to_id: envelope.reply_to_id.clone(),
-    })
+    let plaintext = decrypt_for_identity(recipient, &encrypted_payload)?;
+    decompress_payload(&plaintext, envelope.content_encoding.as_deref())
 }
 
 /// Header structure for signing (excludes actual encrypted content)
@@ -291,10 +362,35 @@ struct EnvelopeHeader {
     to_public_keys: Vec<String>,
     payload_type: String,
     timestamp: i64,
+    /// Only present when set, so uncompressed envelopes sign as before
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_encoding: Option<String>,
     encrypted_payload_hash: String,
 }
 
 impl GnsEnvelope {
+    /// Canonical header bytes covered by the envelope signature
+    pub fn signing_input(&self) -> Result<Vec<u8>, CryptoError> {
+        let header = EnvelopeHeader {
+            id: self.id.clone(),
+            from_public_key: self.from_public_key.clone(),
+            to_public_keys: self.to_public_keys.clone(),
+            payload_type: self.payload_type.clone(),
+            timestamp: self.timestamp,
+            content_encoding: self.content_encoding.clone(),
+            encrypted_payload_hash: blake3::hash(&serde_json::to_vec(&self.encrypted_payload)?)
+                .to_hex()
+                .to_string(),
+        };
+
+        Ok(canonicalize_for_signing(&serde_json::to_value(&header)?))
+    }
+
+    /// Is the real sender hidden inside the payload?
+    pub fn is_sealed(&self) -> bool {
+        self.payload_type == SEALED_PAYLOAD_TYPE
+    }
+
     /// Check if this envelope is for a specific recipient
     pub fn is_for(&self, public_key_hex: &str) -> bool {
         self.to_public_keys
@@ -311,6 +407,125 @@ impl GnsEnvelope {
     pub fn from_json(json: &str) -> Result<Self, CryptoError> {
         serde_json::from_str(json).map_err(|e| CryptoError::SerializationError(e.to_string()))
     }
+
+    /// Parse an envelope from untrusted input (relay, LAN, other clients)
+    ///
+    /// Refuses oversized input before parsing and runs `validate`, so keys,
+    /// nonces and signatures of the wrong length are rejected here instead
+    /// of deep in decryption. Flat-format `ephemeralPublicKey`/`nonce`
+    /// fields sent next to an object payload are dropped; they aren't
+    /// signed and the object carries its own.
+    pub fn parse_lenient(json: &str) -> Result<Self, CryptoError> {
+        if json.len() > MAX_ENVELOPE_JSON_BYTES {
+            return Err(CryptoError::InvalidEnvelope(format!(
+                "Envelope is {} bytes, limit is {}",
+                json.len(),
+                MAX_ENVELOPE_JSON_BYTES
+            )));
+        }
+        Self::from_value_lenient(
+            serde_json::from_str(json).map_err(|e| CryptoError::SerializationError(e.to_string()))?,
+        )
+    }
+
+    /// `parse_lenient` for an already-decoded JSON value
+    pub fn from_value_lenient(value: serde_json::Value) -> Result<Self, CryptoError> {
+        let mut envelope: Self =
+            serde_json::from_value(value).map_err(|e| CryptoError::SerializationError(e.to_string()))?;
+        if let PayloadWrapper::Object(_) = envelope.encrypted_payload {
+            envelope.ephemeral_public_key = None;
+            envelope.nonce = None;
+        }
+        envelope.validate()?;
+        Ok(envelope)
+    }
+
+    /// Check field shapes without touching any keys
+    ///
+    /// Passing doesn't mean the envelope is authentic, only that
+    /// `open_envelope` can work on it.
+    pub fn validate(&self) -> Result<(), CryptoError> {
+        let invalid = |reason: String| Err(CryptoError::InvalidEnvelope(reason));
+
+        if self.id.is_empty() || self.payload_type.is_empty() {
+            return invalid("id and payloadType are required".to_string());
+        }
+        for (name, value) in [
+            ("id", Some(&self.id)),
+            ("payloadType", Some(&self.payload_type)),
+            ("fromHandle", self.from_handle.as_ref()),
+            ("threadId", self.thread_id.as_ref()),
+            ("replyToId", self.reply_to_id.as_ref()),
+        ] {
+            if value.is_some_and(|v| v.len() > MAX_FIELD_LEN) {
+                return invalid(format!("{} is longer than {}", name, MAX_FIELD_LEN));
+            }
+        }
+
+        check_hex("fromPublicKey", &self.from_public_key, 32)?;
+        check_hex("signature", &self.signature, 64)?;
+        if self.to_public_keys.is_empty() || self.to_public_keys.len() > MAX_RECIPIENTS {
+            return invalid(format!(
+                "Expected 1 to {} recipients, got {}",
+                MAX_RECIPIENTS,
+                self.to_public_keys.len()
+            ));
+        }
+        for key in &self.to_public_keys {
+            check_hex("toPublicKeys", key, 32)?;
+        }
+
+        match self.content_encoding.as_deref() {
+            None | Some(CONTENT_ENCODING_ZSTD) => {}
+            Some(other) => return invalid(format!("Unknown contentEncoding {:?}", other)),
+        }
+
+        match &self.encrypted_payload {
+            PayloadWrapper::Object(payload) => {
+                check_len("ephemeralPublicKey", payload.ephemeral_public_key.len(), 32)?;
+                check_len("nonce", payload.nonce.len(), 12)?;
+                if payload.ciphertext.len() < TAG_LEN {
+                    return invalid("ciphertext is shorter than its tag".to_string());
+                }
+            }
+            PayloadWrapper::String(ciphertext_hex) => {
+                let ephemeral = self.ephemeral_public_key.as_deref().unwrap_or_default();
+                check_hex("ephemeralPublicKey", ephemeral, 32)?;
+                check_hex("nonce", self.nonce.as_deref().unwrap_or_default(), 12)?;
+                if ciphertext_hex.len() < TAG_LEN * 2
+                    || ciphertext_hex.len() % 2 != 0
+                    || !ciphertext_hex.bytes().all(|b| b.is_ascii_hexdigit())
+                {
+                    return invalid("ciphertext is not hex, or shorter than its tag".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ChaCha20-Poly1305 authentication tag length
+const TAG_LEN: usize = 16;
+
+/// `value` must be exactly `bytes` bytes of hex
+fn check_hex(name: &str, value: &str, bytes: usize) -> Result<(), CryptoError> {
+    if value.len() != bytes * 2 || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(CryptoError::InvalidEnvelope(format!(
+            "{} must be {} bytes of hex",
+            name, bytes
+        )));
+    }
+    Ok(())
+}
+
+fn check_len(name: &str, len: usize, expected: usize) -> Result<(), CryptoError> {
+    if len != expected {
+        return Err(CryptoError::InvalidEnvelope(format!(
+            "{} is {} bytes, expected {}",
+            name, len, expected
+        )));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -429,4 +644,165 @@ mod tests {
         let result = open_envelope(&wrong_recipient, &envelope);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_hybrid_envelope_falls_back_without_pq_key() {
+        let sender = GnsIdentity::generate();
+        let recipient = GnsIdentity::generate();
+
+        let envelope = create_hybrid_envelope_with_metadata(
+            &sender,
+            Some("alice"),
+            &recipient.public_key_hex(),
+            &recipient.encryption_key_hex(),
+            None,
+            "text/plain",
+            b"Classic",
+            None,
+            None,
+        )
+        .expect("Envelope creation should succeed");
+
+        let PayloadWrapper::Object(ref payload) = envelope.encrypted_payload else {
+            unreachable!()
+        };
+        assert!(payload.kem_ciphertext.is_none());
+
+        let opened = open_envelope(&recipient, &envelope).expect("Opening should succeed");
+        assert!(opened.signature_valid);
+        assert_eq!(opened.payload, b"Classic");
+    }
+
+    #[test]
+    fn test_large_payload_compressed_transparently() {
+        let sender = GnsIdentity::generate();
+        let recipient = GnsIdentity::generate();
+        let payload = "{\"html\":\"<p>newsletter</p>\"}".repeat(500).into_bytes();
+
+        let envelope = create_envelope(
+            &sender,
+            &recipient.public_key_hex(),
+            &recipient.encryption_key_hex(),
+            "application/json",
+            &payload,
+        )
+        .expect("Envelope creation should succeed");
+        assert_eq!(envelope.content_encoding.as_deref(), Some("zstd"));
+
+        let opened = open_envelope(&recipient, &envelope).expect("Opening should succeed");
+        assert!(opened.signature_valid);
+        assert_eq!(opened.payload, payload);
+
+        // The encoding is signed, so stripping it breaks the signature
+        let mut stripped = envelope.clone();
+        stripped.content_encoding = None;
+        assert!(!open_envelope(&recipient, &stripped).unwrap().signature_valid);
+    }
+
+    fn sample_envelope() -> (GnsIdentity, GnsEnvelope) {
+        let recipient = GnsIdentity::generate();
+        let envelope = create_envelope(
+            &GnsIdentity::generate(),
+            &recipient.public_key_hex(),
+            &recipient.encryption_key_hex(),
+            "text/plain",
+            b"Hello",
+        )
+        .unwrap();
+        (recipient, envelope)
+    }
+
+    #[test]
+    fn test_parse_lenient_accepts_both_payload_formats() {
+        let (recipient, envelope) = sample_envelope();
+
+        // Object payload, with stray flat fields some clients add
+        let mut json = serde_json::to_value(&envelope).unwrap();
+        json["ephemeralPublicKey"] = serde_json::json!("zz");
+        json["nonce"] = serde_json::json!("00");
+        let parsed = GnsEnvelope::parse_lenient(&json.to_string()).unwrap();
+        assert!(parsed.nonce.is_none());
+        assert!(open_envelope(&recipient, &parsed).unwrap().signature_valid);
+
+        // Flat string payload
+        let PayloadWrapper::Object(payload) = &envelope.encrypted_payload else {
+            unreachable!()
+        };
+        let mut flat = envelope.clone();
+        flat.encrypted_payload = PayloadWrapper::String(hex::encode(&payload.ciphertext));
+        flat.ephemeral_public_key = Some(hex::encode(&payload.ephemeral_public_key));
+        flat.nonce = Some(hex::encode(&payload.nonce));
+        let parsed = GnsEnvelope::parse_lenient(&flat.to_json().unwrap()).unwrap();
+        assert_eq!(open_envelope(&recipient, &parsed).unwrap().payload, b"Hello");
+    }
+
+    #[test]
+    fn test_parse_lenient_rejects_malformed_fields() {
+        let (_, envelope) = sample_envelope();
+        let json = serde_json::to_value(&envelope).unwrap();
+        let reject = |field: &str, value: serde_json::Value| {
+            let mut json = json.clone();
+            json[field] = value;
+            let result = GnsEnvelope::parse_lenient(&json.to_string());
+            assert!(result.is_err(), "{} accepted", field);
+        };
+
+        reject("fromPublicKey", serde_json::json!("abcd"));
+        reject("signature", serde_json::json!("g".repeat(128)));
+        reject("toPublicKeys", serde_json::json!([]));
+        reject("toPublicKeys", serde_json::json!(["00".repeat(33)]));
+        reject("id", serde_json::json!(""));
+        reject("threadId", serde_json::json!("t".repeat(MAX_FIELD_LEN + 1)));
+        reject("contentEncoding", serde_json::json!("gzip"));
+        reject("encryptedPayload", serde_json::json!({"ephemeralPublicKey": "00", "nonce": "00", "ciphertext": "00"}));
+        reject("encryptedPayload", serde_json::json!("abc"));
+        reject("encryptedPayload", serde_json::json!(7));
+
+        let huge = format!("\"{}\"", "a".repeat(MAX_ENVELOPE_JSON_BYTES));
+        assert!(GnsEnvelope::parse_lenient(&huge).is_err());
+    }
+
+    #[test]
+    fn test_mutated_envelopes_never_panic() {
+        let (recipient, envelope) = sample_envelope();
+        let wire = envelope.to_json().unwrap().into_bytes();
+
+        // Deterministic xorshift so failures reproduce
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for round in 0..2000 {
+            let mut input = wire.clone();
+            match round % 3 {
+                0 => input.truncate(next() as usize % wire.len()),
+                1 => {
+                    for _ in 0..1 + next() % 4 {
+                        let i = next() as usize % input.len();
+                        input[i] = next() as u8;
+                    }
+                }
+                _ => {
+                    // Swap a quoted value for hex of the wrong length
+                    let text = String::from_utf8_lossy(&input).into_owned();
+                    let quotes: Vec<usize> = text.match_indices('"').map(|(i, _)| i).collect();
+                    let pair = (next() as usize % (quotes.len() / 2)) * 2;
+                    let filler = "ab".repeat(next() as usize % 80);
+                    input = format!("{}{}{}", &text[..=quotes[pair]], filler, &text[quotes[pair + 1]..]).into_bytes();
+                }
+            }
+
+            let text = String::from_utf8_lossy(&input);
+            if let Ok(parsed) = GnsEnvelope::parse_lenient(&text) {
+                let _ = open_envelope(&recipient, &parsed);
+            }
+            if let Ok(parsed) = GnsEnvelope::from_json(&text) {
+                let _ = open_envelope(&recipient, &parsed);
+            }
+        }
+    }
 }
@@ -0,0 +1,246 @@
+//! Proof-of-Trajectory epoch commitments
+//!
+//! Breadcrumbs are grouped into fixed-size epochs in timestamp order and each
+//! epoch is committed to with a SHA-256 Merkle root. Published records carry
+//! the roots (`epoch_roots`); a claim can then prove that a breadcrumb is part
+//! of the trajectory with an inclusion proof instead of uploading raw data.
+//!
+//! Leaves and inner nodes are domain-separated (0x00 / 0x01 prefixes) and an
+//! odd node is promoted to the next level unchanged rather than duplicated.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::breadcrumb::Breadcrumb;
+use crate::errors::CryptoError;
+
+/// Breadcrumbs per epoch
+pub const EPOCH_SIZE: usize = 100;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// A committed epoch of breadcrumbs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EpochRoot {
+    /// Epoch number, counting from the first breadcrumb
+    pub epoch: u64,
+
+    /// Hex-encoded Merkle root
+    pub root: String,
+
+    pub breadcrumb_count: u32,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+/// One step from a leaf towards the root
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProofStep {
+    /// Hex-encoded sibling hash
+    pub hash: String,
+
+    /// Is the sibling on the left?
+    pub is_left: bool,
+}
+
+/// Proof that a breadcrumb is committed to by an epoch root
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InclusionProof {
+    pub leaf_index: usize,
+    pub leaf_hash: String,
+    pub path: Vec<ProofStep>,
+    pub root: String,
+}
+
+impl InclusionProof {
+    /// Check the path leads from the leaf to the root
+    pub fn verify(&self) -> bool {
+        let Ok(mut hash) = decode_hash(&self.leaf_hash) else {
+            return false;
+        };
+
+        for step in &self.path {
+            let Ok(sibling) = decode_hash(&step.hash) else {
+                return false;
+            };
+            hash = if step.is_left {
+                node_hash(&sibling, &hash)
+            } else {
+                node_hash(&hash, &sibling)
+            };
+        }
+
+        hex::encode(hash) == self.root
+    }
+
+    /// Check the proof is for this breadcrumb and leads to the root
+    pub fn verify_breadcrumb(&self, breadcrumb: &Breadcrumb) -> bool {
+        hex::encode(breadcrumb_leaf_hash(breadcrumb)) == self.leaf_hash && self.verify()
+    }
+}
+
+/// Leaf hash of a breadcrumb: its signed data plus the signature
+pub fn breadcrumb_leaf_hash(breadcrumb: &Breadcrumb) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(breadcrumb.signing_data().as_bytes());
+    hasher.update(breadcrumb.signature.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Merkle root over leaf hashes (None when empty)
+pub fn merkle_root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    if leaves.is_empty() {
+        return None;
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    Some(level[0])
+}
+
+/// Hex Merkle root of one epoch's breadcrumbs, in the order given
+pub fn compute_epoch_root(breadcrumbs: &[Breadcrumb]) -> Result<String, CryptoError> {
+    let leaves: Vec<[u8; 32]> = breadcrumbs.iter().map(breadcrumb_leaf_hash).collect();
+    merkle_root(&leaves)
+        .map(hex::encode)
+        .ok_or_else(|| CryptoError::InvalidEnvelope("Cannot commit to an empty epoch".to_string()))
+}
+
+/// Split a trajectory into epochs and commit to each.
+/// Breadcrumbs are sorted by timestamp first; the last epoch may be partial.
+pub fn build_epochs(breadcrumbs: &[Breadcrumb]) -> Vec<EpochRoot> {
+    let mut sorted: Vec<&Breadcrumb> = breadcrumbs.iter().collect();
+    sorted.sort_by_key(|b| b.timestamp);
+
+    sorted
+        .chunks(EPOCH_SIZE)
+        .enumerate()
+        .filter_map(|(epoch, chunk)| {
+            let leaves: Vec<[u8; 32]> = chunk.iter().map(|b| breadcrumb_leaf_hash(b)).collect();
+            Some(EpochRoot {
+                epoch: epoch as u64,
+                root: hex::encode(merkle_root(&leaves)?),
+                breadcrumb_count: chunk.len() as u32,
+                start_time: chunk.first()?.timestamp,
+                end_time: chunk.last()?.timestamp,
+            })
+        })
+        .collect()
+}
+
+/// Inclusion proof for the breadcrumb at `index` within one epoch's breadcrumbs
+pub fn inclusion_proof(breadcrumbs: &[Breadcrumb], index: usize) -> Result<InclusionProof, CryptoError> {
+    if index >= breadcrumbs.len() {
+        return Err(CryptoError::InvalidEnvelope(format!(
+            "Leaf index {} out of range ({} breadcrumbs)",
+            index,
+            breadcrumbs.len()
+        )));
+    }
+
+    let mut level: Vec<[u8; 32]> = breadcrumbs.iter().map(breadcrumb_leaf_hash).collect();
+    let leaf_hash = hex::encode(level[index]);
+    let mut position = index;
+    let mut path = Vec::new();
+
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        // A promoted odd node has no sibling at this level
+        if sibling < level.len() {
+            path.push(ProofStep {
+                hash: hex::encode(level[sibling]),
+                is_left: sibling < position,
+            });
+        }
+        level = next_level(&level);
+        position /= 2;
+    }
+
+    Ok(InclusionProof {
+        leaf_index: index,
+        leaf_hash,
+        path,
+        root: hex::encode(level[0]),
+    })
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn decode_hash(hex_hash: &str) -> Result<[u8; 32], CryptoError> {
+    hex::decode(hex_hash)?
+        .try_into()
+        .map_err(|v: Vec<u8>| CryptoError::InvalidKeyLength { expected: 32, got: v.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breadcrumb::create_breadcrumb;
+    use crate::identity::GnsIdentity;
+
+    fn trajectory(count: usize) -> Vec<Breadcrumb> {
+        let identity = GnsIdentity::generate();
+        (0..count)
+            .map(|i| {
+                let mut b = create_breadcrumb(&identity, 40.0 + i as f64 * 0.01, -74.0, None, None).unwrap();
+                b.timestamp += i as i64;
+                b
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inclusion_proofs_verify() {
+        // Odd count exercises promoted nodes
+        let breadcrumbs = trajectory(7);
+        let root = compute_epoch_root(&breadcrumbs).unwrap();
+
+        for (i, breadcrumb) in breadcrumbs.iter().enumerate() {
+            let proof = inclusion_proof(&breadcrumbs, i).unwrap();
+            assert_eq!(proof.root, root);
+            assert!(proof.verify_breadcrumb(breadcrumb));
+        }
+
+        let proof = inclusion_proof(&breadcrumbs, 3).unwrap();
+        assert!(!proof.verify_breadcrumb(&breadcrumbs[4]));
+
+        let mut tampered = proof.clone();
+        tampered.path[0].is_left = !tampered.path[0].is_left;
+        assert!(!tampered.verify());
+
+        assert!(inclusion_proof(&breadcrumbs, 7).is_err());
+    }
+
+    #[test]
+    fn test_build_epochs() {
+        let breadcrumbs = trajectory(EPOCH_SIZE + 5);
+        let epochs = build_epochs(&breadcrumbs);
+
+        assert_eq!(epochs.len(), 2);
+        assert_eq!(epochs[0].breadcrumb_count as usize, EPOCH_SIZE);
+        assert_eq!(epochs[1].breadcrumb_count, 5);
+        assert_eq!(epochs[0].root, compute_epoch_root(&breadcrumbs[..EPOCH_SIZE]).unwrap());
+        assert!(compute_epoch_root(&[]).is_err());
+    }
+}
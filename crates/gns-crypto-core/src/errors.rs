@@ -39,6 +39,9 @@ pub enum CryptoError {
 
     #[error("Base64 decode error: {0}")]
     Base64DecodeError(String),
+
+    #[error("Compression error: {0}")]
+    CompressionError(String),
 }
 
 impl From<hex::FromHexError> for CryptoError {
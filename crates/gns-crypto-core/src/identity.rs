@@ -11,10 +11,11 @@ use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::rngs::OsRng;
 use sha2::{Digest, Sha512};
 use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret as X25519Secret};
-use zeroize::ZeroizeOnDrop;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::encryption::EncryptedPayload;
 use crate::errors::CryptoError;
+use crate::secret::{SecretBytes, SecretString};
 
 /// GNS Identity - the core cryptographic identity
 ///
@@ -47,18 +48,25 @@ impl GnsIdentity {
         Ok(Self::from_signing_key(signing_key))
     }
 
-    /// Create identity from hex-encoded private key
-    pub fn from_hex(private_key_hex: &str) -> Result<Self, CryptoError> {
-        let bytes = hex::decode(private_key_hex)?;
-        if bytes.len() != 32 {
+    /// Create identity from a private key slice, checking its length
+    pub fn from_slice(private_key: &[u8]) -> Result<Self, CryptoError> {
+        if private_key.len() != 32 {
             return Err(CryptoError::InvalidKeyLength {
                 expected: 32,
-                got: bytes.len(),
+                got: private_key.len(),
             });
         }
         let mut arr = [0u8; 32];
-        arr.copy_from_slice(&bytes);
-        Self::from_bytes(&arr)
+        arr.copy_from_slice(private_key);
+        let identity = Self::from_bytes(&arr);
+        arr.zeroize();
+        identity
+    }
+
+    /// Create identity from hex-encoded private key
+    pub fn from_hex(private_key_hex: &str) -> Result<Self, CryptoError> {
+        let bytes = SecretBytes::from(hex::decode(private_key_hex)?);
+        Self::from_slice(bytes.expose_secret())
     }
 
     /// Internal: create from SigningKey
@@ -98,8 +106,13 @@ impl GnsIdentity {
     }
 
     /// Get Ed25519 private key as hex (USE WITH CAUTION!)
-    pub fn private_key_hex(&self) -> String {
-        hex::encode(self.signing_key.as_bytes())
+    pub fn private_key_hex(&self) -> SecretString {
+        self.private_key_bytes().to_hex()
+    }
+
+    /// Get Ed25519 private key (seed) bytes (USE WITH CAUTION!)
+    pub fn private_key_bytes(&self) -> SecretBytes {
+        SecretBytes::from(self.signing_key.as_bytes().as_slice())
     }
 
     // ==================== SIGNING ====================
@@ -139,9 +152,21 @@ impl GnsIdentity {
         crate::encryption::encrypt_for_recipient(plaintext, recipient_x25519_public)
     }
 
-    /// Decrypt a message sent to us
+    /// Decrypt a message sent to us (classic or post-quantum hybrid)
     pub fn decrypt(&self, encrypted: &EncryptedPayload) -> Result<Vec<u8>, CryptoError> {
-        crate::encryption::decrypt_from_sender(&self.x25519_secret, encrypted)
+        crate::encryption::decrypt_for_identity(self, encrypted)
+    }
+
+    /// ML-KEM-768 public key (hex), derived from the identity seed
+    #[cfg(feature = "pq-hybrid")]
+    pub fn pq_public_key_hex(&self) -> Result<String, CryptoError> {
+        crate::pq::public_key(&self.seed_bytes()).map(hex::encode)
+    }
+
+    /// Ed25519 seed, for deriving further keys
+    #[cfg(feature = "pq-hybrid")]
+    pub(crate) fn seed_bytes(&self) -> [u8; 32] {
+        self.signing_key.to_bytes()
     }
 
     /// Get X25519 secret for internal use (encryption operations)
@@ -219,7 +244,7 @@ mod tests {
         let original = GnsIdentity::generate();
         let private_hex = original.private_key_hex();
 
-        let restored = GnsIdentity::from_hex(&private_hex).unwrap();
+        let restored = GnsIdentity::from_hex(private_hex.expose_secret()).unwrap();
 
         assert_eq!(original.public_key_hex(), restored.public_key_hex());
         assert_eq!(original.encryption_key_hex(), restored.encryption_key_hex());
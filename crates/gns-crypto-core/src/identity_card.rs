@@ -0,0 +1,156 @@
+//! Identity Card Module - Signed public profiles
+//!
+//! An identity card is the shareable, self-signed description of a GNS
+//! identity: its keys plus optional profile fields. The signature covers
+//! the canonical JSON of every field except `signature`, so any client can
+//! check that a card really was issued by the key it advertises.
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::CryptoError;
+use crate::identity::GnsIdentity;
+use crate::signing::{canonicalize_for_signing, verify_signature_hex};
+
+/// Current identity card format version
+pub const IDENTITY_CARD_VERSION: u8 = 1;
+
+/// A self-signed public profile
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityCard {
+    /// Format version
+    pub version: u8,
+
+    /// Ed25519 public key (hex)
+    pub public_key: String,
+
+    /// X25519 encryption public key (hex)
+    pub encryption_key: String,
+
+    /// Claimed @handle, without the "@"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+
+    /// Unix timestamp in milliseconds
+    pub issued_at: i64,
+
+    /// Ed25519 signature over the canonical card (hex)
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl IdentityCard {
+    /// Create and sign a card for an identity
+    pub fn create(
+        identity: &GnsIdentity,
+        handle: Option<String>,
+        display_name: Option<String>,
+        bio: Option<String>,
+        avatar_url: Option<String>,
+    ) -> Result<Self, CryptoError> {
+        let mut card = Self {
+            version: IDENTITY_CARD_VERSION,
+            public_key: identity.public_key_hex(),
+            encryption_key: identity.encryption_key_hex(),
+            handle: handle.map(|h| h.trim_start_matches('@').to_lowercase()),
+            display_name,
+            bio,
+            avatar_url,
+            issued_at: chrono::Utc::now().timestamp_millis(),
+            signature: String::new(),
+        };
+
+        let data = card.signing_data()?;
+        card.signature = hex::encode(identity.sign_bytes(&data));
+        Ok(card)
+    }
+
+    /// Bytes covered by the signature
+    pub fn signing_data(&self) -> Result<Vec<u8>, CryptoError> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("signature");
+        }
+        Ok(canonicalize_for_signing(&value))
+    }
+
+    /// Verify the card's self-signature
+    pub fn verify(&self) -> Result<bool, CryptoError> {
+        if self.version != IDENTITY_CARD_VERSION {
+            return Err(CryptoError::SerializationError(format!(
+                "Unsupported identity card version: {}",
+                self.version
+            )));
+        }
+        if self.signature.is_empty() {
+            return Ok(false);
+        }
+
+        verify_signature_hex(&self.public_key, &self.signing_data()?, &self.signature)
+    }
+
+    /// Parse a card from JSON
+    pub fn from_json(json: &str) -> Result<Self, CryptoError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serialize the card to JSON
+    pub fn to_json(&self) -> Result<String, CryptoError> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_sign_and_verify() {
+        let identity = GnsIdentity::generate();
+        let card = IdentityCard::create(
+            &identity,
+            Some("@Alice".to_string()),
+            Some("Alice".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(card.handle.as_deref(), Some("alice"));
+        assert!(card.verify().unwrap());
+
+        // Survives a JSON roundtrip
+        let parsed = IdentityCard::from_json(&card.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, card);
+        assert!(parsed.verify().unwrap());
+    }
+
+    #[test]
+    fn test_tampered_card_fails() {
+        let identity = GnsIdentity::generate();
+        let mut card =
+            IdentityCard::create(&identity, Some("alice".to_string()), None, None, None).unwrap();
+
+        card.handle = Some("mallory".to_string());
+        assert!(!card.verify().unwrap());
+    }
+
+    #[test]
+    fn test_card_signed_by_other_key_fails() {
+        let alice = GnsIdentity::generate();
+        let mallory = GnsIdentity::generate();
+
+        let mut card = IdentityCard::create(&mallory, None, None, None, None).unwrap();
+        card.public_key = alice.public_key_hex();
+        assert!(!card.verify().unwrap());
+    }
+}
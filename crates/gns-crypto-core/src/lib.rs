@@ -11,19 +11,49 @@
 //! - Secure memory handling with zeroize
 //! - No custom cryptography
 
+pub mod backup;
 pub mod breadcrumb;
+pub mod compression;
 pub mod encryption;
 pub mod envelope;
+pub mod epoch;
 pub mod errors;
 pub mod identity;
+pub mod identity_card;
+#[cfg(feature = "pq-hybrid")]
+pub mod pq;
+pub mod proximity;
+pub mod safety_number;
+pub mod sealed_sender;
+pub mod secret;
 pub mod signing;
+pub mod stream;
+pub mod testvectors;
 
+pub use backup::{
+    export_encrypted, identity_from_mnemonic, identity_to_mnemonic, import_encrypted,
+    EncryptedBackup,
+};
 pub use breadcrumb::{create_breadcrumb, Breadcrumb};
-pub use encryption::{decrypt_from_sender, encrypt_for_recipient, EncryptedPayload};
-pub use envelope::{create_envelope, create_envelope_with_metadata, open_envelope, GnsEnvelope};
+pub use compression::{compress_payload, decompress_payload, DEFAULT_COMPRESSION_THRESHOLD};
+pub use encryption::{
+    decrypt_for_identity, decrypt_from_sender, encrypt_for_recipient, encrypt_for_recipient_keys,
+    EncryptedPayload,
+};
+pub use envelope::{
+    create_envelope, create_envelope_with_metadata, create_envelope_with_options,
+    create_hybrid_envelope_with_metadata, open_envelope, EnvelopeOptions, GnsEnvelope,
+};
+pub use epoch::{build_epochs, compute_epoch_root, inclusion_proof, EpochRoot, InclusionProof};
 pub use errors::CryptoError;
 pub use identity::GnsIdentity;
+pub use identity_card::IdentityCard;
+pub use proximity::{attest_proximity, verify_attestation, ProximityAttestation, ProximityClaim};
+pub use safety_number::{format_safety_number, safety_number};
+pub use sealed_sender::{create_sealed_envelope, open_sealed_envelope, SealedContent};
+pub use secret::{SecretBytes, SecretString};
 pub use signing::{sign_message, verify_signature};
+pub use stream::{StreamDecryptor, StreamEncryptor, STREAM_CHUNK_SIZE};
 
 /// Re-export commonly used types
 pub mod prelude {
@@ -31,6 +61,7 @@ pub mod prelude {
     pub use crate::envelope::GnsEnvelope;
     pub use crate::errors::CryptoError;
     pub use crate::identity::GnsIdentity;
+    pub use crate::identity_card::IdentityCard;
 }
 
 #[cfg(test)]
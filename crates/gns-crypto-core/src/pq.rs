@@ -0,0 +1,110 @@
+//! Post-Quantum Hybrid - ML-KEM-768 alongside X25519
+//!
+//! Enabled with the `pq-hybrid` feature. The ML-KEM keypair is derived
+//! from the identity seed, so no new secret needs to be stored or backed
+//! up. A hybrid payload mixes both shared secrets into the symmetric key,
+//! so it stays confidential as long as either primitive holds.
+
+use hkdf::Hkdf;
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{Ciphertext, Encoded, EncodedSizeUser, KemCore, MlKem768, B32};
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use crate::errors::CryptoError;
+
+type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+
+/// Encoded ML-KEM-768 public (encapsulation) key length
+pub const PQ_PUBLIC_KEY_LEN: usize = 1184;
+
+/// Encoded ML-KEM-768 ciphertext length
+pub const PQ_CIPHERTEXT_LEN: usize = 1088;
+
+const KEYGEN_INFO: &[u8] = b"gns-pq-kem-v1";
+
+/// Derive the ML-KEM keypair from an Ed25519 seed
+fn derive_keypair(seed: &[u8; 32]) -> Result<(DecapsulationKey, EncapsulationKey), CryptoError> {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha256>::new(None, seed)
+        .expand(KEYGEN_INFO, &mut okm)
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+
+    let mut d = [0u8; 32];
+    let mut z = [0u8; 32];
+    d.copy_from_slice(&okm[..32]);
+    z.copy_from_slice(&okm[32..]);
+    let keypair = MlKem768::generate_deterministic(&B32::from(d), &B32::from(z));
+
+    okm.zeroize();
+    d.zeroize();
+    z.zeroize();
+    Ok(keypair)
+}
+
+/// Encoded public key for an identity seed
+pub(crate) fn public_key(seed: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    let (_, ek) = derive_keypair(seed)?;
+    Ok(ek.as_bytes().to_vec())
+}
+
+/// Encapsulate to a recipient's public key: (ciphertext, shared secret)
+pub(crate) fn encapsulate(public_key: &[u8]) -> Result<(Vec<u8>, [u8; 32]), CryptoError> {
+    if public_key.len() != PQ_PUBLIC_KEY_LEN {
+        return Err(CryptoError::InvalidKeyLength {
+            expected: PQ_PUBLIC_KEY_LEN,
+            got: public_key.len(),
+        });
+    }
+    let encoded = Encoded::<EncapsulationKey>::try_from(public_key)
+        .map_err(|_| CryptoError::InvalidKeyFormat("Bad ML-KEM public key".to_string()))?;
+    let ek = EncapsulationKey::from_bytes(&encoded);
+
+    let (ciphertext, shared) = ek
+        .encapsulate(&mut OsRng)
+        .map_err(|_| CryptoError::EncryptionFailed("ML-KEM encapsulation failed".to_string()))?;
+
+    let mut secret = [0u8; 32];
+    secret.copy_from_slice(&shared);
+    Ok((ciphertext.to_vec(), secret))
+}
+
+/// Recover the shared secret with the identity's derived private key
+pub(crate) fn decapsulate(seed: &[u8; 32], ciphertext: &[u8]) -> Result<[u8; 32], CryptoError> {
+    if ciphertext.len() != PQ_CIPHERTEXT_LEN {
+        return Err(CryptoError::DecryptionFailed(format!(
+            "Bad ML-KEM ciphertext length: {}",
+            ciphertext.len()
+        )));
+    }
+    let ciphertext = Ciphertext::<MlKem768>::try_from(ciphertext)
+        .map_err(|_| CryptoError::DecryptionFailed("Bad ML-KEM ciphertext".to_string()))?;
+
+    let (dk, _) = derive_keypair(seed)?;
+    let shared = dk
+        .decapsulate(&ciphertext)
+        .map_err(|_| CryptoError::DecryptionFailed("ML-KEM decapsulation failed".to_string()))?;
+
+    let mut secret = [0u8; 32];
+    secret.copy_from_slice(&shared);
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encapsulate_roundtrip() {
+        let seed = [0x11u8; 32];
+        let public = public_key(&seed).unwrap();
+        assert_eq!(public.len(), PQ_PUBLIC_KEY_LEN);
+        assert_eq!(public, public_key(&seed).unwrap());
+
+        let (ciphertext, shared) = encapsulate(&public).unwrap();
+        assert_eq!(decapsulate(&seed, &ciphertext).unwrap(), shared);
+        assert_ne!(decapsulate(&[0x22u8; 32], &ciphertext).unwrap(), shared);
+    }
+}
@@ -0,0 +1,225 @@
+//! Proximity Module - Co-location Attestations
+//!
+//! Two identities prove they were near each other by each signing a
+//! breadcrumb for the same time window and a claim binding it to the
+//! other party. Once both claims are exchanged, either side (or anyone
+//! shown the result) can check that the cells are within a few grid
+//! steps of each other.
+//!
+//! ## Structure
+//! ```text
+//! ┌─────────────────────────────────────────┐
+//! │ ProximityAttestation                    │
+//! │ ├── claims: one per party, by key       │
+//! │ │   ├── breadcrumb: signed H3 cell      │
+//! │ │   ├── peer_public_key                 │
+//! │ │   └── signature: over both            │
+//! │ ├── max_steps: agreed grid distance     │
+//! │ └── distance: measured grid distance    │
+//! └─────────────────────────────────────────┘
+//! ```
+
+use crate::breadcrumb::{create_breadcrumb, h3_grid_distance, Breadcrumb};
+use crate::errors::CryptoError;
+use crate::identity::GnsIdentity;
+use crate::signing::verify_signature_hex;
+use serde::{Deserialize, Serialize};
+
+/// How far apart in time the two breadcrumbs may be (seconds)
+pub const PROXIMITY_WINDOW_SECONDS: i64 = 5 * 60;
+
+/// Default grid distance that still counts as "together"
+pub const DEFAULT_MAX_GRID_STEPS: u32 = 1;
+
+/// One party's half of a proximity proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProximityClaim {
+    /// Where the signer is, signed on its own
+    pub breadcrumb: Breadcrumb,
+
+    /// Who the signer is proving proximity to (hex)
+    pub peer_public_key: String,
+
+    /// Signature over the breadcrumb signature and the peer (hex)
+    pub signature: String,
+}
+
+/// Both halves, checked against each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProximityAttestation {
+    /// Ordered by the signer's public key, so both sides build the same one
+    pub claims: [ProximityClaim; 2],
+    pub max_steps: u32,
+    pub distance: u32,
+}
+
+impl ProximityClaim {
+    /// The string covered by the claim signature
+    pub fn signing_data(&self) -> String {
+        format!(
+            "gns-proximity-v1:{}:{}",
+            self.breadcrumb.signature,
+            self.peer_public_key.to_lowercase()
+        )
+    }
+
+    /// Check the breadcrumb and claim signatures
+    pub fn verify(&self) -> Result<(), CryptoError> {
+        if !self.breadcrumb.verify()? {
+            return Err(CryptoError::SignatureVerificationFailed);
+        }
+        if !verify_signature_hex(
+            &self.breadcrumb.public_key,
+            self.signing_data().as_bytes(),
+            &self.signature,
+        )? {
+            return Err(CryptoError::SignatureVerificationFailed);
+        }
+        Ok(())
+    }
+}
+
+/// Sign a fresh breadcrumb at the given position for a proof with `peer`
+pub fn create_proximity_claim(
+    identity: &GnsIdentity,
+    latitude: f64,
+    longitude: f64,
+    peer_public_key: &str,
+) -> Result<ProximityClaim, CryptoError> {
+    let breadcrumb = create_breadcrumb(identity, latitude, longitude, None, None)?;
+    Ok(sign_claim(identity, breadcrumb, peer_public_key))
+}
+
+/// Bind an existing breadcrumb to a proof with `peer`
+pub fn sign_claim(identity: &GnsIdentity, breadcrumb: Breadcrumb, peer_public_key: &str) -> ProximityClaim {
+    let mut claim = ProximityClaim {
+        breadcrumb,
+        peer_public_key: peer_public_key.to_lowercase(),
+        signature: String::new(),
+    };
+    claim.signature = hex::encode(identity.sign_bytes(claim.signing_data().as_bytes()));
+    claim
+}
+
+/// Combine two claims into an attestation, checking signatures, that each
+/// names the other, that they fall in the same window and that the cells
+/// are at most `max_steps` apart
+pub fn attest_proximity(
+    a: &ProximityClaim,
+    b: &ProximityClaim,
+    max_steps: u32,
+) -> Result<ProximityAttestation, CryptoError> {
+    let (a, b) = if a.breadcrumb.public_key.to_lowercase() <= b.breadcrumb.public_key.to_lowercase() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    let distance = check_claims(a, b, max_steps)?;
+    Ok(ProximityAttestation {
+        claims: [a.clone(), b.clone()],
+        max_steps,
+        distance,
+    })
+}
+
+/// Re-check an attestation received from someone else
+pub fn verify_attestation(attestation: &ProximityAttestation) -> Result<(), CryptoError> {
+    let [a, b] = &attestation.claims;
+    if a.breadcrumb.public_key.to_lowercase() > b.breadcrumb.public_key.to_lowercase() {
+        return Err(CryptoError::InvalidEnvelope("Claims out of order".to_string()));
+    }
+    let distance = check_claims(a, b, attestation.max_steps)?;
+    if distance != attestation.distance {
+        return Err(CryptoError::InvalidEnvelope("Distance doesn't match the cells".to_string()));
+    }
+    Ok(())
+}
+
+impl ProximityAttestation {
+    /// Whether `public_key` is one of the two parties
+    pub fn involves(&self, public_key: &str) -> bool {
+        self.claims
+            .iter()
+            .any(|claim| claim.breadcrumb.public_key.eq_ignore_ascii_case(public_key))
+    }
+
+    /// When the proof was made: the later of the two breadcrumbs (Unix seconds)
+    pub fn timestamp(&self) -> i64 {
+        self.claims[0].breadcrumb.timestamp.max(self.claims[1].breadcrumb.timestamp)
+    }
+}
+
+/// The grid distance between two valid, matching claims
+fn check_claims(a: &ProximityClaim, b: &ProximityClaim, max_steps: u32) -> Result<u32, CryptoError> {
+    a.verify()?;
+    b.verify()?;
+
+    let (a_key, b_key) = (&a.breadcrumb.public_key, &b.breadcrumb.public_key);
+    if a_key.eq_ignore_ascii_case(b_key) {
+        return Err(CryptoError::InvalidEnvelope("Both claims are from the same identity".to_string()));
+    }
+    if !a.peer_public_key.eq_ignore_ascii_case(b_key) || !b.peer_public_key.eq_ignore_ascii_case(a_key) {
+        return Err(CryptoError::InvalidEnvelope("Claims don't name each other".to_string()));
+    }
+    if a.breadcrumb.resolution != b.breadcrumb.resolution {
+        return Err(CryptoError::InvalidEnvelope("Breadcrumbs use different resolutions".to_string()));
+    }
+    if (a.breadcrumb.timestamp - b.breadcrumb.timestamp).abs() > PROXIMITY_WINDOW_SECONDS {
+        return Err(CryptoError::InvalidEnvelope("Breadcrumbs are from different windows".to_string()));
+    }
+
+    let distance = h3_grid_distance(&a.breadcrumb.h3_index, &b.breadcrumb.h3_index)?;
+    if distance > max_steps {
+        return Err(CryptoError::InvalidEnvelope(format!(
+            "Cells are {} grid steps apart, over {}",
+            distance, max_steps
+        )));
+    }
+    Ok(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attest_and_verify() {
+        let alice = GnsIdentity::generate();
+        let bob = GnsIdentity::generate();
+
+        let a = create_proximity_claim(&alice, 52.5200, 13.4050, &bob.public_key_hex()).unwrap();
+        let b = create_proximity_claim(&bob, 52.5201, 13.4051, &alice.public_key_hex()).unwrap();
+
+        let attestation = attest_proximity(&a, &b, DEFAULT_MAX_GRID_STEPS).unwrap();
+        // Either side builds the same attestation
+        assert_eq!(attest_proximity(&b, &a, DEFAULT_MAX_GRID_STEPS).unwrap(), attestation);
+        assert!(verify_attestation(&attestation).is_ok());
+        assert!(attestation.involves(&alice.public_key_hex()));
+
+        let mut tampered = attestation.clone();
+        tampered.max_steps = 0;
+        tampered.distance = 5;
+        assert!(verify_attestation(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_rejects_mismatched_claims() {
+        let alice = GnsIdentity::generate();
+        let bob = GnsIdentity::generate();
+        let carol = GnsIdentity::generate();
+
+        let a = create_proximity_claim(&alice, 52.5200, 13.4050, &bob.public_key_hex()).unwrap();
+        // Bob's claim names someone else
+        let b = create_proximity_claim(&bob, 52.5200, 13.4050, &carol.public_key_hex()).unwrap();
+        assert!(attest_proximity(&a, &b, DEFAULT_MAX_GRID_STEPS).is_err());
+
+        // Too far apart
+        let far = create_proximity_claim(&bob, 52.5200, 13.9050, &alice.public_key_hex()).unwrap();
+        assert!(attest_proximity(&a, &far, DEFAULT_MAX_GRID_STEPS).is_err());
+
+        // A claim can't be moved onto someone else's breadcrumb
+        let mut stolen = far.clone();
+        stolen.breadcrumb = a.breadcrumb.clone();
+        assert!(stolen.verify().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Safety Numbers - Out-of-band key verification
+//!
+//! Each party's fingerprint is an iterated SHA-512 over its signing and
+//! encryption keys, rendered as 30 digits. The safety number concatenates
+//! both fingerprints in a fixed order, so both sides see the same 60 digits
+//! and can compare them in person or over a trusted channel.
+
+use sha2::{Digest, Sha512};
+
+use crate::errors::CryptoError;
+
+/// Fingerprint format version, mixed into the hash
+pub const FINGERPRINT_VERSION: u16 = 0;
+
+/// Hash iterations per fingerprint
+pub const FINGERPRINT_ITERATIONS: usize = 5200;
+
+/// Digits per fingerprint (6 chunks of 5)
+const CHUNKS: usize = 6;
+
+/// 30-digit fingerprint of one party's keys
+pub fn fingerprint(public_key_hex: &str, encryption_key_hex: &str) -> Result<String, CryptoError> {
+    let public_key = decode_key(public_key_hex)?;
+    let encryption_key = decode_key(encryption_key_hex)?;
+
+    let mut hash = {
+        let mut hasher = Sha512::new();
+        hasher.update(FINGERPRINT_VERSION.to_be_bytes());
+        hasher.update(public_key);
+        hasher.update(encryption_key);
+        hasher.finalize()
+    };
+    for _ in 0..FINGERPRINT_ITERATIONS {
+        let mut hasher = Sha512::new();
+        hasher.update(hash);
+        hasher.update(public_key);
+        hasher.update(encryption_key);
+        hash = hasher.finalize();
+    }
+
+    Ok(hash[..CHUNKS * 5]
+        .chunks(5)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect())
+}
+
+/// 60-digit safety number for a conversation, identical on both sides
+pub fn safety_number(
+    our_public_key: &str,
+    our_encryption_key: &str,
+    their_public_key: &str,
+    their_encryption_key: &str,
+) -> Result<String, CryptoError> {
+    let ours = fingerprint(our_public_key, our_encryption_key)?;
+    let theirs = fingerprint(their_public_key, their_encryption_key)?;
+
+    // Order by signing key so the result doesn't depend on who computes it
+    Ok(if our_public_key.to_lowercase() <= their_public_key.to_lowercase() {
+        ours + &theirs
+    } else {
+        theirs + &ours
+    })
+}
+
+/// Split digits into groups of five for display
+pub fn format_safety_number(digits: &str) -> String {
+    digits
+        .as_bytes()
+        .chunks(5)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_key(key_hex: &str) -> Result<[u8; 32], CryptoError> {
+    hex::decode(key_hex)?
+        .try_into()
+        .map_err(|v: Vec<u8>| CryptoError::InvalidKeyLength { expected: 32, got: v.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::GnsIdentity;
+
+    #[test]
+    fn test_safety_number_is_symmetric() {
+        let alice = GnsIdentity::generate();
+        let bob = GnsIdentity::generate();
+
+        let from_alice = safety_number(
+            &alice.public_key_hex(),
+            &alice.encryption_key_hex(),
+            &bob.public_key_hex(),
+            &bob.encryption_key_hex(),
+        )
+        .unwrap();
+        let from_bob = safety_number(
+            &bob.public_key_hex(),
+            &bob.encryption_key_hex(),
+            &alice.public_key_hex(),
+            &alice.encryption_key_hex(),
+        )
+        .unwrap();
+
+        assert_eq!(from_alice, from_bob);
+        assert_eq!(from_alice.len(), 60);
+        assert!(from_alice.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(format_safety_number(&from_alice).split(' ').count(), 12);
+    }
+
+    #[test]
+    fn test_key_change_changes_fingerprint() {
+        let alice = GnsIdentity::generate();
+        let other = GnsIdentity::generate();
+
+        let before = fingerprint(&alice.public_key_hex(), &alice.encryption_key_hex()).unwrap();
+        let after = fingerprint(&alice.public_key_hex(), &other.encryption_key_hex()).unwrap();
+        assert_ne!(before, after);
+        assert!(fingerprint("abcd", &alice.encryption_key_hex()).is_err());
+    }
+}
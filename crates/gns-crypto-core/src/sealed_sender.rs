@@ -0,0 +1,307 @@
+//! Sealed Sender - Envelopes that hide the sender from the relay
+//!
+//! A regular envelope names its sender in cleartext. A sealed envelope
+//! moves the sender's identity, handle and thread metadata into the
+//! encrypted payload and signs the outer envelope with a one-time Ed25519
+//! key, so the relay only learns the recipient.
+//!
+//! ```text
+//! outer (cleartext, signed by ephemeral key)
+//! ├── from_public_key: ephemeral Ed25519 pubkey
+//! ├── to_public_keys, timestamp, id
+//! ├── payload_type: "gns/sealed-sender"
+//! └── encrypted_payload ──► SealedContent
+//!                           ├── from_public_key / from_handle
+//!                           ├── payload_type / payload
+//!                           ├── thread_id / reply_to_id
+//!                           └── signature (sender's real key)
+//! ```
+//!
+//! The inner signature covers the outer envelope id, recipient and
+//! timestamp, so sealed content can't be lifted into another envelope.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::encryption::{encrypt_for_recipient, EncryptedPayload, PayloadWrapper};
+use crate::envelope::{decrypt_envelope_payload, GnsEnvelope, OpenedEnvelope};
+use crate::errors::CryptoError;
+use crate::identity::GnsIdentity;
+use crate::signing::{canonicalize_for_signing, verify_signature_hex};
+
+/// Outer payload type marking a sealed envelope
+pub const SEALED_PAYLOAD_TYPE: &str = "gns/sealed-sender";
+
+/// The part of a sealed envelope only the recipient can read
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedContent {
+    /// Real sender's Ed25519 public key (hex)
+    pub from_public_key: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_handle: Option<String>,
+
+    /// Inner payload MIME type
+    pub payload_type: String,
+
+    /// Inner payload (hex)
+    pub payload: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to_id: Option<String>,
+
+    /// Sender's signature over `signing_input` (hex)
+    #[serde(default)]
+    pub signature: String,
+}
+
+/// What the sender's real key signs
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SealedHeader<'a> {
+    envelope_id: &'a str,
+    to_public_key: &'a str,
+    timestamp: i64,
+    from_public_key: &'a str,
+    from_handle: &'a Option<String>,
+    payload_type: &'a str,
+    payload_hash: String,
+    thread_id: &'a Option<String>,
+    reply_to_id: &'a Option<String>,
+}
+
+impl SealedContent {
+    /// Canonical bytes covered by the inner signature
+    pub fn signing_input(
+        &self,
+        envelope_id: &str,
+        to_public_key: &str,
+        timestamp: i64,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let header = SealedHeader {
+            envelope_id,
+            to_public_key,
+            timestamp,
+            from_public_key: &self.from_public_key,
+            from_handle: &self.from_handle,
+            payload_type: &self.payload_type,
+            payload_hash: blake3::hash(self.payload.as_bytes()).to_hex().to_string(),
+            thread_id: &self.thread_id,
+            reply_to_id: &self.reply_to_id,
+        };
+        Ok(canonicalize_for_signing(&serde_json::to_value(&header)?))
+    }
+}
+
+/// Build and sign the sealed content for one envelope
+#[allow(clippy::too_many_arguments)]
+pub fn seal_content(
+    sender: &GnsIdentity,
+    sender_handle: Option<&str>,
+    envelope_id: &str,
+    recipient_public_key_hex: &str,
+    timestamp: i64,
+    payload_type: &str,
+    payload: &[u8],
+    thread_id: Option<&str>,
+    reply_to_id: Option<&str>,
+) -> Result<SealedContent, CryptoError> {
+    let mut content = SealedContent {
+        from_public_key: sender.public_key_hex(),
+        from_handle: sender_handle.map(String::from),
+        payload_type: payload_type.to_string(),
+        payload: hex::encode(payload),
+        thread_id: thread_id.map(String::from),
+        reply_to_id: reply_to_id.map(String::from),
+        signature: String::new(),
+    };
+
+    let input = content.signing_input(envelope_id, recipient_public_key_hex, timestamp)?;
+    content.signature = hex::encode(sender.sign_bytes(&input));
+    Ok(content)
+}
+
+/// Wrap encrypted sealed content in an outer envelope signed by `ephemeral`
+pub fn wrap_sealed(
+    ephemeral: &GnsIdentity,
+    envelope_id: &str,
+    recipient_public_key_hex: &str,
+    timestamp: i64,
+    encrypted_content: EncryptedPayload,
+) -> Result<GnsEnvelope, CryptoError> {
+    let mut envelope = GnsEnvelope {
+        id: envelope_id.to_string(),
+        from_public_key: ephemeral.public_key_hex(),
+        from_handle: None,
+        to_public_keys: vec![recipient_public_key_hex.to_string()],
+        payload_type: SEALED_PAYLOAD_TYPE.to_string(),
+        timestamp,
+        content_encoding: None,
+        thread_id: None,
+        reply_to_id: None,
+        encrypted_payload: PayloadWrapper::Object(encrypted_content),
+        ephemeral_public_key: None,
+        nonce: None,
+        signature: String::new(),
+    };
+
+    envelope.signature = hex::encode(ephemeral.sign_bytes(&envelope.signing_input()?));
+    Ok(envelope)
+}
+
+/// Create a sealed-sender envelope
+#[allow(clippy::too_many_arguments)]
+pub fn create_sealed_envelope(
+    sender: &GnsIdentity,
+    sender_handle: Option<&str>,
+    recipient_public_key_hex: &str,
+    recipient_encryption_key_hex: &str,
+    payload_type: &str,
+    payload: &[u8],
+    thread_id: Option<&str>,
+    reply_to_id: Option<&str>,
+) -> Result<GnsEnvelope, CryptoError> {
+    let recipient_enc_key: [u8; 32] = hex::decode(recipient_encryption_key_hex)?
+        .try_into()
+        .map_err(|v: Vec<u8>| CryptoError::InvalidKeyLength { expected: 32, got: v.len() })?;
+
+    let envelope_id = Uuid::new_v4().to_string();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    let content = seal_content(
+        sender,
+        sender_handle,
+        &envelope_id,
+        recipient_public_key_hex,
+        timestamp,
+        payload_type,
+        payload,
+        thread_id,
+        reply_to_id,
+    )?;
+    let encrypted = encrypt_for_recipient(&serde_json::to_vec(&content)?, &recipient_enc_key)?;
+
+    // A fresh signing key per envelope, so envelopes can't be linked by key
+    let ephemeral = GnsIdentity::generate();
+    wrap_sealed(&ephemeral, &envelope_id, recipient_public_key_hex, timestamp, encrypted)
+}
+
+/// Open a sealed-sender envelope, reporting the real sender
+///
+/// `signature_valid` is true only when both the outer (ephemeral) and the
+/// inner (sender) signatures verify.
+pub fn open_sealed_envelope(
+    recipient: &GnsIdentity,
+    envelope: &GnsEnvelope,
+) -> Result<OpenedEnvelope, CryptoError> {
+    if !envelope.is_sealed() {
+        return Err(CryptoError::InvalidEnvelope("Not a sealed-sender envelope".to_string()));
+    }
+
+    let outer_valid = verify_signature_hex(
+        &envelope.from_public_key,
+        &envelope.signing_input()?,
+        &envelope.signature,
+    )?;
+
+    let plaintext = decrypt_envelope_payload(recipient, envelope)?;
+    let content: SealedContent = serde_json::from_slice(&plaintext)?;
+
+    let to_public_key = envelope
+        .to_public_keys
+        .first()
+        .ok_or_else(|| CryptoError::InvalidEnvelope("Sealed envelope has no recipient".to_string()))?;
+    let input = content.signing_input(&envelope.id, to_public_key, envelope.timestamp)?;
+    let inner_valid = verify_signature_hex(&content.from_public_key, &input, &content.signature)?;
+
+    Ok(OpenedEnvelope {
+        payload: hex::decode(&content.payload)?,
+        from_public_key: content.from_public_key,
+        from_handle: content.from_handle,
+        payload_type: content.payload_type,
+        signature_valid: outer_valid && inner_valid,
+        envelope_id: envelope.id.clone(),
+        timestamp: envelope.timestamp,
+        thread_id: content.thread_id,
+        reply_to_id: content.reply_to_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::open_envelope;
+
+    #[test]
+    fn test_sealed_envelope_hides_sender() {
+        let sender = GnsIdentity::generate();
+        let recipient = GnsIdentity::generate();
+
+        let envelope = create_sealed_envelope(
+            &sender,
+            Some("alice"),
+            &recipient.public_key_hex(),
+            &recipient.encryption_key_hex(),
+            "text/plain",
+            b"Hello Bob!",
+            Some("thread-123"),
+            None,
+        )
+        .unwrap();
+
+        // Nothing on the outside identifies the sender
+        let wire = envelope.to_json().unwrap();
+        assert!(!wire.contains(&sender.public_key_hex()));
+        assert!(!wire.contains("alice"));
+        assert!(!wire.contains("thread-123"));
+
+        // open_envelope dispatches to the sealed path
+        let opened = open_envelope(&recipient, &envelope).unwrap();
+        assert!(opened.signature_valid);
+        assert_eq!(opened.from_public_key, sender.public_key_hex());
+        assert_eq!(opened.from_handle.as_deref(), Some("alice"));
+        assert_eq!(opened.payload_type, "text/plain");
+        assert_eq!(opened.payload, b"Hello Bob!");
+        assert_eq!(opened.thread_id.as_deref(), Some("thread-123"));
+    }
+
+    #[test]
+    fn test_sealed_content_bound_to_envelope() {
+        let sender = GnsIdentity::generate();
+        let recipient = GnsIdentity::generate();
+
+        let mut envelope = create_sealed_envelope(
+            &sender,
+            None,
+            &recipient.public_key_hex(),
+            &recipient.encryption_key_hex(),
+            "text/plain",
+            b"Hi",
+            None,
+            None,
+        )
+        .unwrap();
+
+        // Re-wrapping the same ciphertext under a new id and ephemeral key
+        // gives a valid outer signature but breaks the inner one
+        let PayloadWrapper::Object(encrypted) = envelope.encrypted_payload.clone() else {
+            unreachable!()
+        };
+        let rewrapped = wrap_sealed(
+            &GnsIdentity::generate(),
+            "another-id",
+            &recipient.public_key_hex(),
+            envelope.timestamp,
+            encrypted,
+        )
+        .unwrap();
+        assert!(!open_sealed_envelope(&recipient, &rewrapped).unwrap().signature_valid);
+
+        envelope.timestamp += 1;
+        assert!(!open_sealed_envelope(&recipient, &envelope).unwrap().signature_valid);
+    }
+}
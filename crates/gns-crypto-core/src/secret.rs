@@ -0,0 +1,162 @@
+//! Secret Wrappers - Key material that wipes itself
+//!
+//! `SecretString` and `SecretBytes` hold private keys, seeds and mnemonics
+//! on their way out of an identity. Their contents are zeroized when they
+//! are dropped, `Debug` prints a placeholder, and there is no `Display`,
+//! so a secret can't end up in a log line or error message by accident.
+//! Reading the value takes an explicit `expose_secret()`.
+//!
+//! Both serialize as their plain contents, so they can cross the IPC
+//! boundary when a command really does need to hand a key to the user.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Text secret (hex key, mnemonic phrase), zeroized on drop
+#[derive(Clone, Default, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    /// Borrow the secret value
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+/// Binary secret (seed, private key bytes), zeroized on drop
+#[derive(Clone, Default, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    pub fn new(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+
+    /// Borrow the secret value
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Hex-encode into another secret
+    pub fn to_hex(&self) -> SecretString {
+        SecretString(hex::encode(&self.0))
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes([REDACTED; {}])", self.0.len())
+    }
+}
+
+impl Serialize for SecretBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<u8>::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let secret = SecretString::from("deadbeef");
+        assert_eq!(format!("{:?}", secret), "SecretString([REDACTED])");
+
+        let bytes = SecretBytes::from(vec![1, 2, 3]);
+        assert_eq!(format!("{:?}", bytes), "SecretBytes([REDACTED; 3])");
+    }
+
+    #[test]
+    fn test_zeroize_clears_contents() {
+        let mut secret = SecretString::from("deadbeef");
+        secret.zeroize();
+        assert!(secret.is_empty());
+
+        let mut bytes = SecretBytes::from(vec![0xAA; 32]);
+        bytes.zeroize();
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let secret = SecretString::from("deadbeef");
+        let json = serde_json::to_string(&secret).unwrap();
+        assert_eq!(json, "\"deadbeef\"");
+
+        let back: SecretString = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.expose_secret(), "deadbeef");
+    }
+
+    #[test]
+    fn test_bytes_to_hex() {
+        let bytes = SecretBytes::from(vec![0xDE, 0xAD]);
+        assert_eq!(bytes.to_hex().expose_secret(), "dead");
+    }
+}
@@ -114,7 +114,7 @@ mod tests {
     #[test]
     fn test_sign_verify_roundtrip() {
         let identity = GnsIdentity::generate();
-        let private_key = hex::decode(identity.private_key_hex()).unwrap();
+        let private_key = identity.private_key_bytes().expose_secret().to_vec();
         let private_key: [u8; 32] = private_key.try_into().unwrap();
 
         let message = b"Test message to sign";
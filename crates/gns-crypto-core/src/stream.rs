@@ -0,0 +1,277 @@
+//! Streaming Encryption - Chunked encryption for large attachments
+//!
+//! Encrypting a multi-hundred-MB file as one `EncryptedPayload` needs the
+//! whole file in memory twice. The stream format splits it into fixed-size
+//! chunks, each sealed with ChaCha20-Poly1305 under one key derived from an
+//! ephemeral X25519 exchange:
+//!
+//! ```text
+//! header (44 bytes)
+//! ├── magic: "GNSS"
+//! ├── version: 1
+//! ├── ephemeral_public_key: X25519 (32 bytes)
+//! └── nonce_prefix: 7 bytes
+//! chunks
+//! └── ciphertext of up to STREAM_CHUNK_SIZE bytes + 16-byte tag
+//! ```
+//!
+//! Each chunk's nonce is `nonce_prefix || counter (u32 BE) || last flag`,
+//! so chunks can't be reordered, dropped, or the stream truncated without
+//! decryption failing. Every chunk but the last is exactly full size.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::Zeroize;
+
+use crate::errors::CryptoError;
+use crate::identity::GnsIdentity;
+
+/// Plaintext bytes per chunk
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Encoded header length
+pub const STREAM_HEADER_LEN: usize = 4 + 1 + 32 + NONCE_PREFIX_LEN;
+
+const STREAM_MAGIC: &[u8; 4] = b"GNSS";
+const STREAM_VERSION: u8 = 1;
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+const KEY_INFO: &[u8] = b"gns-stream-v1:";
+
+/// Encrypts a stream chunk by chunk
+pub struct StreamEncryptor {
+    chunks: ChunkCipher,
+    buffer: Vec<u8>,
+}
+
+impl StreamEncryptor {
+    /// Start a stream for a recipient; also returns the header to send first
+    pub fn new(recipient_x25519_public: &[u8; 32]) -> Result<(Self, Vec<u8>), CryptoError> {
+        let mut ephemeral_bytes = [0u8; 32];
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        OsRng.fill_bytes(&mut ephemeral_bytes);
+        OsRng.fill_bytes(&mut nonce_prefix);
+
+        let ephemeral_secret = StaticSecret::from(ephemeral_bytes);
+        ephemeral_bytes.zeroize();
+        let ephemeral_public = X25519PublicKey::from(&ephemeral_secret);
+        let shared = ephemeral_secret.diffie_hellman(&X25519PublicKey::from(*recipient_x25519_public));
+
+        let chunks = ChunkCipher::new(
+            shared.as_bytes(),
+            ephemeral_public.as_bytes(),
+            recipient_x25519_public,
+            nonce_prefix,
+        )?;
+
+        let mut header = Vec::with_capacity(STREAM_HEADER_LEN);
+        header.extend_from_slice(STREAM_MAGIC);
+        header.push(STREAM_VERSION);
+        header.extend_from_slice(ephemeral_public.as_bytes());
+        header.extend_from_slice(&nonce_prefix);
+
+        let encryptor = Self {
+            chunks,
+            buffer: Vec::with_capacity(STREAM_CHUNK_SIZE),
+        };
+        Ok((encryptor, header))
+    }
+
+    /// Add plaintext; returns the ciphertext of any chunks completed
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.buffer.extend_from_slice(data);
+
+        // Keep the final (possibly full) chunk back until `finish`
+        let mut out = Vec::new();
+        while self.buffer.len() > STREAM_CHUNK_SIZE {
+            let chunk: Vec<u8> = self.buffer.drain(..STREAM_CHUNK_SIZE).collect();
+            out.extend(self.chunks.seal(&chunk, false)?);
+        }
+        Ok(out)
+    }
+
+    /// Seal the last chunk
+    pub fn finish(mut self) -> Result<Vec<u8>, CryptoError> {
+        let out = self.chunks.seal(&self.buffer, true);
+        self.buffer.zeroize();
+        out
+    }
+}
+
+/// Decrypts a stream chunk by chunk
+pub struct StreamDecryptor {
+    chunks: ChunkCipher,
+    buffer: Vec<u8>,
+}
+
+impl StreamDecryptor {
+    /// Start decrypting a stream from its header
+    pub fn new(recipient: &GnsIdentity, header: &[u8]) -> Result<Self, CryptoError> {
+        if header.len() != STREAM_HEADER_LEN
+            || &header[..4] != STREAM_MAGIC
+            || header[4] != STREAM_VERSION
+        {
+            return Err(CryptoError::DecryptionFailed("Invalid stream header".to_string()));
+        }
+
+        let ephemeral_public: [u8; 32] = header[5..37].try_into().unwrap();
+        let nonce_prefix: [u8; NONCE_PREFIX_LEN] = header[37..].try_into().unwrap();
+
+        let our_secret = StaticSecret::from(*recipient.x25519_secret());
+        let shared = our_secret.diffie_hellman(&X25519PublicKey::from(ephemeral_public));
+
+        Ok(Self {
+            chunks: ChunkCipher::new(
+                shared.as_bytes(),
+                &ephemeral_public,
+                &recipient.encryption_public_key_bytes(),
+                nonce_prefix,
+            )?,
+            buffer: Vec::with_capacity(STREAM_CHUNK_SIZE + TAG_LEN),
+        })
+    }
+
+    /// Add ciphertext; returns the plaintext of any chunks completed
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.buffer.extend_from_slice(data);
+
+        let mut out = Vec::new();
+        while self.buffer.len() > STREAM_CHUNK_SIZE + TAG_LEN {
+            let chunk: Vec<u8> = self.buffer.drain(..STREAM_CHUNK_SIZE + TAG_LEN).collect();
+            out.extend(self.chunks.open(&chunk, false)?);
+        }
+        Ok(out)
+    }
+
+    /// Open the last chunk; fails if the stream was truncated
+    pub fn finish(mut self) -> Result<Vec<u8>, CryptoError> {
+        self.chunks.open(&self.buffer, true)
+    }
+}
+
+/// Per-stream cipher state shared by both directions
+struct ChunkCipher {
+    cipher: ChaCha20Poly1305,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+}
+
+impl ChunkCipher {
+    fn new(
+        shared_secret: &[u8],
+        ephemeral_public: &[u8],
+        recipient_public: &[u8],
+        nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, CryptoError> {
+        let mut info = Vec::with_capacity(KEY_INFO.len() + 64);
+        info.extend_from_slice(KEY_INFO);
+        info.extend_from_slice(ephemeral_public);
+        info.extend_from_slice(recipient_public);
+
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, shared_secret)
+            .expand(&info, &mut key)
+            .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+
+        let cipher = ChaCha20Poly1305::new_from_slice(&key)
+            .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()));
+        key.zeroize();
+
+        Ok(Self {
+            cipher: cipher?,
+            nonce_prefix,
+            counter: 0,
+        })
+    }
+
+    fn next_nonce(&mut self, last: bool) -> Result<[u8; 12], CryptoError> {
+        let mut nonce = [0u8; 12];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&self.counter.to_be_bytes());
+        nonce[11] = last as u8;
+
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| CryptoError::EncryptionFailed("Stream too long".to_string()))?;
+        Ok(nonce)
+    }
+
+    fn seal(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, CryptoError> {
+        let nonce = self.next_nonce(last)?;
+        self.cipher
+            .encrypt(Nonce::from_slice(&nonce), chunk)
+            .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))
+    }
+
+    fn open(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, CryptoError> {
+        let nonce = self.next_nonce(last)?;
+        self.cipher
+            .decrypt(Nonce::from_slice(&nonce), chunk)
+            .map_err(|_| CryptoError::DecryptionFailed("Authentication failed".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt_all(recipient: &GnsIdentity, data: &[u8], piece: usize) -> (Vec<u8>, Vec<u8>) {
+        let (mut encryptor, header) =
+            StreamEncryptor::new(&recipient.encryption_public_key_bytes()).unwrap();
+        let mut ciphertext = Vec::new();
+        for part in data.chunks(piece) {
+            ciphertext.extend(encryptor.push(part).unwrap());
+        }
+        ciphertext.extend(encryptor.finish().unwrap());
+        (header, ciphertext)
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let recipient = GnsIdentity::generate();
+
+        for len in [0, 10, STREAM_CHUNK_SIZE, STREAM_CHUNK_SIZE * 2 + 123] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let (header, ciphertext) = encrypt_all(&recipient, &data, 10_000);
+            assert_eq!(header.len(), STREAM_HEADER_LEN);
+
+            // Feed the decryptor in differently sized pieces
+            let mut decryptor = StreamDecryptor::new(&recipient, &header).unwrap();
+            let mut plaintext = Vec::new();
+            for part in ciphertext.chunks(7_777) {
+                plaintext.extend(decryptor.push(part).unwrap());
+            }
+            plaintext.extend(decryptor.finish().unwrap());
+            assert_eq!(plaintext, data);
+        }
+    }
+
+    #[test]
+    fn test_truncated_stream_fails() {
+        let recipient = GnsIdentity::generate();
+        let data = vec![7u8; STREAM_CHUNK_SIZE * 2 + 5];
+        let (header, ciphertext) = encrypt_all(&recipient, &data, STREAM_CHUNK_SIZE);
+
+        // Dropping the last chunk leaves a non-final chunk at the end
+        let truncated = &ciphertext[..(STREAM_CHUNK_SIZE + TAG_LEN) * 2];
+        let mut decryptor = StreamDecryptor::new(&recipient, &header).unwrap();
+        decryptor.push(truncated).unwrap();
+        assert!(decryptor.finish().is_err());
+
+        // Wrong recipient can't open it
+        let other = GnsIdentity::generate();
+        let mut decryptor = StreamDecryptor::new(&other, &header).unwrap();
+        assert!(decryptor
+            .push(&ciphertext)
+            .and_then(|_| decryptor.finish())
+            .is_err());
+    }
+}
@@ -0,0 +1,643 @@
+//! Test Vectors - Fixed-input outputs for cross-platform compatibility
+//!
+//! `generate` builds signatures, canonical-JSON signing inputs, envelopes,
+//! sealed-sender envelopes and breadcrumbs from fixed seeds, ephemeral
+//! keys, nonces, ids and timestamps, so the output only changes when the
+//! protocol does. The published copy lives in `testvectors/vectors.json`.
+//!
+//! `verify` goes the other way: given a vectors document (ours, or one
+//! written by the Flutter or Panthera implementation) it recomputes every
+//! output from the inputs recorded next to it and reports each mismatch.
+
+use crate::breadcrumb::{lat_lng_to_h3, DEFAULT_H3_RESOLUTION};
+use crate::encryption::{encrypt_with_ephemeral, PayloadWrapper};
+use crate::sealed_sender::{seal_content, wrap_sealed, SealedContent};
+use crate::signing::canonicalize_for_signing;
+use crate::{open_envelope, Breadcrumb, CryptoError, GnsEnvelope, GnsIdentity};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Bump when the vector file layout changes
+pub const VECTORS_VERSION: u32 = 1;
+
+/// Fixed timestamp for all vectors (2024-01-01T00:00:00Z)
+pub const FIXED_TIMESTAMP_MS: i64 = 1_704_067_200_000;
+
+/// One vector whose recorded output doesn't match what this crate computes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorFailure {
+    /// Top-level section, e.g. `envelopes`
+    pub section: String,
+    /// Vector name, or its index when it has none
+    pub name: String,
+    pub reason: String,
+}
+
+impl fmt::Display for VectorFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}: {}", self.section, self.name, self.reason)
+    }
+}
+
+/// Outcome of `verify`
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Vectors checked, passing or not
+    pub checked: usize,
+    pub failures: Vec<VectorFailure>,
+}
+
+/// Ephemeral X25519 secret and nonce that make encryption reproducible
+struct FixedRandomness {
+    secret: [u8; 32],
+    nonce: [u8; 12],
+}
+
+/// Deterministic identity from a single repeated byte
+fn fixed_identity(byte: u8) -> Result<GnsIdentity, CryptoError> {
+    GnsIdentity::from_bytes(&[byte; 32])
+}
+
+// ==================== Generation ====================
+
+/// Build the full vectors document
+pub fn generate() -> Result<Value, CryptoError> {
+    let alice = fixed_identity(0x01)?;
+    let bob = fixed_identity(0x02)?;
+    let parties = [("alice", &alice), ("bob", &bob)];
+
+    Ok(json!({
+        "version": VECTORS_VERSION,
+        "generator": format!("gns-crypto-core {}", env!("CARGO_PKG_VERSION")),
+        "identities": identities(&parties),
+        "signatures": signatures(&alice),
+        "canonicalJson": canonical_json(&alice),
+        "envelopes": envelopes(&alice, &bob)?,
+        "sealedSender": sealed_sender(&alice, &bob)?,
+        "breadcrumbs": breadcrumbs(&alice)?,
+    }))
+}
+
+fn identities(parties: &[(&str, &GnsIdentity)]) -> Value {
+    parties
+        .iter()
+        .map(|(name, identity)| {
+            json!({
+                "name": name,
+                "seed": identity.private_key_hex().expose_secret(),
+                "publicKey": identity.public_key_hex(),
+                "encryptionKey": identity.encryption_key_hex(),
+            })
+        })
+        .collect()
+}
+
+fn signatures(signer: &GnsIdentity) -> Value {
+    let messages: [&[u8]; 3] = [b"", b"hello gns", &[0x00, 0xff, 0x10, 0x80]];
+
+    messages
+        .iter()
+        .map(|message| {
+            json!({
+                "signer": "alice",
+                "messageHex": hex::encode(message),
+                "signature": hex::encode(signer.sign_bytes(message)),
+            })
+        })
+        .collect()
+}
+
+fn canonical_json(signer: &GnsIdentity) -> Value {
+    let inputs = [
+        json!({"b": 1, "a": 2}),
+        json!({"z": {"y": [3, 2, 1], "x": null}, "a": "text with \"quotes\""}),
+        json!({"handle": "alice", "unicode": "café ✓", "nested": {"b": true, "a": false}}),
+    ];
+
+    inputs
+        .iter()
+        .map(|input| {
+            let canonical = canonicalize_for_signing(input);
+            json!({
+                "input": input,
+                "canonical": String::from_utf8_lossy(&canonical),
+                "signer": "alice",
+                "signature": hex::encode(signer.sign_bytes(&canonical)),
+            })
+        })
+        .collect()
+}
+
+fn envelopes(sender: &GnsIdentity, recipient: &GnsIdentity) -> Result<Value, CryptoError> {
+    let randomness = FixedRandomness {
+        secret: [0x03; 32],
+        nonce: [0x04; 12],
+    };
+    let plaintext = serde_json::to_vec(&json!({"type": "text/plain", "text": "Hello Bob!"}))?;
+
+    let mut vectors = Vec::new();
+
+    for format in ["object", "string"] {
+        let template = GnsEnvelope {
+            id: format!("00000000-0000-4000-8000-00000000000{}", vectors.len() + 1),
+            from_public_key: sender.public_key_hex(),
+            from_handle: Some("alice".to_string()),
+            to_public_keys: vec![recipient.public_key_hex()],
+            payload_type: "text/plain".to_string(),
+            timestamp: FIXED_TIMESTAMP_MS,
+            content_encoding: None,
+            thread_id: None,
+            reply_to_id: None,
+            encrypted_payload: PayloadWrapper::String(String::new()),
+            ephemeral_public_key: None,
+            nonce: None,
+            signature: String::new(),
+        };
+        let envelope = build_envelope(&template, sender, recipient, format, &randomness, &plaintext)?;
+
+        // Sanity check: the vector must open with the library itself
+        let opened = open_envelope(recipient, &envelope)?;
+        if !opened.signature_valid || opened.payload != plaintext {
+            return Err(CryptoError::InvalidEnvelope(format!(
+                "{} envelope vector does not roundtrip",
+                format
+            )));
+        }
+
+        vectors.push(json!({
+            "name": format!("{}-payload", format),
+            "payloadFormat": format,
+            "sender": "alice",
+            "recipient": "bob",
+            "ephemeralSecret": hex::encode(randomness.secret),
+            "nonce": hex::encode(randomness.nonce),
+            "plaintextHex": hex::encode(&plaintext),
+            "signingInput": String::from_utf8_lossy(&envelope.signing_input()?),
+            "envelope": serde_json::to_value(&envelope)?,
+        }));
+    }
+
+    Ok(Value::Array(vectors))
+}
+
+/// Encrypt `plaintext` into a copy of `template` in the given payload
+/// format and sign it; every other header field comes from `template`
+fn build_envelope(
+    template: &GnsEnvelope,
+    sender: &GnsIdentity,
+    recipient: &GnsIdentity,
+    format: &str,
+    randomness: &FixedRandomness,
+    plaintext: &[u8],
+) -> Result<GnsEnvelope, CryptoError> {
+    let encrypted = encrypt_with_ephemeral(
+        plaintext,
+        &recipient.encryption_public_key_bytes(),
+        &randomness.secret,
+        &randomness.nonce,
+    )?;
+
+    let mut envelope = template.clone();
+    match format {
+        "object" => {
+            envelope.encrypted_payload = PayloadWrapper::Object(encrypted);
+            envelope.ephemeral_public_key = None;
+            envelope.nonce = None;
+        }
+        "string" => {
+            envelope.encrypted_payload = PayloadWrapper::String(hex::encode(&encrypted.ciphertext));
+            envelope.ephemeral_public_key = Some(hex::encode(&encrypted.ephemeral_public_key));
+            envelope.nonce = Some(hex::encode(&encrypted.nonce));
+        }
+        other => {
+            return Err(CryptoError::InvalidEnvelope(format!("unknown payload format {}", other)));
+        }
+    }
+    envelope.signature = hex::encode(sender.sign_bytes(&envelope.signing_input()?));
+    Ok(envelope)
+}
+
+fn sealed_sender(sender: &GnsIdentity, recipient: &GnsIdentity) -> Result<Value, CryptoError> {
+    let ephemeral_signer = fixed_identity(0x05)?;
+    let randomness = FixedRandomness {
+        secret: [0x06; 32],
+        nonce: [0x07; 12],
+    };
+    let envelope_id = "00000000-0000-4000-8000-000000000101";
+    let plaintext = serde_json::to_vec(&json!({"type": "text/plain", "text": "Sealed hello"}))?;
+
+    let content = seal_content(
+        sender,
+        Some("alice"),
+        envelope_id,
+        &recipient.public_key_hex(),
+        FIXED_TIMESTAMP_MS,
+        "text/plain",
+        &plaintext,
+        Some("thread-1"),
+        None,
+    )?;
+    let envelope = wrap_content(&content, &ephemeral_signer, recipient, envelope_id, FIXED_TIMESTAMP_MS, &randomness)?;
+
+    // Sanity check: the vector must open with the library itself
+    let opened = open_envelope(recipient, &envelope)?;
+    if !opened.signature_valid || opened.payload != plaintext || opened.from_public_key != sender.public_key_hex() {
+        return Err(CryptoError::InvalidEnvelope("sealed-sender vector does not roundtrip".to_string()));
+    }
+
+    Ok(json!([{
+        "name": "sealed-sender",
+        "sender": "alice",
+        "recipient": "bob",
+        "ephemeralSignerSeed": ephemeral_signer.private_key_hex().expose_secret(),
+        "ephemeralSecret": hex::encode(randomness.secret),
+        "nonce": hex::encode(randomness.nonce),
+        "plaintextHex": hex::encode(&plaintext),
+        "innerSigningInput": String::from_utf8_lossy(
+            &content.signing_input(envelope_id, &recipient.public_key_hex(), FIXED_TIMESTAMP_MS)?
+        ),
+        "sealedContent": serde_json::to_value(&content)?,
+        "envelope": serde_json::to_value(&envelope)?,
+    }]))
+}
+
+/// Encrypt sealed content for `recipient` and wrap it in the outer envelope
+fn wrap_content(
+    content: &SealedContent,
+    ephemeral_signer: &GnsIdentity,
+    recipient: &GnsIdentity,
+    envelope_id: &str,
+    timestamp: i64,
+    randomness: &FixedRandomness,
+) -> Result<GnsEnvelope, CryptoError> {
+    let encrypted = encrypt_with_ephemeral(
+        &serde_json::to_vec(content)?,
+        &recipient.encryption_public_key_bytes(),
+        &randomness.secret,
+        &randomness.nonce,
+    )?;
+    wrap_sealed(ephemeral_signer, envelope_id, &recipient.public_key_hex(), timestamp, encrypted)
+}
+
+fn breadcrumbs(signer: &GnsIdentity) -> Result<Value, CryptoError> {
+    let timestamp = FIXED_TIMESTAMP_MS / 1000;
+    let points = [(52.5200, 13.4050), (52.5210, 13.4120)];
+
+    let mut vectors = Vec::new();
+    let mut prev_hash: Option<String> = None;
+
+    for (i, (latitude, longitude)) in points.iter().enumerate() {
+        let breadcrumb = build_breadcrumb(
+            signer,
+            *latitude,
+            *longitude,
+            DEFAULT_H3_RESOLUTION,
+            timestamp + (i as i64) * 600,
+            prev_hash.clone(),
+        )?;
+
+        vectors.push(json!({
+            "signer": "alice",
+            "latitude": latitude,
+            "longitude": longitude,
+            "signingData": breadcrumb.signing_data(),
+            "breadcrumb": serde_json::to_value(&breadcrumb)?,
+        }));
+
+        prev_hash = Some(chain_link(&breadcrumb));
+    }
+
+    Ok(Value::Array(vectors))
+}
+
+fn build_breadcrumb(
+    signer: &GnsIdentity,
+    latitude: f64,
+    longitude: f64,
+    resolution: u8,
+    timestamp: i64,
+    prev_hash: Option<String>,
+) -> Result<Breadcrumb, CryptoError> {
+    let mut breadcrumb = Breadcrumb {
+        h3_index: lat_lng_to_h3(latitude, longitude, resolution)?,
+        timestamp,
+        public_key: signer.public_key_hex(),
+        signature: String::new(),
+        resolution,
+        prev_hash,
+    };
+    breadcrumb.signature = hex::encode(signer.sign_bytes(breadcrumb.signing_data().as_bytes()));
+    Ok(breadcrumb)
+}
+
+/// Chain link as computed by the apps: sha256("h3:timestamp:signature")
+fn chain_link(breadcrumb: &Breadcrumb) -> String {
+    let link = format!("{}:{}:{}", breadcrumb.h3_index, breadcrumb.timestamp, breadcrumb.signature);
+    hex::encode(Sha256::digest(link.as_bytes()))
+}
+
+// ==================== Verification ====================
+
+/// Check every vector in `vectors` against this crate
+///
+/// Inputs (seeds, ephemeral secrets, nonces, plaintexts, ids, timestamps)
+/// are taken from the document, so vectors built from other fixed inputs
+/// verify too. Malformed vectors are reported as failures.
+pub fn verify(vectors: &Value) -> VerifyReport {
+    let mut report = VerifyReport::default();
+
+    let mut parties = HashMap::new();
+    for (i, vector) in section(vectors, "identities").iter().enumerate() {
+        let result = check_identity(vector).map(|identity| {
+            parties.insert(vector["name"].as_str().unwrap_or_default().to_string(), identity);
+        });
+        report.record("identities", i, vector, result);
+    }
+    if parties.is_empty() {
+        report.failures.push(VectorFailure {
+            section: "identities".to_string(),
+            name: "-".to_string(),
+            reason: "no usable identities".to_string(),
+        });
+        return report;
+    }
+
+    let checks: [(&str, VectorCheck); 5] = [
+        ("signatures", check_signature),
+        ("canonicalJson", check_canonical_json),
+        ("envelopes", check_envelope),
+        ("sealedSender", check_sealed_sender),
+        ("breadcrumbs", check_breadcrumb),
+    ];
+    for (name, check) in checks {
+        let mut previous = None;
+        for (i, vector) in section(vectors, name).iter().enumerate() {
+            let result = check(&parties, vector, previous);
+            report.record(name, i, vector, result);
+            previous = Some(vector);
+        }
+    }
+
+    report
+}
+
+/// Checks one vector; `previous` is the one before it in the same section
+type VectorCheck = fn(&HashMap<String, GnsIdentity>, &Value, Option<&Value>) -> Result<(), String>;
+
+impl VerifyReport {
+    pub fn passed(&self) -> bool {
+        self.checked > 0 && self.failures.is_empty()
+    }
+
+    fn record<T>(&mut self, section: &str, index: usize, vector: &Value, result: Result<T, String>) {
+        self.checked += 1;
+        if let Err(reason) = result {
+            let name = vector["name"].as_str().map(String::from).unwrap_or_else(|| index.to_string());
+            self.failures.push(VectorFailure {
+                section: section.to_string(),
+                name,
+                reason,
+            });
+        }
+    }
+}
+
+fn section<'a>(vectors: &'a Value, name: &str) -> &'a [Value] {
+    vectors[name].as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn check_identity(vector: &Value) -> Result<GnsIdentity, String> {
+    str_field(vector, "name")?;
+    let identity = GnsIdentity::from_hex(str_field(vector, "seed")?).map_err(|e| e.to_string())?;
+    expect_eq("publicKey", str_field(vector, "publicKey")?, &identity.public_key_hex())?;
+    expect_eq("encryptionKey", str_field(vector, "encryptionKey")?, &identity.encryption_key_hex())?;
+    Ok(identity)
+}
+
+fn check_signature(parties: &HashMap<String, GnsIdentity>, vector: &Value, _: Option<&Value>) -> Result<(), String> {
+    let signer = party(parties, vector, "signer")?;
+    let message = hex_field(vector, "messageHex")?;
+    expect_eq("signature", str_field(vector, "signature")?, &hex::encode(signer.sign_bytes(&message)))
+}
+
+fn check_canonical_json(
+    parties: &HashMap<String, GnsIdentity>,
+    vector: &Value,
+    _: Option<&Value>,
+) -> Result<(), String> {
+    let signer = party(parties, vector, "signer")?;
+    let canonical = canonicalize_for_signing(&vector["input"]);
+    expect_eq("canonical", str_field(vector, "canonical")?, &String::from_utf8_lossy(&canonical))?;
+    expect_eq("signature", str_field(vector, "signature")?, &hex::encode(signer.sign_bytes(&canonical)))
+}
+
+fn check_envelope(parties: &HashMap<String, GnsIdentity>, vector: &Value, _: Option<&Value>) -> Result<(), String> {
+    let sender = party(parties, vector, "sender")?;
+    let recipient = party(parties, vector, "recipient")?;
+    let randomness = randomness(vector)?;
+    let plaintext = hex_field(vector, "plaintextHex")?;
+    let recorded: GnsEnvelope = serde_json::from_value(vector["envelope"].clone()).map_err(|e| e.to_string())?;
+
+    let expected = build_envelope(
+        &recorded,
+        sender,
+        recipient,
+        str_field(vector, "payloadFormat")?,
+        &randomness,
+        &plaintext,
+    )
+    .map_err(|e| e.to_string())?;
+    let signing_input = expected.signing_input().map_err(|e| e.to_string())?;
+    expect_eq("signingInput", str_field(vector, "signingInput")?, &String::from_utf8_lossy(&signing_input))?;
+    expect_json("envelope", &vector["envelope"], &expected)?;
+
+    let opened = open_envelope(recipient, &recorded).map_err(|e| e.to_string())?;
+    if !opened.signature_valid || opened.payload != plaintext {
+        return Err("envelope does not open to the plaintext".to_string());
+    }
+    Ok(())
+}
+
+fn check_sealed_sender(
+    parties: &HashMap<String, GnsIdentity>,
+    vector: &Value,
+    _: Option<&Value>,
+) -> Result<(), String> {
+    let sender = party(parties, vector, "sender")?;
+    let recipient = party(parties, vector, "recipient")?;
+    let ephemeral_signer =
+        GnsIdentity::from_hex(str_field(vector, "ephemeralSignerSeed")?).map_err(|e| e.to_string())?;
+    let randomness = randomness(vector)?;
+    let plaintext = hex_field(vector, "plaintextHex")?;
+    let recorded_content: SealedContent =
+        serde_json::from_value(vector["sealedContent"].clone()).map_err(|e| e.to_string())?;
+    let recorded: GnsEnvelope = serde_json::from_value(vector["envelope"].clone()).map_err(|e| e.to_string())?;
+
+    let content = seal_content(
+        sender,
+        recorded_content.from_handle.as_deref(),
+        &recorded.id,
+        &recipient.public_key_hex(),
+        recorded.timestamp,
+        &recorded_content.payload_type,
+        &plaintext,
+        recorded_content.thread_id.as_deref(),
+        recorded_content.reply_to_id.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    let signing_input = content
+        .signing_input(&recorded.id, &recipient.public_key_hex(), recorded.timestamp)
+        .map_err(|e| e.to_string())?;
+    expect_eq(
+        "innerSigningInput",
+        str_field(vector, "innerSigningInput")?,
+        &String::from_utf8_lossy(&signing_input),
+    )?;
+    expect_json("sealedContent", &vector["sealedContent"], &content)?;
+
+    let expected = wrap_content(&content, &ephemeral_signer, recipient, &recorded.id, recorded.timestamp, &randomness)
+        .map_err(|e| e.to_string())?;
+    expect_json("envelope", &vector["envelope"], &expected)?;
+
+    let opened = open_envelope(recipient, &recorded).map_err(|e| e.to_string())?;
+    if !opened.signature_valid || opened.payload != plaintext || opened.from_public_key != sender.public_key_hex() {
+        return Err("envelope does not open to the plaintext from the sender".to_string());
+    }
+    Ok(())
+}
+
+fn check_breadcrumb(
+    parties: &HashMap<String, GnsIdentity>,
+    vector: &Value,
+    previous: Option<&Value>,
+) -> Result<(), String> {
+    let signer = party(parties, vector, "signer")?;
+    let recorded: Breadcrumb = serde_json::from_value(vector["breadcrumb"].clone()).map_err(|e| e.to_string())?;
+    let prev_hash = match previous {
+        Some(previous) => {
+            let previous: Breadcrumb =
+                serde_json::from_value(previous["breadcrumb"].clone()).map_err(|e| e.to_string())?;
+            Some(chain_link(&previous))
+        }
+        None => None,
+    };
+    let coordinate = |key| vector[key].as_f64().ok_or_else(|| format!("missing {}", key));
+
+    let expected = build_breadcrumb(
+        signer,
+        coordinate("latitude")?,
+        coordinate("longitude")?,
+        recorded.resolution,
+        recorded.timestamp,
+        prev_hash,
+    )
+    .map_err(|e| e.to_string())?;
+    expect_eq("signingData", str_field(vector, "signingData")?, &expected.signing_data())?;
+    expect_json("breadcrumb", &vector["breadcrumb"], &expected)
+}
+
+// ==================== Field helpers ====================
+
+fn str_field<'a>(vector: &'a Value, key: &str) -> Result<&'a str, String> {
+    vector[key].as_str().ok_or_else(|| format!("missing {}", key))
+}
+
+fn hex_field(vector: &Value, key: &str) -> Result<Vec<u8>, String> {
+    hex::decode(str_field(vector, key)?).map_err(|e| format!("{}: {}", key, e))
+}
+
+fn hex_array<const N: usize>(vector: &Value, key: &str) -> Result<[u8; N], String> {
+    hex_field(vector, key)?
+        .try_into()
+        .map_err(|v: Vec<u8>| format!("{} is {} bytes, expected {}", key, v.len(), N))
+}
+
+fn randomness(vector: &Value) -> Result<FixedRandomness, String> {
+    Ok(FixedRandomness {
+        secret: hex_array(vector, "ephemeralSecret")?,
+        nonce: hex_array(vector, "nonce")?,
+    })
+}
+
+fn party<'a>(parties: &'a HashMap<String, GnsIdentity>, vector: &Value, key: &str) -> Result<&'a GnsIdentity, String> {
+    let name = str_field(vector, key)?;
+    parties.get(name).ok_or_else(|| format!("unknown {} {}", key, name))
+}
+
+fn expect_eq(what: &str, recorded: &str, expected: &str) -> Result<(), String> {
+    if recorded == expected {
+        Ok(())
+    } else {
+        Err(format!("{} is {:?}, expected {:?}", what, recorded, expected))
+    }
+}
+
+/// Compare a recorded JSON object with ours, naming the fields that differ
+fn expect_json<T: serde::Serialize>(what: &str, recorded: &Value, expected: &T) -> Result<(), String> {
+    let expected = serde_json::to_value(expected).map_err(|e| e.to_string())?;
+    if *recorded == expected {
+        return Ok(());
+    }
+
+    let (Some(recorded), Some(expected)) = (recorded.as_object(), expected.as_object()) else {
+        return Err(format!("{} differs", what));
+    };
+    let mut fields: Vec<&str> = recorded
+        .keys()
+        .chain(expected.keys())
+        .filter(|key| recorded.get(*key) != expected.get(*key))
+        .map(String::as_str)
+        .collect();
+    fields.sort_unstable();
+    fields.dedup();
+    Err(format!("{} differs in {}", what, fields.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLISHED: &str = include_str!("../testvectors/vectors.json");
+
+    #[test]
+    fn test_published_vectors_are_current() {
+        let generated = serde_json::to_string_pretty(&generate().unwrap()).unwrap() + "\n";
+        assert_eq!(
+            PUBLISHED, generated,
+            "testvectors/vectors.json is stale; regenerate with `cargo run --bin gns-vectors -- --out testvectors/vectors.json`"
+        );
+    }
+
+    #[test]
+    fn test_generated_vectors_verify() {
+        let report = verify(&generate().unwrap());
+        assert!(report.passed(), "{:?}", report.failures);
+        assert_eq!(report.checked, 2 + 3 + 3 + 2 + 1 + 2);
+    }
+
+    #[test]
+    fn test_mismatches_are_reported() {
+        let mut vectors = generate().unwrap();
+        vectors["canonicalJson"][0]["canonical"] = json!("{\"b\":1,\"a\":2}");
+        vectors["envelopes"][1]["envelope"]["timestamp"] = json!(FIXED_TIMESTAMP_MS + 1);
+        vectors["breadcrumbs"][0]["breadcrumb"]["signature"] = json!("00".repeat(64));
+
+        let report = verify(&vectors);
+        let failed: Vec<String> = report.failures.iter().map(|f| format!("{}/{}", f.section, f.name)).collect();
+        // The second breadcrumb chains to the first one's signature, so it fails too
+        assert_eq!(
+            failed,
+            ["canonicalJson/0", "envelopes/string-payload", "breadcrumbs/0", "breadcrumbs/1"]
+        );
+        assert!(report.failures[1].reason.starts_with("signingInput"), "{}", report.failures[1]);
+    }
+
+    #[test]
+    fn test_empty_document_fails() {
+        let report = verify(&json!({}));
+        assert!(!report.passed());
+        assert_eq!(report.failures[0].reason, "no usable identities");
+    }
+}
@@ -0,0 +1,210 @@
+//! GNS Crypto FFI - uniffi bindings for Swift and Kotlin
+//!
+//! Lets platform extensions (iOS notification service and share
+//! extensions, Android services) sign, open envelopes and check
+//! breadcrumbs with gns-crypto-core directly, without going through the
+//! Tauri webview.
+//!
+//! Generate bindings from a built library:
+//! ```text
+//! cargo build -p gns-crypto-ffi --release
+//! cargo run -p gns-crypto-ffi --features cli --bin uniffi-bindgen -- \
+//!     generate --library target/release/libgns_crypto_ffi.so --language kotlin --out-dir out
+//! ```
+
+use std::sync::Arc;
+
+use gns_crypto_core::{
+    create_breadcrumb, create_envelope_with_metadata, identity_from_mnemonic, open_envelope,
+    signing::verify_signature_hex, Breadcrumb, CryptoError, GnsEnvelope, GnsIdentity,
+};
+
+uniffi::setup_scaffolding!();
+
+/// Errors surfaced to Swift/Kotlin
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum GnsError {
+    #[error("{message}")]
+    Crypto { message: String },
+}
+
+impl From<CryptoError> for GnsError {
+    fn from(e: CryptoError) -> Self {
+        GnsError::Crypto {
+            message: e.to_string(),
+        }
+    }
+}
+
+/// A GNS identity (Ed25519 signing + X25519 encryption keys)
+#[derive(uniffi::Object)]
+pub struct Identity {
+    inner: GnsIdentity,
+}
+
+#[uniffi::export]
+impl Identity {
+    /// Generate a new random identity
+    #[uniffi::constructor]
+    pub fn generate() -> Arc<Self> {
+        Arc::new(Self {
+            inner: GnsIdentity::generate(),
+        })
+    }
+
+    /// Restore from the 32-byte private key (hex), e.g. read from the keychain
+    #[uniffi::constructor]
+    pub fn from_private_key_hex(private_key_hex: String) -> Result<Arc<Self>, GnsError> {
+        Ok(Arc::new(Self {
+            inner: GnsIdentity::from_hex(&private_key_hex)?,
+        }))
+    }
+
+    /// Restore from a 24-word recovery phrase
+    #[uniffi::constructor]
+    pub fn from_mnemonic(phrase: String) -> Result<Arc<Self>, GnsError> {
+        Ok(Arc::new(Self {
+            inner: identity_from_mnemonic(&phrase)?,
+        }))
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        self.inner.public_key_hex()
+    }
+
+    pub fn encryption_key_hex(&self) -> String {
+        self.inner.encryption_key_hex()
+    }
+
+    /// Sign a message, returning the signature as hex
+    pub fn sign(&self, message: Vec<u8>) -> String {
+        hex::encode(self.inner.sign_bytes(&message))
+    }
+
+    /// Create a signed, encrypted envelope; returns its JSON
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_envelope(
+        &self,
+        recipient_public_key_hex: String,
+        recipient_encryption_key_hex: String,
+        payload_type: String,
+        payload: Vec<u8>,
+        sender_handle: Option<String>,
+        thread_id: Option<String>,
+        reply_to_id: Option<String>,
+    ) -> Result<String, GnsError> {
+        let envelope = create_envelope_with_metadata(
+            &self.inner,
+            sender_handle.as_deref(),
+            &recipient_public_key_hex,
+            &recipient_encryption_key_hex,
+            &payload_type,
+            &payload,
+            thread_id.as_deref(),
+            reply_to_id.as_deref(),
+        )?;
+        Ok(envelope.to_json()?)
+    }
+
+    /// Verify and decrypt an envelope addressed to this identity
+    pub fn open_envelope(&self, envelope_json: String) -> Result<OpenedEnvelope, GnsError> {
+        let envelope = GnsEnvelope::from_json(&envelope_json)?;
+        let opened = open_envelope(&self.inner, &envelope)?;
+
+        Ok(OpenedEnvelope {
+            envelope_id: opened.envelope_id,
+            from_public_key: opened.from_public_key,
+            from_handle: opened.from_handle,
+            payload_type: opened.payload_type,
+            payload: opened.payload,
+            signature_valid: opened.signature_valid,
+            timestamp: opened.timestamp,
+            thread_id: opened.thread_id,
+            reply_to_id: opened.reply_to_id,
+        })
+    }
+
+    /// Create a signed breadcrumb at a location; returns its JSON
+    pub fn create_breadcrumb(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        prev_hash: Option<String>,
+    ) -> Result<String, GnsError> {
+        let breadcrumb = create_breadcrumb(&self.inner, latitude, longitude, None, prev_hash)?;
+        Ok(breadcrumb.to_json()?)
+    }
+}
+
+/// A decrypted envelope
+#[derive(uniffi::Record)]
+pub struct OpenedEnvelope {
+    pub envelope_id: String,
+    pub from_public_key: String,
+    pub from_handle: Option<String>,
+    pub payload_type: String,
+    pub payload: Vec<u8>,
+    pub signature_valid: bool,
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub thread_id: Option<String>,
+    pub reply_to_id: Option<String>,
+}
+
+/// Verify an Ed25519 signature (all keys and signatures hex)
+#[uniffi::export]
+pub fn verify_signature(
+    public_key_hex: String,
+    message: Vec<u8>,
+    signature_hex: String,
+) -> Result<bool, GnsError> {
+    Ok(verify_signature_hex(&public_key_hex, &message, &signature_hex)?)
+}
+
+/// Verify a breadcrumb's signature from its JSON
+#[uniffi::export]
+pub fn verify_breadcrumb(breadcrumb_json: String) -> Result<bool, GnsError> {
+    Ok(Breadcrumb::from_json(&breadcrumb_json)?.verify()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        let alice = Identity::generate();
+        let bob = Identity::from_private_key_hex(GnsIdentity::generate().private_key_hex().expose_secret().to_string()).unwrap();
+
+        let json = alice
+            .create_envelope(
+                bob.public_key_hex(),
+                bob.encryption_key_hex(),
+                "text/plain".to_string(),
+                b"hi bob".to_vec(),
+                Some("alice".to_string()),
+                None,
+                None,
+            )
+            .unwrap();
+
+        let opened = bob.open_envelope(json).unwrap();
+        assert!(opened.signature_valid);
+        assert_eq!(opened.from_public_key, alice.public_key_hex());
+        assert_eq!(opened.payload, b"hi bob");
+
+        // Only the recipient can open it
+        let json = alice
+            .create_envelope(
+                bob.public_key_hex(),
+                bob.encryption_key_hex(),
+                "text/plain".to_string(),
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(alice.open_envelope(json).is_err());
+    }
+}
@@ -3,10 +3,16 @@
 //! This crate compiles the gns-crypto-core to WebAssembly,
 //! providing the same cryptographic operations for Panthera web app.
 
-use gns_crypto_core::{create_breadcrumb, create_envelope, open_envelope, GnsIdentity};
+use gns_crypto_core::{
+    create_breadcrumb, create_envelope, open_envelope, GnsIdentity, StreamDecryptor,
+    StreamEncryptor,
+};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "secure-storage")]
+pub mod secure_storage;
+
 /// Initialize panic hook for better error messages
 #[wasm_bindgen(start)]
 pub fn init() {
@@ -114,6 +120,92 @@ pub fn decrypt_message(private_key_hex: &str, encrypted_json: &str) -> Result<Ve
     Ok(plaintext)
 }
 
+// ==================== Streaming Encryption ====================
+
+/// In-progress stream encryption, returned by `encrypt_stream_begin`
+#[wasm_bindgen]
+pub struct EncryptStream {
+    inner: StreamEncryptor,
+    header: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl EncryptStream {
+    /// Stream header; must be stored/sent before the chunks
+    #[wasm_bindgen(getter)]
+    pub fn header(&self) -> Vec<u8> {
+        self.header.clone()
+    }
+}
+
+/// Start encrypting a large file for a recipient
+#[wasm_bindgen]
+pub fn encrypt_stream_begin(recipient_encryption_key_hex: &str) -> Result<EncryptStream, JsError> {
+    let recipient_key: [u8; 32] = hex::decode(recipient_encryption_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid recipient key: {}", e)))?
+        .try_into()
+        .map_err(|_| JsError::new("Recipient key must be 32 bytes"))?;
+
+    let (inner, header) = StreamEncryptor::new(&recipient_key)
+        .map_err(|e| JsError::new(&format!("Encryption failed: {}", e)))?;
+
+    Ok(EncryptStream { inner, header })
+}
+
+/// Feed plaintext; returns ciphertext for any completed chunks (may be empty)
+#[wasm_bindgen]
+pub fn encrypt_stream_push(stream: &mut EncryptStream, data: &[u8]) -> Result<Vec<u8>, JsError> {
+    stream
+        .inner
+        .push(data)
+        .map_err(|e| JsError::new(&format!("Encryption failed: {}", e)))
+}
+
+/// Finish the stream; returns the final chunk. Consumes the stream.
+#[wasm_bindgen]
+pub fn encrypt_stream_finish(stream: EncryptStream) -> Result<Vec<u8>, JsError> {
+    stream
+        .inner
+        .finish()
+        .map_err(|e| JsError::new(&format!("Encryption failed: {}", e)))
+}
+
+/// In-progress stream decryption, returned by `decrypt_stream_begin`
+#[wasm_bindgen]
+pub struct DecryptStream {
+    inner: StreamDecryptor,
+}
+
+/// Start decrypting a stream from its header
+#[wasm_bindgen]
+pub fn decrypt_stream_begin(private_key_hex: &str, header: &[u8]) -> Result<DecryptStream, JsError> {
+    let identity = GnsIdentity::from_hex(private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid private key: {}", e)))?;
+
+    let inner = StreamDecryptor::new(&identity, header)
+        .map_err(|e| JsError::new(&format!("Decryption failed: {}", e)))?;
+
+    Ok(DecryptStream { inner })
+}
+
+/// Feed ciphertext; returns plaintext for any completed chunks (may be empty)
+#[wasm_bindgen]
+pub fn decrypt_stream_push(stream: &mut DecryptStream, data: &[u8]) -> Result<Vec<u8>, JsError> {
+    stream
+        .inner
+        .push(data)
+        .map_err(|e| JsError::new(&format!("Decryption failed: {}", e)))
+}
+
+/// Finish the stream; returns the final plaintext. Fails if truncated.
+#[wasm_bindgen]
+pub fn decrypt_stream_finish(stream: DecryptStream) -> Result<Vec<u8>, JsError> {
+    stream
+        .inner
+        .finish()
+        .map_err(|e| JsError::new(&format!("Decryption failed: {}", e)))
+}
+
 // ==================== Envelope Operations ====================
 
 /// Create a signed and encrypted envelope
@@ -153,13 +245,44 @@ pub fn open_signed_envelope(
     let recipient = GnsIdentity::from_hex(recipient_private_key_hex)
         .map_err(|e| JsError::new(&format!("Invalid recipient key: {}", e)))?;
 
+    let result = open_one(&recipient, envelope_json).map_err(|e| JsError::new(&e))?;
+
+    serde_wasm_bindgen::to_value(&result).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Open many envelopes in one call (e.g. loading a mailbox)
+/// Takes an array of envelope JSON strings; returns an array in the same
+/// order, each item either an opened envelope or `{ error }`
+#[wasm_bindgen]
+pub fn open_envelopes_batch(
+    recipient_private_key_hex: &str,
+    envelopes: js_sys::Array,
+) -> Result<JsValue, JsError> {
+    let recipient = GnsIdentity::from_hex(recipient_private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid recipient key: {}", e)))?;
+
+    let results: Vec<BatchItem<OpenedEnvelopeResult>> = envelopes
+        .iter()
+        .map(|value| {
+            let json = value
+                .as_string()
+                .ok_or_else(|| "Envelope must be a JSON string".to_string())?;
+            open_one(&recipient, &json)
+        })
+        .map(BatchItem::from)
+        .collect();
+
+    serde_wasm_bindgen::to_value(&results).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn open_one(recipient: &GnsIdentity, envelope_json: &str) -> Result<OpenedEnvelopeResult, String> {
     let envelope = gns_crypto_core::GnsEnvelope::from_json(envelope_json)
-        .map_err(|e| JsError::new(&format!("Invalid envelope: {}", e)))?;
+        .map_err(|e| format!("Invalid envelope: {}", e))?;
 
-    let opened = open_envelope(&recipient, &envelope)
-        .map_err(|e| JsError::new(&format!("Failed to open envelope: {}", e)))?;
+    let opened = open_envelope(recipient, &envelope)
+        .map_err(|e| format!("Failed to open envelope: {}", e))?;
 
-    let result = OpenedEnvelopeResult {
+    Ok(OpenedEnvelopeResult {
         from_public_key: opened.from_public_key,
         from_handle: opened.from_handle,
         payload_type: opened.payload_type,
@@ -167,9 +290,7 @@ pub fn open_signed_envelope(
         signature_valid: opened.signature_valid,
         envelope_id: opened.envelope_id,
         timestamp: opened.timestamp,
-    };
-
-    serde_wasm_bindgen::to_value(&result).map_err(|e| JsError::new(&e.to_string()))
+    })
 }
 
 // ==================== Breadcrumb Operations ====================
@@ -204,13 +325,136 @@ pub fn verify_breadcrumb(breadcrumb_json: &str) -> Result<bool, JsError> {
         .map_err(|e| JsError::new(&format!("Verification failed: {}", e)))
 }
 
+/// Verify many breadcrumb signatures in one call
+/// Takes an array of breadcrumb JSON strings; returns an array in the same
+/// order, each item either a boolean or `{ error }`
+#[wasm_bindgen]
+pub fn verify_breadcrumbs_batch(breadcrumbs: js_sys::Array) -> Result<JsValue, JsError> {
+    let results: Vec<BatchItem<bool>> = breadcrumbs
+        .iter()
+        .map(|value| {
+            let json = value
+                .as_string()
+                .ok_or_else(|| "Breadcrumb must be a JSON string".to_string())?;
+            let breadcrumb = gns_crypto_core::Breadcrumb::from_json(&json)
+                .map_err(|e| format!("Invalid breadcrumb: {}", e))?;
+            breadcrumb
+                .verify()
+                .map_err(|e| format!("Verification failed: {}", e))
+        })
+        .map(BatchItem::from)
+        .collect();
+
+    serde_wasm_bindgen::to_value(&results).map_err(|e| JsError::new(&e.to_string()))
+}
+
+// ==================== Backup Operations ====================
+
+/// Encode an identity as a 24-word BIP39 mnemonic
+#[wasm_bindgen]
+pub fn identity_to_mnemonic(private_key_hex: &str) -> Result<String, JsError> {
+    let identity = GnsIdentity::from_hex(private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid private key: {}", e)))?;
+
+    gns_crypto_core::identity_to_mnemonic(&identity)
+        .map(|phrase| phrase.expose_secret().to_string())
+        .map_err(|e| JsError::new(&format!("Mnemonic encoding failed: {}", e)))
+}
+
+/// Restore an identity from a 24-word mnemonic
+/// Returns JSON: { public_key, encryption_key, private_key }
+#[wasm_bindgen]
+pub fn identity_from_mnemonic(phrase: &str) -> Result<JsValue, JsError> {
+    let identity = gns_crypto_core::identity_from_mnemonic(phrase)
+        .map_err(|e| JsError::new(&format!("Invalid mnemonic: {}", e)))?;
+
+    let result = IdentityKeys {
+        public_key: identity.public_key_hex(),
+        encryption_key: identity.encryption_key_hex(),
+        private_key: identity.private_key_hex(),
+    };
+
+    serde_wasm_bindgen::to_value(&result).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Check whether a mnemonic is valid (24 words, valid checksum)
+#[wasm_bindgen]
+pub fn validate_mnemonic(phrase: &str) -> bool {
+    gns_crypto_core::backup::validate_mnemonic(phrase)
+}
+
+/// Export an identity encrypted with a passphrase
+/// Returns the backup as JSON string
+#[wasm_bindgen]
+pub fn export_encrypted_backup(private_key_hex: &str, passphrase: &str) -> Result<String, JsError> {
+    let identity = GnsIdentity::from_hex(private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid private key: {}", e)))?;
+
+    let backup = gns_crypto_core::export_encrypted(&identity, passphrase)
+        .map_err(|e| JsError::new(&format!("Backup export failed: {}", e)))?;
+
+    serde_json::to_string(&backup)
+        .map_err(|e| JsError::new(&format!("Serialization failed: {}", e)))
+}
+
+/// Restore an identity from a passphrase-encrypted backup
+/// Returns JSON: { public_key, encryption_key, private_key }
+#[wasm_bindgen]
+pub fn import_encrypted_backup(backup_json: &str, passphrase: &str) -> Result<JsValue, JsError> {
+    let backup: gns_crypto_core::EncryptedBackup = serde_json::from_str(backup_json)
+        .map_err(|e| JsError::new(&format!("Invalid backup: {}", e)))?;
+
+    let identity = gns_crypto_core::import_encrypted(&backup, passphrase)
+        .map_err(|e| JsError::new(&format!("Backup import failed: {}", e)))?;
+
+    let result = IdentityKeys {
+        public_key: identity.public_key_hex(),
+        encryption_key: identity.encryption_key_hex(),
+        private_key: identity.private_key_hex(),
+    };
+
+    serde_wasm_bindgen::to_value(&result).map_err(|e| JsError::new(&e.to_string()))
+}
+
+// ==================== Identity Card Operations ====================
+
+/// Create a self-signed identity card
+/// Returns card as JSON string
+#[wasm_bindgen]
+pub fn create_identity_card(
+    private_key_hex: &str,
+    handle: Option<String>,
+    display_name: Option<String>,
+    bio: Option<String>,
+    avatar_url: Option<String>,
+) -> Result<String, JsError> {
+    let identity = GnsIdentity::from_hex(private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid private key: {}", e)))?;
+
+    let card = gns_crypto_core::IdentityCard::create(&identity, handle, display_name, bio, avatar_url)
+        .map_err(|e| JsError::new(&format!("Identity card creation failed: {}", e)))?;
+
+    card.to_json()
+        .map_err(|e| JsError::new(&format!("Serialization failed: {}", e)))
+}
+
+/// Verify an identity card's self-signature
+#[wasm_bindgen]
+pub fn verify_identity_card(card_json: &str) -> Result<bool, JsError> {
+    let card = gns_crypto_core::IdentityCard::from_json(card_json)
+        .map_err(|e| JsError::new(&format!("Invalid identity card: {}", e)))?;
+
+    card.verify()
+        .map_err(|e| JsError::new(&format!("Verification failed: {}", e)))
+}
+
 // ==================== Helper Types ====================
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct IdentityKeys {
     public_key: String,
     encryption_key: String,
-    private_key: String,
+    private_key: gns_crypto_core::SecretString,
 }
 
 #[derive(Serialize)]
@@ -231,6 +475,23 @@ struct OpenedEnvelopeResult {
     timestamp: i64,
 }
 
+/// One entry of a batch result: the value itself, or `{ error }`
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BatchItem<T> {
+    Ok(T),
+    Err { error: String },
+}
+
+impl<T> From<Result<T, String>> for BatchItem<T> {
+    fn from(result: Result<T, String>) -> Self {
+        match result {
+            Ok(value) => BatchItem::Ok(value),
+            Err(error) => BatchItem::Err { error },
+        }
+    }
+}
+
 mod serde_bytes {
     use serde::{Serialize, Serializer};
 
@@ -264,10 +525,93 @@ mod tests {
                 .expect("Should parse");
 
         let message = b"Test message";
-        let signature = sign_message(&keys.private_key, message).expect("Should sign");
+        let signature = sign_message(keys.private_key.expose_secret(), message).expect("Should sign");
 
         let valid = verify_signature(&keys.public_key, message, &signature).expect("Should verify");
 
         assert!(valid);
     }
+
+    #[wasm_bindgen_test]
+    fn test_mnemonic_roundtrip() {
+        let keys: IdentityKeys =
+            serde_wasm_bindgen::from_value(generate_identity().expect("Should generate"))
+                .expect("Should parse");
+
+        let phrase = identity_to_mnemonic(keys.private_key.expose_secret()).expect("Should encode");
+        assert!(validate_mnemonic(&phrase));
+
+        let restored: IdentityKeys =
+            serde_wasm_bindgen::from_value(identity_from_mnemonic(&phrase).expect("Should restore"))
+                .expect("Should parse");
+
+        assert_eq!(restored.public_key, keys.public_key);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_identity_card_roundtrip() {
+        let keys: IdentityKeys =
+            serde_wasm_bindgen::from_value(generate_identity().expect("Should generate"))
+                .expect("Should parse");
+
+        let card = create_identity_card(keys.private_key.expose_secret(), Some("alice".to_string()), None, None, None)
+            .expect("Should create card");
+
+        assert!(verify_identity_card(&card).expect("Should verify"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_open_envelopes_batch() {
+        let sender: IdentityKeys =
+            serde_wasm_bindgen::from_value(generate_identity().expect("Should generate"))
+                .expect("Should parse");
+        let recipient: IdentityKeys =
+            serde_wasm_bindgen::from_value(generate_identity().expect("Should generate"))
+                .expect("Should parse");
+
+        let envelope = create_signed_envelope(
+            sender.private_key.expose_secret(),
+            &recipient.public_key,
+            &recipient.encryption_key,
+            "text/plain",
+            b"Hello",
+        )
+        .expect("Should create envelope");
+
+        let batch = js_sys::Array::new();
+        batch.push(&JsValue::from_str(&envelope));
+        batch.push(&JsValue::from_str("not an envelope"));
+
+        let results = js_sys::Array::from(
+            &open_envelopes_batch(recipient.private_key.expose_secret(), batch).expect("Should open batch"),
+        );
+        assert_eq!(results.length(), 2);
+
+        let error = js_sys::Reflect::get(&results.get(1), &JsValue::from_str("error")).unwrap();
+        assert!(error.is_string());
+        let valid =
+            js_sys::Reflect::get(&results.get(0), &JsValue::from_str("signature_valid")).unwrap();
+        assert_eq!(valid.as_bool(), Some(true));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_stream_roundtrip() {
+        let keys: IdentityKeys =
+            serde_wasm_bindgen::from_value(generate_identity().expect("Should generate"))
+                .expect("Should parse");
+        let data: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+
+        let mut stream = encrypt_stream_begin(&keys.encryption_key).expect("Should begin");
+        let header = stream.header();
+        let mut ciphertext = Vec::new();
+        for part in data.chunks(50_000) {
+            ciphertext.extend(encrypt_stream_push(&mut stream, part).expect("Should push"));
+        }
+        ciphertext.extend(encrypt_stream_finish(stream).expect("Should finish"));
+
+        let mut stream = decrypt_stream_begin(keys.private_key.expose_secret(), &header).expect("Should begin");
+        let mut plaintext = decrypt_stream_push(&mut stream, &ciphertext).expect("Should push");
+        plaintext.extend(decrypt_stream_finish(stream).expect("Should finish"));
+        assert_eq!(plaintext, data);
+    }
 }
@@ -0,0 +1,199 @@
+//! Secure key storage - wraps the private key with a WebCrypto AES key
+//!
+//! Enabled with the `secure-storage` feature. A non-extractable AES-GCM key
+//! is generated once and kept in IndexedDB; the browser stores it as an
+//! opaque `CryptoKey`, so script can use it but never read its bytes.
+//! `seal_identity` encrypts the private key under it, and the resulting
+//! JSON is safe to keep in localStorage in place of the hex key.
+
+use gns_crypto_core::GnsIdentity;
+use js_sys::{Array, Object, Promise, Reflect, Uint8Array};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{CryptoKey, IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+const DB_NAME: &str = "gns-secure-storage";
+const DB_VERSION: u32 = 1;
+const STORE_NAME: &str = "keys";
+const WRAPPING_KEY_ID: &str = "identity-wrapping-key";
+const SEALED_VERSION: u8 = 1;
+
+/// Private key encrypted under the IndexedDB wrapping key
+#[derive(Serialize, Deserialize)]
+struct SealedIdentity {
+    version: u8,
+    /// Public key, so the identity can be shown without unsealing
+    public_key: String,
+    iv: String,
+    ciphertext: String,
+}
+
+/// Encrypt a private key with the browser-held wrapping key
+/// Resolves to a JSON string safe to store in localStorage
+#[wasm_bindgen]
+pub async fn seal_identity(private_key_hex: String) -> Result<String, JsError> {
+    let identity = GnsIdentity::from_hex(&private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid private key: {}", e)))?;
+
+    let key = wrapping_key().await.map_err(js_error)?;
+    let mut iv = [0u8; 12];
+    crypto()
+        .and_then(|crypto| crypto.get_random_values_with_u8_array(&mut iv))
+        .map_err(js_error)?;
+
+    let mut secret = hex::decode(&private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid private key: {}", e)))?;
+    let promise = crypto()
+        .and_then(|crypto| {
+            crypto
+                .subtle()
+                .encrypt_with_object_and_u8_array(&aes_gcm_params(&iv)?, &key, &secret)
+        })
+        .map_err(js_error);
+    secret.fill(0);
+    let ciphertext = JsFuture::from(promise?).await.map_err(js_error)?;
+
+    let sealed = SealedIdentity {
+        version: SEALED_VERSION,
+        public_key: identity.public_key_hex(),
+        iv: hex::encode(iv),
+        ciphertext: hex::encode(Uint8Array::new(&ciphertext).to_vec()),
+    };
+    serde_json::to_string(&sealed).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Decrypt a private key sealed by `seal_identity`
+/// Resolves to the private key hex; fails if the wrapping key is gone
+#[wasm_bindgen]
+pub async fn unseal_identity(sealed_json: String) -> Result<String, JsError> {
+    let sealed: SealedIdentity = serde_json::from_str(&sealed_json)
+        .map_err(|e| JsError::new(&format!("Invalid sealed identity: {}", e)))?;
+    if sealed.version != SEALED_VERSION {
+        return Err(JsError::new(&format!("Unsupported sealed identity version {}", sealed.version)));
+    }
+
+    let iv = hex::decode(&sealed.iv).map_err(|e| JsError::new(&e.to_string()))?;
+    let ciphertext = hex::decode(&sealed.ciphertext).map_err(|e| JsError::new(&e.to_string()))?;
+
+    let key = wrapping_key().await.map_err(js_error)?;
+    let promise = crypto()
+        .and_then(|crypto| {
+            crypto
+                .subtle()
+                .decrypt_with_object_and_u8_array(&aes_gcm_params(&iv)?, &key, &ciphertext)
+        })
+        .map_err(js_error)?;
+    let plaintext = JsFuture::from(promise)
+        .await
+        .map_err(|_| JsError::new("Unsealing failed: wrapping key changed or data corrupted"))?;
+
+    let mut secret = Uint8Array::new(&plaintext).to_vec();
+    let private_key_hex = hex::encode(&secret);
+    secret.fill(0);
+
+    // Make sure the blob belongs to the identity it claims
+    let identity = GnsIdentity::from_hex(&private_key_hex)
+        .map_err(|e| JsError::new(&format!("Invalid private key: {}", e)))?;
+    if identity.public_key_hex() != sealed.public_key {
+        return Err(JsError::new("Sealed identity public key mismatch"));
+    }
+
+    Ok(private_key_hex)
+}
+
+/// Load the wrapping key from IndexedDB, generating it on first use
+async fn wrapping_key() -> Result<CryptoKey, JsValue> {
+    let db = open_database().await?;
+
+    if let Some(key) = stored_key(&db).await? {
+        return Ok(key);
+    }
+
+    let algorithm = Object::new();
+    Reflect::set(&algorithm, &"name".into(), &"AES-GCM".into())?;
+    Reflect::set(&algorithm, &"length".into(), &256.into())?;
+    let usages = Array::of2(&"encrypt".into(), &"decrypt".into());
+    let promise = crypto()?
+        .subtle()
+        .generate_key_with_object(&algorithm, false, &usages)?;
+    let key: CryptoKey = JsFuture::from(promise).await?.dyn_into()?;
+
+    // `add` fails if another call stored a key first; overwriting it would
+    // strand anything already sealed, so use theirs instead
+    let store = db
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)?
+        .object_store(STORE_NAME)?;
+    match request_result(&store.add_with_key(&key, &JsValue::from_str(WRAPPING_KEY_ID))?).await {
+        Ok(_) => Ok(key),
+        Err(_) => stored_key(&db).await?.ok_or_else(|| "Failed to store wrapping key".into()),
+    }
+}
+
+async fn stored_key(db: &IdbDatabase) -> Result<Option<CryptoKey>, JsValue> {
+    let store = db
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readonly)?
+        .object_store(STORE_NAME)?;
+    let existing = request_result(&store.get(&JsValue::from_str(WRAPPING_KEY_ID))?).await?;
+    Ok(existing.dyn_into::<CryptoKey>().ok())
+}
+
+async fn open_database() -> Result<IdbDatabase, JsValue> {
+    let factory = web_sys::window()
+        .ok_or("No window")?
+        .indexed_db()?
+        .ok_or("IndexedDB unavailable")?;
+    let request: IdbOpenDbRequest = factory.open_with_u32(DB_NAME, DB_VERSION)?;
+
+    let upgrade_request = request.clone();
+    let on_upgrade = Closure::once_into_js(move || {
+        if let Ok(db) = upgrade_request.result().and_then(|db| db.dyn_into::<IdbDatabase>()) {
+            let _ = db.create_object_store(STORE_NAME);
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+    request_result(&request).await?.dyn_into()
+}
+
+/// Resolve an IndexedDB request to its result
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let success_request = request.clone();
+        let on_success = Closure::once_into_js(move || {
+            let result = success_request.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::NULL, &result);
+        });
+
+        let error_request = request.clone();
+        let on_error = Closure::once_into_js(move || {
+            let error = error_request
+                .error()
+                .ok()
+                .flatten()
+                .map(JsValue::from)
+                .unwrap_or_else(|| "IndexedDB request failed".into());
+            let _ = reject.call1(&JsValue::NULL, &error);
+        });
+
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await
+}
+
+fn aes_gcm_params(iv: &[u8]) -> Result<Object, JsValue> {
+    let params = Object::new();
+    Reflect::set(&params, &"name".into(), &"AES-GCM".into())?;
+    Reflect::set(&params, &"iv".into(), &Uint8Array::from(iv))?;
+    Ok(params)
+}
+
+fn crypto() -> Result<web_sys::Crypto, JsValue> {
+    web_sys::window().ok_or("No window")?.crypto()
+}
+
+fn js_error(value: JsValue) -> JsError {
+    JsError::new(&value.as_string().unwrap_or_else(|| format!("{:?}", value)))
+}
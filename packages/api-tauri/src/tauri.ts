@@ -29,9 +29,22 @@ export interface IdentityInfo {
     encryption_key: string;
 }
 
+/** Passphrase-encrypted seed, as produced by gns-crypto-core */
+export interface EncryptedBackup {
+    version: number;
+    kdf: string;
+    memoryKib: number;
+    iterations: number;
+    parallelism: number;
+    salt: string;
+    nonce: string;
+    ciphertext: string;
+    publicKey: string;
+}
+
 export interface IdentityBackup {
     version: number;
-    private_key: string;
+    backup: EncryptedBackup;
     public_key: string;
     encryption_key: string;
     breadcrumb_count: number;
@@ -102,6 +115,8 @@ export interface Message {
     reply_to_id?: string;
     is_starred?: boolean;
     forwarded_from_id?: string;
+    /** For replies carrying a quote digest, whether it matched our copy */
+    quote_status?: 'verified' | 'mismatch' | 'unavailable' | null;
     reply_to?: Message;
     reactions: Reaction[];
 }
@@ -236,11 +251,44 @@ export async function importIdentity(privateKeyHex: string): Promise<IdentityInf
     return invoke<IdentityInfo>('import_identity', { privateKeyHex });
 }
 
-export async function exportIdentityBackup(): Promise<IdentityBackup> {
+/** Export the identity sealed with `passphrase`; `pin` is required when the app lock is enabled */
+export async function exportIdentityBackup(passphrase: string, pin?: string): Promise<IdentityBackup> {
     if (!isTauriApp()) {
         throw new Error('Cannot export identity from web browser. Use mobile app.');
     }
-    return invoke<IdentityBackup>('export_identity_backup');
+    return invoke<IdentityBackup>('export_identity_backup', { passphrase, pin });
+}
+
+export async function importIdentityBackup(backup: EncryptedBackup, passphrase: string): Promise<IdentityInfo> {
+    if (!isTauriApp()) {
+        throw new Error('Cannot import identity in web browser. Use mobile app.');
+    }
+    return invoke<IdentityInfo>('import_identity_backup', { backup, passphrase });
+}
+
+export interface SignedPayload {
+    public_key: string;
+    signature: string;
+}
+
+/**
+ * Sign with the identity key, which never leaves the backend.
+ *
+ * The signature is over `"gns-app-sign-v1:"` followed by the payload bytes,
+ * never the bare payload, so verifiers must prepend the same prefix. This
+ * keeps app signatures from passing as records, handle statements,
+ * breadcrumbs or envelopes.
+ */
+export async function signPayload(
+    payload: string,
+    encoding: 'utf8' | 'hex' | 'base64' = 'utf8'
+): Promise<SignedPayload> {
+    return invoke<SignedPayload>('sign_payload', { payload, encoding });
+}
+
+/** Decrypt a payload addressed to us; returns the plaintext as base64 */
+export async function decryptPayload(encrypted: unknown): Promise<string> {
+    return invoke<string>('decrypt_payload', { encrypted });
 }
 
 export async function deleteIdentity(): Promise<void> {
@@ -255,11 +303,18 @@ export async function deleteIdentity(): Promise<void> {
     return invoke('delete_identity');
 }
 
+/**
+ * Sign a UTF-8 string and return just the signature (hex).
+ *
+ * Goes through `signPayload`, so the signature covers the
+ * `"gns-app-sign-v1:"` prefix followed by `message`.
+ */
 export async function signString(message: string): Promise<string | null> {
     if (!isTauriApp()) {
         throw new Error('Cannot sign in web browser. Use mobile app to approve.');
     }
-    return invoke<string | null>('sign_string', { message });
+    const { signature } = await signPayload(message);
+    return signature;
 }
 
 // ==================== Handle Commands ====================
@@ -574,6 +629,27 @@ export interface SendGnsRequest {
     memo?: string;
 }
 
+export interface PreviewOperation {
+    kind: string;
+    source: string;
+    destination: string;
+    asset_code: string;
+    asset_issuer: string | null;
+    amount: number;
+}
+
+export interface TransactionPreview {
+    token: string;
+    operations: PreviewOperation[];
+    fee_xlm: number;
+    xlm_before: number;
+    xlm_after: number;
+    gns_before: number;
+    gns_after: number;
+    problems: string[];
+    expires_at: number;
+}
+
 export interface PaymentHistoryItem {
     id: string;
     tx_hash: string;
@@ -625,11 +701,15 @@ export async function createGnsTrustline(): Promise<TransactionResponse> {
     return invoke<TransactionResponse>('create_gns_trustline');
 }
 
-export async function sendGns(request: SendGnsRequest): Promise<TransactionResponse> {
+export async function previewTransaction(request: SendGnsRequest): Promise<TransactionPreview> {
+    return invoke<TransactionPreview>('preview_transaction', { request });
+}
+
+export async function sendGns(request: SendGnsRequest, previewToken: string): Promise<TransactionResponse> {
     if (!isTauriApp()) {
         return { success: false, hash: null, error: 'Not available in web browser', message: null };
     }
-    return invoke<TransactionResponse>('send_gns', { request });
+    return invoke<TransactionResponse>('send_gns', { request, previewToken });
 }
 
 export async function fundTestnetAccount(): Promise<TransactionResponse> {